@@ -0,0 +1,80 @@
+//! Measure the copy path of section data: building the image buffer in [`CopyLodableSectionsPass`], and serializing it
+//! into the output object.
+//!
+//! Staging the borrowed data of each copied range and flattening it only at write time would save at most one copy of
+//! the image, which the `copy image` benchmark measures for comparison.
+//!
+//! Run with `cargo bench --bench copy_sections`.
+
+#[path = "../src/elf/test/builder.rs"]
+mod builder;
+
+use std::hint::black_box;
+
+use criterion::Criterion;
+use object::elf::{PF_R, PF_X, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS};
+use object::read::elf::ElfFile64;
+use object::{Architecture, BinaryFormat, Endianness};
+use soda::elf::pass::section::CopyLodableSectionsPass;
+use soda::output::Object as OutputObject;
+use soda::PassManager;
+
+use crate::builder::ElfBuilder;
+
+/// Run [`CopyLodableSectionsPass`] on the given input data and return the output object.
+fn copy_sections(data: &[u8]) -> OutputObject<'static> {
+    let input = ElfFile64::<Endianness>::parse(data).unwrap();
+    let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let mut pass_mgr = PassManager::new();
+    pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+    pass_mgr.run(input, output).unwrap()
+}
+
+/// Create the data of an input with the given number of executable sections of the given size.
+fn get_large_sections_input(num_sections: u64, section_size: u64) -> Vec<u8> {
+    let mut builder = ElfBuilder::new();
+    for i in 0..num_sections {
+        builder.add_section(
+            &format!(".text.{}", i),
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            0x1000 + i * section_size,
+            vec![0xC3; section_size as usize],
+        );
+    }
+    builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, num_sections * section_size);
+    builder.build()
+}
+
+fn main() {
+    let spdlog_data = include_bytes!("../src/elf/test/libspdlog.so.1.12.0").to_vec();
+    let inputs = [
+        ("libspdlog", spdlog_data),
+        ("synthetic", get_large_sections_input(2000, 0x1000)),
+    ];
+
+    let mut criterion = Criterion::default().configure_from_args();
+    for (name, data) in &inputs {
+        let image_size = copy_sections(data).write().unwrap().len();
+        let mut group =
+            criterion.benchmark_group(format!("{} ({} bytes of output)", name, image_size));
+        group.bench_function("parse", |b| {
+            b.iter(|| {
+                black_box(ElfFile64::<Endianness>::parse(black_box(data.as_slice())).unwrap())
+            })
+        });
+        group.bench_function("copy", |b| {
+            b.iter(|| black_box(copy_sections(black_box(data))))
+        });
+        group.bench_function("copy and write", |b| {
+            b.iter(|| black_box(copy_sections(black_box(data)).write().unwrap()))
+        });
+        let image = vec![0u8; image_size];
+        group.bench_function("copy image", |b| {
+            b.iter(|| black_box(black_box(image.as_slice()).to_vec()))
+        });
+        group.finish();
+    }
+
+    criterion.final_summary();
+}
@@ -0,0 +1,105 @@
+//! Measure the lookups of section maps by input section index against a linear scan of the section maps.
+//!
+//! Run with `cargo bench --bench section_map`.
+
+#[path = "../src/elf/test/builder.rs"]
+mod builder;
+
+use std::convert::Infallible;
+use std::hint::black_box;
+
+use criterion::Criterion;
+use object::elf::{PF_R, PF_X, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS};
+use object::read::elf::ElfFile64;
+use object::{Architecture, BinaryFormat, Endianness, SectionIndex};
+use soda::elf::pass::section::CopyLodableSectionsPass;
+use soda::output::Object as OutputObject;
+use soda::{Pass, PassContext, PassHandle, PassManager};
+
+use crate::builder::ElfBuilder;
+
+/// A pass that measures the lookups on the output of [`CopyLodableSectionsPass`].
+struct MeasureLookupsPass {
+    name: String,
+    cls_pass: PassHandle<CopyLodableSectionsPass>,
+}
+
+impl Pass<ElfFile64<'static>> for MeasureLookupsPass {
+    const NAME: &'static str = "measure section map lookups";
+
+    type Output = ();
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile64<'static>>) -> Result<Self::Output, Self::Error> {
+        let output = ctx.get_pass_output(self.cls_pass);
+        let max_idx = output
+            .section_maps
+            .iter()
+            .map(|map| map.index.0)
+            .max()
+            .unwrap();
+        let idxs: Vec<_> = (0..max_idx + 1).map(SectionIndex).collect();
+
+        let mut criterion = Criterion::default().configure_from_args();
+        let mut group = criterion.benchmark_group(format!(
+            "{} ({} sections)",
+            self.name,
+            output.section_maps.len()
+        ));
+        group.bench_function("index", |b| {
+            b.iter(|| {
+                for idx in &idxs {
+                    black_box(output.is_section_copied(black_box(*idx)));
+                }
+            })
+        });
+        group.bench_function("linear", |b| {
+            b.iter(|| {
+                for idx in &idxs {
+                    let idx = black_box(*idx);
+                    black_box(output.section_maps.iter().any(|map| map.index == idx));
+                }
+            })
+        });
+        group.finish();
+
+        Ok(())
+    }
+}
+
+/// Create an input with the given number of executable sections.
+fn get_many_sections_input(num_sections: u64) -> ElfFile64<'static> {
+    let mut builder = ElfBuilder::new();
+    for i in 0..num_sections {
+        builder.add_section(
+            &format!(".text.{}", i),
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            0x1000 + i * 0x10,
+            vec![0xC3; 0x10],
+        );
+    }
+    builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, num_sections * 0x10);
+    builder.build_elf64()
+}
+
+fn main() {
+    let spdlog_data = include_bytes!("../src/elf/test/libspdlog.so.1.12.0").as_slice();
+    let inputs = [
+        ("libspdlog", ElfFile64::parse(spdlog_data).unwrap()),
+        ("synthetic", get_many_sections_input(2000)),
+    ];
+
+    for (name, input) in inputs {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(MeasureLookupsPass {
+            name: String::from(name),
+            cls_pass,
+        });
+        pass_mgr.run(input, output).unwrap();
+    }
+
+    Criterion::default().configure_from_args().final_summary();
+}
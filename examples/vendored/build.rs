@@ -0,0 +1,31 @@
+//! Converts the vendor shared library and links it statically.
+//!
+//! Real crates usually ship the vendor shared library prebuilt. This example builds it from `vendor/foo.c` first so that
+//! the repository does not carry any binary.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let lib = out_dir.join("libfoo.so");
+    let status = Command::new("cc")
+        .args(["-shared", "-fPIC", "-O1", "-o"])
+        .arg(&lib)
+        .arg("vendor/foo.c")
+        .status()
+        .unwrap();
+    assert!(status.success(), "cannot build the vendor shared library");
+    println!("cargo:rerun-if-changed=vendor/foo.c");
+
+    let needed = soda::build::Builder::new(&lib)
+        .section_name(".foo")
+        .only_export(&["foo_*"])
+        .convert_to(&out_dir)
+        .unwrap();
+    for soname in needed {
+        if let Some(name) = soda::build::get_link_lib_name(&soname) {
+            println!("cargo:rustc-link-lib=dylib={}", name);
+        }
+    }
+}
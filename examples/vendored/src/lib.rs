@@ -0,0 +1,36 @@
+//! Safe wrappers of a vendor library that is linked statically after being converted by soda.
+
+use std::ffi::{c_char, c_int, c_ulong};
+
+extern "C" {
+    fn foo_add(a: c_int, b: c_int) -> c_int;
+    fn foo_format(buffer: *mut c_char, size: c_ulong, value: c_int) -> c_int;
+    fn foo_num_calls() -> c_int;
+}
+
+/// Add the given numbers.
+pub fn add(a: i32, b: i32) -> i32 {
+    unsafe { foo_add(a, b) }
+}
+
+/// Format the given number the way the vendor library does.
+pub fn format(value: i32) -> String {
+    let mut buffer = [0u8; 32];
+    let len = unsafe { foo_format(buffer.as_mut_ptr().cast(), buffer.len() as c_ulong, value) };
+    String::from_utf8_lossy(&buffer[..len as usize]).into_owned()
+}
+
+/// Get the number of calls into the vendor library so far.
+pub fn num_calls() -> i32 {
+    unsafe { foo_num_calls() }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_call_vendor_library() {
+        assert_eq!(super::add(40, 2), 42);
+        assert_eq!(super::format(7), "foo(7)");
+        assert_eq!(super::num_calls(), 2);
+    }
+}
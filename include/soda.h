@@ -0,0 +1,88 @@
+/* Generated by cbindgen from src/capi.rs. Do not edit. */
+
+#ifndef SODA_H
+#define SODA_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The conversion succeeded.
+#define SODA_OK 0
+
+// The conversion failed with an error that is not a conversion error, such as an invalid argument or an unreadable
+// list file.
+#define SODA_ERROR_FAILURE 1
+
+// The input is not a supported shared library.
+#define SODA_ERROR_INVALID_INPUT 2
+
+// The input uses features that cannot be converted.
+#define SODA_ERROR_UNSUPPORTED 3
+
+// A conversion pass failed.
+#define SODA_ERROR_CONVERSION_FAILED 4
+
+// Options of the conversion. Null pointers select the defaults of the command line tool.
+typedef struct soda_options {
+  // Name of the output section that holds the copied image, like `--section-name`.
+  const char *section_name;
+  // Path to a file that lists the names of the defined symbols to keep global, like `--only-export`.
+  const char *only_export_file;
+  // Path to a file that lists symbols to be renamed, like `--redefine-syms`.
+  const char *redefine_syms_file;
+  // Name of the symbol defined at the start of the converted image. An empty string omits the symbol.
+  const char *image_start_symbol;
+  // Name of the symbol defined at the end of the converted image. An empty string omits the symbol.
+  const char *image_end_symbol;
+  // Fill the padding bytes between sections in executable segments with trap instructions.
+  bool trap_padding;
+  // Convert position-independent executables as well.
+  bool allow_pie;
+} soda_options;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Convert the shared library given by `input` and `len` into a relocatable object file of the same format.
+//
+// `opts` may be null to use the default options. On success, `*out` and `*out_len` receive the output, which must be
+// released with `soda_free_buffer`, and `SODA_OK` is returned. On failure, an error code is returned and, if
+// `err_msg` is not null, `*err_msg` receives a message that must be released with `soda_free_error`. `*out` and
+// `*err_msg` are set to null when they receive nothing.
+//
+// # Safety
+//
+// `input` must point to `len` readable bytes. `opts` must be null or point to valid options whose strings are null
+// or NUL-terminated. `out` and `out_len` must be valid for writes, and `err_msg` must be null or valid for writes.
+int32_t soda_convert(const uint8_t *input,
+                     size_t len,
+                     const struct soda_options *opts,
+                     uint8_t **out,
+                     size_t *out_len,
+                     char **err_msg);
+
+// Release an output buffer returned by `soda_convert`. Null buffers are ignored.
+//
+// # Safety
+//
+// `buf` and `len` must be null or an output buffer and its length returned by `soda_convert` that has not been
+// released yet.
+void soda_free_buffer(uint8_t *buf,
+                      size_t len);
+
+// Release an error message returned by `soda_convert`. Null messages are ignored.
+//
+// # Safety
+//
+// `msg` must be null or an error message returned by `soda_convert` that has not been released yet.
+void soda_free_error(char *msg);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SODA_H */
@@ -0,0 +1,187 @@
+//! Convert shared libraries from build scripts.
+//!
+//! A crate that bundles a vendor shared library can convert it in its build script and link the output statically:
+//!
+//! ```no_run
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! let needed = soda::build::Builder::new("vendor/libfoo.so")
+//!     .only_export(&["foo_init", "foo_compute"])
+//!     .convert_to(&out_dir)
+//!     .unwrap();
+//! for soname in needed {
+//!     if let Some(name) = soda::build::get_link_lib_name(&soname) {
+//!         println!("cargo:rustc-link-lib=dylib={}", name);
+//!     }
+//! }
+//! ```
+
+use std::fmt::Arguments;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::pass::{DiagnosticSink, Severity};
+use crate::{ConvertOptions, Error, Input};
+
+/// Converts an ELF shared library in a build script, and tells Cargo to link the output object.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    input: PathBuf,
+    options: ConvertOptions,
+}
+
+impl Builder {
+    /// Create a new `Builder` that converts the shared library at the given path with the default options.
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            options: ConvertOptions::default(),
+        }
+    }
+
+    /// Set the name of the output section that holds the copied image.
+    pub fn section_name(mut self, name: &str) -> Self {
+        self.options.section_name = String::from(name);
+        self
+    }
+
+    /// Keep only the given defined symbols global. The names may be glob patterns.
+    pub fn only_export(mut self, names: &[&str]) -> Self {
+        self.options.only_export = Some(names.iter().map(|name| String::from(*name)).collect());
+        self
+    }
+
+    /// Replace all the options of the conversion, including the ones set by the other methods.
+    pub fn options(mut self, options: ConvertOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Convert the shared library into an object file in the given directory, which is usually `OUT_DIR`, and print the
+    /// instructions that make Cargo link the object and rerun the build script when the shared library changes.
+    ///
+    /// Warnings of the conversion are printed as Cargo warnings unless the options have a diagnostic sink. The names
+    /// of the shared libraries that the converted library depends on are returned, which the build script should link
+    /// as well.
+    pub fn convert_to(&self, out_dir: impl AsRef<Path>) -> Result<Vec<String>, Error> {
+        self.convert_to_with(out_dir.as_ref(), &mut std::io::stdout())
+    }
+
+    fn convert_to_with(
+        &self,
+        out_dir: &Path,
+        instructions: &mut dyn Write,
+    ) -> Result<Vec<String>, Error> {
+        let input = std::fs::read(&self.input)?;
+        writeln!(
+            instructions,
+            "cargo:rerun-if-changed={}",
+            self.input.display()
+        )?;
+
+        let mut options = self.options.clone();
+        if options.diagnostics.is_none() {
+            options.diagnostics = Some(Arc::new(CargoWarningSink));
+        }
+        // Only ELF inputs have needed libraries, so other formats are rejected before the conversion.
+        let input = Input::parse(&input)?;
+        let needed = input.needed_libraries()?;
+        let output = input.convert(&options)?;
+
+        let file_name = self.input.file_name().unwrap_or_default().to_string_lossy();
+        let object_path = out_dir.join(get_object_name(&file_name));
+        std::fs::write(&object_path, output)?;
+        writeln!(
+            instructions,
+            "cargo:rustc-link-arg={}",
+            object_path.display()
+        )?;
+
+        Ok(needed)
+    }
+}
+
+/// Get the name to pass to `cargo:rustc-link-lib` for the shared library with the given soname, such as `ssl` for
+/// `libssl.so.3`. `None` is returned if the soname does not follow the `libNAME.so` convention.
+pub fn get_link_lib_name(soname: &str) -> Option<&str> {
+    let name = soname.strip_prefix("lib")?;
+    let pos = name.find(".so")?;
+    let (name, ext) = name.split_at(pos);
+    if name.is_empty() || !(ext == ".so" || ext.starts_with(".so.")) {
+        return None;
+    }
+    Some(name)
+}
+
+/// Get the file name of the object converted from the shared library with the given file name, such as `foo.o` for
+/// `libfoo.so.1`.
+fn get_object_name(file_name: &str) -> String {
+    let name = get_link_lib_name(file_name).unwrap_or(file_name);
+    format!("{}.o", name)
+}
+
+/// Prints warnings as Cargo warnings, and drops the other diagnostics.
+#[derive(Debug)]
+struct CargoWarningSink;
+
+impl DiagnosticSink for CargoWarningSink {
+    fn report(&self, severity: Severity, message: Arguments<'_>) {
+        if severity == Severity::Warning {
+            // Cargo warnings end at the end of the line.
+            println!("cargo:warning={}", message.to_string().replace('\n', " "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{get_link_lib_name, get_object_name, Builder};
+
+    #[test]
+    fn test_get_link_lib_name() {
+        assert_eq!(get_link_lib_name("libssl.so.3"), Some("ssl"));
+        assert_eq!(get_link_lib_name("libstdc++.so.6"), Some("stdc++"));
+        assert_eq!(get_link_lib_name("libfoo.so"), Some("foo"));
+        assert_eq!(get_link_lib_name("ld-linux-x86-64.so.2"), None);
+        assert_eq!(get_link_lib_name("libfoo.sox"), None);
+        assert_eq!(get_object_name("libfoo-utils.so.1"), "foo-utils.o");
+        assert_eq!(get_object_name("foo.dll"), "foo.dll.o");
+    }
+
+    #[test]
+    fn test_convert_to() {
+        let input = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/elf/test/libspdlog.so.1.12.0"
+        );
+        let out_dir = std::env::temp_dir().join(format!("soda-build-{}", std::process::id()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        let mut instructions = Vec::new();
+        let needed = Builder::new(input)
+            .section_name(".spdlog")
+            .convert_to_with(&out_dir, &mut instructions)
+            .unwrap();
+        assert!(
+            needed.iter().any(|soname| soname == "libc.so.6"),
+            "{:?}",
+            needed
+        );
+
+        let object_path = out_dir.join("spdlog.o");
+        assert_eq!(
+            String::from_utf8(instructions).unwrap(),
+            format!(
+                "cargo:rerun-if-changed={}\ncargo:rustc-link-arg={}\n",
+                input,
+                object_path.display()
+            )
+        );
+
+        let output = std::fs::read(&object_path).unwrap();
+        let output_file = object::File::parse(output.as_slice()).unwrap();
+        assert!(object::Object::section_by_name(&output_file, ".spdlog").is_some());
+
+        std::fs::remove_dir_all(&out_dir).ok();
+    }
+}
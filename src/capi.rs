@@ -0,0 +1,198 @@
+//! C ABI of the conversion.
+//!
+//! The declarations in `include/soda.h` are generated from this module with cbindgen. Buffers and error messages
+//! returned to the caller are owned by the caller, who releases them with [`soda_free_buffer`] and
+//! [`soda_free_error`].
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use crate::{ConvertOptions, Error};
+
+/// The conversion succeeded.
+pub const SODA_OK: i32 = 0;
+
+/// The conversion failed with an error that is not a conversion error, such as an invalid argument or an unreadable
+/// list file.
+pub const SODA_ERROR_FAILURE: i32 = 1;
+
+/// The input is not a supported shared library.
+pub const SODA_ERROR_INVALID_INPUT: i32 = 2;
+
+/// The input uses features that cannot be converted.
+pub const SODA_ERROR_UNSUPPORTED: i32 = 3;
+
+/// A conversion pass failed.
+pub const SODA_ERROR_CONVERSION_FAILED: i32 = 4;
+
+// The codes mirror the exit codes of the command line tool. cbindgen cannot evaluate the casts, so they are repeated.
+const _: () = assert!(SODA_ERROR_FAILURE == Error::EXIT_FAILURE as i32);
+const _: () = assert!(SODA_ERROR_INVALID_INPUT == Error::EXIT_INVALID_INPUT as i32);
+const _: () = assert!(SODA_ERROR_UNSUPPORTED == Error::EXIT_UNSUPPORTED as i32);
+const _: () = assert!(SODA_ERROR_CONVERSION_FAILED == Error::EXIT_CONVERSION_FAILED as i32);
+
+/// Options of the conversion. Null pointers select the defaults of the command line tool.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SodaOptions {
+    /// Name of the output section that holds the copied image, like `--section-name`.
+    pub section_name: *const c_char,
+
+    /// Path to a file that lists the names of the defined symbols to keep global, like `--only-export`.
+    pub only_export_file: *const c_char,
+
+    /// Path to a file that lists symbols to be renamed, like `--redefine-syms`.
+    pub redefine_syms_file: *const c_char,
+
+    /// Name of the symbol defined at the start of the converted image. An empty string omits the symbol.
+    pub image_start_symbol: *const c_char,
+
+    /// Name of the symbol defined at the end of the converted image. An empty string omits the symbol.
+    pub image_end_symbol: *const c_char,
+
+    /// Fill the padding bytes between sections in executable segments with trap instructions.
+    pub trap_padding: bool,
+
+    /// Convert position-independent executables as well.
+    pub allow_pie: bool,
+}
+
+/// Convert the shared library given by `input` and `len` into a relocatable object file of the same format.
+///
+/// `opts` may be null to use the default options. On success, `*out` and `*out_len` receive the output, which must be
+/// released with `soda_free_buffer`, and `SODA_OK` is returned. On failure, an error code is returned and, if
+/// `err_msg` is not null, `*err_msg` receives a message that must be released with `soda_free_error`. `*out` and
+/// `*err_msg` are set to null when they receive nothing.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes. `opts` must be null or point to valid options whose strings are null
+/// or NUL-terminated. `out` and `out_len` must be valid for writes, and `err_msg` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn soda_convert(
+    input: *const u8,
+    len: usize,
+    opts: *const SodaOptions,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+    err_msg: *mut *mut c_char,
+) -> i32 {
+    if !err_msg.is_null() {
+        *err_msg = std::ptr::null_mut();
+    }
+    if out.is_null() || out_len.is_null() {
+        return report_error(
+            err_msg,
+            SODA_ERROR_FAILURE,
+            "output pointers must not be null",
+        );
+    }
+    *out = std::ptr::null_mut();
+    *out_len = 0;
+    if input.is_null() {
+        return report_error(err_msg, SODA_ERROR_FAILURE, "input must not be null");
+    }
+
+    let input = std::slice::from_raw_parts(input, len);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let options = match opts.as_ref() {
+            Some(opts) => get_convert_options(opts)?,
+            None => ConvertOptions::default(),
+        };
+        crate::convert(input, &options).map_err(|err| {
+            let code = i32::from(err.exit_code());
+            (code, format!("{:#}", anyhow::Error::from(err)))
+        })
+    }));
+
+    match result {
+        Ok(Ok(output)) => {
+            let output = Box::into_raw(output.into_boxed_slice());
+            *out_len = output.len();
+            *out = output.cast();
+            SODA_OK
+        }
+        Ok(Err((code, msg))) => report_error(err_msg, code, &msg),
+        Err(_) => report_error(err_msg, SODA_ERROR_FAILURE, "the conversion panicked"),
+    }
+}
+
+/// Release an output buffer returned by `soda_convert`. Null buffers are ignored.
+///
+/// # Safety
+///
+/// `buf` and `len` must be null or an output buffer and its length returned by `soda_convert` that has not been
+/// released yet.
+#[no_mangle]
+pub unsafe extern "C" fn soda_free_buffer(buf: *mut u8, len: usize) {
+    if !buf.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(buf, len)));
+    }
+}
+
+/// Release an error message returned by `soda_convert`. Null messages are ignored.
+///
+/// # Safety
+///
+/// `msg` must be null or an error message returned by `soda_convert` that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn soda_free_error(msg: *mut c_char) {
+    if !msg.is_null() {
+        drop(CString::from_raw(msg));
+    }
+}
+
+/// Store a copy of the given message into `err_msg` if it is not null, and return the given code.
+unsafe fn report_error(err_msg: *mut *mut c_char, code: i32, msg: &str) -> i32 {
+    if !err_msg.is_null() {
+        // Messages never contain NUL bytes, but strip them anyway rather than fail to report.
+        let msg = CString::new(msg.replace('\0', "")).unwrap();
+        *err_msg = msg.into_raw();
+    }
+    code
+}
+
+unsafe fn get_convert_options(opts: &SodaOptions) -> Result<ConvertOptions, (i32, String)> {
+    let mut options = ConvertOptions {
+        trap_padding: opts.trap_padding,
+        allow_pie: opts.allow_pie,
+        ..Default::default()
+    };
+
+    if let Some(name) = get_str(opts.section_name)? {
+        options.section_name = String::from(name);
+    }
+    if let Some(path) = get_str(opts.only_export_file)? {
+        let only_export = crate::read_export_list(Path::new(path)).map_err(|err| {
+            let msg = format!("cannot read export list \"{}\": {}", path, err);
+            (SODA_ERROR_FAILURE, msg)
+        })?;
+        options.only_export = Some(only_export);
+    }
+    if let Some(path) = get_str(opts.redefine_syms_file)? {
+        options.redefine_syms = crate::read_redefine_syms(Path::new(path)).map_err(|err| {
+            let msg = format!("cannot read symbol rename list \"{}\": {}", path, err);
+            (SODA_ERROR_FAILURE, msg)
+        })?;
+    }
+    if let Some(name) = get_str(opts.image_start_symbol)? {
+        options.image_start_symbol = String::from(name);
+    }
+    if let Some(name) = get_str(opts.image_end_symbol)? {
+        options.image_end_symbol = String::from(name);
+    }
+
+    Ok(options)
+}
+
+/// Get the given NUL-terminated string, or `None` if the pointer is null.
+unsafe fn get_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, (i32, String)> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|_| (SODA_ERROR_FAILURE, String::from("options must be UTF-8")))
+}
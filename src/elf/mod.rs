@@ -1,76 +1,995 @@
-mod pass;
+pub mod pass;
+pub(crate) mod writer;
 
 #[cfg(test)]
-mod test;
+pub(crate) mod test;
 
-use anyhow::anyhow;
-use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
-use object::write::Object as OutputObject;
-use object::{Architecture, BinaryFormat, Endian, Endianness, Object as _, ObjectKind, ReadRef};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::elf::pass::init_array::{GenerateFiniArrayPass, GenerateInitArrayPass};
+use object::elf::{
+    DF_1_NODELETE, DF_1_NOOPEN, DF_1_NOW, DF_1_PIE, DF_BIND_NOW, DF_SYMBOLIC, DF_TEXTREL,
+    DT_BIND_NOW, DT_FLAGS, DT_FLAGS_1, DT_NEEDED, DT_NULL, DT_SONAME, DT_SYMBOLIC, DT_TEXTREL,
+    EF_ARM_ABI_FLOAT_HARD, EF_ARM_ABI_FLOAT_SOFT, EF_ARM_EABIMASK, EF_LARCH_ABI_MODIFIER_MASK,
+    EF_LARCH_OBJABI_V1, EF_PPC64_ABI, EF_RISCV_FLOAT_ABI, EF_RISCV_RVC, EF_RISCV_RVE, EF_RISCV_TSO,
+    ELFCLASS32, ELFCLASS64, ELFDATA2LSB, ELFDATA2MSB, PT_DYNAMIC, PT_INTERP, PT_TLS,
+};
+use object::read::elf::{
+    Dyn as _, ElfFile, FileHeader as ElfFileHeader, ProgramHeader as _, SectionHeader as _,
+};
+use object::read::StringTable;
+use object::{
+    AddressSize, Architecture, BinaryFormat, Endian, Endianness, FileFlags, Object as _,
+    ObjectKind, ReadRef,
+};
+
+use crate::elf::pass::boundary::GenerateBoundarySymbolsPass;
+use crate::elf::pass::defsym::{DefineSymbolsPass, SymbolValue};
+use crate::elf::pass::entry::GenerateEntrySymbolPass;
+use crate::elf::pass::gc::CollectGarbagePass;
+use crate::elf::pass::got::ScrubGotPass;
+use crate::elf::pass::ifunc::GenerateIfuncInitPass;
+use crate::elf::pass::init_array::{CtorsStyle, GenerateFiniArrayPass, GenerateInitArrayPass};
+use crate::elf::pass::layout::StabilizeLayoutPass;
+use crate::elf::pass::note::{describe_options, GenerateProvenanceNotePass};
 use crate::elf::pass::reloc::ConvertRelocationPass;
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::relro::ProtectRelroPass;
+use crate::elf::pass::require::RequireSymbolsPass;
 use crate::elf::pass::section::CopyLodableSectionsPass;
-use crate::elf::pass::symbol::GenerateSymbolPass;
-use crate::pass::PassManager;
+use crate::elf::pass::size::SummarizeSizesPass;
+use crate::elf::pass::symbol::{GenerateSymbolPass, SymbolNamespace};
+use crate::elf::pass::symbol_list::{ListSymbolsPass, SymbolList};
+use crate::elf::pass::textrel::CheckTextRelocationsPass;
+use crate::output::{Object as OutputObject, OutputBackend};
+use crate::pass::{
+    CreateContextError, DiagnosticSink, Diagnostics, PassManager, PassObserver, SectionNaming,
+};
+use crate::utils::demangle::with_demangle_enabled;
+use crate::utils::stringify::arch_to_str;
+use crate::Error;
+
+/// Options that control the conversion of a shared library. The observer, the diagnostic sink and demangling apply to
+/// all input formats, and the other options only apply to ELF inputs.
+#[derive(Clone, Debug)]
+pub struct ConvertOptions {
+    /// Name of the output section that holds the copied image.
+    pub section_name: String,
+
+    /// Fill the padding bytes between copied sections in executable segments with trap instructions.
+    pub trap_padding: bool,
+
+    /// The maximum allowed ratio between the size of the output image and the size of the input file. A value of 0
+    /// disables the check.
+    pub max_image_size_factor: u64,
+
+    /// Make the data covered by the PT_GNU_RELRO segment read-only at startup, like the dynamic loader does for the
+    /// input shared library. The data stays in place within the copied image. Only supported on x86_64 Linux.
+    pub protect_relro: bool,
+
+    /// The maximum alignment of input sections. Larger alignments are clamped to this value.
+    pub max_section_align: u64,
+
+    /// Continue when the data of an input section is truncated in the input file.
+    pub allow_truncated: bool,
+
+    /// Warn if the TLS image of the input is larger than this many bytes. A value of 0 disables the warning.
+    pub tls_size_warning: u64,
+
+    /// Keep the default version of versioned defined symbols in their names as `name@@VERSION`.
+    pub keep_symbol_versions: bool,
+
+    /// Report exported data objects that are likely targets of copy relocations.
+    pub copy_reloc_check: bool,
+
+    /// Demote exported data objects that are likely targets of copy relocations to local symbols.
+    pub demote_exported_data: bool,
+
+    /// Do not generate undefined symbols that no dynamic relocation refers to.
+    pub prune_undefined: bool,
+
+    /// Names of undefined symbols that are never pruned.
+    pub keep_undefined: Vec<String>,
+
+    /// Drop the definitions of linker-synthesized symbols that no dynamic relocation refers to.
+    pub drop_special_symbols: bool,
+
+    /// Drop the definitions of symbols in sections that are not copied and that no dynamic relocation refers to, instead
+    /// of turning them into undefined symbols.
+    pub drop_excluded_symbols: bool,
+
+    /// Names of symbols whose references are redirected to `__wrap_NAME`, like the `--wrap` option of ld.
+    pub wrap: Vec<String>,
+
+    /// Pairs of old and new names of symbols to be renamed, like the `--redefine-sym` option of objcopy.
+    pub redefine_syms: Vec<(String, String)>,
+
+    /// Names and values of global symbols to be defined, like the `--defsym` option of ld.
+    pub defsyms: Vec<(String, SymbolValue)>,
+
+    /// Names of global symbols that must be in the output, like the `--require-defined` option of ld. An undefined symbol
+    /// is added for each name that the input neither defines nor refers to, and references of the input to them are
+    /// never pruned.
+    pub require_defined: Vec<String>,
+
+    /// Glob patterns of the names of defined symbols that remain global. If given, all the other defined symbols are
+    /// demoted to local hidden symbols.
+    pub only_export: Option<Vec<String>>,
+
+    /// Names of the root symbols of the garbage collection of copied sections. If given, the copied sections that the
+    /// root symbols cannot reach through dynamic relocations are zero-filled, and their symbols and relocations are
+    /// dropped. See [`CollectGarbagePass`] for the sections that are always kept.
+    pub gc_roots: Option<Vec<String>>,
+
+    /// Bind references to the symbols defined in the copied image to the definitions directly, so that definitions in
+    /// other objects of the final link cannot interpose on them, like linking the input with `-Bsymbolic`. Weak
+    /// definitions and the symbols given in [`Self::only_export`] stay interposable.
+    ///
+    /// If `None`, references are bound locally if the input is linked with `-Bsymbolic` itself, which marks it with
+    /// DF_SYMBOLIC.
+    pub bind_locally: Option<bool>,
+
+    /// Make all defined global symbols weak.
+    pub weaken: bool,
+
+    /// Names of defined global symbols to be made weak.
+    pub weaken_symbols: Vec<String>,
+
+    /// Name of the symbol defined at the start of the copied image. No symbol is defined if the name is empty.
+    pub image_start_symbol: String,
+
+    /// Name of the symbol defined at the end of the copied image. No symbol is defined if the name is empty.
+    pub image_end_symbol: String,
+
+    /// Convert position-independent executables instead of rejecting them.
+    pub allow_pie: bool,
+
+    /// Fail if the input has text relocations, which are dynamic relocations whose sites lie in executable sections.
+    /// They are reported as a warning otherwise.
+    pub reject_textrel: bool,
+
+    /// The kind of sections that hold the pointers to the initialization and termination functions.
+    pub ctors_style: CtorsStyle,
+
+    /// Pairs of old and new names of output sections to be renamed, like the `--rename-section` option of objcopy.
+    pub rename_sections: Vec<(String, String)>,
+
+    /// Order the symbols of the output by their indexes in the dynamic symbol table of the input and the relocations by
+    /// their addresses in the input, so that the outputs of different versions of the input or of soda only differ where
+    /// their contents do. See [`StabilizeLayoutPass`] for the guarantees.
+    pub stable_layout: bool,
+
+    /// The writer that serializes the output. Both backends write equivalent files.
+    pub backend: OutputBackend,
+
+    /// Emit a `.note.soda` section that records the soname and the build ID of the input, the version of soda and these
+    /// options.
+    pub provenance_note: bool,
+
+    /// The list that receives the global symbols that the output defines and requires, after all the symbols have been
+    /// renamed, wrapped and pruned.
+    pub symbol_list: Option<Arc<SymbolList>>,
+
+    /// The observer that is notified of the progress of the conversion and may cancel it.
+    pub observer: Option<Arc<dyn PassObserver>>,
+
+    /// The sink that receives the diagnostics of the conversion. Diagnostics are dropped if there is no sink.
+    pub diagnostics: Option<Arc<dyn DiagnosticSink>>,
+
+    /// Demangle C++ and Rust symbol names in diagnostics and error messages.
+    pub demangle: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            section_name: String::from(CopyLodableSectionsPass::DEFAULT_SECTION_NAME),
+            trap_padding: false,
+            max_image_size_factor: CopyLodableSectionsPass::DEFAULT_MAX_IMAGE_SIZE_FACTOR,
+            protect_relro: false,
+            max_section_align: CopyLodableSectionsPass::DEFAULT_MAX_SECTION_ALIGN,
+            allow_truncated: false,
+            tls_size_warning: CopyLodableSectionsPass::DEFAULT_TLS_SIZE_WARNING,
+            keep_symbol_versions: false,
+            copy_reloc_check: true,
+            demote_exported_data: false,
+            prune_undefined: true,
+            keep_undefined: Vec::new(),
+            drop_special_symbols: false,
+            drop_excluded_symbols: false,
+            wrap: Vec::new(),
+            redefine_syms: Vec::new(),
+            defsyms: Vec::new(),
+            require_defined: Vec::new(),
+            only_export: None,
+            gc_roots: None,
+            bind_locally: None,
+            weaken: false,
+            weaken_symbols: Vec::new(),
+            image_start_symbol: String::from(GenerateBoundarySymbolsPass::DEFAULT_START_SYMBOL),
+            image_end_symbol: String::from(GenerateBoundarySymbolsPass::DEFAULT_END_SYMBOL),
+            allow_pie: false,
+            reject_textrel: false,
+            ctors_style: CtorsStyle::InitArray,
+            rename_sections: Vec::new(),
+            stable_layout: false,
+            backend: OutputBackend::Object,
+            provenance_note: true,
+            symbol_list: None,
+            observer: None,
+            diagnostics: None,
+            demangle: true,
+        }
+    }
+}
 
 /// Convert the given ELF input shared library into an ELF relocatable file.
-pub fn convert<'d, E, R>(input: ElfFile<'d, E, R>) -> anyhow::Result<OutputObject<'static>>
+pub fn convert<'d, E, R>(
+    input: ElfFile<'d, E, R>,
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
 {
     assert_eq!(input.kind(), ObjectKind::Dynamic);
 
-    let output = create_elf_output(&input)?;
+    with_demangle_enabled(options.demangle, || {
+        let diagnostics = Diagnostics::new(options.diagnostics.clone());
+        let section_naming = create_section_naming(options)?;
+        let is_pie = check_pie(&input, options, &diagnostics)?;
+        let dynamic_flags = check_dynamic_flags(&input, options, &diagnostics)?;
+        let output = create_elf_output(&input)?;
 
-    let mut pass_mgr = PassManager::new();
-    init_passes(&mut pass_mgr);
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_observer(options.observer.clone());
+        pass_mgr.set_diagnostics(diagnostics);
+        pass_mgr.set_section_naming(section_naming);
+        init_passes(&mut pass_mgr, options, is_pie, dynamic_flags, None);
 
-    let output = pass_mgr.run(input, output)?;
-    Ok(output)
+        let output = pass_mgr.run(input, output)?;
+        Ok(output)
+    })
 }
 
-fn create_elf_output<'d, E, R>(input: &ElfFile<'d, E, R>) -> anyhow::Result<OutputObject<'static>>
+/// Merge the given ELF input shared libraries, each given with its name, into a single ELF relocatable file.
+///
+/// The inputs are converted one after another into the same output, each into its own output sections. Global symbols
+/// are shared among the inputs, so that references to symbols defined by another input bind to their definitions
+/// directly instead of staying undefined. Multiple strong definitions of the same symbol are rejected.
+///
+/// The symbols that mark the boundaries of the image of each input are suffixed with the name of the input, such as
+/// `__soda_image_start_foo` for `libfoo.so`.
+pub fn merge<'d, E, R>(
+    inputs: Vec<(String, ElfFile<'d, E, R>)>,
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
 {
-    const SUPPORTED_ARCH: &'static [Architecture] = &[Architecture::X86_64];
+    with_demangle_enabled(options.demangle, || {
+        let Some((_, first_input)) = inputs.first() else {
+            return Err(CreateContextError::NoMergeInput.into());
+        };
 
-    let endian = Endianness::from_big_endian(input.endian().is_big_endian()).unwrap();
-    let arch = input.architecture();
+        let mut output = create_elf_output(first_input)?;
+        let mut input_names = HashSet::new();
+        for (name, input) in &inputs {
+            assert_eq!(input.kind(), ObjectKind::Dynamic);
+            if !input_names.insert(name.as_str()) {
+                return Err(CreateContextError::DuplicateMergeInput(name.clone()).into());
+            }
+            if input.architecture() != first_input.architecture()
+                || input.endian().is_big_endian() != first_input.endian().is_big_endian()
+            {
+                return Err(CreateContextError::MismatchedMergeArch {
+                    name: name.clone(),
+                    first_name: inputs[0].0.clone(),
+                }
+                .into());
+            }
+        }
+
+        let diagnostics = Diagnostics::new(options.diagnostics.clone());
+        let section_naming = create_section_naming(options)?;
+        let namespace = Rc::new(RefCell::new(SymbolNamespace::default()));
+        let num_inputs = inputs.len();
+        for (input_idx, (name, input)) in inputs.into_iter().enumerate() {
+            diagnostics.info(format_args!("Merging input shared library {}", name));
+            // Cancellation is not specific to any input.
+            let wrap_error = |error| match error {
+                Error::Cancelled => Error::Cancelled,
+                error => Error::MergeInputError {
+                    name: name.clone(),
+                    error: Box::new(error),
+                },
+            };
+            let is_pie = check_pie(&input, options, &diagnostics).map_err(wrap_error)?;
+            let dynamic_flags =
+                check_dynamic_flags(&input, options, &diagnostics).map_err(wrap_error)?;
+
+            let suffix = get_merge_symbol_suffix(&name);
+            let mut input_options = options.clone();
+            for symbol in [
+                &mut input_options.image_start_symbol,
+                &mut input_options.image_end_symbol,
+            ] {
+                if !symbol.is_empty() {
+                    *symbol = format!("{}_{}", symbol, suffix);
+                }
+            }
+            // The symbols are defined once, and references of later inputs bind to them through the namespace.
+            if input_idx > 0 {
+                input_options.defsyms.clear();
+            }
+            // The layout is rebuilt once all the inputs are converted, since the namespace refers to the symbols of the output
+            // that it replaces.
+            if input_idx + 1 < num_inputs {
+                input_options.stable_layout = false;
+            }
+
+            namespace.borrow_mut().begin_input(&name);
+            let mut pass_mgr = PassManager::new();
+            pass_mgr.set_observer(options.observer.clone());
+            pass_mgr.set_diagnostics(diagnostics.clone());
+            pass_mgr.set_section_naming(section_naming.clone());
+            init_passes(
+                &mut pass_mgr,
+                &input_options,
+                is_pie,
+                dynamic_flags,
+                Some(namespace.clone()),
+            );
+            output = pass_mgr
+                .run(input, output)
+                .map_err(|err| wrap_error(err.into()))?;
+        }
 
-    if !SUPPORTED_ARCH.contains(&arch) {
-        return Err(anyhow!(
-            "unsupported architecture: {}",
-            crate::utils::stringify::arch_to_str(arch)
+        diagnostics.info(format_args!(
+            "Bound {} references to symbols defined by other inputs",
+            namespace.borrow().num_bound()
+        ));
+
+        Ok(output)
+    })
+}
+
+/// Create the renames of the output sections given by the options.
+fn create_section_naming(options: &ConvertOptions) -> Result<SectionNaming, CreateContextError> {
+    let reserved_names = [
+        options.section_name.as_str(),
+        CopyLodableSectionsPass::TLS_SECTION_NAME,
+        ".init_array",
+        ".fini_array",
+        ".ctors",
+        ".dtors",
+        crate::elf::pass::ifunc::STUB_SECTION_NAME,
+        crate::elf::pass::ifunc::INIT_ARRAY_SECTION_NAME,
+        crate::elf::pass::relro::STUB_SECTION_NAME,
+        crate::elf::pass::relro::INIT_ARRAY_SECTION_NAME,
+        crate::elf::pass::reloc::TLSDESC_RESOLVER_SECTION_NAME,
+        crate::elf::pass::note::NOTE_SECTION_NAME,
+    ];
+    SectionNaming::new(&options.rename_sections, &reserved_names)
+}
+
+/// Determine whether the given input is a position-independent executable, and reject it unless the options allow it.
+fn check_pie<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    options: &ConvertOptions,
+    diagnostics: &Diagnostics,
+) -> Result<bool, Error>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    // Position-independent executables are of the same object kind as shared libraries, but they usually contain copy
+    // relocations and refer to symbols that only the startup files define, which make the conversion fail later with
+    // confusing errors.
+    let is_pie = is_pie(input)?;
+    if is_pie {
+        if !options.allow_pie {
+            return Err(CreateContextError::PositionIndependentExecutable.into());
+        }
+        diagnostics.warn(format_args!(
+            "Converting a position-independent executable: the interpreter section is dropped, the entry point is \
+             only kept as the local symbol \"{}\", and the startup code is still part of the image",
+            GenerateEntrySymbolPass::SYMBOL_NAME
+        ));
+    }
+    Ok(is_pie)
+}
+
+/// Get the flags in the dynamic section of the given input, and report the ones that mean something else once the input
+/// is linked statically.
+fn check_dynamic_flags<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    options: &ConvertOptions,
+    diagnostics: &Diagnostics,
+) -> Result<DynamicFlags, Error>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let flags = get_dynamic_flags(input)?;
+    if flags.is_symbolic() {
+        match options.bind_locally {
+            None => diagnostics.info(format_args!(
+                "The input is linked with -Bsymbolic (DF_SYMBOLIC), so references to its own symbols are bound to \
+                 its definitions as with --bind-locally"
+            )),
+            Some(false) => diagnostics.info(format_args!(
+                "The input is linked with -Bsymbolic (DF_SYMBOLIC), but references to its own symbols are not bound \
+                 locally, so definitions in the final link may interpose on them"
+            )),
+            Some(true) => {}
+        }
+    }
+    if flags.is_bind_now() {
+        diagnostics.info(format_args!(
+            "The input asks for immediate binding (DF_BIND_NOW or DF_1_NOW), which no longer applies: the final link \
+             decides how references to shared libraries are bound"
         ));
     }
+    if flags.is_nodelete() {
+        diagnostics.info(format_args!(
+            "The input is never unloaded (DF_1_NODELETE), which always holds once it's linked statically: its code \
+             and data live as long as the program"
+        ));
+    }
+    if flags.is_noopen() {
+        diagnostics.info(format_args!(
+            "The input may not be loaded with dlopen (DF_1_NOOPEN), which no longer applies: it's part of whatever \
+             the output is linked into"
+        ));
+    }
+    Ok(flags)
+}
 
-    Ok(OutputObject::new(BinaryFormat::Elf, arch, endian))
+/// Get the suffix of the boundary symbols of the input with the given file name when merging. The `lib` prefix and the
+/// `.so` extension together with any version after it are removed, and the characters that cannot appear in C
+/// identifiers are replaced with underscores.
+///
+/// For example, `libfoo-utils.so.1` gives `foo_utils`.
+fn get_merge_symbol_suffix(name: &str) -> String {
+    let name = name.strip_prefix("lib").unwrap_or(name);
+    let name = match name.find(".so") {
+        Some(pos) => &name[..pos],
+        None => name,
+    };
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
 }
 
-/// Register passes required to convert an ELF shared library.
-fn init_passes<'d, E, R>(pass_mgr: &mut PassManager<ElfFile<'d, E, R>>)
+/// Determine whether the given ELF file requests a program interpreter. Shared libraries don't, but position-independent
+/// executables do, although they are of the same object kind.
+pub fn has_interpreter<'d, E, R>(input: &ElfFile<'d, E, R>) -> bool
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    input
+        .raw_segments()
+        .iter()
+        .any(|seg_header| seg_header.p_type(endian) == PT_INTERP)
+}
+
+/// Get the address range of the dynamic table of the given ELF file, which is given by its PT_DYNAMIC segment.
+pub fn get_dynamic_table_range<'d, E, R>(input: &ElfFile<'d, E, R>) -> Option<Range<u64>>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    input
+        .raw_segments()
+        .iter()
+        .find(|seg_header| seg_header.p_type(endian) == PT_DYNAMIC)
+        .map(|seg_header| {
+            let addr: u64 = seg_header.p_vaddr(endian).into();
+            addr..addr + seg_header.p_memsz(endian).into()
+        })
+}
+
+/// The TLS initialization image of an ELF file, which is described by its PT_TLS segment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TlsImage {
+    /// Address of the image.
+    pub addr: u64,
+
+    /// Size of the initialized part of the image.
+    pub file_size: u64,
+
+    /// Size of the whole image, including the zero-initialized part. Each thread needs this much TLS space.
+    pub mem_size: u64,
+
+    /// Alignment of the image.
+    pub align: u64,
+}
+
+impl TlsImage {
+    /// Get the address range of the whole image, which is saturated at the end of the address space.
+    pub fn addr_range(&self) -> Range<u64> {
+        self.addr..self.addr.saturating_add(self.mem_size)
+    }
+}
+
+/// Get the TLS initialization image of the given ELF file, which is given by its PT_TLS segment.
+pub fn get_tls_image<'d, E, R>(input: &ElfFile<'d, E, R>) -> Option<TlsImage>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    input
+        .raw_segments()
+        .iter()
+        .find(|seg_header| seg_header.p_type(endian) == PT_TLS)
+        .map(|seg_header| TlsImage {
+            addr: seg_header.p_vaddr(endian).into(),
+            file_size: seg_header.p_filesz(endian).into(),
+            mem_size: seg_header.p_memsz(endian).into(),
+            align: seg_header.p_align(endian).into(),
+        })
+}
+
+/// Determine whether the given ELF file is a position-independent executable, which either requests a program
+/// interpreter or is marked with DF_1_PIE in its dynamic section.
+pub fn is_pie<'d, E, R>(input: &ElfFile<'d, E, R>) -> object::read::Result<bool>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    if has_interpreter(input) {
+        return Ok(true);
+    }
+
+    Ok(get_dynamic_flags(input)?.flags_1 & u64::from(DF_1_PIE) != 0)
+}
+
+/// Determine whether the given ELF file is marked as having text relocations, by either a DT_TEXTREL entry or DF_TEXTREL
+/// in the DT_FLAGS entry of its dynamic section.
+pub fn has_text_relocations<'d, E, R>(input: &ElfFile<'d, E, R>) -> object::read::Result<bool>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    Ok(get_dynamic_flags(input)?.flags & u64::from(DF_TEXTREL) != 0)
+}
+
+/// Get the names of the shared libraries that the given input depends on, which are listed by its DT_NEEDED entries, in
+/// the order of the entries.
+pub fn get_needed_libraries<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+) -> object::read::Result<Vec<String>>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let sections = input.raw_header().sections(endian, input.data())?;
+    let Some((dynamic, link)) = sections.dynamic(endian, input.data())? else {
+        return Ok(Vec::new());
+    };
+    // The string table is read as a slice, which is what `Dyn::string` expects.
+    let strings = sections.section(link)?.data(endian, input.data())?;
+    let strings = StringTable::new(strings, 0, strings.len() as u64);
+
+    let mut needed = Vec::new();
+    for entry in dynamic {
+        if entry.tag32(endian) == Some(DT_NEEDED) {
+            let name = entry.string(endian, strings)?;
+            needed.push(String::from_utf8_lossy(name).into_owned());
+        }
+    }
+    Ok(needed)
+}
+
+/// The flags in the dynamic section of an ELF file, which tell the dynamic linker how to load and bind it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DynamicFlags {
+    /// Value of the DT_FLAGS entry. The flags of the legacy DT_SYMBOLIC, DT_TEXTREL and DT_BIND_NOW entries are
+    /// included.
+    pub flags: u64,
+
+    /// Value of the DT_FLAGS_1 entry.
+    pub flags_1: u64,
+}
+
+impl DynamicFlags {
+    /// Determine whether the file binds references to its own symbols to its own definitions, like when it's linked
+    /// with `-Bsymbolic`.
+    pub fn is_symbolic(&self) -> bool {
+        self.flags & u64::from(DF_SYMBOLIC) != 0
+    }
+
+    /// Determine whether the file asks for all of its symbols to be bound when it's loaded rather than lazily.
+    pub fn is_bind_now(&self) -> bool {
+        self.flags & u64::from(DF_BIND_NOW) != 0 || self.flags_1 & u64::from(DF_1_NOW) != 0
+    }
+
+    /// Determine whether the file is never unloaded once it's loaded.
+    pub fn is_nodelete(&self) -> bool {
+        self.flags_1 & u64::from(DF_1_NODELETE) != 0
+    }
+
+    /// Determine whether the file may not be loaded with `dlopen`.
+    pub fn is_noopen(&self) -> bool {
+        self.flags_1 & u64::from(DF_1_NOOPEN) != 0
+    }
+}
+
+/// Get the flags in the dynamic section of the given input. The flags are empty if it has no dynamic section.
+pub fn get_dynamic_flags<'d, E, R>(input: &ElfFile<'d, E, R>) -> object::read::Result<DynamicFlags>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let sections = input.raw_header().sections(endian, input.data())?;
+    let Some((dynamic, _)) = sections.dynamic(endian, input.data())? else {
+        return Ok(DynamicFlags::default());
+    };
+
+    let mut flags = DynamicFlags::default();
+    for entry in dynamic {
+        match entry.tag32(endian) {
+            Some(DT_NULL) => break,
+            Some(DT_FLAGS) => flags.flags |= entry.d_val(endian).into(),
+            Some(DT_FLAGS_1) => flags.flags_1 |= entry.d_val(endian).into(),
+            Some(DT_SYMBOLIC) => flags.flags |= u64::from(DF_SYMBOLIC),
+            Some(DT_TEXTREL) => flags.flags |= u64::from(DF_TEXTREL),
+            Some(DT_BIND_NOW) => flags.flags |= u64::from(DF_BIND_NOW),
+            _ => {}
+        }
+    }
+    Ok(flags)
+}
+
+/// Get the soname of the given input, which is given by its DT_SONAME entry.
+pub fn get_soname<'d, E, R>(input: &ElfFile<'d, E, R>) -> object::read::Result<Option<String>>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let sections = input.raw_header().sections(endian, input.data())?;
+    let Some((dynamic, link)) = sections.dynamic(endian, input.data())? else {
+        return Ok(None);
+    };
+    let strings = sections.section(link)?.data(endian, input.data())?;
+    let strings = StringTable::new(strings, 0, strings.len() as u64);
+
+    for entry in dynamic {
+        if entry.tag32(endian) == Some(DT_SONAME) {
+            let name = entry.string(endian, strings)?;
+            return Ok(Some(String::from_utf8_lossy(name).into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Create an empty output relocatable file for the given ELF input shared library, which targets the same architecture
+/// and ABI as the input.
+pub fn create_elf_output<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+) -> Result<OutputObject<'static>, CreateContextError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let arch = input.architecture();
+    check_elf_layout(input, arch)?;
+    let endian = if input.endian().is_big_endian() {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+
+    // The OS/ABI is carried over for tools that tell the operating systems apart by it. The ABI version only describes
+    // what the input requires of the dynamic linker, which doesn't apply to relocatable files.
+    let mut output = OutputObject::new(BinaryFormat::Elf, arch, endian);
+    output.flags = FileFlags::Elf {
+        os_abi: input.raw_header().e_ident().os_abi,
+        abi_version: 0,
+        e_flags: get_output_e_flags(arch, input.raw_header().e_flags(input.endian())),
+    };
+
+    Ok(output)
+}
+
+/// Check that the given input targets a supported architecture, and that the class and the byte order of its header are
+/// the ones of the architecture. Inputs whose header contradicts their machine would otherwise be converted into objects
+/// that linkers reject with confusing errors.
+fn check_elf_layout<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    arch: Architecture,
+) -> Result<(), CreateContextError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    // The supported architectures with the class and, unless they come in both byte orders, the byte order of their ELF
+    // files.
+    #[rustfmt::skip]
+    const SUPPORTED_ARCH: &[(Architecture, AddressSize, Option<Endianness>)] = &[
+        (Architecture::X86_64,      AddressSize::U64, Some(Endianness::Little)),
+        (Architecture::I386,        AddressSize::U32, Some(Endianness::Little)),
+        (Architecture::Aarch64,     AddressSize::U64, None),
+        (Architecture::Arm,         AddressSize::U32, None),
+        (Architecture::LoongArch64, AddressSize::U64, Some(Endianness::Little)),
+        (Architecture::PowerPc64,   AddressSize::U64, None),
+        (Architecture::Riscv64,     AddressSize::U64, Some(Endianness::Little)),
+        (Architecture::S390x,       AddressSize::U64, Some(Endianness::Big)),
+        (Architecture::Mips64,      AddressSize::U64, None),
+    ];
+
+    let Some(&(_, address_size, endian)) = SUPPORTED_ARCH
+        .iter()
+        .find(|(supported_arch, _, _)| *supported_arch == arch)
+    else {
+        return Err(CreateContextError::UnsupportedArch(arch));
+    };
+
+    let e_ident = input.raw_header().e_ident();
+    let (is_64, class_name) = match e_ident.class {
+        ELFCLASS32 => (false, "ELFCLASS32"),
+        ELFCLASS64 => (true, "ELFCLASS64"),
+        class => {
+            return Err(CreateContextError::CorruptedData(format!(
+                "invalid ELF class {} in e_ident",
+                class
+            )))
+        }
+    };
+    let (is_big_endian, data_name) = match e_ident.data {
+        ELFDATA2LSB => (false, "ELFDATA2LSB"),
+        ELFDATA2MSB => (true, "ELFDATA2MSB"),
+        data => {
+            return Err(CreateContextError::CorruptedData(format!(
+                "invalid data encoding {} in e_ident",
+                data
+            )))
+        }
+    };
+
+    if is_64 != (address_size == AddressSize::U64) {
+        return Err(CreateContextError::CorruptedData(format!(
+            "e_ident says {}, but {} files are {}",
+            class_name,
+            arch_to_str(arch),
+            if is_64 { "ELFCLASS32" } else { "ELFCLASS64" }
+        )));
+    }
+    if let Some(endian) = endian {
+        if is_big_endian != endian.is_big_endian() {
+            return Err(CreateContextError::CorruptedData(format!(
+                "e_ident says {}, but {} files are {}",
+                data_name,
+                arch_to_str(arch),
+                if is_big_endian {
+                    "ELFDATA2LSB"
+                } else {
+                    "ELFDATA2MSB"
+                }
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the flags in the ELF header of the output relocatable file from those of the input shared library. Linkers refuse
+/// to link objects that disagree on the ABI, so the flags that describe the ABI of the code are carried over.
+fn get_output_e_flags(arch: Architecture, input_e_flags: u32) -> u32 {
+    match arch {
+        Architecture::Arm => {
+            input_e_flags & (EF_ARM_EABIMASK | EF_ARM_ABI_FLOAT_SOFT | EF_ARM_ABI_FLOAT_HARD)
+        }
+        Architecture::LoongArch64 => {
+            input_e_flags & (EF_LARCH_ABI_MODIFIER_MASK | EF_LARCH_OBJABI_V1)
+        }
+        Architecture::PowerPc64 => input_e_flags & EF_PPC64_ABI,
+        Architecture::Riscv64 => {
+            input_e_flags & (EF_RISCV_RVC | EF_RISCV_FLOAT_ABI | EF_RISCV_RVE | EF_RISCV_TSO)
+        }
+        // The ISA level, the ABI and the PIC flags all describe the code on MIPS.
+        Architecture::Mips64 => input_e_flags,
+        _ => 0,
+    }
+}
+
+/// Register passes required to convert an ELF shared library. Custom passes may be added to the pass manager after them.
+///
+/// The entry point is kept if the input is a position-independent executable. References to the symbols of the input
+/// are bound locally by default if the given dynamic flags of the input mark it as linked with `-Bsymbolic`. The global
+/// symbols are added through the given namespace, if any, when the input is merged with others.
+pub fn init_passes<'d, E, R>(
+    pass_mgr: &mut PassManager<ElfFile<'d, E, R>>,
+    options: &ConvertOptions,
+    is_pie: bool,
+    dynamic_flags: DynamicFlags,
+    namespace: Option<Rc<RefCell<SymbolNamespace>>>,
+) where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
 {
     // Copy input sections to output sections.
-    let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+    let cls_pass = pass_mgr.add_pass(CopyLodableSectionsPass {
+        section_name: options.section_name.clone(),
+        trap_padding: options.trap_padding,
+        max_image_size_factor: options.max_image_size_factor,
+        min_output_align: if options.protect_relro {
+            ProtectRelroPass::PAGE_SIZE
+        } else {
+            1
+        },
+        max_section_align: options.max_section_align,
+        allow_truncated: options.allow_truncated,
+        tls_size_warning: options.tls_size_warning,
+    });
+
+    // Read the dynamic relocations in the input shared library once for the passes below.
+    let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+
+    // Zero-fill the copied sections that the given root symbols cannot reach.
+    let gc_pass = options.gc_roots.as_ref().map(|roots| {
+        pass_mgr.add_pass(CollectGarbagePass {
+            cls_pass,
+            reloc_index_pass,
+            roots: roots.clone(),
+            symbolic: dynamic_flags.is_symbolic(),
+        })
+    });
+
+    // Report the relocations that patch code, or fail early if they are rejected.
+    pass_mgr.add_pass(CheckTextRelocationsPass {
+        cls_pass,
+        reloc_index_pass,
+        reject: options.reject_textrel,
+    });
+
+    // Zero the stale data in the copied GOT sections before the relocation conversion writes into them.
+    pass_mgr.add_pass(ScrubGotPass {
+        cls_pass,
+        reloc_index_pass,
+    });
 
     // Copy the dynamic symbols in the input shared library into the normal symbols in the output relocatable object.
-    let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass { cls_pass });
+    let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass {
+        keep_symbol_versions: options.keep_symbol_versions,
+        copy_reloc_check: options.copy_reloc_check,
+        demote_exported_data: options.demote_exported_data,
+        prune_undefined: options.prune_undefined,
+        keep_undefined: options
+            .keep_undefined
+            .iter()
+            .chain(&options.require_defined)
+            .cloned()
+            .collect(),
+        drop_special_symbols: options.drop_special_symbols,
+        drop_excluded_symbols: options.drop_excluded_symbols,
+        wrap: options.wrap.clone(),
+        redefine_syms: options.redefine_syms.clone(),
+        only_export: options.only_export.clone(),
+        weaken: options.weaken,
+        weaken_symbols: options.weaken_symbols.clone(),
+        namespace: namespace.clone(),
+        gc_pass,
+        ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+    });
+
+    // Define the symbols given in the options.
+    let defsym_pass = (!options.defsyms.is_empty()).then(|| {
+        pass_mgr.add_pass(DefineSymbolsPass {
+            sym_gen_pass,
+            symbols: options.defsyms.clone(),
+            namespace: namespace.clone(),
+        })
+    });
+
+    // Add undefined symbols for the required symbols, except for the ones defined above.
+    let required_names: Vec<String> = options
+        .require_defined
+        .iter()
+        .filter(|name| !options.defsyms.iter().any(|(defsym, _)| defsym == *name))
+        .cloned()
+        .collect();
+    let require_pass = (!required_names.is_empty()).then(|| {
+        pass_mgr.add_pass(RequireSymbolsPass {
+            sym_gen_pass,
+            names: required_names,
+            namespace,
+        })
+    });
+
+    // Define symbols that mark the boundaries of the copied image.
+    let boundary_pass = pass_mgr.add_pass(GenerateBoundarySymbolsPass {
+        cls_pass,
+        start_symbol: options.image_start_symbol.clone(),
+        end_symbol: options.image_end_symbol.clone(),
+    });
+
+    // Keep the entry point of position-independent executables as a local symbol.
+    if is_pie {
+        pass_mgr.add_pass(GenerateEntrySymbolPass { cls_pass });
+    }
+
+    // Generate .init_array and .fini_array sections, or .ctors and .dtors sections, in the output relocatable file. The relocations they convert are
+    // left out of the relocation conversion below.
+    let init_array_pass = pass_mgr.add_pass(GenerateInitArrayPass::new(
+        cls_pass,
+        reloc_index_pass,
+        options.ctors_style,
+    ));
+    let fini_array_pass = pass_mgr.add_pass(GenerateFiniArrayPass::new(
+        cls_pass,
+        reloc_index_pass,
+        options.ctors_style,
+    ));
 
     // Convert the dynamic relocations in the input shared library to corresponding static relocations in the output
     // relocatable file.
     pass_mgr.add_pass(ConvertRelocationPass {
+        bind_locally: options
+            .bind_locally
+            .unwrap_or_else(|| dynamic_flags.is_symbolic()),
+        keep_exported_bound: options.only_export.is_some(),
+        init_array_pass: Some(init_array_pass),
+        fini_array_pass: Some(fini_array_pass),
+        gc_pass,
+        ..ConvertRelocationPass::new(cls_pass, reloc_index_pass, sym_gen_pass)
+    });
+
+    // Generate initializers that apply the IRELATIVE relocations in the input shared library.
+    pass_mgr.add_pass(GenerateIfuncInitPass {
+        cls_pass,
+        reloc_index_pass,
+        gc_pass,
+    });
+
+    // Generate an initializer that makes the RELRO data read-only once the initializers above have written into it.
+    if options.protect_relro {
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+    }
+
+    // List the global symbols of the output once they no longer change.
+    if let Some(list) = &options.symbol_list {
+        pass_mgr.add_pass(ListSymbolsPass {
+            sym_gen_pass,
+            defsym_pass,
+            require_pass,
+            boundary_pass,
+            list: list.clone(),
+        });
+    }
+
+    // Report how much the output adds to the final binary.
+    pass_mgr.add_pass(SummarizeSizesPass {
         cls_pass,
-        sym_gen_pass,
+        reloc_index_pass,
     });
 
-    // Generate .init_array and .fini_array sections in the output relocatable file.
-    pass_mgr.add_pass(GenerateInitArrayPass::new(cls_pass));
-    pass_mgr.add_pass(GenerateFiniArrayPass::new(cls_pass));
+    // Record where the output comes from.
+    if options.provenance_note {
+        pass_mgr.add_pass(GenerateProvenanceNotePass {
+            options: describe_options(options),
+        });
+    }
+
+    // Reorder the symbols and the relocations once the output no longer changes.
+    if options.stable_layout {
+        pass_mgr.add_pass(StabilizeLayoutPass {
+            sym_gen_pass,
+            defsym_pass,
+            require_pass,
+            boundary_pass,
+        });
+    }
 }
@@ -0,0 +1,148 @@
+use std::convert::Infallible;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{ReadRef, SymbolFlags, SymbolKind, SymbolScope};
+
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::output::{Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that defines global symbols at the start and the end of the copied image.
+///
+/// Runtime components that patch or protect the converted code at run time can refer to these symbols to find the
+/// extent of the image. The end symbol sits exactly at the end of the output section. The pass outputs the defined
+/// symbols.
+#[derive(Debug)]
+pub struct GenerateBoundarySymbolsPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+
+    /// Name of the symbol at the start of the image. No symbol is generated if the name is empty.
+    pub start_symbol: String,
+
+    /// Name of the symbol at the end of the image. No symbol is generated if the name is empty.
+    pub end_symbol: String,
+}
+
+impl GenerateBoundarySymbolsPass {
+    /// The default value of [`Self::start_symbol`].
+    pub const DEFAULT_START_SYMBOL: &'static str = "__soda_image_start";
+
+    /// The default value of [`Self::end_symbol`].
+    pub const DEFAULT_END_SYMBOL: &'static str = "__soda_image_end";
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for GenerateBoundarySymbolsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "generate boundary symbols";
+
+    type Output = Vec<SymbolId>;
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut output = ctx.output.borrow_mut();
+
+        let mut defined = Vec::with_capacity(2);
+        for (name, value) in [
+            (&self.start_symbol, 0),
+            (&self.end_symbol, cls_output.output_section_size),
+        ] {
+            if name.is_empty() {
+                continue;
+            }
+
+            defined.push(output.add_symbol(OutputSymbol {
+                name: name.as_bytes().to_vec(),
+                value,
+                size: 0,
+                kind: SymbolKind::Label,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: OutputSymbolSection::Section(cls_output.output_section_id),
+                flags: SymbolFlags::None,
+            }));
+        }
+
+        Ok(defined)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object::read::elf::ElfFile64;
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, ObjectSymbol as _,
+    };
+
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::PassManager;
+
+    use super::GenerateBoundarySymbolsPass;
+
+    /// Run the pass on the given input, and get the name, address and section name of each defined global output
+    /// symbol.
+    fn run_boundary_test(
+        input: ElfFile64<'static>,
+        start_symbol: &str,
+        end_symbol: &str,
+    ) -> Vec<(String, u64, String)> {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(GenerateBoundarySymbolsPass {
+            cls_pass,
+            start_symbol: String::from(start_symbol),
+            end_symbol: String::from(end_symbol),
+        });
+        let output = pass_mgr.run(input, output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        output_file
+            .symbols()
+            .filter(|sym| sym.is_global() && sym.is_definition())
+            .map(|sym| {
+                let section = output_file
+                    .section_by_index(sym.section_index().unwrap())
+                    .unwrap();
+                (
+                    String::from(sym.name().unwrap()),
+                    sym.address(),
+                    String::from(section.name().unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_boundary_symbols_pass() {
+        let input = || crate::elf::test::builder::minimal_dso().build_elf64();
+
+        // The end symbol sits at the end of .data, which is also the end of the image.
+        assert_eq!(
+            run_boundary_test(
+                input(),
+                GenerateBoundarySymbolsPass::DEFAULT_START_SYMBOL,
+                GenerateBoundarySymbolsPass::DEFAULT_END_SYMBOL
+            ),
+            vec![
+                (String::from("__soda_image_start"), 0, String::from(".soda")),
+                (
+                    String::from("__soda_image_end"),
+                    0x2010,
+                    String::from(".soda")
+                ),
+            ]
+        );
+
+        assert_eq!(
+            run_boundary_test(input(), "", "image_end"),
+            vec![(String::from("image_end"), 0x2010, String::from(".soda"))]
+        );
+    }
+}
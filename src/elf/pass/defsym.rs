@@ -0,0 +1,289 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{ReadRef, SymbolFlags, SymbolKind, SymbolScope};
+use thiserror::Error;
+
+use crate::elf::pass::symbol::{
+    get_plain_symbol_name, GenerateSymbolError, GenerateSymbolPass, SymbolNamespace,
+};
+use crate::output::{Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that defines global symbols given on the command line, like the `--defsym` option of ld.
+///
+/// A symbol is either defined as an absolute symbol with the given value, or as an alias of a symbol defined by the
+/// input shared library, at the same location in the output. Undefined references of the input to a defined symbol bind
+/// to the definition. Defining a symbol that the input shared library defines as well is rejected. The pass outputs the
+/// defined symbols.
+#[derive(Debug)]
+pub struct DefineSymbolsPass {
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+
+    /// Names and values of the symbols to define.
+    pub symbols: Vec<(String, SymbolValue)>,
+
+    /// The namespace of global symbols shared with the conversions of other inputs that are merged into the same output.
+    pub namespace: Option<Rc<RefCell<SymbolNamespace>>>,
+}
+
+/// The value of a symbol defined by [`DefineSymbolsPass`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SymbolValue {
+    /// An absolute value.
+    Absolute(u64),
+
+    /// The location of the symbol with the given name that the input shared library defines.
+    Symbol(String),
+}
+
+impl SymbolValue {
+    /// Parse a value given as a hexadecimal number with a `0x` prefix, a decimal number, or a symbol name.
+    pub fn parse(value: &str) -> Option<Self> {
+        let number = match value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        };
+        match number {
+            Some(number) => Some(Self::Absolute(number)),
+            None if !value.is_empty() && !value.starts_with(|c: char| c.is_ascii_digit()) => {
+                Some(Self::Symbol(String::from(value)))
+            }
+            None => None,
+        }
+    }
+}
+
+impl Display for SymbolValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Absolute(value) => write!(f, "{:#x}", value),
+            Self::Symbol(name) => f.write_str(name),
+        }
+    }
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for DefineSymbolsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "define symbols";
+
+    type Output = Vec<SymbolId>;
+    type Error = DefineSymbolError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let sym_map = ctx.get_pass_output(self.sym_gen_pass);
+        let mut output = ctx.output.borrow_mut();
+
+        let mut defined = Vec::with_capacity(self.symbols.len());
+        let mut num_bound = 0;
+        for (name, value) in &self.symbols {
+            // Find the global output symbols of the input with the same name. Definitions conflict, and the undefined
+            // symbol, if any, becomes the definition.
+            let mut reference = None;
+            for (_, output_sym) in sym_map.iter() {
+                let sym = output.symbol(output_sym);
+                if sym.scope == SymbolScope::Compilation
+                    || get_plain_symbol_name(&sym.name) != name.as_bytes()
+                {
+                    continue;
+                }
+                if !sym.is_undefined() {
+                    return Err(DefineSymbolError::Conflict(name.clone()));
+                }
+                if sym.name == name.as_bytes() {
+                    reference.get_or_insert(output_sym);
+                }
+            }
+
+            let (sym_value, size, kind, section) = match value {
+                SymbolValue::Absolute(value) => {
+                    (*value, 0, SymbolKind::Label, OutputSymbolSection::Absolute)
+                }
+                SymbolValue::Symbol(target) => {
+                    let target_sym = sym_map
+                        .get_by_name(target)
+                        .map(|output_sym| output.symbol(output_sym))
+                        .filter(|sym| !sym.is_undefined())
+                        .ok_or_else(|| DefineSymbolError::UndefinedTarget {
+                            name: name.clone(),
+                            target: target.clone(),
+                        })?;
+                    (
+                        target_sym.value,
+                        target_sym.size,
+                        target_sym.kind,
+                        target_sym.section,
+                    )
+                }
+            };
+            let symbol = OutputSymbol {
+                name: name.as_bytes().to_vec(),
+                value: sym_value,
+                size,
+                kind,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section,
+                flags: SymbolFlags::None,
+            };
+
+            let output_sym = if let Some(namespace) = &self.namespace {
+                let mut namespace = namespace.borrow_mut();
+                namespace.begin_input("--defsym");
+                namespace.add_symbol(&mut output, symbol)?
+            } else if let Some(reference) = reference {
+                *output.symbol_mut(reference) = symbol;
+                reference
+            } else {
+                output.add_symbol(symbol)
+            };
+            defined.push(output_sym);
+            if reference.is_some() {
+                num_bound += 1;
+            }
+
+            ctx.diagnostics
+                .debug(format_args!("Defined symbol \"{}\" as {}", name, value));
+        }
+
+        if !self.symbols.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "Defined {} symbols, {} of which the input refers to",
+                self.symbols.len(),
+                num_bound
+            ));
+        }
+
+        Ok(defined)
+    }
+}
+
+/// Errors that may occur when defining symbols.
+#[derive(Debug, Error)]
+pub enum DefineSymbolError {
+    #[error("cannot define symbol \"{0}\", since the input shared library defines it")]
+    Conflict(String),
+
+    #[error("cannot define symbol \"{name}\" as \"{target}\", which the input shared library does not define")]
+    UndefinedTarget { name: String, target: String },
+
+    #[error(transparent)]
+    GenerateSymbolError(#[from] GenerateSymbolError),
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{R_X86_64_64, STB_GLOBAL, STT_NOTYPE};
+    use object::{Object as _, ObjectSection as _, ObjectSymbol as _, RelocationTarget};
+
+    use crate::elf::test::builder::BuilderSymbol;
+    use crate::ConvertOptions;
+
+    use super::{DefineSymbolError, SymbolValue};
+
+    fn convert_with_defsyms(defsyms: &[(&str, SymbolValue)]) -> Result<Vec<u8>, crate::Error> {
+        // The input refers to the undefined symbol vendor_heap_size.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let sym = builder.add_symbol(BuilderSymbol::new(
+            "vendor_heap_size",
+            STB_GLOBAL,
+            STT_NOTYPE,
+            0,
+            0,
+            0,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_64, sym, 0);
+        let options = ConvertOptions {
+            defsyms: defsyms
+                .iter()
+                .map(|(name, value)| (String::from(*name), value.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        crate::convert_elf(&builder.build(), &options)
+    }
+
+    #[test]
+    fn test_define_symbols_pass() {
+        let output = convert_with_defsyms(&[
+            ("vendor_heap_size", SymbolValue::Absolute(0x10000)),
+            ("func_alias", SymbolValue::Symbol(String::from("func"))),
+        ])
+        .unwrap();
+        let output_file = object::File::parse(&*output).unwrap();
+        let find_symbols = |name| {
+            output_file
+                .symbols()
+                .filter(|sym| sym.name() == Ok(name))
+                .collect::<Vec<_>>()
+        };
+
+        // The reference binds to the absolute definition.
+        let heap_size = find_symbols("vendor_heap_size");
+        assert_eq!(heap_size.len(), 1);
+        assert!(heap_size[0].is_global() && heap_size[0].is_definition());
+        assert_eq!(heap_size[0].section(), object::SymbolSection::Absolute);
+        assert_eq!(heap_size[0].address(), 0x10000);
+        let soda = output_file.section_by_name(".soda").unwrap();
+        assert!(soda
+            .relocations()
+            .any(|(_, reloc)| reloc.target() == RelocationTarget::Symbol(heap_size[0].index())));
+
+        // The alias is defined at the location of the target.
+        let func = &find_symbols("func")[0];
+        let func_alias = &find_symbols("func_alias")[0];
+        assert!(func_alias.is_global());
+        assert_eq!(func_alias.section_index(), func.section_index());
+        assert_eq!(func_alias.address(), func.address());
+    }
+
+    #[test]
+    fn test_define_symbols_pass_errors() {
+        let define_symbol_err = |defsym| {
+            let err = convert_with_defsyms(&[defsym]).unwrap_err();
+            let source = std::error::Error::source(&err).unwrap();
+            match source.downcast_ref::<DefineSymbolError>() {
+                Some(err) => err.to_string(),
+                None => panic!("unexpected error {:?}", err),
+            }
+        };
+
+        let err = define_symbol_err(("func", SymbolValue::Absolute(1)));
+        assert_eq!(
+            err,
+            "cannot define symbol \"func\", since the input shared library defines it"
+        );
+        let err = define_symbol_err(("alias", SymbolValue::Symbol(String::from("missing"))));
+        assert_eq!(
+            err,
+            "cannot define symbol \"alias\" as \"missing\", which the input shared library does not define"
+        );
+    }
+
+    #[test]
+    fn test_symbol_value_parse() {
+        assert_eq!(
+            SymbolValue::parse("0x10000"),
+            Some(SymbolValue::Absolute(0x10000))
+        );
+        assert_eq!(
+            SymbolValue::parse("4096"),
+            Some(SymbolValue::Absolute(4096))
+        );
+        assert_eq!(
+            SymbolValue::parse("vendor_heap"),
+            Some(SymbolValue::Symbol(String::from("vendor_heap")))
+        );
+        assert_eq!(SymbolValue::parse("0xg"), None);
+        assert_eq!(SymbolValue::parse("12ab"), None);
+        assert_eq!(SymbolValue::parse(""), None);
+    }
+}
@@ -0,0 +1,60 @@
+use std::convert::Infallible;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{Object as _, ReadRef, SymbolFlags, SymbolKind, SymbolScope};
+
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::output::{Symbol as OutputSymbol, SymbolSection as OutputSymbolSection};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that defines a local symbol at the entry point of the input, which is only meaningful for position-independent
+/// executables.
+///
+/// The entry point of the converted executable is not the entry point of the final link, so it is kept as an ordinary
+/// local symbol that only helps to find the startup code when inspecting the output.
+#[derive(Debug)]
+pub struct GenerateEntrySymbolPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+}
+
+impl GenerateEntrySymbolPass {
+    /// Name of the generated symbol.
+    pub const SYMBOL_NAME: &'static str = "__soda_entry";
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for GenerateEntrySymbolPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "generate entry symbol";
+
+    type Output = ();
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+
+        let entry = ctx.input.entry();
+        let Some(loc) = cls_output.translate(entry) else {
+            ctx.diagnostics.warn(format_args!(
+                "Entry point {:#x} lies outside of the copied image; no entry symbol is generated",
+                entry
+            ));
+            return Ok(());
+        };
+
+        ctx.output.borrow_mut().add_symbol(OutputSymbol {
+            name: Self::SYMBOL_NAME.as_bytes().to_vec(),
+            value: loc.offset,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: OutputSymbolSection::Section(loc.section_id),
+            flags: SymbolFlags::None,
+        });
+
+        Ok(())
+    }
+}
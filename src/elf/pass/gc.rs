@@ -0,0 +1,532 @@
+use std::collections::{HashMap, HashSet};
+
+use object::elf::{SHF_EXECINSTR, SHF_TLS, STV_PROTECTED};
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::read::Error as ReadError;
+use object::{
+    Architecture, Endian as _, Object as _, ObjectSection as _, ObjectSymbol as _, ReadRef,
+    RelocationTarget, SectionFlags, SectionIndex, SymbolFlags,
+};
+use thiserror::Error;
+
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::{CopyLodableSectionsOutput, CopyLodableSectionsPass, SectionMap};
+use crate::pass::{Cancelled, Pass, PassContext, PassHandle};
+
+/// Check for cancellation once every this many relocations.
+const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+/// A pass that collects the copied input sections that the given root symbols cannot reach, like the `--gc-sections`
+/// option of ld.
+///
+/// The references between input sections are given by the dynamic relocations: a relocation whose site is in one section
+/// and whose target address is in another one makes the latter reachable from the former. Starting from the sections
+/// that define the root symbols, references are followed until no more sections become reachable.
+///
+/// References that leave no dynamic relocation behind, such as calls and PC-relative references to definitions that
+/// cannot be interposed, cannot be followed. The collection is therefore conservative, and only sections made up of
+/// exported data objects are candidates. The following sections are always kept:
+///
+/// - Executable sections and TLS sections;
+/// - Sections with bytes that no exported symbol covers, since they may hold local data;
+/// - Sections that define protected symbols, since references to them are bound within the input;
+/// - Sections that overlap other sections.
+///
+/// Nothing is collected from inputs linked with `-Bsymbolic`, whose references to their own symbols are bound within the
+/// input as well, nor from MIPS inputs, whose GOT entries are mostly relocated without dynamic relocations.
+///
+/// A relocation whose target address lies in no section keeps the section before the address, and a relocation whose
+/// target address is the end of a section keeps that section as well as the one starting there.
+///
+/// The copied image keeps the layout of the input, so the collected sections are zero-filled in place rather than
+/// removed. Their symbols and the relocations whose sites lie within them are dropped by the passes that generate them.
+#[derive(Debug)]
+pub struct CollectGarbagePass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+
+    /// Names of the symbols whose sections are kept, along with the sections reachable from them.
+    pub roots: Vec<String>,
+
+    /// Whether the input binds references to its own symbols within itself, like when it's linked with `-Bsymbolic`.
+    pub symbolic: bool,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for CollectGarbagePass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "collect garbage";
+
+    type Output = CollectedSections;
+    type Error = CollectGarbageError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+
+        if self.symbolic {
+            ctx.diagnostics.warn(format_args!(
+                "Not collecting unreachable sections: the input is linked with -Bsymbolic (DF_SYMBOLIC), so its \
+                 references to its own symbols may leave no dynamic relocation behind"
+            ));
+            return Ok(CollectedSections::default());
+        }
+        if ctx.input.architecture() == Architecture::Mips64 {
+            ctx.diagnostics.warn(format_args!(
+                "Not collecting unreachable sections: the GOT of MIPS inputs is mostly relocated without dynamic \
+                 relocations"
+            ));
+            return Ok(CollectedSections::default());
+        }
+
+        let candidates = find_candidates(&ctx.input, cls_output)?;
+
+        // Addresses of the defined dynamic symbols, which are the targets of the relocations against them.
+        let mut symbol_addrs = HashMap::new();
+        let mut reachable = HashSet::new();
+        let mut worklist = Vec::new();
+        let mut found_roots = HashSet::new();
+        for input_sym in ctx.input.dynamic_symbols() {
+            let Some(section_idx) = input_sym.section_index() else {
+                continue;
+            };
+            symbol_addrs.insert(input_sym.index(), input_sym.address());
+
+            let name = input_sym.name()?;
+            if self.roots.iter().any(|root| root == name) {
+                found_roots.insert(name);
+                if candidates.contains(&section_idx) && reachable.insert(section_idx) {
+                    worklist.push(section_idx);
+                }
+            }
+        }
+        for root in &self.roots {
+            if !found_roots.contains(root.as_str()) {
+                ctx.diagnostics.warn(format_args!(
+                    "GC root \"{}\" is not defined by the input",
+                    root
+                ));
+            }
+        }
+
+        // References from sections that are always kept make their targets reachable right away. The references from
+        // candidates are followed once their sites become reachable.
+        let mut references: HashMap<SectionIndex, Vec<SectionIndex>> = HashMap::new();
+        for (reloc_idx, (addr, reloc)) in reloc_index.iter().enumerate() {
+            if reloc_idx % CANCEL_CHECK_INTERVAL == 0 {
+                ctx.check_cancelled()?;
+            }
+
+            let target_base = match reloc.target() {
+                RelocationTarget::Symbol(sym_idx) => match symbol_addrs.get(&sym_idx) {
+                    Some(addr) => *addr,
+                    // References to undefined symbols leave the image.
+                    None => continue,
+                },
+                _ => 0,
+            };
+            let addend = if reloc.has_implicit_addend() {
+                read_implicit_addend(&ctx.input, cls_output, addr)
+            } else {
+                reloc.addend()
+            };
+            let target_addr = target_base.wrapping_add(addend as u64);
+
+            let site = cls_output
+                .find_input_section(addr)
+                .filter(|idx| candidates.contains(idx));
+            for target in find_target_sections(cls_output, target_addr) {
+                if !candidates.contains(&target) {
+                    continue;
+                }
+                match site {
+                    Some(site) if site != target => {
+                        references.entry(site).or_default().push(target)
+                    }
+                    Some(_) => {}
+                    None => {
+                        if reachable.insert(target) {
+                            worklist.push(target);
+                        }
+                    }
+                }
+            }
+        }
+
+        while let Some(section_idx) = worklist.pop() {
+            for target in references.get(&section_idx).into_iter().flatten() {
+                if reachable.insert(*target) {
+                    worklist.push(*target);
+                }
+            }
+        }
+
+        let mut collected = CollectedSections::default();
+        let mut output = ctx.output.borrow_mut();
+        for map in &cls_output.section_maps {
+            if !candidates.contains(&map.index) || reachable.contains(&map.index) {
+                continue;
+            }
+
+            let size = map.addr_range.end - map.addr_range.start;
+            ctx.diagnostics.debug(format_args!(
+                "Collected unreachable section \"{}\" (section index {}, {} bytes)",
+                map.name, map.index.0, size
+            ));
+            // The copied image starts at address 0 of the input, see CopyLodableSectionsOutput::translate.
+            output
+                .section_mut(cls_output.output_section_id)
+                .data_mut()
+                .get_mut(map.addr_range.start as usize..map.addr_range.end as usize)
+                .unwrap_or_default()
+                .fill(0);
+            collected.size += size;
+            collected.sections.push(map.clone());
+        }
+
+        ctx.diagnostics.info(format_args!(
+            "Collected {} unreachable sections, zero-filling {} bytes of the image",
+            collected.sections.len(),
+            collected.size
+        ));
+
+        Ok(collected)
+    }
+}
+
+/// The output of [`CollectGarbagePass`].
+#[derive(Debug, Default)]
+pub struct CollectedSections {
+    /// The collected input sections, sorted by their addresses.
+    pub sections: Vec<SectionMap>,
+
+    /// Total size of the collected sections in bytes.
+    pub size: u64,
+}
+
+impl CollectedSections {
+    /// Determine whether the specified input section is collected.
+    pub fn is_section_collected(&self, idx: SectionIndex) -> bool {
+        self.sections.iter().any(|map| map.index == idx)
+    }
+
+    /// Determine whether the given address in the input image lies within a collected section.
+    pub fn contains(&self, addr: u64) -> bool {
+        let pos = self
+            .sections
+            .partition_point(|map| map.addr_range.end <= addr);
+        self.sections
+            .get(pos)
+            .is_some_and(|map| map.addr_range.contains(&addr))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CollectGarbageError {
+    #[error("read ELF failed: {0:?}")]
+    ReadElfError(#[from] ReadError),
+
+    #[error("{0}")]
+    Cancelled(#[from] Cancelled),
+}
+
+/// Find the copied input sections that may be collected. See [`CollectGarbagePass`] for the sections that are always
+/// kept.
+fn find_candidates<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    cls_output: &CopyLodableSectionsOutput,
+) -> Result<HashSet<SectionIndex>, ReadError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let mut exported_ranges: HashMap<SectionIndex, Vec<(u64, u64)>> = HashMap::new();
+    let mut protected_sections = HashSet::new();
+    for input_sym in input.dynamic_symbols() {
+        let Some(section_idx) = input_sym.section_index() else {
+            continue;
+        };
+        if let SymbolFlags::Elf { st_other, .. } = input_sym.flags() {
+            if st_other & 0x3 == STV_PROTECTED {
+                protected_sections.insert(section_idx);
+            }
+        }
+        if input_sym.is_global() {
+            let start = input_sym.address();
+            exported_ranges
+                .entry(section_idx)
+                .or_default()
+                .push((start, start.saturating_add(input_sym.size())));
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for (pos, map) in cls_output.section_maps.iter().enumerate() {
+        let section = input.section_by_index(map.index)?;
+        let SectionFlags::Elf { sh_flags } = section.flags() else {
+            continue;
+        };
+        if sh_flags & u64::from(SHF_EXECINSTR | SHF_TLS) != 0
+            || protected_sections.contains(&map.index)
+        {
+            continue;
+        }
+
+        let overlaps_prev =
+            pos > 0 && cls_output.section_maps[pos - 1].addr_range.end > map.addr_range.start;
+        let overlaps_next = cls_output
+            .section_maps
+            .get(pos + 1)
+            .is_some_and(|next| next.addr_range.start < map.addr_range.end);
+        if overlaps_prev || overlaps_next {
+            continue;
+        }
+
+        // The section is made up of exported symbols if they cover it from start to end without any gap.
+        let Some(ranges) = exported_ranges.get_mut(&map.index) else {
+            continue;
+        };
+        ranges.sort_unstable();
+        let mut covered_end = map.addr_range.start;
+        for (start, end) in ranges.iter() {
+            if *start > covered_end {
+                break;
+            }
+            covered_end = covered_end.max(*end);
+        }
+        if covered_end >= map.addr_range.end {
+            candidates.insert(map.index);
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Find the copied input sections that a reference to the given address keeps alive.
+///
+/// These are the section that contains the address and the section that ends at it, or the last section before the
+/// address if there are neither.
+fn find_target_sections(
+    cls_output: &CopyLodableSectionsOutput,
+    addr: u64,
+) -> impl Iterator<Item = SectionIndex> + '_ {
+    let before = cls_output
+        .section_maps
+        .iter()
+        .take_while(|map| map.addr_range.start <= addr);
+    let touching: Vec<_> = before
+        .clone()
+        .filter(|map| map.addr_range.contains(&addr) || map.addr_range.end == addr)
+        .map(|map| map.index)
+        .collect();
+    let fallback = touching
+        .is_empty()
+        .then(|| before.last().map(|map| map.index))
+        .flatten();
+    touching.into_iter().chain(fallback)
+}
+
+/// Read the implicit addend of the relocation at the given address from the input. The addend is assumed to be a word,
+/// which is the size of the relocations that refer to addresses.
+fn read_implicit_addend<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    cls_output: &CopyLodableSectionsOutput,
+    addr: u64,
+) -> i64
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let data = cls_output
+        .section_maps
+        .iter()
+        .find(|map| map.addr_range.contains(&addr))
+        .and_then(|map| input.section_by_index(map.index).ok())
+        .and_then(|section| {
+            let data = section.data().ok()?;
+            data.get((addr - section.address()) as usize..)
+        })
+        .unwrap_or_default();
+    if input.is_64() {
+        data.get(..8).map_or(0, |bytes| {
+            endian.read_u64_bytes(bytes.try_into().unwrap()) as i64
+        })
+    } else {
+        data.get(..4).map_or(0, |bytes| {
+            endian.read_u32_bytes(bytes.try_into().unwrap()) as i32 as i64
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::R_X86_64_RELATIVE;
+    use object::read::elf::ElfFile64;
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, ObjectSymbol as _,
+    };
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::elf::test::builder::{dead_blob_dso, ElfBuilder};
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Pass, PassHandle, PassManager};
+
+    use super::CollectGarbagePass;
+
+    struct CollectGarbagePassTest {
+        roots: &'static [&'static str],
+        expected_sections: &'static [&'static str],
+        expected_size: u64,
+    }
+
+    impl PassTest for CollectGarbagePassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CollectGarbagePass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(CollectGarbagePass {
+                cls_pass,
+                reloc_index_pass,
+                roots: self.roots.iter().map(|root| String::from(*root)).collect(),
+                symbolic: false,
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let names: Vec<_> = output
+                .sections
+                .iter()
+                .map(|map| map.name.as_str())
+                .collect();
+            assert_eq!(names, self.expected_sections);
+            assert_eq!(output.size, self.expected_size);
+        }
+
+        fn check_output_object(&mut self, output: &OutputObject<'static>) {
+            let data = output.write().unwrap();
+            let output_file = object::File::parse(&*data).unwrap();
+            let image = output_file
+                .section_by_name(".soda")
+                .unwrap()
+                .data()
+                .unwrap()
+                .to_vec();
+            let is_blob_collected = self.expected_sections.contains(&".rodata.blob");
+            assert!(image[0x3000..0x4000]
+                .iter()
+                .all(|b| *b == if is_blob_collected { 0 } else { 0xAB }));
+            let is_chained_collected = self.expected_sections.contains(&".rodata.chained");
+            assert!(image[0x4010..0x4020]
+                .iter()
+                .all(|b| *b == if is_chained_collected { 0 } else { 0xCD }));
+        }
+    }
+
+    fn run_gc_test(
+        builder: &ElfBuilder,
+        roots: &'static [&'static str],
+        expected_sections: &'static [&'static str],
+        expected_size: u64,
+    ) {
+        crate::pass::test::run_pass_test(
+            CollectGarbagePassTest {
+                roots,
+                expected_sections,
+                expected_size,
+            },
+            builder.build_elf64(),
+            OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
+        );
+    }
+
+    #[test]
+    fn test_collect_garbage_pass() {
+        let builder = dead_blob_dso();
+
+        // The blob is only collected once nothing refers to it, and the chained data once `table` is no longer a root.
+        // .text and .data are always kept: .text is executable, and `data` covers only half of .data.
+        run_gc_test(&builder, &["func", "table"], &[".rodata.blob"], 0x1000);
+        run_gc_test(
+            &builder,
+            &["func"],
+            &[".rodata.blob", ".data.table", ".rodata.chained"],
+            0x1018,
+        );
+        run_gc_test(&builder, &["blob", "table"], &[], 0);
+    }
+
+    #[test]
+    fn test_collect_garbage_pass_conservative() {
+        // A pointer in .data, which is always kept, keeps the blob.
+        let mut builder = dead_blob_dso();
+        builder.add_relocation(0x2008, R_X86_64_RELATIVE, 0, 0x3000);
+        run_gc_test(
+            &builder,
+            &["func"],
+            &[".data.table", ".rodata.chained"],
+            0x18,
+        );
+
+        // A pointer to the end of the blob keeps both the blob and .data.table starting there, which keeps the chained
+        // data in turn.
+        let mut builder = dead_blob_dso();
+        builder.add_relocation(0x2008, R_X86_64_RELATIVE, 0, 0x4000);
+        run_gc_test(&builder, &["func"], &[], 0);
+
+        // A pointer into the gap after the last section keeps the section before it.
+        let mut builder = dead_blob_dso();
+        builder.segments[2].p_memsz = 0x1100;
+        builder.add_relocation(0x2008, R_X86_64_RELATIVE, 0, 0x4080);
+        run_gc_test(
+            &builder,
+            &["func"],
+            &[".rodata.blob", ".data.table"],
+            0x1008,
+        );
+    }
+
+    #[test]
+    fn test_convert_gc_roots() {
+        let input = dead_blob_dso().build();
+        let convert = |roots: &[&str]| {
+            let options = crate::ConvertOptions {
+                gc_roots: Some(roots.iter().map(|root| String::from(*root)).collect()),
+                ..Default::default()
+            };
+            let output = crate::convert_elf(&input, &options).unwrap();
+            let output_file = object::File::parse(&*output).unwrap();
+            let symbols: Vec<_> = output_file
+                .symbols()
+                .filter(|sym| sym.is_definition())
+                .map(|sym| String::from(sym.name().unwrap()))
+                .collect();
+            let soda = output_file.section_by_name(".soda").unwrap();
+            let relocs: Vec<_> = soda.relocations().map(|(offset, _)| offset).collect();
+            (symbols, relocs)
+        };
+
+        // The symbols and the relocations of the collected sections are dropped.
+        let (symbols, relocs) = convert(&["func"]);
+        for name in ["func", "data"] {
+            assert!(symbols.iter().any(|sym| sym == name), "{:?}", symbols);
+        }
+        for name in ["blob", "table", "chained"] {
+            assert!(!symbols.iter().any(|sym| sym == name), "{:?}", symbols);
+        }
+        assert!(relocs.is_empty());
+
+        let (symbols, relocs) = convert(&["func", "table"]);
+        for name in ["table", "chained"] {
+            assert!(symbols.iter().any(|sym| sym == name), "{:?}", symbols);
+        }
+        assert!(!symbols.iter().any(|sym| sym == "blob"));
+        assert_eq!(relocs, vec![0x4000]);
+    }
+}
@@ -0,0 +1,237 @@
+use std::convert::Infallible;
+use std::ops::Range;
+
+use object::elf::{
+    R_390_TLS_DTPMOD, R_AARCH64_TLS_DTPMOD, R_ARM_TLS_DTPMOD32, R_LARCH_TLS_DTPMOD64,
+    R_PPC64_DTPMOD64, R_RISCV_TLS_DTPMOD64, R_X86_64_DTPMOD64,
+};
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{Architecture, Object as _, ObjectSection as _, ReadRef, RelocationKind, SectionKind};
+
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// Names of the GOT sections whose copied data is scrubbed. Sections of other kinds than writable data are left alone.
+const GOT_SECTION_NAMES: &[&str] = &[".got", ".got.plt"];
+
+/// A pass that zeroes the data of the GOT sections copied into the output, except for the slots that dynamic relocations
+/// refer to.
+///
+/// The link of the input shared library leaves absolute addresses in the GOT, such as the address of the dynamic table
+/// in the reserved entries at the start of `.got.plt`, which mean nothing after the conversion. The slots that dynamic
+/// relocations refer to are kept, because the relocation conversion reads implicit addends and TLS offsets from them.
+/// The second half of each tls_index pair is kept as well, since the offset it holds may have no relocation of its own.
+///
+/// `.plt` is left alone, because code still calls through its stubs. MIPS GOTs are left alone too, since the relocation
+/// conversion reads the entries of the primary GOT, none of which have dynamic relocations.
+#[derive(Debug)]
+pub struct ScrubGotPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for ScrubGotPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "scrub GOT";
+
+    type Output = ScrubGotOutput;
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let mut scrubbed_ranges = Vec::new();
+        if ctx.input.architecture() == Architecture::Mips64 {
+            return Ok(ScrubGotOutput { scrubbed_ranges });
+        }
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+        let word_size = if ctx.input.is_64() { 8 } else { 4 };
+        let dtpmod_kind = get_dtpmod_kind(ctx.input.architecture());
+
+        let mut output = ctx.output.borrow_mut();
+        for input_sec in ctx.input.sections() {
+            if input_sec.kind() != SectionKind::Data
+                || !input_sec
+                    .name()
+                    .is_ok_and(|name| GOT_SECTION_NAMES.contains(&name))
+                || !cls_output.is_section_copied(input_sec.index())
+            {
+                continue;
+            }
+
+            let sec_range = input_sec.address()..input_sec.address() + input_sec.size();
+            let mut next_addr = sec_range.start;
+            let mut scrub = |range: Range<u64>| {
+                if range.is_empty() {
+                    return;
+                }
+                let Some(loc) = cls_output.translate(range.start) else {
+                    return;
+                };
+                let len = (range.end - range.start) as usize;
+                if let Some(data) = output
+                    .section_mut(loc.section_id)
+                    .data_mut()
+                    .get_mut(loc.offset as usize..)
+                    .and_then(|data| data.get_mut(..len))
+                {
+                    data.fill(0);
+                    scrubbed_ranges.push(range);
+                }
+            };
+
+            for (addr, reloc) in reloc_index.in_range(sec_range.clone()) {
+                let mut len = match reloc.size() {
+                    0 => word_size,
+                    size => u64::from(size) / 8,
+                };
+                if Some(reloc.kind()) == dtpmod_kind {
+                    len = 2 * word_size;
+                }
+                scrub(next_addr..addr.max(next_addr));
+                next_addr = next_addr.max(addr + len);
+            }
+            scrub(next_addr..sec_range.end.max(next_addr));
+        }
+
+        ctx.diagnostics.debug(format_args!(
+            "Scrubbed {} GOT ranges: {:x?}",
+            scrubbed_ranges.len(),
+            scrubbed_ranges
+        ));
+
+        Ok(ScrubGotOutput { scrubbed_ranges })
+    }
+}
+
+/// The output of [`ScrubGotPass`].
+#[derive(Debug)]
+pub struct ScrubGotOutput {
+    /// Ranges of the input address space whose copied data is zeroed, in address order within each GOT section.
+    pub scrubbed_ranges: Vec<Range<u64>>,
+}
+
+/// Get the kind of the DTPMOD relocations of the given architecture, which refer to the first half of a tls_index pair.
+fn get_dtpmod_kind(arch: Architecture) -> Option<RelocationKind> {
+    let r_type = match arch {
+        Architecture::X86_64 => R_X86_64_DTPMOD64,
+        Architecture::Arm => R_ARM_TLS_DTPMOD32,
+        Architecture::Aarch64 => R_AARCH64_TLS_DTPMOD,
+        Architecture::LoongArch64 => R_LARCH_TLS_DTPMOD64,
+        Architecture::PowerPc64 => R_PPC64_DTPMOD64,
+        Architecture::Riscv64 => R_RISCV_TLS_DTPMOD64,
+        Architecture::S390x => R_390_TLS_DTPMOD,
+        _ => return None,
+    };
+    Some(RelocationKind::Elf(r_type))
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        PF_R, PF_W, PT_LOAD, R_X86_64_DTPMOD64, R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT, SHF_ALLOC,
+        SHF_WRITE, SHT_PROGBITS,
+    };
+    use object::read::elf::ElfFile64;
+    use object::{Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _};
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Pass, PassHandle, PassManager};
+
+    use super::ScrubGotPass;
+
+    struct ScrubGotPassTest;
+
+    impl PassTest for ScrubGotPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = ScrubGotPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(ScrubGotPass {
+                cls_pass,
+                reloc_index_pass,
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            assert_eq!(output.scrubbed_ranges, vec![0x3018..0x3020, 0x3020..0x3038]);
+        }
+    }
+
+    #[test]
+    fn test_scrub_got_pass() {
+        // .got holds a GLOB_DAT slot, a tls_index pair and a slot resolved by the link. .got.plt holds the three
+        // reserved slots and two JUMP_SLOT slots.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_section(
+            ".got",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_WRITE,
+            0x3000,
+            vec![0xaa; 0x20],
+        );
+        builder.add_section(
+            ".got.plt",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_WRITE,
+            0x3020,
+            vec![0xaa; 0x28],
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x48);
+        builder.add_relocation(0x3000, R_X86_64_GLOB_DAT, 2, 0);
+        builder.add_relocation(0x3008, R_X86_64_DTPMOD64, 0, 0);
+        builder.add_relocation(0x3038, R_X86_64_JUMP_SLOT, 1, 0);
+        builder.add_relocation(0x3040, R_X86_64_JUMP_SLOT, 1, 0);
+        let output =
+            || OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(ScrubGotPassTest, builder.build_elf64(), output());
+
+        let mut pass_mgr = PassManager::new();
+        ScrubGotPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output()).unwrap();
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let data = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .data()
+            .unwrap();
+
+        // The GLOB_DAT slot and the tls_index pair are kept, and the slot resolved by the link is zeroed.
+        assert_eq!(&data[0x3000..0x3018], &[0xaa; 0x18]);
+        assert_eq!(&data[0x3018..0x3020], &[0; 8]);
+
+        // The reserved .got.plt header slots are zeroed, and the JUMP_SLOT slots are kept.
+        assert_eq!(&data[0x3020..0x3038], &[0; 0x18]);
+        assert_eq!(&data[0x3038..0x3048], &[0xaa; 0x10]);
+
+        // Other sections are left alone.
+        assert_eq!(&data[0x1000..0x1010], &[0xc3; 0x10]);
+    }
+
+    #[test]
+    fn test_scrub_got_spdlog() {
+        let input = crate::elf::test::get_test_input_file();
+        let got_plt = input.section_by_name(".got.plt").unwrap();
+        let header = got_plt.address() as usize..got_plt.address() as usize + 0x18;
+        assert_ne!(&got_plt.data().unwrap()[..0x18], &[0; 0x18]);
+
+        let output = crate::convert(input.data(), &Default::default()).unwrap();
+        let output_file = object::File::parse(output.as_slice()).unwrap();
+        let output_data = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .data()
+            .unwrap();
+        assert_eq!(&output_data[header], &[0; 0x18]);
+    }
+}
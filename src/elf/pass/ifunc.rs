@@ -0,0 +1,588 @@
+use object::elf::{
+    R_386_IRELATIVE, R_390_IRELATIVE, R_AARCH64_ADR_PREL_PG_HI21, R_AARCH64_CALL26,
+    R_AARCH64_IRELATIVE, R_AARCH64_LDST64_ABS_LO12_NC, R_ARM_IRELATIVE, R_LARCH_B26,
+    R_LARCH_IRELATIVE, R_LARCH_PCALA_HI20, R_LARCH_PCALA_LO12, R_PPC64_IRELATIVE,
+    R_X86_64_IRELATIVE, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_INIT_ARRAY, SHT_PROGBITS,
+};
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{
+    Architecture, Object as _, ReadRef, RelocationEncoding, RelocationKind, SectionFlags,
+    SectionKind,
+};
+use thiserror::Error;
+
+use crate::elf::pass::gc::CollectGarbagePass;
+use crate::elf::pass::reloc::{
+    output_reloc_size, take_implicit_addend, R_MIPS_IRELATIVE, R_RISCV_IRELATIVE,
+};
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::output::{Object as OutputObject, Relocation as OutputRelocation, SectionId, SymbolId};
+use crate::pass::{Pass, PassContext, PassHandle, SectionNaming};
+use crate::utils::stringify::{arch_to_str, reloc_kind_to_str};
+
+/// A pass that converts the IRELATIVE relocations in the input shared library.
+///
+/// An IRELATIVE relocation asks the dynamic loader to call an IFUNC resolver and store the returned address into the
+/// relocated slot. Static linkers have no equivalent relocation for arbitrary slots, so for each IRELATIVE relocation
+/// this pass generates a small stub function that does the same thing, and registers the stub in a `.init_array`
+/// section whose priority makes it run before all user constructors.
+///
+/// IFUNC symbols exported by the input shared library are kept as STT_GNU_IFUNC symbols by [`GenerateSymbolPass`]. The
+/// static linker resolves references to them on its own.
+///
+/// [`GenerateSymbolPass`]: crate::elf::pass::symbol::GenerateSymbolPass
+#[derive(Debug)]
+pub struct GenerateIfuncInitPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+
+    /// The pass that collects unreachable sections. IRELATIVE relocations whose sites lie in the collected sections are
+    /// dropped.
+    pub gc_pass: Option<PassHandle<CollectGarbagePass>>,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for GenerateIfuncInitPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "generate ifunc initializers";
+
+    type Output = ();
+    type Error = GenerateIfuncInitError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        // No stub generator is available for i386, ARM, PowerPC64, RISC-V and s390x yet, so their IRELATIVE relocations
+        // are rejected below.
+        let arch = ctx.input.architecture();
+        let (irelative_kind, get_stub): (_, Option<StubGenerator>) = match arch {
+            Architecture::X86_64 => (
+                RelocationKind::Elf(R_X86_64_IRELATIVE),
+                Some(get_stub_x86_64),
+            ),
+            Architecture::I386 => (RelocationKind::Elf(R_386_IRELATIVE), None),
+            Architecture::Aarch64 => (
+                RelocationKind::Elf(R_AARCH64_IRELATIVE),
+                Some(get_stub_aarch64),
+            ),
+            Architecture::Arm => (RelocationKind::Elf(R_ARM_IRELATIVE), None),
+            Architecture::LoongArch64 => (
+                RelocationKind::Elf(R_LARCH_IRELATIVE),
+                Some(get_stub_loongarch64),
+            ),
+            Architecture::PowerPc64 => (RelocationKind::Elf(R_PPC64_IRELATIVE), None),
+            Architecture::Riscv64 => (RelocationKind::Elf(R_RISCV_IRELATIVE), None),
+            Architecture::S390x => (RelocationKind::Elf(R_390_IRELATIVE), None),
+            Architecture::Mips64 => (RelocationKind::Elf(R_MIPS_IRELATIVE), None),
+            arch => {
+                return Err(GenerateIfuncInitError::UnsupportedArch(arch));
+            }
+        };
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let collected = self.gc_pass.map(|pass| ctx.get_pass_output(pass));
+
+        let irelative_relocs: Vec<_> = ctx
+            .get_pass_output(self.reloc_index_pass)
+            .iter()
+            .filter(|(_, input_reloc)| input_reloc.kind() == irelative_kind)
+            .filter(|(addr, _)| !collected.is_some_and(|collected| collected.contains(*addr)))
+            .collect();
+        if irelative_relocs.is_empty() {
+            return Ok(());
+        }
+        let Some(get_stub) = get_stub else {
+            return Err(GenerateIfuncInitError::UnsupportedArch(arch));
+        };
+
+        let mut output = ctx.output.borrow_mut();
+        let mut builder = IfuncStubBuilder::new(&mut output, &ctx.section_naming);
+
+        for (input_reloc_addr, input_reloc) in irelative_relocs {
+            let slot = match cls_output.translate(input_reloc_addr) {
+                Some(loc) => loc,
+                None => {
+                    ctx.diagnostics.warn(format_args!(
+                        "{} relocation at {:#x} happens outside of loadable sections",
+                        reloc_kind_to_str(arch, irelative_kind),
+                        input_reloc_addr
+                    ));
+                    continue;
+                }
+            };
+
+            // The resolver address of a REL relocation is stored in the slot.
+            let resolver_addr = if input_reloc.has_implicit_addend() {
+                let size = output_reloc_size(arch, irelative_kind);
+                take_implicit_addend(builder.output, &slot, usize::from(size), ctx.input.endian())
+                    .ok_or(GenerateIfuncInitError::TruncatedAddend(input_reloc_addr))?
+            } else {
+                input_reloc.addend()
+            };
+            let resolver = cls_output.get_address_reference(resolver_addr as u64);
+            let (code, relocs) = get_stub(resolver, (slot.section_symbol, slot.offset as i64));
+            builder.add_stub(&code, relocs);
+        }
+
+        ctx.diagnostics.info(format_args!(
+            "Generated {} initializers for IRELATIVE relocations",
+            builder.num_stubs
+        ));
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when generating initializers for IRELATIVE relocations.
+#[derive(Debug, Error)]
+pub enum GenerateIfuncInitError {
+    #[error("unsupported architecture: {}", arch_to_str(*.0))]
+    UnsupportedArch(Architecture),
+
+    #[error("the resolver address of the IRELATIVE relocation at {0:#x} lies outside of the copied image")]
+    TruncatedAddend(u64),
+}
+
+/// Name of the output section that contains the generated stubs.
+pub const STUB_SECTION_NAME: &str = ".text.soda.irelative";
+
+/// Name of the output section that refers to the generated stubs. The priority is the highest available one so that
+/// the stubs run before any constructor that might call an IFUNC.
+pub const INIT_ARRAY_SECTION_NAME: &str = ".init_array.00000";
+
+/// Emits IFUNC stubs and the `.init_array` entries that call them.
+struct IfuncStubBuilder<'o> {
+    output: &'o mut OutputObject<'static>,
+    section_naming: &'o SectionNaming,
+    stub_section: Option<(SectionId, SymbolId)>,
+    init_array_section: Option<SectionId>,
+    num_stubs: usize,
+}
+
+impl<'o> IfuncStubBuilder<'o> {
+    fn new(output: &'o mut OutputObject<'static>, section_naming: &'o SectionNaming) -> Self {
+        Self {
+            output,
+            section_naming,
+            stub_section: None,
+            init_array_section: None,
+            num_stubs: 0,
+        }
+    }
+
+    /// Add a stub with the given code and relocations, and register it in the `.init_array` section.
+    fn add_stub(&mut self, code: &[u8], relocs: Vec<OutputRelocation>) {
+        let (stub_sec_id, stub_sec_sym) = self.get_stub_section();
+        let stub_offset = self.output.append_section_data(stub_sec_id, code, 16);
+        for mut reloc in relocs {
+            reloc.offset += stub_offset;
+            self.output.add_relocation(stub_sec_id, reloc).unwrap();
+        }
+
+        let init_array_sec_id = self.get_init_array_section();
+        let entry_offset = self
+            .output
+            .append_section_data(init_array_sec_id, &[0u8; 8], 8);
+        self.output
+            .add_relocation(
+                init_array_sec_id,
+                OutputRelocation {
+                    offset: entry_offset,
+                    size: 64,
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    symbol: stub_sec_sym,
+                    addend: stub_offset as i64,
+                },
+            )
+            .unwrap();
+
+        self.num_stubs += 1;
+    }
+
+    fn get_stub_section(&mut self) -> (SectionId, SymbolId) {
+        if let Some(sec) = self.stub_section {
+            return sec;
+        }
+
+        let sec_id = self.output.add_section(
+            Vec::new(),
+            self.section_naming
+                .get(STUB_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Elf(SHT_PROGBITS),
+        );
+        self.output.section_mut(sec_id).flags = SectionFlags::Elf {
+            sh_flags: (SHF_ALLOC | SHF_EXECINSTR) as u64,
+        };
+        let sec_sym = self.output.section_symbol(sec_id);
+
+        self.stub_section = Some((sec_id, sec_sym));
+        (sec_id, sec_sym)
+    }
+
+    fn get_init_array_section(&mut self) -> SectionId {
+        if let Some(sec_id) = self.init_array_section {
+            return sec_id;
+        }
+
+        let sec_id = self.output.add_section(
+            Vec::new(),
+            self.section_naming
+                .get(INIT_ARRAY_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Elf(SHT_INIT_ARRAY),
+        );
+        self.output.section_mut(sec_id).flags = SectionFlags::Elf {
+            sh_flags: (SHF_ALLOC | SHF_WRITE) as u64,
+        };
+
+        self.init_array_section = Some(sec_id);
+        sec_id
+    }
+}
+
+/// Generates the code of a stub that calls the resolver and stores the returned address into the slot, together with
+/// the relocations relative to the beginning of the stub. Both the resolver and the slot are given as a symbol plus an
+/// addend.
+type StubGenerator = fn((SymbolId, i64), (SymbolId, i64)) -> (Vec<u8>, Vec<OutputRelocation>);
+
+/// Generate the x86_64 stub code, together with the relocations relative to the beginning of the stub:
+///
+/// ```text
+/// sub  $8, %rsp
+/// call resolver
+/// lea  slot(%rip), %rcx
+/// mov  %rax, (%rcx)
+/// add  $8, %rsp
+/// ret
+/// ```
+fn get_stub_x86_64(
+    resolver: (SymbolId, i64),
+    slot: (SymbolId, i64),
+) -> (Vec<u8>, Vec<OutputRelocation>) {
+    #[rustfmt::skip]
+    let code = vec![
+        0x48, 0x83, 0xEC, 0x08,                   // sub  $8, %rsp
+        0xE8, 0x00, 0x00, 0x00, 0x00,             // call resolver
+        0x48, 0x8D, 0x0D, 0x00, 0x00, 0x00, 0x00, // lea  slot(%rip), %rcx
+        0x48, 0x89, 0x01,                         // mov  %rax, (%rcx)
+        0x48, 0x83, 0xC4, 0x08,                   // add  $8, %rsp
+        0xC3,                                     // ret
+    ];
+
+    // The PC-relative displacements are relative to the end of the instructions, which is 4 bytes after the
+    // displacement fields.
+    let relocs = vec![
+        OutputRelocation {
+            offset: 5,
+            size: 32,
+            kind: RelocationKind::Relative,
+            encoding: RelocationEncoding::Generic,
+            symbol: resolver.0,
+            addend: resolver.1 - 4,
+        },
+        OutputRelocation {
+            offset: 12,
+            size: 32,
+            kind: RelocationKind::Relative,
+            encoding: RelocationEncoding::Generic,
+            symbol: slot.0,
+            addend: slot.1 - 4,
+        },
+    ];
+
+    (code, relocs)
+}
+
+/// Generate the AArch64 stub code, together with the relocations relative to the beginning of the stub:
+///
+/// ```text
+/// stp  x29, x30, [sp, #-16]!
+/// bl   resolver
+/// adrp x1, slot
+/// str  x0, [x1, :lo12:slot]
+/// ldp  x29, x30, [sp], #16
+/// ret
+/// ```
+fn get_stub_aarch64(
+    resolver: (SymbolId, i64),
+    slot: (SymbolId, i64),
+) -> (Vec<u8>, Vec<OutputRelocation>) {
+    #[rustfmt::skip]
+    let code = vec![
+        0xFD, 0x7B, 0xBF, 0xA9, // stp  x29, x30, [sp, #-16]!
+        0x00, 0x00, 0x00, 0x94, // bl   resolver
+        0x01, 0x00, 0x00, 0x90, // adrp x1, slot
+        0x20, 0x00, 0x00, 0xF9, // str  x0, [x1, :lo12:slot]
+        0xFD, 0x7B, 0xC1, 0xA8, // ldp  x29, x30, [sp], #16
+        0xC0, 0x03, 0x5F, 0xD6, // ret
+    ];
+
+    let relocs = vec![
+        OutputRelocation {
+            offset: 4,
+            size: 0,
+            kind: RelocationKind::Elf(R_AARCH64_CALL26),
+            encoding: RelocationEncoding::Generic,
+            symbol: resolver.0,
+            addend: resolver.1,
+        },
+        OutputRelocation {
+            offset: 8,
+            size: 0,
+            kind: RelocationKind::Elf(R_AARCH64_ADR_PREL_PG_HI21),
+            encoding: RelocationEncoding::Generic,
+            symbol: slot.0,
+            addend: slot.1,
+        },
+        OutputRelocation {
+            offset: 12,
+            size: 0,
+            kind: RelocationKind::Elf(R_AARCH64_LDST64_ABS_LO12_NC),
+            encoding: RelocationEncoding::Generic,
+            symbol: slot.0,
+            addend: slot.1,
+        },
+    ];
+
+    (code, relocs)
+}
+
+/// Generate the LoongArch64 stub code, together with the relocations relative to the beginning of the stub:
+///
+/// ```text
+/// addi.d    $sp, $sp, -16
+/// st.d      $ra, $sp, 8
+/// bl        resolver
+/// pcalau12i $t0, %pc_hi20(slot)
+/// st.d      $a0, $t0, %pc_lo12(slot)
+/// ld.d      $ra, $sp, 8
+/// addi.d    $sp, $sp, 16
+/// ret
+/// ```
+fn get_stub_loongarch64(
+    resolver: (SymbolId, i64),
+    slot: (SymbolId, i64),
+) -> (Vec<u8>, Vec<OutputRelocation>) {
+    #[rustfmt::skip]
+    let code = vec![
+        0x63, 0xC0, 0xFF, 0x02, // addi.d    $sp, $sp, -16
+        0x61, 0x20, 0xC0, 0x29, // st.d      $ra, $sp, 8
+        0x00, 0x00, 0x00, 0x54, // bl        resolver
+        0x0C, 0x00, 0x00, 0x1A, // pcalau12i $t0, %pc_hi20(slot)
+        0x84, 0x01, 0xC0, 0x29, // st.d      $a0, $t0, %pc_lo12(slot)
+        0x61, 0x20, 0xC0, 0x28, // ld.d      $ra, $sp, 8
+        0x63, 0x40, 0xC0, 0x02, // addi.d    $sp, $sp, 16
+        0x20, 0x00, 0x00, 0x4C, // ret
+    ];
+
+    let relocs = vec![
+        OutputRelocation {
+            offset: 8,
+            size: 0,
+            kind: RelocationKind::Elf(R_LARCH_B26),
+            encoding: RelocationEncoding::Generic,
+            symbol: resolver.0,
+            addend: resolver.1,
+        },
+        OutputRelocation {
+            offset: 12,
+            size: 0,
+            kind: RelocationKind::Elf(R_LARCH_PCALA_HI20),
+            encoding: RelocationEncoding::Generic,
+            symbol: slot.0,
+            addend: slot.1,
+        },
+        OutputRelocation {
+            offset: 16,
+            size: 0,
+            kind: RelocationKind::Elf(R_LARCH_PCALA_LO12),
+            encoding: RelocationEncoding::Generic,
+            symbol: slot.0,
+            addend: slot.1,
+        },
+    ];
+
+    (code, relocs)
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        R_AARCH64_ADR_PREL_PG_HI21, R_AARCH64_IRELATIVE, R_AARCH64_LDST64_ABS_LO12_NC,
+        R_LARCH_IRELATIVE, R_LARCH_PCALA_HI20, R_LARCH_PCALA_LO12, R_X86_64_IRELATIVE,
+    };
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, RelocationKind,
+    };
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::PassManager;
+
+    use super::{GenerateIfuncInitPass, INIT_ARRAY_SECTION_NAME, STUB_SECTION_NAME};
+
+    #[test]
+    fn test_generate_ifunc_init_pass() {
+        // .data[0] and .data[8] are initialized by the resolver at .text[0].
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2000, R_X86_64_IRELATIVE, 0, 0x1000);
+        builder.add_relocation(0x2008, R_X86_64_IRELATIVE, 0, 0x1000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateIfuncInitPass {
+            cls_pass,
+            reloc_index_pass,
+            gc_pass: None,
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stub_section = output_file.section_by_name(STUB_SECTION_NAME).unwrap();
+        // Each stub is 24 bytes long and aligned to 16 bytes.
+        assert_eq!(stub_section.size(), 32 + 24);
+        let stub_relocs: Vec<_> = stub_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.kind(), reloc.addend()))
+            .collect();
+        assert_eq!(
+            stub_relocs,
+            vec![
+                (5, RelocationKind::Relative, 0x1000 - 4),
+                (12, RelocationKind::Relative, 0x2000 - 4),
+                (32 + 5, RelocationKind::Relative, 0x1000 - 4),
+                (32 + 12, RelocationKind::Relative, 0x2008 - 4),
+            ]
+        );
+
+        let init_array_section = output_file
+            .section_by_name(INIT_ARRAY_SECTION_NAME)
+            .unwrap();
+        assert_eq!(init_array_section.size(), 2 * 8);
+        let init_array_relocs: Vec<_> = init_array_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.addend()))
+            .collect();
+        assert_eq!(init_array_relocs, vec![(0, 0), (8, 32)]);
+    }
+
+    #[test]
+    fn test_generate_ifunc_init_pass_implicit_addend() {
+        // .data[0] is initialized by the resolver at .text[4], whose address is stored in the slot.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.is_rela = false;
+        crate::elf::test::write_u64(&mut builder.section_mut(2).data, 0, 0x1004);
+        builder.add_relocation(0x2000, R_X86_64_IRELATIVE, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateIfuncInitPass {
+            cls_pass,
+            reloc_index_pass,
+            gc_pass: None,
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stub_section = output_file.section_by_name(STUB_SECTION_NAME).unwrap();
+        let (_, resolver_reloc) = stub_section.relocations().next().unwrap();
+        assert_eq!(resolver_reloc.addend(), 0x1004 - 4);
+    }
+
+    #[test]
+    fn test_generate_ifunc_init_pass_aarch64() {
+        // .data[0] is initialized by the resolver at .text[0].
+        let mut builder = crate::elf::test::builder::minimal_dso().into_aarch64();
+        builder.add_relocation(0x2000, R_AARCH64_IRELATIVE, 0, 0x1000);
+        let output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateIfuncInitPass {
+            cls_pass,
+            reloc_index_pass,
+            gc_pass: None,
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stub_section = output_file.section_by_name(STUB_SECTION_NAME).unwrap();
+        assert_eq!(stub_section.size(), 24);
+        // CALL26 relocations are read back as PltRelative.
+        let stub_relocs: Vec<_> = stub_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.kind(), reloc.addend()))
+            .collect();
+        assert_eq!(
+            stub_relocs,
+            vec![
+                (4, RelocationKind::PltRelative, 0x1000),
+                (8, RelocationKind::Elf(R_AARCH64_ADR_PREL_PG_HI21), 0x2000),
+                (
+                    12,
+                    RelocationKind::Elf(R_AARCH64_LDST64_ABS_LO12_NC),
+                    0x2000
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_ifunc_init_pass_loongarch64() {
+        // .data[0] is initialized by the resolver at .text[0].
+        let mut builder = crate::elf::test::builder::minimal_dso().into_loongarch64();
+        builder.add_relocation(0x2000, R_LARCH_IRELATIVE, 0, 0x1000);
+        let output = OutputObject::new(
+            BinaryFormat::Elf,
+            Architecture::LoongArch64,
+            Endianness::Little,
+        );
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateIfuncInitPass {
+            cls_pass,
+            reloc_index_pass,
+            gc_pass: None,
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stub_section = output_file.section_by_name(STUB_SECTION_NAME).unwrap();
+        assert_eq!(stub_section.size(), 32);
+        // B26 relocations are read back as relative relocations.
+        let stub_relocs: Vec<_> = stub_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.kind(), reloc.addend()))
+            .collect();
+        assert_eq!(
+            stub_relocs,
+            vec![
+                (8, RelocationKind::Relative, 0x1000),
+                (12, RelocationKind::Elf(R_LARCH_PCALA_HI20), 0x2000),
+                (16, RelocationKind::Elf(R_LARCH_PCALA_LO12), 0x2000),
+            ]
+        );
+    }
+}
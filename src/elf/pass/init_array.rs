@@ -1,24 +1,47 @@
-use object::elf::{R_X86_64_RELATIVE, SHT_FINI_ARRAY, SHT_INIT_ARRAY};
-use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
-use object::write::{Relocation as OutputRelocation, SymbolId};
+use object::elf::{
+    DT_FINI, DT_INIT, SHF_ALLOC, SHF_WRITE, SHT_FINI_ARRAY, SHT_INIT_ARRAY, SHT_PREINIT_ARRAY,
+    SHT_PROGBITS,
+};
+use object::read::elf::{Dyn as _, ElfFile, ElfSection, FileHeader as ElfFileHeader};
+use object::read::Error as ReadError;
 use object::{
-    Architecture, Object as _, ObjectSection as _, ReadRef, Relocation, RelocationKind, SectionKind,
+    AddressSize, Architecture, Endian, Object as _, ObjectSection as _, ReadRef, Relocation,
+    RelocationEncoding, RelocationKind, SectionFlags, SectionKind,
 };
 use thiserror::Error;
 
-use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::elf::pass::reloc::{
+    get_elf_reloc_kind, output_reloc_size, DynamicRelocationTypes, RelocationSlots,
+    RelocationSource,
+};
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::{CopyLodableSectionsOutput, CopyLodableSectionsPass};
+use crate::output::Relocation as OutputRelocation;
 use crate::pass::{Pass, PassContext, PassHandle};
-
-/// Generate a .init_array section in the output relocatable file.
+use crate::utils::stringify::{arch_to_str, reloc_kind_to_str};
+
+/// Generate .init_array sections in the output relocatable file, together with a .preinit_array section if the input
+/// has any.
+///
+/// Input sections named with a priority, like `.init_array.00065`, go to output sections of the same names, so that the
+/// final link sorts them. The function given by the DT_INIT entry of the dynamic table, if any, is called by the first
+/// entry of the array that comes first in the final link, since the dynamic linker calls it before the functions in
+/// the init arrays.
+///
+/// With [`CtorsStyle::Legacy`], the entries go to `.ctors` sections instead of `.init_array`, see [`CtorsStyle`].
 #[derive(Debug)]
 pub struct GenerateInitArrayPass {
     inner: GenerateFuncPtrArray,
 }
 
 impl GenerateInitArrayPass {
-    pub fn new(cls_pass: PassHandle<CopyLodableSectionsPass>) -> Self {
+    pub fn new(
+        cls_pass: PassHandle<CopyLodableSectionsPass>,
+        reloc_index_pass: PassHandle<IndexRelocationsPass>,
+        ctors_style: CtorsStyle,
+    ) -> Self {
         Self {
-            inner: GenerateFuncPtrArray::new(cls_pass),
+            inner: GenerateFuncPtrArray::new(cls_pass, reloc_index_pass, ctors_style),
         }
     }
 }
@@ -30,24 +53,38 @@ where
 {
     const NAME: &'static str = "generate init array";
 
-    type Output = ();
+    type Output = FuncPtrArrayOutput;
     type Error = GenerateInitFiniArrayError;
 
     fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
-        self.inner.generate(ctx, SHT_INIT_ARRAY)
+        let mut output = self.inner.generate(ctx, SHT_PREINIT_ARRAY)?;
+        let init_output = self.inner.generate(ctx, SHT_INIT_ARRAY)?;
+        output.converted_relocs.extend(init_output.converted_relocs);
+        Ok(output)
     }
 }
 
-/// Generate a .fini_array section in the output relocatable file.
+/// Generate .fini_array sections in the output relocatable file.
+///
+/// Input sections named with a priority are kept apart like in [`GenerateInitArrayPass`]. The function given by the
+/// DT_FINI entry of the dynamic table, if any, is called by the first entry of the array that comes first in the final
+/// link. The entries are called in the reverse order, so it's called after the functions in the fini arrays, just like
+/// the dynamic linker does.
+///
+/// With [`CtorsStyle::Legacy`], the entries go to `.dtors` sections instead of `.fini_array`, see [`CtorsStyle`].
 #[derive(Debug)]
 pub struct GenerateFiniArrayPass {
     inner: GenerateFuncPtrArray,
 }
 
 impl GenerateFiniArrayPass {
-    pub fn new(cls_pass: PassHandle<CopyLodableSectionsPass>) -> Self {
+    pub fn new(
+        cls_pass: PassHandle<CopyLodableSectionsPass>,
+        reloc_index_pass: PassHandle<IndexRelocationsPass>,
+        ctors_style: CtorsStyle,
+    ) -> Self {
         Self {
-            inner: GenerateFuncPtrArray::new(cls_pass),
+            inner: GenerateFuncPtrArray::new(cls_pass, reloc_index_pass, ctors_style),
         }
     }
 }
@@ -59,7 +96,7 @@ where
 {
     const NAME: &'static str = "generate fini array";
 
-    type Output = ();
+    type Output = FuncPtrArrayOutput;
     type Error = GenerateInitFiniArrayError;
 
     fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
@@ -67,142 +104,842 @@ where
     }
 }
 
+/// The kind of sections that hold the pointers to the initialization and termination functions in the output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CtorsStyle {
+    /// Generate `.init_array` and `.fini_array` sections.
+    #[default]
+    InitArray,
+
+    /// Generate `.ctors` and `.dtors` sections for old toolchains whose linker scripts ignore `.init_array` and
+    /// `.fini_array`.
+    ///
+    /// The entries of these sections are called from the last one to the first one, so they are reversed relative to
+    /// the order of `.init_array` to keep the order of the calls. The -1 and 0 sentinels that terminate the sections are
+    /// supplied by the linker script, and the -1 entries of the input are dropped since they would end the calls early.
+    /// Prioritized sections like `.init_array.00065` go to `.ctors.65470`, like GCC names them. `.preinit_array` has
+    /// no legacy counterpart and is generated as it is.
+    Legacy,
+}
+
+/// The output of [`GenerateInitArrayPass`] and [`GenerateFiniArrayPass`].
+#[derive(Debug, Default)]
+pub struct FuncPtrArrayOutput {
+    /// Addresses of the input relocations that are converted into relocations of the generated arrays.
+    pub converted_relocs: Vec<u64>,
+}
+
 #[derive(Debug, Error)]
 pub enum GenerateInitFiniArrayError {
-    #[error("unsupported architecture: {0:?}")]
+    #[error("read ELF failed: {0:?}")]
+    ReadElfError(#[from] ReadError),
+
+    #[error("unsupported architecture: {}", arch_to_str(*.0))]
     UnsupportedArch(Architecture),
 
-    #[error("unsupported reloc: {0:?}")]
-    UnsupportedReloc(RelocationKind),
+    #[error("unsupported reloc: {}", reloc_kind_to_str(*.0, *.1))]
+    UnsupportedReloc(Architecture, RelocationKind),
+
+    #[error("init/fini array entry at {0:#x} is truncated")]
+    TruncatedEntry(u64),
 }
 
 #[derive(Debug)]
 struct GenerateFuncPtrArray {
     cls_pass: PassHandle<CopyLodableSectionsPass>,
+    reloc_index_pass: PassHandle<IndexRelocationsPass>,
+    ctors_style: CtorsStyle,
 }
 
 impl GenerateFuncPtrArray {
-    fn new(cls_pass: PassHandle<CopyLodableSectionsPass>) -> Self {
-        Self { cls_pass }
+    fn new(
+        cls_pass: PassHandle<CopyLodableSectionsPass>,
+        reloc_index_pass: PassHandle<IndexRelocationsPass>,
+        ctors_style: CtorsStyle,
+    ) -> Self {
+        Self {
+            cls_pass,
+            reloc_index_pass,
+            ctors_style,
+        }
     }
 
     fn generate<'d, E, R>(
         &self,
         ctx: &PassContext<ElfFile<'d, E, R>>,
         sec_type: u32,
-    ) -> Result<(), GenerateInitFiniArrayError>
+    ) -> Result<FuncPtrArrayOutput, GenerateInitFiniArrayError>
     where
         E: ElfFileHeader,
         R: ReadRef<'d>,
     {
-        assert!(sec_type == SHT_INIT_ARRAY || sec_type == SHT_FINI_ARRAY);
-        let output_sec_name = match sec_type {
-            SHT_INIT_ARRAY => ".init_array",
-            SHT_FINI_ARRAY => ".fini_array",
+        let (output_sec_name, legacy_sec_name, func_tag) = match sec_type {
+            SHT_PREINIT_ARRAY => (".preinit_array", None, None),
+            SHT_INIT_ARRAY => (".init_array", Some(".ctors"), Some((DT_INIT, "DT_INIT"))),
+            SHT_FINI_ARRAY => (".fini_array", Some(".dtors"), Some((DT_FINI, "DT_FINI"))),
             _ => unreachable!(),
         };
+        let legacy_sec_name = legacy_sec_name.filter(|_| self.ctors_style == CtorsStyle::Legacy);
 
         let cls_output = ctx.get_pass_output(self.cls_pass);
-
-        let input_sections = ctx
-            .input
-            .sections()
-            .filter(|sec| sec.kind() == SectionKind::Elf(sec_type));
-
-        let arch = ctx.input.architecture();
-        let mut output_sec_size = 0;
-        let mut output_relocs = Vec::new();
-
-        for input_sec in input_sections {
-            if !cls_output.is_section_copied(input_sec.index()) {
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+
+        // The input sections are grouped by the names of their output sections, in the order of the input. Sections
+        // named with a priority, like .init_array.00065, keep their names so that the final link sorts them.
+        let mut arrays: Vec<(String, Vec<ElfSection<'d, '_, E, R>>)> = Vec::new();
+        for input_sec in ctx.input.sections() {
+            if input_sec.kind() != SectionKind::Elf(sec_type)
+                || !cls_output.is_section_copied(input_sec.index())
+                || input_sec.size() == 0
+            {
                 continue;
             }
 
-            let input_sec_size = input_sec.size();
-            if input_sec_size == 0 {
-                continue;
+            let name = get_output_section_name(output_sec_name, input_sec.name()?);
+            match arrays
+                .iter_mut()
+                .find(|(array_name, _)| *array_name == name)
+            {
+                Some((_, input_sections)) => input_sections.push(input_sec),
+                None => arrays.push((name, vec![input_sec])),
             }
+        }
 
-            output_sec_size += input_sec_size;
+        // The legacy initialization or termination function goes to the front of the array that comes first in the
+        // final link.
+        let mut func_addr = None;
+        if let Some((tag, tag_name)) = func_tag {
+            if let Some(addr) = get_dynamic_tag_value(&ctx.input, tag)? {
+                if cls_output.translate(addr).is_some() {
+                    func_addr = Some((tag_name, addr));
+                } else {
+                    ctx.diagnostics.warn(format_args!(
+                        "The {} function at {:#x} lies outside of loadable sections and is never called",
+                        tag_name, addr
+                    ));
+                }
+            }
+        }
+        let func_array = func_addr.map(|_| {
+            let first = arrays
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (name, _))| {
+                    get_init_priority(output_sec_name, name).map_or(u64::MAX, u64::from)
+                })
+                .map(|(pos, _)| pos);
+            first.unwrap_or_else(|| {
+                arrays.push((String::from(output_sec_name), Vec::new()));
+                arrays.len() - 1
+            })
+        });
+
+        if sec_type == SHT_PREINIT_ARRAY && !arrays.is_empty() {
+            ctx.diagnostics.warn(format_args!(
+                "The input has a .preinit_array section, whose functions the dynamic linker never calls in shared \
+                 libraries; they are only called if the output is linked into an executable, before any other \
+                 initialization function including those of the libraries it depends on"
+            ));
+        }
 
-            let input_sec_addr = input_sec.address();
-            let input_sec_addr_range = input_sec_addr..input_sec_addr + input_sec_size;
+        let arch = ctx.input.architecture();
+        let ptr_size: u8 = if ctx.input.is_64() { 64 } else { 32 };
+        let entry_size = u64::from(ptr_size / 8);
+        let mut converted_relocs = Vec::new();
+
+        for (pos, (name, input_sections)) in arrays.into_iter().enumerate() {
+            let mut output_sec_data = Vec::new();
+            let mut output_relocs = Vec::new();
+
+            if let Some((tag_name, addr)) = func_addr.filter(|_| func_array == Some(pos)) {
+                let (symbol, addend) = cls_output.get_address_reference(addr);
+                output_sec_data.resize(usize::from(ptr_size / 8), 0);
+                output_relocs.push((
+                    RelocationSource::DynamicTag(tag_name, addr),
+                    OutputRelocation {
+                        offset: 0,
+                        size: ptr_size,
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        symbol,
+                        addend,
+                    },
+                ));
+            }
 
-            // Find all input relocations associated with the input section and convert them to corresponding output
-            // relocations associated with the output section.
+            for input_sec in input_sections {
+                // The input sections are concatenated in the output section, one entry at a time.
+                let input_sec_addr = input_sec.address();
+                let input_sec_size = input_sec.size();
+                let input_sec_data = input_sec.data()?;
+                if (input_sec_data.len() as u64) < input_sec_size {
+                    return Err(GenerateInitFiniArrayError::TruncatedEntry(
+                        input_sec_addr + input_sec_data.len() as u64,
+                    ));
+                }
 
-            for (input_reloc_addr, input_reloc) in ctx.input.dynamic_relocations().unwrap() {
-                if !input_sec_addr_range.contains(&input_reloc_addr) {
-                    continue;
+                for entry_offset in (0..input_sec_size).step_by(entry_size as usize) {
+                    let entry_addr = input_sec_addr + entry_offset;
+                    let entry_end = (entry_offset + entry_size).min(input_sec_size);
+                    let entry_data = &input_sec_data[entry_offset as usize..entry_end as usize];
+                    let output_entry_offset = output_sec_data.len() as u64;
+
+                    // Find all input relocations associated with the entry and convert them to corresponding output
+                    // relocations associated with the output section.
+                    let mut relocated = false;
+                    for (input_reloc_addr, input_reloc) in
+                        reloc_index.in_range(entry_addr..input_sec_addr + entry_end)
+                    {
+                        // The addends of REL relocations are read from the input section, since the copy in the
+                        // output might have been rewritten by the conversion of the same relocation.
+                        let addend = if input_reloc.has_implicit_addend() {
+                            read_entry(ctx, &input_sec, input_reloc_addr)? as i64
+                        } else {
+                            input_reloc.addend()
+                        };
+
+                        let output_reloc = convert_init_fini_array_reloc(
+                            arch,
+                            output_entry_offset + (input_reloc_addr - entry_addr),
+                            input_reloc,
+                            addend,
+                            cls_output,
+                        )?;
+                        output_relocs.push((
+                            RelocationSource::dynamic(
+                                ctx.input.architecture(),
+                                input_reloc_addr,
+                                input_reloc,
+                            ),
+                            output_reloc,
+                        ));
+                        converted_relocs.push(input_reloc_addr);
+                        relocated = true;
+                    }
+
+                    if entry_end - entry_offset < entry_size && legacy_sec_name.is_some() {
+                        ctx.diagnostics.warn(format_args!(
+                            "Dropping the truncated {} entry at {:#x}, which cannot be reversed",
+                            output_sec_name, entry_addr
+                        ));
+                        continue;
+                    }
+                    if relocated || entry_end - entry_offset < entry_size {
+                        output_sec_data.extend_from_slice(entry_data);
+                        continue;
+                    }
+
+                    // Entries without any dynamic relocation hold final addresses, like in prelinked libraries. The
+                    // addresses in the input image are turned into relocations, and null entries are dropped since
+                    // there is nothing to call. Other entries, such as the -1 sentinels of some toolchains, are copied
+                    // as they are, unless they would end the calls of .ctors or .dtors early.
+                    let value = read_entry(ctx, &input_sec, entry_addr)?;
+                    let is_sentinel = value == u64::MAX >> (64 - ptr_size);
+                    if value == 0 || (is_sentinel && legacy_sec_name.is_some()) {
+                        ctx.diagnostics.debug(format_args!(
+                            "Dropping the {} entry at {:#x} holding {:#x}",
+                            output_sec_name, entry_addr, value
+                        ));
+                        continue;
+                    }
+                    if cls_output.translate(value).is_some() {
+                        let (symbol, addend) = cls_output.get_address_reference(value);
+                        output_relocs.push((
+                            RelocationSource::UnrelocatedEntry(entry_addr),
+                            OutputRelocation {
+                                offset: output_entry_offset,
+                                size: ptr_size,
+                                kind: RelocationKind::Absolute,
+                                encoding: RelocationEncoding::Generic,
+                                symbol,
+                                addend,
+                            },
+                        ));
+                    } else if !is_sentinel {
+                        ctx.diagnostics.warn(format_args!(
+                            "The {} entry at {:#x} holds the address {:#x}, which has no relocation and lies outside \
+                             of loadable sections; it's copied as it is",
+                            output_sec_name, entry_addr, value
+                        ));
+                    }
+                    output_sec_data.extend_from_slice(entry_data);
                 }
+            }
 
-                let output_reloc = convert_init_fini_array_reloc(
-                    arch,
-                    input_reloc_addr,
-                    &input_reloc,
-                    cls_output.output_section_symbol,
-                )?;
-                output_relocs.push(output_reloc);
+            // The relocated entries are zeroed, since the addends of the output relocations are either explicit or
+            // written into the entries when the relocations are added.
+            for (_, output_reloc) in &output_relocs {
+                let start = output_reloc.offset as usize;
+                let end = start + usize::from(output_reloc.size) / 8;
+                if let Some(entry) = output_sec_data.get_mut(start..end) {
+                    entry.fill(0);
+                }
             }
-        }
 
-        if output_sec_size == 0 {
-            return Ok(());
+            let (name, output_sec_type) = match legacy_sec_name {
+                Some(legacy_sec_name) => {
+                    reverse_entries(&mut output_sec_data, &mut output_relocs, entry_size);
+                    let name = get_legacy_section_name(output_sec_name, legacy_sec_name, &name);
+                    (name, SHT_PROGBITS)
+                }
+                None => (name, sec_type),
+            };
+
+            let mut output = ctx.output.borrow_mut();
+            let output_sec_id = output.add_section(
+                Vec::new(),
+                ctx.section_naming.get(&name).as_bytes().to_vec(),
+                SectionKind::Elf(output_sec_type),
+            );
+            // Linkers only turn the entries into dynamic relocations in position-independent outputs if the array is
+            // allocated.
+            output.section_mut(output_sec_id).flags = SectionFlags::Elf {
+                sh_flags: (SHF_ALLOC | SHF_WRITE) as u64,
+            };
+
+            // The array is aligned to the size of a pointer.
+            output.set_section_data(output_sec_id, output_sec_data, u64::from(ptr_size / 8));
+
+            let mut slots = RelocationSlots::new(ctx.diagnostics.clone());
+            for (source, r) in output_relocs {
+                slots.add(&mut output, output_sec_id, r, source);
+            }
         }
 
-        let mut output = ctx.output.borrow_mut();
-        let output_sec_id = output.add_section(
-            Vec::new(),
-            output_sec_name.as_bytes().to_vec(),
-            SectionKind::Elf(sec_type),
-        );
+        Ok(FuncPtrArrayOutput { converted_relocs })
+    }
+}
 
-        const INIT_FINI_ARRAY_ALIGN: u64 = 8;
-        output.set_section_data(
-            output_sec_id,
-            vec![0u8; output_sec_size as usize],
-            INIT_FINI_ARRAY_ALIGN,
-        );
+/// Get the name of the output section of the given input function pointer array section, given the name of the plain
+/// output section like `.init_array`. Sections named with a numeric priority like `.init_array.00065` keep their names,
+/// and other sections go to the plain output section.
+fn get_output_section_name(plain_name: &str, input_name: &str) -> String {
+    match get_init_priority(plain_name, input_name) {
+        Some(_) => String::from(input_name),
+        None => String::from(plain_name),
+    }
+}
 
-        for r in output_relocs {
-            output.add_relocation(output_sec_id, r).unwrap();
-        }
+/// Get the name of the legacy output section like `.ctors` of the given output function pointer array section, given
+/// the names of the plain output sections like `.init_array` and `.ctors`. The priorities are inverted, since the
+/// legacy sections are called in the reverse order.
+fn get_legacy_section_name(plain_name: &str, legacy_plain_name: &str, name: &str) -> String {
+    match get_init_priority(plain_name, name) {
+        Some(priority) => format!(
+            "{}.{:05}",
+            legacy_plain_name,
+            u32::from(u16::MAX).saturating_sub(priority)
+        ),
+        None => String::from(legacy_plain_name),
+    }
+}
+
+/// Reverse the order of the pointer-sized entries of the given array, together with the offsets of their relocations.
+/// The array holds whole entries only.
+fn reverse_entries(
+    data: &mut Vec<u8>,
+    relocs: &mut [(RelocationSource, OutputRelocation)],
+    entry_size: u64,
+) {
+    let len = data.len() as u64;
+    *data = data
+        .chunks(entry_size as usize)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+    for (_, reloc) in relocs.iter_mut() {
+        let entry_offset = reloc.offset - reloc.offset % entry_size;
+        reloc.offset = len - entry_size - entry_offset + reloc.offset % entry_size;
+    }
+    relocs.reverse();
+}
 
-        Ok(())
+/// Get the priority of the given function pointer array section, given the name of the plain section like
+/// `.init_array`. Plain sections have no priority, and come after all the sections with priorities in the final link.
+fn get_init_priority(plain_name: &str, name: &str) -> Option<u32> {
+    let suffix = name.strip_prefix(plain_name)?.strip_prefix('.')?;
+    if suffix.is_empty() || !suffix.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
+    suffix.parse().ok()
+}
+
+/// Get the value of the entry with the given tag in the dynamic table of the given input, if any.
+fn get_dynamic_tag_value<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    tag: u32,
+) -> Result<Option<u64>, ReadError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let Some((dynamic, _)) = input
+        .raw_header()
+        .sections(endian, input.data())?
+        .dynamic(endian, input.data())?
+    else {
+        return Ok(None);
+    };
+    let value = dynamic
+        .iter()
+        .find(|entry| entry.tag32(endian) == Some(tag))
+        .map(|entry| entry.d_val(endian).into());
+    Ok(value)
+}
+
+/// Read the pointer-sized entry at the given address from the given input section.
+fn read_entry<'d, E, R>(
+    ctx: &PassContext<ElfFile<'d, E, R>>,
+    input_sec: &ElfSection<'d, '_, E, R>,
+    entry_addr: u64,
+) -> Result<u64, GenerateInitFiniArrayError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let offset = (entry_addr - input_sec.address()) as usize;
+    let size = if ctx.input.is_64() { 8 } else { 4 };
+    let slot = input_sec
+        .data()?
+        .get(offset..offset + size)
+        .ok_or(GenerateInitFiniArrayError::TruncatedEntry(entry_addr))?;
+
+    let endian = ctx.input.endian();
+    let value = if size == 4 {
+        u64::from(endian.read_u32_bytes(slot.try_into().unwrap()))
+    } else {
+        endian.read_u64_bytes(slot.try_into().unwrap())
+    };
+    Ok(value)
 }
 
+fn unsupported_reloc(arch: Architecture, input_reloc: &Relocation) -> GenerateInitFiniArrayError {
+    GenerateInitFiniArrayError::UnsupportedReloc(arch, get_elf_reloc_kind(arch, input_reloc))
+}
+
+/// Convert the given input relocation of an entry, which must be a RELATIVE relocation, into an output relocation at the
+/// given offset of the output section. The addend is truncated to the address size of the architecture.
 fn convert_init_fini_array_reloc(
     arch: Architecture,
-    input_reloc_addr: u64,
+    offset: u64,
     input_reloc: &Relocation,
-    output_main_sec_sym: SymbolId,
+    addend: i64,
+    cls_output: &CopyLodableSectionsOutput,
 ) -> Result<OutputRelocation, GenerateInitFiniArrayError> {
-    match arch {
-        Architecture::X86_64 => {
-            convert_init_fini_array_reloc_x86_64(input_reloc_addr, input_reloc, output_main_sec_sym)
-        }
-        arch => Err(GenerateInitFiniArrayError::UnsupportedArch(arch)),
+    let types = DynamicRelocationTypes::of(arch)
+        .ok_or(GenerateInitFiniArrayError::UnsupportedArch(arch))?;
+    // Function pointers are relocated by RELATIVE relocations, which are R_MIPS_REL32 relocations composed with
+    // R_MIPS_64 and without a symbol on MIPS.
+    if input_reloc.kind() != RelocationKind::Elf(types.relative) {
+        return Err(unsupported_reloc(arch, input_reloc));
     }
+
+    let mut addr = addend as u64;
+    if arch.address_size() != Some(AddressSize::U64) {
+        addr = addr as u32 as u64;
+    }
+    let (symbol, addend) = cls_output.get_address_reference(addr);
+    Ok(OutputRelocation {
+        offset,
+        size: output_reloc_size(arch, input_reloc.kind()),
+        kind: RelocationKind::Absolute,
+        encoding: input_reloc.encoding(),
+        symbol,
+        addend,
+    })
 }
 
-fn convert_init_fini_array_reloc_x86_64(
-    input_reloc_addr: u64,
-    input_reloc: &Relocation,
-    output_main_sec_sym: SymbolId,
-) -> Result<OutputRelocation, GenerateInitFiniArrayError> {
-    let output_reloc = match input_reloc.kind() {
-        RelocationKind::Elf(R_X86_64_RELATIVE) => OutputRelocation {
-            offset: input_reloc_addr,
-            size: 64,
-            kind: RelocationKind::Absolute,
-            encoding: input_reloc.encoding(),
-            symbol: output_main_sec_sym,
-            addend: input_reloc.addend(),
-        },
-        kind => {
-            return Err(GenerateInitFiniArrayError::UnsupportedReloc(kind));
-        }
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        DT_FINI, DT_INIT, DT_NULL, PF_R, PF_W, PT_LOAD, R_X86_64_RELATIVE, SHF_ALLOC, SHF_WRITE,
+        SHT_DYNAMIC, SHT_FINI_ARRAY, SHT_INIT_ARRAY, SHT_PREINIT_ARRAY,
+    };
+    use std::fmt::Arguments;
+    use std::sync::{Arc, Mutex};
+
+    use object::read::elf::ElfFile64;
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, RelocationTarget,
+        SectionKind,
     };
-    Ok(output_reloc)
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Diagnostics, Pass, PassHandle, PassManager};
+    use crate::{DiagnosticSink, Severity};
+
+    use super::{CtorsStyle, GenerateFiniArrayPass, GenerateInitArrayPass};
+
+    /// Runs [`GenerateInitArrayPass`] and checks the addresses of the relocations it converts.
+    #[derive(Default)]
+    struct GenerateInitArrayPassTest {
+        converted_relocs: Vec<u64>,
+        ctors_style: CtorsStyle,
+    }
+
+    impl PassTest for GenerateInitArrayPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = GenerateInitArrayPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(GenerateInitArrayPass::new(
+                cls_pass,
+                reloc_index_pass,
+                self.ctors_style,
+            ))
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            assert_eq!(output.converted_relocs, self.converted_relocs);
+        }
+    }
+
+    #[test]
+    fn test_generate_init_array_pass() {
+        // .init_array holds a pointer to func and a -1 sentinel without any relocation.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let mut data = vec![0xaa; 8];
+        data.extend_from_slice(&[0xff; 8]);
+        builder.add_section(
+            ".init_array",
+            SHT_INIT_ARRAY,
+            SHF_ALLOC | SHF_WRITE,
+            0x3000,
+            data,
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x10);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        let output =
+            || OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = GenerateInitArrayPassTest {
+            converted_relocs: vec![0x3000],
+            ..Default::default()
+        };
+        crate::pass::test::run_pass_test(test, builder.build_elf64(), output());
+
+        let mut pass_mgr = PassManager::new();
+        GenerateInitArrayPassTest::default().setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output()).unwrap();
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let init_array = output_file.section_by_name(".init_array").unwrap();
+
+        // The relocated entry is zeroed, and the sentinel is copied.
+        let mut expected = vec![0; 8];
+        expected.extend_from_slice(&[0xff; 8]);
+        assert_eq!(init_array.data().unwrap(), expected);
+
+        let relocs: Vec<_> = init_array.relocations().collect();
+        assert_eq!(relocs.len(), 1);
+        let (offset, reloc) = &relocs[0];
+        assert_eq!(*offset, 0);
+        assert!(matches!(reloc.target(), RelocationTarget::Symbol(_)));
+        assert_eq!(reloc.addend(), 0x1000);
+    }
+
+    /// Collects the warnings reported to it.
+    #[derive(Debug, Default)]
+    struct CollectingSink {
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl DiagnosticSink for CollectingSink {
+        fn report(&self, severity: Severity, message: Arguments<'_>) {
+            if severity == Severity::Warning {
+                self.warnings.lock().unwrap().push(message.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_init_array_pass_unrelocated_entries() {
+        // .init_array holds a pointer to .text[8], a null pointer and a pointer outside of the image, none of which has
+        // a relocation, followed by a relocated pointer to func.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let data = [0x1008u64, 0, 0x9000, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        builder.add_section(
+            ".init_array",
+            SHT_INIT_ARRAY,
+            SHF_ALLOC | SHF_WRITE,
+            0x3000,
+            data,
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x20);
+        builder.add_relocation(0x3018, R_X86_64_RELATIVE, 0, 0x1000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        GenerateInitArrayPassTest::default().setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+        assert_eq!(
+            *sink.warnings.lock().unwrap(),
+            [String::from(
+                "The .init_array entry at 0x3010 holds the address 0x9000, which has no relocation and lies outside \
+                 of loadable sections; it's copied as it is"
+            )]
+        );
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let init_array = output_file.section_by_name(".init_array").unwrap();
+
+        // The null entry is dropped, and the entry outside of the image is copied.
+        let expected: Vec<_> = [0u64, 0x9000, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        assert_eq!(init_array.data().unwrap(), expected);
+
+        // The entry inside of the image is relocated.
+        let relocs: Vec<_> = init_array
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.addend()))
+            .collect();
+        assert_eq!(relocs, vec![(0, 0x1008), (0x10, 0x1000)]);
+    }
+
+    #[test]
+    fn test_generate_init_array_pass_dt_init() {
+        // DT_INIT refers to .text[4], and .init_array holds a pointer to func.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_section(
+            ".init_array",
+            SHT_INIT_ARRAY,
+            SHF_ALLOC | SHF_WRITE,
+            0x3000,
+            vec![0; 8],
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 8);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        let dynamic_data = [(DT_INIT, 0x1004), (DT_NULL, 0)]
+            .iter()
+            .flat_map(|&(tag, value)| [tag as u64, value])
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        GenerateInitArrayPassTest::default().setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let init_array = output_file.section_by_name(".init_array").unwrap();
+        assert_eq!(init_array.data().unwrap(), &[0; 16]);
+
+        // The DT_INIT function is called before the functions of the input array.
+        let relocs: Vec<_> = init_array
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.addend()))
+            .collect();
+        assert_eq!(relocs, vec![(0, 0x1004), (8, 0x1000)]);
+    }
+
+    #[test]
+    fn test_generate_init_array_pass_priorities() {
+        // .init_array.00200 and .init_array.00100 hold one pointer each, .init_array holds two pointers and
+        // .preinit_array holds one pointer. DT_INIT refers to .text[0xe].
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let arrays = [
+            (".init_array.00200", SHT_INIT_ARRAY, 0x3000, 8),
+            (".init_array.00100", SHT_INIT_ARRAY, 0x3008, 8),
+            (".init_array", SHT_INIT_ARRAY, 0x3010, 0x10),
+            (".preinit_array", SHT_PREINIT_ARRAY, 0x3020, 8),
+        ];
+        for (name, sh_type, addr, size) in arrays {
+            builder.add_section(name, sh_type, SHF_ALLOC | SHF_WRITE, addr, vec![0; size]);
+        }
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x28);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x3008, R_X86_64_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x3010, R_X86_64_RELATIVE, 0, 0x1008);
+        builder.add_relocation(0x3018, R_X86_64_RELATIVE, 0, 0x100c);
+        builder.add_relocation(0x3020, R_X86_64_RELATIVE, 0, 0x1002);
+        let dynamic_data = [(DT_INIT, 0x100e), (DT_NULL, 0)]
+            .iter()
+            .flat_map(|&(tag, value)| [tag as u64, value])
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+        let output =
+            || OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        // The relocations of .preinit_array are converted first.
+        let test = GenerateInitArrayPassTest {
+            converted_relocs: vec![0x3020, 0x3000, 0x3008, 0x3010, 0x3018],
+            ..Default::default()
+        };
+        crate::pass::test::run_pass_test(test, builder.build_elf64(), output());
+
+        let mut pass_mgr = PassManager::new();
+        GenerateInitArrayPassTest::default().setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output()).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let get_relocs = |name| {
+            output_file
+                .section_by_name(name)
+                .unwrap()
+                .relocations()
+                .map(|(offset, reloc)| (offset, reloc.addend()))
+                .collect::<Vec<_>>()
+        };
+
+        // The DT_INIT function is called first, by the array with the lowest priority.
+        assert_eq!(get_relocs(".init_array.00200"), vec![(0, 0x1000)]);
+        assert_eq!(
+            get_relocs(".init_array.00100"),
+            vec![(0, 0x100e), (8, 0x1004)]
+        );
+        assert_eq!(get_relocs(".init_array"), vec![(0, 0x1008), (8, 0x100c)]);
+        assert_eq!(get_relocs(".preinit_array"), vec![(0, 0x1002)]);
+    }
+
+    /// The name, the kind, the data and the offsets and addends of the relocations of an output section.
+    type ArraySnapshot = (String, SectionKind, Vec<u8>, Vec<(u64, i64)>);
+
+    /// Get the snapshot of each init or fini array section in the given output, in the order of the output.
+    fn get_arrays(output: OutputObject) -> Vec<ArraySnapshot> {
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        output_file
+            .sections()
+            .filter(|sec| {
+                let name = sec.name().unwrap();
+                [".init_array", ".fini_array", ".ctors", ".dtors"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+            })
+            .map(|sec| {
+                let relocs = sec
+                    .relocations()
+                    .map(|(offset, reloc)| (offset, reloc.addend()))
+                    .collect();
+                (
+                    String::from(sec.name().unwrap()),
+                    sec.kind(),
+                    sec.data().unwrap().to_vec(),
+                    relocs,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_init_fini_array_pass_ctors_style() {
+        // .init_array.00100 holds a pointer to func, .init_array holds two pointers into .text and a -1 sentinel
+        // between them, and .fini_array holds two pointers into .text. DT_INIT and DT_FINI refer to .text as well.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let arrays = [
+            (".init_array.00100", SHT_INIT_ARRAY, 0x3000, vec![0; 8]),
+            (
+                ".init_array",
+                SHT_INIT_ARRAY,
+                0x3008,
+                [vec![0; 8], vec![0xff; 8], vec![0; 8]].concat(),
+            ),
+            (".fini_array", SHT_FINI_ARRAY, 0x3020, vec![0; 0x10]),
+        ];
+        for (name, sh_type, addr, data) in arrays {
+            builder.add_section(name, sh_type, SHF_ALLOC | SHF_WRITE, addr, data);
+        }
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x30);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x3008, R_X86_64_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x3018, R_X86_64_RELATIVE, 0, 0x1008);
+        builder.add_relocation(0x3020, R_X86_64_RELATIVE, 0, 0x100c);
+        builder.add_relocation(0x3028, R_X86_64_RELATIVE, 0, 0x100e);
+        let dynamic_data = [(DT_INIT, 0x1002), (DT_FINI, 0x1006), (DT_NULL, 0)]
+            .iter()
+            .flat_map(|&(tag, value)| [tag as u64, value])
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+
+        let run = |ctors_style| {
+            let mut pass_mgr = PassManager::new();
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(GenerateInitArrayPass::new(
+                cls_pass,
+                reloc_index_pass,
+                ctors_style,
+            ));
+            pass_mgr.add_pass(GenerateFiniArrayPass::new(
+                cls_pass,
+                reloc_index_pass,
+                ctors_style,
+            ));
+            let output =
+                OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+            get_arrays(pass_mgr.run(builder.build_elf64(), output).unwrap())
+        };
+
+        let init_array = SectionKind::Elf(SHT_INIT_ARRAY);
+        let fini_array = SectionKind::Elf(SHT_FINI_ARRAY);
+        assert_eq!(
+            run(CtorsStyle::InitArray),
+            vec![
+                (
+                    String::from(".init_array.00100"),
+                    init_array,
+                    vec![0; 0x10],
+                    vec![(0, 0x1002), (8, 0x1000)]
+                ),
+                (
+                    String::from(".init_array"),
+                    init_array,
+                    [vec![0; 8], vec![0xff; 8], vec![0; 8]].concat(),
+                    vec![(0, 0x1004), (0x10, 0x1008)]
+                ),
+                (
+                    String::from(".fini_array"),
+                    fini_array,
+                    vec![0; 0x18],
+                    vec![(0, 0x1006), (8, 0x100c), (0x10, 0x100e)]
+                ),
+            ]
+        );
+
+        // The entries are reversed and the sentinel is dropped.
+        assert_eq!(
+            run(CtorsStyle::Legacy),
+            vec![
+                (
+                    String::from(".ctors.65435"),
+                    SectionKind::Data,
+                    vec![0; 0x10],
+                    vec![(0, 0x1000), (8, 0x1002)]
+                ),
+                (
+                    String::from(".ctors"),
+                    SectionKind::Data,
+                    vec![0; 0x10],
+                    vec![(0, 0x1008), (8, 0x1004)]
+                ),
+                (
+                    String::from(".dtors"),
+                    SectionKind::Data,
+                    vec![0; 0x18],
+                    vec![(0, 0x100e), (8, 0x100c), (0x10, 0x1006)]
+                ),
+            ]
+        );
+    }
 }
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use object::elf::{
+    FileHeader32, FileHeader64, SHT_NULL, SHT_REL, SHT_RELA, SHT_STRTAB, SHT_SYMTAB,
+    SHT_SYMTAB_SHNDX,
+};
+use object::read::elf::{
+    ElfFile, FileHeader as ElfFileHeader, Rel as _, Rela as _, SectionHeader as _,
+};
+use object::read::{Error as ReadError, SectionIndex, SymbolIndex};
+use object::{
+    BinaryFormat, Endianness, FileKind, Object as _, ObjectSection as _, ObjectSymbol as _,
+    ObjectSymbolTable as _, ReadRef, RelocationEncoding, RelocationKind, SymbolFlags, SymbolKind,
+    SymbolSection,
+};
+use thiserror::Error;
+
+use crate::elf::pass::boundary::GenerateBoundarySymbolsPass;
+use crate::elf::pass::defsym::DefineSymbolsPass;
+use crate::elf::pass::require::RequireSymbolsPass;
+use crate::elf::pass::symbol::GenerateSymbolPass;
+use crate::output::{
+    Error as WriteError, Object as OutputObject, Relocation as OutputRelocation,
+    Symbol as OutputSymbol, SymbolSection as OutputSymbolSection,
+};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that lays out the symbols and the relocations of the output in an order that only depends on the input, so
+/// that the outputs of different versions of the input or of soda can be compared entry by entry.
+///
+/// The object writer emits symbols and relocations in the order that the passes add them, which changes whenever a
+/// pass is added or moved. This pass runs last and rebuilds the output from its serialized file, in which:
+///
+/// - Section symbols come first, in the order of their sections.
+/// - Symbols that the pass does not know of, such as the symbols of the inputs merged before, keep their order.
+/// - Symbols taken from the dynamic symbol table of the input follow in the order of their indexes in it.
+/// - Symbols that other passes add, such as the boundary symbols, come last in the order of their names.
+/// - Relocations of each section are sorted by their offsets, which follow the addresses of the input relocations.
+///   Relocations at the same offset keep their order.
+///
+/// The writer still puts all the local symbols before the global ones, and the order above holds within each class.
+/// Sections keep the order in which they are created. Pruned symbols are left out rather than replaced, so they still
+/// shift the indexes of the following symbols; the list of symbols given in
+/// [`ConvertOptions::symbol_list`](crate::ConvertOptions) is sorted by name and compares better in that case.
+#[derive(Debug)]
+pub struct StabilizeLayoutPass {
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+    pub defsym_pass: Option<PassHandle<DefineSymbolsPass>>,
+    pub require_pass: Option<PassHandle<RequireSymbolsPass>>,
+    pub boundary_pass: PassHandle<GenerateBoundarySymbolsPass>,
+}
+
+/// Place of a symbol in the stable layout, in increasing order.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum SymbolRank {
+    Section(usize),
+    Unknown(usize),
+    Input(usize),
+    Added(Vec<u8>),
+}
+
+/// The name, the value and whether the symbol is local, which tell the symbols of an output apart in practice. Symbols
+/// of the serialized output are matched with the symbols known to the passes by these.
+type SymbolKey = (Vec<u8>, u64, bool);
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for StabilizeLayoutPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "stabilize layout";
+
+    type Output = ();
+    type Error = StabilizeLayoutError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let sym_map = ctx.get_pass_output(self.sym_gen_pass);
+        let mut output = ctx.output.borrow_mut();
+        let input_symtab = ctx.input.dynamic_symbol_table();
+
+        let mut ranks = HashMap::new();
+        for (input_sym_idx, output_sym) in sym_map.iter() {
+            let sym = output.symbol(output_sym);
+            let input_defined = input_symtab
+                .as_ref()
+                .and_then(|symtab| symtab.symbol_by_index(input_sym_idx).ok())
+                .is_some_and(|sym| !sym.is_undefined());
+            // References that are bound to a definition outside of the input keep the place of the definition.
+            if !input_defined && !sym.is_undefined() {
+                continue;
+            }
+            let key = (sym.name.clone(), sym.value, sym.is_local());
+            let rank = ranks
+                .entry(key)
+                .or_insert(SymbolRank::Input(input_sym_idx.0));
+            *rank = rank.clone().min(SymbolRank::Input(input_sym_idx.0));
+        }
+
+        let added_syms = [
+            self.defsym_pass.map(|pass| ctx.get_pass_output(pass)),
+            self.require_pass.map(|pass| ctx.get_pass_output(pass)),
+            Some(ctx.get_pass_output(self.boundary_pass)),
+        ];
+        for output_sym in added_syms.into_iter().flatten().flatten() {
+            let sym = output.symbol(*output_sym);
+            ranks
+                .entry((sym.name.clone(), sym.value, sym.is_local()))
+                .or_insert_with(|| SymbolRank::Added(sym.name.clone()));
+        }
+
+        let data = output.write()?;
+        let (stable_output, num_relocs) = match FileKind::parse(&*data)? {
+            FileKind::Elf32 => rebuild_output::<FileHeader32<Endianness>>(&output, &data, &ranks)?,
+            FileKind::Elf64 => rebuild_output::<FileHeader64<Endianness>>(&output, &data, &ranks)?,
+            _ => unreachable!("ELF outputs are serialized into ELF files"),
+        };
+        *output = stable_output;
+
+        ctx.diagnostics.debug(format_args!(
+            "Laid out {} symbols and {} relocations in a stable order",
+            ranks.len(),
+            num_relocs
+        ));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StabilizeLayoutError {
+    #[error("read serialized output failed: {0:?}")]
+    ReadError(#[from] ReadError),
+
+    #[error("write output failed: {0}")]
+    WriteError(#[from] WriteError),
+}
+
+/// Build a new output from the given serialized output, with the symbols ordered by the given ranks and the relocations
+/// sorted by their offsets. The header flags and the architecture are taken from the given output. Get the new output
+/// and the number of its relocations.
+fn rebuild_output<Elf>(
+    output: &OutputObject<'static>,
+    data: &[u8],
+    ranks: &HashMap<SymbolKey, SymbolRank>,
+) -> Result<(OutputObject<'static>, usize), StabilizeLayoutError>
+where
+    Elf: ElfFileHeader<Endian = Endianness>,
+{
+    let file = ElfFile::<Elf>::parse(data)?;
+    let endian = file.endian();
+    let header = file.raw_header();
+    let is_mips64el = header.is_mips64el(endian);
+    let sections = header.sections(endian, data)?;
+    let shstrndx = header.shstrndx(endian, data)? as usize;
+    let strtab_index = sections
+        .symbols(endian, data, SHT_SYMTAB)?
+        .string_section()
+        .0;
+
+    let mut stable_output = OutputObject::new(BinaryFormat::Elf, output.architecture(), endian);
+    stable_output.flags = output.flags;
+    stable_output.set_mangling(output.mangling());
+
+    // The symbol table, the string tables and the relocation sections are written again by the object writer.
+    let mut section_ids = HashMap::new();
+    for (index, section_header) in sections.iter().enumerate() {
+        let is_table = match section_header.sh_type(endian) {
+            SHT_NULL | SHT_SYMTAB | SHT_SYMTAB_SHNDX | SHT_REL | SHT_RELA => true,
+            SHT_STRTAB => index == strtab_index || index == shstrndx,
+            _ => false,
+        };
+        if is_table {
+            continue;
+        }
+
+        let section = file.section_by_index(SectionIndex(index))?;
+        let kind = section.kind();
+        let align = section.align().max(1);
+        let section_id =
+            stable_output.add_section(Vec::new(), section.name_bytes()?.to_vec(), kind);
+        if kind.is_bss() {
+            stable_output.append_section_bss(section_id, section.size(), align);
+        } else {
+            stable_output.set_section_data(section_id, section.data()?.to_vec(), align);
+        }
+        stable_output.section_mut(section_id).flags = section.flags();
+        section_ids.insert(section.index(), section_id);
+    }
+
+    // The null symbol is written by the object writer as well.
+    let mut symbols: Vec<_> = file.symbols().filter(|sym| sym.index().0 != 0).collect();
+    symbols.sort_by_cached_key(|sym| match sym.section_index() {
+        Some(SectionIndex(index)) if sym.kind() == SymbolKind::Section => {
+            SymbolRank::Section(index)
+        }
+        _ => {
+            let key = (
+                sym.name_bytes().unwrap_or_default().to_vec(),
+                sym.address(),
+                sym.is_local(),
+            );
+            ranks
+                .get(&key)
+                .cloned()
+                .unwrap_or(SymbolRank::Unknown(sym.index().0))
+        }
+    });
+
+    let mut symbol_ids = HashMap::with_capacity(symbols.len());
+    for sym in symbols {
+        let section = match sym.section() {
+            SymbolSection::Undefined => OutputSymbolSection::Undefined,
+            SymbolSection::Absolute => OutputSymbolSection::Absolute,
+            SymbolSection::Common => OutputSymbolSection::Common,
+            SymbolSection::Section(index) => OutputSymbolSection::Section(section_ids[&index]),
+            _ => OutputSymbolSection::None,
+        };
+        let symbol_id = match section {
+            OutputSymbolSection::Section(section_id) if sym.kind() == SymbolKind::Section => {
+                stable_output.section_symbol(section_id)
+            }
+            _ => stable_output.add_symbol(OutputSymbol {
+                name: sym.name_bytes()?.to_vec(),
+                value: sym.address(),
+                size: sym.size(),
+                kind: sym.kind(),
+                scope: sym.scope(),
+                weak: sym.is_weak(),
+                section,
+                flags: match sym.flags() {
+                    SymbolFlags::Elf { st_info, st_other } => {
+                        SymbolFlags::Elf { st_info, st_other }
+                    }
+                    _ => SymbolFlags::None,
+                },
+            }),
+        };
+        symbol_ids.insert(sym.index(), symbol_id);
+    }
+
+    // The relocations are added with their raw types, which the object writer keeps as they are. Implicit addends are
+    // part of the copied section data already.
+    let mut num_relocs = 0;
+    for section in sections.iter() {
+        let mut relocs: Vec<(u64, u32, u32, i64)> = match section.sh_type(endian) {
+            SHT_RELA => section
+                .rela(endian, data)?
+                .map_or(&[][..], |(relocs, _)| relocs)
+                .iter()
+                .map(|reloc| {
+                    (
+                        reloc.r_offset(endian).into(),
+                        reloc.r_sym(endian, is_mips64el),
+                        reloc.r_type(endian, is_mips64el),
+                        reloc.r_addend(endian).into(),
+                    )
+                })
+                .collect(),
+            SHT_REL => section
+                .rel(endian, data)?
+                .map_or(&[][..], |(relocs, _)| relocs)
+                .iter()
+                .map(|reloc| {
+                    (
+                        reloc.r_offset(endian).into(),
+                        reloc.r_sym(endian),
+                        reloc.r_type(endian),
+                        0,
+                    )
+                })
+                .collect(),
+            _ => continue,
+        };
+
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        let section_id = section_ids[&SectionIndex(section.sh_info(endian) as usize)];
+        for (offset, r_sym, r_type, addend) in relocs {
+            stable_output.add_relocation(
+                section_id,
+                OutputRelocation {
+                    offset,
+                    size: 0,
+                    kind: RelocationKind::Elf(r_type),
+                    encoding: RelocationEncoding::Generic,
+                    symbol: symbol_ids[&SymbolIndex(r_sym as usize)],
+                    addend,
+                },
+            )?;
+            num_relocs += 1;
+        }
+    }
+
+    Ok((stable_output, num_relocs))
+}
+
+#[cfg(test)]
+mod test {
+    use object::read::elf::ElfFile64;
+    use object::{Endianness, Object as _, ObjectSection as _, ObjectSymbol as _};
+
+    use crate::elf::pass::boundary::GenerateBoundarySymbolsPass;
+    use crate::elf::pass::reloc::ConvertRelocationPass;
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::elf::pass::symbol::GenerateSymbolPass;
+    use crate::pass::PassManager;
+
+    use super::StabilizeLayoutPass;
+
+    /// Convert the test input with the boundary symbols generated before or after the symbols of the input.
+    fn convert_test_input(boundary_first: bool, stable_layout: bool) -> Vec<u8> {
+        let input = crate::elf::test::get_test_input_file();
+        let output = crate::elf::create_elf_output(&input).unwrap();
+
+        let mut pass_mgr: PassManager<ElfFile64<'static, Endianness>> = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let boundary_pass = GenerateBoundarySymbolsPass {
+            cls_pass,
+            start_symbol: String::from(GenerateBoundarySymbolsPass::DEFAULT_START_SYMBOL),
+            end_symbol: String::from(GenerateBoundarySymbolsPass::DEFAULT_END_SYMBOL),
+        };
+        let (sym_gen_pass, boundary_pass) = if boundary_first {
+            let boundary_pass = pass_mgr.add_pass(boundary_pass);
+            let sym_gen_pass =
+                pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+            (sym_gen_pass, boundary_pass)
+        } else {
+            let sym_gen_pass =
+                pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+            (sym_gen_pass, pass_mgr.add_pass(boundary_pass))
+        };
+        pass_mgr.add_pass(ConvertRelocationPass::new(
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+        ));
+        if stable_layout {
+            pass_mgr.add_pass(StabilizeLayoutPass {
+                sym_gen_pass,
+                defsym_pass: None,
+                require_pass: None,
+                boundary_pass,
+            });
+        }
+
+        pass_mgr.run(input, output).unwrap().write().unwrap()
+    }
+
+    #[test]
+    fn test_stabilize_layout_pass() {
+        assert_ne!(
+            convert_test_input(true, false),
+            convert_test_input(false, false)
+        );
+
+        let output_data = convert_test_input(true, true);
+        assert_eq!(output_data, convert_test_input(false, true));
+
+        let output_file = ElfFile64::<Endianness>::parse(&*output_data).unwrap();
+        let offsets: Vec<_> = output_file
+            .section_by_name(CopyLodableSectionsPass::DEFAULT_SECTION_NAME)
+            .unwrap()
+            .relocations()
+            .map(|(offset, _)| offset)
+            .collect();
+        assert!(!offsets.is_empty());
+        assert!(offsets.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // The boundary symbols come after the symbols of the input.
+        let names: Vec<_> = output_file
+            .symbols()
+            .map(|sym| String::from(sym.name().unwrap()))
+            .collect();
+        assert_eq!(
+            &names[names.len() - 2..],
+            &["__soda_image_end", "__soda_image_start"]
+        );
+    }
+}
@@ -1,4 +1,18 @@
+pub mod boundary;
+pub mod defsym;
+pub mod entry;
+pub mod gc;
+pub mod got;
+pub mod ifunc;
 pub mod init_array;
+pub mod layout;
+pub mod note;
 pub mod reloc;
+pub mod reloc_index;
+pub mod relro;
+pub mod require;
 pub mod section;
+pub mod size;
 pub mod symbol;
+pub mod symbol_list;
+pub mod textrel;
@@ -0,0 +1,355 @@
+use std::convert::Infallible;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader, SectionHeader as _};
+use object::{Endian, Object as _, ReadRef, SectionKind};
+
+use crate::elf::ConvertOptions;
+use crate::pass::{Pass, PassContext};
+
+/// Name of the output section that holds the provenance note.
+pub const NOTE_SECTION_NAME: &str = ".note.soda";
+
+/// Owner name of the provenance note.
+pub const NOTE_NAME: &[u8] = b"soda";
+
+/// Type of the provenance note.
+pub const NT_SODA_PROVENANCE: u32 = 1;
+
+/// A pass that emits a `.note.soda` section recording where the output relocatable file comes from.
+///
+/// The section holds a single ELF note owned by `soda` and of type [`NT_SODA_PROVENANCE`], whose descriptor is a
+/// [`Provenance`]. The section is not allocated, so linkers carry it into their output without loading it. When several
+/// inputs are merged, each of them adds its own section.
+#[derive(Debug)]
+pub struct GenerateProvenanceNotePass {
+    /// Description of the options of the conversion, as given by [`describe_options`].
+    pub options: String,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for GenerateProvenanceNotePass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "generate provenance note";
+
+    type Output = Provenance;
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        // Inputs whose dynamic table cannot be read have already failed the PIE check, so errors are only reported.
+        let soname = crate::elf::get_soname(&ctx.input).unwrap_or_else(|err| {
+            ctx.diagnostics
+                .warn(format_args!("Cannot read the soname of the input: {}", err));
+            None
+        });
+        let build_id = ctx.input.build_id().unwrap_or_else(|err| {
+            ctx.diagnostics.warn(format_args!(
+                "Cannot read the build ID of the input: {}",
+                err
+            ));
+            None
+        });
+
+        let provenance = Provenance {
+            soname: soname.unwrap_or_default(),
+            build_id: build_id.map(<[u8]>::to_vec).unwrap_or_default(),
+            soda_version: String::from(env!("CARGO_PKG_VERSION")),
+            options: self.options.clone(),
+        };
+
+        let mut output = ctx.output.borrow_mut();
+        let sec_id = output.add_section(
+            Vec::new(),
+            ctx.section_naming
+                .get(NOTE_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Note,
+        );
+        output.set_section_data(sec_id, provenance.to_note(ctx.input.endian()), 4);
+
+        Ok(provenance)
+    }
+}
+
+/// Provenance of an output relocatable file, which is recorded in the descriptor of its provenance note.
+///
+/// The descriptor starts with the version of its layout as a 32-bit word, which is [`Self::VERSION`]. The fields follow
+/// in the order below, each as a 32-bit length followed by that many bytes. All words are in the byte order of the file.
+/// Later versions of the layout only append fields, so readers ignore the bytes after the fields they know.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Provenance {
+    /// The soname of the input given by its DT_SONAME entry, or empty if it has none.
+    pub soname: String,
+
+    /// The build ID of the input given by its NT_GNU_BUILD_ID note, or empty if it has none.
+    pub build_id: Vec<u8>,
+
+    /// The version of soda that converted the input.
+    pub soda_version: String,
+
+    /// The options of the conversion, one `name: value` pair per line.
+    pub options: String,
+}
+
+impl Provenance {
+    /// The version of the descriptor layout written by this version of soda.
+    pub const VERSION: u32 = 1;
+
+    /// Encode the provenance into the descriptor of a provenance note in the given byte order.
+    pub fn to_desc<En: Endian>(&self, endian: En) -> Vec<u8> {
+        let mut desc = endian.write_u32_bytes(Self::VERSION).to_vec();
+        for field in [
+            self.soname.as_bytes(),
+            &self.build_id,
+            self.soda_version.as_bytes(),
+            self.options.as_bytes(),
+        ] {
+            desc.extend_from_slice(&endian.write_u32_bytes(field.len() as u32));
+            desc.extend_from_slice(field);
+        }
+        desc
+    }
+
+    /// Decode the provenance from the descriptor of a provenance note in the given byte order. Returns `None` if the
+    /// descriptor is malformed.
+    pub fn from_desc<En: Endian>(endian: En, desc: &[u8]) -> Option<Self> {
+        let mut desc = desc;
+        let version = read_u32(endian, &mut desc)?;
+        if version == 0 {
+            return None;
+        }
+
+        let mut read_field = || {
+            let len = read_u32(endian, &mut desc)? as usize;
+            let field = desc.get(..len)?;
+            desc = &desc[len..];
+            Some(field)
+        };
+        let soname = String::from_utf8_lossy(read_field()?).into_owned();
+        let build_id = read_field()?.to_vec();
+        let soda_version = String::from_utf8_lossy(read_field()?).into_owned();
+        let options = String::from_utf8_lossy(read_field()?).into_owned();
+        Some(Self {
+            soname,
+            build_id,
+            soda_version,
+            options,
+        })
+    }
+
+    /// Encode the provenance into a whole note in the given byte order, which is the note header followed by the owner
+    /// name and the descriptor, each padded to 4 bytes.
+    pub fn to_note<En: Endian>(&self, endian: En) -> Vec<u8> {
+        let desc = self.to_desc(endian);
+        // The name size counts the terminating NUL byte.
+        let mut note = Vec::new();
+        note.extend_from_slice(&endian.write_u32_bytes(NOTE_NAME.len() as u32 + 1));
+        note.extend_from_slice(&endian.write_u32_bytes(desc.len() as u32));
+        note.extend_from_slice(&endian.write_u32_bytes(NT_SODA_PROVENANCE));
+        note.extend_from_slice(NOTE_NAME);
+        note.push(0);
+        pad_to_word(&mut note);
+        note.extend_from_slice(&desc);
+        pad_to_word(&mut note);
+        note
+    }
+}
+
+/// Read the provenance notes in the `.note.soda` sections of the given ELF file, in the order of the sections. Notes
+/// whose descriptors are malformed are skipped.
+pub fn read_provenance<'d, E, R>(input: &ElfFile<'d, E, R>) -> object::read::Result<Vec<Provenance>>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let sections = input.raw_header().sections(endian, input.data())?;
+
+    let mut provenances = Vec::new();
+    // The note section may have been renamed, so all note sections are searched.
+    for section in sections.iter() {
+        let Some(mut notes) = section.notes(endian, input.data())? else {
+            continue;
+        };
+        while let Some(note) = notes.next()? {
+            if note.name() != NOTE_NAME || note.n_type(endian) != NT_SODA_PROVENANCE {
+                continue;
+            }
+            provenances.extend(Provenance::from_desc(endian, note.desc()));
+        }
+    }
+    Ok(provenances)
+}
+
+/// Describe the options that affect the contents of the output of a conversion, one `name: value` pair per line.
+pub fn describe_options(options: &ConvertOptions) -> String {
+    let ConvertOptions {
+        section_name,
+        trap_padding,
+        max_image_size_factor,
+        protect_relro,
+        max_section_align,
+        allow_truncated,
+        tls_size_warning,
+        keep_symbol_versions,
+        copy_reloc_check,
+        demote_exported_data,
+        prune_undefined,
+        keep_undefined,
+        drop_special_symbols,
+        drop_excluded_symbols,
+        wrap,
+        redefine_syms,
+        defsyms,
+        require_defined,
+        only_export,
+        gc_roots,
+        bind_locally,
+        weaken,
+        weaken_symbols,
+        image_start_symbol,
+        image_end_symbol,
+        allow_pie,
+        reject_textrel,
+        ctors_style,
+        rename_sections,
+        stable_layout: _,
+        backend: _,
+        provenance_note: _,
+        symbol_list: _,
+        observer: _,
+        diagnostics: _,
+        demangle: _,
+    } = options;
+
+    let fields: [(&str, &dyn std::fmt::Debug); 29] = [
+        ("section_name", section_name),
+        ("trap_padding", trap_padding),
+        ("max_image_size_factor", max_image_size_factor),
+        ("protect_relro", protect_relro),
+        ("max_section_align", max_section_align),
+        ("allow_truncated", allow_truncated),
+        ("tls_size_warning", tls_size_warning),
+        ("keep_symbol_versions", keep_symbol_versions),
+        ("copy_reloc_check", copy_reloc_check),
+        ("demote_exported_data", demote_exported_data),
+        ("prune_undefined", prune_undefined),
+        ("keep_undefined", keep_undefined),
+        ("drop_special_symbols", drop_special_symbols),
+        ("drop_excluded_symbols", drop_excluded_symbols),
+        ("wrap", wrap),
+        ("redefine_syms", redefine_syms),
+        ("defsyms", defsyms),
+        ("require_defined", require_defined),
+        ("only_export", only_export),
+        ("gc_roots", gc_roots),
+        ("bind_locally", bind_locally),
+        ("weaken", weaken),
+        ("weaken_symbols", weaken_symbols),
+        ("image_start_symbol", image_start_symbol),
+        ("image_end_symbol", image_end_symbol),
+        ("allow_pie", allow_pie),
+        ("reject_textrel", reject_textrel),
+        ("ctors_style", ctors_style),
+        ("rename_sections", rename_sections),
+    ];
+    fields
+        .iter()
+        .map(|(name, value)| format!("{}: {:?}\n", name, value))
+        .collect()
+}
+
+fn read_u32<En: Endian>(endian: En, data: &mut &[u8]) -> Option<u32> {
+    let (word, rest) = data.split_first_chunk::<4>()?;
+    *data = rest;
+    Some(endian.read_u32_bytes(*word))
+}
+
+fn pad_to_word(data: &mut Vec<u8>) {
+    data.resize(data.len().next_multiple_of(4), 0);
+}
+
+#[cfg(test)]
+mod test {
+    use object::{Endian, Endianness, Object as _, ObjectSection as _, SectionKind};
+
+    use crate::ConvertOptions;
+
+    use super::{Provenance, NOTE_SECTION_NAME};
+
+    #[test]
+    fn test_provenance_desc() {
+        let provenance = Provenance {
+            soname: String::from("libfoo.so.1"),
+            build_id: vec![0xde, 0xad, 0xbe, 0xef],
+            soda_version: String::from("1.2.3"),
+            options: String::from("weaken: true\n"),
+        };
+
+        for endian in [Endianness::Little, Endianness::Big] {
+            let desc = provenance.to_desc(endian);
+            let note = provenance.to_note(endian);
+            let decode = |desc: &[u8]| Provenance::from_desc(endian, desc);
+            assert_eq!(decode(&desc), Some(provenance.clone()));
+
+            // The note is the 12-byte header, the padded name and the padded descriptor.
+            assert_eq!(note.len() % 4, 0);
+            assert_eq!(&note[12..20], b"soda\0\0\0\0");
+            assert_eq!(&note[20..20 + desc.len()], &desc[..]);
+
+            // Fields appended by later versions are ignored, and truncated fields are rejected.
+            let mut extended = desc.clone();
+            extended[..4].copy_from_slice(&endian.write_u32_bytes(2));
+            extended.extend_from_slice(&[0; 8]);
+            assert_eq!(decode(&extended), Some(provenance.clone()));
+            assert_eq!(decode(&desc[..desc.len() - 1]), None);
+            assert_eq!(decode(&[0; 4]), None);
+        }
+    }
+
+    #[test]
+    fn test_provenance_note_spdlog() {
+        let input = crate::elf::test::get_test_input_file();
+        let build_id = input.build_id().unwrap().unwrap().to_vec();
+
+        let options = ConvertOptions {
+            weaken: true,
+            ..Default::default()
+        };
+        let output = crate::convert(input.data(), &options).unwrap();
+        let output_file = object::File::parse(output.as_slice()).unwrap();
+
+        // The note section is not allocated, and is aligned so that readelf finds the notes.
+        let section = output_file.section_by_name(NOTE_SECTION_NAME).unwrap();
+        assert_eq!(section.kind(), SectionKind::Note);
+        assert_eq!(section.align(), 4);
+        assert_eq!(section.size() % 4, 0);
+
+        let provenances = crate::read_provenance(&output).unwrap();
+        assert_eq!(provenances.len(), 1);
+        let provenance = &provenances[0];
+        assert_eq!(provenance.soname, "libspdlog.so.1.12");
+        assert_eq!(provenance.build_id, build_id);
+        assert_eq!(provenance.soda_version, env!("CARGO_PKG_VERSION"));
+        assert!(provenance
+            .options
+            .lines()
+            .any(|line| line == "weaken: true"));
+        assert!(provenance
+            .options
+            .lines()
+            .any(|line| line == "section_name: \".soda\""));
+
+        // The note is left out when asked.
+        let options = ConvertOptions {
+            provenance_note: false,
+            ..Default::default()
+        };
+        let output = crate::convert(input.data(), &options).unwrap();
+        let output_file = object::File::parse(output.as_slice()).unwrap();
+        assert!(output_file.section_by_name(NOTE_SECTION_NAME).is_none());
+        assert!(crate::read_provenance(&output).unwrap().is_empty());
+    }
+}
@@ -1,108 +1,534 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
 use object::elf::{
-    R_X86_64_64, R_X86_64_DTPMOD64, R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT, R_X86_64_RELATIVE,
+    DT_MIPS_GOTSYM, DT_MIPS_LOCAL_GOTNO, DT_MIPS_SYMTABNO, DT_PLTGOT, R_386_32, R_386_COPY,
+    R_386_GLOB_DAT, R_386_IRELATIVE, R_386_JMP_SLOT, R_386_RELATIVE, R_386_TLS_LE, R_386_TLS_LE_32,
+    R_386_TLS_TPOFF, R_386_TLS_TPOFF32, R_390_64, R_390_COPY, R_390_GLOB_DAT, R_390_IRELATIVE,
+    R_390_JMP_SLOT, R_390_RELATIVE, R_390_TLS_DTPMOD, R_390_TLS_DTPOFF, R_390_TLS_LDO64,
+    R_390_TLS_LE64, R_390_TLS_TPOFF, R_AARCH64_ABS64, R_AARCH64_COPY, R_AARCH64_GLOB_DAT,
+    R_AARCH64_IRELATIVE, R_AARCH64_JUMP_SLOT, R_AARCH64_RELATIVE, R_AARCH64_TLS_DTPMOD,
+    R_AARCH64_TLS_DTPREL, R_AARCH64_TLS_TPREL, R_ARM_ABS32, R_ARM_COPY, R_ARM_GLOB_DAT,
+    R_ARM_IRELATIVE, R_ARM_JUMP_SLOT, R_ARM_RELATIVE, R_ARM_TLS_DTPMOD32, R_ARM_TLS_DTPOFF32,
+    R_ARM_TLS_LDO32, R_ARM_TLS_LE32, R_ARM_TLS_TPOFF32, R_LARCH_64, R_LARCH_COPY,
+    R_LARCH_IRELATIVE, R_LARCH_JUMP_SLOT, R_LARCH_RELATIVE, R_LARCH_TLS_DTPMOD64,
+    R_LARCH_TLS_DTPREL64, R_LARCH_TLS_TPREL64, R_MIPS_64, R_MIPS_COPY, R_MIPS_JUMP_SLOT,
+    R_MIPS_NONE, R_MIPS_REL32, R_MIPS_TLS_DTPMOD64, R_MIPS_TLS_DTPREL64, R_MIPS_TLS_TPREL64,
+    R_PPC64_ADDR64, R_PPC64_COPY, R_PPC64_DTPMOD64, R_PPC64_DTPREL64, R_PPC64_GLOB_DAT,
+    R_PPC64_IRELATIVE, R_PPC64_JMP_SLOT, R_PPC64_RELATIVE, R_PPC64_TPREL64, R_RISCV_64,
+    R_RISCV_COPY, R_RISCV_JUMP_SLOT, R_RISCV_RELATIVE, R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_DTPREL64,
+    R_RISCV_TLS_TPREL64, R_X86_64_64, R_X86_64_COPY, R_X86_64_DTPMOD64, R_X86_64_DTPOFF64,
+    R_X86_64_GLOB_DAT, R_X86_64_IRELATIVE, R_X86_64_JUMP_SLOT, R_X86_64_RELATIVE, R_X86_64_SIZE32,
+    R_X86_64_SIZE64, R_X86_64_TLSDESC, R_X86_64_TPOFF64, SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS,
+    STT_GNU_IFUNC,
 };
-use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::read::elf::{Dyn as _, ElfFile, FileHeader as ElfFileHeader};
 use object::read::Error as ReadError;
-use object::write::Relocation as OutputRelocation;
-use object::{Architecture, Object as _, ReadRef, RelocationKind, RelocationTarget};
+use object::{
+    Architecture, Endian, Object as _, ObjectSymbol as _, ObjectSymbolTable as _, ReadRef,
+    Relocation, RelocationEncoding, RelocationKind, RelocationTarget, SectionFlags, SectionKind,
+    SymbolFlags, SymbolIndex, SymbolKind, SymbolScope,
+};
 use thiserror::Error;
 
-use crate::elf::pass::section::CopyLodableSectionsPass;
-use crate::elf::pass::symbol::GenerateSymbolPass;
-use crate::pass::{Pass, PassContext, PassHandle};
+use crate::elf::pass::gc::CollectGarbagePass;
+use crate::elf::pass::init_array::{GenerateFiniArrayPass, GenerateInitArrayPass};
+use crate::elf::pass::reloc_index::{IndexRelocationsPass, RelocationIndex};
+use crate::elf::pass::section::{
+    CopyLodableSectionsOutput, CopyLodableSectionsPass, OutputLocation,
+};
+use crate::elf::pass::symbol::{create_undefined_symbol, GenerateSymbolPass, SymbolMap};
+use crate::output::{
+    Object as OutputObject, Relocation as OutputRelocation, SectionId, Symbol as OutputSymbol,
+    SymbolId, SymbolSection as OutputSymbolSection,
+};
+use crate::pass::{Cancelled, Diagnostics, Pass, PassContext, PassHandle, SectionNaming};
+use crate::utils::demangle::demangle;
+use crate::utils::stringify::{arch_to_str, reloc_kind_to_str, reloc_type_to_str};
 
 /// A pass that converts the dynamic relocations in the input shared library into corresponding static relocations in
 /// the output relocatable file.
+///
+/// TLS relocations are converted into the corresponding static relocations against output TLS symbols, which the final
+/// link resolves. Static linkers cannot resolve TLS module IDs though, so DTPMOD64 relocations are resolved to module
+/// ID 1 in place, assuming that the output is linked into an executable. No relocation is emitted for them. Note that GNU
+/// ld rejects DTPOFF64 relocations in executable sections and gold resolves them to TP offsets in executables, so the
+/// output of libraries that use the general-dynamic model can't be linked with either of them.
+///
+/// TLS descriptors, which libraries built with `-mtls-dialect=gnu2` use instead of tls_index pairs, are downgraded to
+/// static TLS: the resolver of each descriptor is a generated function that returns the TP offset stored in the
+/// argument of the descriptor, which is filled by a TPOFF64 relocation. This is only correct if the output is linked
+/// into an executable as well.
+///
+/// Relocations against global symbols that are defined in sections not copied into the output relocatable file refer to
+/// undefined symbols of the same names instead, which are generated on the fly.
+///
+/// Relocations that refer to the addresses of symbols are bound to the symbols, so definitions in other objects of the
+/// final link may interpose on them like they may on the symbols of the input shared library. With
+/// [`Self::bind_locally`], they refer to the definitions in the copied image instead, like in a library linked with
+/// `-Bsymbolic`.
+///
+/// The relocations that [`Self::init_array_pass`] and [`Self::fini_array_pass`] convert are left out, and so are the
+/// relocations in the sections collected by [`Self::gc_pass`]. Converted relocations that overlap each other are
+/// reported, see [`RelocationSlots`].
 #[derive(Debug)]
 pub struct ConvertRelocationPass {
     pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
     pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+
+    /// Bind relocations that refer to the addresses of symbols defined in the output to the sections of the definitions.
+    /// Weak definitions, IFUNC symbols and TLS symbols stay bound to the symbols.
+    pub bind_locally: bool,
+
+    /// Keep relocations that refer to global definitions bound to the symbols even with [`Self::bind_locally`]. This is
+    /// set when the exported symbols are given explicitly.
+    pub keep_exported_bound: bool,
+
+    /// Passes that convert the relocations in the init and fini arrays into relocations of arrays of their own. The
+    /// relocations they convert are not converted again into the copied image.
+    pub init_array_pass: Option<PassHandle<GenerateInitArrayPass>>,
+    pub fini_array_pass: Option<PassHandle<GenerateFiniArrayPass>>,
+
+    /// The pass that collects unreachable sections. Relocations whose sites lie in the collected sections are dropped.
+    pub gc_pass: Option<PassHandle<CollectGarbagePass>>,
 }
 
 impl ConvertRelocationPass {
-    fn convert_x86_64_relocations<'d, E, R>(
+    pub fn new(
+        cls_pass: PassHandle<CopyLodableSectionsPass>,
+        reloc_index_pass: PassHandle<IndexRelocationsPass>,
+        sym_gen_pass: PassHandle<GenerateSymbolPass>,
+    ) -> Self {
+        Self {
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+            bind_locally: false,
+            keep_exported_bound: false,
+            init_array_pass: None,
+            fini_array_pass: None,
+            gc_pass: None,
+        }
+    }
+
+    /// Get the addresses of the input relocations that are converted by [`Self::init_array_pass`] and
+    /// [`Self::fini_array_pass`].
+    fn get_converted_elsewhere<'d, E, R>(
         &self,
         ctx: &PassContext<ElfFile<'d, E, R>>,
-    ) -> Result<(), ConvertRelocationError>
+    ) -> HashSet<u64>
     where
         E: ElfFileHeader,
         R: ReadRef<'d>,
     {
-        assert_eq!(ctx.input.architecture(), Architecture::X86_64);
+        let init_array_relocs = self
+            .init_array_pass
+            .map(|pass| &ctx.get_pass_output(pass).converted_relocs);
+        let fini_array_relocs = self
+            .fini_array_pass
+            .map(|pass| &ctx.get_pass_output(pass).converted_relocs);
+        init_array_relocs
+            .into_iter()
+            .chain(fini_array_relocs)
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Get the symbol and the addend of a relocation that refers to the address of the given output symbol plus the
+    /// given addend. See [`Self::bind_locally`].
+    fn bind_address_reference(
+        &self,
+        output: &mut OutputObject<'static>,
+        symbol: SymbolId,
+        addend: i64,
+    ) -> (SymbolId, i64) {
+        if !self.bind_locally {
+            return (symbol, addend);
+        }
+
+        let sym = output.symbol(symbol);
+        let OutputSymbolSection::Section(section_id) = sym.section else {
+            return (symbol, addend);
+        };
+        let is_ifunc = matches!(
+            sym.flags,
+            SymbolFlags::Elf { st_info, .. } if st_info & 0xf == STT_GNU_IFUNC
+        );
+        let is_exported = self.keep_exported_bound && sym.scope != SymbolScope::Compilation;
+        if sym.weak || sym.kind == SymbolKind::Tls || is_ifunc || is_exported {
+            return (symbol, addend);
+        }
+
+        let value = sym.value;
+        (output.section_symbol(section_id), value as i64 + addend)
+    }
 
-        let input_reloc_iter = match ctx.input.dynamic_relocations() {
-            Some(iter) => iter,
-            None => {
-                return Ok(());
+    /// Get the symbol and the addend of a relocation that refers to the address of the target of the given input
+    /// relocation plus the given addend.
+    ///
+    /// Relocations against section symbols are rebased onto the output locations of the sections, like RELATIVE
+    /// relocations. Relocations without a target symbol refer to the absolute value of the addend, so they are made
+    /// against a local absolute symbol of value 0, which is added on first use and kept in `absolute_symbol`.
+    #[allow(clippy::too_many_arguments)]
+    fn get_address_target<'d, E, R>(
+        &self,
+        ctx: &PassContext<ElfFile<'d, E, R>>,
+        output: &mut OutputObject<'static>,
+        sym_map: &mut SymbolMap,
+        absolute_symbol: &mut Option<SymbolId>,
+        input_reloc_addr: u64,
+        input_reloc: &Relocation,
+        addend: i64,
+    ) -> Result<(SymbolId, i64), ConvertRelocationError>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let sym_idx = match input_reloc.target() {
+            RelocationTarget::Symbol(sym_idx) => sym_idx,
+            RelocationTarget::Absolute => {
+                let symbol = *absolute_symbol.get_or_insert_with(|| add_absolute_symbol(output));
+                return Ok((symbol, addend));
+            }
+            _ => {
+                return Err(ConvertRelocationError::MissingTargetSymbol {
+                    addr: input_reloc_addr,
+                    arch: ctx.input.architecture(),
+                    kind: get_elf_reloc_kind(ctx.input.architecture(), input_reloc),
+                });
             }
         };
 
+        let section_sym = ctx
+            .input
+            .dynamic_symbol_table()
+            .and_then(|symtab| symtab.symbol_by_index(sym_idx).ok())
+            .filter(|sym| sym.kind() == SymbolKind::Section);
+        if let Some(section_sym) = section_sym {
+            let mut addr = section_sym.address().wrapping_add(addend as u64);
+            if !ctx.input.is_64() {
+                addr = addr as u32 as u64;
+            }
+            return Ok(ctx
+                .get_pass_output(self.cls_pass)
+                .get_address_reference(addr));
+        }
+
+        let output_sym_id = get_target_symbol(
+            &ctx.input,
+            &ctx.diagnostics,
+            output,
+            sym_map,
+            input_reloc_addr,
+            sym_idx,
+        )?;
+        Ok(self.bind_address_reference(output, output_sym_id, addend))
+    }
+}
+
+impl ConvertRelocationPass {
+    /// Convert the dynamic relocations of the input, whose types are given by the table of its architecture, and count
+    /// them by type.
+    fn convert_relocations<'d, E, R>(
+        &self,
+        ctx: &PassContext<ElfFile<'d, E, R>>,
+        types: &DynamicRelocationTypes,
+    ) -> Result<RelocationStats, ConvertRelocationError>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let arch = ctx.input.architecture();
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+
         let cls_output = ctx.get_pass_output(self.cls_pass);
-        let sym_map = ctx.get_pass_output(self.sym_gen_pass);
+        // Symbols that are generated on the fly are added to a copy of the symbol map so that later relocations reuse
+        // them.
+        let mut sym_map = ctx.get_pass_output(self.sym_gen_pass).clone();
 
         let mut output = ctx.output.borrow_mut();
+        let mut slots = RelocationSlots::new(ctx.diagnostics.clone());
+        let mut unsupported = UnsupportedRelocations::new(arch);
+        let converted_elsewhere = self.get_converted_elsewhere(ctx);
+        let collected = self.gc_pass.map(|pass| ctx.get_pass_output(pass));
+        let mut absolute_symbol = None;
+
+        // Most GOT entries don't have any dynamic relocation on MIPS.
+        if arch == Architecture::Mips64 {
+            convert_mips64_got(
+                self,
+                &ctx.input,
+                &ctx.diagnostics,
+                &mut output,
+                &mut slots,
+                cls_output,
+                &mut sym_map,
+            )?;
+        }
+
+        let mut relative_relocs =
+            convert_relative_relocations(reloc_index, cls_output, arch, types);
+        let word_size = u64::from(output_reloc_size(arch, RelocationKind::Absolute) / 8);
+        let dtpoff_addrs = types
+            .tls_module
+            .map(|(_, dtpoff)| reloc_index.addrs_of_kind(RelocationKind::Elf(dtpoff)))
+            .unwrap_or_default();
+        let mut num_dtpmod_relocs = 0;
+        let mut tlsdesc_resolver = None;
+        let mut num_tlsdesc_relocs = 0;
+        let mut stats = RelocationStats::default();
+
+        for (reloc_idx, (input_reloc_addr, input_reloc)) in reloc_index.iter().enumerate() {
+            if reloc_idx % CANCEL_CHECK_INTERVAL == 0 {
+                ctx.check_cancelled()?;
+            }
+
+            if let Some(r_type) = get_elf_r_type(arch, input_reloc) {
+                *stats.counts.entry(r_type).or_default() += 1;
+            }
 
-        for (input_reloc_addr, input_reloc) in input_reloc_iter {
-            if input_reloc_addr >= cls_output.output_section_size {
-                log::warn!("Relocation happens outside of loadable sections");
+            if converted_elsewhere.contains(&input_reloc_addr)
+                || collected.is_some_and(|collected| collected.contains(input_reloc_addr))
+            {
                 continue;
             }
 
-            if input_reloc.size() != 0 && input_reloc.size() != 64 {
-                log::warn!("Unexpected relocation size");
+            if let Some((section_id, output_reloc)) = relative_relocs[reloc_idx].take() {
+                slots.add(
+                    &mut output,
+                    section_id,
+                    output_reloc,
+                    RelocationSource::dynamic(arch, input_reloc_addr, input_reloc),
+                );
+                continue;
             }
 
-            let output_reloc_offset = input_reloc_addr;
-
-            let output_reloc = match input_reloc.kind() {
-                RelocationKind::Elf(R_X86_64_RELATIVE) => OutputRelocation {
-                    offset: output_reloc_offset,
-                    size: 64,
-                    kind: RelocationKind::Absolute,
-                    encoding: input_reloc.encoding(),
-                    symbol: cls_output.output_section_symbol,
-                    addend: input_reloc.addend(),
-                },
-
-                RelocationKind::Absolute
-                | RelocationKind::Elf(R_X86_64_64)
-                | RelocationKind::Elf(R_X86_64_GLOB_DAT)
-                | RelocationKind::Elf(R_X86_64_JUMP_SLOT) => {
-                    let target_sym_idx = match input_reloc.target() {
-                        RelocationTarget::Symbol(sym_idx) => sym_idx,
-                        _ => todo!(),
-                    };
-                    let output_sym_id = sym_map.get_output_symbol(target_sym_idx).unwrap();
+            if types.is_left_out(arch, input_reloc) {
+                continue;
+            }
+            let class = types.classify(arch, input_reloc);
+
+            let Some(output_reloc_loc) = slots.translate(
+                cls_output,
+                RelocationSource::dynamic(arch, input_reloc_addr, input_reloc),
+            ) else {
+                continue;
+            };
+
+            let reloc_size =
+                get_output_reloc_size(&ctx.diagnostics, arch, input_reloc_addr, input_reloc);
+
+            let output_reloc_offset = output_reloc_loc.offset;
+
+            // The addends of REL relocations are stored in the relocated slots. They are turned into explicit addends of
+            // the output relocations. The addend of a TLS descriptor is stored in its argument.
+            let addend = if input_reloc.has_implicit_addend() {
+                let (addend_offset, addend_size) = match class {
+                    DynamicRelocationClass::TlsDescriptor(_) => (word_size, word_size as usize * 8),
+                    _ => (0, usize::from(reloc_size)),
+                };
+                take_implicit_addend(
+                    &mut output,
+                    &OutputLocation {
+                        offset: output_reloc_offset + addend_offset,
+                        ..output_reloc_loc
+                    },
+                    addend_size,
+                    ctx.input.endian(),
+                )
+                .ok_or(ConvertRelocationError::TruncatedAddend(input_reloc_addr))?
+            } else {
+                input_reloc.addend()
+            };
+
+            let output_reloc = match class {
+                DynamicRelocationClass::Relative => {
+                    let mut addr = addend as u64;
+                    if !ctx.input.is_64() {
+                        addr = addr as u32 as u64;
+                    }
+                    let (symbol, addend) = cls_output.get_address_reference(addr);
+                    OutputRelocation {
+                        offset: output_reloc_offset,
+                        size: reloc_size,
+                        kind: RelocationKind::Absolute,
+                        encoding: input_reloc.encoding(),
+                        symbol,
+                        addend,
+                    }
+                }
+
+                DynamicRelocationClass::Address => {
+                    let (symbol, addend) = self.get_address_target(
+                        ctx,
+                        &mut output,
+                        &mut sym_map,
+                        &mut absolute_symbol,
+                        input_reloc_addr,
+                        input_reloc,
+                        addend,
+                    )?;
                     OutputRelocation {
                         offset: output_reloc_offset,
-                        size: 64,
+                        size: reloc_size,
                         kind: RelocationKind::Absolute,
                         encoding: input_reloc.encoding(),
+                        symbol,
+                        addend,
+                    }
+                }
+
+                DynamicRelocationClass::TlsOffset(static_type) => {
+                    // Relocations without a symbol refer to an offset within the TLS image of the input shared library.
+                    let symbol = match input_reloc.target() {
+                        RelocationTarget::Symbol(sym_idx) => get_target_symbol(
+                            &ctx.input,
+                            &ctx.diagnostics,
+                            &mut output,
+                            &mut sym_map,
+                            input_reloc_addr,
+                            sym_idx,
+                        )?,
+                        _ => get_tls_section_symbol(cls_output, arch, input_reloc.kind())?,
+                    };
+                    OutputRelocation {
+                        offset: output_reloc_offset,
+                        size: reloc_size,
+                        kind: RelocationKind::Elf(static_type),
+                        encoding: input_reloc.encoding(),
+                        symbol,
+                        addend,
+                    }
+                }
+
+                DynamicRelocationClass::TlsModule(static_dtpoff_type) => {
+                    if let Some(offset_reloc) = resolve_tls_index(
+                        &mut output,
+                        cls_output,
+                        &output_reloc_loc,
+                        input_reloc_addr,
+                        ctx.input.endian(),
+                        dtpoff_addrs.contains(&(input_reloc_addr + word_size)),
+                        RelocationKind::Elf(static_dtpoff_type),
+                    )? {
+                        slots.add(
+                            &mut output,
+                            output_reloc_loc.section_id,
+                            offset_reloc,
+                            RelocationSource::TlsIndexOffset(input_reloc_addr),
+                        );
+                    }
+                    num_dtpmod_relocs += 1;
+                    continue;
+                }
+
+                DynamicRelocationClass::TlsDescriptor(tpoff_type) => {
+                    let symbol = match input_reloc.target() {
+                        RelocationTarget::Symbol(sym_idx) => get_target_symbol(
+                            &ctx.input,
+                            &ctx.diagnostics,
+                            &mut output,
+                            &mut sym_map,
+                            input_reloc_addr,
+                            sym_idx,
+                        )?,
+                        _ => get_tls_section_symbol(cls_output, arch, input_reloc.kind())?,
+                    };
+                    let resolver = *tlsdesc_resolver.get_or_insert_with(|| {
+                        add_tlsdesc_resolver(&mut output, &ctx.section_naming)
+                    });
+                    slots.add(
+                        &mut output,
+                        output_reloc_loc.section_id,
+                        OutputRelocation {
+                            offset: output_reloc_offset,
+                            size: reloc_size,
+                            kind: RelocationKind::Absolute,
+                            encoding: RelocationEncoding::Generic,
+                            symbol: resolver,
+                            addend: 0,
+                        },
+                        RelocationSource::dynamic(arch, input_reloc_addr, input_reloc),
+                    );
+                    num_tlsdesc_relocs += 1;
+
+                    // The argument of the descriptor is the TP offset of the variable.
+                    OutputRelocation {
+                        offset: output_reloc_offset + word_size,
+                        size: reloc_size,
+                        kind: RelocationKind::Elf(tpoff_type),
+                        encoding: input_reloc.encoding(),
+                        symbol,
+                        addend,
+                    }
+                }
+
+                DynamicRelocationClass::Size(r_type) => {
+                    // The relocated slot holds the size of the target symbol, which the static linker knows as well.
+                    let RelocationTarget::Symbol(target_sym_idx) = input_reloc.target() else {
+                        return Err(ConvertRelocationError::MissingTargetSymbol {
+                            addr: input_reloc_addr,
+                            arch,
+                            kind: get_elf_reloc_kind(arch, input_reloc),
+                        });
+                    };
+                    let output_sym_id = get_target_symbol(
+                        &ctx.input,
+                        &ctx.diagnostics,
+                        &mut output,
+                        &mut sym_map,
+                        input_reloc_addr,
+                        target_sym_idx,
+                    )?;
+                    OutputRelocation {
+                        offset: output_reloc_offset,
+                        size: reloc_size,
+                        kind: RelocationKind::Elf(r_type),
+                        encoding: input_reloc.encoding(),
                         symbol: output_sym_id,
-                        addend: input_reloc.addend(),
+                        addend,
                     }
                 }
 
-                RelocationKind::Elf(R_X86_64_DTPMOD64) => OutputRelocation {
-                    offset: output_reloc_offset,
-                    size: 64,
-                    kind: RelocationKind::Elf(R_X86_64_DTPMOD64),
-                    encoding: input_reloc.encoding(),
-                    symbol: cls_output.output_section_symbol, // TODO: no symbols should be associated with this reloc.
-                    addend: input_reloc.addend(),
-                },
-
-                kind => {
-                    return Err(ConvertRelocationError::UnsupportedReloc(kind));
+                DynamicRelocationClass::Copy => {
+                    let sym_name = match input_reloc.target() {
+                        RelocationTarget::Symbol(sym_idx) => {
+                            get_dynamic_symbol_name(&ctx.input, sym_idx)
+                        }
+                        _ => String::new(),
+                    };
+                    return Err(ConvertRelocationError::CopyReloc(sym_name));
+                }
+
+                DynamicRelocationClass::Unsupported => {
+                    unsupported.add(input_reloc);
+                    continue;
                 }
             };
 
-            output
-                .add_relocation(cls_output.output_section_id, output_reloc)
-                .unwrap();
+            slots.add(
+                &mut output,
+                output_reloc_loc.section_id,
+                output_reloc,
+                RelocationSource::dynamic(arch, input_reloc_addr, input_reloc),
+            );
+        }
+
+        unsupported.check()?;
+
+        warn_resolved_tls_module_ids(&ctx.diagnostics, num_dtpmod_relocs);
+
+        if num_tlsdesc_relocs > 0 {
+            ctx.diagnostics.warn(format_args!(
+                "{} TLS descriptors are resolved to static TLS offsets, which is only correct if the output is linked \
+                 into an executable",
+                num_tlsdesc_relocs
+            ));
         }
 
-        Ok(())
+        Ok(stats)
     }
 }
 
@@ -113,7 +539,7 @@ where
 {
     const NAME: &'static str = "convert relocations";
 
-    type Output = ();
+    type Output = RelocationStats;
     type Error = ConvertRelocationError;
 
     fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error>
@@ -121,65 +547,2820 @@ where
         E: ElfFileHeader,
         R: ReadRef<'d>,
     {
-        match ctx.input.architecture() {
-            Architecture::X86_64 => {
-                self.convert_x86_64_relocations(ctx)?;
-            }
-            arch => {
-                return Err(ConvertRelocationError::UnsupportedArch(arch));
-            }
+        let arch = ctx.input.architecture();
+        let types = DynamicRelocationTypes::of(arch)
+            .ok_or(ConvertRelocationError::UnsupportedArch(arch))?;
+        let stats = self.convert_relocations(ctx, types)?;
+        ctx.diagnostics.debug(format_args!(
+            "Converted relocations: {}",
+            format_reloc_type_counts(
+                arch,
+                stats.counts.iter().map(|(r_type, count)| (*r_type, *count))
+            )
+        ));
+
+        Ok(stats)
+    }
+}
+
+/// The output of [`ConvertRelocationPass`].
+#[derive(Debug, Default)]
+pub struct RelocationStats {
+    /// The number of input dynamic relocations of each ELF relocation type, including the relocations that are left to
+    /// other passes.
+    pub counts: HashMap<u32, usize>,
+}
+
+/// The number of input relocations converted between checks for cancellation.
+const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+/// The type of RISC-V IRELATIVE relocations, which the object crate doesn't define yet.
+pub const R_RISCV_IRELATIVE: u32 = 58;
+
+/// The type of MIPS IRELATIVE relocations, which the object crate doesn't define yet.
+pub const R_MIPS_IRELATIVE: u32 = 128;
+
+/// The composite type of the MIPS relocations that relocate 64-bit words, which is R_MIPS_REL32 followed by R_MIPS_64
+/// and R_MIPS_NONE.
+const R_MIPS_REL32_64: u32 = R_MIPS_REL32 | (R_MIPS_64 << 8);
+
+/// The types of the dynamic relocations of an architecture, which drive the conversion of [`ConvertRelocationPass`].
+#[derive(Debug)]
+pub struct DynamicRelocationTypes {
+    /// The type of relocations that relocate addresses within the image.
+    pub relative: u32,
+
+    /// The type of relocations that relocate words to the addresses of symbols. MIPS relocates both addresses within the
+    /// image and addresses of symbols with REL32 relocations, which refer to no symbol in the former case.
+    pub abs: u32,
+    pub glob_dat: Option<u32>,
+    pub jump_slot: u32,
+
+    /// The types of relocations that relocate words to TLS offsets, together with the types of their static equivalents.
+    pub tls_offsets: &'static [(u32, u32)],
+
+    /// The type of relocations that relocate words to TLS module IDs, together with the type of the relocations of the
+    /// offsets that follow them in tls_index pairs.
+    pub tls_module: Option<(u32, u32)>,
+
+    /// The type of relocations of TLS descriptors, together with the type of the static relocations of the TP offsets
+    /// that they are downgraded to.
+    pub tlsdesc: Option<(u32, u32)>,
+
+    /// The types of relocations that relocate words to the sizes of symbols.
+    pub size: &'static [u32],
+
+    pub irelative: u32,
+    pub copy: u32,
+
+    /// The type of relocations that relocate nothing, which are skipped rather than reported as unsupported.
+    pub none: Option<u32>,
+}
+
+impl DynamicRelocationTypes {
+    const X86_64: Self = Self {
+        relative: R_X86_64_RELATIVE,
+        abs: R_X86_64_64,
+        glob_dat: Some(R_X86_64_GLOB_DAT),
+        jump_slot: R_X86_64_JUMP_SLOT,
+        tls_offsets: &[
+            (R_X86_64_DTPOFF64, R_X86_64_DTPOFF64),
+            (R_X86_64_TPOFF64, R_X86_64_TPOFF64),
+        ],
+        tls_module: Some((R_X86_64_DTPMOD64, R_X86_64_DTPOFF64)),
+        tlsdesc: Some((R_X86_64_TLSDESC, R_X86_64_TPOFF64)),
+        size: &[R_X86_64_SIZE32, R_X86_64_SIZE64],
+        irelative: R_X86_64_IRELATIVE,
+        copy: R_X86_64_COPY,
+        none: None,
+    };
+
+    /// The static equivalents of TLS_TPOFF and TLS_TPOFF32 are TLS_LE and TLS_LE_32, which hold the negative and the
+    /// positive TP offset of the variable, respectively.
+    const I386: Self = Self {
+        relative: R_386_RELATIVE,
+        abs: R_386_32,
+        glob_dat: Some(R_386_GLOB_DAT),
+        jump_slot: R_386_JMP_SLOT,
+        tls_offsets: &[
+            (R_386_TLS_TPOFF, R_386_TLS_LE),
+            (R_386_TLS_TPOFF32, R_386_TLS_LE_32),
+        ],
+        tls_module: None,
+        tlsdesc: None,
+        size: &[],
+        irelative: R_386_IRELATIVE,
+        copy: R_386_COPY,
+        none: None,
+    };
+
+    /// The static equivalents of TLS_DTPOFF32 and TLS_TPOFF32 are TLS_LDO32 and TLS_LE32, respectively.
+    const ARM: Self = Self {
+        relative: R_ARM_RELATIVE,
+        abs: R_ARM_ABS32,
+        glob_dat: Some(R_ARM_GLOB_DAT),
+        jump_slot: R_ARM_JUMP_SLOT,
+        tls_offsets: &[
+            (R_ARM_TLS_DTPOFF32, R_ARM_TLS_LDO32),
+            (R_ARM_TLS_TPOFF32, R_ARM_TLS_LE32),
+        ],
+        tls_module: Some((R_ARM_TLS_DTPMOD32, R_ARM_TLS_DTPOFF32)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_ARM_IRELATIVE,
+        copy: R_ARM_COPY,
+        none: None,
+    };
+
+    const AARCH64: Self = Self {
+        relative: R_AARCH64_RELATIVE,
+        abs: R_AARCH64_ABS64,
+        glob_dat: Some(R_AARCH64_GLOB_DAT),
+        jump_slot: R_AARCH64_JUMP_SLOT,
+        tls_offsets: &[
+            (R_AARCH64_TLS_DTPREL, R_AARCH64_TLS_DTPREL),
+            (R_AARCH64_TLS_TPREL, R_AARCH64_TLS_TPREL),
+        ],
+        tls_module: Some((R_AARCH64_TLS_DTPMOD, R_AARCH64_TLS_DTPREL)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_AARCH64_IRELATIVE,
+        copy: R_AARCH64_COPY,
+        none: None,
+    };
+
+    const LOONGARCH64: Self = Self {
+        relative: R_LARCH_RELATIVE,
+        abs: R_LARCH_64,
+        glob_dat: None,
+        jump_slot: R_LARCH_JUMP_SLOT,
+        tls_offsets: &[
+            (R_LARCH_TLS_DTPREL64, R_LARCH_TLS_DTPREL64),
+            (R_LARCH_TLS_TPREL64, R_LARCH_TLS_TPREL64),
+        ],
+        tls_module: Some((R_LARCH_TLS_DTPMOD64, R_LARCH_TLS_DTPREL64)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_LARCH_IRELATIVE,
+        copy: R_LARCH_COPY,
+        none: None,
+    };
+
+    const PPC64: Self = Self {
+        relative: R_PPC64_RELATIVE,
+        abs: R_PPC64_ADDR64,
+        glob_dat: Some(R_PPC64_GLOB_DAT),
+        jump_slot: R_PPC64_JMP_SLOT,
+        tls_offsets: &[
+            (R_PPC64_DTPREL64, R_PPC64_DTPREL64),
+            (R_PPC64_TPREL64, R_PPC64_TPREL64),
+        ],
+        tls_module: Some((R_PPC64_DTPMOD64, R_PPC64_DTPREL64)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_PPC64_IRELATIVE,
+        copy: R_PPC64_COPY,
+        none: None,
+    };
+
+    const RISCV64: Self = Self {
+        relative: R_RISCV_RELATIVE,
+        abs: R_RISCV_64,
+        glob_dat: None,
+        jump_slot: R_RISCV_JUMP_SLOT,
+        tls_offsets: &[
+            (R_RISCV_TLS_DTPREL64, R_RISCV_TLS_DTPREL64),
+            (R_RISCV_TLS_TPREL64, R_RISCV_TLS_TPREL64),
+        ],
+        tls_module: Some((R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_DTPREL64)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_RISCV_IRELATIVE,
+        copy: R_RISCV_COPY,
+        none: None,
+    };
+
+    /// The static equivalents of TLS_DTPOFF and TLS_TPOFF are TLS_LDO64 and TLS_LE64, respectively.
+    const S390X: Self = Self {
+        relative: R_390_RELATIVE,
+        abs: R_390_64,
+        glob_dat: Some(R_390_GLOB_DAT),
+        jump_slot: R_390_JMP_SLOT,
+        tls_offsets: &[
+            (R_390_TLS_DTPOFF, R_390_TLS_LDO64),
+            (R_390_TLS_TPOFF, R_390_TLS_LE64),
+        ],
+        tls_module: Some((R_390_TLS_DTPMOD, R_390_TLS_DTPOFF)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_390_IRELATIVE,
+        copy: R_390_COPY,
+        none: None,
+    };
+
+    /// The dynamic relocation table always starts with an R_MIPS_NONE relocation.
+    const MIPS64: Self = Self {
+        relative: R_MIPS_REL32_64,
+        abs: R_MIPS_REL32_64,
+        glob_dat: None,
+        jump_slot: R_MIPS_JUMP_SLOT,
+        tls_offsets: &[
+            (R_MIPS_TLS_DTPREL64, R_MIPS_TLS_DTPREL64),
+            (R_MIPS_TLS_TPREL64, R_MIPS_TLS_TPREL64),
+        ],
+        tls_module: Some((R_MIPS_TLS_DTPMOD64, R_MIPS_TLS_DTPREL64)),
+        tlsdesc: None,
+        size: &[],
+        irelative: R_MIPS_IRELATIVE,
+        copy: R_MIPS_COPY,
+        none: Some(R_MIPS_NONE),
+    };
+
+    /// Get the types of the dynamic relocations of the given architecture, or `None` if the architecture is not
+    /// supported.
+    pub fn of(arch: Architecture) -> Option<&'static Self> {
+        match arch {
+            Architecture::X86_64 => Some(&Self::X86_64),
+            Architecture::I386 => Some(&Self::I386),
+            Architecture::Arm => Some(&Self::ARM),
+            Architecture::Aarch64 => Some(&Self::AARCH64),
+            Architecture::LoongArch64 => Some(&Self::LOONGARCH64),
+            Architecture::PowerPc64 => Some(&Self::PPC64),
+            Architecture::Riscv64 => Some(&Self::RISCV64),
+            Architecture::S390x => Some(&Self::S390X),
+            Architecture::Mips64 => Some(&Self::MIPS64),
+            _ => None,
+        }
+    }
+
+    /// Determine whether the given relocation of the given architecture is left out of the conversion. IRELATIVE
+    /// relocations are applied by the initializers generated by GenerateIfuncInitPass.
+    fn is_left_out(&self, arch: Architecture, reloc: &Relocation) -> bool {
+        let r_type = get_elf_r_type(arch, reloc);
+        r_type == Some(self.irelative) || (self.none.is_some() && r_type == self.none)
+    }
+
+    /// Classify the given relocation of the given architecture by how it is converted.
+    fn classify(&self, arch: Architecture, reloc: &Relocation) -> DynamicRelocationClass {
+        // The object crate parses absolute relocations of any size into generic kinds.
+        let Some(r_type) = get_elf_r_type(arch, reloc) else {
+            return if reloc.kind() == RelocationKind::Absolute {
+                DynamicRelocationClass::Address
+            } else {
+                DynamicRelocationClass::Unsupported
+            };
+        };
+
+        let has_symbol = matches!(reloc.target(), RelocationTarget::Symbol(_));
+        if r_type == self.relative && !(self.relative == self.abs && has_symbol) {
+            return DynamicRelocationClass::Relative;
+        }
+        if reloc.kind() == RelocationKind::Absolute
+            || r_type == self.abs
+            || self.glob_dat == Some(r_type)
+            || r_type == self.jump_slot
+        {
+            return DynamicRelocationClass::Address;
+        }
+        if let Some(static_type) = self.get_static_tls_offset_type(r_type) {
+            return DynamicRelocationClass::TlsOffset(static_type);
+        }
+        if let Some((_, dtpoff)) = self.tls_module.filter(|(dtpmod, _)| *dtpmod == r_type) {
+            let static_dtpoff = self.get_static_tls_offset_type(dtpoff).unwrap_or(dtpoff);
+            return DynamicRelocationClass::TlsModule(static_dtpoff);
+        }
+        if let Some((_, tpoff)) = self.tlsdesc.filter(|(tlsdesc, _)| *tlsdesc == r_type) {
+            return DynamicRelocationClass::TlsDescriptor(tpoff);
+        }
+        if self.size.contains(&r_type) {
+            return DynamicRelocationClass::Size(r_type);
+        }
+        if r_type == self.copy {
+            return DynamicRelocationClass::Copy;
         }
+        DynamicRelocationClass::Unsupported
+    }
 
-        Ok(())
+    /// Get the type of the static equivalent of the given type of relocations of TLS offsets.
+    fn get_static_tls_offset_type(&self, r_type: u32) -> Option<u32> {
+        self.tls_offsets
+            .iter()
+            .find(|(dynamic_type, _)| *dynamic_type == r_type)
+            .map(|(_, static_type)| *static_type)
     }
 }
 
+/// How a dynamic relocation is converted, see [`DynamicRelocationTypes::classify`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DynamicRelocationClass {
+    /// A relocation of an address within the image, which is rebased onto the output locations.
+    Relative,
+
+    /// A relocation of the address of a symbol, which is converted into an absolute relocation.
+    Address,
+
+    /// A relocation of a TLS offset, which is converted into the static relocation of the given type.
+    TlsOffset(u32),
+
+    /// A relocation of a TLS module ID, which is resolved in place. The given type is the type of the static relocation
+    /// of the offset that follows the module ID.
+    TlsModule(u32),
+
+    /// A relocation of a TLS descriptor, whose argument is relocated by the static relocation of the given type.
+    TlsDescriptor(u32),
+
+    /// A relocation of the size of a symbol, which is kept with the given type.
+    Size(u32),
+
+    Copy,
+    Unsupported,
+}
+
 /// Errors that may occur when converting input relocations.
 #[derive(Debug, Error)]
 pub enum ConvertRelocationError {
     #[error("read ELF failed: {0:?}")]
     ReadElfError(#[from] ReadError),
 
-    #[error("unsupported architecture: {0:?}")]
+    #[error("unsupported architecture: {}", arch_to_str(*.0))]
     UnsupportedArch(Architecture),
 
-    #[error("unsupported reloc: {0:?}")]
-    UnsupportedReloc(RelocationKind),
+    #[error("cancelled while converting relocations")]
+    Cancelled(#[from] Cancelled),
+
+    #[error("unsupported relocations: {}", format_reloc_counts(.0))]
+    UnsupportedRelocs(Vec<(String, usize)>),
+
+    #[error(
+        "copy relocation against symbol \"{0}\"; copy relocations only appear in executables, so check that the input \
+         is a shared library rather than an executable"
+    )]
+    CopyReloc(String),
+
+    #[error(
+        "relocation refers to symbol \"{name}\" (symbol index {index}) that is not defined in any copied section"
+    )]
+    MissingSymbol { index: usize, name: String },
+
+    #[error("relocation {} at {addr:#x} has no target symbol", reloc_kind_to_str(*.arch, *.kind))]
+    MissingTargetSymbol {
+        addr: u64,
+        arch: Architecture,
+        kind: RelocationKind,
+    },
+
+    #[error("TLS relocation {} in a shared library without a PT_TLS segment", reloc_kind_to_str(*.0, *.1))]
+    MissingTls(Architecture, RelocationKind),
+
+    #[error("the implicit addend of the relocation at {0:#x} lies outside of the copied image")]
+    TruncatedAddend(u64),
+
+    #[error("DTPMOD relocation at {0:#x} is not followed by the offset of a tls_index pair")]
+    TruncatedTlsIndex(u64),
+
+    #[error("the dynamic section defines DT_PLTGOT but lacks {0}, which describes the layout of the MIPS GOT")]
+    MissingMipsGotTag(&'static str),
+
+    #[error("the GOT entry at {0:#x} lies outside of the copied image")]
+    TruncatedGot(u64),
+
+    #[error(
+        "the MIPS GOT at {pltgot:#x} with {local_gotno} local entries and global entries from symbol index {gotsym} to \
+         {symtabno} extends past the end of the address space"
+    )]
+    InvalidMipsGot {
+        pltgot: u64,
+        local_gotno: u64,
+        gotsym: u64,
+        symtabno: u64,
+    },
 }
 
-#[cfg(test)]
-mod test {
-    use object::read::elf::ElfFile64;
-    use object::write::Object as OutputObject;
-    use object::{Architecture, BinaryFormat, Endianness};
+/// The slots of the output sections that converted relocations are emitted for, which validates the relocations before
+/// they are added to the output.
+///
+/// Overlapping relocations leave the result of the final link undefined, and are a symptom of a bug in the conversion or
+/// of an input that lists a relocation twice. They are reported with the sources of both relocations, and are emitted
+/// anyway. So are 64-bit absolute relocations that are not aligned to 8 bytes, which packed data may hold but some
+/// linkers reject. Relocations that lie outside of the copied image or that extend past the end of their output
+/// sections would corrupt the output, so they are reported and dropped.
+#[derive(Debug)]
+pub struct RelocationSlots {
+    diagnostics: Diagnostics,
 
-    use crate::elf::pass::section::CopyLodableSectionsPass;
-    use crate::elf::pass::symbol::GenerateSymbolPass;
-    use crate::pass::test::PassTest;
-    use crate::pass::{PassHandle, PassManager};
+    /// The claimed slots of each output section, keyed by their start offsets. The end offsets of the slots and the
+    /// sources of their relocations are the values. The claimed slots don't overlap each other.
+    slots: HashMap<SectionId, BTreeMap<u64, (u64, RelocationSource)>>,
+}
 
-    use super::ConvertRelocationPass;
+impl RelocationSlots {
+    /// Create an empty set of slots that reports overlapping relocations to the given diagnostics.
+    pub fn new(diagnostics: Diagnostics) -> Self {
+        Self {
+            diagnostics,
+            slots: HashMap::new(),
+        }
+    }
 
-    struct ConvertRelocationPassTest;
+    /// Translate the address of the given source into its location in the output object, and report the source if the
+    /// address lies outside of the copied image.
+    pub fn translate(
+        &self,
+        cls_output: &CopyLodableSectionsOutput,
+        source: RelocationSource,
+    ) -> Option<OutputLocation> {
+        let loc = cls_output.translate(source.address());
+        if loc.is_none() {
+            self.diagnostics.warn(format_args!(
+                "Dropping {}, which lies outside of the copied image 0..{:#x}; no loadable section or segment covers \
+                 it, so the relocation table may be corrupted or point into sections that are missing from the input",
+                source, cls_output.output_section_size
+            ));
+        }
+        loc
+    }
 
-    impl PassTest for ConvertRelocationPassTest {
-        type Input = ElfFile64<'static>;
-        type Pass = ConvertRelocationPass;
+    /// Add the given relocation converted from the given source to the given output section. The relocation is dropped
+    /// if it extends past the end of the section, and is reported if it is misaligned or overlaps a relocation added
+    /// before.
+    pub fn add(
+        &mut self,
+        output: &mut OutputObject<'static>,
+        section_id: SectionId,
+        reloc: OutputRelocation,
+        source: RelocationSource,
+    ) {
+        let start = reloc.offset;
+        let end = start + u64::from(reloc.size.max(8)) / 8;
+        let section = output.section(section_id);
+        let section_size = section.data().len() as u64;
+        if end > section_size {
+            self.diagnostics.warn(format_args!(
+                "Dropping the relocation at offset {:#x} of output section \"{}\" converted from {}, which extends \
+                 past the end of the section at {:#x}",
+                start,
+                section.name().unwrap_or_default(),
+                source,
+                section_size
+            ));
+            return;
+        }
 
-        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
-            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
-            let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass { cls_pass });
-            pass_mgr.add_pass(ConvertRelocationPass {
-                cls_pass,
-                sym_gen_pass,
-            })
+        if reloc.kind == RelocationKind::Absolute && reloc.size == 64 && !start.is_multiple_of(8) {
+            self.diagnostics.warn(format_args!(
+                "The 64-bit relocation at offset {:#x} of output section \"{}\" converted from {} is not aligned to \
+                 8 bytes",
+                start,
+                section.name().unwrap_or_default(),
+                source
+            ));
+        }
+
+        let section_slots = self.slots.entry(section_id).or_default();
+        match section_slots.range(..end).next_back() {
+            Some((prev_start, (prev_end, prev_source))) if *prev_end > start => {
+                self.diagnostics.warn(format_args!(
+                    "Relocation at offset {:#x} of output section \"{}\" converted from {} overlaps the relocation at \
+                     offset {:#x} converted from {}",
+                    start,
+                    output.section(section_id).name().unwrap_or_default(),
+                    source,
+                    prev_start,
+                    prev_source
+                ));
+            }
+            _ => {
+                section_slots.insert(start, (end, source));
+            }
+        }
+
+        output.add_relocation(section_id, reloc).unwrap();
+    }
+}
+
+/// Where a converted relocation comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RelocationSource {
+    /// The input dynamic relocation of the given kind of the given architecture at the given address.
+    Dynamic(u64, Architecture, RelocationKind),
+
+    /// The offset in the tls_index pair of the DTPMOD relocation at the given address.
+    TlsIndexOffset(u64),
+
+    /// The MIPS GOT entry at the given address.
+    MipsGotEntry(u64),
+
+    /// The entry of the dynamic table with the given tag name, which holds the given address.
+    DynamicTag(&'static str, u64),
+
+    /// The init or fini array entry at the given address, which holds an absolute address but has no relocation.
+    UnrelocatedEntry(u64),
+}
+
+impl RelocationSource {
+    /// Create a source for the given input dynamic relocation at the given address, whose kind is kept as an ELF
+    /// relocation type if possible so that it's reported by name.
+    pub fn dynamic(arch: Architecture, addr: u64, reloc: &Relocation) -> Self {
+        Self::Dynamic(addr, arch, get_elf_reloc_kind(arch, reloc))
+    }
+
+    /// Get the input address of the source.
+    pub fn address(&self) -> u64 {
+        match *self {
+            Self::Dynamic(addr, _, _)
+            | Self::TlsIndexOffset(addr)
+            | Self::MipsGotEntry(addr)
+            | Self::DynamicTag(_, addr)
+            | Self::UnrelocatedEntry(addr) => addr,
         }
     }
+}
 
-    #[test]
-    fn test_convert_relocation_pass() {
-        let input = crate::elf::test::get_test_input_file();
-        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
-        crate::pass::test::run_pass_test(ConvertRelocationPassTest, input, output);
+impl Display for RelocationSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dynamic(addr, arch, kind) => write!(
+                f,
+                "the {} relocation at {:#x}",
+                reloc_kind_to_str(*arch, *kind),
+                addr
+            ),
+            Self::TlsIndexOffset(addr) => write!(
+                f,
+                "the offset of the tls_index pair of the DTPMOD relocation at {:#x}",
+                addr
+            ),
+            Self::MipsGotEntry(addr) => write!(f, "the MIPS GOT entry at {:#x}", addr),
+            Self::DynamicTag(tag, addr) => write!(f, "the {} entry of {:#x}", tag, addr),
+            Self::UnrelocatedEntry(addr) => write!(f, "the unrelocated entry at {:#x}", addr),
+        }
+    }
+}
+
+/// The input relocations of kinds that the conversion doesn't support. They are collected while the relocations are
+/// converted, so that all the unsupported kinds are reported together.
+#[derive(Debug)]
+struct UnsupportedRelocations {
+    arch: Architecture,
+    counts: HashMap<String, usize>,
+}
+
+impl UnsupportedRelocations {
+    fn new(arch: Architecture) -> Self {
+        Self {
+            arch,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Record the given unsupported relocation.
+    fn add(&mut self, reloc: &Relocation) {
+        let name = reloc_kind_to_str(self.arch, get_elf_reloc_kind(self.arch, reloc)).into_owned();
+        *self.counts.entry(name).or_default() += 1;
+    }
+
+    /// Fail with all the recorded kinds and their counts, if any.
+    fn check(self) -> Result<(), ConvertRelocationError> {
+        if self.counts.is_empty() {
+            return Ok(());
+        }
+
+        let mut counts: Vec<_> = self.counts.into_iter().collect();
+        counts.sort();
+        Err(ConvertRelocationError::UnsupportedRelocs(counts))
+    }
+}
+
+/// Format the given relocation names and counts, like `R_X86_64_64 (2), R_X86_64_GLOB_DAT (1)`.
+fn format_reloc_counts(counts: &[(String, usize)]) -> String {
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    counts.join(", ")
+}
+
+/// Format the given ELF relocation types of the given architecture and their counts in the order of the types.
+fn format_reloc_type_counts(
+    arch: Architecture,
+    counts: impl Iterator<Item = (u32, usize)>,
+) -> String {
+    let mut counts: Vec<_> = counts.collect();
+    counts.sort();
+    let counts: Vec<_> = counts
+        .into_iter()
+        .map(|(r_type, count)| (reloc_type_to_str(arch, r_type).into_owned(), count))
+        .collect();
+    format_reloc_counts(&counts)
+}
+
+/// Get the ELF relocation type of the given input relocation of the given architecture.
+pub fn get_elf_r_type(arch: Architecture, reloc: &Relocation) -> Option<u32> {
+    get_elf_r_type_of_kind(arch, reloc.kind(), reloc.size(), reloc.encoding())
+}
+
+/// Get the kind of the given relocation of the given architecture as an ELF relocation type, or its generic kind if it
+/// has no ELF relocation type.
+pub fn get_elf_reloc_kind(arch: Architecture, reloc: &Relocation) -> RelocationKind {
+    get_elf_r_type(arch, reloc).map_or(reloc.kind(), RelocationKind::Elf)
+}
+
+/// Get the size in bits of the slot that a dynamic relocation of the given kind relocates on the given architecture,
+/// which is the size of its converted relocation. It's the size of an address except for the kinds that relocate
+/// narrower slots, such as R_X86_64_SIZE32.
+pub fn output_reloc_size(arch: Architecture, kind: RelocationKind) -> u8 {
+    match (arch, kind) {
+        (Architecture::X86_64, RelocationKind::Elf(R_X86_64_SIZE32)) => 32,
+        _ => arch.address_size().map_or(64, |size| size.bytes() * 8),
+    }
+}
+
+/// Get the size in bits of the converted relocation of the given input relocation at the given address. The object crate
+/// parses some relocation types into generic kinds with their own sizes, such as R_X86_64_32 into 32-bit absolute
+/// relocations. Those sizes are kept, and reported if they differ from the one that [`output_reloc_size`] expects.
+fn get_output_reloc_size(
+    diagnostics: &Diagnostics,
+    arch: Architecture,
+    addr: u64,
+    input_reloc: &Relocation,
+) -> u8 {
+    let size = output_reloc_size(arch, input_reloc.kind());
+    let input_size = input_reloc.size();
+    if input_size == 0 || input_size == size {
+        return size;
+    }
+
+    diagnostics.warn(format_args!(
+        "The {} relocation at {:#x} relocates a {}-bit slot rather than a {}-bit one, and is converted into a {}-bit \
+         {:?} relocation",
+        reloc_kind_to_str(arch, get_elf_reloc_kind(arch, input_reloc)),
+        addr,
+        input_size,
+        size,
+        input_size,
+        input_reloc.kind()
+    ));
+    input_size
+}
+
+/// Get the ELF relocation type of a relocation of the given architecture, kind, size and encoding. The object crate
+/// parses some relocation types into generic kinds, which are mapped back here.
+fn get_elf_r_type_of_kind(
+    arch: Architecture,
+    kind: RelocationKind,
+    size: u8,
+    encoding: RelocationEncoding,
+) -> Option<u32> {
+    use object::elf;
+    use RelocationEncoding as Enc;
+    use RelocationKind as Kind;
+
+    if let Kind::Elf(r_type) = kind {
+        return Some(r_type);
+    }
+
+    let r_type = match (arch, kind, size, encoding) {
+        (Architecture::X86_64, Kind::Absolute, 64, _) => elf::R_X86_64_64,
+        (Architecture::X86_64, Kind::Absolute, 32, Enc::X86Signed) => elf::R_X86_64_32S,
+        (Architecture::X86_64, Kind::Absolute, 32, _) => elf::R_X86_64_32,
+        (Architecture::X86_64, Kind::Absolute, 16, _) => elf::R_X86_64_16,
+        (Architecture::X86_64, Kind::Absolute, 8, _) => elf::R_X86_64_8,
+        (Architecture::X86_64, Kind::Relative, 32, _) => elf::R_X86_64_PC32,
+        (Architecture::X86_64, Kind::Relative, 16, _) => elf::R_X86_64_PC16,
+        (Architecture::X86_64, Kind::Relative, 8, _) => elf::R_X86_64_PC8,
+        (Architecture::X86_64, Kind::Got, 32, _) => elf::R_X86_64_GOT32,
+        (Architecture::X86_64, Kind::PltRelative, 32, _) => elf::R_X86_64_PLT32,
+        (Architecture::X86_64, Kind::GotRelative, 32, _) => elf::R_X86_64_GOTPCREL,
+
+        (Architecture::I386, Kind::Absolute, 32, _) => elf::R_386_32,
+        (Architecture::I386, Kind::Absolute, 16, _) => elf::R_386_16,
+        (Architecture::I386, Kind::Absolute, 8, _) => elf::R_386_8,
+        (Architecture::I386, Kind::Relative, 32, _) => elf::R_386_PC32,
+        (Architecture::I386, Kind::Relative, 16, _) => elf::R_386_PC16,
+        (Architecture::I386, Kind::Relative, 8, _) => elf::R_386_PC8,
+        (Architecture::I386, Kind::Got, 32, _) => elf::R_386_GOT32,
+        (Architecture::I386, Kind::PltRelative, 32, _) => elf::R_386_PLT32,
+        (Architecture::I386, Kind::GotBaseOffset, 32, _) => elf::R_386_GOTOFF,
+        (Architecture::I386, Kind::GotBaseRelative, 32, _) => elf::R_386_GOTPC,
+
+        (Architecture::Aarch64, Kind::Absolute, 64, _) => elf::R_AARCH64_ABS64,
+        (Architecture::Aarch64, Kind::Absolute, 32, _) => elf::R_AARCH64_ABS32,
+        (Architecture::Aarch64, Kind::Absolute, 16, _) => elf::R_AARCH64_ABS16,
+        (Architecture::Aarch64, Kind::Relative, 64, _) => elf::R_AARCH64_PREL64,
+        (Architecture::Aarch64, Kind::Relative, 32, _) => elf::R_AARCH64_PREL32,
+        (Architecture::Aarch64, Kind::Relative, 16, _) => elf::R_AARCH64_PREL16,
+        (Architecture::Aarch64, Kind::PltRelative, 26, _) => elf::R_AARCH64_CALL26,
+
+        (Architecture::Arm, Kind::Absolute, 32, _) => elf::R_ARM_ABS32,
+
+        (Architecture::LoongArch64, Kind::Absolute, 64, _) => elf::R_LARCH_64,
+        (Architecture::LoongArch64, Kind::Absolute, 32, _) => elf::R_LARCH_32,
+        (Architecture::LoongArch64, Kind::Relative, 32, Enc::Generic) => elf::R_LARCH_32_PCREL,
+        (Architecture::LoongArch64, Kind::Relative, 16, Enc::LoongArchBranch) => elf::R_LARCH_B16,
+        (Architecture::LoongArch64, Kind::Relative, 21, Enc::LoongArchBranch) => elf::R_LARCH_B21,
+        (Architecture::LoongArch64, Kind::Relative, 26, Enc::LoongArchBranch) => elf::R_LARCH_B26,
+
+        (Architecture::PowerPc64, Kind::Absolute, 64, _) => elf::R_PPC64_ADDR64,
+        (Architecture::PowerPc64, Kind::Absolute, 32, _) => elf::R_PPC64_ADDR32,
+
+        (Architecture::Riscv64, Kind::Absolute, 64, _) => elf::R_RISCV_64,
+        (Architecture::Riscv64, Kind::Absolute, 32, _) => elf::R_RISCV_32,
+
+        (Architecture::S390x, Kind::Absolute, 64, _) => elf::R_390_64,
+        (Architecture::S390x, Kind::Absolute, 32, _) => elf::R_390_32,
+        (Architecture::S390x, Kind::Absolute, 16, _) => elf::R_390_16,
+        (Architecture::S390x, Kind::Absolute, 8, _) => elf::R_390_8,
+        (Architecture::S390x, Kind::Relative, 64, _) => elf::R_390_PC64,
+        (Architecture::S390x, Kind::Relative, 32, Enc::S390xDbl) => elf::R_390_PC32DBL,
+        (Architecture::S390x, Kind::Relative, 32, _) => elf::R_390_PC32,
+        (Architecture::S390x, Kind::Relative, 16, Enc::S390xDbl) => elf::R_390_PC16DBL,
+        (Architecture::S390x, Kind::Relative, 16, _) => elf::R_390_PC16,
+        (Architecture::S390x, Kind::PltRelative, 32, _) => elf::R_390_PLT32DBL,
+        (Architecture::S390x, Kind::PltRelative, 16, _) => elf::R_390_PLT16DBL,
+        (Architecture::S390x, Kind::Got, 64, _) => elf::R_390_GOT64,
+        (Architecture::S390x, Kind::Got, 32, _) => elf::R_390_GOT32,
+        (Architecture::S390x, Kind::Got, 16, _) => elf::R_390_GOT16,
+        (Architecture::S390x, Kind::GotRelative, 32, _) => elf::R_390_GOTENT,
+        (Architecture::S390x, Kind::GotBaseOffset, 64, _) => elf::R_390_GOTOFF64,
+        (Architecture::S390x, Kind::GotBaseOffset, 32, _) => elf::R_390_GOTOFF32,
+        (Architecture::S390x, Kind::GotBaseOffset, 16, _) => elf::R_390_GOTOFF16,
+        (Architecture::S390x, Kind::GotBaseRelative, 64, _) => elf::R_390_GOTPC,
+        (Architecture::S390x, Kind::GotBaseRelative, 32, _) => elf::R_390_GOTPCDBL,
+
+        (Architecture::Mips64, Kind::Absolute, 64, _) => elf::R_MIPS_64,
+        (Architecture::Mips64, Kind::Absolute, 32, _) => elf::R_MIPS_32,
+        (Architecture::Mips64, Kind::Absolute, 16, _) => elf::R_MIPS_16,
+
+        _ => return None,
+    };
+    Some(r_type)
+}
+
+/// Convert the RELATIVE relocations that carry explicit addends ahead of the other relocations, on several threads if
+/// the `parallel` feature is enabled.
+///
+/// They make up most of the dynamic relocations of typical shared libraries, and their conversion neither looks up
+/// symbols nor modifies the output. The result has an element for each relocation in the index, in the order of the
+/// index. Relocations that are reported or that are not RELATIVE relocations have no element, and are converted by the
+/// conversion loop, which adds all the converted relocations to the output in order.
+fn convert_relative_relocations(
+    reloc_index: &RelocationIndex,
+    cls_output: &CopyLodableSectionsOutput,
+    arch: Architecture,
+    types: &DynamicRelocationTypes,
+) -> Vec<Option<(SectionId, OutputRelocation)>> {
+    let size = output_reloc_size(arch, RelocationKind::Elf(types.relative));
+    crate::utils::parallel::map(reloc_index.as_slice(), |(input_reloc_addr, input_reloc)| {
+        if input_reloc.kind() != RelocationKind::Elf(types.relative)
+            || input_reloc.has_implicit_addend()
+            || input_reloc.size() != 0
+            || types.classify(arch, input_reloc) != DynamicRelocationClass::Relative
+        {
+            return None;
+        }
+
+        let output_reloc_loc = cls_output.translate(*input_reloc_addr)?;
+        let (symbol, addend) = cls_output.get_address_reference(input_reloc.addend() as u64);
+        let output_reloc = OutputRelocation {
+            offset: output_reloc_loc.offset,
+            size,
+            kind: RelocationKind::Absolute,
+            encoding: input_reloc.encoding(),
+            symbol,
+            addend,
+        };
+        Some((output_reloc_loc.section_id, output_reloc))
+    })
+}
+
+/// Get the output symbol corresponding to the input dynamic symbol with the given index, which is the target of the
+/// relocation at the given address. Input symbols that are not mapped to any output symbol are added as undefined
+/// symbols on the fly.
+fn get_target_symbol<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    diagnostics: &Diagnostics,
+    output: &mut OutputObject<'static>,
+    sym_map: &mut SymbolMap,
+    input_reloc_addr: u64,
+    sym_idx: SymbolIndex,
+) -> Result<SymbolId, ConvertRelocationError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let output_sym_id = match sym_map.get_output_symbol(sym_idx) {
+        Some(output_sym_id) => output_sym_id,
+        None => {
+            let (name, output_sym_id) = add_missing_symbol(input, output, sym_idx)?;
+            diagnostics.warn(format_args!(
+                "Relocation at {:#x} refers to symbol \"{}\" that is not defined in any copied section; it is \
+                 referred to as an undefined symbol",
+                input_reloc_addr,
+                demangle(&name)
+            ));
+            sym_map.insert(sym_idx, name.as_bytes(), output_sym_id);
+            output_sym_id
+        }
+    };
+
+    if let Some((_, name)) = sym_map.get_input_symbol(output_sym_id) {
+        diagnostics.trace(format_args!(
+            "Converting relocation at {:#x} against symbol \"{}\"",
+            input_reloc_addr,
+            demangle(name)
+        ));
+    }
+    Ok(output_sym_id)
+}
+
+/// Resolve the module ID in the tls_index pair of pointer-sized words at the given output location, which is the target
+/// of a DTPMOD relocation at the given input address.
+///
+/// Static linkers cannot resolve module IDs, so the output is assumed to be linked into an executable, whose module ID
+/// is always 1. The offset in the second half of the pair has no dynamic relocation if it was resolved by the link of
+/// the input shared library. It's an offset within the TLS image then, which is turned into the returned relocation of the
+/// given DTPOFF kind against the output TLS section.
+fn resolve_tls_index(
+    output: &mut OutputObject<'static>,
+    cls_output: &CopyLodableSectionsOutput,
+    loc: &OutputLocation,
+    input_reloc_addr: u64,
+    endian: impl Endian,
+    has_offset_reloc: bool,
+    dtpoff_kind: RelocationKind,
+) -> Result<Option<OutputRelocation>, ConvertRelocationError> {
+    let tls_sec_sym = get_tls_section_symbol(cls_output, output.architecture(), dtpoff_kind)?;
+    let word_size = output.architecture().address_size().unwrap().bytes() as usize;
+    let Some(tls_index) = output
+        .section_mut(loc.section_id)
+        .data_mut()
+        .get_mut(loc.offset as usize..)
+        .and_then(|data| data.get_mut(..2 * word_size))
+    else {
+        return Err(ConvertRelocationError::TruncatedTlsIndex(input_reloc_addr));
+    };
+    let (module_id, tls_offset) = tls_index.split_at_mut(word_size);
+    let tls_offset = if word_size == 4 {
+        module_id.copy_from_slice(&endian.write_u32_bytes(1));
+        endian.read_u32_bytes(tls_offset.try_into().unwrap()) as u64
+    } else {
+        module_id.copy_from_slice(&endian.write_u64_bytes(1));
+        endian.read_u64_bytes(tls_offset.try_into().unwrap())
+    };
+
+    if has_offset_reloc {
+        return Ok(None);
+    }
+
+    Ok(Some(OutputRelocation {
+        offset: loc.offset + word_size as u64,
+        size: word_size as u8 * 8,
+        kind: dtpoff_kind,
+        encoding: RelocationEncoding::Generic,
+        symbol: tls_sec_sym,
+        addend: tls_offset as i64,
+    }))
+}
+
+/// Warn about the given number of module IDs resolved by [`resolve_tls_index`].
+fn warn_resolved_tls_module_ids(diagnostics: &Diagnostics, num_dtpmod_relocs: usize) {
+    if num_dtpmod_relocs > 0 {
+        diagnostics.warn(format_args!(
+            "{} DTPMOD relocations are resolved to TLS module ID 1, which is only correct if the output is linked \
+             into an executable",
+            num_dtpmod_relocs
+        ));
+    }
+}
+
+/// Get the section symbol of the output TLS section, which TLS relocations of the given kind refer to.
+fn get_tls_section_symbol(
+    cls_output: &CopyLodableSectionsOutput,
+    arch: Architecture,
+    kind: RelocationKind,
+) -> Result<SymbolId, ConvertRelocationError> {
+    cls_output
+        .tls
+        .as_ref()
+        .map(|tls| tls.section_symbol)
+        .ok_or(ConvertRelocationError::MissingTls(arch, kind))
+}
+
+/// Read the implicit addend of the given size in bits, which is 8, 16, 32 or 64, from the given output location, and zero
+/// the location. Returns `None` if the location lies outside of the output section data.
+pub fn take_implicit_addend(
+    output: &mut OutputObject<'static>,
+    loc: &OutputLocation,
+    size: usize,
+    endian: impl Endian,
+) -> Option<i64> {
+    let slot = output
+        .section_mut(loc.section_id)
+        .data_mut()
+        .get_mut(loc.offset as usize..)?
+        .get_mut(..size / 8)?;
+
+    // Addends narrower than 64 bits are sign-extended.
+    let addend = match size {
+        8 => slot[0] as i8 as i64,
+        16 => endian.read_u16_bytes(slot.try_into().unwrap()) as i16 as i64,
+        32 => endian.read_u32_bytes(slot.try_into().unwrap()) as i32 as i64,
+        _ => endian.read_u64_bytes(slot.try_into().unwrap()) as i64,
+    };
+    slot.fill(0);
+
+    Some(addend)
+}
+
+/// Convert the entries of the primary GOT of a MIPS64 shared library, which the dynamic linker relocates without any
+/// dynamic relocation.
+///
+/// The GOT starts at DT_PLTGOT. Its first DT_MIPS_LOCAL_GOTNO entries are local entries holding addresses within the
+/// image, which are converted like RELATIVE relocations. The first entry is reserved for the lazy resolver, and so is
+/// the second one if its most significant bit is set. The remaining entries are global entries, one for each dynamic
+/// symbol starting at DT_MIPS_GOTSYM, which are converted like GLOB_DAT relocations. Secondary GOTs created by
+/// multi-GOT links are relocated by explicit REL32 relocations and need no special care.
+fn convert_mips64_got<'d, E, R>(
+    pass: &ConvertRelocationPass,
+    input: &ElfFile<'d, E, R>,
+    diagnostics: &Diagnostics,
+    output: &mut OutputObject<'static>,
+    slots: &mut RelocationSlots,
+    cls_output: &CopyLodableSectionsOutput,
+    sym_map: &mut SymbolMap,
+) -> Result<(), ConvertRelocationError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let endian = input.endian();
+    let Some((dynamic, _)) = input
+        .raw_header()
+        .sections(endian, input.data())?
+        .dynamic(endian, input.data())?
+    else {
+        return Ok(());
+    };
+
+    let mut pltgot = None;
+    let mut local_gotno = None;
+    let mut gotsym = None;
+    let mut symtabno = None;
+    for entry in dynamic {
+        let value = Some(entry.d_val(endian).into());
+        match entry.tag32(endian) {
+            Some(DT_PLTGOT) => pltgot = value,
+            Some(DT_MIPS_LOCAL_GOTNO) => local_gotno = value,
+            Some(DT_MIPS_GOTSYM) => gotsym = value,
+            Some(DT_MIPS_SYMTABNO) => symtabno = value,
+            _ => {}
+        }
+    }
+
+    let Some(pltgot) = pltgot else {
+        return Ok(());
+    };
+    let local_gotno = local_gotno.ok_or(ConvertRelocationError::MissingMipsGotTag(
+        "DT_MIPS_LOCAL_GOTNO",
+    ))?;
+    let gotsym = gotsym.ok_or(ConvertRelocationError::MissingMipsGotTag("DT_MIPS_GOTSYM"))?;
+    let symtabno = symtabno.ok_or(ConvertRelocationError::MissingMipsGotTag(
+        "DT_MIPS_SYMTABNO",
+    ))?;
+
+    // The tags are not trusted, so the end of the GOT is checked once for all the entries before it.
+    let num_entries = local_gotno
+        .checked_add(symtabno.saturating_sub(gotsym))
+        .filter(|num_entries| {
+            num_entries
+                .checked_mul(8)
+                .and_then(|size| pltgot.checked_add(size))
+                .is_some()
+        })
+        .ok_or(ConvertRelocationError::InvalidMipsGot {
+            pltgot,
+            local_gotno,
+            gotsym,
+            symtabno,
+        })?;
+    for entry_idx in 0..num_entries {
+        let entry_addr = pltgot + entry_idx * 8;
+        let loc = cls_output
+            .translate(entry_addr)
+            .ok_or(ConvertRelocationError::TruncatedGot(entry_addr))?;
+        let value = output
+            .section(loc.section_id)
+            .data()
+            .get(loc.offset as usize..)
+            .and_then(|data| data.get(..8))
+            .map(|data| endian.read_u64_bytes(data.try_into().unwrap()))
+            .ok_or(ConvertRelocationError::TruncatedGot(entry_addr))?;
+
+        if entry_idx == 0 || (entry_idx == 1 && value >> 63 != 0) {
+            continue;
+        }
+
+        let (symbol, addend) = if entry_idx < local_gotno {
+            cls_output.get_address_reference(value)
+        } else {
+            let sym_idx = SymbolIndex((gotsym + (entry_idx - local_gotno)) as usize);
+            let symbol =
+                get_target_symbol(input, diagnostics, output, sym_map, entry_addr, sym_idx)?;
+            pass.bind_address_reference(output, symbol, 0)
+        };
+
+        // The value of the entry is carried by the relocation now.
+        take_implicit_addend(output, &loc, 64, endian);
+        slots.add(
+            output,
+            loc.section_id,
+            OutputRelocation {
+                offset: loc.offset,
+                size: 64,
+                kind: RelocationKind::Absolute,
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend,
+            },
+            RelocationSource::MipsGotEntry(entry_addr),
+        );
+    }
+
+    Ok(())
+}
+
+/// Name of the output section that contains the generated TLS descriptor resolver.
+pub const TLSDESC_RESOLVER_SECTION_NAME: &str = ".text.soda.tlsdesc";
+
+/// Add the resolver of TLS descriptors that hold static TLS offsets to the output, and return the symbol that refers to
+/// it. The resolver is called with the address of the descriptor in %rax and returns the TP offset stored in the
+/// argument of the descriptor, just like `_dl_tlsdesc_return` in glibc:
+///
+/// ```text
+/// mov 8(%rax), %rax
+/// ret
+/// ```
+fn add_tlsdesc_resolver(
+    output: &mut OutputObject<'static>,
+    section_naming: &SectionNaming,
+) -> SymbolId {
+    let sec_id = output.add_section(
+        Vec::new(),
+        section_naming
+            .get(TLSDESC_RESOLVER_SECTION_NAME)
+            .as_bytes()
+            .to_vec(),
+        SectionKind::Elf(SHT_PROGBITS),
+    );
+    let sec = output.section_mut(sec_id);
+    sec.flags = SectionFlags::Elf {
+        sh_flags: (SHF_ALLOC | SHF_EXECINSTR) as u64,
+    };
+    sec.set_data(vec![0x48, 0x8b, 0x40, 0x08, 0xc3], 16);
+    output.section_symbol(sec_id)
+}
+
+/// Add a local absolute symbol of value 0, against which relocations without a target symbol are made.
+fn add_absolute_symbol(output: &mut OutputObject<'static>) -> SymbolId {
+    output.add_symbol(OutputSymbol {
+        name: Vec::new(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Null,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: OutputSymbolSection::Absolute,
+        flags: SymbolFlags::None,
+    })
+}
+
+/// Add an undefined global output symbol for the input dynamic symbol with the given index, which is not mapped to any
+/// output symbol. Return the name of the input symbol together with the added output symbol.
+fn add_missing_symbol<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    output: &mut OutputObject<'static>,
+    sym_idx: SymbolIndex,
+) -> Result<(String, SymbolId), ConvertRelocationError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let input_sym = input
+        .dynamic_symbol_table()
+        .and_then(|symtab| symtab.symbol_by_index(sym_idx).ok());
+    let Some(output_sym) = input_sym
+        .as_ref()
+        .map(create_undefined_symbol)
+        .transpose()?
+        .flatten()
+    else {
+        return Err(ConvertRelocationError::MissingSymbol {
+            index: sym_idx.0,
+            name: get_dynamic_symbol_name(input, sym_idx),
+        });
+    };
+
+    let name = String::from_utf8_lossy(&output_sym.name).into_owned();
+    Ok((name, output.add_symbol(output_sym)))
+}
+
+/// Get the demangled name of the dynamic symbol with the given index, or an empty string if the name is unavailable.
+fn get_dynamic_symbol_name<'d, E, R>(input: &ElfFile<'d, E, R>, sym_idx: SymbolIndex) -> String
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let name = input
+        .dynamic_symbol_table()
+        .and_then(|symtab| symtab.symbol_by_index(sym_idx).ok())
+        .and_then(|sym| sym.name().ok())
+        .unwrap_or_default();
+    String::from(demangle(name))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fmt::Arguments;
+    use std::sync::{Arc, Mutex};
+
+    use object::elf::{
+        DT_MIPS_GOTSYM, DT_MIPS_LOCAL_GOTNO, DT_MIPS_SYMTABNO, DT_NULL, DT_PLTGOT, R_386_32,
+        R_386_GLOB_DAT, R_386_JMP_SLOT, R_386_RELATIVE, R_386_TLS_LE, R_386_TLS_LE_32,
+        R_386_TLS_TPOFF, R_386_TLS_TPOFF32, R_390_64, R_390_GLOB_DAT, R_390_RELATIVE,
+        R_390_TLS_DTPMOD, R_390_TLS_DTPOFF, R_390_TLS_LDO64, R_390_TLS_LE64, R_390_TLS_TPOFF,
+        R_AARCH64_ABS64, R_AARCH64_GLOB_DAT, R_AARCH64_RELATIVE, R_AARCH64_TLS_DTPMOD,
+        R_AARCH64_TLS_DTPREL, R_AARCH64_TLS_TPREL, R_ARM_ABS32, R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT,
+        R_ARM_RELATIVE, R_ARM_TLS_DTPMOD32, R_ARM_TLS_DTPOFF32, R_ARM_TLS_LDO32, R_ARM_TLS_LE32,
+        R_ARM_TLS_TPOFF32, R_MIPS_64, R_MIPS_NONE, R_MIPS_REL32, R_PPC64_ADDR64, R_PPC64_DTPMOD64,
+        R_PPC64_DTPREL64, R_PPC64_GLOB_DAT, R_PPC64_RELATIVE, R_PPC64_TPREL64, R_RISCV_64,
+        R_RISCV_JUMP_SLOT, R_RISCV_RELATIVE, R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_DTPREL64,
+        R_RISCV_TLS_TPREL64, R_X86_64_64, R_X86_64_COPY, R_X86_64_DTPMOD64, R_X86_64_DTPOFF64,
+        R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT, R_X86_64_PC32, R_X86_64_RELATIVE, R_X86_64_SIZE32,
+        R_X86_64_SIZE64, R_X86_64_TLSDESC, R_X86_64_TPOFF32, R_X86_64_TPOFF64, SHN_ABS, SHN_COMMON,
+        SHN_UNDEF, SHT_DYNAMIC, SHT_PROGBITS, STB_GLOBAL, STB_LOCAL, STB_WEAK, STT_FUNC,
+        STT_GNU_IFUNC, STT_OBJECT, STT_SECTION,
+    };
+    use object::read::elf::ElfFile64;
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, ObjectSymbol as _,
+        RelocationEncoding, RelocationKind, RelocationTarget, SectionKind, SymbolKind,
+        SymbolSection,
+    };
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::elf::pass::symbol::GenerateSymbolPass;
+    use crate::elf::test::builder::BuilderSymbol;
+    use crate::output::{Object as OutputObject, Relocation as OutputRelocation};
+    use crate::pass::test::PassTest;
+    use crate::pass::{Diagnostics, Pass, PassHandle, PassManager};
+    use crate::utils::stringify::reloc_type_to_str;
+    use crate::{DiagnosticSink, Severity};
+
+    use super::{ConvertRelocationError, ConvertRelocationPass, RelocationSlots, RelocationSource};
+
+    struct ConvertRelocationPassTest;
+
+    impl PassTest for ConvertRelocationPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = ConvertRelocationPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            let sym_gen_pass =
+                pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+            pass_mgr.add_pass(ConvertRelocationPass::new(
+                cls_pass,
+                reloc_index_pass,
+                sym_gen_pass,
+            ))
+        }
+    }
+
+    struct RelocationStatsTest;
+
+    impl PassTest for RelocationStatsTest {
+        type Input = ElfFile64<'static>;
+        type Pass = ConvertRelocationPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            ConvertRelocationPassTest.setup(pass_mgr)
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let mut counts: Vec<_> = output
+                .counts
+                .iter()
+                .map(|(r_type, count)| (*r_type, *count))
+                .collect();
+            counts.sort();
+            assert_eq!(
+                counts,
+                vec![
+                    (R_X86_64_64, 970),
+                    (R_X86_64_GLOB_DAT, 177),
+                    (R_X86_64_JUMP_SLOT, 368),
+                    (R_X86_64_RELATIVE, 80),
+                    (R_X86_64_DTPMOD64, 1),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_convert_relocation_pass() {
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(RelocationStatsTest, input, output);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_unsupported_relocs() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2000, R_X86_64_PC32, 1, 0);
+        builder.add_relocation(0x2004, R_X86_64_PC32, 2, 0);
+        builder.add_relocation(0x2008, R_X86_64_TPOFF32, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        // All the unsupported types are reported at once.
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::UnsupportedRelocs(counts))
+                if counts == &[("R_X86_64_PC32".to_owned(), 2), ("R_X86_64_TPOFF32".to_owned(), 1)]
+        ));
+        assert_eq!(
+            err.error.to_string(),
+            "unsupported relocations: R_X86_64_PC32 (2), R_X86_64_TPOFF32 (1)"
+        );
+    }
+
+    #[test]
+    fn test_reloc_type_to_str() {
+        assert_eq!(
+            reloc_type_to_str(Architecture::X86_64, R_X86_64_GLOB_DAT),
+            "R_X86_64_GLOB_DAT"
+        );
+        assert_eq!(
+            reloc_type_to_str(Architecture::I386, R_386_JMP_SLOT),
+            "R_386_JMP_SLOT"
+        );
+        assert_eq!(
+            reloc_type_to_str(Architecture::Riscv64, 58),
+            "R_RISCV_IRELATIVE"
+        );
+        assert_eq!(
+            reloc_type_to_str(Architecture::Mips64, (R_MIPS_64 << 8) | R_MIPS_REL32),
+            "R_MIPS_REL32/R_MIPS_64/R_MIPS_NONE"
+        );
+        assert_eq!(
+            reloc_type_to_str(Architecture::X86_64, 0xfff),
+            "R_X86_64_0xfff"
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_versioned_target() {
+        // .data[0] refers to func@VER_1 and .data[8] refers to func@@VER_2.
+        let mut builder = crate::elf::pass::symbol::test::get_versioned_builder();
+        builder.add_relocation(0x2000, R_X86_64_64, 3, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, 1, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        // Only one global definition of func exists.
+        let global_funcs = output_file
+            .symbols()
+            .filter(|sym| sym.is_global() && sym.is_definition() && sym.name() == Ok("func"))
+            .count();
+        assert_eq!(global_funcs, 1);
+
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![
+                (0x2000, String::from("func.VER_1")),
+                (0x2008, String::from("func"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_wrap() {
+        // .data[0] calls malloc through its PLT slot and .data[8] refers to func.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let malloc = builder.add_symbol(BuilderSymbol::new(
+            "malloc", STB_GLOBAL, STT_FUNC, SHN_UNDEF, 0, 0,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_JUMP_SLOT, malloc, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, 1, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass {
+            wrap: vec![String::from("malloc"), String::from("func")],
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        pass_mgr.add_pass(ConvertRelocationPass::new(
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+        ));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![
+                (0x2000, String::from("__wrap_malloc")),
+                (0x2008, String::from("__wrap_func"))
+            ]
+        );
+
+        let find_symbol = |name: &str| {
+            output_file
+                .symbols()
+                .find(|sym| sym.name() == Ok(name))
+                .unwrap()
+        };
+        assert!(find_symbol("__wrap_malloc").is_undefined());
+        assert!(find_symbol("__wrap_func").is_undefined());
+        assert!(output_file.symbols().all(|sym| sym.name() != Ok("malloc")));
+
+        // The definition of func is still exported, and is also available as __real_func.
+        let func = find_symbol("func");
+        let real_func = find_symbol("__real_func");
+        assert!(func.is_global() && func.is_definition());
+        assert!(real_func.is_global() && real_func.is_definition());
+        assert_eq!(real_func.address(), func.address());
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_redefine_sym() {
+        // .data[0] calls malloc through its PLT slot and .data[8] refers to func.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let malloc = builder.add_symbol(BuilderSymbol::new(
+            "malloc", STB_GLOBAL, STT_FUNC, SHN_UNDEF, 0, 0,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_JUMP_SLOT, malloc, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, 1, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass {
+            redefine_syms: vec![
+                (String::from("malloc"), String::from("my_malloc")),
+                (String::from("func"), String::from("lib_func")),
+            ],
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        pass_mgr.add_pass(ConvertRelocationPass::new(
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+        ));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![
+                (0x2000, String::from("my_malloc")),
+                (0x2008, String::from("lib_func"))
+            ]
+        );
+
+        let find_symbol = |name: &str| output_file.symbols().find(|sym| sym.name() == Ok(name));
+        assert!(find_symbol("my_malloc").unwrap().is_undefined());
+        let lib_func = find_symbol("lib_func").unwrap();
+        assert!(lib_func.is_global() && lib_func.is_definition());
+        assert!(find_symbol("malloc").is_none());
+        assert!(find_symbol("func").is_none());
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_absolute_symbol() {
+        // .data[0] refers to an absolute symbol.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let stamp = builder.add_symbol(BuilderSymbol::new(
+            "version_stamp",
+            STB_GLOBAL,
+            STT_OBJECT,
+            SHN_ABS,
+            0x12345678,
+            0,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_64, stamp, 4);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stamp = output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok("version_stamp"))
+            .unwrap();
+        assert!(stamp.is_global());
+        assert_eq!(stamp.section(), SymbolSection::Absolute);
+        assert_eq!(stamp.address(), 0x12345678);
+
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![(0x2000, String::from("version_stamp"))]
+        );
+        let (_, reloc) = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .next()
+            .unwrap();
+        assert_eq!(reloc.addend(), 4);
+    }
+
+    /// Convert the relocations of an input that refers to a default, a weak and an IFUNC function and to a data object,
+    /// and get the offset, the target and the addend of each output relocation. Section symbols are given by the names
+    /// of their sections.
+    fn run_bind_locally_test(
+        bind_locally: bool,
+        only_export: Option<Vec<String>>,
+    ) -> Vec<(u64, String, i64)> {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let weak_func = builder.add_symbol(BuilderSymbol::new(
+            "weak_func",
+            STB_WEAK,
+            STT_FUNC,
+            1,
+            0x1004,
+            4,
+        ));
+        let ifunc = builder.add_symbol(BuilderSymbol::new(
+            "ifunc",
+            STB_GLOBAL,
+            STT_GNU_IFUNC,
+            1,
+            0x1008,
+            4,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x2008, R_X86_64_JUMP_SLOT, weak_func, 0);
+        builder.add_relocation(0x1000, R_X86_64_64, 2, 4);
+        builder.add_relocation(0x1008, R_X86_64_GLOB_DAT, ifunc, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let keep_exported_bound = only_export.is_some();
+        let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass {
+            only_export,
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        pass_mgr.add_pass(ConvertRelocationPass {
+            bind_locally,
+            keep_exported_bound,
+            ..ConvertRelocationPass::new(cls_pass, reloc_index_pass, sym_gen_pass)
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut relocs: Vec<_> = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = if sym.kind() == SymbolKind::Section {
+                    let section = output_file
+                        .section_by_index(sym.section_index().unwrap())
+                        .unwrap();
+                    String::from(section.name().unwrap())
+                } else {
+                    String::from(sym.name().unwrap())
+                };
+                (offset, target, reloc.addend())
+            })
+            .collect();
+        relocs.sort();
+        relocs
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_bind_locally() {
+        let reloc = |offset, target: &str, addend| (offset, String::from(target), addend);
+
+        // All relocations are bound to symbols by default.
+        assert_eq!(
+            run_bind_locally_test(false, None),
+            vec![
+                reloc(0x1000, "data", 4),
+                reloc(0x1008, "ifunc", 0),
+                reloc(0x2000, "func", 0),
+                reloc(0x2008, "weak_func", 0),
+            ]
+        );
+
+        // Default definitions are bound to the image, while weak and IFUNC ones stay interposable.
+        assert_eq!(
+            run_bind_locally_test(true, None),
+            vec![
+                reloc(0x1000, ".soda", 0x2004),
+                reloc(0x1008, "ifunc", 0),
+                reloc(0x2000, ".soda", 0x1000),
+                reloc(0x2008, "weak_func", 0),
+            ]
+        );
+
+        // Explicitly exported symbols stay interposable as well, while weak symbols that are not exported can't be
+        // interposed and are bound to the image.
+        assert_eq!(
+            run_bind_locally_test(true, Some(vec![String::from("func")])),
+            vec![
+                reloc(0x1000, ".soda", 0x2004),
+                reloc(0x1008, "ifunc", 0),
+                reloc(0x2000, "func", 0),
+                reloc(0x2008, ".soda", 0x1004),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_missing_symbol() {
+        // .data[0] and .data[8] refer to a symbol defined in a section that is not copied.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let comment = builder.add_section(".comment", SHT_PROGBITS, 0, 0, vec![0u8; 0x10]);
+        let sym = builder.add_symbol(BuilderSymbol::new(
+            "uncopied", STB_GLOBAL, STT_OBJECT, comment, 0, 0,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_GLOB_DAT, sym, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, sym, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        // Both relocations refer to the same undefined symbol.
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![
+                (0x2000, String::from("uncopied")),
+                (0x2008, String::from("uncopied"))
+            ]
+        );
+        let uncopied_syms: Vec<_> = output_file
+            .symbols()
+            .filter(|sym| sym.name() == Ok("uncopied"))
+            .collect();
+        assert_eq!(uncopied_syms.len(), 1);
+        assert!(uncopied_syms[0].is_undefined() && uncopied_syms[0].is_global());
+
+        // Local symbols cannot be referred to as undefined symbols.
+        builder.symbols[sym as usize - 1].st_info = (STB_LOCAL << 4) | STT_OBJECT;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::MissingSymbol { index, name })
+                if *index == sym as usize && name == "uncopied"
+        ));
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_common_symbol() {
+        // .data[0] refers to a common symbol of 32 bytes aligned to 16 bytes.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let common = builder.add_symbol(BuilderSymbol::new(
+            "common_buf",
+            STB_GLOBAL,
+            STT_OBJECT,
+            SHN_COMMON,
+            16,
+            32,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_64, common, 8);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let common = output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok("common_buf"))
+            .unwrap();
+        assert!(common.is_global());
+        assert_eq!(common.section(), SymbolSection::Common);
+        assert_eq!(common.address(), 16);
+        assert_eq!(common.size(), 32);
+
+        // The relocation refers to the common symbol itself.
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![(0x2000, String::from("common_buf"))]
+        );
+        let (_, reloc) = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .next()
+            .unwrap();
+        assert_eq!(reloc.addend(), 8);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_copy_reloc() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2000, R_X86_64_COPY, 2, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::CopyReloc(name)) if name == "data"
+        ));
+        assert!(err.error.to_string().contains("executable"));
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_size_reloc() {
+        // .data[0] holds the size of data plus 4 and .data[8] holds the 32-bit size of func.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2000, R_X86_64_SIZE64, 2, 4);
+        builder.add_relocation(0x2008, R_X86_64_SIZE32, 1, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(
+            get_relocation_targets(&output_file),
+            vec![
+                (0x2000, String::from("data")),
+                (0x2008, String::from("func"))
+            ]
+        );
+        let relocs: Vec<_> = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .map(|(_, reloc)| (reloc.kind(), reloc.addend()))
+            .collect();
+        assert_eq!(
+            relocs,
+            vec![
+                (RelocationKind::Elf(R_X86_64_SIZE64), 4),
+                (RelocationKind::Elf(R_X86_64_SIZE32), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_reloc_size() {
+        use object::elf::{
+            R_386_TLS_DTPMOD32, R_386_TLS_DTPOFF32, R_390_JMP_SLOT, R_390_TLS_TPOFF,
+            R_AARCH64_JUMP_SLOT, R_LARCH_64, R_LARCH_JUMP_SLOT, R_LARCH_RELATIVE,
+            R_LARCH_TLS_TPREL64, R_MIPS_REL32, R_PPC64_JMP_SLOT, R_RISCV_TLS_DTPMOD64,
+            R_X86_64_DTPMOD64,
+        };
+
+        use super::output_reloc_size;
+
+        #[rustfmt::skip]
+        let cases: &[(Architecture, &[u32], u8)] = &[
+            (Architecture::X86_64, &[
+                R_X86_64_RELATIVE, R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT, R_X86_64_DTPMOD64, R_X86_64_DTPOFF64,
+                R_X86_64_TPOFF64, R_X86_64_TLSDESC, R_X86_64_SIZE64,
+            ], 64),
+            (Architecture::X86_64, &[R_X86_64_SIZE32], 32),
+            (Architecture::I386, &[
+                R_386_RELATIVE, R_386_GLOB_DAT, R_386_JMP_SLOT, R_386_TLS_TPOFF, R_386_TLS_TPOFF32,
+                R_386_TLS_DTPMOD32, R_386_TLS_DTPOFF32,
+            ], 32),
+            (Architecture::Arm, &[
+                R_ARM_RELATIVE, R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT, R_ARM_TLS_DTPMOD32, R_ARM_TLS_DTPOFF32,
+                R_ARM_TLS_TPOFF32,
+            ], 32),
+            (Architecture::Aarch64, &[
+                R_AARCH64_RELATIVE, R_AARCH64_GLOB_DAT, R_AARCH64_JUMP_SLOT, R_AARCH64_TLS_DTPMOD,
+                R_AARCH64_TLS_DTPREL, R_AARCH64_TLS_TPREL,
+            ], 64),
+            (Architecture::LoongArch64, &[
+                R_LARCH_RELATIVE, R_LARCH_64, R_LARCH_JUMP_SLOT, R_LARCH_TLS_TPREL64,
+            ], 64),
+            (Architecture::PowerPc64, &[
+                R_PPC64_RELATIVE, R_PPC64_GLOB_DAT, R_PPC64_JMP_SLOT, R_PPC64_DTPMOD64, R_PPC64_TPREL64,
+            ], 64),
+            (Architecture::Riscv64, &[
+                R_RISCV_RELATIVE, R_RISCV_JUMP_SLOT, R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_TPREL64,
+            ], 64),
+            (Architecture::S390x, &[
+                R_390_RELATIVE, R_390_GLOB_DAT, R_390_JMP_SLOT, R_390_TLS_DTPMOD, R_390_TLS_TPOFF,
+            ], 64),
+            (Architecture::Mips64, &[R_MIPS_REL32 | (R_MIPS_64 << 8)], 64),
+        ];
+        for &(arch, r_types, size) in cases {
+            for &r_type in r_types {
+                let kind = RelocationKind::Elf(r_type);
+                assert_eq!(output_reloc_size(arch, kind), size, "{:?} {:?}", arch, kind);
+            }
+        }
+
+        // Generic absolute relocations are as wide as an address unless the input relocation says otherwise.
+        for (arch, size) in [(Architecture::X86_64, 64), (Architecture::Arm, 32)] {
+            assert_eq!(output_reloc_size(arch, RelocationKind::Absolute), size);
+        }
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_narrow_reloc() {
+        use object::elf::R_X86_64_32;
+
+        // .data[8] holds the 32-bit address of func, which the object crate parses as a 32-bit absolute relocation.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2008, R_X86_64_32, 1, 4);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+        // The first warning is about data, which may be a target of copy relocations.
+        assert_eq!(
+            sink.warnings.lock().unwrap()[1..],
+            [String::from(
+                "The R_X86_64_32 relocation at 0x2008 relocates a 32-bit slot rather than a 64-bit one, and is \
+                 converted into a 32-bit Absolute relocation"
+            )]
+        );
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let soda = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            crate::utils::dump::render_relocations(&output_file, &soda),
+            "0x2008 Absolute/32 func+0x4\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_implicit_addend() {
+        // .data[0] refers to func + 4 and .data[8] refers to data + 8, with the addends stored in the slots.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.is_rela = false;
+        crate::elf::test::write_u64(&mut builder.section_mut(2).data, 0, 0x1004);
+        crate::elf::test::write_u64(&mut builder.section_mut(2).data, 8, 8);
+        builder.add_relocation(0x2000, R_X86_64_RELATIVE, 0, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, 2, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let output_section = output_file.section_by_name(".soda").unwrap();
+
+        // The addends become explicit and the slots are zeroed.
+        let relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.addend()))
+            .collect();
+        assert_eq!(relocs, vec![(0x2000, 0x1004), (0x2008, 8)]);
+        assert_eq!(&output_section.data().unwrap()[0x2000..0x2010], &[0u8; 16]);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_tls() {
+        // .data[0..0x10] is a tls_index pair of tls_var, .data[0x10..0x20] is a tls_index pair whose offset was resolved
+        // to 0xc, .data[0x20] holds the TP offset of the image offset 8 and .data[0x28] holds the TP offset of tls_var.
+        let mut builder = crate::elf::test::builder::tls_dso();
+        builder.section_mut(2).data[0x18] = 0xc;
+        builder.add_relocation(0x2000, R_X86_64_DTPMOD64, 3, 0);
+        builder.add_relocation(0x2008, R_X86_64_DTPOFF64, 3, 0);
+        builder.add_relocation(0x2010, R_X86_64_DTPMOD64, 0, 0);
+        builder.add_relocation(0x2020, R_X86_64_TPOFF64, 0, 8);
+        builder.add_relocation(0x2028, R_X86_64_TPOFF64, 3, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        // TLS symbols keep their offsets within the TLS image.
+        let tls_var = output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok("tls_var"))
+            .unwrap();
+        assert_eq!(tls_var.kind(), SymbolKind::Tls);
+        assert_eq!(tls_var.address(), 4);
+
+        // Module IDs are resolved to 1 in place and offsets without relocations refer to the TLS section.
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let output_section_data = output_section.data().unwrap();
+        assert_eq!(&output_section_data[0x2000..0x2008], &1u64.to_le_bytes());
+        assert_eq!(&output_section_data[0x2010..0x2018], &1u64.to_le_bytes());
+
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = if sym.kind() == SymbolKind::Section {
+                    assert_eq!(sym.section_index(), Some(tls_section_index));
+                    String::from(".tdata")
+                } else {
+                    String::from(sym.name().unwrap())
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2008,
+                    RelocationKind::Elf(R_X86_64_DTPOFF64),
+                    String::from("tls_var"),
+                    0
+                ),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_X86_64_DTPOFF64),
+                    String::from(".tdata"),
+                    0xc
+                ),
+                (
+                    0x2020,
+                    RelocationKind::Elf(R_X86_64_TPOFF64),
+                    String::from(".tdata"),
+                    8
+                ),
+                (
+                    0x2028,
+                    RelocationKind::Elf(R_X86_64_TPOFF64),
+                    String::from("tls_var"),
+                    0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_tlsdesc() {
+        // .data[0..0x10] is a TLS descriptor of tls_var and .data[0x10..0x20] is a TLS descriptor of the image offset 8.
+        let mut builder = crate::elf::test::builder::tls_dso();
+        builder.add_relocation(0x2000, R_X86_64_TLSDESC, 3, 0);
+        builder.add_relocation(0x2010, R_X86_64_TLSDESC, 0, 8);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        // The resolver returns the argument of the descriptor.
+        let resolver_section = output_file.section_by_name(".text.soda.tlsdesc").unwrap();
+        assert_eq!(resolver_section.kind(), SectionKind::Text);
+        assert_eq!(
+            resolver_section.data().unwrap(),
+            &[0x48, 0x8b, 0x40, 0x08, 0xc3]
+        );
+
+        let target_name = |sym_idx| {
+            let sym = output_file.symbol_by_index(sym_idx).unwrap();
+            if sym.kind() == SymbolKind::Section {
+                let section = output_file
+                    .section_by_index(sym.section_index().unwrap())
+                    .unwrap();
+                String::from(section.name().unwrap())
+            } else {
+                String::from(sym.name().unwrap())
+            }
+        };
+        let mut relocs: Vec<_> = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                (offset, reloc.kind(), target_name(sym_idx), reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2000,
+                    RelocationKind::Absolute,
+                    String::from(".text.soda.tlsdesc"),
+                    0
+                ),
+                (
+                    0x2008,
+                    RelocationKind::Elf(R_X86_64_TPOFF64),
+                    String::from("tls_var"),
+                    0
+                ),
+                (
+                    0x2010,
+                    RelocationKind::Absolute,
+                    String::from(".text.soda.tlsdesc"),
+                    0
+                ),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_X86_64_TPOFF64),
+                    String::from(".tdata"),
+                    8
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_tls_without_tls_segment() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2000, R_X86_64_TPOFF64, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::MissingTls(
+                Architecture::X86_64,
+                RelocationKind::Elf(R_X86_64_TPOFF64)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_missing_target_symbol() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        // SIZE relocations need a symbol whose size they refer to.
+        builder.add_relocation(0x2000, R_X86_64_SIZE64, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::MissingTargetSymbol {
+                addr: 0x2000,
+                arch: Architecture::X86_64,
+                kind: RelocationKind::Elf(R_X86_64_SIZE64),
+            })
+        ));
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_non_symbol_target() {
+        // .data[0] holds an absolute value given by a relocation without a symbol, and .data[8] refers to .text[4] by
+        // the section symbol of .text.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let text_sym =
+            builder.add_symbol(BuilderSymbol::new("", STB_LOCAL, STT_SECTION, 1, 0x1000, 0));
+        builder.add_relocation(0x2000, R_X86_64_64, 0, 0x1234);
+        builder.add_relocation(0x2008, R_X86_64_64, text_sym, 4);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let relocs: Vec<_> = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                (offset, sym.section(), sym.address(), reloc.addend())
+            })
+            .collect();
+        let soda_idx = output_file.section_by_name(".soda").unwrap().index();
+        assert_eq!(
+            relocs,
+            vec![
+                (0x2000, SymbolSection::Absolute, 0, 0x1234),
+                (0x2008, SymbolSection::Section(soda_idx), 0, 0x1004),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_i386() {
+        // .data[0] refers to func + 4, .data[4] refers to data + 4, .data[8] and .data[0xc] refer to func, .data[0x10]
+        // holds the negative TP offset of tls_var and .data[0x14] holds the positive TP offset of the image offset 8.
+        // The addends are stored in the slots.
+        let mut builder = crate::elf::test::builder::tls_dso().into_i386();
+        let data = &mut builder.section_mut(2).data;
+        data[0..4].copy_from_slice(&0x1004u32.to_le_bytes());
+        data[4..8].copy_from_slice(&4u32.to_le_bytes());
+        data[0x14..0x18].copy_from_slice(&8u32.to_le_bytes());
+        builder.add_relocation(0x2000, R_386_RELATIVE, 0, 0);
+        builder.add_relocation(0x2004, R_386_32, 2, 0);
+        builder.add_relocation(0x2008, R_386_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x200c, R_386_JMP_SLOT, 1, 0);
+        builder.add_relocation(0x2010, R_386_TLS_TPOFF, 3, 0);
+        builder.add_relocation(0x2014, R_386_TLS_TPOFF32, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        pass_mgr.add_pass(ConvertRelocationPass::new(
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+        ));
+        let output = pass_mgr.run(builder.build_elf32(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert!(!output_file.is_64());
+
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                assert!(reloc.has_implicit_addend());
+                (offset, reloc.kind(), reloc.size(), target)
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (0x2000, RelocationKind::Absolute, 32, String::from(".soda")),
+                (0x2004, RelocationKind::Absolute, 32, String::from("data")),
+                (0x2008, RelocationKind::Absolute, 32, String::from("func")),
+                (0x200c, RelocationKind::Absolute, 32, String::from("func")),
+                (
+                    0x2010,
+                    RelocationKind::Elf(R_386_TLS_LE),
+                    0,
+                    String::from("tls_var")
+                ),
+                (
+                    0x2014,
+                    RelocationKind::Elf(R_386_TLS_LE_32),
+                    0,
+                    String::from(".tdata")
+                ),
+            ]
+        );
+
+        // The addends are stored in the slots of the output as well.
+        let output_section_data = output_section.data().unwrap();
+        let addends: Vec<_> = output_section_data[0x2000..0x2018]
+            .chunks(4)
+            .map(|slot| u32::from_le_bytes(slot.try_into().unwrap()))
+            .collect();
+        assert_eq!(addends, vec![0x1004, 4, 0, 0, 0, 8]);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_arm() {
+        // .data[0] refers to func + 4, .data[4] refers to data + 4, .data[8] and .data[0xc] refer to func,
+        // .data[0x10..0x18] is a tls_index pair of tls_var, .data[0x18..0x20] is a tls_index pair of the image offset 4
+        // whose offset was resolved by the link of the library, and .data[0x20] holds the TP offset of the image offset
+        // 8. The addends are stored in the slots.
+        let mut builder = crate::elf::test::builder::tls_dso().into_arm();
+        let data = &mut builder.section_mut(2).data;
+        data[0..4].copy_from_slice(&0x1004u32.to_le_bytes());
+        data[4..8].copy_from_slice(&4u32.to_le_bytes());
+        data[0x1c..0x20].copy_from_slice(&4u32.to_le_bytes());
+        data[0x20..0x24].copy_from_slice(&8u32.to_le_bytes());
+        builder.add_relocation(0x2000, R_ARM_RELATIVE, 0, 0);
+        builder.add_relocation(0x2004, R_ARM_ABS32, 2, 0);
+        builder.add_relocation(0x2008, R_ARM_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x200c, R_ARM_JUMP_SLOT, 1, 0);
+        builder.add_relocation(0x2010, R_ARM_TLS_DTPMOD32, 3, 0);
+        builder.add_relocation(0x2014, R_ARM_TLS_DTPOFF32, 3, 0);
+        builder.add_relocation(0x2018, R_ARM_TLS_DTPMOD32, 0, 0);
+        builder.add_relocation(0x2020, R_ARM_TLS_TPOFF32, 0, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        pass_mgr.add_pass(ConvertRelocationPass::new(
+            cls_pass,
+            reloc_index_pass,
+            sym_gen_pass,
+        ));
+        let output = pass_mgr.run(builder.build_elf32(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::Arm);
+
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target)
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (0x2000, RelocationKind::Absolute, String::from(".soda")),
+                (0x2004, RelocationKind::Absolute, String::from("data")),
+                (0x2008, RelocationKind::Absolute, String::from("func")),
+                (0x200c, RelocationKind::Absolute, String::from("func")),
+                (
+                    0x2014,
+                    RelocationKind::Elf(R_ARM_TLS_LDO32),
+                    String::from("tls_var")
+                ),
+                (
+                    0x201c,
+                    RelocationKind::Elf(R_ARM_TLS_LDO32),
+                    String::from(".tdata")
+                ),
+                (
+                    0x2020,
+                    RelocationKind::Elf(R_ARM_TLS_LE32),
+                    String::from(".tdata")
+                ),
+            ]
+        );
+
+        // The module IDs are resolved to 1, and the addends are stored in the slots of the output as well.
+        let output_section_data = output_section.data().unwrap();
+        let slots: Vec<_> = output_section_data[0x2000..0x2024]
+            .chunks(4)
+            .map(|slot| u32::from_le_bytes(slot.try_into().unwrap()))
+            .collect();
+        assert_eq!(slots, vec![0x1004, 4, 0, 0, 1, 0, 1, 4, 8]);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_aarch64() {
+        // .data[0] refers to func + 4, .data[8] refers to data + 8, .data[0x10..0x20] is a tls_index pair of tls_var,
+        // .data[0x20] refers to func and .data[0x28] holds the TP offset of the image offset 4.
+        let mut builder = crate::elf::test::builder::tls_dso().into_aarch64();
+        builder.add_relocation(0x2000, R_AARCH64_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x2008, R_AARCH64_ABS64, 2, 8);
+        builder.add_relocation(0x2010, R_AARCH64_TLS_DTPMOD, 3, 0);
+        builder.add_relocation(0x2018, R_AARCH64_TLS_DTPREL, 3, 0);
+        builder.add_relocation(0x2020, R_AARCH64_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x2028, R_AARCH64_TLS_TPREL, 0, 4);
+        let output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::Aarch64);
+
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            &output_section.data().unwrap()[0x2010..0x2018],
+            &1u64.to_le_bytes()
+        );
+
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2000,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1004
+                ),
+                (0x2008, RelocationKind::Absolute, String::from("data"), 8),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_AARCH64_TLS_DTPREL),
+                    String::from("tls_var"),
+                    0
+                ),
+                (0x2020, RelocationKind::Absolute, String::from("func"), 0),
+                (
+                    0x2028,
+                    RelocationKind::Elf(R_AARCH64_TLS_TPREL),
+                    String::from(".tdata"),
+                    4
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_ppc64() {
+        // .data[0] refers to func + 4, .data[8] refers to data + 8, .data[0x10..0x20] is a tls_index pair of tls_var,
+        // .data[0x20] refers to func and .data[0x28] holds the TP offset of the image offset 4.
+        let mut builder = crate::elf::test::builder::tls_dso().into_ppc64le();
+        builder.add_relocation(0x2000, R_PPC64_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x2008, R_PPC64_ADDR64, 2, 8);
+        builder.add_relocation(0x2010, R_PPC64_DTPMOD64, 3, 0);
+        builder.add_relocation(0x2018, R_PPC64_DTPREL64, 3, 0);
+        builder.add_relocation(0x2020, R_PPC64_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x2028, R_PPC64_TPREL64, 0, 4);
+        let output = OutputObject::new(
+            BinaryFormat::Elf,
+            Architecture::PowerPc64,
+            Endianness::Little,
+        );
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::PowerPc64);
+
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            &output_section.data().unwrap()[0x2010..0x2018],
+            &1u64.to_le_bytes()
+        );
+
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2000,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1004
+                ),
+                (0x2008, RelocationKind::Absolute, String::from("data"), 8),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_PPC64_DTPREL64),
+                    String::from("tls_var"),
+                    0
+                ),
+                (0x2020, RelocationKind::Absolute, String::from("func"), 0),
+                (
+                    0x2028,
+                    RelocationKind::Elf(R_PPC64_TPREL64),
+                    String::from(".tdata"),
+                    4
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_riscv64() {
+        // .data[0] refers to func + 4, .data[8] refers to data + 8, .data[0x10..0x20] is a tls_index pair of tls_var,
+        // .data[0x20] is the PLT slot of func and .data[0x28] holds the TP offset of the image offset 4.
+        let mut builder = crate::elf::test::builder::tls_dso().into_riscv64();
+        builder.add_relocation(0x2000, R_RISCV_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x2008, R_RISCV_64, 2, 8);
+        builder.add_relocation(0x2010, R_RISCV_TLS_DTPMOD64, 3, 0);
+        builder.add_relocation(0x2018, R_RISCV_TLS_DTPREL64, 3, 0);
+        builder.add_relocation(0x2020, R_RISCV_JUMP_SLOT, 1, 0);
+        builder.add_relocation(0x2028, R_RISCV_TLS_TPREL64, 0, 4);
+        let output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::Riscv64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::Riscv64);
+
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            &output_section.data().unwrap()[0x2010..0x2018],
+            &1u64.to_le_bytes()
+        );
+
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2000,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1004
+                ),
+                (0x2008, RelocationKind::Absolute, String::from("data"), 8),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_RISCV_TLS_DTPREL64),
+                    String::from("tls_var"),
+                    0
+                ),
+                (0x2020, RelocationKind::Absolute, String::from("func"), 0),
+                (
+                    0x2028,
+                    RelocationKind::Elf(R_RISCV_TLS_TPREL64),
+                    String::from(".tdata"),
+                    4
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_s390x() {
+        // .data[0] refers to func + 4, .data[8] refers to data + 8, .data[0x10..0x20] is a tls_index pair of tls_var,
+        // .data[0x20..0x30] is a tls_index pair of the image offset 4 whose offset was resolved by the link of the
+        // library, .data[0x30] refers to func and .data[0x38] holds the TP offset of the image offset 4. The slots are
+        // big-endian.
+        let mut builder = crate::elf::test::builder::tls_dso().into_s390x();
+        let data = builder.section_mut(2);
+        data.data = vec![0u8; 0x40];
+        data.data[0x28..0x30].copy_from_slice(&4u64.to_be_bytes());
+        data.sh_size = 0x40;
+        builder.segments[1].p_filesz = 0x40;
+        builder.segments[1].p_memsz = 0x40;
+        builder.add_relocation(0x2000, R_390_RELATIVE, 0, 0x1004);
+        builder.add_relocation(0x2008, R_390_64, 2, 8);
+        builder.add_relocation(0x2010, R_390_TLS_DTPMOD, 3, 0);
+        builder.add_relocation(0x2018, R_390_TLS_DTPOFF, 3, 0);
+        builder.add_relocation(0x2020, R_390_TLS_DTPMOD, 0, 0);
+        builder.add_relocation(0x2030, R_390_GLOB_DAT, 1, 0);
+        builder.add_relocation(0x2038, R_390_TLS_TPOFF, 0, 4);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::S390x, Endianness::Big);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::S390x);
+        assert!(!output_file.is_little_endian());
+
+        // The module IDs are written in big-endian.
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let output_section_data = output_section.data().unwrap();
+        assert_eq!(&output_section_data[0x2010..0x2018], &1u64.to_be_bytes());
+        assert_eq!(&output_section_data[0x2020..0x2028], &1u64.to_be_bytes());
+
+        let tls_section_index = output_file.section_by_name(".tdata").unwrap().index();
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section if sym.section_index() == Some(tls_section_index) => {
+                        String::from(".tdata")
+                    }
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2000,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1004
+                ),
+                (0x2008, RelocationKind::Absolute, String::from("data"), 8),
+                (
+                    0x2018,
+                    RelocationKind::Elf(R_390_TLS_LDO64),
+                    String::from("tls_var"),
+                    0
+                ),
+                (
+                    0x2028,
+                    RelocationKind::Elf(R_390_TLS_LDO64),
+                    String::from(".tdata"),
+                    4
+                ),
+                (0x2030, RelocationKind::Absolute, String::from("func"), 0),
+                (
+                    0x2038,
+                    RelocationKind::Elf(R_390_TLS_LE64),
+                    String::from(".tdata"),
+                    4
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_mips64el() {
+        // .data[0..0x28] is the GOT: two reserved entries, a local entry holding the address of func, and the global
+        // entries of func and data. .data[0x28] refers to the image offset 0x1004 and .data[0x30] refers to data + 8.
+        let mut builder = crate::elf::test::builder::minimal_dso().into_mips64el();
+        let data = builder.section_mut(2);
+        data.data = vec![0u8; 0x40];
+        crate::elf::test::write_u64(&mut data.data, 0x8, 0x8000_0000_0000_0000);
+        crate::elf::test::write_u64(&mut data.data, 0x10, 0x1000);
+        crate::elf::test::write_u64(&mut data.data, 0x28, 0x1004);
+        crate::elf::test::write_u64(&mut data.data, 0x30, 8);
+        data.sh_size = 0x40;
+        builder.segments[1].p_filesz = 0x40;
+        builder.segments[1].p_memsz = 0x40;
+
+        let dynamic_tags = [
+            (DT_PLTGOT, 0x2000),
+            (DT_MIPS_LOCAL_GOTNO, 3),
+            (DT_MIPS_GOTSYM, 1),
+            (DT_MIPS_SYMTABNO, 3),
+            (DT_NULL, 0),
+        ];
+        let dynamic_data = dynamic_tags
+            .iter()
+            .flat_map(|&(tag, value)| [tag as u64, value])
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+
+        let r_rel32_64 = R_MIPS_REL32 | (R_MIPS_64 << 8);
+        builder.add_relocation(0, R_MIPS_NONE, 0, 0);
+        builder.add_relocation(0x2028, r_rel32_64, 0, 0);
+        builder.add_relocation(0x2030, r_rel32_64, 2, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::Mips64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert_eq!(output_file.architecture(), Architecture::Mips64);
+
+        // The reserved entries are left alone, and the other relocated slots are zeroed.
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let output_section_data = output_section.data().unwrap();
+        assert_eq!(
+            &output_section_data[0x2008..0x2010],
+            &0x8000_0000_0000_0000u64.to_le_bytes()
+        );
+        assert!(output_section_data[0x2010..0x2040].iter().all(|b| *b == 0));
+
+        let mut relocs: Vec<_> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                let target = match sym.kind() {
+                    SymbolKind::Section => String::from(".soda"),
+                    _ => String::from(sym.name().unwrap()),
+                };
+                (offset, reloc.kind(), target, reloc.addend())
+            })
+            .collect();
+        relocs.sort_by_key(|(offset, ..)| *offset);
+        assert_eq!(
+            relocs,
+            vec![
+                (
+                    0x2010,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1000
+                ),
+                (0x2018, RelocationKind::Absolute, String::from("func"), 0),
+                (0x2020, RelocationKind::Absolute, String::from("data"), 0),
+                (
+                    0x2028,
+                    RelocationKind::Absolute,
+                    String::from(".soda"),
+                    0x1004
+                ),
+                (0x2030, RelocationKind::Absolute, String::from("data"), 8),
+            ]
+        );
+
+        // The layout of the GOT cannot be determined without all the MIPS dynamic tags.
+        let dynamic_data = &mut builder.section_mut(dynamic).data;
+        dynamic_data[0x20..0x28].copy_from_slice(&(DT_NULL as u64).to_le_bytes());
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::Mips64, Endianness::Little);
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::MissingMipsGotTag("DT_MIPS_GOTSYM"))
+        ));
+
+        // A GOT that extends past the end of the address space is rejected rather than overflowing.
+        let dynamic_data = &mut builder.section_mut(dynamic).data;
+        dynamic_data[0x20..0x28].copy_from_slice(&(DT_MIPS_GOTSYM as u64).to_le_bytes());
+        dynamic_data[0x18..0x20].copy_from_slice(&u64::MAX.to_le_bytes());
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::Mips64, Endianness::Little);
+        let mut pass_mgr = PassManager::new();
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ConvertRelocationError>(),
+            Some(ConvertRelocationError::InvalidMipsGot {
+                local_gotno: u64::MAX,
+                ..
+            })
+        ));
+    }
+
+    /// Get the offsets and the target symbol names of the relocations in the `.soda` output section, sorted by offset.
+    fn get_relocation_targets(output_file: &object::File) -> Vec<(u64, String)> {
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        let mut targets: Vec<(u64, String)> = output_section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym_idx) = reloc.target() else {
+                    panic!("unexpected relocation target");
+                };
+                let sym = output_file.symbol_by_index(sym_idx).unwrap();
+                (offset, String::from(sym.name().unwrap()))
+            })
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    /// Collects the warnings reported to it.
+    #[derive(Debug, Default)]
+    struct CollectingSink {
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl DiagnosticSink for CollectingSink {
+        fn report(&self, severity: Severity, message: Arguments<'_>) {
+            if severity == Severity::Warning {
+                self.warnings.lock().unwrap().push(message.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_relocation_slots() {
+        let mut output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let section_id = output.add_section(Vec::new(), b".soda".to_vec(), SectionKind::Data);
+        output.set_section_data(section_id, vec![0u8; 0x20], 8);
+        let symbol = output.section_symbol(section_id);
+        let reloc = |offset, size| OutputRelocation {
+            offset,
+            size,
+            kind: RelocationKind::Absolute,
+            encoding: RelocationEncoding::Generic,
+            symbol,
+            addend: 0,
+        };
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut slots = RelocationSlots::new(Diagnostics::new(Some(sink.clone())));
+        let source = |addr| {
+            RelocationSource::Dynamic(addr, Architecture::X86_64, RelocationKind::Elf(R_X86_64_64))
+        };
+        slots.add(&mut output, section_id, reloc(0, 64), source(0x1000));
+        slots.add(&mut output, section_id, reloc(8, 64), source(0x1008));
+        slots.add(&mut output, section_id, reloc(0x10, 32), source(0x1010));
+        slots.add(&mut output, section_id, reloc(0x14, 32), source(0x1014));
+        assert!(sink.warnings.lock().unwrap().is_empty());
+
+        // A relocation within a claimed slot is reported together with the relocation of the slot.
+        slots.add(
+            &mut output,
+            section_id,
+            reloc(0xc, 32),
+            RelocationSource::TlsIndexOffset(0x2000),
+        );
+        assert_eq!(
+            *sink.warnings.lock().unwrap(),
+            vec![String::from(
+                "Relocation at offset 0xc of output section \".soda\" converted from the offset of the tls_index \
+                 pair of the DTPMOD relocation at 0x2000 overlaps the relocation at offset 0x8 converted from the \
+                 R_X86_64_64 relocation at 0x1008"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_invalid_slots() {
+        // .data[4] holds a misaligned pointer, .data[0xc] holds a pointer that extends past the end of .data, and 0x3000
+        // lies outside of the image.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2004, R_X86_64_64, 1, 0);
+        builder.add_relocation(0x200c, R_X86_64_64, 2, 0);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        ConvertRelocationPassTest.setup(&mut pass_mgr);
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+        // The first warning is about data, which may be a target of copy relocations.
+        assert_eq!(
+            sink.warnings.lock().unwrap()[1..],
+            [
+                String::from(
+                    "The 64-bit relocation at offset 0x2004 of output section \".soda\" converted from the \
+                     R_X86_64_64 relocation at 0x2004 is not aligned to 8 bytes"
+                ),
+                String::from(
+                    "Dropping the relocation at offset 0x200c of output section \".soda\" converted from the \
+                     R_X86_64_64 relocation at 0x200c, which extends past the end of the section at 0x2010"
+                ),
+                String::from(
+                    "Dropping the R_X86_64_RELATIVE relocation at 0x3000, which lies outside of the copied image \
+                     0..0x2010; no loadable section or segment covers it, so the relocation table may be corrupted \
+                     or point into sections that are missing from the input"
+                ),
+            ]
+        );
+
+        // Only the misaligned relocation is emitted.
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let relocs: Vec<_> = output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .map(|(offset, _)| offset)
+            .collect();
+        assert_eq!(relocs, vec![0x2004]);
+    }
+
+    #[test]
+    fn test_convert_relocation_pass_nobits() {
+        use object::elf::{PF_R, PF_W, PT_LOAD, SHF_ALLOC, SHF_WRITE};
+
+        // A zero-initialized .bss follows .data in the same segment, and holds a pointer to func that is fixed up at
+        // load time. The segment extends past .bss into memory that no section describes, which holds another pointer.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.segments.pop();
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x2000, 0x40);
+        let bss = builder.add_nobits_section(".bss", SHF_ALLOC | SHF_WRITE, 0x2010, 0x20);
+        builder.section_mut(bss).sh_addralign = 8;
+        builder.add_relocation(0x2018, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x2020, R_X86_64_64, 1, 4);
+        builder.add_relocation(0x2038, R_X86_64_RELATIVE, 0, 0x1008);
+
+        let options = crate::ConvertOptions {
+            provenance_note: false,
+            ..Default::default()
+        };
+        let output = crate::convert_elf(&builder.build(), &options).unwrap();
+        let output_file = object::File::parse(&*output).unwrap();
+        let soda = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(soda.size(), 0x2040);
+        assert_eq!(
+            crate::utils::dump::render_relocations(&output_file, &soda),
+            "0x2018 Absolute/64 .soda+0x1000\n0x2020 Absolute/64 func+0x4\n0x2038 Absolute/64 .soda+0x1008\n"
+        );
     }
 }
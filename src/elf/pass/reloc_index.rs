@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::ops::Range;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{Object as _, ReadRef, Relocation, RelocationKind};
+
+use crate::pass::{Pass, PassContext};
+
+/// A pass that reads the dynamic relocations of the input shared library once, so that later passes look them up in
+/// the produced [`RelocationIndex`] instead of parsing the relocation sections again.
+#[derive(Debug, Default)]
+pub struct IndexRelocationsPass;
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for IndexRelocationsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "index relocations";
+
+    type Output = RelocationIndex;
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let relocs: Vec<_> = ctx
+            .input
+            .dynamic_relocations()
+            .into_iter()
+            .flatten()
+            .collect();
+
+        // The sort is stable, so relocations at the same address keep their order in the input.
+        let mut by_addr: Vec<_> = (0..relocs.len()).collect();
+        by_addr.sort_by_key(|idx| relocs[*idx].0);
+
+        ctx.diagnostics
+            .debug(format_args!("Indexed {} dynamic relocations", relocs.len()));
+
+        Ok(RelocationIndex { relocs, by_addr })
+    }
+}
+
+/// The dynamic relocations of the input shared library, together with their addresses.
+#[derive(Debug)]
+pub struct RelocationIndex {
+    /// The relocations in the order of the input.
+    relocs: Vec<(u64, Relocation)>,
+
+    /// Indexes into `relocs` sorted by the addresses of the relocations.
+    by_addr: Vec<usize>,
+}
+
+impl RelocationIndex {
+    /// Get the number of dynamic relocations.
+    pub fn len(&self) -> usize {
+        self.relocs.len()
+    }
+
+    /// Determine whether the input has no dynamic relocation.
+    pub fn is_empty(&self) -> bool {
+        self.relocs.is_empty()
+    }
+
+    /// Get the relocations and their addresses in the order of the input.
+    pub fn as_slice(&self) -> &[(u64, Relocation)] {
+        &self.relocs
+    }
+
+    /// Iterate over the relocations and their addresses in the order of the input.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &Relocation)> {
+        self.relocs.iter().map(|(addr, reloc)| (*addr, reloc))
+    }
+
+    /// Iterate over the relocations whose addresses are within the given range, in address order.
+    pub fn in_range(&self, range: Range<u64>) -> impl Iterator<Item = (u64, &Relocation)> {
+        let start = self
+            .by_addr
+            .partition_point(|idx| self.relocs[*idx].0 < range.start);
+        self.by_addr[start..]
+            .iter()
+            .map(|idx| {
+                let (addr, reloc) = &self.relocs[*idx];
+                (*addr, reloc)
+            })
+            .take_while(move |(addr, _)| *addr < range.end)
+    }
+
+    /// Get the addresses of the relocations of the given kind.
+    pub fn addrs_of_kind(&self, kind: RelocationKind) -> HashSet<u64> {
+        self.iter()
+            .filter(|(_, reloc)| reloc.kind() == kind)
+            .map(|(addr, _)| addr)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{R_X86_64_64, R_X86_64_RELATIVE};
+    use object::read::elf::ElfFile64;
+    use object::{Architecture, BinaryFormat, Endianness, RelocationKind};
+
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Pass, PassHandle, PassManager};
+
+    use super::IndexRelocationsPass;
+
+    struct IndexRelocationsPassTest;
+
+    impl PassTest for IndexRelocationsPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = IndexRelocationsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default::<IndexRelocationsPass>()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            assert_eq!(output.len(), 4);
+            assert_eq!(
+                output.iter().map(|(addr, _)| addr).collect::<Vec<_>>(),
+                vec![0x2008, 0x2000, 0x2008, 0x1000]
+            );
+
+            // Relocations at the same address keep their order in the input.
+            assert_eq!(
+                output
+                    .in_range(0x2000..0x2010)
+                    .map(|(addr, reloc)| (addr, reloc.kind()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (0x2000, RelocationKind::Absolute),
+                    (0x2008, RelocationKind::Elf(R_X86_64_RELATIVE)),
+                    (0x2008, RelocationKind::Absolute),
+                ]
+            );
+            assert_eq!(output.in_range(0x1001..0x2000).count(), 0);
+
+            assert_eq!(
+                output.addrs_of_kind(RelocationKind::Elf(R_X86_64_RELATIVE)),
+                [0x1000, 0x2008].into_iter().collect()
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_relocations_pass() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_relocation(0x2008, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x2000, R_X86_64_64, 1, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, 2, 0);
+        builder.add_relocation(0x1000, R_X86_64_RELATIVE, 0, 0x2000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(IndexRelocationsPassTest, builder.build_elf64(), output);
+    }
+}
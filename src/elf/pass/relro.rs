@@ -0,0 +1,323 @@
+use object::elf::{
+    ELFOSABI_GNU, ELFOSABI_NONE, PT_GNU_RELRO, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE,
+    SHT_INIT_ARRAY, SHT_PROGBITS,
+};
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader, ProgramHeader as _};
+use object::{
+    Architecture, Object as _, ReadRef, RelocationEncoding, RelocationKind, SectionFlags,
+    SectionKind,
+};
+use thiserror::Error;
+
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::output::{Relocation as OutputRelocation, SymbolId};
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::utils::stringify::arch_to_str;
+
+/// A pass that makes the data covered by the PT_GNU_RELRO segment of the input shared library read-only at startup.
+///
+/// The dynamic loader protects the RELRO data of a shared library once it has relocated it. The final link cannot
+/// place the copied RELRO data under the PT_GNU_RELRO segment of its own output, since code refers to the data through
+/// PC-relative addressing that carries no relocation, so the data has to stay in place within the copied image. This
+/// pass generates a constructor that calls `mprotect` on the pages of the RELRO data instead, and registers it in a
+/// `.init_array` section whose priority makes it run right after the IFUNC initializers, which may write into the data,
+/// and before all user constructors.
+///
+/// The pages of the input image are only pages of the output if the copied image is page-aligned, see
+/// [`CopyLodableSectionsPass::min_output_align`]. Like the dynamic loader, the pass leaves out the partial page at the
+/// end of the RELRO data, and the partial page at its start if other segments share it.
+#[derive(Debug)]
+pub struct ProtectRelroPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+}
+
+impl ProtectRelroPass {
+    /// The page size that the protected range is aligned to.
+    pub const PAGE_SIZE: u64 = 0x1000;
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for ProtectRelroPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "protect relro data";
+
+    type Output = ();
+    type Error = ProtectRelroError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let endian = ctx.input.endian();
+        let segments = ctx.input.raw_segments();
+        let Some(relro_seg) = segments
+            .iter()
+            .find(|seg_header| seg_header.p_type(endian) == PT_GNU_RELRO)
+        else {
+            ctx.diagnostics
+                .info(format_args!("No PT_GNU_RELRO segment to protect"));
+            return Ok(());
+        };
+
+        let arch = ctx.input.architecture();
+        if arch != Architecture::X86_64 {
+            return Err(ProtectRelroError::UnsupportedArch(arch));
+        }
+        // The constructor makes a raw system call, whose number is only that of mprotect on Linux.
+        let os_abi = ctx.input.raw_header().e_ident().os_abi;
+        if os_abi != ELFOSABI_NONE && os_abi != ELFOSABI_GNU {
+            return Err(ProtectRelroError::UnsupportedOsAbi(os_abi));
+        }
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let relro_start: u64 = relro_seg.p_vaddr(endian).into();
+        let relro_end = relro_start
+            .saturating_add(relro_seg.p_memsz(endian).into())
+            .min(cls_output.output_section_size);
+
+        // The partial page at the start is only protected if it holds nothing but the RELRO data.
+        let page_start = relro_start - relro_start % Self::PAGE_SIZE;
+        let is_page_shared = segments.iter().any(|seg_header| {
+            let seg_start: u64 = seg_header.p_vaddr(endian).into();
+            let seg_end = seg_start.saturating_add(seg_header.p_memsz(endian).into());
+            seg_header.p_type(endian) == PT_LOAD && seg_start < relro_start && seg_end > page_start
+        });
+        let start = if is_page_shared {
+            relro_start.next_multiple_of(Self::PAGE_SIZE)
+        } else {
+            page_start
+        };
+        let end = relro_end - relro_end % Self::PAGE_SIZE;
+
+        if start >= end {
+            ctx.diagnostics.warn(format_args!(
+                "The RELRO data at {:#x}..{:#x} covers no whole page and is left writable",
+                relro_start, relro_end
+            ));
+            return Ok(());
+        }
+        let size = u32::try_from(end - start)
+            .map_err(|_| ProtectRelroError::RangeTooLarge(end - start))?;
+
+        let mut output = ctx.output.borrow_mut();
+
+        let stub_sec_id = output.add_section(
+            Vec::new(),
+            ctx.section_naming
+                .get(STUB_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Elf(SHT_PROGBITS),
+        );
+        output.section_mut(stub_sec_id).flags = SectionFlags::Elf {
+            sh_flags: (SHF_ALLOC | SHF_EXECINSTR) as u64,
+        };
+        let stub_sec_sym = output.section_symbol(stub_sec_id);
+        let (code, reloc) = get_stub_x86_64(cls_output.get_address_reference(start), size);
+        output.append_section_data(stub_sec_id, &code, 16);
+        output.add_relocation(stub_sec_id, reloc).unwrap();
+
+        let init_array_sec_id = output.add_section(
+            Vec::new(),
+            ctx.section_naming
+                .get(INIT_ARRAY_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Elf(SHT_INIT_ARRAY),
+        );
+        output.section_mut(init_array_sec_id).flags = SectionFlags::Elf {
+            sh_flags: (SHF_ALLOC | SHF_WRITE) as u64,
+        };
+        output.append_section_data(init_array_sec_id, &[0u8; 8], 8);
+        output
+            .add_relocation(
+                init_array_sec_id,
+                OutputRelocation {
+                    offset: 0,
+                    size: 64,
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    symbol: stub_sec_sym,
+                    addend: 0,
+                },
+            )
+            .unwrap();
+
+        ctx.diagnostics.info(format_args!(
+            "Generated an initializer that protects the RELRO data at {:#x}..{:#x}",
+            start, end
+        ));
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when protecting the RELRO data.
+#[derive(Debug, Error)]
+pub enum ProtectRelroError {
+    #[error("protecting RELRO data is not supported on {}", arch_to_str(*.0))]
+    UnsupportedArch(Architecture),
+
+    #[error("protecting RELRO data is only supported on Linux, but the input has OS/ABI {0}")]
+    UnsupportedOsAbi(u8),
+
+    #[error("the RELRO data spans {0:#x} bytes, which is too large to protect")]
+    RangeTooLarge(u64),
+}
+
+/// Name of the output section that contains the generated constructor.
+pub const STUB_SECTION_NAME: &str = ".text.soda.relro";
+
+/// Name of the output section that refers to the generated constructor. The priority makes it run right after the IFUNC
+/// initializers in [`INIT_ARRAY_SECTION_NAME`](crate::elf::pass::ifunc::INIT_ARRAY_SECTION_NAME).
+pub const INIT_ARRAY_SECTION_NAME: &str = ".init_array.00001";
+
+/// Generate the x86_64 constructor code that protects the given number of bytes at the given page, together with the
+/// relocation relative to the beginning of the code:
+///
+/// ```text
+/// lea  start(%rip), %rdi
+/// mov  $size, %esi
+/// mov  $PROT_READ, %edx
+/// mov  $SYS_mprotect, %eax
+/// syscall
+/// ret
+/// ```
+fn get_stub_x86_64(start: (SymbolId, i64), size: u32) -> (Vec<u8>, OutputRelocation) {
+    let [s0, s1, s2, s3] = size.to_le_bytes();
+    #[rustfmt::skip]
+    let code = vec![
+        0x48, 0x8D, 0x3D, 0x00, 0x00, 0x00, 0x00, // lea  start(%rip), %rdi
+        0xBE, s0, s1, s2, s3,                     // mov  $size, %esi
+        0xBA, 0x01, 0x00, 0x00, 0x00,             // mov  $PROT_READ, %edx
+        0xB8, 0x0A, 0x00, 0x00, 0x00,             // mov  $SYS_mprotect, %eax
+        0x0F, 0x05,                               // syscall
+        0xC3,                                     // ret
+    ];
+
+    // The PC-relative displacement is relative to the end of the instruction, which is 4 bytes after the displacement
+    // field.
+    let reloc = OutputRelocation {
+        offset: 3,
+        size: 32,
+        kind: RelocationKind::Relative,
+        encoding: RelocationEncoding::Generic,
+        symbol: start.0,
+        addend: start.1 - 4,
+    };
+
+    (code, reloc)
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{ELFOSABI_FREEBSD, ELFOSABI_GNU};
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, RelocationKind,
+    };
+
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::PassManager;
+
+    use super::{ProtectRelroError, ProtectRelroPass, INIT_ARRAY_SECTION_NAME, STUB_SECTION_NAME};
+
+    #[test]
+    fn test_protect_relro_pass() {
+        // .data.rel.ro takes up the page at 0x2000.
+        let builder = crate::elf::test::builder::relro_dso();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass(CopyLodableSectionsPass {
+            min_output_align: ProtectRelroPass::PAGE_SIZE,
+            ..Default::default()
+        });
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+
+        let stub_section = output_file.section_by_name(STUB_SECTION_NAME).unwrap();
+        // The size of the range is the immediate operand of the second instruction.
+        assert_eq!(
+            &stub_section.data().unwrap()[7..12],
+            &[0xBE, 0x00, 0x10, 0x00, 0x00]
+        );
+        let stub_relocs: Vec<_> = stub_section
+            .relocations()
+            .map(|(offset, reloc)| (offset, reloc.kind(), reloc.addend()))
+            .collect();
+        assert_eq!(stub_relocs, vec![(3, RelocationKind::Relative, 0x2000 - 4)]);
+
+        let init_array_section = output_file
+            .section_by_name(INIT_ARRAY_SECTION_NAME)
+            .unwrap();
+        assert_eq!(init_array_section.size(), 8);
+        assert_eq!(init_array_section.relocations().count(), 1);
+    }
+
+    #[test]
+    fn test_protect_relro_pass_shared_page() {
+        // The RELRO data at 0x2800..0x3000 shares its page with the data before it, which leaves no whole page to
+        // protect.
+        let mut builder = crate::elf::test::builder::relro_dso();
+        let relro_seg = &mut builder.segments[2];
+        relro_seg.p_vaddr = 0x2800;
+        relro_seg.p_paddr = 0x2800;
+        relro_seg.p_memsz = 0x800;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert!(output_file.section_by_name(STUB_SECTION_NAME).is_none());
+    }
+
+    #[test]
+    fn test_protect_relro_pass_unsupported_arch() {
+        let builder = crate::elf::test::builder::relro_dso().into_aarch64();
+        let output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ProtectRelroError>(),
+            Some(ProtectRelroError::UnsupportedArch(Architecture::Aarch64))
+        ));
+    }
+
+    #[test]
+    fn test_protect_relro_pass_unsupported_os_abi() {
+        // The system call number of mprotect on Linux is that of unlink on FreeBSD.
+        let mut builder = crate::elf::test::builder::relro_dso();
+        builder.os_abi = ELFOSABI_FREEBSD;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<ProtectRelroError>(),
+            Some(ProtectRelroError::UnsupportedOsAbi(ELFOSABI_FREEBSD))
+        ));
+
+        // GNU/Linux inputs are supported.
+        let mut builder = crate::elf::test::builder::relro_dso();
+        builder.os_abi = ELFOSABI_GNU;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        pass_mgr.add_pass(ProtectRelroPass { cls_pass });
+        assert!(pass_mgr.run(builder.build_elf64(), output).is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{ReadRef, SymbolFlags, SymbolKind, SymbolScope};
+
+use crate::elf::pass::symbol::{
+    get_plain_symbol_name, GenerateSymbolError, GenerateSymbolPass, SymbolNamespace,
+};
+use crate::output::{Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that makes sure that the output has a global symbol with each of the given names, like the
+/// `--require-defined` option of ld.
+///
+/// An undefined symbol is added for each name that the input shared library neither defines nor refers to, so that the
+/// final link pulls the members that define it out of static libraries. The names are taken literally: wrapping a
+/// required symbol does not redirect it to `__wrap_NAME`. Undefined symbols of the input with the given names are never
+/// pruned, which is up to [`GenerateSymbolPass`]. The pass outputs the added symbols.
+#[derive(Debug)]
+pub struct RequireSymbolsPass {
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+
+    /// Names of the required symbols.
+    pub names: Vec<String>,
+
+    /// The namespace of global symbols shared with the conversions of other inputs that are merged into the same output.
+    pub namespace: Option<Rc<RefCell<SymbolNamespace>>>,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for RequireSymbolsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "require symbols";
+
+    type Output = Vec<SymbolId>;
+    type Error = GenerateSymbolError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let sym_map = ctx.get_pass_output(self.sym_gen_pass);
+        let mut output = ctx.output.borrow_mut();
+
+        let mut added_names = Vec::new();
+        let mut added = Vec::new();
+        for name in &self.names {
+            let exists = sym_map.iter().any(|(_, output_sym)| {
+                let sym = output.symbol(output_sym);
+                sym.scope != SymbolScope::Compilation
+                    && get_plain_symbol_name(&sym.name) == name.as_bytes()
+            });
+            if exists || added_names.contains(&name.as_str()) {
+                continue;
+            }
+
+            let symbol = OutputSymbol {
+                name: name.as_bytes().to_vec(),
+                value: 0,
+                size: 0,
+                kind: SymbolKind::Unknown,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: OutputSymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            };
+            let output_sym = match &self.namespace {
+                Some(namespace) => namespace.borrow_mut().add_symbol(&mut output, symbol)?,
+                None => output.add_symbol(symbol),
+            };
+            added_names.push(name.as_str());
+            added.push(output_sym);
+        }
+
+        if !added_names.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "Added {} required undefined symbols: {}",
+                added_names.len(),
+                added_names.join(", ")
+            ));
+        }
+
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{R_X86_64_64, STB_GLOBAL, STT_FUNC};
+    use object::{Object as _, ObjectSymbol as _};
+
+    use crate::elf::test::builder::BuilderSymbol;
+    use crate::ConvertOptions;
+
+    /// Convert an input that defines `func` and `data`, refers to `used` through a relocation and declares `unused`
+    /// without referring to it, and get the names of the undefined global output symbols.
+    fn run_require_defined_test(options: ConvertOptions) -> Vec<String> {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let used = builder.add_symbol(BuilderSymbol::new("used", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_symbol(BuilderSymbol::new("unused", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_relocation(0x2000, R_X86_64_64, used, 0);
+
+        let output = crate::convert_elf(&builder.build(), &options).unwrap();
+        let output_file = object::File::parse(&*output).unwrap();
+        let mut names: Vec<_> = output_file
+            .symbols()
+            .filter(|sym| sym.is_undefined() && sym.is_global())
+            .map(|sym| String::from(sym.name().unwrap()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_require_symbols_pass() {
+        assert_eq!(
+            run_require_defined_test(ConvertOptions::default()),
+            vec![String::from("used")]
+        );
+
+        // Required symbols are added unless the input defines them, and win over pruning. Wrapping does not apply to
+        // them.
+        let options = ConvertOptions {
+            require_defined: ["func", "used", "unused", "vendor_init", "vendor_init"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            wrap: vec![String::from("vendor_init")],
+            ..Default::default()
+        };
+        assert_eq!(
+            run_require_defined_test(options),
+            vec![
+                String::from("unused"),
+                String::from("used"),
+                String::from("vendor_init")
+            ]
+        );
+    }
+}
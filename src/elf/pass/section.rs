@@ -1,23 +1,187 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
 use std::ops::Range;
 
-use object::elf::{PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_PROGBITS};
+use object::elf::{
+    PF_X, PT_INTERP, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHF_TLS, SHF_WRITE, SHT_PROGBITS,
+};
 use object::read::elf::{
-    ElfFile, ElfSection, ElfSegment, FileHeader as ElfFileHeader, ProgramHeader as _,
+    ElfFile, ElfSection, FileHeader as ElfFileHeader, ProgramHeader as _, SectionHeader as _,
 };
 use object::read::Error as ReadError;
-use object::write::{SectionId, SymbolId};
 use object::{
-    Object, ObjectSection, ObjectSegment, ReadRef, SectionFlags, SectionIndex, SectionKind,
+    Architecture, Object, ObjectSection, ReadRef, SectionFlags, SectionIndex, SectionKind,
 };
+use thiserror::Error;
 
-use crate::pass::{Pass, PassContext};
+use crate::output::{Object as OutputObject, SectionId, SymbolId};
+use crate::pass::{Diagnostics, Pass, PassContext, SectionNaming};
+use crate::utils::stringify::section_type_to_str;
 
 /// A pass that copies loadable sections in the input shared library into the output relocatable object.
 ///
 /// All such input sections will be copied into the same section in the output relocatable object so that internal
-/// references won't break in further linking.
-#[derive(Debug, Default)]
-pub struct CopyLodableSectionsPass;
+/// references won't break in further linking. Zero-initialized sections are zero-filled, and so is the memory of the
+/// loadable segment that extends past the last section.
+#[derive(Debug)]
+pub struct CopyLodableSectionsPass {
+    /// Name of the output section that holds the copied image.
+    pub section_name: String,
+
+    /// Fill the padding bytes between copied sections in executable segments with trap instructions.
+    pub trap_padding: bool,
+
+    /// The maximum allowed ratio between the size of the output image and the size of the input file. A value of 0
+    /// disables the check.
+    pub max_image_size_factor: u64,
+
+    /// The minimum alignment of the output section. Passes that refer to the pages of the input image in the output,
+    /// such as [`ProtectRelroPass`], need it to be at least the page size.
+    ///
+    /// [`ProtectRelroPass`]: crate::elf::pass::relro::ProtectRelroPass
+    pub min_output_align: u64,
+
+    /// The maximum alignment of input sections. Larger alignments are clamped to this value.
+    pub max_section_align: u64,
+
+    /// Continue when the data of an input section is truncated in the input file. The missing bytes are zero-filled.
+    pub allow_truncated: bool,
+
+    /// Warn if the TLS image is larger than this many bytes. A value of 0 disables the warning.
+    pub tls_size_warning: u64,
+}
+
+impl CopyLodableSectionsPass {
+    /// The default value of [`Self::section_name`].
+    pub const DEFAULT_SECTION_NAME: &'static str = ".soda";
+
+    /// Name of the output section that holds the TLS initialization image.
+    pub const TLS_SECTION_NAME: &'static str = ".tdata";
+
+    /// The default value of [`Self::max_image_size_factor`].
+    pub const DEFAULT_MAX_IMAGE_SIZE_FACTOR: u64 = 16;
+
+    /// The default value of [`Self::max_section_align`].
+    pub const DEFAULT_MAX_SECTION_ALIGN: u64 = 0x10000;
+
+    /// The default value of [`Self::tls_size_warning`].
+    pub const DEFAULT_TLS_SIZE_WARNING: u64 = 4096;
+
+    fn clamp_section_align(&self, align: u64) -> u64 {
+        align.min(self.max_section_align)
+    }
+
+    /// Copy the TLS initialization image described by the PT_TLS segment of the input shared library into a `.tdata`
+    /// output section.
+    ///
+    /// The zero-initialized part of the image is included in the output section as well. Code that uses the
+    /// local-dynamic model refers to TLS variables by their offsets from the start of the image, which only stay valid
+    /// if the whole image is kept contiguous in the final link.
+    fn copy_tls_image<'d, E, R>(
+        &self,
+        input: &ElfFile<'d, E, R>,
+        output: &mut OutputObject<'static>,
+        section_naming: &SectionNaming,
+        diagnostics: &Diagnostics,
+    ) -> Result<Option<TlsOutput>, CopySectionsError>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let Some(tls_image) = crate::elf::get_tls_image(input) else {
+            return Ok(None);
+        };
+        let tls_addr = tls_image.addr;
+        let tls_size = tls_image.mem_size;
+        let tls_range = tls_image.addr_range();
+
+        let mut tls_sections: Vec<_> = input
+            .sections()
+            .filter(|sec| match sec.flags() {
+                SectionFlags::Elf { sh_flags } => sh_flags & SHF_TLS as u64 != 0,
+                _ => false,
+            })
+            .filter(|sec| is_section_in_segment(sec, &tls_range))
+            .collect();
+        tls_sections.sort_by_key(|sec| sec.address());
+
+        self.check_image_size(input, tls_size)?;
+        let tls_buffer = copy_sections_data(input, &tls_sections, tls_addr, tls_size)?;
+
+        let tls_sec_id = output.add_section(
+            Vec::new(),
+            section_naming
+                .get(Self::TLS_SECTION_NAME)
+                .as_bytes()
+                .to_vec(),
+            SectionKind::Elf(SHT_PROGBITS),
+        );
+        let tls_sec_sym = output.section_symbol(tls_sec_id);
+        let tls_sec = output.section_mut(tls_sec_id);
+        tls_sec.flags = SectionFlags::Elf {
+            sh_flags: (SHF_ALLOC | SHF_WRITE | SHF_TLS) as u64,
+        };
+        tls_sec.set_data(tls_buffer, self.clamp_section_align(tls_image.align).max(1));
+
+        diagnostics.info(format_args!(
+            "TLS image size: {} bytes, aligned to {} bytes",
+            tls_size, tls_image.align
+        ));
+        if self.tls_size_warning != 0 && tls_size > self.tls_size_warning {
+            diagnostics.warn(format_args!(
+                "The TLS image is {} bytes, more than {} bytes; it becomes part of the static TLS block that every \
+                 thread of the program reserves when it starts, instead of being allocated when first used, and \
+                 loading fails if the output ends up in a shared library that is opened with dlopen, since only a \
+                 small surplus of static TLS is kept for those",
+                tls_size,
+                self.tls_size_warning
+            ));
+        }
+
+        Ok(Some(TlsOutput {
+            section_id: tls_sec_id,
+            section_symbol: tls_sec_sym,
+            size: tls_size,
+            sections: tls_sections.iter().map(|sec| sec.index()).collect(),
+        }))
+    }
+
+    /// Refuse to allocate images that are unreasonably large compared to the input file. Such images are usually the
+    /// result of a corrupted or malicious input that claims huge section addresses.
+    fn check_image_size<'d, E, R>(
+        &self,
+        input: &ElfFile<'d, E, R>,
+        size: u64,
+    ) -> Result<(), CopySectionsError>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let mut limit = usize::MAX as u64;
+        if self.max_image_size_factor != 0 {
+            let input_size = input.data().len().unwrap_or(u64::MAX);
+            limit = limit.min(input_size.saturating_mul(self.max_image_size_factor));
+        }
+        if size > limit {
+            return Err(CopySectionsError::ImageTooLarge { size, limit });
+        }
+        Ok(())
+    }
+}
+
+impl Default for CopyLodableSectionsPass {
+    fn default() -> Self {
+        Self {
+            section_name: String::from(Self::DEFAULT_SECTION_NAME),
+            trap_padding: false,
+            max_image_size_factor: Self::DEFAULT_MAX_IMAGE_SIZE_FACTOR,
+            min_output_align: 1,
+            max_section_align: Self::DEFAULT_MAX_SECTION_ALIGN,
+            allow_truncated: false,
+            tls_size_warning: Self::DEFAULT_TLS_SIZE_WARNING,
+        }
+    }
+}
 
 impl<'d, E, R> Pass<ElfFile<'d, E, R>> for CopyLodableSectionsPass
 where
@@ -27,7 +191,7 @@ where
     const NAME: &'static str = "copy sections";
 
     type Output = CopyLodableSectionsOutput;
-    type Error = ReadError;
+    type Error = CopySectionsError;
 
     fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error>
     where
@@ -36,30 +200,35 @@ where
     {
         let mut output = ctx.output.borrow_mut();
 
-        // TODO: make the output section's name customizable.
         let output_sec_id = output.add_section(
             Vec::new(),
-            ".soda".as_bytes().to_vec(),
+            ctx.section_naming
+                .get(&self.section_name)
+                .as_bytes()
+                .to_vec(),
             SectionKind::Elf(SHT_PROGBITS),
         );
         let output_sec_sym = output.section_symbol(output_sec_id);
-        let output_sec = output.section_mut(output_sec_id);
 
         let mut ret = CopyLodableSectionsOutput {
             output_section_id: output_sec_id,
             output_section_symbol: output_sec_sym,
             output_section_size: 0,
             section_maps: Vec::new(),
+            section_maps_by_index: Vec::new(),
+            truncated_ranges: Vec::new(),
+            tls: None,
+            dispositions: Vec::new(),
         };
 
         // First we collect all loadable sections. The returned section list is sorted by their base addresses.
-        let input_sections = collect_loadable_sections(&ctx.input);
+        let input_sections =
+            collect_loadable_sections(&ctx.input, &ctx.diagnostics, &mut ret.dispositions);
         if input_sections.is_empty() {
+            ret.report_dispositions(&ctx.input, &ctx.diagnostics);
             return Ok(ret);
         }
 
-        output_sec.flags = get_output_section_flags(&input_sections);
-
         // Copy the data of the collected input sections to the output section.
         // First calculate the size and alignment of the output section, together with the offset of each input section
         // in the output section.
@@ -70,93 +239,346 @@ where
             let input_sec_addr = input_sec.address();
             let input_sec_size = input_sec.size();
             let input_sec_align = input_sec.align();
+            let input_sec_end = input_sec_addr.checked_add(input_sec_size).ok_or_else(|| {
+                CopySectionsError::SectionAddressOverflow {
+                    name: input_sec_name.to_string(),
+                    addr: input_sec_addr,
+                    size: input_sec_size,
+                }
+            })?;
+
+            if input_sec_align != 0 && !input_sec_align.is_power_of_two() {
+                return Err(CopySectionsError::InvalidSectionAlign {
+                    name: input_sec_name.into_owned(),
+                    align: input_sec_align,
+                });
+            }
+            if input_sec_align > self.max_section_align {
+                ctx.diagnostics.warn(format_args!(
+                    "Alignment {:#x} of section \"{}\" (section index {}) exceeds the limit and is clamped to {:#x}",
+                    input_sec_align,
+                    input_sec_name,
+                    input_sec.index().0,
+                    self.max_section_align
+                ));
+            }
+            let input_sec_align = self.clamp_section_align(input_sec_align);
+
+            let input_sec_file_size = input_sec.file_range().map_or(0, |(_, size)| size);
+            let input_sec_data_size = read_section_data(&ctx.input, input_sec)?.len() as u64;
+            if input_sec_data_size < input_sec_file_size {
+                if !self.allow_truncated {
+                    return Err(CopySectionsError::TruncatedSection {
+                        name: input_sec_name.into_owned(),
+                        expected: input_sec_file_size,
+                        actual: input_sec_data_size,
+                    });
+                }
+
+                let truncated_range =
+                    input_sec_addr + input_sec_data_size..input_sec_addr + input_sec_file_size;
+                ctx.diagnostics.warn(format_args!(
+                    "Section \"{}\" (section index {}) is truncated: expected {} bytes, got {} bytes; range {:#x}..{:#x} is zero-filled",
+                    input_sec_name,
+                    input_sec.index().0,
+                    input_sec_file_size,
+                    input_sec_data_size,
+                    truncated_range.start,
+                    truncated_range.end
+                ));
+                ret.truncated_ranges.push(truncated_range);
+            }
 
             if input_sec_addr < output_sec_size {
-                log::warn!(
+                ctx.diagnostics.warn(format_args!(
                     "Overlapping section \"{}\" (section index {})",
                     input_sec_name,
                     input_sec.index().0
-                );
+                ));
             }
             if input_sec_align != 0 && input_sec_addr % input_sec_align != 0 {
-                log::warn!(
+                ctx.diagnostics.warn(format_args!(
                     "Unaligned input section \"{}\" (section index {})",
                     input_sec_name,
                     input_sec.index().0
-                );
+                ));
             }
 
-            let input_sec_end = input_sec_addr.checked_add(input_sec_size).unwrap();
-            output_sec_size = input_sec_end;
+            output_sec_size = output_sec_size.max(input_sec_end);
             ret.section_maps.push(SectionMap {
                 index: input_sec.index(),
+                name: input_sec_name.into_owned(),
                 addr_range: input_sec_addr..input_sec_end,
             });
         }
+        ret.index_section_maps();
 
-        assert!(output_sec_size <= std::usize::MAX as u64);
+        // The loadable segment that holds the last section may extend past it, when the zero-initialized memory at its
+        // end is not described by any section. The memory is made part of the image as well, so that relocations that
+        // land in it are converted rather than dropped.
+        let segment_end = SegmentRanges::new(&ctx.input, PT_LOAD)
+            .get_covering_end(output_sec_size.saturating_sub(1))
+            .unwrap_or(0);
+        if segment_end > output_sec_size {
+            ctx.diagnostics.info(format_args!(
+                "The loadable segment that holds the last section extends past it, so {:#x}..{:#x} is zero-filled",
+                output_sec_size, segment_end
+            ));
+            output_sec_size = segment_end;
+        }
+
+        self.check_image_size(&ctx.input, output_sec_size)?;
         ret.output_section_size = output_sec_size;
+        ctx.diagnostics
+            .info(format_args!("Output image size: {} bytes", output_sec_size));
 
         // Calculate the alignment of the output section.
-        let output_sec_align = input_sections.iter().map(|sec| sec.align()).max().unwrap();
-
-        // Then do the data copy.
-        let mut output_buffer = vec![0u8; output_sec_size as usize];
-        for input_sec in &input_sections {
-            let sec_data = input_sec.uncompressed_data()?;
-            assert!(sec_data.len() <= input_sec.size() as usize);
-
-            if sec_data.is_empty() {
-                continue;
-            }
+        let output_sec_align = input_sections
+            .iter()
+            .map(|sec| self.clamp_section_align(sec.align()))
+            .max()
+            .unwrap_or(1)
+            .max(self.min_output_align);
 
-            let input_sec_addr = input_sec.address();
-            let output_range = input_sec_addr as usize..input_sec_addr as usize + sec_data.len();
+        // Then do the data copy. The data of each input section is appended to the output buffer in address order, and
+        // only the gaps between sections are zero-filled.
+        let mut output_buffer =
+            copy_sections_data(&ctx.input, &input_sections, 0, output_sec_size)?;
 
-            let output_slice = &mut output_buffer[output_range];
-            output_slice.copy_from_slice(&sec_data);
+        if self.trap_padding {
+            fill_exec_padding(
+                &ctx.input,
+                &ret.section_maps,
+                &mut output_buffer,
+                &ctx.diagnostics,
+            );
         }
 
         // Set the output section's data.
+        let output_sec = output.section_mut(output_sec_id);
+        output_sec.flags = get_output_section_flags(&input_sections)?;
         output_sec.set_data(output_buffer, output_sec_align);
 
+        // The TLS initialization image is copied into a separate TLS output section, so that the final link places it
+        // into the TLS segment.
+        ret.tls = self.copy_tls_image(
+            &ctx.input,
+            &mut output,
+            &ctx.section_naming,
+            &ctx.diagnostics,
+        )?;
+        if let Some(tls) = &ret.tls {
+            for (idx, disposition) in &mut ret.dispositions {
+                if tls.sections.contains(idx) {
+                    *disposition = SectionDisposition::Copied;
+                }
+            }
+        }
+        ret.report_dispositions(&ctx.input, &ctx.diagnostics);
+
         Ok(ret)
     }
 }
 
-fn collect_loadable_sections<'d, 'f, E, R>(
+/// Errors that may occur when copying loadable sections.
+#[derive(Debug, Error)]
+pub enum CopySectionsError {
+    #[error("read ELF failed: {0:?}")]
+    ReadElfError(#[from] ReadError),
+
+    #[error(
+        "output image size {size:#x} exceeds the limit {limit:#x}; the input may be corrupted"
+    )]
+    ImageTooLarge { size: u64, limit: u64 },
+
+    #[error(
+        "section \"{name}\" has invalid alignment {align:#x}; alignment must be a power of two"
+    )]
+    InvalidSectionAlign { name: String, align: u64 },
+
+    #[error(
+        "section \"{name}\" at {addr:#x} with size {size:#x} extends beyond the address space"
+    )]
+    SectionAddressOverflow { name: String, addr: u64, size: u64 },
+
+    #[error("section \"{name}\" at {addr:#x} lies outside of the image based at {base:#x}")]
+    SectionOutsideImage { name: String, addr: u64, base: u64 },
+
+    #[error("section \"{0}\" does not carry ELF section flags")]
+    InvalidSectionFlags(String),
+
+    #[error("section \"{name}\" is truncated: expected {expected} bytes, got {actual} bytes")]
+    TruncatedSection {
+        name: String,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// Copy the data of the given input sections into a buffer of the given size, in which offset 0 corresponds to the given
+/// base address.
+///
+/// The buffer is allocated once with its final size as the capacity. The data of each input section, which is borrowed
+/// from the input file where possible, is copied into the buffer in address order and only the gaps between sections and
+/// the trailing NOBITS sections are zero-filled, rather than zero-filling the whole image before the copy. Sections that
+/// extend beyond the size are cut off.
+///
+/// If the sections are disjoint, the buffer is split into the parts that each section and each gap occupy, which are
+/// filled on several threads if the `parallel` feature is enabled. Overlapping sections are copied one after another in
+/// the given order instead, so that later sections overwrite the data of earlier ones.
+fn copy_sections_data<'d, 'f, E, R>(
     input: &'f ElfFile<'d, E, R>,
-) -> Vec<ElfSection<'d, 'f, E, R>>
+    input_sections: &[ElfSection<'d, 'f, E, R>],
+    base: u64,
+    size: u64,
+) -> Result<Vec<u8>, CopySectionsError>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
 {
-    let endian = input.endian();
-    let mut input_sections = Vec::new();
+    let mut placements = Vec::with_capacity(input_sections.len());
+    for input_sec in input_sections {
+        let sec_data = read_section_data(input, input_sec)?;
 
-    for (seg_header, seg) in input.raw_segments().iter().zip(input.segments()) {
-        let seg_type = seg_header.p_type(endian);
-        if seg_type != PT_LOAD {
-            // Skip sections in non-loadable segments.
+        if sec_data.is_empty() {
             continue;
         }
 
-        // Enumerate all sections in the current segment which is loadable.
-        for input_sec in input.sections() {
-            // We don't deal with the UND section. (i.e. the section at index 0)
-            if input_sec.index().0 == 0 {
-                continue;
-            }
+        let offset = input_sec
+            .address()
+            .checked_sub(base)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .ok_or_else(|| CopySectionsError::SectionOutsideImage {
+                name: String::from_utf8_lossy(input_sec.name_bytes().unwrap_or_default())
+                    .into_owned(),
+                addr: input_sec.address(),
+                base,
+            })?;
+        placements.push((offset, sec_data));
+    }
 
-            if input_sec.address() == 0 {
-                // Input sections whose address is 0 are not included in the memory image.
-                continue;
+    // The callers have checked the size against the image size limit, which never exceeds the address space.
+    let size = size as usize;
+    let mut buffer = Vec::with_capacity(size);
+
+    let is_disjoint = placements
+        .windows(2)
+        .all(|pair| pair[0].0 + pair[0].1.len() <= pair[1].0);
+    if !is_disjoint {
+        for (offset, data) in &placements {
+            if buffer.len() < *offset {
+                buffer.resize(*offset, 0);
             }
 
-            if !is_section_in_segment(&input_sec, &seg) {
-                continue;
+            // Overlapping sections overwrite the tail of the data copied so far.
+            let overlap_size = (buffer.len() - offset).min(data.len());
+            buffer[*offset..*offset + overlap_size].copy_from_slice(&data[..overlap_size]);
+            buffer.extend_from_slice(&data[overlap_size..]);
+        }
+        buffer.resize(size, 0);
+        return Ok(buffer);
+    }
+
+    // Each part of the spare capacity is paired with the data to copy into it, or with nothing if it is a gap to be
+    // zero-filled.
+    let mut parts = Vec::with_capacity(placements.len() * 2 + 1);
+    let mut rest = &mut buffer.spare_capacity_mut()[..size];
+    let mut rest_offset = 0;
+    for (offset, data) in &placements {
+        let start = (*offset).min(size);
+        let end = offset.saturating_add(data.len()).min(size);
+        let (gap, tail) = std::mem::take(&mut rest).split_at_mut(start - rest_offset);
+        let (chunk, tail) = tail.split_at_mut(end - start);
+        parts.push((gap, None));
+        parts.push((chunk, Some(&data[..end - start])));
+        rest = tail;
+        rest_offset = end;
+    }
+    parts.push((rest, None));
+
+    crate::utils::parallel::for_each_mut(&mut parts, |(part, data)| match data {
+        Some(data) => {
+            for (dst, src) in part.iter_mut().zip(data.iter()) {
+                dst.write(*src);
+            }
+        }
+        None => {
+            for dst in part.iter_mut() {
+                dst.write(0);
             }
+        }
+    });
+
+    // SAFETY: The parts cover the first `size` bytes of the spare capacity, and each of them has been written above.
+    unsafe { buffer.set_len(size) };
+    Ok(buffer)
+}
+
+/// Read the data of the given input section.
+///
+/// If the input file is truncated in the middle of the section, only the bytes present in the input file are returned.
+fn read_section_data<'d, 'f, E, R>(
+    input: &'f ElfFile<'d, E, R>,
+    input_sec: &ElfSection<'d, 'f, E, R>,
+) -> Result<Cow<'d, [u8]>, ReadError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    if let Some((offset, size)) = input_sec.file_range() {
+        let file_size = input.data().len().unwrap_or(u64::MAX);
+        if offset.saturating_add(size) > file_size {
+            let available = file_size.saturating_sub(offset);
+            let data = input.data().read_bytes_at(offset, available).unwrap_or(&[]);
+            return Ok(Cow::Borrowed(data));
+        }
+    }
+
+    input_sec.uncompressed_data()
+}
+
+fn collect_loadable_sections<'d, 'f, E, R>(
+    input: &'f ElfFile<'d, E, R>,
+    diagnostics: &Diagnostics,
+    dispositions: &mut Vec<(SectionIndex, SectionDisposition)>,
+) -> Vec<ElfSection<'d, 'f, E, R>>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let loadable_ranges = SegmentRanges::new(input, PT_LOAD);
+    let interp_ranges = SegmentRanges::new(input, PT_INTERP);
+
+    let mut input_sections = Vec::new();
+    for input_sec in input.sections() {
+        // We don't deal with the UND section. (i.e. the section at index 0)
+        if input_sec.index().0 == 0 {
+            continue;
+        }
+
+        let disposition = if !is_section_allocatable(&input_sec) {
+            // Non-allocatable sections are not included in the memory image.
+            SectionDisposition::SkippedNotAllocatable
+        } else if interp_ranges.contains_section(&input_sec) {
+            // The program interpreter path of position-independent executables is meaningless in the output, so the
+            // section holding it is dropped and its address range is left zero-filled.
+            SectionDisposition::SkippedInterpreter
+        } else if loadable_ranges.contains_section(&input_sec) {
+            SectionDisposition::Copied
+        } else if input_sec.address() == 0 {
+            // This usually indicates that the section's sh_addr was zeroed by a broken post-processing tool.
+            diagnostics.warn(format_args!(
+                "Allocatable section \"{}\" (section index {}) has address 0 and is not covered by any loadable segment",
+                input_sec.name().unwrap_or("<unknown>"),
+                input_sec.index().0
+            ));
+            SectionDisposition::SkippedZeroAddress
+        } else {
+            SectionDisposition::SkippedNotLoadable
+        };
 
+        dispositions.push((input_sec.index(), disposition));
+        if disposition == SectionDisposition::Copied {
             input_sections.push(input_sec);
         }
     }
@@ -165,9 +587,99 @@ where
     input_sections
 }
 
+fn is_section_allocatable<'d, 'f, E, R>(sec: &ElfSection<'d, 'f, E, R>) -> bool
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    match sec.flags() {
+        SectionFlags::Elf { sh_flags } => sh_flags & SHF_ALLOC as u64 != 0,
+        _ => false,
+    }
+}
+
+/// Fill the bytes within executable segments that are not covered by any copied section with trap instructions, so
+/// that execution running past the end of a function faults instead of sliding into the next section.
+fn fill_exec_padding<'d, E, R>(
+    input: &ElfFile<'d, E, R>,
+    section_maps: &[SectionMap],
+    buffer: &mut [u8],
+    diagnostics: &Diagnostics,
+) where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let arch = input.architecture();
+    let pattern = match get_trap_pattern(arch) {
+        Some(pattern) => pattern,
+        None => {
+            diagnostics.warn(format_args!(
+                "Trap padding is not supported on architecture {}",
+                crate::utils::stringify::arch_to_str(arch)
+            ));
+            return;
+        }
+    };
+
+    let endian = input.endian();
+    for seg_header in input.raw_segments() {
+        if seg_header.p_type(endian) != PT_LOAD || seg_header.p_flags(endian) & PF_X == 0 {
+            continue;
+        }
+
+        let seg_addr: u64 = seg_header.p_vaddr(endian).into();
+        let seg_size: u64 = seg_header.p_memsz(endian).into();
+        let seg_end = seg_addr.saturating_add(seg_size).min(buffer.len() as u64);
+
+        // The section maps are sorted by address, so we can find the gaps within the segment in a single sweep.
+        let mut cursor = seg_addr;
+        for map in section_maps {
+            if map.addr_range.end <= cursor {
+                continue;
+            }
+            if map.addr_range.start >= seg_end {
+                break;
+            }
+            if map.addr_range.start > cursor {
+                fill_with_pattern(buffer, cursor..map.addr_range.start, pattern);
+            }
+            cursor = map.addr_range.end;
+        }
+
+        if cursor < seg_end {
+            fill_with_pattern(buffer, cursor..seg_end, pattern);
+        }
+    }
+}
+
+/// Get the byte pattern of a single trap instruction on the given architecture.
+fn get_trap_pattern(arch: Architecture) -> Option<&'static [u8]> {
+    match arch {
+        // int3
+        Architecture::I386 | Architecture::X86_64 | Architecture::X86_64_X32 => Some(&[0xCC]),
+        // brk #0; AArch64 instructions are always little-endian.
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => Some(&[0x00, 0x00, 0x20, 0xD4]),
+        // ebreak
+        Architecture::Riscv32 | Architecture::Riscv64 => Some(&[0x73, 0x00, 0x10, 0x00]),
+        // break 0
+        Architecture::LoongArch64 => Some(&[0x00, 0x00, 0x2A, 0x00]),
+        // An invalid instruction: opcode 0 is never assigned.
+        Architecture::S390x => Some(&[0x00, 0x00]),
+        _ => None,
+    }
+}
+
+/// Fill the given address range of the buffer with the pattern. The pattern is aligned to its own length so that
+/// multi-byte instructions start on an instruction boundary.
+fn fill_with_pattern(buffer: &mut [u8], range: Range<u64>, pattern: &[u8]) {
+    for addr in range {
+        buffer[addr as usize] = pattern[addr as usize % pattern.len()];
+    }
+}
+
 fn get_output_section_flags<'d, 'f, E, R>(
     input_sections: &[ElfSection<'d, 'f, E, R>],
-) -> SectionFlags
+) -> Result<SectionFlags, CopySectionsError>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
@@ -178,7 +690,10 @@ where
     for input_sec in input_sections {
         let sec_flags = match input_sec.flags() {
             SectionFlags::Elf { sh_flags } => sh_flags,
-            _ => unreachable!(),
+            _ => {
+                let name = String::from_utf8_lossy(input_sec.name_bytes()?).into_owned();
+                return Err(CopySectionsError::InvalidSectionFlags(name));
+            }
         };
         writable |= sec_flags & SHF_WRITE as u64 != 0;
         executable |= sec_flags & SHF_EXECINSTR as u64 != 0;
@@ -192,9 +707,9 @@ where
         raw_flags |= SHF_EXECINSTR;
     }
 
-    SectionFlags::Elf {
+    Ok(SectionFlags::Elf {
         sh_flags: raw_flags as u64,
-    }
+    })
 }
 
 #[derive(Debug)]
@@ -208,20 +723,188 @@ pub struct CopyLodableSectionsOutput {
     /// Size of the output section.
     pub output_section_size: u64,
 
-    /// Gives the information about copied sections.
+    /// Gives the information about copied sections, sorted by their addresses.
     pub section_maps: Vec<SectionMap>,
+
+    /// Section indexes and positions of the entries of `section_maps`, sorted by the section indexes. Section maps are
+    /// looked up by index for every dynamic symbol.
+    section_maps_by_index: Vec<(SectionIndex, usize)>,
+
+    /// Ranges of the input address space whose data is missing from the truncated input file and zero-filled.
+    pub truncated_ranges: Vec<Range<u64>>,
+
+    /// The output section that holds the TLS initialization image, if the input shared library has one.
+    pub tls: Option<TlsOutput>,
+
+    /// What became of each input section except for the null section, sorted by the section indexes.
+    pub dispositions: Vec<(SectionIndex, SectionDisposition)>,
 }
 
 impl CopyLodableSectionsOutput {
+    /// Get what became of the specified input section.
+    pub fn section_disposition(&self, idx: SectionIndex) -> Option<SectionDisposition> {
+        let pos = self.dispositions.partition_point(|(i, _)| i.0 < idx.0);
+        match self.dispositions.get(pos) {
+            Some((i, disposition)) if *i == idx => Some(*disposition),
+            _ => None,
+        }
+    }
+
     /// Determine whether the specified input section is copied into the output section.
     pub fn is_section_copied(&self, idx: SectionIndex) -> bool {
         self.get_section_map(idx).is_some()
+            || self
+                .tls
+                .as_ref()
+                .is_some_and(|tls| tls.sections.contains(&idx))
     }
 
-    fn get_section_map(&self, section_idx: SectionIndex) -> Option<&SectionMap> {
+    /// Translate an offset within the TLS initialization image into its location in the output TLS section. The end of
+    /// the image is translated as well.
+    pub fn translate_tls(&self, offset: u64) -> Option<OutputLocation> {
+        self.tls
+            .as_ref()
+            .filter(|tls| offset <= tls.size)
+            .map(|tls| OutputLocation {
+                section_id: tls.section_id,
+                section_symbol: tls.section_symbol,
+                offset,
+            })
+    }
+
+    /// Translate an address in the input image into its location in the output object.
+    pub fn translate(&self, addr: u64) -> Option<OutputLocation> {
+        (addr < self.output_section_size).then_some(OutputLocation {
+            section_id: self.output_section_id,
+            section_symbol: self.output_section_symbol,
+            offset: addr,
+        })
+    }
+
+    /// Find the copied input section that contains the given address in the input image.
+    pub fn find_input_section(&self, addr: u64) -> Option<SectionIndex> {
         self.section_maps
             .iter()
-            .find(|map| map.index == section_idx)
+            .find(|map| map.addr_range.contains(&addr))
+            .map(|map| map.index)
+    }
+
+    /// Translate the address of a symbol of the given size in the input image into its location in the output object.
+    ///
+    /// Zero-size symbols such as `__stop_SECTION` markers and end labels may sit at the end of the copied image, and are
+    /// translated into the end of the output section. Other symbols are translated by their first byte, see
+    /// [`Self::contains_symbol`] for the rest of them.
+    pub fn translate_symbol(&self, addr: u64, size: u64) -> Option<OutputLocation> {
+        if let Some(loc) = self.translate(addr) {
+            return Some(loc);
+        }
+        if size != 0 {
+            return None;
+        }
+
+        let mut loc = self.translate(addr.checked_sub(1)?)?;
+        loc.offset += 1;
+        Some(loc)
+    }
+
+    /// Determine whether a symbol of the given nonzero size at the given address in the input image lies entirely within
+    /// the copied image, so that all of its bytes are present in the output.
+    pub fn contains_symbol(&self, addr: u64, size: u64) -> bool {
+        addr.checked_add(size)
+            .is_some_and(|end| end <= self.output_section_size)
+    }
+
+    /// Get the output symbol and addend that together refer to the given address in the input image.
+    ///
+    /// Addresses that cannot be translated are referenced relative to the main output section.
+    pub fn get_address_reference(&self, addr: u64) -> (SymbolId, i64) {
+        match self.translate(addr) {
+            Some(loc) => (loc.section_symbol, loc.offset as i64),
+            None => (self.output_section_symbol, addr as i64),
+        }
+    }
+
+    fn get_section_map(&self, section_idx: SectionIndex) -> Option<&SectionMap> {
+        let pos = self
+            .section_maps_by_index
+            .partition_point(|(idx, _)| idx.0 < section_idx.0);
+        match self.section_maps_by_index.get(pos) {
+            Some((idx, pos)) if *idx == section_idx => Some(&self.section_maps[*pos]),
+            _ => None,
+        }
+    }
+
+    /// Report what became of each input section, so that missing symbols can be traced back to their sections.
+    fn report_dispositions<'d, E, R>(&self, input: &ElfFile<'d, E, R>, diagnostics: &Diagnostics)
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let endian = input.endian();
+        let sections = input.raw_header().sections(endian, input.data()).ok();
+        for (idx, disposition) in &self.dispositions {
+            let name = input
+                .section_by_index(*idx)
+                .and_then(|sec| sec.name().map(String::from))
+                .unwrap_or_else(|_| String::from("<unknown>"));
+            let sh_type = sections
+                .as_ref()
+                .and_then(|sections| sections.section(*idx).ok())
+                .map_or(Cow::Borrowed("<unknown>"), |section| {
+                    section_type_to_str(section.sh_type(endian))
+                });
+            diagnostics.debug(format_args!(
+                "Section \"{}\" (section index {}, {}): {}",
+                name, idx.0, sh_type, disposition
+            ));
+        }
+    }
+
+    /// Index the section maps by their section indexes. Called once all the section maps are added.
+    fn index_section_maps(&mut self) {
+        // The sort is stable, so the first of several maps of the same section wins like in a linear search.
+        let mut by_index: Vec<_> = self
+            .section_maps
+            .iter()
+            .enumerate()
+            .map(|(pos, map)| (map.index, pos))
+            .collect();
+        by_index.sort_by_key(|(idx, _)| idx.0);
+        self.section_maps_by_index = by_index;
+    }
+}
+
+/// What became of an input section.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SectionDisposition {
+    /// The section is copied into the output.
+    Copied,
+
+    /// The section is skipped because it is not allocatable, so it is not part of the memory image.
+    SkippedNotAllocatable,
+
+    /// The section is skipped because it holds the program interpreter path.
+    SkippedInterpreter,
+
+    /// The section is skipped because it is not covered by any loadable segment.
+    SkippedNotLoadable,
+
+    /// The section is skipped because it has address 0 and is not covered by any loadable segment, which usually means
+    /// that its address was zeroed by a broken post-processing tool.
+    SkippedZeroAddress,
+}
+
+impl Display for SectionDisposition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Copied => "copied",
+            Self::SkippedNotAllocatable => "skipped because it is not allocatable",
+            Self::SkippedInterpreter => "skipped because it holds the program interpreter path",
+            Self::SkippedNotLoadable => "skipped because it is not covered by any loadable segment",
+            Self::SkippedZeroAddress => {
+                "skipped because it has address 0 and is not covered by any loadable segment"
+            }
+        })
     }
 }
 
@@ -229,111 +912,950 @@ impl CopyLodableSectionsOutput {
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub struct SectionMap {
     pub index: SectionIndex,
+
+    /// Name of the input section.
+    pub name: String,
+
     pub addr_range: Range<u64>,
 }
 
+/// The output section that holds the TLS initialization image of the input shared library.
+#[derive(Clone, Debug)]
+pub struct TlsOutput {
+    /// The ID of the output TLS section.
+    pub section_id: SectionId,
+
+    /// The ID of the output TLS section symbol.
+    pub section_symbol: SymbolId,
+
+    /// Size of the TLS initialization image, including the zero-initialized part.
+    pub size: u64,
+
+    /// Indexes of the input TLS sections.
+    pub sections: Vec<SectionIndex>,
+}
+
+/// The location of an input address in the output object.
+#[derive(Clone, Copy, Debug)]
+pub struct OutputLocation {
+    /// The ID of the output section containing the location.
+    pub section_id: SectionId,
+
+    /// The ID of the output section symbol.
+    pub section_symbol: SymbolId,
+
+    /// Offset of the location within the output section.
+    pub offset: u64,
+}
+
 fn is_section_in_segment<'d, 'f, E, R>(
     sec: &ElfSection<'d, 'f, E, R>,
-    seg: &ElfSegment<'d, 'f, E, R>,
+    seg_range: &Range<u64>,
 ) -> bool
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let sec_range = get_section_range(sec);
+    sec_range.start >= seg_range.start && sec_range.end <= seg_range.end
+}
+
+/// Get the address range of the given section.
+///
+/// Segment ranges are saturated at the end of the address space, and so are section ranges, so that sections that wrap
+/// around are still recognized and rejected later.
+fn get_section_range<'d, 'f, E, R>(sec: &ElfSection<'d, 'f, E, R>) -> Range<u64>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
 {
     let sec_addr = sec.address();
-    let seg_addr = seg.address();
+    sec_addr..sec_addr.saturating_add(sec.size())
+}
+
+/// The address ranges of the segments of some type, which tell whether a section is covered by one of the segments in
+/// logarithmic time.
+struct SegmentRanges {
+    /// Start addresses of the segments in ascending order.
+    starts: Vec<u64>,
+
+    /// The greatest end address among the segments that start at or before the start address of the same index.
+    max_ends: Vec<u64>,
+}
+
+impl SegmentRanges {
+    fn new<'d, E, R>(input: &ElfFile<'d, E, R>, p_type: u32) -> Self
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let endian = input.endian();
+        let mut ranges: Vec<_> = input
+            .raw_segments()
+            .iter()
+            .filter(|seg_header| seg_header.p_type(endian) == p_type)
+            .map(|seg_header| {
+                let addr: u64 = seg_header.p_vaddr(endian).into();
+                let size: u64 = seg_header.p_memsz(endian).into();
+                addr..addr.saturating_add(size)
+            })
+            .collect();
+        ranges.sort_by_key(|range| range.start);
 
-    let sec_size = sec.size();
-    let seg_size = seg.size();
+        let starts = ranges.iter().map(|range| range.start).collect();
+        let max_ends = ranges
+            .iter()
+            .scan(0, |max_end, range| {
+                *max_end = range.end.max(*max_end);
+                Some(*max_end)
+            })
+            .collect();
+        Self { starts, max_ends }
+    }
 
-    let sec_end_addr = sec_addr + sec_size;
-    let seg_end_addr = seg_addr + seg_size;
+    /// Determine whether the given section is entirely covered by one of the segments.
+    fn contains_section<'d, 'f, E, R>(&self, sec: &ElfSection<'d, 'f, E, R>) -> bool
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        // Some segment covers the section if and only if the segments that start at or before the section reach its
+        // end.
+        let sec_range = get_section_range(sec);
+        let count = self
+            .starts
+            .partition_point(|start| *start <= sec_range.start);
+        count > 0 && self.max_ends[count - 1] >= sec_range.end
+    }
 
-    sec_addr >= seg_addr && sec_end_addr <= seg_end_addr
+    /// Get the greatest end address of the segments that cover the given address, if any.
+    fn get_covering_end(&self, addr: u64) -> Option<u64> {
+        let count = self.starts.partition_point(|start| *start <= addr);
+        (count > 0 && self.max_ends[count - 1] > addr).then(|| self.max_ends[count - 1])
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::ops::Range;
+    use std::borrow::Cow;
+    use std::fmt::Arguments;
+    use std::marker::PhantomData;
+    use std::sync::{Arc, Mutex};
 
-    use object::read::elf::ElfFile64;
+    use object::elf::{
+        PF_R, PF_X, PT_LOAD, SHF_ALLOC, SHF_COMPRESSED, SHF_EXECINSTR, SHT_PROGBITS,
+    };
+    use object::read::elf::{ElfFile, ElfFile64, FileHeader as ElfFileHeader};
     use object::read::SectionIndex;
-    use object::write::Object as OutputObject;
-    use object::{Architecture, BinaryFormat, Endianness};
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, SectionKind,
+    };
 
+    use crate::elf::test::builder::ElfBuilder;
+    use crate::output::{Object as OutputObject, SectionId};
     use crate::pass::test::PassTest;
-    use crate::pass::{Pass, PassHandle, PassManager};
+    use crate::pass::{Diagnostics, Pass, PassHandle, PassManager};
+    use crate::utils::dump::render_section;
+    use crate::{DiagnosticSink, Severity};
 
-    use super::{CopyLodableSectionsPass, SectionMap};
+    use super::{CopyLodableSectionsPass, CopySectionsError, SectionDisposition, SectionMap};
 
-    struct CopyLoadableSectionPassTest;
-
-    impl PassTest for CopyLoadableSectionPassTest {
-        type Input = ElfFile64<'static>;
-        type Pass = CopyLodableSectionsPass;
+    struct CopyLoadableSectionPassTest<E> {
+        expected_size: u64,
+        expected_section_maps: Vec<SectionMap>,
+        _marker: PhantomData<E>,
+    }
+
+    impl<E> CopyLoadableSectionPassTest<E> {
+        fn new(expected_size: u64, expected_section_maps: Vec<SectionMap>) -> Self {
+            Self {
+                expected_size,
+                expected_section_maps,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<E> PassTest for CopyLoadableSectionPassTest<E>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        type Input = ElfFile<'static, E>;
+        type Pass = CopyLodableSectionsPass;
 
         fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
             pass_mgr.add_pass_default::<CopyLodableSectionsPass>()
         }
 
         fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
-            fn addr_range(addr: u64, size: u64) -> Range<u64> {
-                addr..addr + size
-            }
+            assert_eq!(output.output_section_size, self.expected_size);
+            assert_eq!(output.section_maps, self.expected_section_maps);
+        }
+    }
+
+    macro_rules! make_section_maps {
+        ( $( { $index:expr, $name:expr, $addr:expr, $size:expr $(,)? } ),* $(,)? ) => {
+            vec![
+                $(
+                    SectionMap {
+                        index: SectionIndex($index),
+                        name: String::from($name),
+                        addr_range: $addr..$addr + $size,
+                    }
+                ),*
+            ]
+        };
+    }
 
-            macro_rules! make_section_maps {
-                ( $( { $index:expr, $addr:expr, $size:expr $(,)? } ),* $(,)? ) => {
-                    vec![
-                        $(
-                            SectionMap {
-                                index: SectionIndex($index),
-                                addr_range: addr_range($addr, $size),
-                            }
-                        ),*
-                    ]
-                };
+    #[test]
+    fn test_cls_pass() {
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = CopyLoadableSectionPassTest::new(
+            0x95e28,
+            make_section_maps! {
+                { 1, ".note.gnu.property", 0x2e0, 0x30 },
+                { 2, ".note.gnu.build-id", 0x310, 0x24 },
+                { 3, ".gnu.hash", 0x338, 0x2910 },
+                { 4, ".dynsym", 0x2c48, 0x8a48 },
+                { 5, ".dynstr", 0xb690, 0x1cb3f },
+                { 6, ".gnu.version", 0x281d0, 0xb86 },
+                { 7, ".gnu.version_r", 0x28d58, 0x180 },
+                { 8, ".rela.dyn", 0x28ed8, 0x7320 },
+                { 9, ".rela.plt", 0x301f8, 0x2280 },
+                { 10, ".init", 0x33000, 0x1b },
+                { 11, ".plt", 0x33020, 0x1710 },
+                { 12, ".plt.got", 0x34730, 0x28 },
+                { 13, ".text", 0x34760, 0x4a4a4 },
+                { 14, ".fini", 0x7ec04, 0xd },
+                { 15, ".rodata", 0x7f000, 0x4d70 },
+                { 16, ".eh_frame_hdr", 0x83d70, 0x1b5c },
+                { 17, ".eh_frame", 0x858d0, 0x9804 },
+                { 18, ".gcc_except_table", 0x8f0d4, 0x2234 },
+                { 19, ".tbss", 0x92390, 0x10 },
+                { 20, ".init_array", 0x92390, 0x8 },
+                { 21, ".fini_array", 0x92398, 0x8 },
+                { 22, ".data.rel.ro", 0x923a0, 0x2490 },
+                { 23, ".dynamic", 0x94830, 0x210 },
+                { 24, ".got", 0x94a40, 0x598 },
+                { 25, ".got.plt", 0x94fe8, 0xb98 },
+                { 26, ".data", 0x95b80, 0xa0 },
+                { 27, ".bss", 0x95c20, 0x208 },
+            },
+        );
+        crate::pass::test::run_pass_test(test, input, output);
+    }
+
+    #[test]
+    fn test_cls_pass_elf32() {
+        let input = crate::elf::test::get_test_input_file32();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+        let test = CopyLoadableSectionPassTest::new(
+            0x400c,
+            make_section_maps! {
+                { 1, ".gnu.hash", 0x114, 0x30 },
+                { 2, ".dynsym", 0x144, 0x70 },
+                { 3, ".dynstr", 0x1b4, 0x44 },
+                { 4, ".rel.dyn", 0x1f8, 0x28 },
+                { 5, ".text", 0x1000, 0x43 },
+                { 6, ".eh_frame", 0x2000, 0x54 },
+                { 7, ".init_array", 0x3f60, 0x4 },
+                { 8, ".dynamic", 0x3f64, 0x88 },
+                { 9, ".got", 0x3fec, 0x8 },
+                { 10, ".got.plt", 0x3ff4, 0xc },
+                { 11, ".data", 0x4000, 0xc },
+            },
+        );
+        crate::pass::test::run_pass_test(test, input, output);
+    }
+
+    struct CopyDataTest<E>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        get_input: fn() -> ElfFile<'static, E>,
+        expected_size: usize,
+        output_section_id: Option<SectionId>,
+    }
+
+    impl<E> PassTest for CopyDataTest<E>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        type Input = ElfFile<'static, E>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default::<CopyLodableSectionsPass>()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            self.output_section_id = Some(output.output_section_id);
+        }
+
+        fn check_output_object(&mut self, output: &OutputObject<'static>) {
+            let input = (self.get_input)();
+            let data = output.section(self.output_section_id.unwrap()).data();
+            assert_eq!(data.len(), self.expected_size);
+
+            for input_sec in input.sections() {
+                if input_sec.address() == 0 || input_sec.kind() == SectionKind::UninitializedTls {
+                    continue;
+                }
+
+                let addr = input_sec.address() as usize;
+                let sec_data = input_sec.data().unwrap();
+                assert_eq!(&data[addr..addr + sec_data.len()], sec_data);
             }
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_copy_data() {
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = CopyDataTest {
+            get_input: crate::elf::test::get_test_input_file,
+            expected_size: 0x95e28,
+            output_section_id: None,
+        };
+        crate::pass::test::run_pass_test(test, input, output);
+
+        let input = crate::elf::test::get_test_input_file32();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+        let test = CopyDataTest {
+            get_input: crate::elf::test::get_test_input_file32,
+            expected_size: 0x400c,
+            output_section_id: None,
+        };
+        crate::pass::test::run_pass_test(test, input, output);
+    }
+
+    #[test]
+    fn test_cls_pass_image_too_large() {
+        const SHOFF: usize = 0xbe638;
+        const BSS_SECTION_INDEX: usize = 27;
+        const RW_SEGMENT_INDEX: usize = 3;
+
+        // Move .bss far away and extend the containing segment to cover it.
+        let input = crate::elf::test::get_patched_test_input_file(|data| {
+            crate::elf::test::write_u64(data, SHOFF + BSS_SECTION_INDEX * 64 + 16, 0x10_0000_0000);
+            crate::elf::test::write_u64(data, 64 + RW_SEGMENT_INDEX * 56 + 40, 0x10_0000_0000);
+        });
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(input, output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::ImageTooLarge { .. })
+        ));
+    }
+
+    struct MinOutputAlignTest;
+
+    impl PassTest for MinOutputAlignTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass(CopyLodableSectionsPass {
+                min_output_align: 0x1000,
+                ..Default::default()
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            // .data.rel.ro stays at its input address.
+            let loc = output.translate(0x923a0).unwrap();
+            assert_eq!(loc.section_id, output.output_section_id);
+            assert_eq!(loc.offset, 0x923a0);
+        }
 
-            assert_eq!(output.output_section_size, 0x95e28);
+        fn check_output_object(&mut self, output: &OutputObject<'static>) {
+            let output_data = output.write().unwrap();
+            let output_file = object::File::parse(&*output_data).unwrap();
             assert_eq!(
-                output.section_maps,
-                make_section_maps! {
-                    { 1, 0x2e0, 0x30 },
-                    { 2, 0x310, 0x24 },
-                    { 3, 0x338, 0x2910 },
-                    { 4, 0x2c48, 0x8a48 },
-                    { 5, 0xb690, 0x1cb3f },
-                    { 6, 0x281d0, 0xb86 },
-                    { 7, 0x28d58, 0x180 },
-                    { 8, 0x28ed8, 0x7320 },
-                    { 9, 0x301f8, 0x2280 },
-                    { 10, 0x33000, 0x1b },
-                    { 11, 0x33020, 0x1710 },
-                    { 12, 0x34730, 0x28 },
-                    { 13, 0x34760, 0x4a4a4 },
-                    { 14, 0x7ec04, 0xd },
-                    { 15, 0x7f000, 0x4d70 },
-                    { 16, 0x83d70, 0x1b5c },
-                    { 17, 0x858d0, 0x9804 },
-                    { 18, 0x8f0d4, 0x2234 },
-                    { 19, 0x92390, 0x10 },
-                    { 20, 0x92390, 0x8 },
-                    { 21, 0x92398, 0x8 },
-                    { 22, 0x923a0, 0x2490 },
-                    { 23, 0x94830, 0x210 },
-                    { 24, 0x94a40, 0x598 },
-                    { 25, 0x94fe8, 0xb98 },
-                    { 26, 0x95b80, 0xa0 },
-                    { 27, 0x95c20, 0x208 },
-                }
+                output_file.section_by_name(".soda").unwrap().align(),
+                0x1000
             );
         }
     }
 
     #[test]
-    fn test_cls_pass() {
+    fn test_cls_pass_min_output_align() {
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(MinOutputAlignTest, input, output);
+    }
+
+    struct TranslateSymbolTest;
+
+    impl PassTest for TranslateSymbolTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let main = output.output_section_id;
+            let translate = |addr, size| {
+                output
+                    .translate_symbol(addr, size)
+                    .map(|loc| (loc.section_id, loc.offset))
+            };
+
+            // A zero-size marker at the start of .data.rel.ro.
+            assert_eq!(translate(0x2000, 0), Some((main, 0x2000)));
+            // A marker at the seam between .data.rel.ro and .data.
+            assert_eq!(translate(0x3000, 0), Some((main, 0x3000)));
+            // A marker at the end of .data, which is the end of the image.
+            assert_eq!(translate(0x3010, 0), Some((main, 0x3010)));
+            // Symbols with a size only belong to the image if their first byte does.
+            assert_eq!(translate(0x3010, 4), None);
+
+            assert!(output.contains_symbol(0x2000, 0x1000));
+            assert!(output.contains_symbol(0x2ff8, 0x10));
+            assert!(!output.contains_symbol(0x3008, 0x10));
+        }
+
+        fn check_output_object(&mut self, _output: &OutputObject<'static>) {}
+    }
+
+    #[test]
+    fn test_cls_pass_translate_symbol() {
+        let builder = crate::elf::test::builder::relro_dso();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(TranslateSymbolTest, builder.build_elf64(), output);
+    }
+
+    struct ZeroAddressSectionTest<E> {
+        expected_section_maps: Vec<SectionMap>,
+        _marker: PhantomData<E>,
+    }
+
+    impl<E> PassTest for ZeroAddressSectionTest<E>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        type Input = ElfFile<'static, E>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default::<CopyLodableSectionsPass>()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            assert_eq!(output.section_maps, self.expected_section_maps);
+        }
+    }
+
+    /// Run [`ZeroAddressSectionTest`] on both the 64-bit and the 32-bit variants of the given input.
+    fn run_zero_address_section_test(builder: ElfBuilder, expected_section_maps: Vec<SectionMap>) {
+        let test = ZeroAddressSectionTest {
+            expected_section_maps: expected_section_maps.clone(),
+            _marker: PhantomData,
+        };
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(test, builder.build_elf64(), output);
+
+        let test = ZeroAddressSectionTest {
+            expected_section_maps,
+            _marker: PhantomData,
+        };
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+        crate::pass::test::run_pass_test(test, builder.into_i386().build_elf32(), output);
+    }
+
+    #[test]
+    fn test_cls_pass_zero_address_section() {
+        // An allocatable section at address 0 inside a PT_LOAD segment starting at 0 is copied.
+        let mut builder = ElfBuilder::new();
+        let sec = builder.add_section(".text", SHT_PROGBITS, SHF_ALLOC, 0, vec![0xC3; 0x10]);
+        builder.add_section(".comment", SHT_PROGBITS, 0, 0, vec![0; 0x8]);
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0, 0x10);
+        run_zero_address_section_test(
+            builder,
+            vec![SectionMap {
+                index: SectionIndex(sec as usize),
+                name: String::from(".text"),
+                addr_range: 0..0x10,
+            }],
+        );
+
+        // An allocatable section at address 0 outside of every PT_LOAD segment is not copied.
+        let mut builder = ElfBuilder::new();
+        builder.add_section(".text", SHT_PROGBITS, SHF_ALLOC, 0, vec![0xC3; 0x10]);
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, 0x10);
+        run_zero_address_section_test(builder, Vec::new());
+    }
+
+    struct SectionDispositionsTest {
+        expected_dispositions: Vec<(usize, SectionDisposition)>,
+    }
+
+    impl PassTest for SectionDispositionsTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default::<CopyLodableSectionsPass>()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let dispositions: Vec<_> = output
+                .dispositions
+                .iter()
+                .map(|(idx, disposition)| (idx.0, *disposition))
+                .collect();
+            assert_eq!(dispositions, self.expected_dispositions);
+            for (idx, disposition) in &self.expected_dispositions {
+                assert_eq!(
+                    output.section_disposition(SectionIndex(*idx)),
+                    Some(*disposition)
+                );
+            }
+            assert_eq!(output.section_disposition(SectionIndex(0)), None);
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_dispositions() {
+        // Every allocatable section of spdlog is copied, and the other ones are skipped.
+        let mut expected_dispositions: Vec<_> = (1..=27)
+            .map(|idx| (idx, SectionDisposition::Copied))
+            .collect();
+        expected_dispositions
+            .extend((28..=31).map(|idx| (idx, SectionDisposition::SkippedNotAllocatable)));
+        let test = SectionDispositionsTest {
+            expected_dispositions,
+        };
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(test, input, output);
+
+        // Allocatable sections outside of every PT_LOAD segment are skipped, and the TLS image is copied.
+        let mut builder = crate::elf::test::builder::tls_dso();
+        let zero = builder.add_section(".zero", SHT_PROGBITS, SHF_ALLOC, 0, vec![0; 0x8]);
+        let unloaded =
+            builder.add_section(".unloaded", SHT_PROGBITS, SHF_ALLOC, 0x8000, vec![0; 0x8]);
+        let mut expected_dispositions: Vec<_> = (1..=4)
+            .map(|idx| (idx, SectionDisposition::Copied))
+            .collect();
+        expected_dispositions.push((zero as usize, SectionDisposition::SkippedZeroAddress));
+        expected_dispositions.push((unloaded as usize, SectionDisposition::SkippedNotLoadable));
+        // The symbol, string and relocation tables added by the builder.
+        expected_dispositions
+            .extend((7..=10).map(|idx| (idx, SectionDisposition::SkippedNotAllocatable)));
+        let test = SectionDispositionsTest {
+            expected_dispositions,
+        };
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(test, builder.build_elf64(), output);
+    }
+
+    #[test]
+    fn test_cls_pass_overlapping_segments() {
+        let mut builder = ElfBuilder::new();
+        builder.add_section(".a", SHT_PROGBITS, SHF_ALLOC, 0x1000, vec![1; 0x10]);
+        // Only covered by the first segment, although the second segment starts in between.
+        builder.add_section(".b", SHT_PROGBITS, SHF_ALLOC, 0x2000, vec![2; 0x10]);
+        // Straddles two adjacent segments, neither of which covers it.
+        builder.add_section(".c", SHT_PROGBITS, SHF_ALLOC, 0x3ff0, vec![3; 0x20]);
+        builder.add_segment(PT_LOAD, PF_R, 0x4000, 0x1000);
+        builder.add_segment(PT_LOAD, PF_R, 0x1000, 0x2000);
+        builder.add_segment(PT_LOAD, PF_R, 0x1800, 0x100);
+        builder.add_segment(PT_LOAD, PF_R, 0x3000, 0x1000);
+
+        // The image extends to the end of the segment that holds .b.
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = CopyLoadableSectionPassTest::new(
+            0x3000,
+            make_section_maps! {
+                { 1, ".a", 0x1000, 0x10 },
+                { 2, ".b", 0x2000, 0x10 },
+            },
+        );
+        crate::pass::test::run_pass_test(test, builder.build_elf64(), output);
+    }
+
+    #[derive(Default)]
+    struct TrapPaddingTest {
+        output_section_id: Option<SectionId>,
+    }
+
+    impl PassTest for TrapPaddingTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass(CopyLodableSectionsPass {
+                trap_padding: true,
+                ..Default::default()
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            self.output_section_id = Some(output.output_section_id);
+        }
+
+        fn check_output_object(&mut self, output: &OutputObject<'static>) {
+            let data = output.section(self.output_section_id.unwrap()).data();
+
+            // Gaps within the executable segment are filled with int3.
+            assert!(data[0x3301b..0x33020].iter().all(|b| *b == 0xCC));
+            assert!(data[0x34758..0x34760].iter().all(|b| *b == 0xCC));
+
+            // Gaps within non-executable segments are left untouched.
+            assert!(data[0x858cc..0x858d0].iter().all(|b| *b == 0));
+
+            // Section contents are left untouched.
+            assert_ne!(data[0x3301a], 0xCC);
+            assert_ne!(data[0x34760], 0xCC);
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_output_checksum() {
+        // FNV-1a, which is stable across toolchains unlike the hasher of the standard library.
+        fn checksum(data: &[u8]) -> u64 {
+            data.iter().fold(0xcbf29ce484222325, |hash, b| {
+                (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+            })
+        }
+
         let input = crate::elf::test::get_test_input_file();
         let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
-        crate::pass::test::run_pass_test(CopyLoadableSectionPassTest, input, output);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let output = pass_mgr.run(input, output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let checksums: Vec<_> = [".soda", ".tdata"]
+            .iter()
+            .map(|name| {
+                let data = output_file
+                    .section_by_name(name)
+                    .map_or(&[][..], |sec| sec.data().unwrap());
+                (data.len(), checksum(data))
+            })
+            .collect();
+        assert_eq!(
+            checksums,
+            vec![(0x95e28, 0x34bf1571b4d58a9f), (0x10, 0x88201fb960ff6465),]
+        );
+    }
+
+    #[test]
+    fn test_cls_pass_trap_padding() {
+        let input = crate::elf::test::get_test_input_file();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(TrapPaddingTest::default(), input, output);
+    }
+
+    #[test]
+    fn test_cls_pass_clamp_section_align() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.section_mut(1).sh_addralign = 1 << 40;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            output_section.align(),
+            CopyLodableSectionsPass::DEFAULT_MAX_SECTION_ALIGN
+        );
+    }
+
+    #[test]
+    fn test_cls_pass_invalid_section_align() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.section_mut(1).sh_addralign = 24;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::InvalidSectionAlign { align: 24, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cls_pass_section_address_overflow() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_section(
+            ".wrap",
+            SHT_PROGBITS,
+            SHF_ALLOC,
+            u64::MAX - 0x7,
+            vec![0u8; 0x10],
+        );
+        builder.add_segment(PT_LOAD, PF_R, u64::MAX - 0xFFF, 0x1000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::SectionAddressOverflow { size: 0x10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cls_pass_tls_image_too_large() {
+        let mut builder = crate::elf::test::builder::tls_dso();
+        builder.segments.last_mut().unwrap().p_memsz = u64::MAX / 2;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::ImageTooLarge {
+                size: 0x7FFF_FFFF_FFFF_FFFF,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cls_pass_truncated_section() {
+        // Chop off the tail of the .data section.
+        let mut data = crate::elf::test::builder::minimal_dso().build();
+        data.truncate(data.len() - 4);
+        let input: ElfFile64<'static> = ElfFile64::parse(&*Vec::leak(data)).unwrap();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(input, output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::TruncatedSection {
+                expected: 0x10,
+                actual: 0xc,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_read_section_data_borrowed() {
+        let input = crate::elf::test::builder::minimal_dso().build_elf64();
+        for input_sec in input.sections() {
+            let data = super::read_section_data(&input, &input_sec).unwrap();
+            assert!(matches!(data, Cow::Borrowed(_)), "{:?}", input_sec.name());
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_compressed_section() {
+        // Loaders never decompress sections, so compressed data in a loadable section is rejected rather than copied.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.section_mut(2).sh_flags |= SHF_COMPRESSED as u64;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<CopySectionsError>(),
+            Some(CopySectionsError::ReadElfError(_))
+        ));
+    }
+
+    /// Checks the indexed lookups of section maps against linear searches, and optionally measures both.
+    struct SectionMapIndexTest;
+
+    impl PassTest for SectionMapIndexTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CopyLodableSectionsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            pass_mgr.add_pass_default::<CopyLodableSectionsPass>()
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let find_linearly =
+                |idx: SectionIndex| output.section_maps.iter().find(|map| map.index == idx);
+
+            // Indexes past the last section map must not be found either.
+            let max_idx = output
+                .section_maps
+                .iter()
+                .map(|map| map.index.0)
+                .max()
+                .unwrap();
+            for idx in (0..max_idx + 3).map(SectionIndex) {
+                assert_eq!(output.get_section_map(idx), find_linearly(idx), "{:?}", idx);
+            }
+        }
+    }
+
+    /// Create an input with the given number of executable sections.
+    fn get_many_sections_input(num_sections: u64) -> ElfFile64<'static> {
+        let mut builder = ElfBuilder::new();
+        for i in 0..num_sections {
+            builder.add_section(
+                &format!(".text.{}", i),
+                SHT_PROGBITS,
+                SHF_ALLOC | SHF_EXECINSTR,
+                0x1000 + i * 0x10,
+                vec![0xC3; 0x10],
+            );
+        }
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, num_sections * 0x10);
+        builder.build_elf64()
+    }
+
+    #[test]
+    fn test_cls_pass_section_map_index() {
+        for input in [
+            crate::elf::test::get_test_input_file(),
+            get_many_sections_input(100),
+        ] {
+            let output =
+                OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+            crate::pass::test::run_pass_test(SectionMapIndexTest, input, output);
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_tls_image() {
+        let input = crate::elf::test::builder::tls_dso().build_elf64();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let output = pass_mgr.run(input, output).unwrap();
+
+        // The zero-initialized part of the image follows the initialized data.
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let tls_section = output_file.section_by_name(".tdata").unwrap();
+        assert_eq!(
+            render_section(&output_file, &tls_section),
+            "2 .tdata Tls SHT_PROGBITS flags=0x403 size=0x10 align=8"
+        );
+        assert_eq!(
+            tls_section.data().unwrap(),
+            &[1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct CollectingSink {
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl DiagnosticSink for CollectingSink {
+        fn report(&self, severity: Severity, message: Arguments<'_>) {
+            if severity == Severity::Warning {
+                self.warnings.lock().unwrap().push(message.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_tls_size_warning() {
+        // The TLS image is 16 bytes.
+        for (tls_size_warning, expect_warning) in [(8, true), (16, false), (0, false)] {
+            let input = crate::elf::test::builder::tls_dso().build_elf64();
+            let output =
+                OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+            let sink = Arc::new(CollectingSink::default());
+            let mut pass_mgr = PassManager::new();
+            pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+            pass_mgr.add_pass(CopyLodableSectionsPass {
+                tls_size_warning,
+                ..Default::default()
+            });
+            pass_mgr.run(input, output).unwrap();
+
+            let warnings = sink.warnings.lock().unwrap();
+            assert_eq!(
+                warnings
+                    .iter()
+                    .any(|warning| warning
+                        .starts_with("The TLS image is 16 bytes, more than 8 bytes")),
+                expect_warning,
+                "{:?}",
+                warnings
+            );
+        }
+    }
+
+    #[test]
+    fn test_cls_pass_aarch64() {
+        // The executable segment extends 8 bytes beyond .text.
+        let mut builder = crate::elf::test::builder::minimal_dso().into_aarch64();
+        builder.segments[0].p_memsz = 0x18;
+        let output =
+            OutputObject::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass(CopyLodableSectionsPass {
+            trap_padding: true,
+            ..Default::default()
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        // The padding within the executable segment is filled with BRK instructions.
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let output_section = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            render_section(&output_file, &output_section),
+            "1 .soda Text SHT_PROGBITS flags=0x7 size=0x2010 align=16"
+        );
+        let output_section_data = output_section.data().unwrap();
+        assert_eq!(&output_section_data[0x1000..0x1010], &[0xC3; 0x10]);
+        assert_eq!(
+            &output_section_data[0x1010..0x1018],
+            &[0x00, 0x00, 0x20, 0xD4, 0x00, 0x00, 0x20, 0xD4]
+        );
+    }
+
+    #[test]
+    fn test_cls_pass_section_name() {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.add_pass(CopyLodableSectionsPass {
+            section_name: String::from(".vendor_image"),
+            ..Default::default()
+        });
+        let output = pass_mgr
+            .run(
+                crate::elf::test::builder::minimal_dso().build_elf64(),
+                output,
+            )
+            .unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        assert!(output_file.section_by_name(".soda").is_none());
+        assert_eq!(
+            render_section(
+                &output_file,
+                &output_file.section_by_name(".vendor_image").unwrap()
+            ),
+            "1 .vendor_image Text SHT_PROGBITS flags=0x7 size=0x2010 align=16"
+        );
     }
 }
@@ -0,0 +1,315 @@
+use std::fmt::Write as _;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::read::Error as ReadError;
+use object::{Object as _, ObjectSection as _, ReadRef, SectionKind};
+
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// Prefixes of input section names under which sections are grouped in the size summary, such as `.text.hot` under
+/// `.text`. Longer prefixes come first, so that `.data.rel.ro.local` is grouped under `.data.rel.ro` rather than `.data`.
+const SECTION_GROUPS: &[&str] = &[
+    ".data.rel.ro",
+    ".text",
+    ".rodata",
+    ".data",
+    ".bss",
+    ".tdata",
+    ".tbss",
+];
+
+/// A pass that summarizes how many bytes the copied input sections contribute to the output, and estimates how much the
+/// final binary grows when the output is linked into it.
+///
+/// The summary is reported as an informational diagnostic at the end of the conversion.
+#[derive(Debug)]
+pub struct SummarizeSizesPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for SummarizeSizesPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "summarize sizes";
+
+    type Output = SizeSummary;
+    type Error = ReadError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+
+        let mut summary = SizeSummary {
+            groups: Vec::new(),
+            num_relocs: reloc_index.len(),
+            reloc_size: if ctx.input.is_64() { 24 } else { 12 },
+        };
+        let tls_sections = cls_output.tls.as_ref().map_or(&[][..], |tls| &tls.sections);
+        let maps = cls_output.section_maps.iter().map(|map| {
+            (
+                map.index,
+                map.name.as_str(),
+                map.addr_range.end - map.addr_range.start,
+            )
+        });
+        for (index, name, size) in maps {
+            let section = ctx.input.section_by_index(index)?;
+            summary.add(name, size, is_nobits(section.kind()));
+        }
+        // TLS sections within a loadable segment are copied into the image as well, and are only counted once.
+        for index in tls_sections {
+            if cls_output
+                .section_maps
+                .iter()
+                .any(|map| map.index == *index)
+            {
+                continue;
+            }
+            let section = ctx.input.section_by_index(*index)?;
+            summary.add(section.name()?, section.size(), is_nobits(section.kind()));
+        }
+
+        let mut table = String::new();
+        for group in &summary.groups {
+            let _ = writeln!(
+                table,
+                "  {:<24} {:>12}{}",
+                group.name,
+                group.size,
+                if group.nobits_size > 0 {
+                    format!(" ({} zero-initialized)", group.nobits_size)
+                } else {
+                    String::new()
+                }
+            );
+        }
+        ctx.diagnostics.info(format_args!(
+            "Output size breakdown by input section:\n{}  {:<24} {:>12} ({} zero-initialized)\n{} relocations take \
+             about {} bytes in the output object; the final binary is estimated to grow by {} bytes",
+            table,
+            "total",
+            summary.total_size(),
+            summary.total_nobits_size(),
+            summary.num_relocs,
+            summary.num_relocs as u64 * summary.reloc_size,
+            summary.estimated_growth()
+        ));
+
+        Ok(summary)
+    }
+}
+
+/// The output of [`SummarizeSizesPass`].
+#[derive(Debug)]
+pub struct SizeSummary {
+    /// The sizes of the copied input sections grouped by their names, in the order that each group is first seen.
+    pub groups: Vec<SectionGroupSize>,
+
+    /// The number of dynamic relocations of the input.
+    pub num_relocs: usize,
+
+    /// The approximate size of each relocation in the output object.
+    pub reloc_size: u64,
+}
+
+/// The size of a group of copied input sections.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SectionGroupSize {
+    /// Name of the group, which is the name of its sections with the suffix after a known prefix removed.
+    pub name: String,
+
+    /// Number of bytes that the sections contribute to the output.
+    pub size: u64,
+
+    /// Number of bytes of zero-initialized sections among them.
+    pub nobits_size: u64,
+}
+
+impl SizeSummary {
+    /// Get the total number of bytes that the copied input sections contribute to the output.
+    pub fn total_size(&self) -> u64 {
+        self.groups.iter().map(|group| group.size).sum()
+    }
+
+    /// Get the total number of bytes of the zero-initialized input sections.
+    pub fn total_nobits_size(&self) -> u64 {
+        self.groups.iter().map(|group| group.nobits_size).sum()
+    }
+
+    /// Estimate the number of bytes that the final binary grows by, which is the size of the copied sections except for
+    /// the zero-initialized ones.
+    pub fn estimated_growth(&self) -> u64 {
+        self.total_size() - self.total_nobits_size()
+    }
+
+    fn add(&mut self, section_name: &str, size: u64, nobits: bool) {
+        let name = get_section_group(section_name);
+        let pos = match self.groups.iter().position(|group| group.name == name) {
+            Some(pos) => pos,
+            None => {
+                self.groups.push(SectionGroupSize {
+                    name: String::from(name),
+                    size: 0,
+                    nobits_size: 0,
+                });
+                self.groups.len() - 1
+            }
+        };
+        let group = &mut self.groups[pos];
+        group.size += size;
+        if nobits {
+            group.nobits_size += size;
+        }
+    }
+}
+
+/// Get the name of the group of the input section with the given name.
+fn get_section_group(name: &str) -> &str {
+    SECTION_GROUPS
+        .iter()
+        .find(|prefix| {
+            name.strip_prefix(**prefix)
+                .is_some_and(|suffix| suffix.is_empty() || suffix.starts_with('.'))
+        })
+        .copied()
+        .unwrap_or(name)
+}
+
+fn is_nobits(kind: SectionKind) -> bool {
+    matches!(
+        kind,
+        SectionKind::UninitializedData | SectionKind::UninitializedTls
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        PF_R, PF_W, PF_X, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_INIT_ARRAY,
+        SHT_PROGBITS,
+    };
+    use object::read::elf::ElfFile64;
+    use object::{Architecture, BinaryFormat, Endianness};
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Pass, PassHandle, PassManager};
+
+    use super::{get_section_group, SectionGroupSize, SummarizeSizesPass};
+
+    struct SummarizeSizesPassTest;
+
+    impl PassTest for SummarizeSizesPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = SummarizeSizesPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(SummarizeSizesPass {
+                cls_pass,
+                reloc_index_pass,
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let group = |name: &str, size, nobits_size| SectionGroupSize {
+                name: String::from(name),
+                size,
+                nobits_size,
+            };
+            assert_eq!(
+                output.groups,
+                vec![
+                    group(".text", 0x30, 0),
+                    group(".data", 0x30, 0),
+                    group(".tdata", 8, 0),
+                    group(".bss", 0x100, 0x100),
+                    group(".tbss", 8, 8),
+                ]
+            );
+            assert_eq!(output.total_size(), 0x170);
+            assert_eq!(output.total_nobits_size(), 0x108);
+            assert_eq!(output.estimated_growth(), 0x68);
+            assert_eq!(output.num_relocs, 1);
+            assert_eq!(output.reloc_size, 24);
+        }
+    }
+
+    #[test]
+    fn test_summarize_sizes_pass() {
+        let mut builder = crate::elf::test::builder::tls_dso();
+        builder.add_section(
+            ".text.hot",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            0x4000,
+            vec![0xc3; 0x20],
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0x4000, 0x20);
+        builder.add_nobits_section(".bss", SHF_ALLOC | SHF_WRITE, 0x5000, 0x100);
+        builder.add_segment(PT_LOAD, PF_R | PF_W, 0x5000, 0x100);
+        builder.add_relocation(0x2000, object::elf::R_X86_64_RELATIVE, 0, 0x1000);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(SummarizeSizesPassTest, builder.build_elf64(), output);
+    }
+
+    struct OverlappingTbssTest;
+
+    impl PassTest for OverlappingTbssTest {
+        type Input = ElfFile64<'static>;
+        type Pass = SummarizeSizesPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            SummarizeSizesPassTest.setup(pass_mgr)
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            let get_size = |name: &str| {
+                output
+                    .groups
+                    .iter()
+                    .find(|group| group.name == name)
+                    .map(|group| (group.size, group.nobits_size))
+            };
+            assert_eq!(get_size(".tdata"), Some((8, 0)));
+            assert_eq!(get_size(".tbss"), Some((0x10, 0x10)));
+            assert_eq!(get_size(".init_array"), Some((0x10, 0)));
+        }
+    }
+
+    #[test]
+    fn test_summarize_sizes_pass_overlapping_tbss() {
+        // Like GNU ld does, .tbss takes no room in the loadable segment and overlaps .init_array at 0x3008.
+        let mut builder = crate::elf::test::builder::tls_dso();
+        builder.section_mut(4).sh_size = 0x10;
+        builder.add_section(
+            ".init_array",
+            SHT_INIT_ARRAY,
+            SHF_ALLOC | SHF_WRITE,
+            0x3008,
+            vec![0u8; 0x10],
+        );
+        builder.segments[2].p_memsz = 0x18;
+        builder.segments[3].p_memsz = 0x18;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(OverlappingTbssTest, builder.build_elf64(), output);
+    }
+
+    #[test]
+    fn test_get_section_group() {
+        assert_eq!(get_section_group(".text"), ".text");
+        assert_eq!(get_section_group(".text.unlikely.foo"), ".text");
+        assert_eq!(get_section_group(".data.rel.ro.local"), ".data.rel.ro");
+        assert_eq!(get_section_group(".data.rel"), ".data");
+        assert_eq!(get_section_group(".textual"), ".textual");
+        assert_eq!(get_section_group(".eh_frame"), ".eh_frame");
+    }
+}
@@ -1,13 +1,34 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
 
-use object::elf::{STB_GLOBAL, STB_GNU_UNIQUE, STB_LOCAL};
-use object::read::elf::{ElfFile, ElfSymbol, FileHeader as ElfFileHeader};
+use object::elf::{
+    SHF_WRITE, SHT_DYNSYM, STB_GLOBAL, STB_GNU_UNIQUE, STB_LOCAL, STB_WEAK, STO_PPC64_LOCAL_BIT,
+    STO_PPC64_LOCAL_MASK, STT_NOTYPE, STT_OBJECT, STV_DEFAULT, STV_HIDDEN, STV_INTERNAL,
+    STV_PROTECTED,
+};
+use object::read::elf::{ElfFile, ElfSymbol, FileHeader as ElfFileHeader, VersionTable};
 use object::read::Error as ReadError;
-use object::write::{Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection};
-use object::{Object, ObjectSymbol, ReadRef, SymbolFlags, SymbolIndex, SymbolScope, SymbolSection};
+use object::{
+    Architecture, Object, ObjectSection, ObjectSymbol, ReadRef, RelocationTarget, SectionFlags,
+    SymbolFlags, SymbolIndex, SymbolKind, SymbolScope, SymbolSection,
+};
+use thiserror::Error;
 
-use crate::elf::pass::section::{CopyLodableSectionsOutput, CopyLodableSectionsPass};
-use crate::pass::{Pass, PassContext, PassHandle};
+use crate::elf::pass::gc::CollectGarbagePass;
+use crate::elf::pass::reloc_index::{IndexRelocationsPass, RelocationIndex};
+use crate::elf::pass::section::{
+    CopyLodableSectionsOutput, CopyLodableSectionsPass, OutputLocation,
+};
+use crate::output::{
+    Object as OutputObject, Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection,
+};
+use crate::pass::{Diagnostics, Pass, PassContext, PassHandle};
+use crate::utils::demangle::demangle_bytes;
+use crate::utils::glob::glob_match;
+use crate::utils::known_symbols::{find_introspection_symbol, find_known_library};
+use crate::utils::stringify::{symbol_bind_to_str, symbol_type_to_str};
 
 /// A pass that generates the symbol table of the output relocatable file.
 ///
@@ -17,12 +38,189 @@ use crate::pass::{Pass, PassContext, PassHandle};
 ///
 /// - Undefined input symbol will generate a corresponding undefined output symbol;
 /// - Defined local symbol will generate a corresponding defined local symbol;
-/// - Defined external symbol will generate a corresponding defined external symbol.
+/// - Defined external symbol will generate a corresponding defined external symbol;
+/// - Absolute (`SHN_ABS`) input symbol will always generate a corresponding absolute output symbol with the same value.
+/// - Common (`SHN_COMMON`) input symbol will generate a corresponding common output symbol with the same alignment and
+///   size.
+///
+/// Definitions of global symbols in sections that are not copied into the output relocatable file become undefined
+/// symbols, so that the final link reports them if they are not defined elsewhere. They are dropped instead if no
+/// dynamic relocation refers to them and dropping is requested. Such symbols are reported either way.
+///
+/// Definitions of symbols synthesized by the linker, such as `_DYNAMIC` and `_end`, would collide with the symbols
+/// synthesized by the final link. They are demoted to local symbols with a `.soda.` prefix, or dropped entirely if no
+/// dynamic relocation refers to them and dropping is requested. See [`SPECIAL_SYMBOLS`] for the full list.
+///
+/// Like the `--wrap` option of ld, references to wrapped symbols from dynamic relocations are redirected to undefined
+/// `__wrap_NAME` symbols, and the definitions of wrapped symbols, if any, are additionally exported as `__real_NAME`.
+/// Note that references that don't go through dynamic relocations, such as direct calls within the input shared
+/// library, cannot be redirected.
+///
+/// Undefined symbols that are not referenced by any dynamic relocation within the copied sections are pruned by
+/// default, since they would force the final link to pull in libraries that are not needed.
+///
+/// Versioned undefined symbols are emitted as `name@VERSION` so that the final link binds them to the right version.
+/// Versioned defined symbols are emitted under their plain names, or as `name@@VERSION` if the symbol versions are kept.
+/// Definitions of non-default (hidden) versions are demoted to local symbols named `name.VERSION` so that they don't
+/// collide with the definition of the default version. References to such definitions within the input shared library
+/// bind to the local symbols.
+///
+/// Exported data objects in writable sections may have been copied into consumers of the input shared library through
+/// copy relocations. Such consumers break once the library is converted, so these symbols are reported, or demoted to
+/// local symbols if requested.
+///
+/// If an export list is given, defined global symbols whose names match none of its patterns are demoted to local
+/// hidden symbols, so that the internals of the input shared library don't leak into the final link. The patterns are
+/// matched against the plain symbol names, without versions. The `__real_NAME` aliases of wrapped symbols are matched
+/// by their own names.
+///
+/// Defined global symbols can be weakened like the `--weaken` option of objcopy, so that the output relocatable file
+/// can be linked together with other strong definitions of the same symbols, such as those in the input shared library.
+///
+/// Symbols can be renamed like the `--redefine-sym` option of objcopy. Both definitions and references can be renamed,
+/// and the other options still match the original names. Renames that lead to multiple global definitions of the same
+/// symbol are rejected.
+///
+/// Exported definitions of well-known entry points of system libraries, such as `malloc` and `operator new`, interpose
+/// the system libraries once linked statically. They are reported.
+///
+/// When several input shared libraries are merged into one output relocatable file, global symbols are added through a
+/// [`SymbolNamespace`] shared by the conversions of all inputs, so that references to symbols defined by another input
+/// bind to their definitions directly. See [`SymbolNamespace`] for details.
 ///
 /// This pass will produce a symbol map that maps input dynamic symbols to output symbols.
 #[derive(Debug)]
 pub struct GenerateSymbolPass {
     pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+
+    /// Keep the default version of versioned defined symbols in their names.
+    pub keep_symbol_versions: bool,
+
+    /// Report exported data objects that are likely targets of copy relocations.
+    pub copy_reloc_check: bool,
+
+    /// Demote exported data objects that are likely targets of copy relocations to local symbols.
+    pub demote_exported_data: bool,
+
+    /// Do not generate undefined symbols that no dynamic relocation refers to.
+    pub prune_undefined: bool,
+
+    /// Names of undefined symbols that are never pruned.
+    pub keep_undefined: Vec<String>,
+
+    /// Drop the definitions of linker-synthesized symbols that no dynamic relocation refers to, instead of demoting
+    /// them to local symbols.
+    pub drop_special_symbols: bool,
+
+    /// Drop the definitions of symbols in sections that are not copied and that no dynamic relocation refers to, instead
+    /// of turning them into undefined symbols.
+    pub drop_excluded_symbols: bool,
+
+    /// Names of symbols to be wrapped.
+    pub wrap: Vec<String>,
+
+    /// Pairs of old and new names of symbols to be renamed.
+    pub redefine_syms: Vec<(String, String)>,
+
+    /// Glob patterns of the names of defined symbols that remain global. If given, all the other defined symbols are
+    /// demoted to local symbols.
+    pub only_export: Option<Vec<String>>,
+
+    /// Make all defined global symbols weak.
+    pub weaken: bool,
+
+    /// Names of defined global symbols to be made weak.
+    pub weaken_symbols: Vec<String>,
+
+    /// The namespace of global symbols shared with the conversions of other inputs that are merged into the same output.
+    pub namespace: Option<Rc<RefCell<SymbolNamespace>>>,
+
+    /// The pass that collects unreachable sections. Symbols in the collected sections are dropped.
+    pub gc_pass: Option<PassHandle<CollectGarbagePass>>,
+}
+
+/// Names of the symbols synthesized by the linker when it produced the input shared library.
+///
+/// Note that `__dso_handle` identifies the input shared library in the `__cxa_atexit` registrations made by the copied
+/// code, which refers to it through PC-relative addressing. The demoted definition keeps these registrations working:
+/// the registered destructors are keyed by the handle of the copied image rather than the handle of the final link,
+/// and they still run at exit.
+pub const SPECIAL_SYMBOLS: &[&str] = &[
+    "_DYNAMIC",
+    "_GLOBAL_OFFSET_TABLE_",
+    "__dso_handle",
+    "_init",
+    "_fini",
+    "_edata",
+    "_end",
+    "__bss_start",
+];
+
+/// Prefix of the names of the demoted linker-synthesized symbols.
+const SPECIAL_SYMBOL_PREFIX: &str = ".soda.";
+
+impl GenerateSymbolPass {
+    pub fn new(
+        cls_pass: PassHandle<CopyLodableSectionsPass>,
+        reloc_index_pass: PassHandle<IndexRelocationsPass>,
+    ) -> Self {
+        Self {
+            cls_pass,
+            reloc_index_pass,
+            keep_symbol_versions: false,
+            copy_reloc_check: true,
+            demote_exported_data: false,
+            prune_undefined: true,
+            keep_undefined: Vec::new(),
+            drop_special_symbols: false,
+            drop_excluded_symbols: false,
+            wrap: Vec::new(),
+            redefine_syms: Vec::new(),
+            only_export: None,
+            weaken: false,
+            weaken_symbols: Vec::new(),
+            namespace: None,
+            gc_pass: None,
+        }
+    }
+}
+
+impl GenerateSymbolPass {
+    fn is_wrapped(&self, name: &[u8]) -> bool {
+        self.wrap
+            .iter()
+            .any(|wrapped_name| wrapped_name.as_bytes() == name)
+    }
+
+    fn get_new_name(&self, name: &[u8]) -> Option<&str> {
+        self.redefine_syms
+            .iter()
+            .find(|(old_name, _)| old_name.as_bytes() == name)
+            .map(|(_, new_name)| new_name.as_str())
+    }
+
+    fn is_exported(&self, name: &[u8]) -> bool {
+        self.only_export.as_ref().is_none_or(|patterns| {
+            patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), name))
+        })
+    }
+
+    fn is_weakened(&self, name: &[u8]) -> bool {
+        self.weaken
+            || self
+                .weaken_symbols
+                .iter()
+                .any(|weakened_name| weakened_name.as_bytes() == name)
+    }
+
+    fn is_kept_undefined(&self, name: &[u8]) -> bool {
+        self.keep_undefined
+            .iter()
+            .any(|kept_name| kept_name.as_bytes() == name)
+    }
 }
 
 impl<'d, E, R> Pass<ElfFile<'d, E, R>> for GenerateSymbolPass
@@ -33,7 +231,7 @@ where
     const NAME: &'static str = "generate symbols";
 
     type Output = SymbolMap;
-    type Error = ReadError;
+    type Error = GenerateSymbolError;
 
     fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error>
     where
@@ -43,61 +241,1020 @@ where
         let mut output = ctx.output.borrow_mut();
 
         let cls_output = ctx.get_pass_output(self.cls_pass);
+        let collected = self.gc_pass.map(|pass| ctx.get_pass_output(pass));
+
+        let endian = ctx.input.endian();
+        let sections = ctx.input.raw_header().sections(endian, ctx.input.data())?;
+        let versions = sections.versions(endian, ctx.input.data())?;
 
-        let mut sym_map = HashMap::new();
+        let referenced_syms =
+            if self.prune_undefined || self.drop_special_symbols || self.drop_excluded_symbols {
+                Some(collect_referenced_symbols(
+                    ctx.get_pass_output(self.reloc_index_pass),
+                    cls_output,
+                ))
+            } else {
+                None
+            };
+
+        let num_input_syms = sections
+            .symbols(endian, ctx.input.data(), SHT_DYNSYM)?
+            .len();
+        let mut pending_syms = PendingSymbols::with_capacity(num_input_syms);
+        let mut copy_reloc_candidates = Vec::new();
+        let mut pruned_syms = Vec::new();
+        let mut special_syms = Vec::new();
+        let mut excluded_syms = Vec::new();
+        let mut wrap_syms: HashMap<&[u8], usize> = HashMap::new();
+        let mut num_weakened_syms = 0;
+        let mut num_localized_syms = 0;
+        let mut interposing_syms = Vec::new();
+        let mut introspection_syms = Vec::new();
+        let mut local_entry_syms = Vec::new();
+        let mut unique_syms = Vec::new();
+        let mut global_defs: HashMap<Vec<u8>, Vec<(&[u8], bool)>> = HashMap::new();
+        let mut num_collected_syms = 0;
         for input_sym in ctx.input.dynamic_symbols() {
-            // Ensure that the section containing the symbol has been copied into the output relocatable file. If not,
-            // such symbols will not cause the generation of an output symbol.
+            let is_referenced = |sym_idx| {
+                referenced_syms
+                    .as_ref()
+                    .is_none_or(|referenced_syms| referenced_syms.contains(&sym_idx))
+            };
+
+            if let (Some(collected), Some(sym_section_idx)) = (collected, input_sym.section_index())
+            {
+                if collected.is_section_collected(sym_section_idx) {
+                    num_collected_syms += 1;
+                    continue;
+                }
+            }
+
+            // Symbols in sections that are not copied into the output relocatable file have no definition to refer to.
+            // Local ones are dropped, and global ones become undefined symbols unless they are dropped. Absolute
+            // symbols have no containing section and are always kept.
             if let Some(sym_section_idx) = input_sym.section_index() {
                 if !cls_output.is_section_copied(sym_section_idx) {
+                    if input_sym.is_local() {
+                        continue;
+                    }
+
+                    let sec_name = ctx
+                        .input
+                        .section_by_index(sym_section_idx)
+                        .and_then(|sec| sec.name().map(String::from))
+                        .unwrap_or_else(|_| String::from("<unknown>"));
+                    let reason = match cls_output.section_disposition(sym_section_idx) {
+                        Some(disposition) => format!("{}: {}", sec_name, disposition),
+                        None => sec_name,
+                    };
+                    excluded_syms.push(format!(
+                        "{} ({})",
+                        demangle_bytes(input_sym.name_bytes()?),
+                        reason
+                    ));
+
+                    if self.drop_excluded_symbols && !is_referenced(input_sym.index()) {
+                        continue;
+                    }
+                    if let Some(output_sym) = create_undefined_symbol(&input_sym)? {
+                        let pending_sym = pending_syms.add(output_sym);
+                        pending_syms.map(input_sym.index(), input_sym.name_bytes()?, pending_sym);
+                    }
+                    continue;
+                }
+            }
+
+            if self.prune_undefined
+                && input_sym.is_undefined()
+                && !is_referenced(input_sym.index())
+                && !self.is_kept_undefined(input_sym.name_bytes()?)
+            {
+                pruned_syms.push(demangle_bytes(input_sym.name_bytes()?));
+                continue;
+            }
+
+            if input_sym.is_undefined() {
+                if let Some(sym) =
+                    find_introspection_symbol(&String::from_utf8_lossy(input_sym.name_bytes()?))
+                {
+                    introspection_syms.push(format!("{} ({})", sym.name, sym.caveat));
+                }
+            }
+
+            let is_special =
+                !input_sym.is_undefined() && is_special_symbol(input_sym.name_bytes()?);
+            if is_special {
+                special_syms.push(String::from_utf8_lossy(input_sym.name_bytes()?).into_owned());
+                if self.drop_special_symbols && !is_referenced(input_sym.index()) {
                     continue;
                 }
             }
 
-            let output_sym = create_output_symbol(&input_sym, cls_output)?;
-            let output_sym_id = output.add_symbol(output_sym);
-            sym_map.insert(input_sym.index(), output_sym_id);
+            let version = get_symbol_version(versions.as_ref(), endian, input_sym.index())?;
+            let mut output_sym = create_output_symbol(
+                &input_sym,
+                cls_output,
+                ctx.input.architecture(),
+                &ctx.diagnostics,
+            )?;
+            if let SymbolFlags::Elf { st_info, .. } = input_sym.flags() {
+                if st_info >> 4 == STB_GNU_UNIQUE {
+                    unique_syms.push(format!(
+                        "{} ({})",
+                        demangle_bytes(input_sym.name_bytes()?),
+                        symbol_type_to_str(st_info & 0xF)
+                    ));
+                }
+            }
+
+            // The st_other field of PowerPC64 ELFv2 functions encodes the offset of their local entry points, which is
+            // kept as is.
+            if let SymbolFlags::Elf { st_other, .. } = output_sym.flags {
+                let local_entry_offset = get_ppc64_local_entry_offset(st_other);
+                if ctx.input.architecture() == Architecture::PowerPc64
+                    && !input_sym.is_undefined()
+                    && local_entry_offset != 0
+                {
+                    local_entry_syms.push(format!(
+                        "{} (+{})",
+                        demangle_bytes(input_sym.name_bytes()?),
+                        local_entry_offset
+                    ));
+                }
+            }
+
+            // Renamed symbols no longer refer to the versioned interface of the input shared library, so they only keep
+            // the version if it's a non-default version that must be demoted.
+            let new_name = self.get_new_name(input_sym.name_bytes()?);
+            if let Some(new_name) = new_name {
+                output_sym.name = new_name.as_bytes().to_vec();
+            }
+            let plain_name = version.is_some().then(|| output_sym.name.clone());
+
+            if let Some(version) = version {
+                if !input_sym.is_undefined() && version.hidden {
+                    let original_name = demangle_bytes(&output_sym.name);
+                    demote_versioned_symbol(&mut output_sym, &version);
+                    ctx.diagnostics.info(format_args!(
+                        "Demoted non-default version definition \"{}@{}\" to local symbol \"{}\"",
+                        original_name,
+                        String::from_utf8_lossy(version.name),
+                        String::from_utf8_lossy(&output_sym.name)
+                    ));
+                } else if new_name.is_none() {
+                    output_sym.name = get_versioned_symbol_name(
+                        &output_sym.name,
+                        &version,
+                        input_sym.is_undefined(),
+                        self.keep_symbol_versions,
+                    );
+                }
+            }
+
+            if is_special {
+                output_sym.name = [SPECIAL_SYMBOL_PREFIX.as_bytes(), &output_sym.name].concat();
+                make_symbol_local(&mut output_sym);
+            }
+
+            // The __real_NAME alias of a wrapped definition is subject to the export list and weakening on its own.
+            let input_sym_name = input_sym.name_bytes()?;
+            let is_wrapped = self.is_wrapped(input_sym_name);
+            let mut real_sym = (is_wrapped && !input_sym.is_undefined())
+                .then(|| create_real_symbol(input_sym_name, &output_sym));
+
+            if !input_sym.is_undefined()
+                && output_sym.scope != SymbolScope::Compilation
+                && !self.is_exported(input_sym_name)
+            {
+                // Symbol versions only apply to global symbols.
+                if let Some(plain_name) = plain_name {
+                    output_sym.name = plain_name;
+                }
+                make_symbol_hidden(&mut output_sym);
+                num_localized_syms += 1;
+            }
+            if let Some(real_sym) = &mut real_sym {
+                if real_sym.scope != SymbolScope::Compilation && !self.is_exported(&real_sym.name) {
+                    make_symbol_hidden(real_sym);
+                    num_localized_syms += 1;
+                }
+            }
+
+            if (self.copy_reloc_check || self.demote_exported_data)
+                && output_sym.scope != SymbolScope::Compilation
+                && is_copy_reloc_candidate(&ctx.input, &input_sym)
+            {
+                copy_reloc_candidates.push(demangle_bytes(&output_sym.name));
+                if self.demote_exported_data {
+                    make_symbol_local(&mut output_sym);
+                }
+            }
+
+            if !input_sym.is_undefined() && self.is_weakened(input_sym_name) {
+                for sym in std::iter::once(&mut output_sym).chain(real_sym.as_mut()) {
+                    if sym.scope != SymbolScope::Compilation && !sym.weak {
+                        make_symbol_weak(sym);
+                        num_weakened_syms += 1;
+                    }
+                }
+            }
+
+            // Only renames can introduce multiple global definitions of the same symbol, so the definitions are not
+            // tracked without them.
+            if !self.redefine_syms.is_empty()
+                && !input_sym.is_undefined()
+                && output_sym.scope != SymbolScope::Compilation
+            {
+                global_defs
+                    .entry(output_sym.name.clone())
+                    .or_default()
+                    .push((input_sym_name, new_name.is_some()));
+            }
+
+            if is_wrapped {
+                if let Some(real_sym) = real_sym {
+                    pending_syms.add(real_sym);
+                }
+
+                let wrap_sym = *wrap_syms.entry(input_sym_name).or_insert_with(|| {
+                    pending_syms.add(create_wrap_symbol(input_sym_name, &output_sym))
+                });
+                if !input_sym.is_undefined() {
+                    pending_syms.add(output_sym);
+                }
+                pending_syms.map(input_sym.index(), input_sym_name, wrap_sym);
+                continue;
+            }
+
+            if !input_sym.is_undefined() && output_sym.scope != SymbolScope::Compilation {
+                if let Some(library) = find_known_library(&String::from_utf8_lossy(input_sym_name))
+                {
+                    interposing_syms.push(format!(
+                        "{} ({})",
+                        demangle_bytes(input_sym_name),
+                        library
+                    ));
+                }
+            }
+
+            let pending_sym = pending_syms.add(output_sym);
+            pending_syms.map(input_sym.index(), input_sym_name, pending_sym);
+        }
+
+        // Renaming must not introduce multiple global definitions of the same symbol.
+        let mut collisions: Vec<_> = global_defs
+            .into_iter()
+            .filter(|(_, defs)| defs.len() > 1 && defs.iter().any(|(_, renamed)| *renamed))
+            .collect();
+        collisions.sort();
+        if let Some((name, defs)) = collisions.into_iter().next() {
+            return Err(GenerateSymbolError::RenameCollision {
+                name: demangle_bytes(&name),
+                old_names: defs
+                    .into_iter()
+                    .map(|(old_name, _)| demangle_bytes(old_name))
+                    .collect(),
+            });
+        }
+
+        let mut namespace = self
+            .namespace
+            .as_ref()
+            .map(|namespace| namespace.borrow_mut());
+        let sym_map = pending_syms.finish(&mut output, namespace.as_deref_mut())?;
+
+        if num_localized_syms > 0 {
+            ctx.diagnostics.info(format_args!(
+                "Localized {} defined symbols that are not in the export list",
+                num_localized_syms
+            ));
+        }
+
+        if num_collected_syms > 0 {
+            ctx.diagnostics.info(format_args!(
+                "Dropped {} symbols in collected sections",
+                num_collected_syms
+            ));
+        }
+
+        if num_weakened_syms > 0 {
+            ctx.diagnostics.info(format_args!(
+                "Weakened {} defined global symbols",
+                num_weakened_syms
+            ));
+        }
+
+        if !pruned_syms.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "Pruned {} unreferenced undefined symbols: {}",
+                pruned_syms.len(),
+                pruned_syms.join(", ")
+            ));
+        }
+
+        if !special_syms.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "{} {} linker-synthesized symbols: {}",
+                if self.drop_special_symbols {
+                    "Dropped or demoted"
+                } else {
+                    "Demoted"
+                },
+                special_syms.len(),
+                special_syms.join(", ")
+            ));
+        }
+
+        if !unique_syms.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "Turned {} {} symbols into {} symbols, which are no longer kept unique across loaded modules: {}",
+                unique_syms.len(),
+                symbol_bind_to_str(STB_GNU_UNIQUE),
+                symbol_bind_to_str(STB_GLOBAL),
+                unique_syms.join(", ")
+            ));
+        }
+
+        if !local_entry_syms.is_empty() {
+            ctx.diagnostics.warn(format_args!(
+                "{} functions have local entry points, which other objects enter with the TOC pointer of the final \
+                 link while the converted code expects the TOC pointer of the input shared library: {}",
+                local_entry_syms.len(),
+                local_entry_syms.join(", ")
+            ));
+        }
+
+        if !excluded_syms.is_empty() {
+            ctx.diagnostics.warn(format_args!(
+                "{} symbols are defined in sections that are not copied and {}: {}",
+                excluded_syms.len(),
+                if self.drop_excluded_symbols {
+                    "are dropped or made undefined"
+                } else {
+                    "are made undefined"
+                },
+                excluded_syms.join(", ")
+            ));
+        }
+
+        if !copy_reloc_candidates.is_empty() {
+            if self.demote_exported_data {
+                ctx.diagnostics.info(format_args!(
+                    "Demoted {} exported data objects to local symbols: {}",
+                    copy_reloc_candidates.len(),
+                    copy_reloc_candidates.join(", ")
+                ));
+            } else {
+                ctx.diagnostics.warn(format_args!(
+                    "{} exported data objects may be targets of copy relocations in existing consumers of the input \
+                     shared library, whose references to them break after conversion: {}",
+                    copy_reloc_candidates.len(),
+                    copy_reloc_candidates.join(", ")
+                ));
+            }
+        }
+
+        if !interposing_syms.is_empty() {
+            ctx.diagnostics.warn(format_args!(
+                "{} exported symbols are well-known entry points of system libraries and will interpose them for the \
+                 whole program once linked statically: {}; consider keeping them local with --only-export",
+                interposing_syms.len(),
+                interposing_syms.join(", ")
+            ));
+        }
+
+        if !introspection_syms.is_empty() {
+            ctx.diagnostics.info(format_args!(
+                "The input calls {} functions that find the calling library as a loaded module, which it no longer \
+                 is once linked statically, so these calls may misbehave: {}",
+                introspection_syms.len(),
+                introspection_syms.join("; ")
+            ));
+        }
+
+        Ok(sym_map)
+    }
+}
+
+/// Errors that may occur when generating output symbols.
+#[derive(Debug, Error)]
+pub enum GenerateSymbolError {
+    #[error("read ELF failed: {0:?}")]
+    ReadElfError(#[from] ReadError),
+
+    #[error("symbol \"{name}\" has address {addr:#x} that is not mapped into any output section")]
+    UnmappedSymbol { name: String, addr: u64 },
+
+    #[error("symbol \"{0}\" has a reserved or invalid section index")]
+    UnknownSymbolSection(String),
+
+    #[error("symbol \"{0}\" does not carry ELF symbol flags")]
+    InvalidSymbolFlags(String),
+
+    #[error(
+        "common symbol \"{name}\" has invalid alignment {align:#x}; alignment must be a power of two"
+    )]
+    InvalidCommonAlign { name: String, align: u64 },
+
+    #[error(
+        "renaming results in multiple definitions of symbol \"{name}\", originally {old_names:?}"
+    )]
+    RenameCollision {
+        name: String,
+        old_names: Vec<String>,
+    },
+
+    #[error("symbol \"{name}\" is defined by both {first_input} and {second_input}")]
+    DuplicateDefinition {
+        name: String,
+        first_input: String,
+        second_input: String,
+    },
+}
+
+/// Output symbols that are collected before they are added to the output object, so that they can be added in a
+/// deterministic order regardless of the order in which they are generated.
+#[derive(Debug, Default)]
+struct PendingSymbols<'d> {
+    symbols: Vec<OutputSymbol>,
+    mappings: Vec<(SymbolIndex, &'d [u8], usize)>,
+}
+
+impl<'d> PendingSymbols<'d> {
+    /// Create an empty set of pending symbols with room for the given number of input symbols.
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            symbols: Vec::with_capacity(capacity),
+            mappings: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Add a pending output symbol and return its index among the pending symbols.
+    fn add(&mut self, symbol: OutputSymbol) -> usize {
+        self.symbols.push(symbol);
+        self.symbols.len() - 1
+    }
+
+    /// Map the given input symbol to the pending output symbol with the given index.
+    fn map(&mut self, input_sym: SymbolIndex, input_sym_name: &'d [u8], pending_sym: usize) {
+        self.mappings.push((input_sym, input_sym_name, pending_sym));
+    }
+
+    /// Add the pending symbols to the output object, local symbols before global symbols and each ordered by value and
+    /// name, and build the symbol map. Global symbols are added through the given namespace, if any.
+    fn finish(
+        self,
+        output: &mut OutputObject<'static>,
+        mut namespace: Option<&mut SymbolNamespace>,
+    ) -> Result<SymbolMap, GenerateSymbolError> {
+        let mut symbols: Vec<(usize, OutputSymbol)> =
+            self.symbols.into_iter().enumerate().collect();
+        symbols.sort_by(|(_, lhs), (_, rhs)| {
+            let key = |sym: &OutputSymbol| sym.scope != SymbolScope::Compilation;
+            key(lhs)
+                .cmp(&key(rhs))
+                .then(lhs.value.cmp(&rhs.value))
+                .then_with(|| lhs.name.cmp(&rhs.name))
+        });
+
+        let mut output_syms = vec![None; symbols.len()];
+        for (pending_sym, symbol) in symbols {
+            let output_sym = match namespace.as_deref_mut() {
+                Some(namespace) => namespace.add_symbol(output, symbol)?,
+                None => output.add_symbol(symbol),
+            };
+            output_syms[pending_sym] = Some(output_sym);
         }
 
-        Ok(SymbolMap(sym_map))
+        let mut mappings = self.mappings;
+        mappings.sort_by_key(|(input_sym, _, _)| input_sym.0);
+
+        let mut sym_map = SymbolMap::with_capacity(mappings.len());
+        for (input_sym, name, pending_sym) in mappings {
+            sym_map.insert(input_sym, name, output_syms[pending_sym].unwrap());
+        }
+        Ok(sym_map)
     }
 }
 
+/// The global symbols of the output relocatable file when several input shared libraries are merged into it.
+///
+/// The inputs are converted one after another into the same output. Each global definition and each undefined symbol is
+/// added to the output only once:
+///
+/// - An undefined symbol binds to the definition of an input converted earlier, if any, regardless of its version.
+///   Otherwise, it is shared by all the inputs that refer to it under the same name.
+/// - A definition turns the undefined symbols of inputs converted earlier that refer to it into the definition itself,
+///   so that relocations against them bind to the definition as well.
+/// - Multiple strong definitions of the same symbol are rejected. A weak definition gives way to any other definition
+///   like it does in the final link.
+///
+/// Local symbols are private to each input and are always added as they are.
+#[derive(Debug, Default)]
+pub struct SymbolNamespace {
+    /// Name of the input that is being converted.
+    current_input: String,
+
+    /// Global definitions by plain name.
+    definitions: HashMap<Vec<u8>, NamespaceDefinition>,
+
+    /// Undefined symbols by full name, including the version.
+    references: HashMap<Vec<u8>, SymbolId>,
+
+    /// Number of references that are bound to definitions of other inputs.
+    num_bound: usize,
+}
+
 #[derive(Debug)]
-pub struct SymbolMap(HashMap<SymbolIndex, SymbolId>);
+struct NamespaceDefinition {
+    symbol: SymbolId,
+    input: String,
+    weak: bool,
+}
+
+impl SymbolNamespace {
+    /// Start adding the symbols of the input with the given name, which is used in messages.
+    pub fn begin_input(&mut self, name: &str) {
+        self.current_input = String::from(name);
+    }
+
+    /// Get the number of references that are bound to definitions of other inputs.
+    pub fn num_bound(&self) -> usize {
+        self.num_bound
+    }
+
+    /// Add the given output symbol of the current input to the output, or bind it to an existing global symbol, and
+    /// return the output symbol that represents it.
+    pub(crate) fn add_symbol(
+        &mut self,
+        output: &mut OutputObject<'static>,
+        symbol: OutputSymbol,
+    ) -> Result<SymbolId, GenerateSymbolError> {
+        if symbol.scope == SymbolScope::Compilation || symbol.name.is_empty() {
+            return Ok(output.add_symbol(symbol));
+        }
+
+        let plain_name = get_plain_symbol_name(&symbol.name).to_vec();
+        if symbol.is_undefined() {
+            if let Some(def) = self.definitions.get(&plain_name) {
+                self.num_bound += 1;
+                return Ok(def.symbol);
+            }
+            let name = symbol.name.clone();
+            let output_sym = *self
+                .references
+                .entry(name)
+                .or_insert_with(|| output.add_symbol(symbol));
+            return Ok(output_sym);
+        }
+
+        if let Some(def) = self.definitions.get_mut(&plain_name) {
+            if symbol.weak {
+                return Ok(def.symbol);
+            }
+            if !def.weak {
+                return Err(GenerateSymbolError::DuplicateDefinition {
+                    name: demangle_bytes(&plain_name),
+                    first_input: def.input.clone(),
+                    second_input: self.current_input.clone(),
+                });
+            }
+
+            *output.symbol_mut(def.symbol) = symbol;
+            def.input = self.current_input.clone();
+            def.weak = false;
+            return Ok(def.symbol);
+        }
+
+        // Each version of the symbol that has been referred to has its own undefined symbol. The first one becomes the
+        // definition, and the others become local aliases of it.
+        let mut references: Vec<(Vec<u8>, SymbolId)> = self
+            .references
+            .iter()
+            .filter(|(name, _)| get_plain_symbol_name(name) == plain_name)
+            .map(|(name, output_sym)| (name.clone(), *output_sym))
+            .collect();
+        references.sort();
+        for (name, _) in &references {
+            self.references.remove(name);
+        }
+        self.num_bound += references.len();
+
+        let weak = symbol.weak;
+        let output_sym = match references.split_first() {
+            Some(((_, output_sym), aliases)) => {
+                for (_, alias) in aliases {
+                    let mut alias_sym = OutputSymbol {
+                        name: symbol.name.clone(),
+                        value: symbol.value,
+                        size: symbol.size,
+                        kind: symbol.kind,
+                        scope: symbol.scope,
+                        weak: symbol.weak,
+                        section: symbol.section,
+                        flags: symbol.flags,
+                    };
+                    make_symbol_local(&mut alias_sym);
+                    *output.symbol_mut(*alias) = alias_sym;
+                }
+                *output.symbol_mut(*output_sym) = symbol;
+                *output_sym
+            }
+            None => output.add_symbol(symbol),
+        };
+        self.definitions.insert(
+            plain_name,
+            NamespaceDefinition {
+                symbol: output_sym,
+                input: self.current_input.clone(),
+                weak,
+            },
+        );
+        Ok(output_sym)
+    }
+}
+
+/// Get the name of the given symbol without the version.
+pub(crate) fn get_plain_symbol_name(name: &[u8]) -> &[u8] {
+    match name.iter().position(|c| *c == b'@') {
+        Some(pos) => &name[..pos],
+        None => name,
+    }
+}
+
+/// Maps input dynamic symbols to output symbols.
+///
+/// Several input symbols may map to the same output symbol, e.g. all references to a wrapped symbol map to its
+/// `__wrap_NAME` symbol. The reverse mapping gives the first input symbol that is mapped to an output symbol.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolMap {
+    /// Output symbols indexed by the indexes of the input symbols.
+    output_syms: Vec<Option<SymbolId>>,
+    num_mapped: usize,
+    names: HashMap<Arc<str>, SymbolId>,
+    input_syms: HashMap<SymbolId, (SymbolIndex, Arc<str>)>,
+}
 
 impl SymbolMap {
+    /// Create an empty symbol map with room for the given number of input symbols.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            output_syms: Vec::with_capacity(capacity),
+            num_mapped: 0,
+            names: HashMap::with_capacity(capacity),
+            input_syms: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Map the given input symbol to the given output symbol.
+    pub fn insert(&mut self, input_sym: SymbolIndex, input_sym_name: &[u8], output_sym: SymbolId) {
+        // Input symbols with the same name share the allocation of the name.
+        let name = String::from_utf8_lossy(input_sym_name);
+        let name = match self.names.get_key_value(name.as_ref()) {
+            Some((name, _)) => name.clone(),
+            None => {
+                let name = Arc::<str>::from(name);
+                self.names.insert(name.clone(), output_sym);
+                name
+            }
+        };
+
+        if input_sym.0 >= self.output_syms.len() {
+            self.output_syms.resize(input_sym.0 + 1, None);
+        }
+        if self.output_syms[input_sym.0].replace(output_sym).is_none() {
+            self.num_mapped += 1;
+        }
+        self.input_syms
+            .entry(output_sym)
+            .or_insert((input_sym, name));
+    }
+
     /// Get the output symbol corresponding to the specified input symbol.
     pub fn get_output_symbol(&self, input_sym: SymbolIndex) -> Option<SymbolId> {
-        self.0.get(&input_sym).copied()
+        self.output_syms.get(input_sym.0).copied().flatten()
+    }
+
+    /// Get the output symbol corresponding to the input symbol with the given name, without version. If several input
+    /// symbols have the name, such as different versions of the same symbol, the first one in the input dynamic symbol
+    /// table wins.
+    pub fn get_by_name(&self, name: &str) -> Option<SymbolId> {
+        self.names.get(name).copied()
+    }
+
+    /// Get the index and the name of the input symbol corresponding to the specified output symbol.
+    pub fn get_input_symbol(&self, output_sym: SymbolId) -> Option<(SymbolIndex, &str)> {
+        self.input_syms
+            .get(&output_sym)
+            .map(|(input_sym, name)| (*input_sym, name.as_ref()))
+    }
+
+    /// Iterate over all the mappings from input symbols to output symbols, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (SymbolIndex, SymbolId)> + '_ {
+        self.output_syms
+            .iter()
+            .enumerate()
+            .filter_map(|(input_sym, output_sym)| Some((SymbolIndex(input_sym), (*output_sym)?)))
+    }
+
+    /// Get the number of mapped input symbols.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.num_mapped
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.num_mapped == 0
+    }
+}
+
+/// Version information attached to a dynamic symbol.
+#[derive(Clone, Copy, Debug)]
+struct SymbolVersion<'d> {
+    name: &'d [u8],
+    hidden: bool,
+}
+
+fn get_symbol_version<'d, E>(
+    versions: Option<&VersionTable<'d, E>>,
+    endian: E::Endian,
+    input_sym_idx: SymbolIndex,
+) -> Result<Option<SymbolVersion<'d>>, ReadError>
+where
+    E: ElfFileHeader,
+{
+    let Some(versions) = versions else {
+        return Ok(None);
+    };
+
+    let version_idx = versions.version_index(endian, input_sym_idx.0);
+    let version = versions.version(version_idx)?.map(|version| SymbolVersion {
+        name: version.name(),
+        hidden: version_idx.is_hidden(),
+    });
+    Ok(version)
+}
+
+fn get_versioned_symbol_name(
+    name: &[u8],
+    version: &SymbolVersion,
+    undefined: bool,
+    keep_version: bool,
+) -> Vec<u8> {
+    let separator: &[u8] = if undefined {
+        b"@"
+    } else if keep_version {
+        b"@@"
+    } else {
+        return name.to_vec();
+    };
+
+    [name, separator, version.name].concat()
+}
+
+/// Demote the given output symbol, which defines a non-default version, to a local symbol with a mangled name.
+fn demote_versioned_symbol(output_sym: &mut OutputSymbol, version: &SymbolVersion) {
+    output_sym.name = [&output_sym.name, b".".as_slice(), version.name].concat();
+    make_symbol_local(output_sym);
+}
+
+fn make_symbol_local(output_sym: &mut OutputSymbol) {
+    output_sym.scope = SymbolScope::Compilation;
+    output_sym.weak = false;
+    if let SymbolFlags::Elf { st_info, .. } = &mut output_sym.flags {
+        *st_info = (STB_LOCAL << 4) | (*st_info & 0xF);
+    }
+}
+
+fn make_symbol_weak(output_sym: &mut OutputSymbol) {
+    output_sym.weak = true;
+    if let SymbolFlags::Elf { st_info, .. } = &mut output_sym.flags {
+        *st_info = (STB_WEAK << 4) | (*st_info & 0xF);
+    }
+}
+
+/// Demote the given output symbol to a local symbol with hidden visibility.
+fn make_symbol_hidden(output_sym: &mut OutputSymbol) {
+    make_symbol_local(output_sym);
+    if let SymbolFlags::Elf { st_other, .. } = &mut output_sym.flags {
+        *st_other = (*st_other & !0x3) | STV_HIDDEN;
+    }
+}
+
+/// Create the `__real_NAME` alias of the given definition of a wrapped symbol.
+fn create_real_symbol(name: &[u8], wrapped_sym: &OutputSymbol) -> OutputSymbol {
+    OutputSymbol {
+        name: [b"__real_".as_slice(), name].concat(),
+        value: wrapped_sym.value,
+        size: wrapped_sym.size,
+        kind: wrapped_sym.kind,
+        scope: wrapped_sym.scope,
+        weak: wrapped_sym.weak,
+        section: wrapped_sym.section,
+        flags: wrapped_sym.flags,
+    }
+}
+
+/// Create the undefined `__wrap_NAME` symbol that replaces references to the given wrapped symbol.
+fn create_wrap_symbol(name: &[u8], wrapped_sym: &OutputSymbol) -> OutputSymbol {
+    let st_type = match wrapped_sym.flags {
+        SymbolFlags::Elf { st_info, .. } => st_info & 0xF,
+        _ => 0,
+    };
+    OutputSymbol {
+        name: [b"__wrap_".as_slice(), name].concat(),
+        value: 0,
+        size: 0,
+        kind: wrapped_sym.kind,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: OutputSymbolSection::Undefined,
+        flags: SymbolFlags::Elf {
+            st_info: (STB_GLOBAL << 4) | st_type,
+            st_other: 0,
+        },
+    }
+}
+
+/// Get the offset of the local entry point of a PowerPC64 ELFv2 function from its global entry point, given the
+/// st_other field of the function symbol.
+fn get_ppc64_local_entry_offset(st_other: u8) -> u64 {
+    let local = (st_other & STO_PPC64_LOCAL_MASK) >> STO_PPC64_LOCAL_BIT;
+    ((1u64 << local) >> 2) << 2
+}
+
+fn is_special_symbol(name: &[u8]) -> bool {
+    SPECIAL_SYMBOLS
+        .iter()
+        .any(|special_name| special_name.as_bytes() == name)
+}
+
+/// Collect the input symbols referenced by dynamic relocations that happen within the copied sections.
+fn collect_referenced_symbols(
+    reloc_index: &RelocationIndex,
+    copied_sections: &CopyLodableSectionsOutput,
+) -> HashSet<SymbolIndex> {
+    reloc_index
+        .iter()
+        .filter(|(input_reloc_addr, _)| copied_sections.translate(*input_reloc_addr).is_some())
+        .filter_map(|(_, input_reloc)| match input_reloc.target() {
+            RelocationTarget::Symbol(sym_idx) => Some(sym_idx),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Determine whether the given input symbol is an exported data object in a writable section, which is what consumers
+/// usually access through copy relocations.
+fn is_copy_reloc_candidate<'d, 'f, E, R>(
+    input: &'f ElfFile<'d, E, R>,
+    input_sym: &ElfSymbol<'d, 'f, E, R>,
+) -> bool
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let SymbolFlags::Elf { st_info, st_other } = input_sym.flags() else {
+        return false;
+    };
+    if st_info >> 4 == STB_LOCAL || st_info & 0xF != STT_OBJECT || st_other & 0x3 != STV_DEFAULT {
+        return false;
+    }
+
+    let Some(sec) = input_sym
+        .section_index()
+        .and_then(|sec_idx| input.section_by_index(sec_idx).ok())
+    else {
+        return false;
+    };
+    matches!(sec.flags(), SectionFlags::Elf { sh_flags } if sh_flags & SHF_WRITE as u64 != 0)
+}
+
+/// Create an undefined global output symbol that refers to the given input symbol by its name.
+///
+/// Return `None` if the input symbol is local or unnamed, since the final link cannot resolve such references.
+pub fn create_undefined_symbol<'d, 'f, E, R>(
+    input_sym: &ElfSymbol<'d, 'f, E, R>,
+) -> Result<Option<OutputSymbol>, ReadError>
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    let name = input_sym.name_bytes()?;
+    if name.is_empty() || input_sym.is_local() {
+        return Ok(None);
     }
+
+    let st_type = match input_sym.flags() {
+        SymbolFlags::Elf { st_info, .. } => st_info & 0xF,
+        _ => STT_NOTYPE,
+    };
+    let bind = if input_sym.is_weak() {
+        STB_WEAK
+    } else {
+        STB_GLOBAL
+    };
+    Ok(Some(OutputSymbol {
+        name: name.to_vec(),
+        value: 0,
+        size: 0,
+        kind: input_sym.kind(),
+        scope: SymbolScope::Linkage,
+        weak: input_sym.is_weak(),
+        section: OutputSymbolSection::Undefined,
+        flags: SymbolFlags::Elf {
+            st_info: (bind << 4) | st_type,
+            st_other: 0,
+        },
+    }))
 }
 
 fn create_output_symbol<'d, 'f, E, R>(
     input_sym: &ElfSymbol<'d, 'f, E, R>,
     copied_sections: &CopyLodableSectionsOutput,
-) -> Result<OutputSymbol, ReadError>
+    arch: Architecture,
+    diagnostics: &Diagnostics,
+) -> Result<OutputSymbol, GenerateSymbolError>
 where
     E: ElfFileHeader,
     R: ReadRef<'d>,
 {
     let name = input_sym.name_bytes()?.to_vec();
 
+    let mut value = input_sym.address();
     let section = match input_sym.section() {
         SymbolSection::None => OutputSymbolSection::None,
         SymbolSection::Undefined => OutputSymbolSection::Undefined,
         SymbolSection::Absolute => OutputSymbolSection::Absolute,
-        SymbolSection::Common => OutputSymbolSection::Common,
+        SymbolSection::Common => {
+            // The value of a common symbol is its alignment rather than its address, and the size is the number of bytes
+            // to allocate.
+            let align = value.max(1);
+            if !align.is_power_of_two() {
+                return Err(GenerateSymbolError::InvalidCommonAlign {
+                    name: demangle_bytes(&name),
+                    align,
+                });
+            }
+            value = align;
+            OutputSymbolSection::Common
+        }
         SymbolSection::Section(sec_idx) => {
-            assert!(copied_sections.is_section_copied(sec_idx));
-            OutputSymbolSection::Section(copied_sections.output_section_id)
+            if !copied_sections.is_section_copied(sec_idx) {
+                return Err(GenerateSymbolError::UnmappedSymbol {
+                    name: demangle_bytes(&name),
+                    addr: value,
+                });
+            }
+            // The value of a TLS symbol is its offset within the TLS initialization image. The lowest bit of the value
+            // of a Thumb function is set, which is kept in the output but is not part of the address of the code.
+            let thumb_bit = if arch == Architecture::Arm && input_sym.kind() == SymbolKind::Text {
+                value & 1
+            } else {
+                0
+            };
+            let size = input_sym.size();
+            let loc = if input_sym.kind() == SymbolKind::Tls {
+                copied_sections.translate_tls(value)
+            } else {
+                let addr = value - thumb_bit;
+                let loc = copied_sections.translate_symbol(addr, size);
+                if loc.is_some() && size != 0 && !copied_sections.contains_symbol(addr, size) {
+                    diagnostics.warn(format_args!(
+                        "Symbol \"{}\" at {:#x} with size {:#x} extends past the end of the copied image, so its \
+                         size does not cover all of its bytes in the output",
+                        demangle_bytes(&name),
+                        addr,
+                        size
+                    ));
+                }
+                loc.map(|loc| OutputLocation {
+                    offset: loc.offset + thumb_bit,
+                    ..loc
+                })
+            };
+            let loc = loc.ok_or_else(|| GenerateSymbolError::UnmappedSymbol {
+                name: demangle_bytes(&name),
+                addr: value,
+            })?;
+            value = loc.offset;
+            OutputSymbolSection::Section(loc.section_id)
+        }
+        _ => {
+            return Err(GenerateSymbolError::UnknownSymbolSection(demangle_bytes(
+                &name,
+            )));
         }
-        _ => unreachable!(),
     };
 
     let (mut st_info, st_other) = match input_sym.flags() {
         SymbolFlags::Elf { st_info, st_other } => (st_info, st_other),
-        _ => unreachable!(),
+        _ => {
+            return Err(GenerateSymbolError::InvalidSymbolFlags(demangle_bytes(
+                &name,
+            )))
+        }
     };
 
     let mut bind = st_info >> 4;
@@ -106,55 +1263,92 @@ where
         st_info = (STB_GLOBAL << 4) | (st_info & 0xF);
     }
 
-    let scope = match input_sym.scope() {
-        SymbolScope::Unknown => {
-            if bind == STB_LOCAL {
+    // The visibility itself is kept in st_other. The scope decides whether references to the symbol may be preempted:
+    // relocations against dynamic symbols are bound to their sections by the writer, which is only correct for
+    // protected symbols. Default symbols stay preemptible and hidden symbols are bound by the final link anyway.
+    // Internal definitions cannot be referred to from other objects, so they become local.
+    let mut weak = input_sym.is_weak();
+    let scope = if bind == STB_LOCAL {
+        SymbolScope::Compilation
+    } else {
+        match st_other & 0x3 {
+            STV_INTERNAL if !input_sym.is_undefined() => {
+                st_info = (STB_LOCAL << 4) | (st_info & 0xF);
+                weak = false;
                 SymbolScope::Compilation
-            } else {
-                SymbolScope::Linkage
             }
+            STV_PROTECTED if !input_sym.is_undefined() => SymbolScope::Dynamic,
+            _ => SymbolScope::Linkage,
         }
-        SymbolScope::Dynamic => SymbolScope::Linkage,
-        scope => scope,
     };
 
     Ok(OutputSymbol {
         name,
-        value: input_sym.address(),
+        value,
         size: input_sym.size(),
         kind: input_sym.kind(),
         scope,
-        weak: input_sym.is_weak(),
+        weak,
         section,
         flags: SymbolFlags::Elf { st_info, st_other },
     })
 }
 
 #[cfg(test)]
-mod test {
-    use object::read::elf::ElfFile64;
-    use object::write::Object as OutputObject;
-    use object::{Architecture, BinaryFormat, Endianness};
+pub(crate) mod test {
+    use std::fmt::Arguments;
+    use std::marker::PhantomData;
+    use std::sync::{Arc, Mutex};
+
+    use object::elf::{
+        R_X86_64_64, STB_GLOBAL, STB_GNU_UNIQUE, STB_LOCAL, STO_PPC64_LOCAL_BIT, STT_FUNC,
+        STT_NOTYPE, STT_OBJECT, STV_HIDDEN, VERSYM_HIDDEN,
+    };
+    use object::read::elf::{ElfFile, ElfFile64, FileHeader as ElfFileHeader};
+    use object::{
+        Architecture, BinaryFormat, Endianness, Object as _, ObjectSection as _, ObjectSymbol as _,
+        SymbolFlags, SymbolKind, SymbolScope,
+    };
 
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
     use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::elf::test::builder::{BuilderSymbol, ElfBuilder};
+    use crate::output::Object as OutputObject;
     use crate::pass::test::PassTest;
-    use crate::pass::{Pass, PassHandle, PassManager};
+    use crate::pass::{Diagnostics, Pass, PassHandle, PassManager};
+    use crate::{DiagnosticSink, Severity};
 
-    use super::GenerateSymbolPass;
+    use super::{get_ppc64_local_entry_offset, GenerateSymbolError, GenerateSymbolPass, SymbolMap};
 
-    struct GenerateSymbolPassTest;
+    struct GenerateSymbolPassTest<E> {
+        expected_len: usize,
+        _marker: PhantomData<E>,
+    }
 
-    impl PassTest for GenerateSymbolPassTest {
-        type Input = ElfFile64<'static>;
+    impl<E> GenerateSymbolPassTest<E> {
+        fn new(expected_len: usize) -> Self {
+            Self {
+                expected_len,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<E> PassTest for GenerateSymbolPassTest<E>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        type Input = ElfFile<'static, E>;
         type Pass = GenerateSymbolPass;
 
         fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
             let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
-            pass_mgr.add_pass(GenerateSymbolPass { cls_pass })
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass))
         }
 
         fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
-            assert_eq!(output.0.len(), 1475);
+            assert_eq!(output.len(), self.expected_len);
         }
     }
 
@@ -162,6 +1356,860 @@ mod test {
     fn test_generate_symbol_pass() {
         let input = crate::elf::test::get_test_input_file();
         let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
-        crate::pass::test::run_pass_test(GenerateSymbolPassTest, input, output);
+        crate::pass::test::run_pass_test(GenerateSymbolPassTest::new(1474), input, output);
+
+        let input = crate::elf::test::get_test_input_file32();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+        crate::pass::test::run_pass_test(GenerateSymbolPassTest::new(6), input, output);
+    }
+
+    #[derive(Default)]
+    struct SymbolVersionTest {
+        keep_symbol_versions: bool,
+        expected_names: Vec<&'static str>,
+        sym_map: Option<SymbolMap>,
+    }
+
+    impl PassTest for SymbolVersionTest {
+        type Input = ElfFile64<'static>;
+        type Pass = GenerateSymbolPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(GenerateSymbolPass {
+                keep_symbol_versions: self.keep_symbol_versions,
+                prune_undefined: false,
+                ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            self.sym_map = Some(output.clone());
+        }
+
+        fn check_output_object(&mut self, output: &OutputObject<'static>) {
+            let sym_map = self.sym_map.as_ref().unwrap();
+            for (input_sym_idx, expected_name) in self.expected_names.iter().enumerate() {
+                let output_sym_id = sym_map
+                    .get_output_symbol(object::SymbolIndex(input_sym_idx + 1))
+                    .unwrap();
+                let output_sym = output.symbol(output_sym_id);
+                assert_eq!(String::from_utf8_lossy(&output_sym.name), *expected_name);
+            }
+
+            // The definition of the non-default version is demoted to a local symbol.
+            let output_sym_id = sym_map.get_output_symbol(object::SymbolIndex(3)).unwrap();
+            assert_eq!(output.symbol(output_sym_id).scope, SymbolScope::Compilation);
+        }
+    }
+
+    fn get_versioned_input() -> ElfFile64<'static> {
+        get_versioned_builder().build_elf64()
+    }
+
+    /// Create a builder for an image that defines `func@@VER_2` and `func@VER_1` and imports `memcpy@GLIBC_2.14`.
+    pub(crate) fn get_versioned_builder() -> ElfBuilder {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let ver_1 = builder.add_version_definition("VER_1");
+        let ver_2 = builder.add_version_definition("VER_2");
+        let glibc = builder.add_version_requirement("libc.so.6", "GLIBC_2.14");
+
+        builder.symbols[0].versym = ver_2;
+        builder.symbols[1] = BuilderSymbol {
+            versym: glibc,
+            ..BuilderSymbol::new("memcpy", STB_GLOBAL, STT_FUNC, 0, 0, 0)
+        };
+        builder.add_symbol(BuilderSymbol {
+            versym: ver_1 | VERSYM_HIDDEN,
+            ..BuilderSymbol::new("func", STB_GLOBAL, STT_FUNC, 1, 0x1008, 8)
+        });
+
+        builder
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_versions() {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = SymbolVersionTest {
+            keep_symbol_versions: false,
+            expected_names: vec!["func", "memcpy@GLIBC_2.14", "func.VER_1"],
+            ..Default::default()
+        };
+        crate::pass::test::run_pass_test(test, get_versioned_input(), output);
+
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let test = SymbolVersionTest {
+            keep_symbol_versions: true,
+            expected_names: vec!["func@@VER_2", "memcpy@GLIBC_2.14", "func.VER_1"],
+            ..Default::default()
+        };
+        crate::pass::test::run_pass_test(test, get_versioned_input(), output);
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_demote_exported_data() {
+        let input = crate::elf::test::builder::minimal_dso().build_elf64();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            demote_exported_data: true,
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(input, output).unwrap();
+
+        // The exported function is kept while the exported data object in .data is demoted.
+        let func = output.symbol_id(b"func").unwrap();
+        assert_eq!(output.symbol(func).scope, SymbolScope::Linkage);
+        let data = output.symbol_id(b"data").unwrap();
+        assert_eq!(output.symbol(data).scope, SymbolScope::Compilation);
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_section_end() {
+        // data_end refers to the end of .data, which is also the end of the image.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_symbol(BuilderSymbol::new(
+            "data_end", STB_GLOBAL, STT_NOTYPE, 2, 0x2010, 0,
+        ));
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let end = output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok("data_end"))
+            .unwrap();
+        assert_eq!(end.address(), 0x2010);
+        let end_section = output_file.section_by_index(end.section_index().unwrap());
+        assert_eq!(end_section.unwrap().name(), Ok(".soda"));
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_section_seams() {
+        // .data.rel.ro ends where .data starts, and .data ends at the end of the image.
+        let mut builder = crate::elf::test::builder::relro_dso();
+        let markers = [
+            ("relro_start", 3, 0x2000, 0),
+            ("relro_end", 3, 0x3000, 0),
+            ("data_end", 2, 0x3010, 0),
+            ("straddle", 2, 0x3008, 0x10),
+        ];
+        for (name, shndx, value, size) in markers {
+            builder.add_symbol(BuilderSymbol::new(
+                name, STB_GLOBAL, STT_NOTYPE, shndx, value, size,
+            ));
+        }
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let get_location = |name: &str| {
+            let sym = output_file
+                .symbols()
+                .find(|sym| sym.name() == Ok(name))
+                .unwrap();
+            let section = output_file.section_by_index(sym.section_index().unwrap());
+            format!("{}+{:#x}", section.unwrap().name().unwrap(), sym.address())
+        };
+        assert_eq!(get_location("relro_start"), ".soda+0x2000");
+        assert_eq!(get_location("relro_end"), ".soda+0x3000");
+        assert_eq!(get_location("data"), ".soda+0x3000");
+        // The marker at the end of the image is translated into the end of the output section.
+        assert_eq!(get_location("data_end"), ".soda+0x3010");
+        assert_eq!(get_location("straddle"), ".soda+0x3008");
+
+        // Only the symbol that extends past the end of the image is reported, rather than the marker at it.
+        let warnings = sink.warnings.lock().unwrap();
+        let seam_warnings: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.contains("extends past the end of the copied image"))
+            .collect();
+        assert_eq!(
+            seam_warnings,
+            vec![
+                "Symbol \"straddle\" at 0x3008 with size 0x10 extends past the end of the copied image, so its size \
+                 does not cover all of its bytes in the output"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_thumb_function() {
+        // The lowest bit of the value of thumb_func marks it as a Thumb function.
+        let mut builder = crate::elf::test::builder::minimal_dso().into_arm();
+        builder.add_symbol(BuilderSymbol::new(
+            "thumb_func",
+            STB_GLOBAL,
+            STT_FUNC,
+            1,
+            0x1005,
+            4,
+        ));
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let output = pass_mgr.run(builder.build_elf32(), output).unwrap();
+
+        // The bit is kept in the value of the output symbol.
+        let thumb_func = output.symbol_id(b"thumb_func").unwrap();
+        assert_eq!(output.symbol(thumb_func).value, 0x1005);
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_ppc64_local_entry() {
+        // func has a local entry point 8 bytes after its global entry point.
+        let mut builder = crate::elf::test::builder::minimal_dso().into_ppc64le();
+        builder.symbols[0].st_other = 3 << STO_PPC64_LOCAL_BIT;
+        let output = OutputObject::new(
+            BinaryFormat::Elf,
+            Architecture::PowerPc64,
+            Endianness::Little,
+        );
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            only_export: Some(vec![String::from("data")]),
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        // The local entry offset survives hiding the symbol.
+        let func = output.symbol_id(b"func").unwrap();
+        assert_eq!(
+            output.symbol(func).flags,
+            SymbolFlags::Elf {
+                st_info: (STB_LOCAL << 4) | STT_FUNC,
+                st_other: (3 << STO_PPC64_LOCAL_BIT) | STV_HIDDEN,
+            }
+        );
+        assert_eq!(get_ppc64_local_entry_offset(3 << STO_PPC64_LOCAL_BIT), 8);
+        assert_eq!(get_ppc64_local_entry_offset(1 << STO_PPC64_LOCAL_BIT), 0);
+        assert_eq!(get_ppc64_local_entry_offset(STV_HIDDEN), 0);
+    }
+
+    #[derive(Debug, Default)]
+    struct CollectingSink {
+        infos: Mutex<Vec<String>>,
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl DiagnosticSink for CollectingSink {
+        fn report(&self, severity: Severity, message: Arguments<'_>) {
+            match severity {
+                Severity::Info => self.infos.lock().unwrap().push(message.to_string()),
+                Severity::Warning => self.warnings.lock().unwrap().push(message.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_introspection() {
+        // dlopen and dl_iterate_phdr are called, while dladdr is pruned since nothing refers to it.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let dlopen =
+            builder.add_symbol(BuilderSymbol::new("dlopen", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        let iterate = builder.add_symbol(BuilderSymbol::new(
+            "dl_iterate_phdr",
+            STB_GLOBAL,
+            STT_FUNC,
+            0,
+            0,
+            0,
+        ));
+        builder.add_symbol(BuilderSymbol::new("dladdr", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_relocation(0x2000, R_X86_64_64, dlopen, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, iterate, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let infos = sink.infos.lock().unwrap();
+        let info = infos
+            .iter()
+            .find(|info| info.starts_with("The input calls"))
+            .unwrap();
+        assert!(info.contains("calls 2 functions"), "{}", info);
+        assert!(info.contains("dlopen (dlopen(NULL) gives"), "{}", info);
+        assert!(info.contains("dl_iterate_phdr ("), "{}", info);
+        assert!(!info.contains("dladdr"), "{}", info);
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_unique_symbols() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_symbol(BuilderSymbol::new(
+            "_ZZ4funcvE5guard",
+            STB_GNU_UNIQUE,
+            STT_OBJECT,
+            2,
+            0x2008,
+            8,
+        ));
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut pass_mgr = PassManager::new();
+        pass_mgr.set_diagnostics(Diagnostics::new(Some(sink.clone())));
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        assert!(sink.infos.lock().unwrap().contains(&String::from(
+            "Turned 1 STB_GNU_UNIQUE symbols into STB_GLOBAL symbols, which are no longer kept unique across loaded \
+             modules: func()::guard (STT_OBJECT)"
+        )));
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_unmapped_symbol() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_symbol(BuilderSymbol::new(
+            "bad", STB_GLOBAL, STT_FUNC, 1, 0x5000, 0,
+        ));
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<GenerateSymbolError>(),
+            Some(GenerateSymbolError::UnmappedSymbol { name, addr: 0x5000 }) if name == "bad"
+        ));
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_prune_undefined() {
+        // .data[0] refers to the undefined symbol "used".
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let used = builder.add_symbol(BuilderSymbol::new("used", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_symbol(BuilderSymbol::new("unused", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_symbol(BuilderSymbol::new("kept", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_relocation(0x2000, R_X86_64_64, used, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            keep_undefined: vec![String::from("kept")],
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut undefined_names: Vec<&str> = output_file
+            .symbols()
+            .filter(|sym| sym.is_undefined() && sym.index().0 != 0)
+            .map(|sym| sym.name().unwrap())
+            .collect();
+        undefined_names.sort();
+        assert_eq!(undefined_names, vec!["kept", "used"]);
+    }
+
+    fn get_special_symbols_input() -> ElfFile64<'static> {
+        // .data[0] refers to __dso_handle.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_symbol(BuilderSymbol::new(
+            "_init", STB_GLOBAL, STT_FUNC, 1, 0x1000, 0,
+        ));
+        builder.add_symbol(BuilderSymbol::new(
+            "_DYNAMIC", STB_GLOBAL, STT_OBJECT, 2, 0x2000, 0,
+        ));
+        builder.add_symbol(BuilderSymbol::new(
+            "_end", STB_GLOBAL, STT_NOTYPE, 2, 0x2010, 0,
+        ));
+        let dso_handle = builder.add_symbol(BuilderSymbol::new(
+            "__dso_handle",
+            STB_GLOBAL,
+            STT_OBJECT,
+            2,
+            0x2008,
+            8,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_64, dso_handle, 0);
+        builder.build_elf64()
+    }
+
+    fn run_special_symbols_test(drop_special_symbols: bool) -> Vec<(String, bool)> {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            drop_special_symbols,
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(get_special_symbols_input(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut syms: Vec<(String, bool)> = output_file
+            .symbols()
+            .filter(|sym| sym.kind() != SymbolKind::Section && sym.index().0 != 0)
+            .map(|sym| (String::from(sym.name().unwrap()), sym.is_global()))
+            .collect();
+        syms.sort();
+        syms
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_special_symbols() {
+        let expected_syms = |names: &[&str]| -> Vec<(String, bool)> {
+            names
+                .iter()
+                .map(|name| (String::from(*name), !name.starts_with(".soda.")))
+                .collect()
+        };
+
+        assert_eq!(
+            run_special_symbols_test(false),
+            expected_syms(&[
+                ".soda._DYNAMIC",
+                ".soda.__dso_handle",
+                ".soda._end",
+                ".soda._init",
+                "data",
+                "func"
+            ])
+        );
+        assert_eq!(
+            run_special_symbols_test(true),
+            expected_syms(&[".soda.__dso_handle", "data", "func"])
+        );
+    }
+
+    /// Run the pass on an input whose exports `excluded` and `excluded_used` are defined in a section that is not copied,
+    /// and get the names of the undefined output symbols. `excluded_used` is referred to by a relocation.
+    fn run_excluded_symbols_test(drop_excluded_symbols: bool) -> Vec<String> {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let comment =
+            builder.add_section(".comment", object::elf::SHT_PROGBITS, 0, 0, vec![0u8; 0x10]);
+        builder.add_symbol(BuilderSymbol::new(
+            "excluded", STB_GLOBAL, STT_OBJECT, comment, 0, 8,
+        ));
+        let used = builder.add_symbol(BuilderSymbol::new(
+            "excluded_used",
+            STB_GLOBAL,
+            STT_OBJECT,
+            comment,
+            8,
+            8,
+        ));
+        builder.add_relocation(0x2000, R_X86_64_64, used, 0);
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            drop_excluded_symbols,
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut undefined_names: Vec<String> = output_file
+            .symbols()
+            .filter(|sym| sym.is_undefined() && sym.index().0 != 0)
+            .map(|sym| {
+                assert!(sym.is_global());
+                String::from(sym.name().unwrap())
+            })
+            .collect();
+        undefined_names.sort();
+        undefined_names
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_excluded_symbols() {
+        assert_eq!(
+            run_excluded_symbols_test(false),
+            vec![String::from("excluded"), String::from("excluded_used")]
+        );
+        assert_eq!(
+            run_excluded_symbols_test(true),
+            vec![String::from("excluded_used")]
+        );
+    }
+
+    /// Run the pass on the given input, and get the name, whether undefined and whether weak of each global output
+    /// symbol.
+    fn run_weaken_test<E>(
+        input: ElfFile<'static, E>,
+        arch: Architecture,
+        weaken: bool,
+    ) -> Vec<(String, bool, bool)>
+    where
+        E: ElfFileHeader<Endian = Endianness> + 'static,
+    {
+        let output = OutputObject::new(BinaryFormat::Elf, arch, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            weaken,
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(input, output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        output_file
+            .symbols()
+            .filter(|sym| sym.is_global())
+            .map(|sym| {
+                (
+                    String::from(sym.name().unwrap()),
+                    sym.is_undefined(),
+                    sym.is_weak(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_weaken() {
+        let check = |original_syms: Vec<(String, bool, bool)>,
+                     weakened_syms: Vec<(String, bool, bool)>| {
+            assert_eq!(original_syms.len(), weakened_syms.len());
+            assert!(original_syms
+                .iter()
+                .any(|(_, undefined, weak)| !undefined && !weak));
+
+            for (original, weakened) in original_syms.iter().zip(&weakened_syms) {
+                let (name, undefined, original_weak) = original;
+                assert_eq!(name, &weakened.0);
+                if *undefined {
+                    assert_eq!(*original_weak, weakened.2, "{} is changed", name);
+                } else {
+                    assert!(weakened.2, "{} is not weak", name);
+                }
+            }
+        };
+
+        let get_input = crate::elf::test::get_test_input_file;
+        check(
+            run_weaken_test(get_input(), Architecture::X86_64, false),
+            run_weaken_test(get_input(), Architecture::X86_64, true),
+        );
+
+        let get_input = crate::elf::test::get_test_input_file32;
+        check(
+            run_weaken_test(get_input(), Architecture::I386, false),
+            run_weaken_test(get_input(), Architecture::I386, true),
+        );
+    }
+
+    /// Run the pass with the given export list and wrapped symbols on the given input, and get the name, whether global
+    /// and whether hidden of each output symbol. Undefined symbols are not pruned.
+    fn run_only_export_test(
+        input: ElfFile64<'static>,
+        only_export: &[&str],
+        wrap: &[&str],
+    ) -> Vec<(String, bool, bool)> {
+        let to_strings = |names: &[&str]| names.iter().map(|name| String::from(*name)).collect();
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            keep_symbol_versions: true,
+            prune_undefined: false,
+            only_export: Some(to_strings(only_export)),
+            wrap: to_strings(wrap),
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let output = pass_mgr.run(input, output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut syms: Vec<(String, bool, bool)> = output_file
+            .symbols()
+            .filter(|sym| sym.kind() != SymbolKind::Section && !sym.name().unwrap().is_empty())
+            .map(|sym| {
+                let SymbolFlags::Elf { st_other, .. } = sym.flags() else {
+                    panic!("unexpected symbol flags");
+                };
+                (
+                    String::from(sym.name().unwrap()),
+                    sym.is_global(),
+                    st_other & 0x3 == STV_HIDDEN,
+                )
+            })
+            .collect();
+        syms.sort();
+        syms
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_only_export() {
+        let sym = |name: &str, global: bool, hidden: bool| (String::from(name), global, hidden);
+
+        assert_eq!(
+            run_only_export_test(
+                crate::elf::test::builder::minimal_dso().build_elf64(),
+                &["f*"],
+                &[]
+            ),
+            vec![sym("data", false, true), sym("func", true, false)]
+        );
+
+        // Patterns match the plain names of versioned symbols. The versions are removed from localized symbols, and
+        // undefined symbols are untouched.
+        assert_eq!(
+            run_only_export_test(get_versioned_input(), &["func"], &[]),
+            vec![
+                sym("func.VER_1", false, false),
+                sym("func@@VER_2", true, false),
+                sym("memcpy@GLIBC_2.14", true, false),
+            ]
+        );
+        assert_eq!(
+            run_only_export_test(get_versioned_input(), &["memcpy"], &[]),
+            vec![
+                sym("func", false, true),
+                sym("func.VER_1", false, false),
+                sym("memcpy@GLIBC_2.14", true, false),
+            ]
+        );
+
+        // The __real_NAME alias of a wrapped symbol is matched by its own name.
+        let input = || crate::elf::test::builder::minimal_dso().build_elf64();
+        assert_eq!(
+            run_only_export_test(input(), &["func"], &["func"]),
+            vec![
+                sym("__real_func", false, true),
+                sym("__wrap_func", true, false),
+                sym("data", false, true),
+                sym("func", true, false),
+            ]
+        );
+        assert_eq!(
+            run_only_export_test(input(), &["__real_*"], &["func"]),
+            vec![
+                sym("__real_func", true, false),
+                sym("__wrap_func", true, false),
+                sym("data", false, true),
+                sym("func", false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_extended_section_index() {
+        use object::elf::{PF_R, PF_X, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR, SHN_XINDEX};
+
+        let mut builder = ElfBuilder::new();
+        builder.add_filler_sections(0xFF00);
+        let text = builder.add_section(
+            ".text",
+            object::elf::SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            0x1000,
+            vec![0xC3; 0x10],
+        );
+        assert!(text > 0xFF00);
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, 0x10);
+        builder.add_symbol(BuilderSymbol {
+            xindex: text as u32,
+            ..BuilderSymbol::new("func", STB_GLOBAL, STT_FUNC, SHN_XINDEX, 0x1008, 8)
+        });
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let func = output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok("func"))
+            .unwrap();
+        assert_eq!(func.address(), 0x1008);
+        let func_section = output_file.section_by_index(func.section_index().unwrap());
+        assert_eq!(func_section.unwrap().name(), Ok(".soda"));
+
+        // Reserved section indexes other than SHN_XINDEX are rejected.
+        builder.symbols[0].st_shndx = object::elf::SHN_LOPROC;
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let err = pass_mgr.run(builder.build_elf64(), output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<GenerateSymbolError>(),
+            Some(GenerateSymbolError::UnknownSymbolSection(name)) if name == "func"
+        ));
+    }
+
+    struct SymbolMapTest;
+
+    impl PassTest for SymbolMapTest {
+        type Input = ElfFile64<'static>;
+        type Pass = GenerateSymbolPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(GenerateSymbolPass {
+                prune_undefined: false,
+                ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            // The null symbol is mapped as well.
+            assert_eq!(output.len(), 4);
+            assert_eq!(output.iter().count(), 4);
+
+            // func@@VER_2 comes before func@VER_1 in the input.
+            let func = output.get_output_symbol(object::SymbolIndex(1)).unwrap();
+            assert_eq!(output.get_by_name("func"), Some(func));
+            assert_eq!(
+                output.get_input_symbol(func),
+                Some((object::SymbolIndex(1), "func"))
+            );
+
+            let memcpy = output.get_by_name("memcpy").unwrap();
+            assert_eq!(
+                output.get_input_symbol(memcpy),
+                Some((object::SymbolIndex(2), "memcpy"))
+            );
+
+            assert_eq!(output.get_by_name("memcpy@GLIBC_2.14"), None);
+            assert_eq!(output.get_by_name("data"), None);
+            assert_eq!(output.get_output_symbol(object::SymbolIndex(100)), None);
+        }
+    }
+
+    #[test]
+    fn test_symbol_map() {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(SymbolMapTest, get_versioned_input(), output);
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_rename_collision() {
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass {
+            redefine_syms: vec![(String::from("func"), String::from("data"))],
+            ..GenerateSymbolPass::new(cls_pass, reloc_index_pass)
+        });
+        let input = crate::elf::test::builder::minimal_dso().build_elf64();
+        let err = pass_mgr.run(input, output).unwrap_err();
+        assert!(matches!(
+            err.error.downcast_ref::<GenerateSymbolError>(),
+            Some(GenerateSymbolError::RenameCollision { name, old_names })
+                if name == "data" && old_names.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_generate_symbol_pass_visibility() {
+        use object::elf::{STV_DEFAULT, STV_INTERNAL, STV_PROTECTED};
+
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        for (name, st_other) in [
+            ("default", STV_DEFAULT),
+            ("protected", STV_PROTECTED),
+            ("hidden", STV_HIDDEN),
+            ("internal", STV_INTERNAL),
+        ] {
+            builder.add_symbol(BuilderSymbol {
+                st_other,
+                ..BuilderSymbol::new(name, STB_GLOBAL, STT_FUNC, 1, 0x1004, 0)
+            });
+        }
+        let output = OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(GenerateSymbolPass::new(cls_pass, reloc_index_pass));
+        let output = pass_mgr.run(builder.build_elf64(), output).unwrap();
+
+        let scope = |name: &str| {
+            output
+                .symbol(output.symbol_id(name.as_bytes()).unwrap())
+                .scope
+        };
+        assert_eq!(scope("default"), SymbolScope::Linkage);
+        assert_eq!(scope("protected"), SymbolScope::Dynamic);
+        assert_eq!(scope("hidden"), SymbolScope::Linkage);
+        assert_eq!(scope("internal"), SymbolScope::Compilation);
+
+        // The visibility is preserved, and internal symbols become local.
+        let output_data = output.write().unwrap();
+        let output_file = object::File::parse(&*output_data).unwrap();
+        let mut syms: Vec<(String, bool, u8)> = output_file
+            .symbols()
+            .filter(|sym| sym.kind() == SymbolKind::Text && sym.address() == 0x1004)
+            .map(|sym| {
+                let SymbolFlags::Elf { st_other, .. } = sym.flags() else {
+                    panic!("unexpected symbol flags");
+                };
+                (
+                    String::from(sym.name().unwrap()),
+                    sym.is_global(),
+                    st_other & 0x3,
+                )
+            })
+            .collect();
+        syms.sort();
+        assert_eq!(
+            syms,
+            vec![
+                (String::from("default"), true, STV_DEFAULT),
+                (String::from("hidden"), true, STV_HIDDEN),
+                (String::from("internal"), false, STV_INTERNAL),
+                (String::from("protected"), true, STV_PROTECTED),
+            ]
+        );
     }
 }
@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::{Object as _, ObjectSymbol as _, ObjectSymbolTable as _, ReadRef, SymbolScope};
+
+use crate::elf::pass::boundary::GenerateBoundarySymbolsPass;
+use crate::elf::pass::defsym::DefineSymbolsPass;
+use crate::elf::pass::require::RequireSymbolsPass;
+use crate::elf::pass::symbol::{get_plain_symbol_name, GenerateSymbolPass};
+use crate::output::{Object as OutputObject, SymbolId, SymbolSection as OutputSymbolSection};
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::utils::stringify::json_string;
+
+/// A pass that lists the global symbols that the output defines and requires into a [`SymbolList`].
+///
+/// The pass runs after all the passes that add or change global symbols, so the list reflects the renamed, wrapped,
+/// weakened and pruned symbols of the output rather than the symbols of the input.
+#[derive(Debug)]
+pub struct ListSymbolsPass {
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+    pub defsym_pass: Option<PassHandle<DefineSymbolsPass>>,
+    pub require_pass: Option<PassHandle<RequireSymbolsPass>>,
+    pub boundary_pass: PassHandle<GenerateBoundarySymbolsPass>,
+
+    /// The list that receives the symbols.
+    pub list: Arc<SymbolList>,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for ListSymbolsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "list symbols";
+
+    type Output = ();
+    type Error = Infallible;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let sym_map = ctx.get_pass_output(self.sym_gen_pass);
+        let output = ctx.output.borrow();
+        let input_symtab = ctx.input.dynamic_symbol_table();
+
+        let mut listed = HashSet::new();
+        let mut symbols = Vec::new();
+        for (input_sym_idx, output_sym) in sym_map.iter() {
+            let input_sym = input_symtab
+                .as_ref()
+                .and_then(|symtab| symtab.symbol_by_index(input_sym_idx).ok());
+            let input_addr = input_sym
+                .filter(|sym| !sym.is_undefined())
+                .map(|sym| sym.address());
+            // References that are bound to a definition outside of the input are listed with the definition.
+            if input_addr.is_none() && !output.symbol(output_sym).is_undefined() {
+                continue;
+            }
+            if listed.insert(output_sym) {
+                symbols.extend(list_symbol(&output, output_sym, input_addr));
+            }
+        }
+
+        let added_syms = [
+            self.defsym_pass.map(|pass| ctx.get_pass_output(pass)),
+            self.require_pass.map(|pass| ctx.get_pass_output(pass)),
+            Some(ctx.get_pass_output(self.boundary_pass)),
+        ];
+        for output_sym in added_syms.into_iter().flatten().flatten() {
+            if listed.insert(*output_sym) {
+                symbols.extend(list_symbol(&output, *output_sym, None));
+            }
+        }
+
+        ctx.diagnostics.debug(format_args!(
+            "Listed {} global output symbols",
+            symbols.len()
+        ));
+        self.list.add(symbols);
+
+        Ok(())
+    }
+}
+
+/// Get the listed symbol of the given output symbol, unless it's local.
+fn list_symbol(
+    output: &OutputObject<'static>,
+    output_sym: SymbolId,
+    input_address: Option<u64>,
+) -> Option<ListedSymbol> {
+    let sym = output.symbol(output_sym);
+    if sym.scope == SymbolScope::Compilation || sym.name.is_empty() {
+        return None;
+    }
+
+    let (section, output_offset) = match sym.section {
+        OutputSymbolSection::Section(section_id) => (
+            output.section(section_id).name().map(String::from),
+            Some(sym.value),
+        ),
+        OutputSymbolSection::Absolute => (None, Some(sym.value)),
+        _ => (None, None),
+    };
+    Some(ListedSymbol {
+        name: String::from_utf8_lossy(&sym.name).into_owned(),
+        defined: !sym.is_undefined(),
+        weak: sym.weak,
+        size: sym.size,
+        input_address,
+        section,
+        output_offset,
+    })
+}
+
+/// The global symbols that the outputs of conversions define and require, collected by [`ListSymbolsPass`].
+///
+/// The list is shared by the conversions that are given it in [`ConvertOptions::symbol_list`](crate::ConvertOptions),
+/// so the symbols of all the inputs merged into an output are listed together.
+#[derive(Debug, Default)]
+pub struct SymbolList {
+    symbols: Mutex<Vec<ListedSymbol>>,
+}
+
+/// A global symbol of an output.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ListedSymbol {
+    /// Name of the symbol in the output.
+    pub name: String,
+
+    /// Whether the output defines the symbol, rather than requires it.
+    pub defined: bool,
+
+    /// Whether the symbol is weak.
+    pub weak: bool,
+
+    /// Size of the symbol.
+    pub size: u64,
+
+    /// Address of the definition in the input shared library. It's `None` for undefined symbols and symbols that are
+    /// not in the input.
+    pub input_address: Option<u64>,
+
+    /// Name of the output section that the symbol is defined in. It's `None` for undefined and absolute symbols.
+    pub section: Option<String>,
+
+    /// Offset of the symbol in its output section, or the value of an absolute symbol. It's `None` for undefined
+    /// symbols.
+    pub output_offset: Option<u64>,
+}
+
+impl SymbolList {
+    /// Create an empty `SymbolList`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the listed symbols, sorted by name.
+    ///
+    /// Undefined symbols are left out if another merged input defines a symbol with the same name, which the reference
+    /// was bound to after it had been listed.
+    pub fn symbols(&self) -> Vec<ListedSymbol> {
+        let mut symbols = self.symbols.lock().unwrap().clone();
+        symbols.sort();
+        symbols.dedup();
+
+        let defined: HashSet<Vec<u8>> = symbols
+            .iter()
+            .filter(|sym| sym.defined)
+            .map(|sym| get_plain_symbol_name(sym.name.as_bytes()).to_vec())
+            .collect();
+        symbols.retain(|sym| {
+            sym.defined || !defined.contains(get_plain_symbol_name(sym.name.as_bytes()))
+        });
+        symbols
+    }
+
+    /// Format the listed symbols as JSON lines, one object per symbol sorted by name, such as:
+    ///
+    /// ```text
+    /// {"name":"foo","defined":true,"weak":false,"size":16,"input_address":4096,"section":".soda","output_offset":4096}
+    /// {"name":"malloc","defined":false,"weak":false,"size":0,"input_address":null,"section":null,"output_offset":null}
+    /// ```
+    ///
+    /// The fields are the fields of [`ListedSymbol`], and always appear in this order.
+    pub fn to_json_lines(&self) -> String {
+        let number = |value: Option<u64>| {
+            value.map_or_else(|| String::from("null"), |value| value.to_string())
+        };
+        self.symbols()
+            .iter()
+            .map(|sym| {
+                format!(
+                    "{{\"name\":{},\"defined\":{},\"weak\":{},\"size\":{},\"input_address\":{},\"section\":{},\
+                     \"output_offset\":{}}}\n",
+                    json_string(&sym.name),
+                    sym.defined,
+                    sym.weak,
+                    sym.size,
+                    number(sym.input_address),
+                    sym.section
+                        .as_deref()
+                        .map_or_else(|| String::from("null"), json_string),
+                    number(sym.output_offset)
+                )
+            })
+            .collect()
+    }
+
+    fn add(&self, symbols: Vec<ListedSymbol>) {
+        self.symbols.lock().unwrap().extend(symbols);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use object::elf::{R_X86_64_64, STB_GLOBAL, STT_FUNC, STT_NOTYPE};
+
+    use crate::elf::test::builder::BuilderSymbol;
+    use crate::{ConvertOptions, SymbolValue};
+
+    use super::SymbolList;
+
+    #[test]
+    fn test_list_symbols_pass() {
+        // The input refers to malloc and to vendor_hook, and declares unused without referring to it.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let malloc =
+            builder.add_symbol(BuilderSymbol::new("malloc", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        let hook = builder.add_symbol(BuilderSymbol::new(
+            "vendor_hook",
+            STB_GLOBAL,
+            STT_NOTYPE,
+            0,
+            0,
+            0,
+        ));
+        builder.add_symbol(BuilderSymbol::new("unused", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_relocation(0x2000, R_X86_64_64, malloc, 0);
+        builder.add_relocation(0x2008, R_X86_64_64, hook, 0);
+
+        let list = Arc::new(SymbolList::new());
+        let options = ConvertOptions {
+            wrap: vec![String::from("malloc")],
+            redefine_syms: vec![(String::from("data"), String::from("vendor_data"))],
+            defsyms: vec![(String::from("vendor_hook"), SymbolValue::Absolute(0x40))],
+            require_defined: vec![String::from("vendor_init")],
+            symbol_list: Some(list.clone()),
+            ..Default::default()
+        };
+        crate::convert_elf(&builder.build(), &options).unwrap();
+
+        assert_eq!(
+            list.to_json_lines(),
+            concat!(
+                r#"{"name":"__soda_image_end","defined":true,"weak":false,"size":0,"input_address":null,"section":".soda","output_offset":8208}"#,
+                "\n",
+                r#"{"name":"__soda_image_start","defined":true,"weak":false,"size":0,"input_address":null,"section":".soda","output_offset":0}"#,
+                "\n",
+                r#"{"name":"__wrap_malloc","defined":false,"weak":false,"size":0,"input_address":null,"section":null,"output_offset":null}"#,
+                "\n",
+                r#"{"name":"func","defined":true,"weak":false,"size":16,"input_address":4096,"section":".soda","output_offset":4096}"#,
+                "\n",
+                r#"{"name":"vendor_data","defined":true,"weak":false,"size":8,"input_address":8192,"section":".soda","output_offset":8192}"#,
+                "\n",
+                r#"{"name":"vendor_hook","defined":true,"weak":false,"size":0,"input_address":null,"section":null,"output_offset":64}"#,
+                "\n",
+                r#"{"name":"vendor_init","defined":false,"weak":false,"size":0,"input_address":null,"section":null,"output_offset":null}"#,
+                "\n",
+            )
+        );
+    }
+}
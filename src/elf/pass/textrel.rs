@@ -0,0 +1,237 @@
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::read::Error as ReadError;
+use object::{Object as _, ObjectSection as _, ReadRef, SectionKind};
+use thiserror::Error;
+
+use crate::elf::pass::reloc_index::IndexRelocationsPass;
+use crate::elf::pass::section::CopyLodableSectionsPass;
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that finds text relocations, which are dynamic relocations whose sites lie in executable input sections.
+///
+/// Shared libraries built without `-fPIC` have them, and are marked with DT_TEXTREL or DF_TEXTREL. They are converted
+/// like any other relocation, but the output then needs a final link that permits text relocations, so that links with
+/// `-z text` fail. The pass reports them, or fails if they are rejected.
+#[derive(Debug)]
+pub struct CheckTextRelocationsPass {
+    pub cls_pass: PassHandle<CopyLodableSectionsPass>,
+    pub reloc_index_pass: PassHandle<IndexRelocationsPass>,
+
+    /// Fail if the input has any text relocation.
+    pub reject: bool,
+}
+
+impl<'d, E, R> Pass<ElfFile<'d, E, R>> for CheckTextRelocationsPass
+where
+    E: ElfFileHeader,
+    R: ReadRef<'d>,
+{
+    const NAME: &'static str = "check text relocations";
+
+    type Output = TextRelocations;
+    type Error = CheckTextRelocationsError;
+
+    fn run(&mut self, ctx: &PassContext<ElfFile<'d, E, R>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let reloc_index = ctx.get_pass_output(self.reloc_index_pass);
+
+        let mut sections: Vec<(String, usize)> = Vec::new();
+        let mut last_section = None;
+        for (addr, _) in reloc_index.in_range(0..u64::MAX) {
+            let Some(section_idx) = cls_output.find_input_section(addr) else {
+                continue;
+            };
+            // Relocations are visited in address order, so those in the same section are adjacent.
+            let is_text = match last_section {
+                Some((last_idx, is_text)) if last_idx == section_idx => is_text,
+                _ => {
+                    let section = ctx.input.section_by_index(section_idx)?;
+                    let is_text = section.kind() == SectionKind::Text;
+                    if is_text {
+                        sections.push((String::from(section.name()?), 0));
+                    }
+                    last_section = Some((section_idx, is_text));
+                    is_text
+                }
+            };
+            if is_text {
+                sections.last_mut().unwrap().1 += 1;
+            }
+        }
+
+        let text_relocs = TextRelocations {
+            marked: crate::elf::has_text_relocations(&ctx.input)?,
+            sections,
+        };
+        if text_relocs.count() == 0 {
+            if text_relocs.marked {
+                ctx.diagnostics.debug(format_args!(
+                    "The input is marked with DT_TEXTREL, but none of its dynamic relocations lies in executable \
+                     sections"
+                ));
+            }
+            return Ok(text_relocs);
+        }
+
+        if self.reject {
+            return Err(CheckTextRelocationsError::TextRelocations(
+                text_relocs.sections,
+            ));
+        }
+        ctx.diagnostics.warn(format_args!(
+            "{} dynamic relocations patch executable sections: {}; the input was likely built without -fPIC, and the \
+             output can only be linked where text relocations are permitted, so linking it with `-z text` fails",
+            text_relocs.count(),
+            format_section_counts(&text_relocs.sections)
+        ));
+
+        Ok(text_relocs)
+    }
+}
+
+/// The output of [`CheckTextRelocationsPass`].
+#[derive(Debug)]
+pub struct TextRelocations {
+    /// Whether the input is marked with DT_TEXTREL or DF_TEXTREL.
+    pub marked: bool,
+
+    /// Names of the executable input sections that hold text relocations, together with their numbers of text
+    /// relocations, in address order.
+    pub sections: Vec<(String, usize)>,
+}
+
+impl TextRelocations {
+    /// Get the number of text relocations.
+    pub fn count(&self) -> usize {
+        self.sections.iter().map(|(_, count)| count).sum()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CheckTextRelocationsError {
+    #[error("read ELF failed: {0:?}")]
+    ReadElfError(#[from] ReadError),
+
+    #[error(
+        "text relocations are rejected: dynamic relocations patch executable sections {}",
+        format_section_counts(.0)
+    )]
+    TextRelocations(Vec<(String, usize)>),
+}
+
+/// Format the given section names and their relocation counts.
+fn format_section_counts(counts: &[(String, usize)]) -> String {
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    counts.join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        DF_TEXTREL, DT_FLAGS, DT_NULL, PF_R, PF_X, PT_LOAD, R_X86_64_64, R_X86_64_RELATIVE,
+        SHF_ALLOC, SHF_EXECINSTR, SHT_DYNAMIC, SHT_PROGBITS,
+    };
+    use object::read::elf::ElfFile64;
+    use object::{Architecture, BinaryFormat, Endianness};
+
+    use crate::elf::pass::reloc_index::IndexRelocationsPass;
+    use crate::elf::pass::section::CopyLodableSectionsPass;
+    use crate::output::Object as OutputObject;
+    use crate::pass::test::PassTest;
+    use crate::pass::{Pass, PassHandle, PassManager};
+
+    use super::CheckTextRelocationsPass;
+
+    struct CheckTextRelocationsPassTest;
+
+    impl PassTest for CheckTextRelocationsPassTest {
+        type Input = ElfFile64<'static>;
+        type Pass = CheckTextRelocationsPass;
+
+        fn setup(&mut self, pass_mgr: &mut PassManager<Self::Input>) -> PassHandle<Self::Pass> {
+            let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+            let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+            pass_mgr.add_pass(CheckTextRelocationsPass {
+                cls_pass,
+                reloc_index_pass,
+                reject: false,
+            })
+        }
+
+        fn check_pass_output(&mut self, output: &<Self::Pass as Pass<Self::Input>>::Output) {
+            assert!(output.marked);
+            assert_eq!(
+                output.sections,
+                vec![(String::from(".text"), 2), (String::from(".text.cold"), 1)]
+            );
+            assert_eq!(output.count(), 3);
+        }
+    }
+
+    fn text_reloc_dso() -> crate::elf::test::builder::ElfBuilder {
+        // Two relocations patch .text and one patches .text.cold, while the one in .data is not a text relocation.
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        builder.add_section(
+            ".text.cold",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            0x3000,
+            vec![0; 0x10],
+        );
+        builder.add_segment(PT_LOAD, PF_R | PF_X, 0x3000, 0x10);
+        builder.add_relocation(0x1008, R_X86_64_64, 1, 0);
+        builder.add_relocation(0x2000, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x3000, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x1000, R_X86_64_RELATIVE, 0, 0x2000);
+        let dynamic_data = [DT_FLAGS as u64, DF_TEXTREL as u64, DT_NULL as u64, 0]
+            .into_iter()
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+        builder
+    }
+
+    #[test]
+    fn test_check_text_relocations_pass() {
+        let builder = text_reloc_dso();
+        let output =
+            || OutputObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        crate::pass::test::run_pass_test(
+            CheckTextRelocationsPassTest,
+            builder.build_elf64(),
+            output(),
+        );
+
+        // Rejecting text relocations fails the pass.
+        let mut pass_mgr = PassManager::new();
+        let cls_pass = pass_mgr.add_pass_default::<CopyLodableSectionsPass>();
+        let reloc_index_pass = pass_mgr.add_pass_default::<IndexRelocationsPass>();
+        pass_mgr.add_pass(CheckTextRelocationsPass {
+            cls_pass,
+            reloc_index_pass,
+            reject: true,
+        });
+        let err = pass_mgr
+            .run(builder.build_elf64(), output())
+            .unwrap_err()
+            .error
+            .to_string();
+        assert!(err.contains(".text (2), .text.cold (1)"), "{}", err);
+    }
+
+    #[test]
+    fn test_check_text_relocations_spdlog() {
+        let input = crate::elf::test::get_test_input_file();
+        assert!(!crate::elf::has_text_relocations(&input).unwrap());
+
+        let options = crate::ConvertOptions {
+            reject_textrel: true,
+            ..Default::default()
+        };
+        assert!(crate::convert(input.data(), &options).is_ok());
+    }
+}
@@ -0,0 +1,763 @@
+#![allow(dead_code)]
+
+use object::elf::{
+    EF_LARCH_ABI_DOUBLE_FLOAT, EF_LARCH_OBJABI_V1, EF_MIPS_ARCH_64R2, EF_MIPS_CPIC, EF_MIPS_PIC,
+    EF_RISCV_FLOAT_ABI_DOUBLE, EF_RISCV_RVC, EM_386, EM_AARCH64, EM_ARM, EM_LOONGARCH, EM_MIPS,
+    EM_PPC64, EM_RISCV, EM_S390, EM_X86_64, ET_DYN, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE,
+    SHN_XINDEX, SHT_DYNSYM, SHT_GNU_VERDEF, SHT_GNU_VERNEED, SHT_GNU_VERSYM, SHT_NOBITS,
+    SHT_PROGBITS, SHT_STRTAB, SHT_SYMTAB_SHNDX, STB_GLOBAL, STT_FUNC, STT_OBJECT, VER_FLG_BASE,
+    VER_NDX_GLOBAL,
+};
+use object::read::elf::{ElfFile32, ElfFile64};
+use object::write::elf::{
+    FileHeader, ProgramHeader, Rel, SectionHeader, SectionIndex, Sym, Verdef, Vernaux, Verneed,
+    Writer,
+};
+use object::write::StringId;
+use object::{Endian as _, Endianness};
+
+/// Synthesizes small ELF shared libraries for tests.
+///
+/// The sections added through the builder are placed at the given virtual addresses and their data is placed at the end
+/// of the file. The dynamic symbol table, the dynamic string table, the symbol version tables and the dynamic relocation
+/// table are emitted as non-allocatable sections so that they never become part of the loaded image.
+#[derive(Clone, Debug)]
+pub struct ElfBuilder {
+    pub endian: Endianness,
+    pub is_64: bool,
+    pub os_abi: u8,
+    pub e_machine: u16,
+    pub e_type: u16,
+    pub e_flags: u32,
+    pub e_entry: u64,
+    pub is_rela: bool,
+    pub sections: Vec<BuilderSection>,
+    pub segments: Vec<ProgramHeader>,
+    pub symbols: Vec<BuilderSymbol>,
+    pub relocations: Vec<Rel>,
+    pub version_defs: Vec<(u16, String)>,
+    pub version_reqs: Vec<(u16, String, String)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuilderSection {
+    pub name: String,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_size: u64,
+    pub sh_addralign: u64,
+    pub data: Vec<u8>,
+}
+
+impl BuilderSection {
+    /// Get the alignment of the section data within the file. Invalid or unreasonably large alignments are ignored so
+    /// that tests can synthesize sections with such alignments.
+    fn file_align(&self) -> usize {
+        if self.sh_addralign.is_power_of_two() && self.sh_addralign <= 0x1000 {
+            self.sh_addralign as usize
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BuilderSymbol {
+    pub name: String,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    /// The extended section index, used if `st_shndx` is `SHN_XINDEX`.
+    pub xindex: u32,
+    pub st_value: u64,
+    pub st_size: u64,
+    pub versym: u16,
+}
+
+impl BuilderSymbol {
+    /// Create a default-visibility symbol.
+    pub fn new(name: &str, bind: u8, kind: u8, st_shndx: u16, st_value: u64, st_size: u64) -> Self {
+        Self {
+            name: String::from(name),
+            st_info: (bind << 4) | kind,
+            st_other: 0,
+            st_shndx,
+            xindex: 0,
+            st_value,
+            st_size,
+            versym: VER_NDX_GLOBAL,
+        }
+    }
+}
+
+impl ElfBuilder {
+    /// Create a builder for a little-endian x86_64 shared library.
+    pub fn new() -> Self {
+        Self {
+            endian: Endianness::Little,
+            is_64: true,
+            os_abi: 0,
+            e_machine: EM_X86_64,
+            e_type: ET_DYN,
+            e_flags: 0,
+            e_entry: 0,
+            is_rela: true,
+            sections: Vec::new(),
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+            version_defs: Vec::new(),
+            version_reqs: Vec::new(),
+        }
+    }
+
+    /// Add a section with the given data and return its section index.
+    pub fn add_section(
+        &mut self,
+        name: &str,
+        sh_type: u32,
+        sh_flags: u32,
+        sh_addr: u64,
+        data: Vec<u8>,
+    ) -> u16 {
+        self.sections.push(BuilderSection {
+            name: String::from(name),
+            sh_type,
+            sh_flags: sh_flags as u64,
+            sh_addr,
+            sh_size: data.len() as u64,
+            sh_addralign: 1,
+            data,
+        });
+        self.sections.len() as u16
+    }
+
+    /// Add a NOBITS section and return its section index.
+    pub fn add_nobits_section(
+        &mut self,
+        name: &str,
+        sh_flags: u32,
+        sh_addr: u64,
+        size: u64,
+    ) -> u16 {
+        let index = self.add_section(name, SHT_NOBITS, sh_flags, sh_addr, Vec::new());
+        self.section_mut(index).sh_size = size;
+        index
+    }
+
+    /// Add the given number of empty non-allocatable sections, which push the indexes of the sections added later into
+    /// the reserved range.
+    pub fn add_filler_sections(&mut self, count: usize) {
+        for _ in 0..count {
+            self.add_section(".filler", SHT_PROGBITS, 0, 0, Vec::new());
+        }
+    }
+
+    /// Get the section with the given section index.
+    pub fn section_mut(&mut self, index: u16) -> &mut BuilderSection {
+        &mut self.sections[index as usize - 1]
+    }
+
+    /// Add a program header.
+    pub fn add_segment(&mut self, p_type: u32, p_flags: u32, p_vaddr: u64, p_memsz: u64) {
+        self.segments.push(ProgramHeader {
+            p_type,
+            p_flags,
+            p_offset: 0,
+            p_vaddr,
+            p_paddr: p_vaddr,
+            p_filesz: 0,
+            p_memsz,
+            p_align: 0x1000,
+        });
+    }
+
+    /// Add a dynamic symbol and return its symbol index.
+    pub fn add_symbol(&mut self, symbol: BuilderSymbol) -> u32 {
+        self.symbols.push(symbol);
+        self.symbols.len() as u32
+    }
+
+    /// Get the dynamic symbol with the given symbol index.
+    pub fn symbol_mut(&mut self, index: u32) -> &mut BuilderSymbol {
+        &mut self.symbols[index as usize - 1]
+    }
+
+    /// Add a dynamic relocation.
+    pub fn add_relocation(&mut self, r_offset: u64, r_type: u32, r_sym: u32, r_addend: i64) {
+        self.relocations.push(Rel {
+            r_offset,
+            r_sym,
+            r_type,
+            r_addend,
+        });
+    }
+
+    /// Add a version definition and return its version index.
+    pub fn add_version_definition(&mut self, name: &str) -> u16 {
+        let index = self.next_version_index();
+        self.version_defs.push((index, String::from(name)));
+        index
+    }
+
+    /// Add a version requirement on the given file and return its version index.
+    pub fn add_version_requirement(&mut self, file: &str, name: &str) -> u16 {
+        let index = self.next_version_index();
+        self.version_reqs
+            .push((index, String::from(file), String::from(name)));
+        index
+    }
+
+    fn next_version_index(&self) -> u16 {
+        (2 + self.version_defs.len() + self.version_reqs.len()) as u16
+    }
+
+    fn has_xindex(&self) -> bool {
+        self.symbols.iter().any(|sym| sym.st_shndx == SHN_XINDEX)
+    }
+
+    fn has_versions(&self) -> bool {
+        !self.version_defs.is_empty() || !self.version_reqs.is_empty()
+    }
+
+    /// Build the ELF file.
+    pub fn build(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(self.endian, self.is_64, &mut buffer);
+
+        writer.reserve_file_header();
+        writer.reserve_program_headers(self.segments.len() as u32);
+
+        // Reserve section indexes and names.
+        writer.reserve_null_section_index();
+        let section_names: Vec<StringId> = self
+            .sections
+            .iter()
+            .map(|sec| {
+                let name = writer.add_section_name(sec.name.as_bytes());
+                writer.reserve_section_index();
+                name
+            })
+            .collect();
+        let symbol_names: Vec<StringId> = self
+            .symbols
+            .iter()
+            .map(|sym| writer.add_dynamic_string(sym.name.as_bytes()))
+            .collect();
+        let dynsym_name = writer.add_section_name(b".dynsym");
+        let dynsym_index = writer.reserve_section_index();
+        let dynsym_shndx_name = self.has_xindex().then(|| {
+            let name = writer.add_section_name(b".dynsym_shndx");
+            writer.reserve_section_index();
+            name
+        });
+        let dynstr_name = writer.add_section_name(b".dynstr");
+        let dynstr_index = writer.reserve_section_index();
+        let rela_name = writer.add_section_name(if self.is_rela {
+            b".rela.dyn"
+        } else {
+            b".rel.dyn"
+        });
+        writer.reserve_section_index();
+        let version_names = self.has_versions().then(|| {
+            let names = [".gnu.version", ".gnu.version_d", ".gnu.version_r"].map(|name| {
+                let name = writer.add_section_name(name.as_bytes());
+                writer.reserve_section_index();
+                name
+            });
+            let base_name = writer.add_dynamic_string(b"libtest.so");
+            let def_names: Vec<StringId> = self
+                .version_defs
+                .iter()
+                .map(|(_, name)| writer.add_dynamic_string(name.as_bytes()))
+                .collect();
+            let req_names: Vec<(StringId, StringId)> = self
+                .version_reqs
+                .iter()
+                .map(|(_, file, name)| {
+                    (
+                        writer.add_dynamic_string(file.as_bytes()),
+                        writer.add_dynamic_string(name.as_bytes()),
+                    )
+                })
+                .collect();
+            (names, base_name, def_names, req_names)
+        });
+        writer.reserve_shstrtab_section_index();
+
+        // Reserve file ranges. The section data is placed at the end of the file so that truncating the file only
+        // affects the section data.
+        writer.reserve_null_dynamic_symbol_index();
+        for _ in &self.symbols {
+            writer.reserve_dynamic_symbol_index();
+        }
+        let elf_align = if self.is_64 { 8 } else { 4 };
+        let sym_size = if self.is_64 { 24 } else { 16 };
+        let dynsym_offset = writer.reserved_len().div_ceil(elf_align) * elf_align;
+        writer.reserve_dynsym();
+        let dynsym_shndx_offset = if self.has_xindex() {
+            writer.reserve((self.symbols.len() + 1) * 4, 4)
+        } else {
+            0
+        };
+        let dynstr_offset = writer.reserved_len();
+        writer.reserve_dynstr();
+        let dynstr_size = writer.reserved_len() - dynstr_offset;
+        let mut version_offsets = [0usize; 3];
+        if self.has_versions() {
+            version_offsets[0] = writer.reserved_len().div_ceil(2) * 2;
+            writer.reserve_gnu_versym();
+            version_offsets[1] = writer.reserved_len().div_ceil(elf_align) * elf_align;
+            writer.reserve_gnu_verdef(1 + self.version_defs.len(), 1 + self.version_defs.len());
+            version_offsets[2] = writer.reserved_len().div_ceil(elf_align) * elf_align;
+            writer.reserve_gnu_verneed(self.version_reqs.len(), self.version_reqs.len());
+        }
+        let rela_offset = writer.reserve_relocations(self.relocations.len(), self.is_rela);
+        writer.reserve_shstrtab();
+        writer.reserve_section_headers();
+        let section_offsets: Vec<usize> = self
+            .sections
+            .iter()
+            .map(|sec| writer.reserve(sec.data.len(), sec.file_align()))
+            .collect();
+
+        // Write everything.
+        writer
+            .write_file_header(&FileHeader {
+                os_abi: self.os_abi,
+                abi_version: 0,
+                e_type: self.e_type,
+                e_machine: self.e_machine,
+                e_entry: self.e_entry,
+                e_flags: self.e_flags,
+            })
+            .unwrap();
+        writer.write_align_program_headers();
+        for seg in &self.segments {
+            writer.write_program_header(seg);
+        }
+
+        writer.write_null_dynamic_symbol();
+        for (sym, name) in self.symbols.iter().zip(&symbol_names) {
+            writer.write_dynamic_symbol(&Sym {
+                name: Some(*name),
+                section: None,
+                st_info: sym.st_info,
+                st_other: sym.st_other,
+                st_shndx: sym.st_shndx,
+                st_value: sym.st_value,
+                st_size: sym.st_size,
+            });
+        }
+        if self.has_xindex() {
+            writer.pad_until(dynsym_shndx_offset);
+            writer.write(&[0u8; 4]);
+            for sym in &self.symbols {
+                writer.write(&self.endian.write_u32_bytes(sym.xindex));
+            }
+        }
+        writer.write_dynstr();
+
+        if let Some((_, base_name, def_names, req_names)) = &version_names {
+            writer.write_null_gnu_versym();
+            for sym in &self.symbols {
+                writer.write_gnu_versym(sym.versym);
+            }
+
+            writer.write_align_gnu_verdef();
+            writer.write_gnu_verdef(&Verdef {
+                version: 1,
+                flags: VER_FLG_BASE,
+                index: 1,
+                aux_count: 1,
+                name: *base_name,
+            });
+            for ((index, _), name) in self.version_defs.iter().zip(def_names) {
+                writer.write_gnu_verdef(&Verdef {
+                    version: 1,
+                    flags: 0,
+                    index: *index,
+                    aux_count: 1,
+                    name: *name,
+                });
+            }
+
+            writer.write_align_gnu_verneed();
+            for ((index, _, _), (file, name)) in self.version_reqs.iter().zip(req_names) {
+                writer.write_gnu_verneed(&Verneed {
+                    version: 1,
+                    aux_count: 1,
+                    file: *file,
+                });
+                writer.write_gnu_vernaux(&Vernaux {
+                    flags: 0,
+                    index: *index,
+                    name: *name,
+                });
+            }
+        }
+
+        writer.write_align_relocation();
+        // The writer lays out r_info of mips64el relocations in the MIPS64 order only if they are RELA relocations, so
+        // the fields of REL relocations are swapped such that they end up in that order anyway.
+        let is_mips64el_rel = self.is_64
+            && self.endian == Endianness::Little
+            && self.e_machine == EM_MIPS
+            && !self.is_rela;
+        for rel in &self.relocations {
+            if is_mips64el_rel {
+                let rel = Rel {
+                    r_offset: rel.r_offset,
+                    r_sym: rel.r_type.swap_bytes(),
+                    r_type: rel.r_sym,
+                    r_addend: 0,
+                };
+                writer.write_relocation(false, &rel);
+            } else {
+                writer.write_relocation(self.is_rela, rel);
+            }
+        }
+        writer.write_shstrtab();
+
+        writer.write_null_section_header();
+        for ((sec, name), offset) in self
+            .sections
+            .iter()
+            .zip(section_names)
+            .zip(&section_offsets)
+        {
+            writer.write_section_header(&SectionHeader {
+                name: Some(name),
+                sh_type: sec.sh_type,
+                sh_flags: sec.sh_flags,
+                sh_addr: sec.sh_addr,
+                sh_offset: *offset as u64,
+                sh_size: sec.sh_size,
+                sh_link: 0,
+                sh_info: 0,
+                sh_addralign: sec.sh_addralign,
+                sh_entsize: 0,
+            });
+        }
+        let num_local = 1 + self
+            .symbols
+            .iter()
+            .take_while(|sym| sym.st_info >> 4 == 0)
+            .count();
+        writer.write_section_header(&SectionHeader {
+            name: Some(dynsym_name),
+            sh_type: SHT_DYNSYM,
+            sh_flags: 0,
+            sh_addr: 0,
+            sh_offset: dynsym_offset as u64,
+            sh_size: ((self.symbols.len() + 1) * sym_size) as u64,
+            sh_link: dynstr_index.0,
+            sh_info: num_local as u32,
+            sh_addralign: elf_align as u64,
+            sh_entsize: sym_size as u64,
+        });
+        if let Some(name) = dynsym_shndx_name {
+            writer.write_section_header(&SectionHeader {
+                name: Some(name),
+                sh_type: SHT_SYMTAB_SHNDX,
+                sh_flags: 0,
+                sh_addr: 0,
+                sh_offset: dynsym_shndx_offset as u64,
+                sh_size: ((self.symbols.len() + 1) * 4) as u64,
+                sh_link: dynsym_index.0,
+                sh_info: 0,
+                sh_addralign: 4,
+                sh_entsize: 4,
+            });
+        }
+        writer.write_section_header(&SectionHeader {
+            name: Some(dynstr_name),
+            sh_type: SHT_STRTAB,
+            sh_flags: 0,
+            sh_addr: 0,
+            sh_offset: dynstr_offset as u64,
+            sh_size: dynstr_size as u64,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 1,
+            sh_entsize: 0,
+        });
+        writer.write_relocation_section_header(
+            rela_name,
+            SectionIndex(0),
+            dynsym_index,
+            rela_offset,
+            self.relocations.len(),
+            self.is_rela,
+        );
+        if let Some((names, _, _, _)) = &version_names {
+            let num_defs = 1 + self.version_defs.len();
+            let num_reqs = self.version_reqs.len();
+            let headers = [
+                (
+                    SHT_GNU_VERSYM,
+                    dynsym_index,
+                    0,
+                    (self.symbols.len() + 1) * 2,
+                    2,
+                ),
+                (
+                    SHT_GNU_VERDEF,
+                    dynstr_index,
+                    num_defs,
+                    num_defs * (20 + 8),
+                    elf_align,
+                ),
+                (
+                    SHT_GNU_VERNEED,
+                    dynstr_index,
+                    num_reqs,
+                    num_reqs * (16 + 16),
+                    elf_align,
+                ),
+            ];
+            for (((sh_type, link, info, size, align), name), offset) in
+                headers.into_iter().zip(names).zip(version_offsets)
+            {
+                writer.write_section_header(&SectionHeader {
+                    name: Some(*name),
+                    sh_type,
+                    sh_flags: 0,
+                    sh_addr: 0,
+                    sh_offset: offset as u64,
+                    sh_size: size as u64,
+                    sh_link: link.0,
+                    sh_info: info as u32,
+                    sh_addralign: align as u64,
+                    sh_entsize: if sh_type == SHT_GNU_VERSYM { 2 } else { 0 },
+                });
+            }
+        }
+        writer.write_shstrtab_section_header();
+
+        for (sec, offset) in self.sections.iter().zip(&section_offsets) {
+            writer.pad_until(*offset);
+            writer.write(&sec.data);
+        }
+
+        assert_eq!(writer.reserved_len(), writer.len());
+        buffer
+    }
+
+    /// Build the ELF file and parse it as a 64-bit ELF file.
+    pub fn build_elf64(&self) -> ElfFile64<'static> {
+        let data = Vec::leak(self.build());
+        ElfFile64::parse(&*data).unwrap()
+    }
+
+    /// Build the ELF file and parse it as a 32-bit ELF file.
+    pub fn build_elf32(&self) -> ElfFile32<'static> {
+        let data = Vec::leak(self.build());
+        ElfFile32::parse(&*data).unwrap()
+    }
+
+    /// Turn the builder into one that builds an AArch64 shared library.
+    pub fn into_aarch64(mut self) -> Self {
+        self.e_machine = EM_AARCH64;
+        self
+    }
+
+    /// Turn the builder into one that builds an i386 shared library, which uses REL relocations.
+    pub fn into_i386(mut self) -> Self {
+        self.is_64 = false;
+        self.e_machine = EM_386;
+        self.is_rela = false;
+        self
+    }
+
+    /// Turn the builder into one that builds a 32-bit ARM shared library, which uses REL relocations.
+    pub fn into_arm(mut self) -> Self {
+        self.is_64 = false;
+        self.e_machine = EM_ARM;
+        self.is_rela = false;
+        self
+    }
+
+    /// Turn the builder into one that builds a LoongArch64 shared library for the double-float ABI.
+    pub fn into_loongarch64(mut self) -> Self {
+        self.e_machine = EM_LOONGARCH;
+        self.e_flags = EF_LARCH_ABI_DOUBLE_FLOAT | EF_LARCH_OBJABI_V1;
+        self
+    }
+
+    /// Turn the builder into one that builds a little-endian PowerPC64 shared library for the ELFv2 ABI.
+    pub fn into_ppc64le(mut self) -> Self {
+        self.e_machine = EM_PPC64;
+        self.e_flags = 2;
+        self
+    }
+
+    /// Turn the builder into one that builds a big-endian s390x shared library.
+    pub fn into_s390x(mut self) -> Self {
+        self.endian = Endianness::Big;
+        self.e_machine = EM_S390;
+        self
+    }
+
+    /// Turn the builder into one that builds a RISC-V 64 shared library for the double-float ABI with compressed
+    /// instructions.
+    pub fn into_riscv64(mut self) -> Self {
+        self.e_machine = EM_RISCV;
+        self.e_flags = EF_RISCV_RVC | EF_RISCV_FLOAT_ABI_DOUBLE;
+        self
+    }
+
+    /// Turn the builder into one that builds a little-endian MIPS64 shared library for the MIPS64r2 ISA, which uses REL
+    /// relocations.
+    pub fn into_mips64el(mut self) -> Self {
+        self.e_machine = EM_MIPS;
+        self.e_flags = EF_MIPS_ARCH_64R2 | EF_MIPS_PIC | EF_MIPS_CPIC;
+        self.is_rela = false;
+        self
+    }
+}
+
+/// Create a builder containing a minimal image: an executable `.text` section at 0x1000 and a writable `.data` section
+/// at 0x2000, each covered by its own PT_LOAD segment, plus a global function symbol `func` and a global data symbol
+/// `data`.
+pub fn minimal_dso() -> ElfBuilder {
+    use object::elf::{PF_R, PF_W, PF_X, PT_LOAD};
+
+    let mut builder = ElfBuilder::new();
+
+    let text = builder.add_section(
+        ".text",
+        SHT_PROGBITS,
+        SHF_ALLOC | SHF_EXECINSTR,
+        0x1000,
+        vec![0xC3; 0x10],
+    );
+    builder.section_mut(text).sh_addralign = 16;
+    let data = builder.add_section(
+        ".data",
+        SHT_PROGBITS,
+        SHF_ALLOC | SHF_WRITE,
+        0x2000,
+        vec![0u8; 0x10],
+    );
+    builder.section_mut(data).sh_addralign = 8;
+
+    builder.add_segment(PT_LOAD, PF_R | PF_X, 0x1000, 0x10);
+    builder.add_segment(PT_LOAD, PF_R | PF_W, 0x2000, 0x10);
+
+    builder.add_symbol(BuilderSymbol::new(
+        "func", STB_GLOBAL, STT_FUNC, text, 0x1000, 0x10,
+    ));
+    builder.add_symbol(BuilderSymbol::new(
+        "data", STB_GLOBAL, STT_OBJECT, data, 0x2000, 8,
+    ));
+
+    builder
+}
+
+/// Create a builder containing the minimal image of [`minimal_dso`] plus a TLS image at 0x3000 made up of an 8-byte
+/// `.tdata` section and an 8-byte `.tbss` section, covered by a PT_TLS segment aligned to 8 bytes, and a global TLS
+/// symbol `tls_var` at offset 4 within the image. `.data` is enlarged to 0x30 bytes to make room for GOT entries.
+pub fn tls_dso() -> ElfBuilder {
+    use object::elf::{PF_R, PF_W, PT_LOAD, PT_TLS, SHF_TLS, STT_TLS};
+
+    let mut builder = minimal_dso();
+
+    let data = builder.section_mut(2);
+    data.data = vec![0u8; 0x30];
+    data.sh_size = 0x30;
+    builder.segments[1].p_memsz = 0x30;
+
+    let tdata = builder.add_section(
+        ".tdata",
+        SHT_PROGBITS,
+        SHF_ALLOC | SHF_WRITE | SHF_TLS,
+        0x3000,
+        vec![1, 0, 0, 0, 2, 0, 0, 0],
+    );
+    builder.section_mut(tdata).sh_addralign = 8;
+    let tbss = builder.add_nobits_section(".tbss", SHF_ALLOC | SHF_WRITE | SHF_TLS, 0x3008, 8);
+    builder.section_mut(tbss).sh_addralign = 8;
+
+    builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 8);
+    builder.add_segment(PT_TLS, PF_R, 0x3000, 0x10);
+    builder.segments.last_mut().unwrap().p_align = 8;
+
+    builder.add_symbol(BuilderSymbol::new(
+        "tls_var", STB_GLOBAL, STT_TLS, tdata, 4, 4,
+    ));
+
+    builder
+}
+
+/// Create a builder containing the minimal image of [`minimal_dso`] with a page-sized `.data.rel.ro` section at 0x2000,
+/// covered by a PT_GNU_RELRO segment, and `.data` moved back to back with it to 0x3000 along with `data`. Both sections
+/// are covered by the writable PT_LOAD segment, whose end at 0x3010 is the end of the image.
+pub fn relro_dso() -> ElfBuilder {
+    use object::elf::{PF_R, PT_GNU_RELRO};
+
+    let mut builder = minimal_dso();
+
+    builder.section_mut(2).sh_addr = 0x3000;
+    builder.symbol_mut(2).st_value = 0x3000;
+    builder.add_section(
+        ".data.rel.ro",
+        SHT_PROGBITS,
+        SHF_ALLOC | SHF_WRITE,
+        0x2000,
+        vec![0u8; 0x1000],
+    );
+
+    builder.segments[1].p_memsz = 0x1010;
+    builder.add_segment(PT_GNU_RELRO, PF_R, 0x2000, 0x1000);
+
+    builder
+}
+
+/// Create a builder containing the minimal image of [`minimal_dso`] plus data sections that only garbage collection
+/// tells apart, covered by a writable PT_LOAD segment from 0x3000 to 0x4020:
+///
+/// - `.rodata.blob` at 0x3000, a 4 KiB blob of 0xAB bytes defined by `blob`, which nothing refers to;
+/// - `.data.table` at 0x4000, an 8-byte pointer defined by `table`, which a RELATIVE relocation points to 0x4010;
+/// - `.rodata.chained` at 0x4010, 16 bytes of 0xCD defined by `chained`, which only `table` refers to.
+pub fn dead_blob_dso() -> ElfBuilder {
+    use object::elf::{PF_R, PF_W, PT_LOAD, R_X86_64_RELATIVE};
+
+    let mut builder = minimal_dso();
+
+    let data_sections = [
+        (
+            ".rodata.blob",
+            SHF_ALLOC,
+            0x3000,
+            vec![0xAB; 0x1000],
+            "blob",
+        ),
+        (
+            ".data.table",
+            SHF_ALLOC | SHF_WRITE,
+            0x4000,
+            vec![0; 8],
+            "table",
+        ),
+        (
+            ".rodata.chained",
+            SHF_ALLOC,
+            0x4010,
+            vec![0xCD; 0x10],
+            "chained",
+        ),
+    ];
+    for (name, flags, addr, data, sym_name) in data_sections {
+        let size = data.len() as u64;
+        let section = builder.add_section(name, SHT_PROGBITS, flags, addr, data);
+        builder.add_symbol(BuilderSymbol::new(
+            sym_name, STB_GLOBAL, STT_OBJECT, section, addr, size,
+        ));
+    }
+    builder.add_segment(PT_LOAD, PF_R | PF_W, 0x3000, 0x1020);
+    builder.add_relocation(0x4000, R_X86_64_RELATIVE, 0, 0x4010);
+
+    builder
+}
@@ -0,0 +1,11 @@
+extern int ext_counter;
+extern void ext_func(void);
+
+int counter = 1;
+static int hidden = 2;
+int *counter_ptr = &counter;
+void (*callback)(void) = ext_func;
+
+int get_counter(void) { return counter + hidden + ext_counter; }
+
+__attribute__((constructor)) static void init(void) { counter = 3; }
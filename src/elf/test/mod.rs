@@ -1,6 +1,1046 @@
-use object::read::elf::ElfFile64;
+pub mod builder;
+
+use object::read::elf::{ElfFile32, ElfFile64};
 
 pub fn get_test_input_file() -> ElfFile64<'static> {
     let file_data = include_bytes!("libspdlog.so.1.12.0").as_slice();
     ElfFile64::parse(file_data).unwrap()
 }
+
+/// Get the 32-bit test input file, an i386 shared library built from `libsoda32.c` with:
+///
+/// ```text
+/// gcc -m32 -O2 -fPIC -c libsoda32.c -o libsoda32.o
+/// ld -m elf_i386 -shared -z relro --hash-style=gnu -soname libsoda32.so -o libsoda32.so libsoda32.o
+/// ```
+pub fn get_test_input_file32() -> ElfFile32<'static> {
+    let file_data = include_bytes!("libsoda32.so").as_slice();
+    ElfFile32::parse(file_data).unwrap()
+}
+
+/// Get a copy of the test input file that is modified by the given function before parsing.
+pub fn get_patched_test_input_file<F>(patch: F) -> ElfFile64<'static>
+where
+    F: FnOnce(&mut [u8]),
+{
+    let mut file_data = include_bytes!("libspdlog.so.1.12.0").to_vec();
+    patch(&mut file_data);
+    ElfFile64::parse(&*Vec::leak(file_data)).unwrap()
+}
+
+/// Write a little-endian `u64` value at the given offset of the buffer.
+pub fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+#[test]
+fn test_convert_deterministic() {
+    use object::{Object as _, ObjectSymbol as _};
+
+    let options = crate::elf::ConvertOptions::default();
+    let first = crate::elf::convert(get_test_input_file(), &options)
+        .unwrap()
+        .write()
+        .unwrap();
+    let second = crate::elf::convert(get_test_input_file(), &options)
+        .unwrap()
+        .write()
+        .unwrap();
+    assert!(first == second, "conversion output is not deterministic");
+
+    // Local symbols precede global symbols.
+    let output_file = object::File::parse(&*first).unwrap();
+    let first_global = output_file
+        .symbols()
+        .position(|sym| sym.is_global())
+        .unwrap();
+    assert!(output_file
+        .symbols()
+        .skip(first_global)
+        .all(|sym| sym.is_global()));
+}
+
+#[test]
+fn test_has_interpreter() {
+    use object::elf::{PF_R, PT_INTERP};
+
+    let mut builder = builder::minimal_dso();
+    assert!(!crate::elf::has_interpreter(&builder.build_elf64()));
+
+    builder.add_segment(PT_INTERP, PF_R, 0, 0);
+    assert!(crate::elf::has_interpreter(&builder.build_elf64()));
+}
+
+#[test]
+fn test_get_tls_image() {
+    assert_eq!(
+        crate::elf::get_tls_image(&builder::minimal_dso().build_elf64()),
+        None
+    );
+
+    // The builder leaves the file sizes of segments zero.
+
+    let tls_image = crate::elf::get_tls_image(&builder::tls_dso().build_elf64()).unwrap();
+    assert_eq!(
+        tls_image,
+        crate::elf::TlsImage {
+            addr: 0x3000,
+            file_size: 0,
+            mem_size: 0x10,
+            align: 8,
+        }
+    );
+    assert_eq!(tls_image.addr_range(), 0x3000..0x3010);
+}
+
+#[test]
+fn test_is_pie() {
+    use object::elf::{DF_1_NOW, DF_1_PIE, DT_FLAGS_1, DT_NULL, PF_R, PT_INTERP, SHT_DYNAMIC};
+
+    let mut builder = builder::minimal_dso();
+    let dynamic_data = [DT_FLAGS_1 as u64, DF_1_NOW as u64, DT_NULL as u64, 0]
+        .into_iter()
+        .flat_map(u64::to_le_bytes)
+        .collect();
+    let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+    builder.section_mut(dynamic).sh_addralign = 8;
+    assert!(!crate::elf::is_pie(&builder.build_elf64()).unwrap());
+
+    // PIEs linked with -static-pie have no interpreter but are marked in the dynamic section.
+    write_u64(
+        &mut builder.section_mut(dynamic).data,
+        8,
+        (DF_1_NOW | DF_1_PIE) as u64,
+    );
+    assert!(crate::elf::is_pie(&builder.build_elf64()).unwrap());
+
+    let mut builder = builder::minimal_dso();
+    builder.add_segment(PT_INTERP, PF_R, 0, 0);
+    assert!(crate::elf::is_pie(&builder.build_elf64()).unwrap());
+}
+
+#[test]
+fn test_get_dynamic_flags() {
+    use object::elf::{
+        DF_1_NODELETE, DF_1_NOOPEN, DF_BIND_NOW, DF_SYMBOLIC, DF_TEXTREL, DT_BIND_NOW, DT_FLAGS,
+        DT_FLAGS_1, DT_NULL, DT_SYMBOLIC, DT_TEXTREL, SHT_DYNAMIC,
+    };
+
+    let build = |entries: &[u32]| {
+        let mut builder = builder::minimal_dso();
+        let dynamic_data = entries
+            .iter()
+            .flat_map(|entry| u64::from(*entry).to_le_bytes())
+            .collect();
+        let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+        builder.section_mut(dynamic).sh_addralign = 8;
+        builder.build_elf64()
+    };
+
+    let flags = crate::elf::get_dynamic_flags(&builder::minimal_dso().build_elf64()).unwrap();
+    assert_eq!(flags, crate::elf::DynamicFlags::default());
+
+    let input = build(&[
+        DT_FLAGS,
+        DF_SYMBOLIC,
+        DT_FLAGS_1,
+        DF_1_NODELETE | DF_1_NOOPEN,
+        DT_NULL,
+        0,
+    ]);
+    let flags = crate::elf::get_dynamic_flags(&input).unwrap();
+    assert!(flags.is_symbolic() && flags.is_nodelete() && flags.is_noopen());
+    assert!(!flags.is_bind_now());
+
+    // The legacy entries are folded into the flags, and entries after DT_NULL are ignored.
+    let input = build(&[
+        DT_SYMBOLIC,
+        0,
+        DT_TEXTREL,
+        0,
+        DT_BIND_NOW,
+        0,
+        DT_NULL,
+        0,
+        DT_FLAGS_1,
+        DF_1_NODELETE,
+    ]);
+    let flags = crate::elf::get_dynamic_flags(&input).unwrap();
+    assert_eq!(
+        flags.flags,
+        u64::from(DF_SYMBOLIC | DF_TEXTREL | DF_BIND_NOW)
+    );
+    assert_eq!(flags.flags_1, 0);
+    assert!(flags.is_symbolic() && flags.is_bind_now());
+}
+
+#[test]
+fn test_convert_symbolic() {
+    use object::elf::{DF_SYMBOLIC, DT_FLAGS, DT_NULL, R_X86_64_64, SHT_DYNAMIC};
+    use object::Object as _;
+
+    // The input is linked with -Bsymbolic, and refers to func from .data.
+    let mut builder = builder::minimal_dso();
+    let dynamic_data = [DT_FLAGS as u64, DF_SYMBOLIC as u64, DT_NULL as u64, 0]
+        .into_iter()
+        .flat_map(u64::to_le_bytes)
+        .collect();
+    let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+    builder.section_mut(dynamic).sh_addralign = 8;
+    builder.add_relocation(0x2000, R_X86_64_64, 1, 0);
+    let input = builder.build();
+
+    let render = |bind_locally| {
+        let options = crate::ConvertOptions {
+            bind_locally,
+            ..Default::default()
+        };
+        let output = crate::convert_elf(&input, &options).unwrap();
+        let output_file = object::File::parse(&*output).unwrap();
+        let soda = output_file.section_by_name(".soda").unwrap();
+        crate::utils::dump::render_relocations(&output_file, &soda)
+    };
+    assert_eq!(render(None), "0x2000 Absolute/64 .soda+0x1000\n");
+    assert_eq!(render(Some(true)), "0x2000 Absolute/64 .soda+0x1000\n");
+    assert_eq!(render(Some(false)), "0x2000 Absolute/64 func+0x0\n");
+}
+
+#[test]
+fn test_convert_pie() {
+    use object::elf::{PF_R, PT_INTERP, SHF_ALLOC, SHT_PROGBITS};
+    use object::{Object as _, ObjectSection as _, ObjectSymbol as _, SymbolKind};
+
+    // The interpreter path sits at 0x800, before .text.
+    let mut builder = builder::minimal_dso();
+    let interp = builder.add_section(
+        ".interp",
+        SHT_PROGBITS,
+        SHF_ALLOC,
+        0x800,
+        b"/lib64/ld-linux-x86-64.so.2\0".to_vec(),
+    );
+    let interp_size = builder.section_mut(interp).sh_size;
+    builder.add_segment(PT_INTERP, PF_R, 0x800, interp_size);
+    builder.e_entry = 0x1008;
+
+    let err = crate::elf::convert(
+        builder.build_elf64(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("input appears to be a position-independent executable, not a shared library"));
+
+    let options = crate::elf::ConvertOptions {
+        allow_pie: true,
+        ..Default::default()
+    };
+    let output = crate::elf::convert(builder.build_elf64(), &options)
+        .unwrap()
+        .write()
+        .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+
+    // The interpreter path is not copied.
+    let output_section = output_file.section_by_name(".soda").unwrap();
+    let output_data = output_section.data().unwrap();
+    assert!(output_data[0x800..0x800 + interp_size as usize]
+        .iter()
+        .all(|b| *b == 0));
+
+    // The entry point is a local symbol.
+    let entry_sym = output_file
+        .symbols()
+        .find(|sym| sym.name() == Ok("__soda_entry"))
+        .unwrap();
+    assert!(entry_sym.is_local());
+    assert_eq!(entry_sym.kind(), SymbolKind::Text);
+    assert_eq!(entry_sym.section_index(), Some(output_section.index()));
+    assert_eq!(entry_sym.address(), 0x1008);
+}
+
+#[test]
+fn test_convert_i386() {
+    use object::elf::R_386_RELATIVE;
+    use object::{Architecture, Object as _, ObjectSection as _};
+
+    let mut builder = builder::minimal_dso().into_i386();
+    builder.section_mut(2).data[0..4].copy_from_slice(&0x1000u32.to_le_bytes());
+    builder.add_relocation(0x2000, R_386_RELATIVE, 0, 0);
+
+    let output = crate::elf::convert(
+        builder.build_elf32(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert_eq!(output_file.architecture(), Architecture::I386);
+    assert!(!output_file.is_64());
+    assert_eq!(
+        output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_convert_elf32() {
+    use object::read::RelocationTarget;
+    use object::{
+        Object as _, ObjectSection as _, ObjectSymbol as _, RelocationKind, SectionFlags,
+    };
+
+    let output = crate::elf::convert(
+        get_test_input_file32(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert!(!output_file.is_64());
+
+    let get_relocations = |section_name| {
+        let section = output_file.section_by_name(section_name).unwrap();
+        let data = section.data().unwrap();
+        section
+            .relocations()
+            .map(|(offset, reloc)| {
+                assert_eq!(reloc.kind(), RelocationKind::Absolute);
+                assert_eq!(reloc.size(), 32);
+                let RelocationTarget::Symbol(sym) = reloc.target() else {
+                    panic!("unexpected relocation target {:?}", reloc.target());
+                };
+                let target = output_file.symbol_by_index(sym).unwrap();
+                let addend = &data[offset as usize..offset as usize + 4];
+                (
+                    offset,
+                    String::from(target.name().unwrap()),
+                    u32::from_le_bytes(addend.try_into().unwrap()),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // The implicit addends are kept in the relocated slots. The init array entry at 0x3f60 is only relocated in the
+    // generated .init_array section.
+    let mut soda_relocs = get_relocations(".soda");
+    soda_relocs.sort();
+    assert_eq!(
+        soda_relocs,
+        vec![
+            (0x3fec, String::from("counter"), 0),
+            (0x3ff0, String::from("ext_counter"), 0),
+            (0x4004, String::from("ext_func"), 0),
+            (0x4008, String::from("counter"), 0),
+        ]
+    );
+
+    // The init array entries are placed at the start of the output section. The section symbol of .soda has no name.
+    assert_eq!(
+        get_relocations(".init_array"),
+        vec![(0, String::new(), 0x1000)]
+    );
+    assert_eq!(
+        output_file.section_by_name(".init_array").unwrap().flags(),
+        SectionFlags::Elf {
+            sh_flags: (object::elf::SHF_ALLOC | object::elf::SHF_WRITE) as u64
+        }
+    );
+
+    let symbol = |name| {
+        output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok(name))
+            .unwrap()
+    };
+    assert_eq!(symbol("get_counter").address(), 0x1020);
+    assert_eq!(symbol("get_counter").size(), 31);
+    assert_eq!(symbol("counter_ptr").address(), 0x4008);
+    assert_eq!(symbol("counter_ptr").size(), 4);
+    assert!(symbol("ext_func").is_undefined());
+}
+
+/// Create a builder containing the minimal image of [`builder::minimal_dso`], with `func` and `data` renamed to
+/// `<prefix>_func` and `<prefix>_data`, and an 8-byte slot at 0x2008 that refers to the undefined symbol `import`.
+fn merge_input(prefix: &str, import: &str) -> builder::ElfBuilder {
+    use object::elf::{R_X86_64_64, STB_GLOBAL, STT_NOTYPE};
+
+    let mut builder = builder::minimal_dso();
+    builder.symbol_mut(1).name = format!("{}_func", prefix);
+    builder.symbol_mut(2).name = format!("{}_data", prefix);
+    let import = builder.add_symbol(builder::BuilderSymbol::new(
+        import, STB_GLOBAL, STT_NOTYPE, 0, 0, 0,
+    ));
+    builder.add_relocation(0x2008, R_X86_64_64, import, 0);
+    builder
+}
+
+#[test]
+fn test_merge() {
+    use object::read::RelocationTarget;
+    use object::{Object as _, ObjectSection as _, ObjectSymbol as _};
+
+    let output = crate::elf::merge(
+        vec![
+            (
+                String::from("liba.so"),
+                merge_input("a", "b_func").build_elf64(),
+            ),
+            (
+                String::from("libb.so"),
+                merge_input("b", "a_data").build_elf64(),
+            ),
+        ],
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+
+    // Each input is placed into its own output section.
+    let soda_sections: Vec<_> = output_file
+        .sections()
+        .filter(|section| section.name() == Ok(".soda"))
+        .collect();
+    assert_eq!(soda_sections.len(), 2);
+
+    // The references between the inputs are bound to the definitions in the sibling sections.
+    for (section, target_name, target_section) in [
+        (&soda_sections[0], "b_func", &soda_sections[1]),
+        (&soda_sections[1], "a_data", &soda_sections[0]),
+    ] {
+        let targets: Vec<_> = section
+            .relocations()
+            .map(|(offset, reloc)| {
+                let RelocationTarget::Symbol(sym) = reloc.target() else {
+                    panic!("unexpected relocation target {:?}", reloc.target());
+                };
+                (offset, output_file.symbol_by_index(sym).unwrap())
+            })
+            .collect();
+        assert_eq!(targets.len(), 1);
+        let (offset, target) = &targets[0];
+        assert_eq!(*offset, 0x2008);
+        assert_eq!(target.name(), Ok(target_name));
+        assert_eq!(target.section_index(), Some(target_section.index()));
+        assert!(target.is_global());
+    }
+    assert!(output_file
+        .symbols()
+        .all(|sym| !sym.is_undefined() || sym.name() == Ok("")));
+
+    // The boundary symbols of each input are suffixed with its name.
+    let symbol = |name| {
+        output_file
+            .symbols()
+            .find(|sym| sym.name() == Ok(name))
+            .unwrap()
+    };
+    assert_eq!(
+        symbol("__soda_image_start_a").section_index(),
+        Some(soda_sections[0].index())
+    );
+    assert_eq!(
+        symbol("__soda_image_end_b").section_index(),
+        Some(soda_sections[1].index())
+    );
+}
+
+#[test]
+fn test_merge_defsym() {
+    use object::{Object as _, ObjectSymbol as _, SymbolSection};
+
+    // Both inputs refer to the defined symbol, and bind to the same definition.
+    let options = crate::elf::ConvertOptions {
+        defsyms: vec![(
+            String::from("vendor_size"),
+            crate::SymbolValue::Absolute(0x40),
+        )],
+        ..Default::default()
+    };
+    let output = crate::elf::merge(
+        vec![
+            (
+                String::from("liba.so"),
+                merge_input("a", "vendor_size").build_elf64(),
+            ),
+            (
+                String::from("libb.so"),
+                merge_input("b", "vendor_size").build_elf64(),
+            ),
+        ],
+        &options,
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+
+    let symbols: Vec<_> = output_file
+        .symbols()
+        .filter(|sym| sym.name() == Ok("vendor_size"))
+        .collect();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].section(), SymbolSection::Absolute);
+    assert_eq!(symbols[0].address(), 0x40);
+}
+
+#[test]
+fn test_merge_symbol_list() {
+    // liba.so refers to b_func before libb.so defines it, and libb.so refers to a_data after liba.so defines it.
+    let symbol_list = std::sync::Arc::new(crate::SymbolList::new());
+    let options = crate::elf::ConvertOptions {
+        image_start_symbol: String::new(),
+        image_end_symbol: String::new(),
+        symbol_list: Some(symbol_list.clone()),
+        ..Default::default()
+    };
+    crate::elf::merge(
+        vec![
+            (
+                String::from("liba.so"),
+                merge_input("a", "b_func").build_elf64(),
+            ),
+            (
+                String::from("libb.so"),
+                merge_input("b", "a_data").build_elf64(),
+            ),
+        ],
+        &options,
+    )
+    .unwrap();
+
+    // Both references are bound, so only the definitions are listed, each with its own input address.
+    let symbols: Vec<_> = symbol_list
+        .symbols()
+        .into_iter()
+        .map(|sym| (sym.name, sym.defined, sym.input_address))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            (String::from("a_data"), true, Some(0x2000)),
+            (String::from("a_func"), true, Some(0x1000)),
+            (String::from("b_data"), true, Some(0x2000)),
+            (String::from("b_func"), true, Some(0x1000)),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_duplicate_definition() {
+    use object::elf::{STB_WEAK, STT_FUNC};
+
+    let merge = |second: builder::ElfBuilder| {
+        crate::elf::merge(
+            vec![
+                (
+                    String::from("liba.so"),
+                    merge_input("a", "b_func").build_elf64(),
+                ),
+                (String::from("libc.so"), second.build_elf64()),
+            ],
+            &crate::elf::ConvertOptions::default(),
+        )
+    };
+
+    let err = format!(
+        "{:#}",
+        anyhow::Error::from(merge(merge_input("a", "c_func")).map(|_| ()).unwrap_err())
+    );
+    assert!(
+        err.contains("symbol \"a_func\" is defined by both liba.so and libc.so"),
+        "{}",
+        err
+    );
+
+    // Weak definitions give way to the existing definitions.
+    let mut second = merge_input("a", "c_func");
+    for index in [1, 2] {
+        second.symbol_mut(index).st_info = (STB_WEAK << 4) | STT_FUNC;
+    }
+    merge(second).unwrap();
+}
+
+#[test]
+fn test_create_context_error_variants() {
+    use object::elf::{DF_1_PIE, DT_FLAGS_1, DT_NULL, SHT_DYNAMIC};
+
+    use crate::{ConvertOptions, CreateContextError, Error};
+
+    let options = ConvertOptions::default();
+    let create_context_err = |result: Result<Vec<u8>, Error>| match result {
+        Err(Error::CreateContextError(err)) => err,
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    };
+
+    // UnsupportedArch is covered by test_convert_error_variants, CorruptedData by test_check_elf_layout, and
+    // UnsupportedDllDirectory by the PE tests.
+    let mut builder = builder::minimal_dso();
+    let dynamic_data = [(DT_FLAGS_1, u64::from(DF_1_PIE)), (DT_NULL, 0)]
+        .iter()
+        .flat_map(|&(tag, value)| [tag as u64, value])
+        .flat_map(u64::to_le_bytes)
+        .collect();
+    let dynamic = builder.add_section(".dynamic", SHT_DYNAMIC, 0, 0, dynamic_data);
+    builder.section_mut(dynamic).sh_addralign = 8;
+    let err = create_context_err(crate::convert_elf(&builder.build(), &options));
+    assert!(
+        matches!(err, CreateContextError::PositionIndependentExecutable),
+        "{:?}",
+        err
+    );
+
+    let err = create_context_err(crate::merge_elf(&[], &options));
+    assert!(matches!(err, CreateContextError::NoMergeInput), "{:?}", err);
+
+    let x86_64 = builder::minimal_dso().build();
+    let err = create_context_err(crate::merge_elf(
+        &[("liba.so", &x86_64), ("liba.so", &x86_64)],
+        &options,
+    ));
+    assert!(
+        matches!(&err, CreateContextError::DuplicateMergeInput(name) if name == "liba.so"),
+        "{:?}",
+        err
+    );
+
+    let aarch64 = builder::minimal_dso().into_aarch64().build();
+    let err = create_context_err(crate::merge_elf(
+        &[("liba.so", &x86_64), ("libb.so", &aarch64)],
+        &options,
+    ));
+    assert!(
+        matches!(
+            &err,
+            CreateContextError::MismatchedMergeArch { name, first_name }
+                if name == "libb.so" && first_name == "liba.so"
+        ),
+        "{:?}",
+        err
+    );
+
+    let i386 = builder::minimal_dso().into_i386().build();
+    let err = create_context_err(crate::merge_elf(
+        &[("liba.so", &x86_64), ("libb.so", &i386)],
+        &options,
+    ));
+    assert!(
+        matches!(err, CreateContextError::MixedElfClasses),
+        "{:?}",
+        err
+    );
+
+    let rename = |pairs: &[(&str, &str)]| ConvertOptions {
+        rename_sections: pairs
+            .iter()
+            .map(|(old_name, new_name)| (String::from(*old_name), String::from(*new_name)))
+            .collect(),
+        ..Default::default()
+    };
+    let err = create_context_err(crate::convert_elf(
+        &x86_64,
+        &rename(&[(".soda", ".a"), (".soda", ".b")]),
+    ));
+    assert!(
+        matches!(&err, CreateContextError::DuplicateSectionRename(name) if name == ".soda"),
+        "{:?}",
+        err
+    );
+    for pairs in [
+        &[(".soda", ".a"), (".tdata", ".a")][..],
+        &[(".soda", ".text.soda.relro")][..],
+    ] {
+        let err = create_context_err(crate::convert_elf(&x86_64, &rename(pairs)));
+        let (old_name, new_name) = pairs.last().unwrap();
+        assert!(
+            matches!(
+                &err,
+                CreateContextError::SectionRenameCollision { old_name: o, new_name: n }
+                    if o == old_name && n == new_name
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    // Swapping the names of two sections does not collide.
+    assert!(crate::convert_elf(
+        &x86_64,
+        &rename(&[(".soda", ".tdata"), (".tdata", ".soda")])
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_convert_rename_sections() {
+    use object::{Object as _, ObjectSection as _};
+
+    use crate::ConvertOptions;
+
+    let input = include_bytes!("libspdlog.so.1.12.0");
+    let options = ConvertOptions {
+        rename_sections: vec![
+            (String::from(".soda"), String::from(".text.vendor_foo")),
+            (String::from(".tdata"), String::from(".tdata.vendor_foo")),
+            (
+                String::from(".init_array"),
+                String::from(".init_array.vendor_foo"),
+            ),
+            (String::from(".note.soda"), String::from(".note.vendor_foo")),
+        ],
+        ..Default::default()
+    };
+    let output = crate::convert_elf(input, &options).unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    let default_output = crate::convert_elf(input, &Default::default()).unwrap();
+    let default_output_file = object::File::parse(&*default_output).unwrap();
+
+    // The renamed sections keep their contents and relocations. The note records the renames, so its size differs.
+    for (old_name, new_name) in &options.rename_sections[..3] {
+        assert!(
+            output_file.section_by_name(old_name).is_none(),
+            "{}",
+            old_name
+        );
+        let section = output_file.section_by_name(new_name).unwrap();
+        let default_section = default_output_file.section_by_name(old_name).unwrap();
+        assert_eq!(section.size(), default_section.size(), "{}", new_name);
+        assert_eq!(
+            section.relocations().count(),
+            default_section.relocations().count(),
+            "{}",
+            new_name
+        );
+    }
+    assert_eq!(
+        output_file.sections().count(),
+        default_output_file.sections().count()
+    );
+
+    // The renamed provenance note is still found.
+    assert!(output_file.section_by_name(".note.soda").is_none());
+    let provenances = crate::read_provenance(&output).unwrap();
+    assert_eq!(provenances.len(), 1);
+    assert!(provenances[0].options.contains(".text.vendor_foo"));
+}
+
+#[test]
+fn test_check_elf_layout() {
+    use object::Endianness;
+
+    use crate::{ConvertOptions, CreateContextError, Error};
+
+    let corrupted_data = |builder: builder::ElfBuilder| match crate::convert_elf(
+        &builder.build(),
+        &ConvertOptions::default(),
+    ) {
+        Err(err @ Error::CreateContextError(CreateContextError::CorruptedData(_))) => {
+            assert_eq!(err.exit_code(), Error::EXIT_INVALID_INPUT);
+            err.to_string()
+        }
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    };
+
+    let mut x86_64 = builder::minimal_dso();
+    x86_64.endian = Endianness::Big;
+    assert_eq!(
+        corrupted_data(x86_64),
+        "corrupted input: e_ident says ELFDATA2MSB, but x86_64 files are ELFDATA2LSB"
+    );
+
+    let mut s390x = builder::minimal_dso().into_s390x();
+    s390x.endian = Endianness::Little;
+    assert_eq!(
+        corrupted_data(s390x),
+        "corrupted input: e_ident says ELFDATA2LSB, but s390x files are ELFDATA2MSB"
+    );
+
+    let mut i386 = builder::minimal_dso().into_i386();
+    i386.is_64 = true;
+    i386.is_rela = true;
+    assert_eq!(
+        corrupted_data(i386),
+        "corrupted input: e_ident says ELFCLASS64, but i386 files are ELFCLASS32"
+    );
+
+    let mut arm = builder::minimal_dso().into_arm();
+    arm.is_64 = true;
+    assert_eq!(
+        corrupted_data(arm),
+        "corrupted input: e_ident says ELFCLASS64, but arm files are ELFCLASS32"
+    );
+
+    // AArch64 comes in both byte orders.
+    let mut aarch64 = builder::minimal_dso().into_aarch64();
+    aarch64.endian = Endianness::Big;
+    crate::convert_elf(&aarch64.build(), &ConvertOptions::default()).unwrap();
+}
+
+#[test]
+fn test_convert_error_variants() {
+    use object::elf::{EM_SPARCV9, ET_REL, R_X86_64_64};
+    use object::Architecture;
+
+    use crate::elf::pass::reloc::ConvertRelocationError;
+    use crate::{CreateContextError, Error};
+
+    let mut builder = builder::minimal_dso();
+    builder.e_machine = EM_SPARCV9;
+    let err = crate::convert_elf(&builder.build(), &crate::ConvertOptions::default()).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::CreateContextError(CreateContextError::UnsupportedArch(Architecture::Sparc64))
+        ),
+        "{:?}",
+        err
+    );
+
+    let mut builder = builder::minimal_dso();
+    builder.e_type = ET_REL;
+    let err = crate::convert_elf(&builder.build(), &crate::ConvertOptions::default()).unwrap_err();
+    assert!(matches!(err, Error::NotSharedLibrary(_)), "{:?}", err);
+
+    // The error of the failed pass is kept as the source of the error.
+    let mut builder = builder::minimal_dso();
+    builder.add_relocation(0x2000, R_X86_64_64, 42, 0);
+    let err = crate::convert_elf(&builder.build(), &crate::ConvertOptions::default()).unwrap_err();
+    let Error::RunPassError(run_pass_err) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(run_pass_err.name, "convert relocations");
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(matches!(
+        source.downcast_ref::<ConvertRelocationError>(),
+        Some(ConvertRelocationError::MissingSymbol { index: 42, .. })
+    ));
+}
+
+/// An observer that records the names of the started passes and cancels the conversion once the given number of passes
+/// have finished, or once the pass with the given name has started.
+#[derive(Debug, Default)]
+struct CountingObserver {
+    cancel_after: usize,
+    cancel_in: &'static str,
+    started: std::sync::Mutex<Vec<String>>,
+    finished: std::sync::atomic::AtomicUsize,
+}
+
+impl crate::PassObserver for CountingObserver {
+    fn pass_started(&self, name: &str, _index: usize, _count: usize) {
+        self.started.lock().unwrap().push(String::from(name));
+    }
+
+    fn pass_finished(&self, _name: &str, _index: usize, _count: usize) {
+        self.finished
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn should_cancel(&self) -> bool {
+        self.finished.load(std::sync::atomic::Ordering::Relaxed) >= self.cancel_after
+            || self
+                .started
+                .lock()
+                .unwrap()
+                .last()
+                .is_some_and(|name| name == self.cancel_in)
+    }
+}
+
+#[test]
+fn test_convert_cancelled() {
+    use std::sync::Arc;
+
+    use object::elf::R_X86_64_RELATIVE;
+
+    let mut builder = builder::minimal_dso();
+    builder.add_relocation(0x2000, R_X86_64_RELATIVE, 0, 0x1000);
+    let input = builder.build();
+    let convert = |observer: &Arc<CountingObserver>| {
+        let options = crate::ConvertOptions {
+            observer: Some(observer.clone()),
+            ..Default::default()
+        };
+        crate::convert_elf(&input, &options)
+    };
+
+    // Cancelled between passes.
+    let observer = Arc::new(CountingObserver {
+        cancel_after: 1,
+        ..Default::default()
+    });
+    let err = convert(&observer).unwrap_err();
+    assert!(matches!(err, crate::Error::Cancelled), "{:?}", err);
+    assert_eq!(
+        *observer.started.lock().unwrap(),
+        vec![String::from("copy sections")]
+    );
+
+    // Cancelled within the relocation loop.
+    let observer = Arc::new(CountingObserver {
+        cancel_after: usize::MAX,
+        cancel_in: "convert relocations",
+        ..Default::default()
+    });
+    let err = convert(&observer).unwrap_err();
+    assert!(matches!(err, crate::Error::Cancelled), "{:?}", err);
+    let started = observer.started.lock().unwrap();
+    assert_eq!(started.last().unwrap(), "convert relocations");
+    assert_eq!(
+        observer.finished.load(std::sync::atomic::Ordering::Relaxed),
+        started.len() - 1
+    );
+}
+
+#[test]
+fn test_convert_os_abi() {
+    use object::elf::{ELFOSABI_FREEBSD, ELFOSABI_NONE};
+    use object::{FileFlags, Object as _};
+
+    let mut builder = builder::minimal_dso();
+    builder.os_abi = ELFOSABI_FREEBSD;
+    let input = builder.build();
+
+    // The OS/ABI is carried over, while the ABI version is not.
+    let output = crate::convert_elf(&input, &Default::default()).unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert_eq!(
+        output_file.flags(),
+        FileFlags::Elf {
+            os_abi: ELFOSABI_FREEBSD,
+            abi_version: 0,
+            e_flags: 0
+        }
+    );
+
+    let output = crate::convert_elf(&builder::minimal_dso().build(), &Default::default()).unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert!(matches!(
+        output_file.flags(),
+        FileFlags::Elf {
+            os_abi: ELFOSABI_NONE,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_convert_riscv64() {
+    use object::elf::{EF_RISCV_FLOAT_ABI_DOUBLE, EF_RISCV_RVC, R_RISCV_RELATIVE};
+    use object::{Architecture, FileFlags, Object as _, ObjectSection as _};
+
+    let mut builder = builder::minimal_dso().into_riscv64();
+    builder.add_relocation(0x2000, R_RISCV_RELATIVE, 0, 0x1000);
+
+    let output = crate::elf::convert(
+        builder.build_elf64(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert_eq!(output_file.architecture(), Architecture::Riscv64);
+    assert_eq!(
+        output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .count(),
+        1
+    );
+
+    // The float ABI and the RVC flags are carried over, or the output can't be linked with other objects.
+    let FileFlags::Elf { e_flags, .. } = output_file.flags() else {
+        panic!("unexpected file flags");
+    };
+    assert_eq!(e_flags, EF_RISCV_RVC | EF_RISCV_FLOAT_ABI_DOUBLE);
+}
+
+#[test]
+fn test_convert_mips64el() {
+    use object::elf::{EF_MIPS_ARCH_64R2, EF_MIPS_CPIC, EF_MIPS_PIC, R_MIPS_64, R_MIPS_REL32};
+    use object::{Architecture, FileFlags, Object as _, ObjectSection as _};
+
+    let mut builder = builder::minimal_dso().into_mips64el();
+    write_u64(&mut builder.section_mut(2).data, 0, 0x1000);
+    builder.add_relocation(0x2000, R_MIPS_REL32 | (R_MIPS_64 << 8), 0, 0);
+
+    let output = crate::elf::convert(
+        builder.build_elf64(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert_eq!(output_file.architecture(), Architecture::Mips64);
+    assert!(output_file.is_little_endian());
+    assert_eq!(
+        output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .count(),
+        1
+    );
+
+    // The ISA level and the PIC flags are carried over.
+    let FileFlags::Elf { e_flags, .. } = output_file.flags() else {
+        panic!("unexpected file flags");
+    };
+    assert_eq!(e_flags, EF_MIPS_ARCH_64R2 | EF_MIPS_PIC | EF_MIPS_CPIC);
+}
+
+#[test]
+fn test_convert_loongarch64() {
+    use object::elf::{
+        EF_LARCH_ABI_DOUBLE_FLOAT, EF_LARCH_OBJABI_V1, R_LARCH_64, R_LARCH_IRELATIVE,
+        R_LARCH_JUMP_SLOT, R_LARCH_RELATIVE,
+    };
+    use object::{Architecture, FileFlags, Object as _, ObjectSection as _};
+
+    // .data[0x18] is initialized by the resolver at .text[0].
+    let mut builder = builder::tls_dso().into_loongarch64();
+    builder.add_relocation(0x2000, R_LARCH_RELATIVE, 0, 0x1000);
+    builder.add_relocation(0x2008, R_LARCH_64, 2, 0);
+    builder.add_relocation(0x2010, R_LARCH_JUMP_SLOT, 1, 0);
+    builder.add_relocation(0x2018, R_LARCH_IRELATIVE, 0, 0x1000);
+
+    let output = crate::elf::convert(
+        builder.build_elf64(),
+        &crate::elf::ConvertOptions::default(),
+    )
+    .unwrap()
+    .write()
+    .unwrap();
+    let output_file = object::File::parse(&*output).unwrap();
+    assert_eq!(output_file.architecture(), Architecture::LoongArch64);
+
+    // The IRELATIVE relocation is applied by the generated initializer instead.
+    assert_eq!(
+        output_file
+            .section_by_name(".soda")
+            .unwrap()
+            .relocations()
+            .count(),
+        3
+    );
+    assert!(output_file
+        .section_by_name(".text.soda.irelative")
+        .is_some());
+
+    let FileFlags::Elf { e_flags, .. } = output_file.flags() else {
+        panic!("unexpected file flags");
+    };
+    assert_eq!(e_flags, EF_LARCH_ABI_DOUBLE_FLOAT | EF_LARCH_OBJABI_V1);
+}
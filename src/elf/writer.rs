@@ -0,0 +1,473 @@
+//! The raw output backend, which writes ELF outputs through [`object::write::elf::Writer`].
+//!
+//! The backend lays out the file in the order of the object writer: the file header, the data of each section, the
+//! symbol table with its string tables, the relocations of each section, and the section headers. Sections and symbols
+//! keep the indexes that the object writer gives them, so that the two backends write equivalent files, and the layout
+//! of each part of the file stays under the control of this module.
+
+use object::elf;
+use object::write::elf::{FileHeader, Rel, SectionHeader, SectionIndex, Sym, SymbolIndex, Writer};
+use object::write::{StringId, WritableBuffer};
+use object::{
+    AddressSize, Architecture, FileFlags, RelocationEncoding, RelocationKind, SectionFlags,
+    SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
+
+use crate::output::{Error, Object as OutputObject, Relocation as OutputRelocation, Result};
+use crate::output::{Symbol as OutputSymbol, SymbolSection as OutputSymbolSection};
+
+/// Determine whether the relocations of the given architecture carry explicit addends.
+pub(crate) fn has_relocation_addend(arch: Architecture) -> Result<bool> {
+    match arch {
+        Architecture::X86_64
+        | Architecture::Aarch64
+        | Architecture::LoongArch64
+        | Architecture::Mips64
+        | Architecture::PowerPc64
+        | Architecture::Riscv64
+        | Architecture::S390x => Ok(true),
+        Architecture::I386 | Architecture::Arm => Ok(false),
+        _ => Err(Error(format!("unimplemented architecture {:?}", arch))),
+    }
+}
+
+/// Write the given ELF output into the given buffer.
+pub(crate) fn write(output: &OutputObject<'_>, buffer: &mut dyn WritableBuffer) -> Result<()> {
+    let arch = output.architecture();
+    let is_rela = has_relocation_addend(arch)?;
+    let is_64 = arch.address_size() == Some(AddressSize::U64);
+    let e_machine = get_machine(arch)?;
+
+    let reloc_names: Vec<Vec<u8>> = output
+        .sections()
+        .map(|(_, section)| {
+            if section.relocations().is_empty() {
+                return Vec::new();
+            }
+            let prefix: &[u8] = if is_rela { b".rela" } else { b".rel" };
+            [prefix, section.name_bytes()].concat()
+        })
+        .collect();
+
+    let mut writer = Writer::new(output.endian(), is_64, buffer);
+    writer.reserve_file_header();
+
+    // Sections are followed by their relocation sections.
+    let mut section_offsets = Vec::new();
+    for ((_, section), reloc_name) in output.sections().zip(&reloc_names) {
+        let index = writer.reserve_section_index();
+        let offset = if section.is_bss() {
+            writer.reserve(0, section.align() as usize)
+        } else {
+            writer.reserve(section.data().len(), section.align() as usize)
+        };
+        let name = writer.add_section_name(section.name_bytes());
+        let reloc_name = (!section.relocations().is_empty()).then(|| {
+            writer.reserve_section_index();
+            writer.add_section_name(reloc_name)
+        });
+        section_offsets.push(SectionOffsets {
+            index,
+            offset,
+            name,
+            reloc_offset: 0,
+            reloc_name,
+        });
+    }
+
+    // Local symbols precede global ones.
+    let symbols: Vec<_> = output.symbols().map(|(_, symbol)| symbol).collect();
+    let mut symbol_indexes = vec![SymbolIndex(0); symbols.len()];
+    let mut symbol_names = vec![None; symbols.len()];
+    writer.reserve_null_symbol_index();
+    for local in [true, false] {
+        for (idx, symbol) in symbols.iter().enumerate() {
+            if symbol.is_local() == local {
+                let section = symbol.section.id().map(|id| section_offsets[id.0].index);
+                symbol_indexes[idx] = writer.reserve_symbol_index(section);
+            }
+        }
+    }
+    let num_local = symbols.iter().filter(|symbol| symbol.is_local()).count() as u32 + 1;
+    for (idx, symbol) in symbols.iter().enumerate() {
+        if symbol.kind != SymbolKind::Section && !symbol.name.is_empty() {
+            symbol_names[idx] = Some(writer.add_string(&symbol.name));
+        }
+    }
+
+    writer.reserve_symtab_section_index();
+    writer.reserve_symtab();
+    if writer.symtab_shndx_needed() {
+        writer.reserve_symtab_shndx_section_index();
+    }
+    writer.reserve_symtab_shndx();
+    writer.reserve_strtab_section_index();
+    writer.reserve_strtab();
+
+    for ((_, section), offsets) in output.sections().zip(&mut section_offsets) {
+        let num_relocs = section.relocations().len();
+        if num_relocs != 0 {
+            offsets.reloc_offset = writer.reserve_relocations(num_relocs, is_rela);
+        }
+    }
+
+    writer.reserve_shstrtab_section_index();
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    let (os_abi, abi_version, e_flags) = match output.flags {
+        FileFlags::Elf {
+            os_abi,
+            abi_version,
+            e_flags,
+        } => (os_abi, abi_version, e_flags),
+        _ => (elf::ELFOSABI_NONE, 0, 0),
+    };
+    writer.write_file_header(&FileHeader {
+        os_abi,
+        abi_version,
+        e_type: elf::ET_REL,
+        e_machine,
+        e_entry: 0,
+        e_flags,
+    })?;
+
+    for ((_, section), offsets) in output.sections().zip(&section_offsets) {
+        writer.write_align(section.align() as usize);
+        debug_assert_eq!(offsets.offset, writer.len());
+        if !section.is_bss() {
+            writer.write(section.data());
+        }
+    }
+
+    writer.write_null_symbol();
+    for local in [true, false] {
+        for (idx, symbol) in symbols.iter().enumerate() {
+            if symbol.is_local() == local {
+                writer.write_symbol(&get_symbol(symbol, symbol_names[idx], &section_offsets)?);
+            }
+        }
+    }
+    writer.write_symtab_shndx();
+    writer.write_strtab();
+
+    for (_, section) in output.sections() {
+        if section.relocations().is_empty() {
+            continue;
+        }
+        writer.write_align_relocation();
+        for reloc in section.relocations() {
+            writer.write_relocation(
+                is_rela,
+                &Rel {
+                    r_offset: reloc.offset,
+                    r_sym: symbol_indexes[reloc.symbol.0].0,
+                    r_type: get_relocation_type(arch, reloc)?,
+                    r_addend: reloc.addend,
+                },
+            );
+        }
+    }
+
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    let symtab_index = writer.symtab_index();
+    for ((_, section), offsets) in output.sections().zip(&section_offsets) {
+        writer.write_section_header(&SectionHeader {
+            name: Some(offsets.name),
+            sh_type: match section.kind() {
+                SectionKind::UninitializedData | SectionKind::UninitializedTls => elf::SHT_NOBITS,
+                SectionKind::Note => elf::SHT_NOTE,
+                SectionKind::Elf(sh_type) => sh_type,
+                _ => elf::SHT_PROGBITS,
+            },
+            sh_flags: match section.flags {
+                SectionFlags::Elf { sh_flags } => sh_flags,
+                _ => get_default_section_flags(section.kind(), section.name())?,
+            },
+            sh_addr: 0,
+            sh_offset: offsets.offset as u64,
+            sh_size: section.size(),
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: section.align(),
+            sh_entsize: match section.kind() {
+                SectionKind::ReadOnlyString | SectionKind::OtherString => 1,
+                _ => 0,
+            },
+        });
+
+        if let Some(reloc_name) = offsets.reloc_name {
+            writer.write_relocation_section_header(
+                reloc_name,
+                offsets.index,
+                symtab_index,
+                offsets.reloc_offset,
+                section.relocations().len(),
+                is_rela,
+            );
+        }
+    }
+    writer.write_symtab_section_header(num_local);
+    writer.write_symtab_shndx_section_header();
+    writer.write_strtab_section_header();
+    writer.write_shstrtab_section_header();
+
+    debug_assert_eq!(writer.reserved_len(), writer.len());
+    Ok(())
+}
+
+/// Where the parts of an output section are written.
+struct SectionOffsets {
+    index: SectionIndex,
+    offset: usize,
+    name: StringId,
+    reloc_offset: usize,
+    reloc_name: Option<StringId>,
+}
+
+fn get_machine(arch: Architecture) -> Result<u16> {
+    match arch {
+        Architecture::X86_64 => Ok(elf::EM_X86_64),
+        Architecture::I386 => Ok(elf::EM_386),
+        Architecture::Aarch64 => Ok(elf::EM_AARCH64),
+        Architecture::Arm => Ok(elf::EM_ARM),
+        Architecture::LoongArch64 => Ok(elf::EM_LOONGARCH),
+        Architecture::Mips64 => Ok(elf::EM_MIPS),
+        Architecture::PowerPc64 => Ok(elf::EM_PPC64),
+        Architecture::Riscv64 => Ok(elf::EM_RISCV),
+        Architecture::S390x => Ok(elf::EM_S390),
+        _ => Err(Error(format!("unimplemented architecture {:?}", arch))),
+    }
+}
+
+/// Get the section flags of a section of the given kind that has no ELF flags.
+fn get_default_section_flags(kind: SectionKind, name: Option<&str>) -> Result<u64> {
+    let sh_flags = match kind {
+        SectionKind::Text => elf::SHF_ALLOC | elf::SHF_EXECINSTR,
+        SectionKind::Data | SectionKind::ReadOnlyDataWithRel | SectionKind::UninitializedData => {
+            elf::SHF_ALLOC | elf::SHF_WRITE
+        }
+        SectionKind::Tls | SectionKind::UninitializedTls => {
+            elf::SHF_ALLOC | elf::SHF_WRITE | elf::SHF_TLS
+        }
+        SectionKind::ReadOnlyData => elf::SHF_ALLOC,
+        SectionKind::ReadOnlyString => elf::SHF_ALLOC | elf::SHF_STRINGS | elf::SHF_MERGE,
+        SectionKind::OtherString => elf::SHF_STRINGS | elf::SHF_MERGE,
+        SectionKind::Other
+        | SectionKind::Debug
+        | SectionKind::Metadata
+        | SectionKind::Linker
+        | SectionKind::Note
+        | SectionKind::Elf(_) => 0,
+        _ => {
+            return Err(Error(format!(
+                "unimplemented section `{}` kind {:?}",
+                name.unwrap_or(""),
+                kind
+            )))
+        }
+    };
+    Ok(sh_flags.into())
+}
+
+/// Get the symbol table entry of the given symbol.
+fn get_symbol(
+    symbol: &OutputSymbol,
+    name: Option<StringId>,
+    section_offsets: &[SectionOffsets],
+) -> Result<Sym> {
+    let st_info = match symbol.flags {
+        SymbolFlags::Elf { st_info, .. } => st_info,
+        _ => {
+            let st_type = match symbol.kind {
+                SymbolKind::Text if !symbol.is_undefined() => elf::STT_FUNC,
+                SymbolKind::Data if symbol.is_common() => elf::STT_COMMON,
+                SymbolKind::Data if !symbol.is_undefined() => elf::STT_OBJECT,
+                SymbolKind::Section => elf::STT_SECTION,
+                SymbolKind::File => elf::STT_FILE,
+                SymbolKind::Tls => elf::STT_TLS,
+                SymbolKind::Null | SymbolKind::Text | SymbolKind::Data | SymbolKind::Label => {
+                    elf::STT_NOTYPE
+                }
+                _ if symbol.is_undefined() => elf::STT_NOTYPE,
+                kind => {
+                    return Err(Error(format!(
+                        "unimplemented symbol `{}` kind {:?}",
+                        symbol.name().unwrap_or(""),
+                        kind
+                    )))
+                }
+            };
+            let st_bind = if symbol.weak {
+                elf::STB_WEAK
+            } else if symbol.is_local() && !symbol.is_undefined() {
+                elf::STB_LOCAL
+            } else {
+                elf::STB_GLOBAL
+            };
+            (st_bind << 4) + st_type
+        }
+    };
+    let st_other = match symbol.flags {
+        SymbolFlags::Elf { st_other, .. } => st_other,
+        _ if symbol.scope == SymbolScope::Linkage => elf::STV_HIDDEN,
+        _ => elf::STV_DEFAULT,
+    };
+    let (st_shndx, section) = match symbol.section {
+        OutputSymbolSection::None | OutputSymbolSection::Absolute => (elf::SHN_ABS, None),
+        OutputSymbolSection::Undefined => (elf::SHN_UNDEF, None),
+        OutputSymbolSection::Common => (elf::SHN_COMMON, None),
+        OutputSymbolSection::Section(id) => (0, Some(section_offsets[id.0].index)),
+    };
+    Ok(Sym {
+        name,
+        section,
+        st_info,
+        st_other,
+        st_shndx,
+        st_value: symbol.value,
+        st_size: symbol.size,
+    })
+}
+
+/// Get the ELF type of the given relocation, which may be one of the generic kinds.
+fn get_relocation_type(arch: Architecture, reloc: &OutputRelocation) -> Result<u32> {
+    use RelocationEncoding::Generic;
+    use RelocationKind::{Absolute, Elf, PltRelative, Relative};
+
+    let r_type = match (arch, reloc.kind, reloc.encoding, reloc.size) {
+        (_, Elf(r_type), _, _) => r_type,
+        (Architecture::X86_64, Absolute, Generic, 64) => elf::R_X86_64_64,
+        (Architecture::X86_64, Absolute, Generic, 32) => elf::R_X86_64_32,
+        (Architecture::X86_64, Absolute, RelocationEncoding::X86Signed, 32) => elf::R_X86_64_32S,
+        (Architecture::X86_64, Relative, _, 32) => elf::R_X86_64_PC32,
+        (Architecture::X86_64, PltRelative, _, 32) => elf::R_X86_64_PLT32,
+        (Architecture::I386, Absolute, _, 32) => elf::R_386_32,
+        (Architecture::I386, Relative, _, 32) => elf::R_386_PC32,
+        (Architecture::I386, PltRelative, _, 32) => elf::R_386_PLT32,
+        (Architecture::Aarch64, Absolute, Generic, 64) => elf::R_AARCH64_ABS64,
+        (Architecture::Aarch64, Absolute, Generic, 32) => elf::R_AARCH64_ABS32,
+        (Architecture::Aarch64, Relative, Generic, 64) => elf::R_AARCH64_PREL64,
+        (Architecture::Aarch64, Relative, Generic, 32) => elf::R_AARCH64_PREL32,
+        (Architecture::Arm, Absolute, _, 32) => elf::R_ARM_ABS32,
+        (Architecture::LoongArch64, Absolute, _, 64) => elf::R_LARCH_64,
+        (Architecture::LoongArch64, Absolute, _, 32) => elf::R_LARCH_32,
+        (Architecture::LoongArch64, Relative, _, 32) => elf::R_LARCH_32_PCREL,
+        (Architecture::Mips64, Absolute, _, 64) => elf::R_MIPS_64,
+        (Architecture::Mips64, Absolute, _, 32) => elf::R_MIPS_32,
+        (Architecture::PowerPc64, Absolute, _, 64) => elf::R_PPC64_ADDR64,
+        (Architecture::PowerPc64, Absolute, _, 32) => elf::R_PPC64_ADDR32,
+        (Architecture::Riscv64, Absolute, _, 64) => elf::R_RISCV_64,
+        (Architecture::Riscv64, Absolute, _, 32) => elf::R_RISCV_32,
+        (Architecture::Riscv64, Relative, Generic, 32) => elf::R_RISCV_32_PCREL,
+        (Architecture::S390x, Absolute, Generic, 64) => elf::R_390_64,
+        (Architecture::S390x, Absolute, Generic, 32) => elf::R_390_32,
+        (Architecture::S390x, Relative, Generic, 64) => elf::R_390_PC64,
+        (Architecture::S390x, Relative, Generic, 32) => elf::R_390_PC32,
+        _ => return Err(Error(format!("unimplemented relocation {:?}", reloc))),
+    };
+    Ok(r_type)
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{
+        R_386_RELATIVE, R_390_RELATIVE, R_AARCH64_RELATIVE, R_ARM_RELATIVE, R_LARCH_RELATIVE,
+        R_MIPS_64, R_MIPS_REL32, R_PPC64_RELATIVE, R_RISCV_RELATIVE, R_X86_64_64,
+        R_X86_64_RELATIVE, STB_GLOBAL, STT_NOTYPE,
+    };
+
+    use crate::elf::test::builder::{self, BuilderSymbol, ElfBuilder};
+    use crate::{ConvertOptions, OutputBackend};
+
+    /// Convert the given input with both backends, and check that they write the same file.
+    fn check_backends(input: &[u8], options: &ConvertOptions) {
+        let output = crate::convert(input, options).unwrap();
+        let options = ConvertOptions {
+            backend: OutputBackend::Raw,
+            ..options.clone()
+        };
+        let raw_output = crate::convert(input, &options).unwrap();
+        assert!(raw_output == output);
+
+        let mut streamed_output = Vec::new();
+        crate::convert_to_writer(input, &options, &mut streamed_output).unwrap();
+        assert!(streamed_output == raw_output);
+    }
+
+    #[test]
+    fn test_raw_backend() {
+        let fixtures: [&[u8]; 2] = [
+            include_bytes!("test/libspdlog.so.1.12.0"),
+            include_bytes!("test/libsoda32.so"),
+        ];
+        let stable_layout = ConvertOptions {
+            stable_layout: true,
+            ..Default::default()
+        };
+        for input in fixtures {
+            check_backends(input, &ConvertOptions::default());
+            check_backends(input, &stable_layout);
+        }
+
+        // The stubs that write the RELRO data at run time refer to it with generic relative relocations.
+        let protect_relro = ConvertOptions {
+            protect_relro: true,
+            ..Default::default()
+        };
+        check_backends(fixtures[0], &protect_relro);
+    }
+
+    #[test]
+    fn test_raw_backend_architectures() {
+        let with_relocation = |builder: ElfBuilder, r_type: u32, addend: i64| {
+            let mut builder = builder;
+            builder.add_relocation(0x2000, r_type, 0, addend);
+            builder
+        };
+        let inputs = [
+            with_relocation(builder::minimal_dso(), R_X86_64_RELATIVE, 0x1000),
+            with_relocation(builder::minimal_dso().into_i386(), R_386_RELATIVE, 0),
+            with_relocation(builder::minimal_dso().into_arm(), R_ARM_RELATIVE, 0),
+            with_relocation(
+                builder::minimal_dso().into_aarch64(),
+                R_AARCH64_RELATIVE,
+                0x1000,
+            ),
+            with_relocation(
+                builder::minimal_dso().into_loongarch64(),
+                R_LARCH_RELATIVE,
+                0x1000,
+            ),
+            with_relocation(
+                builder::minimal_dso().into_ppc64le(),
+                R_PPC64_RELATIVE,
+                0x1000,
+            ),
+            with_relocation(
+                builder::minimal_dso().into_riscv64(),
+                R_RISCV_RELATIVE,
+                0x1000,
+            ),
+            with_relocation(builder::minimal_dso().into_s390x(), R_390_RELATIVE, 0x1000),
+            with_relocation(
+                builder::minimal_dso().into_mips64el(),
+                R_MIPS_REL32 | (R_MIPS_64 << 8),
+                0,
+            ),
+        ];
+        for input in inputs {
+            check_backends(&input.build(), &ConvertOptions::default());
+        }
+
+        // A relocation against an imported symbol keeps its symbol.
+        let mut builder = builder::minimal_dso();
+        let import = builder.add_symbol(BuilderSymbol::new(
+            "malloc", STB_GLOBAL, STT_NOTYPE, 0, 0, 0,
+        ));
+        builder.add_relocation(0x2008, R_X86_64_64, import, 0);
+        check_backends(&builder.build(), &ConvertOptions::default());
+    }
+}
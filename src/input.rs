@@ -0,0 +1,229 @@
+//! Shared libraries that are parsed once and then checked, inspected and converted.
+
+use std::io::Write;
+use std::ops::Range;
+
+use object::read::File as InputFile;
+use object::{Architecture, BinaryFormat, Object as _, ObjectKind, ObjectSegment as _};
+
+use crate::elf::TlsImage;
+use crate::output::Object as OutputObject;
+use crate::{ConvertOptions, Error};
+
+/// An input file that has been parsed, together with a summary of its headers.
+///
+/// The input borrows the data it was parsed from, so the data is read once and parsed once no matter how many times the
+/// input is checked or inspected before it is converted. Unlike the conversion functions, parsing accepts files of any
+/// object kind, so that callers can report other kinds in their own way. Converting a file that is not a shared
+/// library fails with [`Error::NotSharedLibrary`].
+///
+/// # Example
+///
+/// ```
+/// let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/elf/test/libspdlog.so.1.12.0");
+/// let data = std::fs::read(input_path).unwrap();
+///
+/// let input = soda::Input::parse(&data).unwrap();
+/// assert_eq!(input.summary().kind, object::ObjectKind::Dynamic);
+/// let output = input.convert(&soda::ConvertOptions::default()).unwrap();
+/// assert_eq!(output, soda::convert(&data, &soda::ConvertOptions::default()).unwrap());
+/// ```
+#[derive(Debug)]
+pub struct Input<'d> {
+    file: InputFile<'d>,
+    data: &'d [u8],
+    summary: InputSummary,
+}
+
+/// A summary of the headers of an input file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputSummary {
+    /// The file format.
+    pub format: BinaryFormat,
+
+    /// The object kind, which is [`ObjectKind::Dynamic`] for shared libraries.
+    pub kind: ObjectKind,
+
+    /// The target architecture.
+    pub architecture: Architecture,
+
+    /// Whether the file uses 64-bit addresses.
+    pub is_64: bool,
+
+    /// Whether the file is big-endian.
+    pub is_big_endian: bool,
+
+    /// The address ranges of the segments that are loaded into memory, in the order of the file.
+    pub segments: Vec<Range<u64>>,
+
+    /// The address range of the dynamic table given by the PT_DYNAMIC segment of an ELF file.
+    pub dynamic_table: Option<Range<u64>>,
+
+    /// The TLS initialization image given by the PT_TLS segment of an ELF file. Its size and alignment are what the
+    /// output adds to the static TLS block of the program.
+    pub tls_image: Option<TlsImage>,
+}
+
+impl<'d> Input<'d> {
+    /// Parse the given input file data.
+    pub fn parse(data: &'d [u8]) -> Result<Self, Error> {
+        let file = InputFile::parse(data)?;
+        let (dynamic_table, tls_image) = match &file {
+            InputFile::Elf32(elf_file) => (
+                crate::elf::get_dynamic_table_range(elf_file),
+                crate::elf::get_tls_image(elf_file),
+            ),
+            InputFile::Elf64(elf_file) => (
+                crate::elf::get_dynamic_table_range(elf_file),
+                crate::elf::get_tls_image(elf_file),
+            ),
+            _ => (None, None),
+        };
+        let summary = InputSummary {
+            format: file.format(),
+            kind: file.kind(),
+            architecture: file.architecture(),
+            is_64: file.is_64(),
+            is_big_endian: !file.is_little_endian(),
+            segments: file
+                .segments()
+                .map(|seg| seg.address()..seg.address() + seg.size())
+                .collect(),
+            dynamic_table,
+            tls_image,
+        };
+        Ok(Self {
+            file,
+            data,
+            summary,
+        })
+    }
+
+    /// Get the data the input was parsed from.
+    pub fn data(&self) -> &'d [u8] {
+        self.data
+    }
+
+    /// Get the summary of the headers of the input.
+    pub fn summary(&self) -> &InputSummary {
+        &self.summary
+    }
+
+    /// Get the parsed input file.
+    pub fn file(&self) -> &InputFile<'d> {
+        &self.file
+    }
+
+    /// Get the names of the shared libraries that the input depends on, like [`needed_libraries`](crate::needed_libraries).
+    /// Only ELF inputs are supported.
+    pub fn needed_libraries(&self) -> Result<Vec<String>, Error> {
+        let needed = match &self.file {
+            InputFile::Elf32(elf_file) => crate::elf::get_needed_libraries(elf_file)?,
+            InputFile::Elf64(elf_file) => crate::elf::get_needed_libraries(elf_file)?,
+            input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+        };
+        Ok(needed)
+    }
+
+    /// Convert the input like [`convert`](crate::convert).
+    pub fn convert(self, options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+        crate::write_output(self.into_output(options)?, options)
+    }
+
+    /// Convert the input like [`convert_to_writer`](crate::convert_to_writer).
+    pub fn convert_to_writer(
+        self,
+        options: &ConvertOptions,
+        writer: &mut dyn Write,
+    ) -> Result<(), Error> {
+        crate::write_output_to(self.into_output(options)?, options, writer)
+    }
+
+    /// Check that the input is a shared library, and get the parsed file.
+    pub(crate) fn into_shared_library(self) -> Result<InputFile<'d>, Error> {
+        if self.summary.kind != ObjectKind::Dynamic {
+            return Err(Error::NotSharedLibrary(self.summary.kind));
+        }
+        Ok(self.file)
+    }
+
+    pub(crate) fn into_output(
+        self,
+        options: &ConvertOptions,
+    ) -> Result<OutputObject<'static>, Error> {
+        let data = self.data;
+        let output = match self.into_shared_library()? {
+            InputFile::Elf32(elf_file) => crate::elf::convert(elf_file, options)?,
+            InputFile::Elf64(elf_file) => crate::elf::convert(elf_file, options)?,
+            InputFile::MachO64(macho_file) => {
+                crate::macho::convert(crate::macho::MachOInput::new(macho_file, data)?, options)?
+            }
+            InputFile::Pe64(pe_file) => crate::pe::convert(pe_file, options)?,
+            input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+        };
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use object::{Architecture, BinaryFormat, ObjectKind};
+
+    use crate::{ConvertOptions, Error};
+
+    use super::Input;
+
+    const SPDLOG: &[u8] = include_bytes!("elf/test/libspdlog.so.1.12.0");
+
+    #[test]
+    fn test_input_summary() {
+        let input = Input::parse(SPDLOG).unwrap();
+        let summary = input.summary();
+        assert_eq!(summary.format, BinaryFormat::Elf);
+        assert_eq!(summary.kind, ObjectKind::Dynamic);
+        assert_eq!(summary.architecture, Architecture::X86_64);
+        assert!(summary.is_64);
+        assert!(!summary.is_big_endian);
+        assert!(!summary.segments.is_empty());
+
+        // The dynamic table is in one of the loaded segments.
+        let dynamic_table = summary.dynamic_table.clone().unwrap();
+        assert!(!dynamic_table.is_empty());
+        assert!(summary
+            .segments
+            .iter()
+            .any(|seg| seg.start <= dynamic_table.start && dynamic_table.end <= seg.end));
+
+        // The TLS image only holds zero-initialized variables.
+        let tls_image = summary.tls_image.unwrap();
+        assert_eq!(tls_image.file_size, 0);
+        assert_eq!(tls_image.mem_size, 0x10);
+        assert_eq!(tls_image.align, 8);
+
+        assert!(input
+            .needed_libraries()
+            .unwrap()
+            .iter()
+            .any(|soname| soname == "libc.so.6"));
+    }
+
+    #[test]
+    fn test_input_convert() {
+        let data = SPDLOG;
+        let options = ConvertOptions::default();
+
+        let input = Input::parse(data).unwrap();
+        assert_eq!(input.summary().kind, ObjectKind::Dynamic);
+        assert!(input.convert(&options).unwrap() == crate::convert(data, &options).unwrap());
+
+        // Converting a relocatable file fails, although parsing it succeeds.
+        let object = crate::convert(data, &options).unwrap();
+        let input = Input::parse(&object).unwrap();
+        assert_eq!(input.summary().kind, ObjectKind::Relocatable);
+        assert!(input.summary().dynamic_table.is_none());
+        assert!(matches!(
+            input.convert(&options),
+            Err(Error::NotSharedLibrary(ObjectKind::Relocatable))
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+//! Convert shared libraries into relocatable object files that can be linked statically.
+//!
+//! The [`convert`] function converts a shared library given as its file data into the data of a relocatable object file
+//! of the same format, and [`merge_elf`] merges several ELF shared libraries into one. [`convert_elf`],
+//! [`convert_macho`] and [`convert_pe`] only accept inputs of a single format. [`convert_to_writer`] and
+//! [`merge_elf_to_writer`] write the output as it is serialized instead, which saves holding a copy of the whole
+//! output in memory. An [`Input`] parses a file once, so that it can be checked and inspected before it is converted.
+//! Each conversion runs a series of passes through a [`PassManager`]. The passes are available in the `pass` module of
+//! each format, so that custom pipelines can be built out of them. The passes build an [`output::Object`], which the
+//! [`OutputBackend`] given in the [`ConvertOptions`] serializes.
+//!
+//! The library keeps no process-global state and never logs through the `log` crate. Warnings and progress messages of
+//! a conversion are reported to the [`DiagnosticSink`] given in its [`ConvertOptions`], so conversions can run on
+//! several threads at once.
+//!
+//! The [`build::Builder`] converts shared libraries from build scripts and links them into the crate.
+//! [`search::find_library`] finds shared libraries by name like the `-l` option of linkers, and
+//! [`link_args::LinkArgs`] collects the libraries and flags that the final link of a converted object needs.
+//!
+//! With the `capi` feature, the `capi` module exposes the conversion through a C ABI. The header is `include/soda.h`.
+
+pub mod build;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod elf;
+mod input;
+pub mod link_args;
+pub mod macho;
+pub mod output;
+pub mod pass;
+pub mod pe;
+pub mod search;
+mod utils;
+
+use std::io::Write;
+use std::path::Path;
+
+use object::read::File as InputFile;
+use object::write::StreamingBuffer;
+use object::{BinaryFormat, ObjectKind};
+use thiserror::Error;
+
+use crate::output::Object as OutputObject;
+
+pub use crate::elf::pass::defsym::SymbolValue;
+pub use crate::elf::pass::init_array::CtorsStyle;
+pub use crate::elf::pass::note::Provenance;
+pub use crate::elf::pass::symbol_list::{ListedSymbol, SymbolList};
+pub use crate::elf::ConvertOptions;
+pub use crate::input::{Input, InputSummary};
+pub use crate::output::OutputBackend;
+pub use crate::pass::{
+    Cancelled, CreateContextError, DiagnosticSink, Diagnostics, Pass, PassContext, PassHandle,
+    PassManager, PassObserver, RunPassError, Severity,
+};
+
+/// Convert the given shared library into a relocatable file of the same format. See [`ConvertOptions`] for the options
+/// that apply to inputs other than ELF.
+pub fn convert(input: &[u8], options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    write_output(convert_object(input, options)?, options)
+}
+
+/// Convert the given shared library like [`convert`], and write the output into the given writer as it is serialized.
+///
+/// The serialized output is never held in memory as a whole, so the peak memory usage is lower than with [`convert`].
+/// Nothing is written if the conversion fails. Errors of the writer are returned as [`Error::IoError`], and the writer
+/// may have received part of the output then. It is advisable to use a buffered writer like
+/// [`BufWriter`](std::io::BufWriter).
+pub fn convert_to_writer(
+    input: &[u8],
+    options: &ConvertOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    write_output_to(convert_object(input, options)?, options, writer)
+}
+
+fn convert_object(input: &[u8], options: &ConvertOptions) -> Result<OutputObject<'static>, Error> {
+    Input::parse(input)?.into_output(options)
+}
+
+/// Convert the given ELF shared library into an ELF relocatable file.
+///
+/// # Example
+///
+/// ```
+/// use object::{Object as _, ObjectSymbol as _};
+///
+/// let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/elf/test/libspdlog.so.1.12.0");
+/// let input = std::fs::read(input_path).unwrap();
+///
+/// let output = soda::convert_elf(&input, &soda::ConvertOptions::default()).unwrap();
+/// let output_file = object::File::parse(output.as_slice()).unwrap();
+/// assert_eq!(output_file.kind(), object::ObjectKind::Relocatable);
+/// assert!(output_file
+///     .symbols()
+///     .any(|sym| sym.name() == Ok("__soda_image_start")));
+/// ```
+pub fn convert_elf(input: &[u8], options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    let output = match parse_input(input)? {
+        InputFile::Elf32(elf_file) => crate::elf::convert(elf_file, options)?,
+        InputFile::Elf64(elf_file) => crate::elf::convert(elf_file, options)?,
+        input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+    };
+    write_output(output, options)
+}
+
+/// Merge the given ELF shared libraries, each given with its name, into a single ELF relocatable file. See
+/// [`elf::merge`] for details.
+pub fn merge_elf(inputs: &[(&str, &[u8])], options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    write_output(merge_elf_object(inputs, options)?, options)
+}
+
+/// Merge the given ELF shared libraries like [`merge_elf`], and write the output into the given writer as it is
+/// serialized. See [`convert_to_writer`] for details.
+pub fn merge_elf_to_writer(
+    inputs: &[(&str, &[u8])],
+    options: &ConvertOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    write_output_to(merge_elf_object(inputs, options)?, options, writer)
+}
+
+/// Merge the given ELF shared libraries, each parsed already and given with its name, like [`merge_elf_to_writer`].
+pub fn merge_inputs_to_writer(
+    inputs: Vec<(&str, Input<'_>)>,
+    options: &ConvertOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    write_output_to(merge_input_objects(inputs, options)?, options, writer)
+}
+
+fn merge_elf_object(
+    inputs: &[(&str, &[u8])],
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error> {
+    let inputs = inputs
+        .iter()
+        .map(|(name, data)| Ok((*name, Input::parse(data)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    merge_input_objects(inputs, options)
+}
+
+fn merge_input_objects(
+    inputs: Vec<(&str, Input<'_>)>,
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error> {
+    let mut elf32_inputs = Vec::new();
+    let mut elf64_inputs = Vec::new();
+    for (name, input) in inputs {
+        match input.into_shared_library()? {
+            InputFile::Elf32(elf_file) => elf32_inputs.push((String::from(name), elf_file)),
+            InputFile::Elf64(elf_file) => elf64_inputs.push((String::from(name), elf_file)),
+            input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+        }
+    }
+
+    let output = match (elf32_inputs.is_empty(), elf64_inputs.is_empty()) {
+        (false, false) => return Err(CreateContextError::MixedElfClasses.into()),
+        (true, _) => crate::elf::merge(elf64_inputs, options)?,
+        (false, true) => crate::elf::merge(elf32_inputs, options)?,
+    };
+    Ok(output)
+}
+
+/// Convert the given Mach-O dynamic library into a Mach-O relocatable file. Only the options that apply to all input
+/// formats are used.
+pub fn convert_macho(input: &[u8], options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    let output = match parse_input(input)? {
+        InputFile::MachO64(macho_file) => {
+            crate::macho::convert(crate::macho::MachOInput::new(macho_file, input)?, options)?
+        }
+        input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+    };
+    write_output(output, options)
+}
+
+/// Convert the given PE DLL into a COFF relocatable file. Only the options that apply to all input formats are used.
+pub fn convert_pe(input: &[u8], options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    let output = match parse_input(input)? {
+        InputFile::Pe64(pe_file) => crate::pe::convert(pe_file, options)?,
+        input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+    };
+    write_output(output, options)
+}
+
+/// Errors that may occur when converting a shared library.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("cannot parse input: {0}")]
+    ParseError(#[from] object::read::Error),
+
+    #[error("input is not a shared library but of object kind {0:?}")]
+    NotSharedLibrary(ObjectKind),
+
+    #[error("{} format is not supported", crate::utils::stringify::binary_format_to_str(*.0))]
+    UnsupportedFormat(BinaryFormat),
+
+    #[error(transparent)]
+    CreateContextError(#[from] CreateContextError),
+
+    #[error(transparent)]
+    RunPassError(RunPassError),
+
+    #[error("the conversion is cancelled")]
+    Cancelled,
+
+    #[error("cannot merge {name}")]
+    MergeInputError {
+        name: String,
+        #[source]
+        error: Box<Error>,
+    },
+
+    #[error("cannot write output: {0}")]
+    WriteError(crate::output::Error),
+}
+
+impl Error {
+    /// Process exit code of a failure with any error that is not a conversion error.
+    pub const EXIT_FAILURE: u8 = 1;
+
+    /// Process exit code of a failure because the input is not a supported shared library.
+    pub const EXIT_INVALID_INPUT: u8 = 2;
+
+    /// Process exit code of a failure because the input uses features that cannot be converted.
+    pub const EXIT_UNSUPPORTED: u8 = 3;
+
+    /// Process exit code of a failure of a conversion pass.
+    pub const EXIT_CONVERSION_FAILED: u8 = 4;
+
+    /// Process exit code of a cancelled conversion.
+    pub const EXIT_CANCELLED: u8 = 5;
+
+    /// Get the process exit code that reports this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::IoError(_) | Self::WriteError(_) => Self::EXIT_FAILURE,
+            Self::ParseError(_)
+            | Self::NotSharedLibrary(_)
+            | Self::UnsupportedFormat(_)
+            | Self::CreateContextError(CreateContextError::CorruptedData(_)) => {
+                Self::EXIT_INVALID_INPUT
+            }
+            Self::CreateContextError(_) => Self::EXIT_UNSUPPORTED,
+            Self::RunPassError(_) => Self::EXIT_CONVERSION_FAILED,
+            Self::Cancelled => Self::EXIT_CANCELLED,
+            Self::MergeInputError { error, .. } => error.exit_code(),
+        }
+    }
+}
+
+impl From<RunPassError> for Error {
+    fn from(err: RunPassError) -> Self {
+        if err.is_cancelled() {
+            Self::Cancelled
+        } else {
+            Self::RunPassError(err)
+        }
+    }
+}
+
+/// Get the names of the shared libraries that the given ELF shared library depends on, which are listed by its DT_NEEDED
+/// entries.
+pub fn needed_libraries(input: &[u8]) -> Result<Vec<String>, Error> {
+    let needed = match parse_input(input)? {
+        InputFile::Elf32(elf_file) => crate::elf::get_needed_libraries(&elf_file)?,
+        InputFile::Elf64(elf_file) => crate::elf::get_needed_libraries(&elf_file)?,
+        input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+    };
+    Ok(needed)
+}
+
+/// Read the provenance notes that soda recorded in the given ELF relocatable file, one for each converted input. The
+/// result is empty if the file has no provenance note, such as when it was converted with
+/// [`ConvertOptions::provenance_note`] disabled.
+pub fn read_provenance(input: &[u8]) -> Result<Vec<Provenance>, Error> {
+    let provenances = match InputFile::parse(input)? {
+        InputFile::Elf32(elf_file) => crate::elf::pass::note::read_provenance(&elf_file)?,
+        InputFile::Elf64(elf_file) => crate::elf::pass::note::read_provenance(&elf_file)?,
+        input_file => return Err(Error::UnsupportedFormat(input_file.format())),
+    };
+    Ok(provenances)
+}
+
+/// Read a list of symbol names or glob patterns for [`ConvertOptions::only_export`], or of symbol names for
+/// [`ConvertOptions::gc_roots`], from the given file. Each non-empty line that does not start with `#` gives a name.
+pub fn read_export_list(path: &Path) -> std::io::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    let names = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    Ok(names)
+}
+
+/// Read a list of symbol renames for [`ConvertOptions::redefine_syms`] from the given file. Each non-empty line that
+/// does not start with `#` gives the old name and the new name separated by whitespace.
+pub fn read_redefine_syms(path: &Path) -> std::io::Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut renames = Vec::new();
+    for (line_idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [old_name, new_name] = fields[..] else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line {}: expected OLD NEW", line_idx + 1),
+            ));
+        };
+        renames.push((String::from(old_name), String::from(new_name)));
+    }
+    Ok(renames)
+}
+
+/// Parse the given input file data, and check that it is a shared library.
+fn parse_input(input: &[u8]) -> Result<InputFile<'_>, Error> {
+    Input::parse(input)?.into_shared_library()
+}
+
+/// Serialize the given output with the backend given in the options.
+fn write_output(output: OutputObject<'static>, options: &ConvertOptions) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    output
+        .emit(options.backend, &mut buffer)
+        .map_err(Error::WriteError)?;
+    Ok(buffer)
+}
+
+fn write_output_to(
+    output: OutputObject<'static>,
+    options: &ConvertOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut stream = StreamingBuffer::new(writer);
+    output
+        .emit(options.backend, &mut stream)
+        .map_err(Error::WriteError)?;
+    stream.result()?;
+    Ok(())
+}
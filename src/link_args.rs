@@ -0,0 +1,202 @@
+//! Arguments for the final link of converted objects.
+//!
+//! A converted object still depends on the shared libraries that the input depends on, and may need flags that the
+//! final link does not pass by default. [`LinkArgs`] collects them from the inputs and formats them as a response file
+//! that is passed to gcc or clang as `@FILE`:
+//!
+//! ```
+//! let input_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/elf/test/libspdlog.so.1.12.0");
+//! let data = std::fs::read(input_path).unwrap();
+//!
+//! let mut link_args = soda::link_args::LinkArgs::new("spdlog.o");
+//! link_args.add_input(&soda::Input::parse(&data).unwrap()).unwrap();
+//! assert!(link_args.to_response_file().starts_with("spdlog.o\n-lstdc++\n"));
+//! ```
+
+use std::path::PathBuf;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader};
+use object::read::File as InputFile;
+use object::{Object as _, ObjectSymbol as _, ReadRef};
+
+use crate::{Error, Input};
+
+/// Functions that look up symbols by name at run time. A library that calls them may look up its own symbols, which are
+/// only found if the final binary exports them.
+const LOOKUP_SYMBOLS: &[&str] = &["dlsym", "dlvsym"];
+
+/// Arguments for the final link of a converted object.
+#[derive(Clone, Debug, Default)]
+pub struct LinkArgs {
+    /// Path to the converted object.
+    pub object: PathBuf,
+
+    /// Sonames of the shared libraries that the inputs depend on, in the order that they are first seen.
+    pub needed: Vec<String>,
+
+    /// Sonames of the inputs, which are left out of the needed libraries since they are merged into the object.
+    pub sonames: Vec<String>,
+
+    /// Whether any input is marked as having text relocations, so that the final link must permit them.
+    pub text_relocations: bool,
+
+    /// Whether any input looks up symbols by name at run time, so that the final binary should export its symbols.
+    pub export_dynamic: bool,
+}
+
+impl LinkArgs {
+    /// Create a new `LinkArgs` for the converted object at the given path, without any input.
+    pub fn new(object: impl Into<PathBuf>) -> Self {
+        Self {
+            object: object.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add an input that is converted into the object. Only ELF inputs have needed libraries and flags.
+    pub fn add_input(&mut self, input: &Input<'_>) -> Result<(), Error> {
+        match input.file() {
+            InputFile::Elf32(elf_file) => self.add_elf_input(elf_file)?,
+            InputFile::Elf64(elf_file) => self.add_elf_input(elf_file)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn add_elf_input<'d, E, R>(&mut self, input: &ElfFile<'d, E, R>) -> object::read::Result<()>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        for soname in crate::elf::get_needed_libraries(input)? {
+            if !self.needed.contains(&soname) {
+                self.needed.push(soname);
+            }
+        }
+        if let Some(soname) = crate::elf::get_soname(input)? {
+            self.sonames.push(soname);
+        }
+        self.text_relocations |= crate::elf::has_text_relocations(input)?;
+        self.export_dynamic |= input.dynamic_symbols().any(|sym| {
+            sym.is_undefined() && sym.name().is_ok_and(|name| LOOKUP_SYMBOLS.contains(&name))
+        });
+        Ok(())
+    }
+
+    /// Get the sonames of the needed libraries that are linked with `-lNAME`, which needs the unversioned development
+    /// symlink `libNAME.so`. It's usually installed by the development package of the library rather than the library
+    /// itself.
+    pub fn versioned_needed(&self) -> impl Iterator<Item = &str> {
+        self.linked_needed().filter(|soname| {
+            crate::build::get_link_lib_name(soname).is_some() && !soname.ends_with(".so")
+        })
+    }
+
+    /// Get the arguments, one per item.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![self.object.to_string_lossy().into_owned()];
+        args.extend(self.linked_needed().map(get_link_flag));
+        if self.text_relocations {
+            args.push(String::from("-Wl,-z,notext"));
+        }
+        if self.export_dynamic {
+            args.push(String::from("-Wl,--export-dynamic"));
+        }
+        args
+    }
+
+    /// Format the arguments as a response file for gcc and clang, one argument per line.
+    pub fn to_response_file(&self) -> String {
+        self.args()
+            .iter()
+            .map(|arg| format!("{}\n", quote_arg(arg)))
+            .collect()
+    }
+
+    fn linked_needed(&self) -> impl Iterator<Item = &str> {
+        self.needed
+            .iter()
+            .filter(|soname| !self.sonames.contains(soname))
+            .map(String::as_str)
+    }
+}
+
+/// Get the linker flag that links the shared library with the given soname, which is `-lNAME` for `libNAME.so` and
+/// `libNAME.so.VERSION`, and `-l:SONAME` for any other soname.
+pub fn get_link_flag(soname: &str) -> String {
+    match crate::build::get_link_lib_name(soname) {
+        Some(name) => format!("-l{}", name),
+        None => format!("-l:{}", soname),
+    }
+}
+
+/// Quote the given argument for a response file if it has whitespace, quotes or backslashes.
+fn quote_arg(arg: &str) -> String {
+    if !arg.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\')) {
+        return String::from(arg);
+    }
+
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Input;
+
+    use super::{get_link_flag, quote_arg, LinkArgs};
+
+    const SPDLOG: &[u8] = include_bytes!("elf/test/libspdlog.so.1.12.0");
+
+    #[test]
+    fn test_link_args() {
+        let mut link_args = LinkArgs::new("out/spdlog.o");
+        link_args.add_input(&Input::parse(SPDLOG).unwrap()).unwrap();
+        assert_eq!(link_args.sonames, vec![String::from("libspdlog.so.1.12")]);
+        assert!(!link_args.text_relocations);
+        assert!(!link_args.export_dynamic);
+        assert_eq!(
+            link_args.to_response_file(),
+            "out/spdlog.o\n-lstdc++\n-lm\n-lgcc_s\n-lc\n-l:ld-linux-x86-64.so.2\n"
+        );
+        assert_eq!(
+            link_args.versioned_needed().collect::<Vec<_>>(),
+            vec!["libstdc++.so.6", "libm.so.6", "libgcc_s.so.1", "libc.so.6"]
+        );
+
+        // Needed libraries that are merged into the object are left out, and flags are added after the libraries.
+        link_args.needed.push(String::from("libspdlog.so.1.12"));
+        link_args.text_relocations = true;
+        link_args.export_dynamic = true;
+        let args = link_args.args();
+        assert!(!args.iter().any(|arg| arg.contains("spdlog.so")));
+        assert_eq!(
+            &args[args.len() - 2..],
+            &["-Wl,-z,notext", "-Wl,--export-dynamic"]
+        );
+    }
+
+    #[test]
+    fn test_get_link_flag() {
+        assert_eq!(get_link_flag("libz.so.1"), "-lz");
+        assert_eq!(get_link_flag("libfoo.so"), "-lfoo");
+        assert_eq!(
+            get_link_flag("ld-linux-x86-64.so.2"),
+            "-l:ld-linux-x86-64.so.2"
+        );
+    }
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("out/foo.o"), "out/foo.o");
+        assert_eq!(quote_arg("my dir/foo.o"), "\"my dir/foo.o\"");
+        assert_eq!(quote_arg("a\\b\"c"), "\"a\\\\b\\\"c\"");
+    }
+}
@@ -0,0 +1,111 @@
+pub mod pass;
+
+#[cfg(test)]
+mod test;
+
+use object::macho::{MachHeader64, LC_BUILD_VERSION};
+use object::read::macho::{LoadCommandVariant, MachHeader as _, MachOFile64};
+use object::write::{MachOBuildVersion, Mangling};
+use object::{Architecture, BinaryFormat, Endianness, Object as _, ObjectKind};
+
+use crate::macho::pass::init_func::GenerateInitFuncPass;
+use crate::macho::pass::reloc::ConvertRelocationPass;
+use crate::macho::pass::section::CopyLoadableSegmentsPass;
+use crate::macho::pass::symbol::GenerateSymbolPass;
+use crate::output::Object as OutputObject;
+use crate::pass::{CreateContextError, Diagnostics, PassManager};
+use crate::utils::demangle::with_demangle_enabled;
+use crate::{ConvertOptions, Error};
+
+/// A Mach-O input dynamic library.
+///
+/// The object crate doesn't expose the load commands of a parsed Mach-O file, so the header and the raw file data are
+/// kept alongside the parsed file for the passes that need to read the dyld information.
+#[derive(Debug)]
+pub struct MachOInput<'d> {
+    pub file: MachOFile64<'d, Endianness>,
+    pub header: &'d MachHeader64<Endianness>,
+    pub data: &'d [u8],
+}
+
+impl<'d> MachOInput<'d> {
+    /// Create an input from the given parsed file and the data it was parsed from.
+    pub fn new(file: MachOFile64<'d, Endianness>, data: &'d [u8]) -> Result<Self, Error> {
+        let header = MachHeader64::parse(data, 0)?;
+        Ok(Self { file, header, data })
+    }
+
+    /// Get the endianness of the input.
+    pub fn endian(&self) -> Endianness {
+        self.header.endian().unwrap()
+    }
+}
+
+/// Convert the given Mach-O input dynamic library into a Mach-O relocatable file.
+pub fn convert(
+    input: MachOInput<'_>,
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error> {
+    assert_eq!(input.file.kind(), ObjectKind::Dynamic);
+
+    let output = create_macho_output(&input)?;
+
+    let mut pass_mgr = PassManager::new();
+    pass_mgr.set_observer(options.observer.clone());
+    pass_mgr.set_diagnostics(Diagnostics::new(options.diagnostics.clone()));
+    init_passes(&mut pass_mgr);
+
+    let output = with_demangle_enabled(options.demangle, || pass_mgr.run(input, output))?;
+    Ok(output)
+}
+
+fn create_macho_output(input: &MachOInput<'_>) -> Result<OutputObject<'static>, Error> {
+    const SUPPORTED_ARCH: &[Architecture] = &[Architecture::X86_64, Architecture::Aarch64];
+
+    let arch = input.file.architecture();
+    if !SUPPORTED_ARCH.contains(&arch) {
+        return Err(CreateContextError::UnsupportedArch(arch).into());
+    }
+
+    let mut output = OutputObject::new(BinaryFormat::MachO, arch, Endianness::Little);
+
+    // Symbol names read from the input already carry the leading underscore of C symbols on Darwin, which must not be
+    // added once more.
+    output.mangling = Mangling::None;
+
+    // Linkers warn about objects without any platform, so the platform of the input is carried over.
+    let endian = input.endian();
+    let mut commands = input.header.load_commands(endian, input.data, 0)?;
+    while let Some(command) = commands.next()? {
+        if command.cmd() != LC_BUILD_VERSION {
+            continue;
+        }
+        if let LoadCommandVariant::BuildVersion(build_version) = command.variant()? {
+            let mut version = MachOBuildVersion::default();
+            version.platform = build_version.platform.get(endian);
+            version.minos = build_version.minos.get(endian);
+            version.sdk = build_version.sdk.get(endian);
+            output.set_macho_build_version(version);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Register passes required to convert a Mach-O dynamic library.
+fn init_passes(pass_mgr: &mut PassManager<MachOInput<'_>>) {
+    // Copy the loadable segments in the input dynamic library into a single output section.
+    let cls_pass = pass_mgr.add_pass(CopyLoadableSegmentsPass);
+
+    // Copy the external symbols in the input dynamic library into the output relocatable object.
+    let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass { cls_pass });
+
+    // Convert the rebase and bind information of dyld into relocations.
+    pass_mgr.add_pass(ConvertRelocationPass {
+        cls_pass,
+        sym_gen_pass,
+    });
+
+    // Generate the initializer and terminator function pointer sections of the output relocatable file.
+    pass_mgr.add_pass(GenerateInitFuncPass { cls_pass });
+}
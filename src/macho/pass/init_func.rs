@@ -0,0 +1,133 @@
+use object::macho::{
+    SECTION_TYPE, S_INIT_FUNC_OFFSETS, S_MOD_INIT_FUNC_POINTERS, S_MOD_TERM_FUNC_POINTERS,
+};
+use object::read::Error as ReadError;
+use object::{
+    Object as _, ObjectSection as _, ObjectSegment as _, RelocationEncoding, RelocationKind,
+    SectionFlags, SectionKind,
+};
+use thiserror::Error;
+
+use crate::macho::pass::section::CopyLoadableSegmentsPass;
+use crate::macho::MachOInput;
+use crate::output::Relocation as OutputRelocation;
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that generates the initializer and terminator function pointer sections of the output relocatable file.
+///
+/// dyld runs the functions listed in the `S_MOD_INIT_FUNC_POINTERS` and `S_MOD_TERM_FUNC_POINTERS` sections of each
+/// image. These sections are copied along with the rest of their segments, where they have no effect, so this pass
+/// generates `__DATA,__mod_init_func` and `__DATA,__mod_term_func` output sections that point to the same functions.
+///
+/// Newer linkers list the initializers in `S_INIT_FUNC_OFFSETS` sections instead, as 32-bit offsets from the Mach-O
+/// header. They are converted into pointers in the `__DATA,__mod_init_func` output section as well.
+#[derive(Debug)]
+pub struct GenerateInitFuncPass {
+    pub cls_pass: PassHandle<CopyLoadableSegmentsPass>,
+}
+
+impl<'d> Pass<MachOInput<'d>> for GenerateInitFuncPass {
+    const NAME: &'static str = "generate init functions";
+
+    type Output = ();
+    type Error = GenerateInitFuncError;
+
+    fn run(&mut self, ctx: &PassContext<MachOInput<'d>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+
+        // The Mach-O header lies at the start of the segment that maps the start of the file.
+        let header_addr = ctx
+            .input
+            .file
+            .segments()
+            .find(|segment| {
+                let (offset, size) = segment.file_range();
+                offset == 0 && size != 0
+            })
+            .map(|segment| segment.address())
+            .unwrap_or(cls_output.image_base);
+
+        let mut init_funcs = Vec::new();
+        let mut term_funcs = Vec::new();
+        for section in ctx.input.file.sections() {
+            let SectionFlags::MachO { flags } = section.flags() else {
+                continue;
+            };
+            let data = section.data()?;
+            match flags & SECTION_TYPE {
+                S_MOD_INIT_FUNC_POINTERS => init_funcs.extend(
+                    data.chunks_exact(8)
+                        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())),
+                ),
+                S_MOD_TERM_FUNC_POINTERS => term_funcs.extend(
+                    data.chunks_exact(8)
+                        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())),
+                ),
+                S_INIT_FUNC_OFFSETS => init_funcs.extend(data.chunks_exact(4).map(|bytes| {
+                    header_addr + u64::from(u32::from_le_bytes(bytes.try_into().unwrap()))
+                })),
+                _ => {}
+            }
+        }
+
+        let mut output = ctx.output.borrow_mut();
+        for (section_name, section_type, funcs) in [
+            ("__mod_init_func", S_MOD_INIT_FUNC_POINTERS, init_funcs),
+            ("__mod_term_func", S_MOD_TERM_FUNC_POINTERS, term_funcs),
+        ] {
+            if funcs.is_empty() {
+                continue;
+            }
+
+            let section_id = output.add_section(
+                b"__DATA".to_vec(),
+                section_name.as_bytes().to_vec(),
+                SectionKind::Data,
+            );
+            output.section_mut(section_id).flags = SectionFlags::MachO {
+                flags: section_type,
+            };
+            output
+                .section_mut(section_id)
+                .set_data(vec![0u8; funcs.len() * 8], 8);
+
+            for (idx, func) in funcs.into_iter().enumerate() {
+                if cls_output.translate(func).is_none() {
+                    return Err(GenerateInitFuncError::FuncOutsideImage(func));
+                }
+
+                let (symbol, addend) = cls_output.get_address_reference(func);
+                output
+                    .add_relocation(
+                        section_id,
+                        OutputRelocation {
+                            offset: idx as u64 * 8,
+                            size: 64,
+                            kind: RelocationKind::Absolute,
+                            encoding: RelocationEncoding::Generic,
+                            symbol,
+                            addend,
+                        },
+                    )
+                    .unwrap();
+            }
+
+            ctx.diagnostics
+                .info(format_args!("Generated output section {}", section_name));
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when generating the initializer and terminator function pointer sections.
+#[derive(Debug, Error)]
+pub enum GenerateInitFuncError {
+    #[error("read Mach-O failed: {0:?}")]
+    ReadMachOError(#[from] ReadError),
+
+    #[error(
+        "the initializer or terminator function at {0:#x} lies outside of the copied segments"
+    )]
+    FuncOutsideImage(u64),
+}
@@ -0,0 +1,4 @@
+pub mod init_func;
+pub mod reloc;
+pub mod section;
+pub mod symbol;
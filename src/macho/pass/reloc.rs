@@ -0,0 +1,543 @@
+use std::collections::HashSet;
+
+use object::macho::{
+    BIND_IMMEDIATE_MASK, BIND_OPCODE_ADD_ADDR_ULEB, BIND_OPCODE_DONE, BIND_OPCODE_DO_BIND,
+    BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED, BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB,
+    BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB, BIND_OPCODE_MASK, BIND_OPCODE_SET_ADDEND_SLEB,
+    BIND_OPCODE_SET_DYLIB_ORDINAL_IMM, BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB,
+    BIND_OPCODE_SET_DYLIB_SPECIAL_IMM, BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB,
+    BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM, BIND_OPCODE_SET_TYPE_IMM, BIND_OPCODE_THREADED,
+    BIND_SYMBOL_FLAGS_WEAK_IMPORT, BIND_TYPE_POINTER, LC_DYLD_CHAINED_FIXUPS,
+    REBASE_IMMEDIATE_MASK, REBASE_OPCODE_ADD_ADDR_IMM_SCALED, REBASE_OPCODE_ADD_ADDR_ULEB,
+    REBASE_OPCODE_DONE, REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB, REBASE_OPCODE_DO_REBASE_IMM_TIMES,
+    REBASE_OPCODE_DO_REBASE_ULEB_TIMES, REBASE_OPCODE_DO_REBASE_ULEB_TIMES_SKIPPING_ULEB,
+    REBASE_OPCODE_MASK, REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB, REBASE_OPCODE_SET_TYPE_IMM,
+    REBASE_TYPE_POINTER,
+};
+use object::read::macho::{LoadCommandVariant, MachHeader as _};
+use object::read::Error as ReadError;
+use object::{RelocationEncoding, RelocationKind};
+use thiserror::Error;
+
+use crate::macho::pass::section::CopyLoadableSegmentsPass;
+use crate::macho::pass::symbol::{get_or_create_symbol, GenerateSymbolPass};
+use crate::macho::MachOInput;
+use crate::output::Relocation as OutputRelocation;
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// Size of the pointers fixed up by dyld. Only 64-bit inputs are supported.
+const POINTER_SIZE: u64 = 8;
+
+/// A pass that converts the dyld fixups of the input dynamic library into relocations in the output relocatable file.
+///
+/// The rebase information lists the pointers that dyld slides by the load address. Each of them becomes a relocation
+/// against the output section. The bind information, including the lazy one, lists the pointers that dyld binds to
+/// symbols. Each of them becomes a relocation against the symbol, which is undefined in the output relocatable file
+/// unless the input dynamic library defines it. Lazy pointers are bound eagerly by the final link, so the stub helpers
+/// they initially point to are never run.
+///
+/// The weak bind information only coalesces weak definitions across images, which the final link does on its own, so it
+/// is ignored.
+///
+/// Only the opcode-based fixups of `LC_DYLD_INFO` and `LC_DYLD_INFO_ONLY` are supported for now. Inputs that use chained
+/// fixups are rejected.
+#[derive(Debug)]
+pub struct ConvertRelocationPass {
+    pub cls_pass: PassHandle<CopyLoadableSegmentsPass>,
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+}
+
+impl<'d> Pass<MachOInput<'d>> for ConvertRelocationPass {
+    const NAME: &'static str = "convert relocations";
+
+    type Output = ();
+    type Error = ConvertRelocationError;
+
+    fn run(&mut self, ctx: &PassContext<MachOInput<'d>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut sym_map = ctx.get_pass_output(self.sym_gen_pass).clone();
+        let mut output = ctx.output.borrow_mut();
+
+        // Segments are referred to by their indices among all the segment load commands.
+        let endian = ctx.input.endian();
+        let mut segment_addrs = Vec::new();
+        let mut dyld_info = None;
+        let mut commands = ctx.input.header.load_commands(endian, ctx.input.data, 0)?;
+        while let Some(command) = commands.next()? {
+            if command.cmd() == LC_DYLD_CHAINED_FIXUPS {
+                return Err(ConvertRelocationError::ChainedFixups);
+            }
+            match command.variant()? {
+                LoadCommandVariant::Segment64(segment, _) => {
+                    segment_addrs.push(segment.vmaddr.get(endian));
+                }
+                LoadCommandVariant::DyldInfo(info) => {
+                    dyld_info = Some(info);
+                }
+                _ => {}
+            }
+        }
+
+        let Some(dyld_info) = dyld_info else {
+            ctx.diagnostics.warn(format_args!(
+                "Input has no dyld information; no relocations are generated"
+            ));
+            return Ok(());
+        };
+
+        let get_opcodes = |offset: u32, size: u32| {
+            let offset = offset as usize;
+            ctx.input
+                .data
+                .get(offset..offset + size as usize)
+                .ok_or(ConvertRelocationError::TruncatedDyldInfo)
+        };
+        let rebases = decode_rebase_opcodes(
+            get_opcodes(
+                dyld_info.rebase_off.get(endian),
+                dyld_info.rebase_size.get(endian),
+            )?,
+            &segment_addrs,
+        )?;
+        let mut binds = decode_bind_opcodes(
+            get_opcodes(
+                dyld_info.bind_off.get(endian),
+                dyld_info.bind_size.get(endian),
+            )?,
+            &segment_addrs,
+        )?;
+        binds.extend(decode_bind_opcodes(
+            get_opcodes(
+                dyld_info.lazy_bind_off.get(endian),
+                dyld_info.lazy_bind_size.get(endian),
+            )?,
+            &segment_addrs,
+        )?);
+        if dyld_info.weak_bind_size.get(endian) != 0 {
+            ctx.diagnostics
+                .debug(format_args!("Ignoring weak bind information"));
+        }
+
+        // Lazy pointers are both rebased to their stub helpers and bound to their symbols. The binds win.
+        let bound_addrs: HashSet<_> = binds.iter().map(|bind| bind.address).collect();
+
+        let section_id = cls_output.output_section_id;
+        for address in rebases {
+            if bound_addrs.contains(&address) {
+                continue;
+            }
+
+            let offset = cls_output
+                .translate(address)
+                .ok_or(ConvertRelocationError::FixupOutsideImage(address))?;
+            let data = output.section(section_id).data();
+            let value = data
+                .get(offset as usize..(offset + POINTER_SIZE) as usize)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(ConvertRelocationError::FixupOutsideImage(address))?;
+
+            let (symbol, addend) = cls_output.get_address_reference(value);
+            output
+                .add_relocation(
+                    section_id,
+                    OutputRelocation {
+                        offset,
+                        size: (POINTER_SIZE * 8) as u8,
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        symbol,
+                        addend,
+                    },
+                )
+                .unwrap();
+        }
+
+        for bind in binds {
+            let offset = cls_output
+                .translate(bind.address)
+                .ok_or(ConvertRelocationError::FixupOutsideImage(bind.address))?;
+            let symbol =
+                get_or_create_symbol(&mut output, &mut sym_map, bind.name, bind.weak_import);
+
+            // The addend is implicit in the section data, where lazy pointers still hold the addresses of their stub
+            // helpers.
+            output.section_mut(section_id).data_mut()
+                [offset as usize..(offset + POINTER_SIZE) as usize]
+                .fill(0);
+            output
+                .add_relocation(
+                    section_id,
+                    OutputRelocation {
+                        offset,
+                        size: (POINTER_SIZE * 8) as u8,
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        symbol,
+                        addend: bind.addend,
+                    },
+                )
+                .unwrap();
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when converting dyld fixups.
+#[derive(Debug, Error)]
+pub enum ConvertRelocationError {
+    #[error("read Mach-O failed: {0:?}")]
+    ReadMachOError(#[from] ReadError),
+
+    #[error(
+        "the input uses chained fixups (LC_DYLD_CHAINED_FIXUPS), which are not supported yet; relink it with \
+         -Wl,-no_fixup_chains to use the classic dyld information instead"
+    )]
+    ChainedFixups,
+
+    #[error("the dyld information lies outside of the input file")]
+    TruncatedDyldInfo,
+
+    #[error("the dyld opcodes end unexpectedly")]
+    TruncatedOpcodes,
+
+    #[error("invalid dyld opcode {0:#x}")]
+    InvalidOpcode(u8),
+
+    #[error("dyld opcode refers to segment {0}, which does not exist")]
+    InvalidSegment(u8),
+
+    #[error("unsupported fixup type {0}")]
+    UnsupportedFixupType(u8),
+
+    #[error("threaded binds are not supported")]
+    ThreadedBind,
+
+    #[error("the fixup at {0:#x} lies outside of the copied segments")]
+    FixupOutsideImage(u64),
+}
+
+/// A pointer that dyld binds to a symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Bind<'d> {
+    address: u64,
+    name: &'d [u8],
+    weak_import: bool,
+    addend: i64,
+}
+
+/// Decode the given rebase opcodes into the addresses of the pointers to be rebased.
+fn decode_rebase_opcodes(
+    opcodes: &[u8],
+    segment_addrs: &[u64],
+) -> Result<Vec<u64>, ConvertRelocationError> {
+    let mut reader = OpcodeReader::new(opcodes);
+    let mut rebases = Vec::new();
+    let mut address = 0u64;
+
+    while let Some(byte) = reader.next_byte() {
+        let imm = byte & REBASE_IMMEDIATE_MASK;
+        match byte & REBASE_OPCODE_MASK {
+            REBASE_OPCODE_DONE => break,
+            REBASE_OPCODE_SET_TYPE_IMM => {
+                if imm != REBASE_TYPE_POINTER {
+                    return Err(ConvertRelocationError::UnsupportedFixupType(imm));
+                }
+            }
+            REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                let segment_addr = segment_addrs
+                    .get(imm as usize)
+                    .ok_or(ConvertRelocationError::InvalidSegment(imm))?;
+                address = segment_addr.wrapping_add(reader.read_uleb128()?);
+            }
+            REBASE_OPCODE_ADD_ADDR_ULEB => {
+                address = address.wrapping_add(reader.read_uleb128()?);
+            }
+            REBASE_OPCODE_ADD_ADDR_IMM_SCALED => {
+                address = address.wrapping_add(u64::from(imm) * POINTER_SIZE);
+            }
+            REBASE_OPCODE_DO_REBASE_IMM_TIMES => {
+                for _ in 0..imm {
+                    rebases.push(address);
+                    address = address.wrapping_add(POINTER_SIZE);
+                }
+            }
+            REBASE_OPCODE_DO_REBASE_ULEB_TIMES => {
+                for _ in 0..reader.read_uleb128()? {
+                    rebases.push(address);
+                    address = address.wrapping_add(POINTER_SIZE);
+                }
+            }
+            REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB => {
+                rebases.push(address);
+                address = address
+                    .wrapping_add(reader.read_uleb128()?)
+                    .wrapping_add(POINTER_SIZE);
+            }
+            REBASE_OPCODE_DO_REBASE_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = reader.read_uleb128()?;
+                let skip = reader.read_uleb128()?;
+                for _ in 0..count {
+                    rebases.push(address);
+                    address = address.wrapping_add(skip).wrapping_add(POINTER_SIZE);
+                }
+            }
+            _ => return Err(ConvertRelocationError::InvalidOpcode(byte)),
+        }
+    }
+
+    Ok(rebases)
+}
+
+/// Decode the given bind opcodes into the pointers to be bound.
+///
+/// The lazy bind opcodes consist of a sequence of independent binds, each of which ends with a `BIND_OPCODE_DONE`, so
+/// the opcode doesn't stop the decoding.
+fn decode_bind_opcodes<'d>(
+    opcodes: &'d [u8],
+    segment_addrs: &[u64],
+) -> Result<Vec<Bind<'d>>, ConvertRelocationError> {
+    let mut reader = OpcodeReader::new(opcodes);
+    let mut binds = Vec::new();
+    let mut address = 0u64;
+    let mut name: &[u8] = &[];
+    let mut weak_import = false;
+    let mut addend = 0i64;
+
+    while let Some(byte) = reader.next_byte() {
+        let imm = byte & BIND_IMMEDIATE_MASK;
+        let mut bind = |address: u64| {
+            binds.push(Bind {
+                address,
+                name,
+                weak_import,
+                addend,
+            })
+        };
+        match byte & BIND_OPCODE_MASK {
+            BIND_OPCODE_DONE => {}
+            // The final link resolves symbols regardless of the library that dyld would look them up in.
+            BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {}
+            BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
+                reader.read_uleb128()?;
+            }
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                name = reader.read_cstr()?;
+                weak_import = imm & BIND_SYMBOL_FLAGS_WEAK_IMPORT != 0;
+            }
+            BIND_OPCODE_SET_TYPE_IMM => {
+                if imm != BIND_TYPE_POINTER {
+                    return Err(ConvertRelocationError::UnsupportedFixupType(imm));
+                }
+            }
+            BIND_OPCODE_SET_ADDEND_SLEB => {
+                addend = reader.read_sleb128()?;
+            }
+            BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                let segment_addr = segment_addrs
+                    .get(imm as usize)
+                    .ok_or(ConvertRelocationError::InvalidSegment(imm))?;
+                address = segment_addr.wrapping_add(reader.read_uleb128()?);
+            }
+            BIND_OPCODE_ADD_ADDR_ULEB => {
+                address = address.wrapping_add(reader.read_uleb128()?);
+            }
+            BIND_OPCODE_DO_BIND => {
+                bind(address);
+                address = address.wrapping_add(POINTER_SIZE);
+            }
+            BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                bind(address);
+                address = address
+                    .wrapping_add(reader.read_uleb128()?)
+                    .wrapping_add(POINTER_SIZE);
+            }
+            BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                bind(address);
+                address = address.wrapping_add((u64::from(imm) + 1) * POINTER_SIZE);
+            }
+            BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = reader.read_uleb128()?;
+                let skip = reader.read_uleb128()?;
+                for _ in 0..count {
+                    bind(address);
+                    address = address.wrapping_add(skip).wrapping_add(POINTER_SIZE);
+                }
+            }
+            BIND_OPCODE_THREADED => return Err(ConvertRelocationError::ThreadedBind),
+            _ => return Err(ConvertRelocationError::InvalidOpcode(byte)),
+        }
+    }
+
+    Ok(binds)
+}
+
+/// Reads the operands of dyld opcodes.
+#[derive(Debug)]
+struct OpcodeReader<'d> {
+    data: &'d [u8],
+    offset: usize,
+}
+
+impl<'d> OpcodeReader<'d> {
+    fn new(data: &'d [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(byte)
+    }
+
+    fn read_uleb128(&mut self) -> Result<u64, ConvertRelocationError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self
+                .next_byte()
+                .ok_or(ConvertRelocationError::TruncatedOpcodes)?;
+            if shift < 64 {
+                value |= u64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_sleb128(&mut self) -> Result<i64, ConvertRelocationError> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self
+                .next_byte()
+                .ok_or(ConvertRelocationError::TruncatedOpcodes)?;
+            if shift < 64 {
+                value |= i64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_cstr(&mut self) -> Result<&'d [u8], ConvertRelocationError> {
+        let rest = &self.data[self.offset..];
+        let len = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(ConvertRelocationError::TruncatedOpcodes)?;
+        self.offset += len + 1;
+        Ok(&rest[..len])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_rebase_opcodes() {
+        let segment_addrs = [0, 0x1000, 0x4000];
+        let opcodes = [
+            REBASE_OPCODE_SET_TYPE_IMM | REBASE_TYPE_POINTER,
+            REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2,
+            0x90,
+            0x01, // 0x4090
+            REBASE_OPCODE_DO_REBASE_IMM_TIMES | 2,
+            REBASE_OPCODE_ADD_ADDR_IMM_SCALED | 1,
+            REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB,
+            0x08,
+            REBASE_OPCODE_DO_REBASE_ULEB_TIMES_SKIPPING_ULEB,
+            0x02,
+            0x10,
+            REBASE_OPCODE_DONE,
+            REBASE_OPCODE_DO_REBASE_IMM_TIMES | 1,
+        ];
+        assert_eq!(
+            decode_rebase_opcodes(&opcodes, &segment_addrs).unwrap(),
+            vec![0x4090, 0x4098, 0x40a8, 0x40b8, 0x40d0]
+        );
+
+        assert!(matches!(
+            decode_rebase_opcodes(&[REBASE_OPCODE_SET_TYPE_IMM | 2], &segment_addrs),
+            Err(ConvertRelocationError::UnsupportedFixupType(2))
+        ));
+        assert!(matches!(
+            decode_rebase_opcodes(
+                &[REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 3, 0],
+                &segment_addrs
+            ),
+            Err(ConvertRelocationError::InvalidSegment(3))
+        ));
+        assert!(matches!(
+            decode_rebase_opcodes(&[REBASE_OPCODE_ADD_ADDR_ULEB, 0x80], &segment_addrs),
+            Err(ConvertRelocationError::TruncatedOpcodes)
+        ));
+    }
+
+    #[test]
+    fn test_decode_bind_opcodes() {
+        let segment_addrs = [0, 0x1000, 0x4000];
+        let mut opcodes = vec![
+            BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+        ];
+        opcodes.extend_from_slice(b"_malloc\0");
+        opcodes.extend_from_slice(&[
+            BIND_OPCODE_SET_TYPE_IMM | BIND_TYPE_POINTER,
+            BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2,
+            0x10,
+            BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED | 1,
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM | BIND_SYMBOL_FLAGS_WEAK_IMPORT,
+        ]);
+        opcodes.extend_from_slice(b"_weak\0");
+        opcodes.extend_from_slice(&[
+            BIND_OPCODE_SET_ADDEND_SLEB,
+            0x7c, // -4
+            BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB,
+            0x02,
+            0x00,
+            BIND_OPCODE_DONE,
+            BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB,
+            0x81,
+            0x01,
+            BIND_OPCODE_DO_BIND,
+            BIND_OPCODE_DONE,
+        ]);
+
+        let bind = |address, name, weak_import, addend| Bind {
+            address,
+            name,
+            weak_import,
+            addend,
+        };
+        assert_eq!(
+            decode_bind_opcodes(&opcodes, &segment_addrs).unwrap(),
+            vec![
+                bind(0x4010, &b"_malloc"[..], false, 0),
+                bind(0x4020, &b"_weak"[..], true, -4),
+                bind(0x4028, &b"_weak"[..], true, -4),
+                bind(0x4030, &b"_weak"[..], true, -4),
+            ]
+        );
+
+        assert!(matches!(
+            decode_bind_opcodes(&[BIND_OPCODE_THREADED], &segment_addrs),
+            Err(ConvertRelocationError::ThreadedBind)
+        ));
+        assert!(matches!(
+            decode_bind_opcodes(
+                &[BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM, b'_'],
+                &segment_addrs
+            ),
+            Err(ConvertRelocationError::TruncatedOpcodes)
+        ));
+    }
+}
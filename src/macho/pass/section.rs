@@ -0,0 +1,140 @@
+use std::ops::Range;
+
+use object::read::Error as ReadError;
+use object::{Object as _, ObjectSection as _, ObjectSegment as _, SectionKind};
+use thiserror::Error;
+
+use crate::macho::MachOInput;
+use crate::output::{SectionId, SymbolId};
+use crate::pass::{Pass, PassContext};
+
+/// A pass that copies the loadable segments in the input dynamic library into a single section of the output
+/// relocatable object.
+///
+/// Code refers to data in other segments through PC-relative addressing that carries no fixup, so the segments are
+/// copied into the same output section with their distances unchanged. The output section lives in its own `__SODA`
+/// segment, which holds both code and writable data; the final link has to make it writable and executable, e.g. with
+/// `-segprot __SODA rwx rwx`.
+#[derive(Debug, Default)]
+pub struct CopyLoadableSegmentsPass;
+
+impl CopyLoadableSegmentsPass {
+    /// Name of the output segment.
+    pub const SEGMENT_NAME: &'static str = "__SODA";
+
+    /// Name of the output section.
+    pub const SECTION_NAME: &'static str = "__soda";
+}
+
+impl<'d> Pass<MachOInput<'d>> for CopyLoadableSegmentsPass {
+    const NAME: &'static str = "copy segments";
+
+    type Output = CopyLoadableSegmentsOutput;
+    type Error = CopySegmentsError;
+
+    fn run(&mut self, ctx: &PassContext<MachOInput<'d>>) -> Result<Self::Output, Self::Error> {
+        let mut output = ctx.output.borrow_mut();
+
+        // __PAGEZERO only reserves address space, and __LINKEDIT holds the information for dyld rather than the image.
+        let mut segments = Vec::new();
+        for segment in ctx.input.file.segments() {
+            let name = segment.name()?.unwrap_or_default();
+            if name == "__PAGEZERO" || name == "__LINKEDIT" {
+                continue;
+            }
+            segments.push((
+                segment.address()..segment.address() + segment.size(),
+                segment.data()?,
+            ));
+        }
+
+        let image_base = segments
+            .iter()
+            .map(|(range, _)| range.start)
+            .min()
+            .unwrap_or(0);
+        let image_end = segments
+            .iter()
+            .map(|(range, _)| range.end)
+            .max()
+            .unwrap_or(0);
+        let image_size = image_end - image_base;
+        ctx.diagnostics
+            .info(format_args!("Output image size: {} bytes", image_size));
+
+        // Zero-filled parts of segments are not present in the input file.
+        let mut buffer = vec![0u8; image_size as usize];
+        for (range, data) in &segments {
+            let offset = (range.start - image_base) as usize;
+            let size = data.len().min((range.end - range.start) as usize);
+            buffer[offset..offset + size].copy_from_slice(&data[..size]);
+        }
+
+        let output_section_id = output.add_section(
+            Self::SEGMENT_NAME.as_bytes().to_vec(),
+            Self::SECTION_NAME.as_bytes().to_vec(),
+            SectionKind::Text,
+        );
+        let output_section_symbol = output.section_symbol(output_section_id);
+
+        // The sections keep their alignment as long as the output section is aligned like the most aligned of them.
+        let align = ctx
+            .input
+            .file
+            .sections()
+            .map(|section| section.align())
+            .max()
+            .unwrap_or(1);
+        output
+            .section_mut(output_section_id)
+            .set_data(buffer, align);
+
+        Ok(CopyLoadableSegmentsOutput {
+            output_section_id,
+            output_section_symbol,
+            image_base,
+            segment_ranges: segments.into_iter().map(|(range, _)| range).collect(),
+        })
+    }
+}
+
+/// Errors that may occur when copying loadable segments.
+#[derive(Debug, Error)]
+pub enum CopySegmentsError {
+    #[error("read Mach-O failed: {0:?}")]
+    ReadMachOError(#[from] ReadError),
+}
+
+#[derive(Clone, Debug)]
+pub struct CopyLoadableSegmentsOutput {
+    /// The ID of the output section that holds the copied image.
+    pub output_section_id: SectionId,
+
+    /// The ID of the output section symbol.
+    pub output_section_symbol: SymbolId,
+
+    /// The lowest address of the copied segments, which is placed at the start of the output section.
+    pub image_base: u64,
+
+    /// Address ranges of the copied segments.
+    pub segment_ranges: Vec<Range<u64>>,
+}
+
+impl CopyLoadableSegmentsOutput {
+    /// Translate the given address in the input image into an offset within the output section. Returns `None` if the
+    /// address does not lie in any copied segment.
+    pub fn translate(&self, addr: u64) -> Option<u64> {
+        self.segment_ranges
+            .iter()
+            .any(|range| range.contains(&addr))
+            .then(|| addr - self.image_base)
+    }
+
+    /// Get the output symbol and addend that together refer to the given address in the input image.
+    pub fn get_address_reference(&self, addr: u64) -> (SymbolId, i64) {
+        (
+            self.output_section_symbol,
+            addr.wrapping_sub(self.image_base) as i64,
+        )
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use object::read::Error as ReadError;
+use object::{Object as _, ObjectSymbol as _, SymbolFlags, SymbolKind, SymbolScope};
+use thiserror::Error;
+
+use crate::macho::pass::section::CopyLoadableSegmentsPass;
+use crate::macho::MachOInput;
+use crate::output::{
+    Object as OutputObject, Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection,
+};
+use crate::pass::{Pass, PassContext, PassHandle};
+
+/// A pass that generates the symbol table of the output relocatable file.
+///
+/// Each external symbol defined in a copied segment of the input dynamic library generates a corresponding defined
+/// output symbol. Symbol names are kept as they appear in the input symbol table, so C symbols keep their leading
+/// underscore. Weak definitions (`N_WEAK_DEF`) stay weak, and private external symbols (`N_PEXT`) stay private to the
+/// final link.
+///
+/// Undefined symbols are not copied. Instead, undefined output symbols are created on demand for the symbols referred
+/// to by the bind information.
+#[derive(Debug)]
+pub struct GenerateSymbolPass {
+    pub cls_pass: PassHandle<CopyLoadableSegmentsPass>,
+}
+
+impl<'d> Pass<MachOInput<'d>> for GenerateSymbolPass {
+    const NAME: &'static str = "generate symbols";
+
+    type Output = SymbolMap;
+    type Error = GenerateSymbolError;
+
+    fn run(&mut self, ctx: &PassContext<MachOInput<'d>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut output = ctx.output.borrow_mut();
+
+        let mut sym_map = SymbolMap::default();
+        for input_sym in ctx.input.file.symbols() {
+            if !input_sym.is_global() || !input_sym.is_definition() {
+                continue;
+            }
+
+            let name = input_sym.name_bytes()?;
+            let Some(value) = cls_output.translate(input_sym.address()) else {
+                ctx.diagnostics.warn(format_args!(
+                    "Symbol \"{}\" lies outside of the copied segments; it is dropped",
+                    String::from_utf8_lossy(name)
+                ));
+                continue;
+            };
+
+            let output_sym = output.add_symbol(OutputSymbol {
+                name: name.to_vec(),
+                value,
+                size: 0,
+                kind: input_sym.kind(),
+                scope: input_sym.scope(),
+                weak: input_sym.is_weak(),
+                section: OutputSymbolSection::Section(cls_output.output_section_id),
+                flags: SymbolFlags::None,
+            });
+            sym_map.insert(name, output_sym);
+        }
+
+        ctx.diagnostics
+            .info(format_args!("Generated {} symbols", sym_map.len()));
+
+        Ok(sym_map)
+    }
+}
+
+/// Errors that may occur when generating symbols.
+#[derive(Debug, Error)]
+pub enum GenerateSymbolError {
+    #[error("read Mach-O failed: {0:?}")]
+    ReadMachOError(#[from] ReadError),
+}
+
+/// Maps symbol names to output symbols.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolMap {
+    symbols: HashMap<Vec<u8>, SymbolId>,
+}
+
+impl SymbolMap {
+    /// Map the given symbol name to the given output symbol.
+    pub fn insert(&mut self, name: &[u8], output_sym: SymbolId) {
+        self.symbols.entry(name.to_vec()).or_insert(output_sym);
+    }
+
+    /// Get the output symbol with the given name.
+    pub fn get(&self, name: &[u8]) -> Option<SymbolId> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Get the number of mapped symbols.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Determine whether no symbol is mapped.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// Get the output symbol with the given name, or create an undefined output symbol with the name if there is no such
+/// symbol yet.
+pub fn get_or_create_symbol(
+    output: &mut OutputObject<'static>,
+    sym_map: &mut SymbolMap,
+    name: &[u8],
+    weak: bool,
+) -> SymbolId {
+    if let Some(output_sym) = sym_map.get(name) {
+        return output_sym;
+    }
+
+    let output_sym = output.add_symbol(OutputSymbol {
+        name: name.to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Unknown,
+        scope: SymbolScope::Dynamic,
+        weak,
+        section: OutputSymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    sym_map.insert(name, output_sym);
+    output_sym
+}
@@ -0,0 +1,359 @@
+#![allow(dead_code)]
+
+use object::macho::{
+    BIND_OPCODE_DONE, BIND_OPCODE_DO_BIND, BIND_OPCODE_SET_DYLIB_ORDINAL_IMM,
+    BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB, BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+    BIND_OPCODE_SET_TYPE_IMM, BIND_TYPE_POINTER, CPU_SUBTYPE_ARM64_ALL, CPU_SUBTYPE_X86_64_ALL,
+    CPU_TYPE_ARM64, CPU_TYPE_X86_64, LC_BUILD_VERSION, LC_DYLD_CHAINED_FIXUPS, LC_DYLD_INFO_ONLY,
+    LC_SEGMENT_64, LC_SYMTAB, MH_DYLIB, MH_MAGIC_64, N_EXT, N_PEXT, N_SECT, N_UNDF, N_WEAK_DEF,
+    PLATFORM_MACOS, REBASE_OPCODE_ADD_ADDR_IMM_SCALED, REBASE_OPCODE_DONE,
+    REBASE_OPCODE_DO_REBASE_IMM_TIMES, REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB,
+    REBASE_OPCODE_SET_TYPE_IMM, REBASE_TYPE_POINTER, S_MOD_INIT_FUNC_POINTERS,
+};
+use object::read::macho::MachOFile64;
+
+use crate::macho::MachOInput;
+
+/// Synthesizes small 64-bit little-endian Mach-O dynamic libraries for tests.
+///
+/// Each segment is placed at the file offset equal to its virtual address, so the first segment has to start at address
+/// 0 and leave room for the header and the load commands. The dyld information, the symbol table and the string table
+/// are placed into a `__LINKEDIT` segment after the other segments.
+#[derive(Clone, Debug)]
+pub struct MachOBuilder {
+    pub cputype: u32,
+    pub cpusubtype: u32,
+    pub segments: Vec<BuilderSegment>,
+    pub symbols: Vec<BuilderSymbol>,
+    pub rebase_opcodes: Vec<u8>,
+    pub bind_opcodes: Vec<u8>,
+    pub lazy_bind_opcodes: Vec<u8>,
+    pub chained_fixups: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuilderSegment {
+    pub name: String,
+    pub vmaddr: u64,
+    pub vmsize: u64,
+    pub sections: Vec<BuilderSection>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuilderSection {
+    pub name: String,
+    pub addr: u64,
+    pub flags: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuilderSymbol {
+    pub name: String,
+    pub n_type: u8,
+    pub n_sect: u8,
+    pub n_desc: u16,
+    pub value: u64,
+}
+
+impl MachOBuilder {
+    /// Create a builder of an x86_64 dynamic library without any segments or symbols.
+    pub fn new() -> Self {
+        Self {
+            cputype: CPU_TYPE_X86_64,
+            cpusubtype: CPU_SUBTYPE_X86_64_ALL,
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            rebase_opcodes: Vec::new(),
+            bind_opcodes: Vec::new(),
+            lazy_bind_opcodes: Vec::new(),
+            chained_fixups: false,
+        }
+    }
+
+    /// Add a segment and return its index.
+    pub fn add_segment(&mut self, name: &str, vmaddr: u64, vmsize: u64) -> usize {
+        self.segments.push(BuilderSegment {
+            name: String::from(name),
+            vmaddr,
+            vmsize,
+            sections: Vec::new(),
+        });
+        self.segments.len() - 1
+    }
+
+    /// Add a section to the given segment and return its 1-based index among all the sections, which symbols refer to.
+    pub fn add_section(
+        &mut self,
+        segment: usize,
+        name: &str,
+        addr: u64,
+        flags: u32,
+        data: Vec<u8>,
+    ) -> u8 {
+        self.segments[segment].sections.push(BuilderSection {
+            name: String::from(name),
+            addr,
+            flags,
+            data,
+        });
+        self.segments
+            .iter()
+            .map(|segment| segment.sections.len())
+            .sum::<usize>() as u8
+    }
+
+    pub fn add_symbol(&mut self, symbol: BuilderSymbol) {
+        self.symbols.push(symbol);
+    }
+
+    /// Turn the builder into one that builds an AArch64 dynamic library.
+    pub fn into_aarch64(mut self) -> Self {
+        self.cputype = CPU_TYPE_ARM64;
+        self.cpusubtype = CPU_SUBTYPE_ARM64_ALL;
+        self
+    }
+
+    /// Build the Mach-O file.
+    pub fn build(&self) -> Vec<u8> {
+        let nsects: usize = self.segments.iter().map(|s| s.sections.len()).sum();
+        let ncmds = self.segments.len() + 4 + usize::from(self.chained_fixups);
+        let sizeofcmds = (self.segments.len() + 1) * 72
+            + nsects * 80
+            + 48
+            + 24
+            + 24
+            + if self.chained_fixups { 16 } else { 0 };
+
+        let image_end = self
+            .segments
+            .iter()
+            .map(|s| s.vmaddr + s.vmsize)
+            .max()
+            .unwrap_or(0);
+        // Lay out __LINKEDIT.
+        let linkedit_off = image_end;
+        let rebase_off = linkedit_off;
+        let bind_off = rebase_off + self.rebase_opcodes.len() as u64;
+        let lazy_bind_off = bind_off + self.bind_opcodes.len() as u64;
+        let symoff = (lazy_bind_off + self.lazy_bind_opcodes.len() as u64 + 7) & !7;
+        let mut strtab = vec![b' ', 0];
+        let mut nlists = Vec::new();
+        for symbol in &self.symbols {
+            push_u32(&mut nlists, strtab.len() as u32);
+            strtab.extend_from_slice(symbol.name.as_bytes());
+            strtab.push(0);
+            nlists.push(symbol.n_type);
+            nlists.push(symbol.n_sect);
+            nlists.extend_from_slice(&symbol.n_desc.to_le_bytes());
+            push_u64(&mut nlists, symbol.value);
+        }
+        let stroff = symoff + nlists.len() as u64;
+        let linkedit_size = stroff + strtab.len() as u64 - linkedit_off;
+
+        let mut data = Vec::new();
+        push_u32(&mut data, MH_MAGIC_64);
+        push_u32(&mut data, self.cputype);
+        push_u32(&mut data, self.cpusubtype);
+        push_u32(&mut data, MH_DYLIB);
+        push_u32(&mut data, ncmds as u32);
+        push_u32(&mut data, sizeofcmds as u32);
+        push_u32(&mut data, 0);
+        push_u32(&mut data, 0);
+
+        let linkedit = BuilderSegment {
+            name: String::from("__LINKEDIT"),
+            vmaddr: linkedit_off,
+            vmsize: linkedit_size,
+            sections: Vec::new(),
+        };
+        for segment in self.segments.iter().chain([&linkedit]) {
+            push_u32(&mut data, LC_SEGMENT_64);
+            push_u32(&mut data, 72 + 80 * segment.sections.len() as u32);
+            push_name(&mut data, &segment.name);
+            push_u64(&mut data, segment.vmaddr);
+            push_u64(&mut data, segment.vmsize);
+            push_u64(&mut data, segment.vmaddr);
+            push_u64(&mut data, segment.vmsize);
+            push_u32(&mut data, 7);
+            push_u32(&mut data, 7);
+            push_u32(&mut data, segment.sections.len() as u32);
+            push_u32(&mut data, 0);
+            for section in &segment.sections {
+                push_name(&mut data, &section.name);
+                push_name(&mut data, &segment.name);
+                push_u64(&mut data, section.addr);
+                push_u64(&mut data, section.data.len() as u64);
+                push_u32(&mut data, section.addr as u32);
+                push_u32(&mut data, 3);
+                push_u32(&mut data, 0);
+                push_u32(&mut data, 0);
+                push_u32(&mut data, section.flags);
+                push_u32(&mut data, 0);
+                push_u32(&mut data, 0);
+                push_u32(&mut data, 0);
+            }
+        }
+
+        push_u32(&mut data, LC_DYLD_INFO_ONLY);
+        push_u32(&mut data, 48);
+        for (off, size) in [
+            (rebase_off, self.rebase_opcodes.len()),
+            (bind_off, self.bind_opcodes.len()),
+            (0, 0),
+            (lazy_bind_off, self.lazy_bind_opcodes.len()),
+            (0, 0),
+        ] {
+            push_u32(&mut data, off as u32);
+            push_u32(&mut data, size as u32);
+        }
+
+        push_u32(&mut data, LC_SYMTAB);
+        push_u32(&mut data, 24);
+        push_u32(&mut data, symoff as u32);
+        push_u32(&mut data, self.symbols.len() as u32);
+        push_u32(&mut data, stroff as u32);
+        push_u32(&mut data, strtab.len() as u32);
+
+        push_u32(&mut data, LC_BUILD_VERSION);
+        push_u32(&mut data, 24);
+        push_u32(&mut data, PLATFORM_MACOS);
+        push_u32(&mut data, 0x000d_0000);
+        push_u32(&mut data, 0x000e_0000);
+        push_u32(&mut data, 0);
+
+        if self.chained_fixups {
+            push_u32(&mut data, LC_DYLD_CHAINED_FIXUPS);
+            push_u32(&mut data, 16);
+            push_u32(&mut data, 0);
+            push_u32(&mut data, 0);
+        }
+        assert_eq!(data.len(), 32 + sizeofcmds);
+
+        data.resize((stroff as usize) + strtab.len(), 0);
+        for section in self.segments.iter().flat_map(|s| &s.sections) {
+            let offset = section.addr as usize;
+            data[offset..offset + section.data.len()].copy_from_slice(&section.data);
+        }
+        for (off, bytes) in [
+            (rebase_off, &self.rebase_opcodes),
+            (bind_off, &self.bind_opcodes),
+            (lazy_bind_off, &self.lazy_bind_opcodes),
+            (symoff, &nlists),
+            (stroff, &strtab),
+        ] {
+            let off = off as usize;
+            data[off..off + bytes.len()].copy_from_slice(bytes);
+        }
+
+        data
+    }
+
+    /// Build the Mach-O file and wrap it as a conversion input.
+    pub fn build_input(&self) -> MachOInput<'static> {
+        let data = &*Vec::leak(self.build());
+        MachOInput::new(MachOFile64::parse(data).unwrap(), data).unwrap()
+    }
+}
+
+impl Default for MachOBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u64(data: &mut Vec<u8>, value: u64) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_name(data: &mut Vec<u8>, name: &str) {
+    let mut bytes = [0u8; 16];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    data.extend_from_slice(&bytes);
+}
+
+/// Build a minimal x86_64 dynamic library with a `__TEXT` segment at 0 and a `__DATA` segment at 0x1000.
+///
+/// `__text` at 0x800 holds `_foo` and the initializer at 0x808. `__data` at 0x1000 holds a pointer to `_foo`, a
+/// pointer bound to `_malloc`, a lazy pointer bound to `_free` that initially points to 0x810, the weak definition
+/// `_bar` and the private external `_hidden`. `__mod_init_func` at 0x1100 points to the initializer.
+pub fn minimal_dylib() -> MachOBuilder {
+    let mut builder = MachOBuilder::new();
+
+    let text_seg = builder.add_segment("__TEXT", 0, 0x1000);
+    let data_seg = builder.add_segment("__DATA", 0x1000, 0x1000);
+    let text = builder.add_section(text_seg, "__text", 0x800, 0x8000_0400, vec![0xC3; 0x18]);
+
+    let mut data = vec![0u8; 0x28];
+    data[0..8].copy_from_slice(&0x800u64.to_le_bytes());
+    data[0x10..0x18].copy_from_slice(&0x810u64.to_le_bytes());
+    let data = builder.add_section(data_seg, "__data", 0x1000, 0, data);
+    builder.add_section(
+        data_seg,
+        "__mod_init_func",
+        0x1100,
+        S_MOD_INIT_FUNC_POINTERS,
+        0x808u64.to_le_bytes().to_vec(),
+    );
+
+    for (name, n_type, n_sect, n_desc, value) in [
+        ("_foo", N_SECT | N_EXT, text, 0, 0x800),
+        ("_local", N_SECT, text, 0, 0x808),
+        ("_bar", N_SECT | N_EXT, data, N_WEAK_DEF, 0x1018),
+        ("_hidden", N_SECT | N_EXT | N_PEXT, data, 0, 0x1020),
+        ("_malloc", N_UNDF | N_EXT, 0, 0x0100, 0),
+        ("_free", N_UNDF | N_EXT, 0, 0x0100, 0),
+    ] {
+        builder.add_symbol(BuilderSymbol {
+            name: String::from(name),
+            n_type,
+            n_sect,
+            n_desc,
+            value,
+        });
+    }
+
+    builder.rebase_opcodes = vec![
+        REBASE_OPCODE_SET_TYPE_IMM | REBASE_TYPE_POINTER,
+        REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x00,
+        REBASE_OPCODE_DO_REBASE_IMM_TIMES | 1,
+        REBASE_OPCODE_ADD_ADDR_IMM_SCALED | 1,
+        REBASE_OPCODE_DO_REBASE_IMM_TIMES | 1,
+        REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x80,
+        0x02,
+        REBASE_OPCODE_DO_REBASE_IMM_TIMES | 1,
+        REBASE_OPCODE_DONE,
+    ];
+
+    let mut bind_opcodes = vec![
+        BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+        BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+    ];
+    bind_opcodes.extend_from_slice(b"_malloc\0");
+    bind_opcodes.extend_from_slice(&[
+        BIND_OPCODE_SET_TYPE_IMM | BIND_TYPE_POINTER,
+        BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x08,
+        BIND_OPCODE_DO_BIND,
+        BIND_OPCODE_DONE,
+    ]);
+    builder.bind_opcodes = bind_opcodes;
+
+    let mut lazy_bind_opcodes = vec![
+        BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1,
+        0x10,
+        BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+        BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+    ];
+    lazy_bind_opcodes.extend_from_slice(b"_free\0");
+    lazy_bind_opcodes.extend_from_slice(&[BIND_OPCODE_DO_BIND, BIND_OPCODE_DONE]);
+    builder.lazy_bind_opcodes = lazy_bind_opcodes;
+
+    builder
+}
@@ -0,0 +1,104 @@
+pub mod builder;
+
+use object::read::RelocationTarget;
+use object::{Object as _, ObjectSection as _, ObjectSymbol as _, SectionFlags, SymbolScope};
+
+/// Convert the given input, and parse the output as a Mach-O file.
+fn convert(input: crate::macho::MachOInput<'static>) -> object::File<'static> {
+    let output = crate::macho::convert(input, &Default::default())
+        .unwrap()
+        .write()
+        .unwrap();
+    object::File::parse(&*Vec::leak(output)).unwrap()
+}
+
+/// Get the offset, the target name and the implicit addend of each relocation in the given section of the output.
+fn get_relocations(file: &object::File, section_name: &str) -> Vec<(u64, String, u64)> {
+    let section = file.section_by_name(section_name).unwrap();
+    let data = section.data().unwrap();
+    section
+        .relocations()
+        .map(|(offset, reloc)| {
+            let target = match reloc.target() {
+                RelocationTarget::Symbol(sym) => {
+                    String::from(file.symbol_by_index(sym).unwrap().name().unwrap())
+                }
+                RelocationTarget::Section(section) => {
+                    String::from(file.section_by_index(section).unwrap().name().unwrap())
+                }
+                target => panic!("unexpected relocation target {:?}", target),
+            };
+            let value = u64::from_le_bytes(
+                data[offset as usize..offset as usize + 8]
+                    .try_into()
+                    .unwrap(),
+            );
+            (offset, target, value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_convert_x86_64() {
+    let output_file = convert(builder::minimal_dylib().build_input());
+
+    let soda = output_file.section_by_name("__soda").unwrap();
+    assert_eq!(soda.segment_name().unwrap(), Some("__SODA"));
+    assert_eq!(soda.address(), 0);
+    assert_eq!(soda.size(), 0x2000);
+    assert_eq!(&soda.data().unwrap()[0x800..0x818], &[0xC3; 0x18]);
+
+    // The lazy pointer at 0x1010 is only bound, not rebased.
+    let mut relocs = get_relocations(&output_file, "__soda");
+    relocs.sort();
+    assert_eq!(
+        relocs,
+        vec![
+            (0x1000, String::from("__soda"), 0x800),
+            (0x1008, String::from("_malloc"), 0),
+            (0x1010, String::from("_free"), 0),
+            (0x1100, String::from("__soda"), 0x808),
+        ]
+    );
+
+    let init_func = output_file.section_by_name("__mod_init_func").unwrap();
+    assert_eq!(init_func.segment_name().unwrap(), Some("__DATA"));
+    assert_eq!(
+        init_func.flags(),
+        SectionFlags::MachO {
+            flags: object::macho::S_MOD_INIT_FUNC_POINTERS
+        }
+    );
+    assert_eq!(
+        get_relocations(&output_file, "__mod_init_func"),
+        vec![(0, String::from("__soda"), 0x808)]
+    );
+    assert!(output_file.section_by_name("__mod_term_func").is_none());
+
+    let find_symbol = |name| output_file.symbols().find(|sym| sym.name() == Ok(name));
+    let symbol = |name| find_symbol(name).unwrap();
+    assert_eq!(symbol("_foo").address(), 0x800);
+    assert_eq!(symbol("_foo").scope(), SymbolScope::Dynamic);
+    assert!(!symbol("_foo").is_weak());
+    assert_eq!(symbol("_bar").address(), 0x1018);
+    assert!(symbol("_bar").is_weak());
+    assert_eq!(symbol("_hidden").scope(), SymbolScope::Linkage);
+    assert!(symbol("_malloc").is_undefined());
+    assert!(find_symbol("_local").is_none());
+}
+
+#[test]
+fn test_convert_aarch64() {
+    let output_file = convert(builder::minimal_dylib().into_aarch64().build_input());
+    assert_eq!(output_file.architecture(), object::Architecture::Aarch64);
+    assert_eq!(get_relocations(&output_file, "__soda").len(), 4);
+}
+
+#[test]
+fn test_convert_chained_fixups() {
+    let mut builder = builder::minimal_dylib();
+    builder.chained_fixups = true;
+
+    let err = crate::macho::convert(builder.build_input(), &Default::default()).unwrap_err();
+    assert!(format!("{:#}", anyhow::Error::from(err)).contains("-no_fixup_chains"));
+}
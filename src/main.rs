@@ -1,19 +1,22 @@
-mod elf;
-mod pass;
-mod utils;
-
 use std::borrow::Cow;
+use std::fmt::Arguments;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use anyhow::{anyhow, Context as _};
 use log::{Level as LogLevel, SetLoggerError};
-use object::read::{File as InputFile, ObjectKind};
-use object::Object as _;
+use object::{BinaryFormat, ObjectKind};
 use structopt::StructOpt;
 
+use soda::link_args::LinkArgs;
+use soda::{
+    ConvertOptions, CtorsStyle, DiagnosticSink, Input, OutputBackend, Severity, SymbolValue,
+};
+
 #[derive(Clone, Debug, StructOpt)]
 #[structopt(
     name = "soda",
@@ -21,11 +24,23 @@ use structopt::StructOpt;
     about = "Convert shared libraries into static libraries"
 )]
 struct Args {
-    /// Path to the input shared library.
-    #[structopt(parse(from_os_str))]
-    input: PathBuf,
+    /// Paths to the input shared libraries. Multiple ELF shared libraries are merged into a single output object, in
+    /// which references between them are bound directly.
+    #[structopt(parse(from_os_str), required_unless = "library")]
+    inputs: Vec<PathBuf>,
+
+    /// Find the input shared library libNAME.so, or else libNAME.so.VERSION with the highest version, in the
+    /// directories given with -L. A NAME that starts with a colon is the exact file name to find. The libraries found
+    /// are used as inputs after the paths given. Can be specified multiple times.
+    #[structopt(short = "l", number_of_values = 1)]
+    library: Vec<String>,
+
+    /// Add the given directory to the directories searched for the libraries given with -l, in order. Can be specified
+    /// multiple times.
+    #[structopt(short = "L", number_of_values = 1, parse(from_os_str))]
+    library_path: Vec<PathBuf>,
 
-    /// Path to the output relocatable object file.
+    /// Path to the output relocatable object file. Required when merging multiple shared libraries.
     #[structopt(short, long)]
     #[structopt(parse(from_os_str))]
     output: Option<PathBuf>,
@@ -33,10 +48,226 @@ struct Args {
     /// Output verbosity.
     #[structopt(short, parse(from_occurrences))]
     verbosity: u8,
+
+    /// Show raw symbol names instead of demangled C++ and Rust names in messages.
+    #[structopt(long)]
+    no_demangle: bool,
+
+    /// Fail if any warning is reported during the conversion. No output file is written then.
+    #[structopt(long)]
+    fatal_warnings: bool,
+
+    /// Name of the output section that holds the copied image of an ELF shared library.
+    #[structopt(long, default_value = ".soda")]
+    section_name: String,
+
+    /// Fill the padding bytes between sections in executable segments with trap instructions.
+    #[structopt(long)]
+    trap_padding: bool,
+
+    /// Maximum allowed ratio between the output image size and the input file size. Set to 0 to disable the check.
+    #[structopt(long, default_value = "16")]
+    max_image_size_factor: u64,
+
+    /// Make the data covered by the PT_GNU_RELRO segment read-only at startup. The data stays in place within the
+    /// copied image, and a generated constructor protects it before user constructors run. Only supported on x86_64 Linux.
+    #[structopt(long)]
+    protect_relro: bool,
+
+    /// Maximum alignment of input sections. Larger alignments are clamped to this value.
+    #[structopt(long, default_value = "65536")]
+    max_section_align: u64,
+
+    /// Continue when section data is truncated in the input file. The missing bytes are zero-filled.
+    #[structopt(long)]
+    allow_truncated: bool,
+
+    /// Warn if the TLS image of the input is larger than this many bytes, since it moves into the static TLS block of
+    /// the program. Set to 0 to disable the warning.
+    #[structopt(long, default_value = "4096")]
+    tls_size_warning: u64,
+
+    /// Keep the default version of versioned defined symbols in their names as `name@@VERSION`.
+    #[structopt(long)]
+    keep_symbol_versions: bool,
+
+    /// Do not report exported data objects that are likely targets of copy relocations.
+    #[structopt(long)]
+    no_copy_reloc_check: bool,
+
+    /// Demote exported data objects that are likely targets of copy relocations to local symbols. Only use this if no
+    /// external code refers to these objects.
+    #[structopt(long)]
+    demote_exported_data: bool,
+
+    /// Keep undefined symbols that no relocation refers to.
+    #[structopt(long)]
+    no_prune_undefined: bool,
+
+    /// Keep the given undefined symbol even if no relocation refers to it. Can be specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    keep_undefined: Vec<String>,
+
+    /// Drop the definitions of linker-synthesized symbols such as _DYNAMIC and _end that no relocation refers to,
+    /// instead of demoting them to local symbols.
+    #[structopt(long)]
+    drop_special_symbols: bool,
+
+    /// Drop the definitions of symbols in sections that are not copied into the output and that no relocation refers
+    /// to, instead of turning them into undefined symbols.
+    #[structopt(long)]
+    drop_excluded_symbols: bool,
+
+    /// Redirect references to the given symbol to __wrap_NAME, and export the definition of the symbol, if any, as
+    /// __real_NAME. Can be specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    wrap: Vec<String>,
+
+    /// Rename the symbol OLD to NEW, given as OLD=NEW. Can be specified multiple times.
+    #[structopt(long, number_of_values = 1, parse(try_from_str = parse_name_pair))]
+    redefine_sym: Vec<(String, String)>,
+
+    /// Define the global symbol NAME, given as NAME=VALUE. VALUE is a hexadecimal number with a 0x prefix, a decimal
+    /// number, or the name of a symbol that the input defines, to define NAME at the same location. References of the
+    /// input to NAME bind to the definition. Can be specified multiple times.
+    #[structopt(long, number_of_values = 1, parse(try_from_str = parse_defsym))]
+    defsym: Vec<(String, SymbolValue)>,
+
+    /// Make sure that the output has the global symbol NAME, adding an undefined symbol if the input neither defines nor
+    /// refers to it, and never prune the references of the input to it. NAME is not affected by --wrap. Can be
+    /// specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    require_defined: Vec<String>,
+
+    /// Path to a file that lists symbols to be renamed, one `OLD NEW` pair per line. Lines starting with `#` are
+    /// ignored.
+    #[structopt(long, parse(from_os_str))]
+    redefine_syms: Option<PathBuf>,
+
+    /// Path to a file that lists the names of the defined symbols to keep global, one name or glob pattern per line.
+    /// All the other defined symbols are demoted to local hidden symbols. Lines starting with `#` are ignored.
+    #[structopt(long, parse(from_os_str))]
+    only_export: Option<PathBuf>,
+
+    /// Path to a file that lists the names of the root symbols, one name per line, and zero-fill the copied sections of
+    /// an ELF shared library that they cannot reach through dynamic relocations. The symbols and relocations in these
+    /// sections are dropped. Only sections made up of exported data objects are collected; code is always kept, since
+    /// calls within the library leave no relocation behind. Lines starting with `#` are ignored.
+    #[structopt(long, parse(from_os_str))]
+    gc_roots: Option<PathBuf>,
+
+    /// Bind references to the symbols defined in the converted library to their definitions, so that other definitions
+    /// in the final link do not interpose on them. Weak definitions and the symbols given in --only-export stay
+    /// interposable. This is the default if the library is linked with -Bsymbolic.
+    #[structopt(long)]
+    bind_locally: bool,
+
+    /// Keep references to the symbols defined in the converted library bound to the symbols even if the library is
+    /// linked with -Bsymbolic.
+    #[structopt(long, conflicts_with = "bind-locally")]
+    no_bind_locally: bool,
+
+    /// Make all defined global symbols weak, so that other strong definitions take precedence in the final link.
+    #[structopt(long)]
+    weaken: bool,
+
+    /// Make the given defined global symbol weak. Can be specified multiple times.
+    #[structopt(long, number_of_values = 1)]
+    weaken_symbol: Vec<String>,
+
+    /// Name of the global symbol defined at the start of the converted image. Set to an empty string to omit it.
+    #[structopt(long, default_value = "__soda_image_start")]
+    image_start_symbol: String,
+
+    /// Name of the global symbol defined at the end of the converted image. Set to an empty string to omit it.
+    #[structopt(long, default_value = "__soda_image_end")]
+    image_end_symbol: String,
+
+    /// Convert position-independent executables as well. Their interpreter section is dropped and their entry point is
+    /// kept as the local symbol __soda_entry. Copy relocations in the executable still make the conversion fail.
+    #[structopt(long)]
+    allow_pie: bool,
+
+    /// Fail if the input has text relocations, which patch code and make the final link fail with `-z text`. They are
+    /// reported as a warning otherwise.
+    #[structopt(long)]
+    reject_textrel: bool,
+
+    /// Kind of the sections that hold the pointers to the initialization and termination functions: `init-array` for
+    /// .init_array and .fini_array, or `legacy` for .ctors and .dtors, whose entries are reversed and whose sentinels
+    /// are left to the linker script.
+    #[structopt(long, default_value = "init-array", parse(try_from_str = parse_ctors_style))]
+    ctors_style: CtorsStyle,
+
+    /// Rename the output section OLD to NEW, given as OLD=NEW, such as `.soda=.text.vendor`. Applies to all the
+    /// sections that soda creates, including .tdata, .data.rel.ro and the .init_array sections. Can be specified
+    /// multiple times.
+    #[structopt(long, number_of_values = 1, parse(try_from_str = parse_name_pair))]
+    rename_section: Vec<(String, String)>,
+
+    /// Order the symbols of the output by their indexes in the dynamic symbol table of the input, and the relocations by
+    /// their addresses in the input, so that the outputs of two versions of a library can be diffed entry by entry.
+    /// Sections keep their order, and pruned symbols still shift the following ones; diff the file written with
+    /// --emit-symbols for those.
+    #[structopt(long)]
+    stable_layout: bool,
+
+    /// Writer of ELF outputs: `object` for the writer of the object crate, or `raw` to write each section, symbol and
+    /// relocation through its low-level ELF writer. Outputs of other formats are always written by `object`.
+    #[structopt(long, default_value = "object", parse(try_from_str = parse_backend))]
+    backend: OutputBackend,
+
+    /// Do not emit the .note.soda section, which records the soname and the build ID of the input, the version of soda
+    /// and the conversion options.
+    #[structopt(long)]
+    no_provenance_note: bool,
+
+    /// Print the provenance notes recorded in the inputs, which are relocatable objects written by soda, instead of
+    /// converting them.
+    #[structopt(long)]
+    print_provenance: bool,
+
+    /// Copy the input to the output path unchanged if it is already a relocatable object file.
+    #[structopt(long)]
+    copy_through: bool,
+
+    /// Write a response file for gcc and clang to the given path, which holds the output object, the -l flags of the
+    /// shared libraries that the inputs depend on, and the flags that the final link needs. Pass it as @PATH when
+    /// linking.
+    #[structopt(long, parse(from_os_str))]
+    emit_link_args: Option<PathBuf>,
+
+    /// Write the global symbols that the output defines and requires to the given path, as one JSON object per line
+    /// sorted by name, with the fields name, defined, weak, size, input_address, section and output_offset. Only ELF
+    /// inputs are listed.
+    #[structopt(long, parse(from_os_str))]
+    emit_symbols: Option<PathBuf>,
 }
 
 impl Args {
-    fn get_output_path(&self) -> Cow<Path> {
+    /// Get the paths to the inputs, which are the paths given followed by the libraries found for -l.
+    fn get_input_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut paths = self.inputs.clone();
+        for name in &self.library {
+            let library = soda::search::find_library(name, &self.library_path)
+                .context(format!("cannot search for -l{}", name))?
+                .ok_or_else(|| {
+                    anyhow!("cannot find -l{} in the directories given with -L", name)
+                })?;
+            if !library.other_versions.is_empty() {
+                log::info!(
+                    "Found -l{} at \"{}\", which has the highest version among {} candidates",
+                    name,
+                    library.path.display(),
+                    library.other_versions.len() + 1
+                );
+            }
+            paths.push(library.path);
+        }
+        Ok(paths)
+    }
+
+    fn get_output_path<'a>(&'a self, input_path: &Path) -> Cow<'a, Path> {
         if let Some(path) = &self.output {
             return Cow::Borrowed(path);
         }
@@ -48,19 +279,91 @@ impl Args {
         // - `/dir/libxyz.so` will be converted to `/dir/xyz.o`
         // - `/dir/xyz.so` will be converted to `/dir/xyz.o`
 
-        let mut path = self.input.clone();
+        let mut path = input_path.to_path_buf();
         let file_name = path.file_name().unwrap().to_str().unwrap();
         path.set_file_name(convert_soname_to_object_name(file_name));
 
         Cow::Owned(path)
     }
+
+    fn get_convert_options(&self, sink: Arc<LogSink>) -> anyhow::Result<ConvertOptions> {
+        let only_export = match &self.only_export {
+            Some(path) => Some(
+                soda::read_export_list(path)
+                    .context(format!("cannot read export list \"{}\"", path.display()))?,
+            ),
+            None => None,
+        };
+
+        let gc_roots = match &self.gc_roots {
+            Some(path) => Some(
+                soda::read_export_list(path)
+                    .context(format!("cannot read GC root list \"{}\"", path.display()))?,
+            ),
+            None => None,
+        };
+
+        let mut redefine_syms = self.redefine_sym.clone();
+        if let Some(path) = &self.redefine_syms {
+            redefine_syms.extend(soda::read_redefine_syms(path).context(format!(
+                "cannot read symbol rename list \"{}\"",
+                path.display()
+            ))?);
+        }
+
+        Ok(ConvertOptions {
+            section_name: self.section_name.clone(),
+            trap_padding: self.trap_padding,
+            max_image_size_factor: self.max_image_size_factor,
+            protect_relro: self.protect_relro,
+            max_section_align: self.max_section_align,
+            allow_truncated: self.allow_truncated,
+            tls_size_warning: self.tls_size_warning,
+            keep_symbol_versions: self.keep_symbol_versions,
+            copy_reloc_check: !self.no_copy_reloc_check,
+            demote_exported_data: self.demote_exported_data,
+            prune_undefined: !self.no_prune_undefined,
+            keep_undefined: self.keep_undefined.clone(),
+            drop_special_symbols: self.drop_special_symbols,
+            drop_excluded_symbols: self.drop_excluded_symbols,
+            wrap: self.wrap.clone(),
+            redefine_syms,
+            defsyms: self.defsym.clone(),
+            require_defined: self.require_defined.clone(),
+            only_export,
+            gc_roots,
+            bind_locally: match (self.bind_locally, self.no_bind_locally) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            weaken: self.weaken,
+            weaken_symbols: self.weaken_symbol.clone(),
+            image_start_symbol: self.image_start_symbol.clone(),
+            image_end_symbol: self.image_end_symbol.clone(),
+            allow_pie: self.allow_pie,
+            reject_textrel: self.reject_textrel,
+            ctors_style: self.ctors_style,
+            rename_sections: self.rename_section.clone(),
+            stable_layout: self.stable_layout,
+            backend: self.backend,
+            provenance_note: !self.no_provenance_note,
+            symbol_list: self.emit_symbols.as_ref().map(|_| Arc::default()),
+            observer: None,
+            diagnostics: Some(sink),
+            demangle: !self.no_demangle,
+        })
+    }
 }
 
 fn main() -> ExitCode {
     let args = Args::from_args();
     if let Err(err) = do_main(&args) {
         eprintln!("Error: {:#}", err);
-        return ExitCode::FAILURE;
+        let exit_code = err
+            .downcast_ref::<soda::Error>()
+            .map_or(soda::Error::EXIT_FAILURE, soda::Error::exit_code);
+        return ExitCode::from(exit_code);
     }
 
     ExitCode::SUCCESS
@@ -69,49 +372,266 @@ fn main() -> ExitCode {
 fn do_main(args: &Args) -> anyhow::Result<()> {
     init_logger(args.verbosity)?;
 
+    let input_paths = args.get_input_paths()?;
+    if args.print_provenance {
+        return print_provenance(&input_paths);
+    }
+
+    if input_paths.len() > 1 {
+        return merge(args, &input_paths);
+    }
+
+    let input_path = &input_paths[0];
     log::info!("Reading input shared library ...");
-    let input_buffer = std::fs::read(&args.input).context(format!(
-        "cannot read input shared library \"{}\"",
-        args.input.display()
-    ))?;
-    let input_file = InputFile::parse(input_buffer.as_slice()).context(format!(
-        "cannot parse input shared library \"{}\"",
-        args.input.display()
-    ))?;
+    let input_buffer = read_input(input_path)?;
+    let input = parse_input(input_path, &input_buffer)?;
+
+    let output_path = &*args.get_output_path(input_path);
 
-    if input_file.kind() != ObjectKind::Dynamic {
-        return Err(anyhow::Error::msg("input file is not a shared library"));
+    if input.summary().kind == ObjectKind::Relocatable && args.copy_through {
+        log::info!("Input file is already a relocatable object; copying it to the output path");
+        return copy_through(input.data(), output_path);
     }
+    check_input_kind(input_path, input.summary().kind)?;
 
     // Open the output file, preparing to write later.
-    let output_path = &*args.get_output_path();
-    let mut output_file = OutputFile::create(output_path).context(format!(
+    let output_file = OutputFile::create(output_path).context(format!(
         "failed to open output file \"{}\"",
         output_path.display()
     ))?;
 
     // Convert the input shared library into output relocatable file.
     log::info!("Start the conversion");
-    let output_object = match input_file {
-        InputFile::Elf32(elf_file) => crate::elf::convert(elf_file)?,
-        InputFile::Elf64(elf_file) => crate::elf::convert(elf_file)?,
-        _ => {
+    let sink = Arc::new(LogSink::default());
+    let convert_options = args.get_convert_options(sink.clone())?;
+    let fatal_warnings = args.fatal_warnings.then_some(&*sink);
+    let mut link_args = LinkArgs::new(output_path);
+    link_args.add_input(&input)?;
+    write_output(output_file, output_path, fatal_warnings, |writer| {
+        input.convert_to_writer(&convert_options, writer)
+    })?;
+    emit_link_args(args, &link_args)?;
+    emit_symbols(args, &convert_options)
+}
+
+/// Merge multiple ELF shared libraries into a single output relocatable file.
+fn merge(args: &Args, input_paths: &[PathBuf]) -> anyhow::Result<()> {
+    let Some(output_path) = &args.output else {
+        return Err(anyhow!(
+            "an output path must be given with -o when merging multiple shared libraries"
+        ));
+    };
+
+    log::info!("Reading input shared libraries ...");
+    let input_buffers = input_paths
+        .iter()
+        .map(|path| read_input(path))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut inputs = Vec::new();
+    let mut link_args = LinkArgs::new(output_path);
+    for (path, buffer) in input_paths.iter().zip(&input_buffers) {
+        let input = parse_input(path, buffer)?;
+        if input.summary().kind == ObjectKind::Relocatable {
+            return Err(anyhow!(
+                "input file \"{}\" is already a relocatable object, not a shared library; link it directly instead \
+                 of merging it",
+                path.display()
+            ));
+        }
+        check_input_kind(path, input.summary().kind)?;
+        if input.summary().format != BinaryFormat::Elf {
             return Err(anyhow!(
-                "{} format is not supported yet",
-                crate::utils::stringify::binary_format_to_str(input_file.format())
+                "input file \"{}\" is not an ELF shared library; only ELF shared libraries can be merged",
+                path.display()
             ));
         }
+
+        link_args.add_input(&input)?;
+        let name = path.file_name().unwrap().to_str().unwrap();
+        inputs.push((name, input));
+    }
+
+    let output_file = OutputFile::create(output_path).context(format!(
+        "failed to open output file \"{}\"",
+        output_path.display()
+    ))?;
+
+    log::info!("Start the conversion");
+    let sink = Arc::new(LogSink::default());
+    let convert_options = args.get_convert_options(sink.clone())?;
+    let fatal_warnings = args.fatal_warnings.then_some(&*sink);
+    write_output(output_file, output_path, fatal_warnings, |writer| {
+        soda::merge_inputs_to_writer(inputs, &convert_options, writer)
+    })?;
+    emit_link_args(args, &link_args)?;
+    emit_symbols(args, &convert_options)
+}
+
+/// Write the given link arguments into the response file given with --emit-link-args, if any.
+fn emit_link_args(args: &Args, link_args: &LinkArgs) -> anyhow::Result<()> {
+    let Some(path) = &args.emit_link_args else {
+        return Ok(());
     };
 
-    // Save the produced output object to the output file.
-    log::info!("Writing output file ...");
-    output_object
-        .write_stream(output_file.writer())
-        .map_err(|err| anyhow!("{:?}", err))
-        .context(format!(
-            "failed to write output file \"{}\"",
-            output_path.display()
+    let versioned: Vec<&str> = link_args.versioned_needed().collect();
+    if !versioned.is_empty() {
+        log::warn!(
+            "The final link needs the unversioned symlinks of {}, which are usually installed by the development \
+             packages of the libraries",
+            versioned.join(", ")
+        );
+    }
+    std::fs::write(path, link_args.to_response_file()).context(format!(
+        "failed to write link arguments \"{}\"",
+        path.display()
+    ))
+}
+
+/// Write the symbols listed during the conversion into the file given with --emit-symbols, if any.
+fn emit_symbols(args: &Args, convert_options: &ConvertOptions) -> anyhow::Result<()> {
+    let (Some(path), Some(symbol_list)) = (&args.emit_symbols, &convert_options.symbol_list) else {
+        return Ok(());
+    };
+    std::fs::write(path, symbol_list.to_json_lines()).context(format!(
+        "failed to write symbol list \"{}\"",
+        path.display()
+    ))
+}
+
+/// Print the provenance notes recorded in the inputs.
+fn print_provenance(input_paths: &[PathBuf]) -> anyhow::Result<()> {
+    for path in input_paths {
+        let data = std::fs::read(path).context(format!("cannot read \"{}\"", path.display()))?;
+        let provenances = soda::read_provenance(&data).context(format!(
+            "cannot read provenance notes of \"{}\"",
+            path.display()
         ))?;
+        if provenances.is_empty() {
+            println!("{}: no provenance note", path.display());
+            continue;
+        }
+
+        for provenance in provenances {
+            let build_id: String = provenance
+                .build_id
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            println!("{}:", path.display());
+            println!("  soname: {}", provenance.soname);
+            println!("  build ID: {}", build_id);
+            println!("  soda version: {}", provenance.soda_version);
+            println!("  options:");
+            for line in provenance.options.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the input file at the given path.
+fn read_input(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let input_buffer = std::fs::read(path).context(format!(
+        "cannot read input shared library \"{}\"",
+        path.display()
+    ))?;
+
+    // Archives are not object files themselves, so they are recognized by their magic before parsing.
+    if input_buffer.starts_with(&object::archive::MAGIC)
+        || input_buffer.starts_with(&object::archive::THIN_MAGIC)
+    {
+        return Err(anyhow!(
+            "input file \"{}\" is a static archive, not a shared library; extract its members with `ar x` and link \
+             them directly instead",
+            path.display()
+        ));
+    }
+
+    Ok(input_buffer)
+}
+
+/// Parse the given data of the input file at the given path.
+fn parse_input<'d>(path: &Path, data: &'d [u8]) -> anyhow::Result<Input<'d>> {
+    Input::parse(data).context(format!(
+        "cannot parse input shared library \"{}\"",
+        path.display()
+    ))
+}
+
+/// Check that the input file at the given path, which is of the given object kind, is a shared library.
+fn check_input_kind(path: &Path, kind: ObjectKind) -> anyhow::Result<()> {
+    match kind {
+        ObjectKind::Dynamic => Ok(()),
+        ObjectKind::Relocatable => Err(anyhow!(
+            "input file \"{}\" is already a relocatable object, not a shared library; link it directly, or pass \
+             --copy-through to copy it to the output path",
+            path.display()
+        )),
+        ObjectKind::Executable => Err(anyhow!(
+            "input file \"{}\" is a position-dependent executable, not a shared library; it cannot be converted",
+            path.display()
+        )),
+        ObjectKind::Core => Err(anyhow!(
+            "input file \"{}\" is a core dump, not a shared library",
+            path.display()
+        )),
+        _ => Err(anyhow!(
+            "input file \"{}\" is of an unknown object kind, not a shared library",
+            path.display()
+        )),
+    }
+}
+
+/// Run the given conversion, which writes the output object into the given output file as it is serialized. If a sink
+/// is given for fatal warnings, the output file is deleted when the sink has received any warning.
+fn write_output(
+    mut output_file: OutputFile,
+    output_path: &Path,
+    fatal_warnings: Option<&LogSink>,
+    convert: impl FnOnce(&mut dyn Write) -> Result<(), soda::Error>,
+) -> anyhow::Result<()> {
+    let context = || format!("failed to write output file \"{}\"", output_path.display());
+    match convert(output_file.writer()) {
+        Ok(()) => {}
+        // The conversion itself reads no files, so I/O errors come from writing the output.
+        Err(soda::Error::IoError(err)) => return Err(anyhow::Error::new(err).context(context())),
+        Err(err) => return Err(err.into()),
+    }
+
+    if let Some(sink) = fatal_warnings {
+        let num_warnings = sink.num_warnings.load(Ordering::Relaxed);
+        if num_warnings > 0 {
+            let noun = if num_warnings == 1 {
+                "warning was"
+            } else {
+                "warnings were"
+            };
+            return Err(anyhow!(
+                "{} {} reported and --fatal-warnings is given",
+                num_warnings,
+                noun
+            ));
+        }
+    }
+    output_file.writer().flush().with_context(context)?;
+
+    output_file.prevent_delete_on_drop();
+    log::info!("Done.");
+
+    Ok(())
+}
+
+/// Write the given input file data to the output path unchanged.
+fn copy_through(input_data: &[u8], output_path: &Path) -> anyhow::Result<()> {
+    let context = || format!("failed to write output file \"{}\"", output_path.display());
+    let mut output_file = OutputFile::create(output_path).with_context(context)?;
+    output_file
+        .writer()
+        .write_all(input_data)
+        .and_then(|_| output_file.writer().flush())
+        .with_context(context)?;
 
     output_file.prevent_delete_on_drop();
     log::info!("Done.");
@@ -119,6 +639,46 @@ fn do_main(args: &Args) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Parse a symbol rename given as `OLD=NEW`.
+fn parse_name_pair(arg: &str) -> anyhow::Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((old_name, new_name)) if !old_name.is_empty() && !new_name.is_empty() => {
+            Ok((String::from(old_name), String::from(new_name)))
+        }
+        _ => Err(anyhow!("expected OLD=NEW, got \"{}\"", arg)),
+    }
+}
+
+fn parse_defsym(arg: &str) -> anyhow::Result<(String, SymbolValue)> {
+    let Some((name, value)) = arg.split_once('=').filter(|(name, _)| !name.is_empty()) else {
+        return Err(anyhow!("expected NAME=VALUE, got \"{}\"", arg));
+    };
+    match SymbolValue::parse(value) {
+        Some(value) => Ok((String::from(name), value)),
+        None => Err(anyhow!(
+            "expected a number or a symbol name as the value of {}, got \"{}\"",
+            name,
+            value
+        )),
+    }
+}
+
+fn parse_ctors_style(arg: &str) -> anyhow::Result<CtorsStyle> {
+    match arg {
+        "init-array" => Ok(CtorsStyle::InitArray),
+        "legacy" => Ok(CtorsStyle::Legacy),
+        _ => Err(anyhow!("expected init-array or legacy, got \"{}\"", arg)),
+    }
+}
+
+fn parse_backend(arg: &str) -> anyhow::Result<OutputBackend> {
+    match arg {
+        "object" => Ok(OutputBackend::Object),
+        "raw" => Ok(OutputBackend::Raw),
+        _ => Err(anyhow!("expected object or raw, got \"{}\"", arg)),
+    }
+}
+
 /// Convert a shared library name into its corresponding object name.
 ///
 /// Examples of the conversion:
@@ -167,6 +727,28 @@ fn init_logger(verbosity: u8) -> Result<(), SetLoggerError> {
     Ok(())
 }
 
+/// Forwards the diagnostics of the conversion to the logger, and counts the warnings.
+#[derive(Debug, Default)]
+struct LogSink {
+    num_warnings: AtomicUsize,
+}
+
+impl DiagnosticSink for LogSink {
+    fn report(&self, severity: Severity, message: Arguments<'_>) {
+        if severity == Severity::Warning {
+            self.num_warnings.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let level = match severity {
+            Severity::Trace => LogLevel::Trace,
+            Severity::Debug => LogLevel::Debug,
+            Severity::Info => LogLevel::Info,
+            Severity::Warning => LogLevel::Warn,
+        };
+        log::log!(level, "{}", message);
+    }
+}
+
 #[derive(Debug)]
 struct OutputFile {
     path: PathBuf,
@@ -0,0 +1,666 @@
+//! The output that the passes build: sections, symbols and relocations added one at a time, and serialized by an
+//! [`OutputBackend`] once all passes have run.
+//!
+//! The API follows [`object::write::Object`], so that passes read like code written against it. Unlike that object,
+//! the output keeps every section, symbol and relocation readable, which is what a backend other than the object
+//! writer needs to serialize it.
+//!
+//! Relocations added to ELF outputs are fixed up as the object writer would do it: preemptible targets are replaced by
+//! section symbols, and the addends of formats without explicit addends are written into the section data. Relocations
+//! of other formats are kept as they are added, and only the object writer handles them.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use object::write::{
+    MachOBuildVersion, Mangling, Object as ObjectWriter, Relocation as ObjectRelocation,
+    Symbol as ObjectSymbol, SymbolId as ObjectSymbolId, SymbolSection as ObjectSymbolSection,
+    WritableBuffer,
+};
+use object::{
+    Architecture, BinaryFormat, Endian, Endianness, FileFlags, RelocationEncoding, RelocationKind,
+    SectionFlags, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
+
+/// The writer that serializes the output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutputBackend {
+    /// Replay the output into an [`object::write::Object`], and serialize it with the object.
+    #[default]
+    Object,
+
+    /// Write ELF outputs through [`object::write::elf::Writer`], which lays out each section, symbol and relocation as
+    /// the backend says. Outputs of other formats are written by the object backend.
+    Raw,
+}
+
+/// An error that occurred when building or serializing the output.
+#[derive(Debug)]
+pub struct Error(pub(crate) String);
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<object::write::Error> for Error {
+    fn from(err: object::write::Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A relocatable object file under construction.
+#[derive(Debug)]
+pub struct Object<'a> {
+    format: BinaryFormat,
+    architecture: Architecture,
+    endian: Endianness,
+    sections: Vec<Section<'a>>,
+    symbols: Vec<Symbol>,
+    symbol_map: HashMap<Vec<u8>, SymbolId>,
+    macho_build_version: Option<MachOBuildVersion>,
+
+    /// File flags that are specific to each file format.
+    pub flags: FileFlags,
+
+    /// The symbol name mangling scheme.
+    pub mangling: Mangling,
+}
+
+impl<'a> Object<'a> {
+    /// Create an empty object file.
+    pub fn new(format: BinaryFormat, architecture: Architecture, endian: Endianness) -> Self {
+        Self {
+            format,
+            architecture,
+            endian,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+            symbol_map: HashMap::new(),
+            macho_build_version: None,
+            flags: FileFlags::None,
+            mangling: Mangling::default(format, architecture),
+        }
+    }
+
+    pub fn format(&self) -> BinaryFormat {
+        self.format
+    }
+
+    pub fn architecture(&self) -> Architecture {
+        self.architecture
+    }
+
+    pub fn endian(&self) -> Endianness {
+        self.endian
+    }
+
+    pub fn mangling(&self) -> Mangling {
+        self.mangling
+    }
+
+    pub fn set_mangling(&mut self, mangling: Mangling) {
+        self.mangling = mangling;
+    }
+
+    /// Set the build version that Mach-O outputs carry in their `LC_BUILD_VERSION` command.
+    pub fn set_macho_build_version(&mut self, info: MachOBuildVersion) {
+        self.macho_build_version = Some(info);
+    }
+
+    /// Add a new section and return its ID.
+    pub fn add_section(&mut self, segment: Vec<u8>, name: Vec<u8>, kind: SectionKind) -> SectionId {
+        let id = SectionId(self.sections.len());
+        self.sections.push(Section {
+            segment,
+            name,
+            kind,
+            size: 0,
+            align: 1,
+            data: Cow::Borrowed(&[]),
+            relocations: Vec::new(),
+            symbol: None,
+            flags: SectionFlags::None,
+        });
+        id
+    }
+
+    pub fn section(&self, section: SectionId) -> &Section<'a> {
+        &self.sections[section.0]
+    }
+
+    pub fn section_mut(&mut self, section: SectionId) -> &mut Section<'a> {
+        &mut self.sections[section.0]
+    }
+
+    /// Get the sections in the order they are added.
+    pub fn sections(&self) -> impl Iterator<Item = (SectionId, &Section<'a>)> {
+        self.sections
+            .iter()
+            .enumerate()
+            .map(|(idx, section)| (SectionId(idx), section))
+    }
+
+    /// Set the data of a section that has no data yet.
+    pub fn set_section_data<T>(&mut self, section: SectionId, data: T, align: u64)
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        self.sections[section.0].set_data(data, align)
+    }
+
+    /// Append data to a section. Get the offset of the data within the section.
+    pub fn append_section_data(&mut self, section: SectionId, data: &[u8], align: u64) -> u64 {
+        self.sections[section.0].append_data(data, align)
+    }
+
+    /// Append zero-initialized data to a section. Get the offset of the data within the section.
+    pub fn append_section_bss(&mut self, section: SectionId, size: u64, align: u64) -> u64 {
+        self.sections[section.0].append_bss(size, align)
+    }
+
+    /// Get the symbol of the given section, and add it if the section has none yet.
+    pub fn section_symbol(&mut self, section_id: SectionId) -> SymbolId {
+        let section = &mut self.sections[section_id.0];
+        if let Some(symbol) = section.symbol {
+            return symbol;
+        }
+
+        // COFF section symbols are named after their sections.
+        let name = if self.format == BinaryFormat::Coff {
+            section.name.clone()
+        } else {
+            Vec::new()
+        };
+        let symbol_id = SymbolId(self.symbols.len());
+        self.symbols.push(Symbol {
+            name,
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Section,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: SymbolSection::Section(section_id),
+            flags: SymbolFlags::None,
+        });
+        section.symbol = Some(symbol_id);
+        symbol_id
+    }
+
+    /// Add a new symbol and return its ID.
+    ///
+    /// Section symbols are the ones of [`section_symbol`](Self::section_symbol). Names of text, data and TLS symbols
+    /// get the global prefix of the mangling scheme, and [`symbol_id`](Self::symbol_id) finds them by their names
+    /// without the prefix.
+    pub fn add_symbol(&mut self, mut symbol: Symbol) -> SymbolId {
+        debug_assert!(symbol.is_undefined() || symbol.scope != SymbolScope::Unknown);
+        if symbol.kind == SymbolKind::Section {
+            let symbol_id = self.section_symbol(symbol.section.id().unwrap());
+            if symbol.flags != SymbolFlags::None {
+                self.symbol_mut(symbol_id).flags = symbol.flags;
+            }
+            return symbol_id;
+        }
+
+        let symbol_id = SymbolId(self.symbols.len());
+        if !symbol.name.is_empty()
+            && matches!(
+                symbol.kind,
+                SymbolKind::Text | SymbolKind::Data | SymbolKind::Tls
+            )
+        {
+            self.symbol_map.insert(symbol.name.clone(), symbol_id);
+            if let Some(prefix) = self.mangling.global_prefix() {
+                symbol.name.insert(0, prefix);
+            }
+        }
+        self.symbols.push(symbol);
+        symbol_id
+    }
+
+    /// Get the ID of the text, data or TLS symbol with the given unmangled name.
+    pub fn symbol_id(&self, name: &[u8]) -> Option<SymbolId> {
+        self.symbol_map.get(name).cloned()
+    }
+
+    pub fn symbol(&self, symbol: SymbolId) -> &Symbol {
+        &self.symbols[symbol.0]
+    }
+
+    pub fn symbol_mut(&mut self, symbol: SymbolId) -> &mut Symbol {
+        &mut self.symbols[symbol.0]
+    }
+
+    /// Get the symbols in the order they are added.
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(idx, symbol)| (SymbolId(idx), symbol))
+    }
+
+    /// Add a relocation to a section. The symbol of the relocation must be added and defined already.
+    pub fn add_relocation(&mut self, section: SectionId, mut relocation: Relocation) -> Result<()> {
+        if self.format == BinaryFormat::Elf {
+            let addend = self.elf_fixup_relocation(&mut relocation)?;
+            if addend != 0 {
+                self.write_relocation_addend(section, &relocation, addend)?;
+            }
+        }
+        self.sections[section.0].relocations.push(relocation);
+        Ok(())
+    }
+
+    /// Fix up the given ELF relocation. Get the addend to write into the section data, which is non-zero only if the
+    /// architecture has no explicit addends.
+    fn elf_fixup_relocation(&mut self, relocation: &mut Relocation) -> Result<i64> {
+        // Relocations against preemptible symbols refer to their sections instead, which linkers would reject in shared
+        // objects otherwise.
+        let symbol = &self.symbols[relocation.symbol.0];
+        let preemptible = symbol.scope == SymbolScope::Dynamic
+            && match (symbol.kind, relocation.kind) {
+                (
+                    SymbolKind::Text | SymbolKind::Data,
+                    RelocationKind::Got
+                    | RelocationKind::GotRelative
+                    | RelocationKind::GotBaseRelative
+                    | RelocationKind::PltRelative
+                    | RelocationKind::Elf(_),
+                ) => false,
+                (SymbolKind::Data, RelocationKind::Absolute) => false,
+                (SymbolKind::Text | SymbolKind::Data, _) => true,
+                _ => false,
+            };
+        if preemptible {
+            if let Some(section) = symbol.section.id() {
+                relocation.addend += symbol.value as i64;
+                relocation.symbol = self.section_symbol(section);
+            }
+        }
+
+        if crate::elf::writer::has_relocation_addend(self.architecture)? {
+            Ok(0)
+        } else {
+            Ok(std::mem::take(&mut relocation.addend))
+        }
+    }
+
+    fn write_relocation_addend(
+        &mut self,
+        section: SectionId,
+        relocation: &Relocation,
+        addend: i64,
+    ) -> Result<()> {
+        let endian = self.endian;
+        let data = self.sections[section.0].data_mut();
+        let offset = relocation.offset as usize;
+        let bytes = match relocation.size {
+            32 => endian.write_u32_bytes(addend as u32).to_vec(),
+            64 => endian.write_u64_bytes(addend as u64).to_vec(),
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented relocation addend {:?}",
+                    relocation
+                )))
+            }
+        };
+        let data_len = data.len();
+        data.get_mut(offset..offset + bytes.len())
+            .ok_or_else(|| {
+                Error(format!(
+                    "invalid relocation offset {}+{} (max {})",
+                    relocation.offset, relocation.size, data_len
+                ))
+            })?
+            .copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    /// Serialize the object with the default backend into a new buffer.
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.emit(OutputBackend::Object, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Serialize the object with the given backend into the given buffer.
+    pub fn emit(&self, backend: OutputBackend, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        match (backend, self.format) {
+            (OutputBackend::Raw, BinaryFormat::Elf) => crate::elf::writer::write(self, buffer),
+            _ => Ok(self.to_object_writer()?.emit(buffer)?),
+        }
+    }
+
+    /// Add the sections, the symbols and the relocations of the object to a new [`object::write::Object`], in the
+    /// order they are added. The IDs of the sections and the symbols stay the same.
+    fn to_object_writer(&self) -> Result<ObjectWriter<'_>> {
+        let mut object = ObjectWriter::new(self.format, self.architecture, self.endian);
+        object.flags = self.flags;
+        // Symbol names are mangled already.
+        object.mangling = Mangling::None;
+        if let Some(version) = self.macho_build_version {
+            object.set_macho_build_version(version);
+        }
+
+        let mut section_ids = Vec::with_capacity(self.sections.len());
+        for section in &self.sections {
+            let section_id =
+                object.add_section(section.segment.clone(), section.name.clone(), section.kind);
+            let object_section = object.section_mut(section_id);
+            object_section.flags = section.flags;
+            if section.is_bss() {
+                object_section.append_bss(section.size, section.align);
+            } else {
+                object_section.set_data(&*section.data, section.align);
+            }
+            section_ids.push(section_id);
+        }
+
+        let mut symbol_ids: Vec<ObjectSymbolId> = Vec::with_capacity(self.symbols.len());
+        for symbol in &self.symbols {
+            let flags = match symbol.flags {
+                SymbolFlags::None => SymbolFlags::None,
+                SymbolFlags::Elf { st_info, st_other } => SymbolFlags::Elf { st_info, st_other },
+                SymbolFlags::MachO { n_desc } => SymbolFlags::MachO { n_desc },
+                SymbolFlags::CoffSection {
+                    selection,
+                    associative_section,
+                } => SymbolFlags::CoffSection {
+                    selection,
+                    associative_section: associative_section.map(|id| section_ids[id.0]),
+                },
+                flags => return Err(Error(format!("unimplemented symbol flags {:?}", flags))),
+            };
+            let symbol_id = object.add_symbol(ObjectSymbol {
+                name: symbol.name.clone(),
+                value: symbol.value,
+                size: symbol.size,
+                kind: symbol.kind,
+                scope: symbol.scope,
+                weak: symbol.weak,
+                section: match symbol.section {
+                    SymbolSection::None => ObjectSymbolSection::None,
+                    SymbolSection::Undefined => ObjectSymbolSection::Undefined,
+                    SymbolSection::Absolute => ObjectSymbolSection::Absolute,
+                    SymbolSection::Common => ObjectSymbolSection::Common,
+                    SymbolSection::Section(id) => ObjectSymbolSection::Section(section_ids[id.0]),
+                },
+                flags,
+            });
+            symbol_ids.push(symbol_id);
+        }
+
+        for (section, section_id) in self.sections.iter().zip(&section_ids) {
+            for reloc in &section.relocations {
+                object.add_relocation(
+                    *section_id,
+                    ObjectRelocation {
+                        offset: reloc.offset,
+                        size: reloc.size,
+                        kind: reloc.kind,
+                        encoding: reloc.encoding,
+                        symbol: symbol_ids[reloc.symbol.0],
+                        addend: reloc.addend,
+                    },
+                )?;
+            }
+        }
+
+        Ok(object)
+    }
+}
+
+/// The ID of a section of an [`Object`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SectionId(pub(crate) usize);
+
+/// A section of an [`Object`].
+#[derive(Debug)]
+pub struct Section<'a> {
+    segment: Vec<u8>,
+    name: Vec<u8>,
+    kind: SectionKind,
+    size: u64,
+    align: u64,
+    data: Cow<'a, [u8]>,
+    relocations: Vec<Relocation>,
+    symbol: Option<SymbolId>,
+
+    /// Section flags that are specific to each file format.
+    pub flags: SectionFlags,
+}
+
+impl<'a> Section<'a> {
+    /// Try to convert the name to a UTF-8 string.
+    pub fn name(&self) -> Option<&str> {
+        std::str::from_utf8(&self.name).ok()
+    }
+
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// Try to convert the segment name to a UTF-8 string.
+    pub fn segment(&self) -> Option<&str> {
+        std::str::from_utf8(&self.segment).ok()
+    }
+
+    pub fn kind(&self) -> SectionKind {
+        self.kind
+    }
+
+    /// Get the size of the section, which includes the zero-initialized data of BSS sections.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn align(&self) -> u64 {
+        self.align
+    }
+
+    /// Determine whether the section only holds zero-initialized data.
+    pub fn is_bss(&self) -> bool {
+        self.kind.is_bss()
+    }
+
+    /// Get the relocations of the section in the order they are added.
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocations
+    }
+
+    /// Set the data of the section, which must have no data yet and must not be a BSS section.
+    pub fn set_data<T>(&mut self, data: T, align: u64)
+    where
+        T: Into<Cow<'a, [u8]>>,
+    {
+        debug_assert!(!self.is_bss());
+        debug_assert!(align.is_power_of_two());
+        debug_assert!(self.data.is_empty());
+        self.data = data.into();
+        self.size = self.data.len() as u64;
+        self.align = align;
+    }
+
+    /// Append data to the section, which must not be a BSS section. Get the offset of the data within the section.
+    pub fn append_data(&mut self, data: &[u8], align: u64) -> u64 {
+        debug_assert!(!self.is_bss());
+        debug_assert!(align.is_power_of_two());
+        self.align = self.align.max(align);
+        let section_data = self.data.to_mut();
+        let offset = section_data.len().next_multiple_of(align as usize);
+        section_data.resize(offset, 0);
+        section_data.extend_from_slice(data);
+        self.size = section_data.len() as u64;
+        offset as u64
+    }
+
+    /// Append zero-initialized data to the section, which must be a BSS section. Get the offset of the data within the
+    /// section.
+    pub fn append_bss(&mut self, size: u64, align: u64) -> u64 {
+        debug_assert!(self.is_bss());
+        debug_assert!(align.is_power_of_two());
+        self.align = self.align.max(align);
+        let offset = self.size.next_multiple_of(align);
+        self.size = offset + size;
+        offset
+    }
+
+    /// Get the data of the section, which must not be a BSS section.
+    pub fn data(&self) -> &[u8] {
+        debug_assert!(!self.is_bss());
+        &self.data
+    }
+
+    /// Get the mutable data of the section, which must not be a BSS section.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        debug_assert!(!self.is_bss());
+        self.data.to_mut()
+    }
+}
+
+/// The ID of a symbol of an [`Object`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SymbolId(pub(crate) usize);
+
+/// The section that a symbol of an [`Object`] is defined in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SymbolSection {
+    /// The symbol has no section, such as a file symbol.
+    None,
+    Undefined,
+    Absolute,
+    Common,
+    Section(SectionId),
+}
+
+impl SymbolSection {
+    /// Get the ID of the section that the symbol is defined in, if any.
+    pub fn id(self) -> Option<SectionId> {
+        match self {
+            Self::Section(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// A symbol of an [`Object`].
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: Vec<u8>,
+
+    /// The value of the symbol, which is an offset within its section if it is defined in one.
+    pub value: u64,
+
+    pub size: u64,
+    pub kind: SymbolKind,
+    pub scope: SymbolScope,
+    pub weak: bool,
+    pub section: SymbolSection,
+
+    /// Symbol flags that are specific to each file format.
+    pub flags: SymbolFlags<SectionId, SymbolId>,
+}
+
+impl Symbol {
+    /// Try to convert the name to a UTF-8 string.
+    pub fn name(&self) -> Option<&str> {
+        std::str::from_utf8(&self.name).ok()
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        self.section == SymbolSection::Undefined
+    }
+
+    pub fn is_common(&self) -> bool {
+        self.section == SymbolSection::Common
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.scope == SymbolScope::Compilation
+    }
+}
+
+/// A relocation within a section of an [`Object`].
+#[derive(Debug)]
+pub struct Relocation {
+    /// The offset of the relocated place within the section.
+    pub offset: u64,
+
+    /// The size of the relocated place in bits.
+    pub size: u8,
+
+    pub kind: RelocationKind,
+    pub encoding: RelocationEncoding,
+
+    /// The symbol that the relocation refers to, which may be a section symbol.
+    pub symbol: SymbolId,
+
+    /// The explicit addend of the relocation, which adds to the implicit addend in the section data.
+    pub addend: i64,
+}
+
+#[cfg(test)]
+mod test {
+    use object::{
+        Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationKind, SectionKind,
+        SymbolFlags, SymbolKind, SymbolScope,
+    };
+
+    use super::{Object, Relocation, Symbol, SymbolSection};
+
+    #[test]
+    fn test_elf_fixup_relocation() {
+        let mut output = Object::new(BinaryFormat::Elf, Architecture::I386, Endianness::Little);
+        let text = output.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+        output.set_section_data(text, vec![0u8; 0x10], 16);
+        let func = output.add_symbol(Symbol {
+            name: b"func".to_vec(),
+            value: 4,
+            size: 4,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        assert_eq!(output.symbol_id(b"func"), Some(func));
+
+        // The preemptible target is replaced by the section symbol, and the addend goes into the data.
+        output
+            .add_relocation(
+                text,
+                Relocation {
+                    offset: 8,
+                    size: 32,
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    symbol: func,
+                    addend: 2,
+                },
+            )
+            .unwrap();
+        let section = output.section(text);
+        let reloc = &section.relocations()[0];
+        assert_eq!(output.symbol(reloc.symbol).kind, SymbolKind::Section);
+        assert_eq!(reloc.addend, 0);
+        assert_eq!(&section.data()[8..12], &6u32.to_le_bytes());
+
+        let place = Relocation {
+            offset: 0xe,
+            size: 32,
+            kind: RelocationKind::Absolute,
+            encoding: RelocationEncoding::Generic,
+            symbol: func,
+            addend: 2,
+        };
+        assert_eq!(
+            output.add_relocation(text, place).unwrap_err().to_string(),
+            "invalid relocation offset 14+32 (max 16)"
+        );
+    }
+}
@@ -1,12 +1,16 @@
 use std::any::Any;
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Arguments, Debug, Formatter};
 use std::marker::PhantomData;
+use std::sync::Arc;
 
-use object::write::Object as OutputObject;
+use object::Architecture;
 use thiserror::Error;
 
+use crate::output::Object as OutputObject;
+
 /// Represent a pass.
 pub trait Pass<I> {
     const NAME: &'static str;
@@ -18,14 +22,146 @@ pub trait Pass<I> {
     fn run(&mut self, ctx: &PassContext<I>) -> Result<Self::Output, Self::Error>;
 }
 
+/// Observe the progress of a pass pipeline, and request it to be cancelled.
+///
+/// The observer may be shared with another thread, such as the UI thread that lets the user cancel the conversion.
+pub trait PassObserver: Debug + Send + Sync {
+    /// Called before the pass with the given name and index starts, out of the given number of passes.
+    #[allow(unused_variables)]
+    fn pass_started(&self, name: &str, index: usize, count: usize) {}
+
+    /// Called after the pass with the given name and index finishes successfully, out of the given number of passes.
+    #[allow(unused_variables)]
+    fn pass_finished(&self, name: &str, index: usize, count: usize) {}
+
+    /// Determine whether the pass pipeline should be cancelled. This is checked between passes, and at coarse intervals
+    /// within long-running passes.
+    fn should_cancel(&self) -> bool {
+        false
+    }
+}
+
+/// Severity of a diagnostic reported during a conversion, in increasing order.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+}
+
+/// Receive the diagnostics reported during a conversion.
+///
+/// Each conversion reports to its own sink, so that messages of conversions running concurrently can be told apart.
+pub trait DiagnosticSink: Debug + Send + Sync {
+    /// Called with each diagnostic of the given severity. The message is only formatted if the sink formats it.
+    fn report(&self, severity: Severity, message: Arguments<'_>);
+}
+
+/// Report diagnostics to an optional [`DiagnosticSink`]. Diagnostics are dropped if there is no sink.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    sink: Option<Arc<dyn DiagnosticSink>>,
+}
+
+impl Diagnostics {
+    /// Create a new `Diagnostics` that reports to the given sink.
+    pub fn new(sink: Option<Arc<dyn DiagnosticSink>>) -> Self {
+        Self { sink }
+    }
+
+    /// Report a diagnostic of the given severity.
+    pub fn report(&self, severity: Severity, message: Arguments<'_>) {
+        if let Some(sink) = &self.sink {
+            sink.report(severity, message);
+        }
+    }
+
+    /// Report a warning about a questionable input or a lossy conversion.
+    pub fn warn(&self, message: Arguments<'_>) {
+        self.report(Severity::Warning, message);
+    }
+
+    /// Report the progress of the conversion.
+    pub fn info(&self, message: Arguments<'_>) {
+        self.report(Severity::Info, message);
+    }
+
+    /// Report details of the conversion.
+    pub fn debug(&self, message: Arguments<'_>) {
+        self.report(Severity::Debug, message);
+    }
+
+    /// Report details of the conversion of single items, such as relocations.
+    pub fn trace(&self, message: Arguments<'_>) {
+        self.report(Severity::Trace, message);
+    }
+}
+
+/// Renames of output sections, like the `--rename-section` option of objcopy. Passes look up the name of each output
+/// section here when they create it, so that symbols and relocations only ever refer to the renamed section.
+#[derive(Clone, Debug, Default)]
+pub struct SectionNaming {
+    renames: HashMap<String, String>,
+}
+
+impl SectionNaming {
+    /// Create a new `SectionNaming` from pairs of old and new names.
+    ///
+    /// `reserved_names` are the names of the output sections that the passes may create. Renaming a section to one of
+    /// them is rejected unless that section is renamed as well, and so is renaming two sections to the same name.
+    pub fn new(
+        renames: &[(String, String)],
+        reserved_names: &[&str],
+    ) -> Result<Self, CreateContextError> {
+        let mut map = HashMap::with_capacity(renames.len());
+        for (old_name, new_name) in renames {
+            if map.insert(old_name.clone(), new_name.clone()).is_some() {
+                return Err(CreateContextError::DuplicateSectionRename(old_name.clone()));
+            }
+        }
+
+        let mut new_names = HashSet::with_capacity(map.len());
+        for (old_name, new_name) in renames {
+            let is_reserved =
+                reserved_names.contains(&new_name.as_str()) && !map.contains_key(new_name);
+            if !new_names.insert(new_name) || is_reserved {
+                return Err(CreateContextError::SectionRenameCollision {
+                    old_name: old_name.clone(),
+                    new_name: new_name.clone(),
+                });
+            }
+        }
+
+        Ok(Self { renames: map })
+    }
+
+    /// Get the name of the output section whose default name is given.
+    pub fn get<'a>(&'a self, name: &'a str) -> &'a str {
+        self.renames.get(name).map_or(name, String::as_str)
+    }
+}
+
 /// Provide context for running a single pass.
 pub struct PassContext<I> {
     pub input: I,
     pub output: RefCell<OutputObject<'static>>,
+    pub diagnostics: Diagnostics,
+    pub section_naming: SectionNaming,
     pass_outputs: Vec<Box<dyn Any>>,
+    observer: Option<Arc<dyn PassObserver>>,
 }
 
 impl<I> PassContext<I> {
+    /// Return an error if the observer of the pass pipeline requests it to be cancelled. Long-running passes call this at
+    /// coarse intervals.
+    pub fn check_cancelled(&self) -> Result<(), Cancelled> {
+        match &self.observer {
+            Some(observer) if observer.should_cancel() => Err(Cancelled),
+            _ => Ok(()),
+        }
+    }
+
     /// Get the value produced by the pass referenced by the given handle.
     ///
     /// # Panics
@@ -64,12 +200,35 @@ where
 #[derive(Default)]
 pub struct PassManager<I> {
     passes: Vec<Box<dyn AbstractPass<I>>>,
+    observer: Option<Arc<dyn PassObserver>>,
+    diagnostics: Diagnostics,
+    section_naming: SectionNaming,
 }
 
 impl<I> PassManager<I> {
     /// Create a new `PassManager` that does not contain any passes.
     pub fn new() -> Self {
-        Self { passes: Vec::new() }
+        Self {
+            passes: Vec::new(),
+            observer: None,
+            diagnostics: Diagnostics::default(),
+            section_naming: SectionNaming::default(),
+        }
+    }
+
+    /// Set the observer of the pass pipeline.
+    pub fn set_observer(&mut self, observer: Option<Arc<dyn PassObserver>>) {
+        self.observer = observer;
+    }
+
+    /// Set the diagnostics that the passes report to.
+    pub fn set_diagnostics(&mut self, diagnostics: Diagnostics) {
+        self.diagnostics = diagnostics;
+    }
+
+    /// Set the renames of the output sections that the passes create.
+    pub fn set_section_naming(&mut self, section_naming: SectionNaming) {
+        self.section_naming = section_naming;
     }
 
     /// Add a pass to the end of the current pass pipeline.
@@ -83,6 +242,7 @@ impl<I> PassManager<I> {
     }
 
     /// Add a pass to the end of the current pass pipeline. The pass object is created via `Default::default`.
+    #[allow(dead_code)]
     pub fn add_pass_default<P>(&mut self) -> PassHandle<P>
     where
         P: Default + Pass<I> + 'static,
@@ -99,14 +259,32 @@ impl<I> PassManager<I> {
         let mut ctx = PassContext {
             input,
             output: RefCell::new(output),
+            diagnostics: self.diagnostics,
+            section_naming: self.section_naming,
             pass_outputs: Vec::with_capacity(self.passes.len()),
+            observer: self.observer,
         };
 
-        for current_pass in &mut self.passes {
-            log::info!("Running pass \"{}\" ...", current_pass.name());
+        let num_passes = self.passes.len();
+        for (idx, current_pass) in self.passes.iter_mut().enumerate() {
+            if let Err(err) = ctx.check_cancelled() {
+                return Err(RunPassError {
+                    name: String::from(current_pass.name()),
+                    error: err.into(),
+                });
+            }
+
+            ctx.diagnostics
+                .info(format_args!("Running pass \"{}\" ...", current_pass.name()));
+            if let Some(observer) = &ctx.observer {
+                observer.pass_started(current_pass.name(), idx, num_passes);
+            }
             match current_pass.run(&ctx) {
                 Ok(result) => {
                     ctx.pass_outputs.push(result);
+                    if let Some(observer) = &ctx.observer {
+                        observer.pass_finished(current_pass.name(), idx, num_passes);
+                    }
                 }
                 Err(err) => {
                     return Err(RunPassError {
@@ -140,17 +318,14 @@ impl<P> PassHandle<P> {
     fn new(idx: usize) -> Self {
         Self {
             idx,
-            _phantom: PhantomData::default(),
+            _phantom: PhantomData,
         }
     }
 }
 
 impl<P> Clone for PassHandle<P> {
     fn clone(&self) -> Self {
-        Self {
-            idx: self.idx.clone(),
-            _phantom: PhantomData::default(),
-        }
+        *self
     }
 }
 
@@ -165,8 +340,11 @@ impl<P> Debug for PassHandle<P> {
 }
 
 /// Errors occured when running a pass pipeline.
+///
+/// The error produced by the failed pass is the source of this error, and can be downcast to the concrete error type of
+/// the pass.
 #[derive(Debug, Error)]
-#[error("pass {name} failed: {error:?}")]
+#[error("pass {name} failed")]
 pub struct RunPassError {
     /// The name of the specific pass that failed.
     pub name: String,
@@ -176,6 +354,55 @@ pub struct RunPassError {
     pub error: anyhow::Error,
 }
 
+/// The error returned when the observer of a pass pipeline requests it to be cancelled.
+#[derive(Clone, Copy, Debug, Error)]
+#[error("cancelled")]
+pub struct Cancelled;
+
+impl RunPassError {
+    /// Determine whether the pass pipeline was cancelled rather than failed.
+    pub fn is_cancelled(&self) -> bool {
+        self.error.chain().any(|err| err.is::<Cancelled>())
+    }
+}
+
+/// Errors occured when checking the input and creating the output before running a pass pipeline.
+#[derive(Debug, Error)]
+pub enum CreateContextError {
+    #[error("unsupported architecture: {}", crate::utils::stringify::arch_to_str(*.0))]
+    UnsupportedArch(Architecture),
+
+    #[error("corrupted input: {0}")]
+    CorruptedData(String),
+
+    #[error(
+        "input appears to be a position-independent executable, not a shared library; pass --allow-pie to convert it \
+         anyway"
+    )]
+    PositionIndependentExecutable,
+
+    #[error("input DLL has {0}")]
+    UnsupportedDllDirectory(&'static str),
+
+    #[error("no input shared library to merge")]
+    NoMergeInput,
+
+    #[error("more than one input shared library is named {0}")]
+    DuplicateMergeInput(String),
+
+    #[error("input shared library {name} targets a different architecture than {first_name}")]
+    MismatchedMergeArch { name: String, first_name: String },
+
+    #[error("cannot merge 32-bit and 64-bit shared libraries into a single output")]
+    MixedElfClasses,
+
+    #[error("section {0} is renamed more than once")]
+    DuplicateSectionRename(String),
+
+    #[error("cannot rename section {old_name} to {new_name}, since another output section has that name")]
+    SectionRenameCollision { old_name: String, new_name: String },
+}
+
 trait AbstractPass<I> {
     fn name(&self) -> &'static str;
     fn run(&mut self, ctx: &PassContext<I>) -> anyhow::Result<Box<dyn Any>>;
@@ -245,7 +472,7 @@ pub mod test {
         fn run(&mut self, ctx: &PassContext<T::Input>) -> Result<Self::Output, Self::Error> {
             self.test
                 .check_pass_output(ctx.get_pass_output(self.target_pass));
-            self.test.check_output_object(&*ctx.output.borrow());
+            self.test.check_output_object(&ctx.output.borrow());
             Ok(())
         }
     }
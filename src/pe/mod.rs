@@ -0,0 +1,106 @@
+pub mod pass;
+
+#[cfg(test)]
+mod test;
+
+use object::pe::{
+    IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR, IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT,
+    IMAGE_DIRECTORY_ENTRY_TLS,
+};
+use object::read::pe::PeFile64;
+use object::{Architecture, BinaryFormat, Endianness, Object as _, ObjectKind};
+
+use crate::output::Object as OutputObject;
+use crate::pass::{CreateContextError, Diagnostics, PassManager};
+use crate::pe::pass::import::ConvertImportPass;
+use crate::pe::pass::reloc::ConvertRelocationPass;
+use crate::pe::pass::section::CopyImagePass;
+use crate::pe::pass::symbol::GenerateSymbolPass;
+use crate::pe::pass::unwind::GenerateUnwindInfoPass;
+use crate::utils::demangle::with_demangle_enabled;
+use crate::{ConvertOptions, Error};
+
+/// Convert the given PE input DLL into a COFF relocatable file.
+pub fn convert(
+    input: PeFile64<'_>,
+    options: &ConvertOptions,
+) -> Result<OutputObject<'static>, Error> {
+    assert_eq!(input.kind(), ObjectKind::Dynamic);
+
+    check_unsupported_directories(&input)?;
+
+    let output = create_coff_output(&input)?;
+
+    let mut pass_mgr = PassManager::new();
+    pass_mgr.set_observer(options.observer.clone());
+    pass_mgr.set_diagnostics(Diagnostics::new(options.diagnostics.clone()));
+    init_passes(&mut pass_mgr);
+
+    let output = with_demangle_enabled(options.demangle, || pass_mgr.run(input, output))?;
+    Ok(output)
+}
+
+fn create_coff_output(input: &PeFile64<'_>) -> Result<OutputObject<'static>, CreateContextError> {
+    const SUPPORTED_ARCH: &[Architecture] = &[Architecture::X86_64];
+
+    let arch = input.architecture();
+    if !SUPPORTED_ARCH.contains(&arch) {
+        return Err(CreateContextError::UnsupportedArch(arch));
+    }
+
+    Ok(OutputObject::new(
+        BinaryFormat::Coff,
+        arch,
+        Endianness::Little,
+    ))
+}
+
+/// Reject inputs that rely on loader features that cannot be expressed in a COFF relocatable file yet.
+fn check_unsupported_directories(input: &PeFile64<'_>) -> Result<(), CreateContextError> {
+    const UNSUPPORTED_DIRECTORIES: &[(usize, &str)] = &[
+        (
+            IMAGE_DIRECTORY_ENTRY_TLS,
+            "a TLS directory; thread-local variables and TLS callbacks are not supported",
+        ),
+        (
+            IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT,
+            "delay-load imports, which are not supported; relink it without /DELAYLOAD",
+        ),
+        (
+            IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR,
+            "a CLR header; managed code cannot be converted",
+        ),
+    ];
+
+    for (index, description) in UNSUPPORTED_DIRECTORIES {
+        let non_empty = input
+            .data_directory(*index)
+            .is_some_and(|dir| dir.size.get(object::LittleEndian) != 0);
+        if non_empty {
+            return Err(CreateContextError::UnsupportedDllDirectory(description));
+        }
+    }
+
+    Ok(())
+}
+
+/// Register passes required to convert a PE DLL.
+fn init_passes(pass_mgr: &mut PassManager<PeFile64<'_>>) {
+    // Copy the image of the input DLL into a single output section.
+    let cls_pass = pass_mgr.add_pass(CopyImagePass);
+
+    // Generate symbols for the exports of the input DLL.
+    let sym_gen_pass = pass_mgr.add_pass(GenerateSymbolPass { cls_pass });
+
+    // Convert the import address table into relocations against undefined symbols.
+    pass_mgr.add_pass(ConvertImportPass {
+        cls_pass,
+        sym_gen_pass,
+    });
+
+    // Convert the base relocations into relocations against the output section.
+    pass_mgr.add_pass(ConvertRelocationPass { cls_pass });
+
+    // Generate the .pdata section that describes how to unwind the functions in the image.
+    pass_mgr.add_pass(GenerateUnwindInfoPass { cls_pass });
+}
@@ -0,0 +1,113 @@
+use object::pe::ImageNtHeaders64;
+use object::read::pe::{Import, PeFile64};
+use object::read::Error as ReadError;
+use object::{LittleEndian, RelocationEncoding, RelocationKind};
+use thiserror::Error;
+
+use crate::output::Relocation as OutputRelocation;
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::pe::pass::section::CopyImagePass;
+use crate::pe::pass::symbol::{get_or_create_symbol, GenerateSymbolPass};
+
+/// A pass that converts the import address table of the input DLL into relocations.
+///
+/// The loader fills each slot of the import address table with the address of the imported function or variable. Each
+/// slot becomes a 64-bit absolute relocation against an undefined symbol with the plain name of the import, such as
+/// `GetLastError`, rather than its `__imp_` name. When the final link resolves the symbol through an import library,
+/// the slot holds the address of the import thunk, which jumps to the function. Variables cannot be reached through
+/// import thunks, so data imports must be resolved by static definitions.
+///
+/// The library each import comes from is not recorded, so the final link has to name the libraries itself. Imports by
+/// ordinal have no name to resolve and are rejected.
+#[derive(Debug)]
+pub struct ConvertImportPass {
+    pub cls_pass: PassHandle<CopyImagePass>,
+    pub sym_gen_pass: PassHandle<GenerateSymbolPass>,
+}
+
+impl<'d> Pass<PeFile64<'d>> for ConvertImportPass {
+    const NAME: &'static str = "convert imports";
+
+    type Output = ();
+    type Error = ConvertImportError;
+
+    fn run(&mut self, ctx: &PassContext<PeFile64<'d>>) -> Result<Self::Output, Self::Error> {
+        let Some(import_table) = ctx.input.import_table()? else {
+            return Ok(());
+        };
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut sym_map = ctx.get_pass_output(self.sym_gen_pass).clone();
+        let mut output = ctx.output.borrow_mut();
+        let section_id = cls_output.output_section_id;
+
+        let mut descriptors = import_table.descriptors()?;
+        while let Some(descriptor) = descriptors.next()? {
+            let dll =
+                String::from_utf8_lossy(import_table.name(descriptor.name.get(LittleEndian))?);
+            ctx.diagnostics
+                .debug(format_args!("Converting imports from {}", dll));
+
+            // The import lookup table is optional, in which case the import address table holds the same entries.
+            let iat = descriptor.first_thunk.get(LittleEndian);
+            let lookup_table = match descriptor.original_first_thunk.get(LittleEndian) {
+                0 => iat,
+                rva => rva,
+            };
+            let mut thunks = import_table.thunks(lookup_table)?;
+
+            let mut slot = u64::from(iat);
+            while let Some(thunk) = thunks.next::<ImageNtHeaders64>()? {
+                let name = match import_table.import::<ImageNtHeaders64>(thunk)? {
+                    Import::Name(_, name) => name,
+                    Import::Ordinal(ordinal) => {
+                        return Err(ConvertImportError::ImportByOrdinal {
+                            dll: dll.into_owned(),
+                            ordinal,
+                        });
+                    }
+                };
+
+                let offset = cls_output
+                    .translate_field(slot, 8)
+                    .ok_or(ConvertImportError::SlotOutsideImage(slot))?;
+                let symbol = get_or_create_symbol(&mut output, &mut sym_map, name);
+
+                // The addend is implicit in the section data, where the slot still holds the RVA of the hint/name
+                // entry.
+                output.section_mut(section_id).data_mut()[offset as usize..offset as usize + 8]
+                    .fill(0);
+                output
+                    .add_relocation(
+                        section_id,
+                        OutputRelocation {
+                            offset,
+                            size: 64,
+                            kind: RelocationKind::Absolute,
+                            encoding: RelocationEncoding::Generic,
+                            symbol,
+                            addend: 0,
+                        },
+                    )
+                    .unwrap();
+
+                slot += 8;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when converting imports.
+#[derive(Debug, Error)]
+pub enum ConvertImportError {
+    #[error("read PE failed: {0:?}")]
+    ReadPeError(#[from] ReadError),
+
+    #[error("import of ordinal {ordinal} from {dll} cannot be converted since it has no name")]
+    ImportByOrdinal { dll: String, ordinal: u16 },
+
+    #[error("the import address table slot at {0:#x} lies outside of the image")]
+    SlotOutsideImage(u64),
+}
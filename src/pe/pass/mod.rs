@@ -0,0 +1,5 @@
+pub mod import;
+pub mod reloc;
+pub mod section;
+pub mod symbol;
+pub mod unwind;
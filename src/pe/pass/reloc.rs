@@ -0,0 +1,100 @@
+use object::pe::{IMAGE_REL_BASED_ABSOLUTE, IMAGE_REL_BASED_DIR64};
+use object::read::pe::{ImageNtHeaders as _, ImageOptionalHeader as _, PeFile64};
+use object::read::Error as ReadError;
+use object::{RelocationEncoding, RelocationKind};
+use thiserror::Error;
+
+use crate::output::Relocation as OutputRelocation;
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::pe::pass::section::CopyImagePass;
+
+/// A pass that converts the base relocations of the input DLL into relocations in the output relocatable file.
+///
+/// The loader adds the distance between the actual and the preferred image base to each pointer listed in the base
+/// relocation table. Each `IMAGE_REL_BASED_DIR64` entry becomes an `IMAGE_REL_AMD64_ADDR64` relocation against the
+/// output section, with the offset of the pointed-to address as the addend. Padding entries are skipped, and other
+/// kinds of entries are rejected.
+#[derive(Debug)]
+pub struct ConvertRelocationPass {
+    pub cls_pass: PassHandle<CopyImagePass>,
+}
+
+impl<'d> Pass<PeFile64<'d>> for ConvertRelocationPass {
+    const NAME: &'static str = "convert relocations";
+
+    type Output = ();
+    type Error = ConvertRelocationError;
+
+    fn run(&mut self, ctx: &PassContext<PeFile64<'d>>) -> Result<Self::Output, Self::Error> {
+        let Some(mut blocks) = ctx
+            .input
+            .data_directories()
+            .relocation_blocks(ctx.input.data(), &ctx.input.section_table())?
+        else {
+            return Ok(());
+        };
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut output = ctx.output.borrow_mut();
+        let section_id = cls_output.output_section_id;
+        let image_base = ctx.input.nt_headers().optional_header().image_base();
+
+        let mut count = 0usize;
+        while let Some(block) = blocks.next()? {
+            for reloc in block {
+                match reloc.typ {
+                    IMAGE_REL_BASED_ABSOLUTE => continue,
+                    IMAGE_REL_BASED_DIR64 => {}
+                    typ => {
+                        return Err(ConvertRelocationError::UnsupportedReloc {
+                            typ,
+                            rva: reloc.virtual_address,
+                        })
+                    }
+                }
+
+                let rva = u64::from(reloc.virtual_address);
+                let offset = cls_output
+                    .translate_field(rva, 8)
+                    .ok_or(ConvertRelocationError::RelocOutsideImage(rva))?;
+                let data = &output.section(section_id).data()[offset as usize..offset as usize + 8];
+                let value = u64::from_le_bytes(data.try_into().unwrap());
+
+                let (symbol, addend) =
+                    cls_output.get_address_reference(value.wrapping_sub(image_base));
+                output
+                    .add_relocation(
+                        section_id,
+                        OutputRelocation {
+                            offset,
+                            size: 64,
+                            kind: RelocationKind::Absolute,
+                            encoding: RelocationEncoding::Generic,
+                            symbol,
+                            addend,
+                        },
+                    )
+                    .unwrap();
+                count += 1;
+            }
+        }
+
+        ctx.diagnostics
+            .info(format_args!("Converted {} base relocations", count));
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when converting base relocations.
+#[derive(Debug, Error)]
+pub enum ConvertRelocationError {
+    #[error("read PE failed: {0:?}")]
+    ReadPeError(#[from] ReadError),
+
+    #[error("unsupported base relocation type {typ} at {rva:#x}")]
+    UnsupportedReloc { typ: u16, rva: u32 },
+
+    #[error("the base relocation at {0:#x} lies outside of the image")]
+    RelocOutsideImage(u64),
+}
@@ -0,0 +1,126 @@
+use object::pe::{
+    IMAGE_SCN_CNT_CODE, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_DISCARDABLE,
+    IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+};
+use object::read::pe::{ImageNtHeaders as _, ImageOptionalHeader as _, PeFile64};
+use object::read::Error as ReadError;
+use object::{LittleEndian, SectionFlags, SectionKind};
+use thiserror::Error;
+
+use crate::output::{SectionId, SymbolId};
+use crate::pass::{Pass, PassContext};
+
+/// A pass that copies the image of the input DLL into a single section of the output relocatable object.
+///
+/// Code refers to data in other sections through RIP-relative addressing that carries no base relocation, so the
+/// sections are copied into the same output section with their distances unchanged. The headers are copied as well, so
+/// code that addresses the image relative to its base (`__ImageBase`), such as the pseudo relocator of the mingw
+/// runtime, still finds what it expects. Discardable sections, such as `.reloc`, are not part of the loaded image and
+/// are left zero-filled.
+///
+/// The output section is readable, writable and executable, since it holds both code and writable data.
+#[derive(Debug, Default)]
+pub struct CopyImagePass;
+
+impl CopyImagePass {
+    /// Name of the output section.
+    pub const SECTION_NAME: &'static str = ".soda";
+
+    /// Maximum alignment of a COFF section.
+    const MAX_ALIGN: u64 = 8192;
+}
+
+impl<'d> Pass<PeFile64<'d>> for CopyImagePass {
+    const NAME: &'static str = "copy image";
+
+    type Output = CopyImageOutput;
+    type Error = CopyImageError;
+
+    fn run(&mut self, ctx: &PassContext<PeFile64<'d>>) -> Result<Self::Output, Self::Error> {
+        let optional_header = ctx.input.nt_headers().optional_header();
+        let image_size = optional_header.size_of_image() as usize;
+        let headers_size = optional_header.size_of_headers() as usize;
+        ctx.diagnostics
+            .info(format_args!("Output image size: {} bytes", image_size));
+
+        let mut buffer = vec![0u8; image_size];
+        let data = ctx.input.data();
+        let headers_size = headers_size.min(image_size).min(data.len());
+        buffer[..headers_size].copy_from_slice(&data[..headers_size]);
+
+        for section in ctx.input.section_table().iter() {
+            if section.characteristics.get(LittleEndian) & IMAGE_SCN_MEM_DISCARDABLE != 0 {
+                continue;
+            }
+
+            // Zero-filled parts of sections are not present in the input file.
+            let section_data = section.pe_data(data)?;
+            let offset = section.virtual_address.get(LittleEndian) as usize;
+            let size = section_data.len().min(image_size.saturating_sub(offset));
+            buffer[offset..offset + size].copy_from_slice(&section_data[..size]);
+        }
+
+        let mut output = ctx.output.borrow_mut();
+        let output_section_id = output.add_section(
+            Vec::new(),
+            Self::SECTION_NAME.as_bytes().to_vec(),
+            SectionKind::Text,
+        );
+        let output_section_symbol = output.section_symbol(output_section_id);
+
+        let section = output.section_mut(output_section_id);
+        section.flags = SectionFlags::Coff {
+            characteristics: IMAGE_SCN_CNT_CODE
+                | IMAGE_SCN_CNT_INITIALIZED_DATA
+                | IMAGE_SCN_MEM_EXECUTE
+                | IMAGE_SCN_MEM_READ
+                | IMAGE_SCN_MEM_WRITE,
+        };
+        let align = u64::from(optional_header.section_alignment()).clamp(1, Self::MAX_ALIGN);
+        section.set_data(buffer, align);
+
+        Ok(CopyImageOutput {
+            output_section_id,
+            output_section_symbol,
+            image_size: image_size as u64,
+        })
+    }
+}
+
+/// Errors that may occur when copying the image.
+#[derive(Debug, Error)]
+pub enum CopyImageError {
+    #[error("read PE failed: {0:?}")]
+    ReadPeError(#[from] ReadError),
+}
+
+#[derive(Clone, Debug)]
+pub struct CopyImageOutput {
+    /// The ID of the output section that holds the copied image.
+    pub output_section_id: SectionId,
+
+    /// The ID of the output section symbol.
+    pub output_section_symbol: SymbolId,
+
+    /// Size of the copied image.
+    pub image_size: u64,
+}
+
+impl CopyImageOutput {
+    /// Translate the given RVA in the input image into an offset within the output section. Returns `None` if the RVA
+    /// lies outside of the image.
+    pub fn translate(&self, rva: u64) -> Option<u64> {
+        (rva < self.image_size).then_some(rva)
+    }
+
+    /// Translate the RVA of a field with the given size in the input image into an offset within the output section.
+    /// Returns `None` if any part of the field lies outside of the image.
+    pub fn translate_field(&self, rva: u64, size: u64) -> Option<u64> {
+        (rva.checked_add(size)? <= self.image_size).then_some(rva)
+    }
+
+    /// Get the output symbol and addend that together refer to the given RVA in the input image.
+    pub fn get_address_reference(&self, rva: u64) -> (SymbolId, i64) {
+        (self.output_section_symbol, rva as i64)
+    }
+}
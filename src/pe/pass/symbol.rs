@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use object::read::pe::{ExportTarget, ImageNtHeaders as _, ImageOptionalHeader as _, PeFile64};
+use object::read::Error as ReadError;
+use object::{Object as _, ObjectSection as _, SectionKind, SymbolFlags, SymbolKind, SymbolScope};
+use thiserror::Error;
+
+use crate::output::{
+    Object as OutputObject, Symbol as OutputSymbol, SymbolId, SymbolSection as OutputSymbolSection,
+};
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::pe::pass::section::CopyImagePass;
+
+/// A pass that generates the symbol table of the output relocatable file.
+///
+/// Each export of the input DLL that is named and refers to an address within the image generates a corresponding
+/// global output symbol. Exports in executable sections become functions, and the others become data objects. Exports
+/// by ordinal only and exports forwarded to other DLLs have no definition to refer to, so they are reported and dropped.
+///
+/// No `__imp_NAME` symbols are defined. Consumers compiled with `__declspec(dllimport)` refer to them, but both GNU ld
+/// and lld synthesize them for locally defined symbols.
+///
+/// The entry point of the input DLL, which is usually `DllMainCRTStartup`, is defined as the global symbol
+/// [`GenerateSymbolPass::ENTRY_SYMBOL_NAME`]. Nothing calls it in the final link, so the program has to call it like
+/// the loader would, with `DLL_PROCESS_ATTACH`, before using the converted DLL.
+#[derive(Debug)]
+pub struct GenerateSymbolPass {
+    pub cls_pass: PassHandle<CopyImagePass>,
+}
+
+impl GenerateSymbolPass {
+    /// Name of the symbol at the entry point of the input DLL.
+    pub const ENTRY_SYMBOL_NAME: &'static str = "__soda_dll_entry";
+}
+
+impl<'d> Pass<PeFile64<'d>> for GenerateSymbolPass {
+    const NAME: &'static str = "generate symbols";
+
+    type Output = SymbolMap;
+    type Error = GenerateSymbolError;
+
+    fn run(&mut self, ctx: &PassContext<PeFile64<'d>>) -> Result<Self::Output, Self::Error> {
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut output = ctx.output.borrow_mut();
+        let image_base = ctx.input.relative_address_base();
+
+        let get_symbol_kind = |rva: u32| {
+            let address = image_base + u64::from(rva);
+            let section = ctx.input.sections().find(|section| {
+                (section.address()..section.address() + section.size()).contains(&address)
+            });
+            match section.map(|section| section.kind()) {
+                Some(SectionKind::Text) => SymbolKind::Text,
+                _ => SymbolKind::Data,
+            }
+        };
+
+        let mut sym_map = SymbolMap::default();
+        let exports = match ctx.input.export_table()? {
+            Some(export_table) => export_table.exports()?,
+            None => Vec::new(),
+        };
+        for export in exports {
+            let Some(name) = export.name else {
+                ctx.diagnostics.warn(format_args!(
+                    "Export with ordinal {} has no name; it is dropped",
+                    export.ordinal
+                ));
+                continue;
+            };
+            let display_name = String::from_utf8_lossy(name);
+
+            let rva = match export.target {
+                ExportTarget::Address(rva) => rva,
+                ExportTarget::ForwardByName(dll, _) | ExportTarget::ForwardByOrdinal(dll, _) => {
+                    ctx.diagnostics.warn(format_args!(
+                        "Export \"{}\" is forwarded to {}; it is dropped",
+                        display_name,
+                        String::from_utf8_lossy(dll)
+                    ));
+                    continue;
+                }
+            };
+            let Some(value) = cls_output.translate(u64::from(rva)) else {
+                ctx.diagnostics.warn(format_args!(
+                    "Export \"{}\" lies outside of the image; it is dropped",
+                    display_name
+                ));
+                continue;
+            };
+
+            let output_sym = output.add_symbol(OutputSymbol {
+                name: name.to_vec(),
+                value,
+                size: 0,
+                kind: get_symbol_kind(rva),
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: OutputSymbolSection::Section(cls_output.output_section_id),
+                flags: SymbolFlags::None,
+            });
+            sym_map.insert(name, output_sym);
+        }
+
+        let entry = ctx
+            .input
+            .nt_headers()
+            .optional_header()
+            .address_of_entry_point();
+        if entry != 0 {
+            if let Some(value) = cls_output.translate(u64::from(entry)) {
+                output.add_symbol(OutputSymbol {
+                    name: GenerateSymbolPass::ENTRY_SYMBOL_NAME.as_bytes().to_vec(),
+                    value,
+                    size: 0,
+                    kind: SymbolKind::Text,
+                    scope: SymbolScope::Linkage,
+                    weak: false,
+                    section: OutputSymbolSection::Section(cls_output.output_section_id),
+                    flags: SymbolFlags::None,
+                });
+            }
+        }
+
+        ctx.diagnostics
+            .info(format_args!("Generated {} symbols", sym_map.len()));
+
+        Ok(sym_map)
+    }
+}
+
+/// Errors that may occur when generating symbols.
+#[derive(Debug, Error)]
+pub enum GenerateSymbolError {
+    #[error("read PE failed: {0:?}")]
+    ReadPeError(#[from] ReadError),
+}
+
+/// Maps symbol names to output symbols.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolMap {
+    symbols: HashMap<Vec<u8>, SymbolId>,
+}
+
+impl SymbolMap {
+    /// Map the given symbol name to the given output symbol.
+    pub fn insert(&mut self, name: &[u8], output_sym: SymbolId) {
+        self.symbols.entry(name.to_vec()).or_insert(output_sym);
+    }
+
+    /// Get the output symbol with the given name.
+    pub fn get(&self, name: &[u8]) -> Option<SymbolId> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Get the number of mapped symbols.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Determine whether no symbol is mapped.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// Get the output symbol with the given name, or create an undefined output symbol with the name if there is no such
+/// symbol yet.
+///
+/// Whether an import is a function or a variable is unknown, and COFF requires a kind, so undefined symbols are created
+/// as data objects, which carry no function type in the symbol table.
+pub fn get_or_create_symbol(
+    output: &mut OutputObject<'static>,
+    sym_map: &mut SymbolMap,
+    name: &[u8],
+) -> SymbolId {
+    if let Some(output_sym) = sym_map.get(name) {
+        return output_sym;
+    }
+
+    let output_sym = output.add_symbol(OutputSymbol {
+        name: name.to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: OutputSymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    sym_map.insert(name, output_sym);
+    output_sym
+}
@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+use object::pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION;
+use object::read::pe::PeFile64;
+use object::{LittleEndian, RelocationEncoding, RelocationKind, SectionKind};
+use thiserror::Error;
+
+use crate::output::{Object as OutputObject, Relocation as OutputRelocation, SectionId};
+use crate::pass::{Pass, PassContext, PassHandle};
+use crate::pe::pass::section::{CopyImageOutput, CopyImagePass};
+
+/// Size of a `RUNTIME_FUNCTION` entry.
+const RUNTIME_FUNCTION_SIZE: u64 = 12;
+
+const UNW_FLAG_EHANDLER: u8 = 0x1;
+const UNW_FLAG_UHANDLER: u8 = 0x2;
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+/// A pass that generates the `.pdata` section of the output relocatable file.
+///
+/// The exception directory of the input DLL lists a `RUNTIME_FUNCTION` entry for each function that allocates stack
+/// space or saves registers, which the unwinder relies on to walk the stack. The entries only hold RVAs, so they are
+/// regenerated in an output `.pdata` section with `IMAGE_REL_AMD64_ADDR32NB` relocations against the copied image, from
+/// which the final link builds its own exception directory. The unwind information stays in the copied image, where the
+/// RVAs of chained entries get relocations as well.
+///
+/// Exception handlers are not supported, since their language-specific data holds RVAs in formats that depend on the
+/// handler. Functions with structured exception handling or C++ exception handling are rejected.
+#[derive(Debug)]
+pub struct GenerateUnwindInfoPass {
+    pub cls_pass: PassHandle<CopyImagePass>,
+}
+
+impl<'d> Pass<PeFile64<'d>> for GenerateUnwindInfoPass {
+    const NAME: &'static str = "generate unwind info";
+
+    type Output = ();
+    type Error = GenerateUnwindInfoError;
+
+    fn run(&mut self, ctx: &PassContext<PeFile64<'d>>) -> Result<Self::Output, Self::Error> {
+        let Some(dir) = ctx.input.data_directory(IMAGE_DIRECTORY_ENTRY_EXCEPTION) else {
+            return Ok(());
+        };
+        let table_rva = u64::from(dir.virtual_address.get(LittleEndian));
+        let table_size = u64::from(dir.size.get(LittleEndian));
+        if table_size == 0 {
+            return Ok(());
+        }
+
+        let cls_output = ctx.get_pass_output(self.cls_pass);
+        let mut output = ctx.output.borrow_mut();
+        let num_entries = table_size / RUNTIME_FUNCTION_SIZE;
+        let table_offset = cls_output
+            .translate_field(table_rva, num_entries * RUNTIME_FUNCTION_SIZE)
+            .ok_or(GenerateUnwindInfoError::OutsideImage(table_rva))?;
+
+        let pdata_id =
+            output.add_section(Vec::new(), b".pdata".to_vec(), SectionKind::ReadOnlyData);
+        output
+            .section_mut(pdata_id)
+            .set_data(vec![0u8; (num_entries * RUNTIME_FUNCTION_SIZE) as usize], 4);
+
+        let mut visitor = UnwindInfoVisitor {
+            cls_output,
+            output: &mut output,
+            visited: HashSet::new(),
+        };
+        for idx in 0..num_entries {
+            let entry_offset = idx * RUNTIME_FUNCTION_SIZE;
+            visitor.convert_runtime_function(
+                table_offset + entry_offset,
+                pdata_id,
+                entry_offset,
+            )?;
+        }
+
+        ctx.diagnostics.info(format_args!(
+            "Generated {} unwind table entries",
+            num_entries
+        ));
+
+        Ok(())
+    }
+}
+
+/// Errors that may occur when generating the unwind information.
+#[derive(Debug, Error)]
+pub enum GenerateUnwindInfoError {
+    #[error("the unwind information at {0:#x} lies outside of the image")]
+    OutsideImage(u64),
+
+    #[error("unsupported unwind information version {version} at {rva:#x}")]
+    UnsupportedVersion { version: u8, rva: u64 },
+
+    #[error(
+        "the function at {0:#x} has an exception handler; structured exception handling and C++ exceptions are not \
+         supported"
+    )]
+    ExceptionHandler(u64),
+}
+
+/// Converts the RVAs in `RUNTIME_FUNCTION` entries and the unwind information they refer to.
+struct UnwindInfoVisitor<'a> {
+    cls_output: &'a CopyImageOutput,
+    output: &'a mut OutputObject<'static>,
+
+    /// RVAs of the unwind information that have been converted, since multiple entries may share them.
+    visited: HashSet<u64>,
+}
+
+impl UnwindInfoVisitor<'_> {
+    /// Convert the `RUNTIME_FUNCTION` entry at the given offset within the copied image into relocations at the given
+    /// offset within the given output section.
+    fn convert_runtime_function(
+        &mut self,
+        entry_offset: u64,
+        section_id: SectionId,
+        offset: u64,
+    ) -> Result<(), GenerateUnwindInfoError> {
+        let begin = self.read_u32(entry_offset)?;
+        let end = self.read_u32(entry_offset + 4)?;
+        let unwind_info = self.read_u32(entry_offset + 8)?;
+
+        for (idx, rva) in [begin, end, unwind_info].into_iter().enumerate() {
+            self.add_image_offset(section_id, offset + idx as u64 * 4, rva);
+        }
+
+        // A set lowest bit marks an RVA of another entry instead of unwind information.
+        if unwind_info & 1 != 0 {
+            let target = unwind_info & !1;
+            self.cls_output
+                .translate_field(target, RUNTIME_FUNCTION_SIZE)
+                .ok_or(GenerateUnwindInfoError::OutsideImage(target))?;
+            if self.visited.insert(target) {
+                let cls_section = self.cls_output.output_section_id;
+                self.convert_runtime_function(target, cls_section, target)?;
+            }
+            return Ok(());
+        }
+
+        if self.visited.insert(unwind_info) {
+            self.convert_unwind_info(begin, unwind_info)?;
+        }
+
+        Ok(())
+    }
+
+    /// Convert the RVAs within the unwind information at the given RVA, which belongs to the function at the given RVA.
+    fn convert_unwind_info(&mut self, func: u64, rva: u64) -> Result<(), GenerateUnwindInfoError> {
+        let header = self.read_u32(rva)?;
+        let version = (header & 0x7) as u8;
+        let flags = ((header >> 3) & 0x1f) as u8;
+        let num_codes = (header >> 16) & 0xff;
+        if version != 1 && version != 2 {
+            return Err(GenerateUnwindInfoError::UnsupportedVersion { version, rva });
+        }
+
+        if flags & (UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER) != 0 {
+            return Err(GenerateUnwindInfoError::ExceptionHandler(func));
+        }
+
+        if flags & UNW_FLAG_CHAININFO != 0 {
+            // The unwind codes are padded to an even number, and the chained entry follows them.
+            let chained = rva + 4 + num_codes.div_ceil(2) * 4;
+            let cls_section = self.cls_output.output_section_id;
+            self.convert_runtime_function(chained, cls_section, chained)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_u32(&self, rva: u64) -> Result<u64, GenerateUnwindInfoError> {
+        let offset = self
+            .cls_output
+            .translate_field(rva, 4)
+            .ok_or(GenerateUnwindInfoError::OutsideImage(rva))? as usize;
+        let data = self
+            .output
+            .section(self.cls_output.output_section_id)
+            .data();
+        let value = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        Ok(u64::from(value))
+    }
+
+    fn add_image_offset(&mut self, section_id: SectionId, offset: u64, rva: u64) {
+        let (symbol, addend) = self.cls_output.get_address_reference(rva);
+        self.output
+            .add_relocation(
+                section_id,
+                OutputRelocation {
+                    offset,
+                    size: 32,
+                    kind: RelocationKind::ImageOffset,
+                    encoding: RelocationEncoding::Generic,
+                    symbol,
+                    addend,
+                },
+            )
+            .unwrap();
+    }
+}
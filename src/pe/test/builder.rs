@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+
+use object::pe::{
+    IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DIRECTORY_ENTRY_IMPORT,
+    IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE, IMAGE_FILE_DLL, IMAGE_FILE_EXECUTABLE_IMAGE,
+    IMAGE_FILE_LARGE_ADDRESS_AWARE, IMAGE_FILE_MACHINE_AMD64, IMAGE_ORDINAL_FLAG64,
+    IMAGE_REL_BASED_DIR64, IMAGE_SUBSYSTEM_WINDOWS_GUI,
+};
+use object::read::pe::PeFile64;
+use object::write::pe::{NtHeaders, Writer};
+
+/// The preferred image base of the synthesized DLLs.
+pub const IMAGE_BASE: u64 = 0x1_8000_0000;
+
+/// Synthesizes a small x86_64 DLL for tests.
+///
+/// The DLL has the following layout:
+///
+/// - `.text` at 0x1000 holds `foo` at 0x1000, an unexported function at 0x1010, and the entry point at 0x1018.
+/// - `.rdata` at 0x2000 holds the export directory, the import directory, and the unwind information at 0x2200 and
+///   0x2210. The latter chains to the former.
+/// - `.data` at 0x3000 holds a pointer to 0x1010, which has a base relocation, the exported variable `counter` at
+///   0x3008, and the import address table at 0x3100 with a single slot for `GetLastError` from `KERNEL32.dll`.
+/// - `.pdata` at 0x4000 lists the functions at 0x1000 and 0x1010.
+///
+/// Besides `foo` and `counter`, the DLL exports 0x1010 by ordinal only and forwards `Forwarded` to `OTHER.Func`.
+#[derive(Clone, Debug, Default)]
+pub struct DllBuilder {
+    /// Import `GetLastError` by ordinal instead of by name.
+    pub import_by_ordinal: bool,
+
+    /// Give the function at 0x1000 an exception handler.
+    pub exception_handler: bool,
+
+    /// Additional data directories, each given as the index, the RVA and the size.
+    pub directories: Vec<(usize, u32, u32)>,
+}
+
+impl DllBuilder {
+    /// Build the DLL.
+    pub fn build(&self) -> Vec<u8> {
+        let mut text = vec![0xCC; 0x20];
+        text[0] = 0xC3;
+        text[0x10] = 0xC3;
+        text[0x18] = 0xC3;
+
+        let mut rdata = vec![0u8; 0x300];
+        // Export directory.
+        write_u32(&mut rdata, 0x0c, 0x2080);
+        write_u32(&mut rdata, 0x10, 1);
+        write_u32(&mut rdata, 0x14, 4);
+        write_u32(&mut rdata, 0x18, 3);
+        write_u32(&mut rdata, 0x1c, 0x2040);
+        write_u32(&mut rdata, 0x20, 0x2060);
+        write_u32(&mut rdata, 0x24, 0x2070);
+        for (idx, rva) in [0x1000, 0x3008, 0x1010, 0x20a0].into_iter().enumerate() {
+            write_u32(&mut rdata, 0x40 + idx * 4, rva);
+        }
+        for (idx, rva) in [0x2090, 0x20b0, 0x20c0].into_iter().enumerate() {
+            write_u32(&mut rdata, 0x60 + idx * 4, rva);
+        }
+        for (idx, ordinal) in [3u16, 1, 0].into_iter().enumerate() {
+            rdata[0x70 + idx * 2..0x72 + idx * 2].copy_from_slice(&ordinal.to_le_bytes());
+        }
+        for (offset, string) in [
+            (0x80, &b"test.dll"[..]),
+            (0x90, b"Forwarded"),
+            (0xa0, b"OTHER.Func"),
+            (0xb0, b"counter"),
+            (0xc0, b"foo"),
+        ] {
+            rdata[offset..offset + string.len()].copy_from_slice(string);
+        }
+
+        // Import directory.
+        let thunk = if self.import_by_ordinal {
+            IMAGE_ORDINAL_FLAG64 | 5
+        } else {
+            0x2190
+        };
+        write_u32(&mut rdata, 0x100, 0x2140);
+        write_u32(&mut rdata, 0x10c, 0x2180);
+        write_u32(&mut rdata, 0x110, 0x3100);
+        write_u64(&mut rdata, 0x140, thunk);
+        rdata[0x180..0x18c].copy_from_slice(b"KERNEL32.dll");
+        rdata[0x192..0x19e].copy_from_slice(b"GetLastError");
+
+        // Unwind information.
+        if self.exception_handler {
+            rdata[0x200] = 0x01 | (0x1 << 3);
+            write_u32(&mut rdata, 0x204, 0x1018);
+        } else {
+            rdata[0x200] = 0x01;
+        }
+        rdata[0x210] = 0x01 | (0x4 << 3);
+        rdata[0x212] = 1;
+        rdata[0x214] = 0x01;
+        rdata[0x215] = 0x02;
+        write_u32(&mut rdata, 0x218, 0x1000);
+        write_u32(&mut rdata, 0x21c, 0x1008);
+        write_u32(&mut rdata, 0x220, 0x2200);
+
+        let mut data = vec![0u8; 0x200];
+        write_u64(&mut data, 0, IMAGE_BASE + 0x1010);
+        write_u32(&mut data, 8, 42);
+        write_u64(&mut data, 0x100, thunk);
+
+        let mut pdata = vec![0u8; 24];
+        for (idx, rva) in [0x1000, 0x1008, 0x2200, 0x1010, 0x1018, 0x2210]
+            .into_iter()
+            .enumerate()
+        {
+            write_u32(&mut pdata, idx * 4, rva);
+        }
+
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(true, 0x1000, 0x200, &mut buffer);
+        writer.reserve_dos_header_and_stub();
+        writer.reserve_nt_headers(16);
+        writer.reserve_section_headers(5);
+        let text_range = writer.reserve_text_section(text.len() as u32);
+        let rdata_range = writer.reserve_rdata_section(rdata.len() as u32);
+        let data_range = writer.reserve_data_section(data.len() as u32, data.len() as u32);
+        let pdata_range = writer.reserve_pdata_section(pdata.len() as u32);
+        writer.add_reloc(0x3000, IMAGE_REL_BASED_DIR64);
+        writer.reserve_reloc_section();
+
+        writer.set_data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT, 0x2000, 0xd0);
+        writer.set_data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT, 0x2100, 0x28);
+        for (index, rva, size) in &self.directories {
+            writer.set_data_directory(*index, *rva, *size);
+        }
+
+        writer.write_dos_header_and_stub().unwrap();
+        writer.write_nt_headers(NtHeaders {
+            machine: IMAGE_FILE_MACHINE_AMD64,
+            time_date_stamp: 0,
+            characteristics: IMAGE_FILE_EXECUTABLE_IMAGE
+                | IMAGE_FILE_LARGE_ADDRESS_AWARE
+                | IMAGE_FILE_DLL,
+            major_linker_version: 0,
+            minor_linker_version: 0,
+            address_of_entry_point: 0x1018,
+            image_base: IMAGE_BASE,
+            major_operating_system_version: 6,
+            minor_operating_system_version: 0,
+            major_image_version: 0,
+            minor_image_version: 0,
+            major_subsystem_version: 6,
+            minor_subsystem_version: 0,
+            subsystem: IMAGE_SUBSYSTEM_WINDOWS_GUI,
+            dll_characteristics: IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE,
+            size_of_stack_reserve: 0x10_0000,
+            size_of_stack_commit: 0x1000,
+            size_of_heap_reserve: 0x10_0000,
+            size_of_heap_commit: 0x1000,
+        });
+        writer.write_section_headers();
+        writer.write_section(text_range.file_offset, &text);
+        writer.write_section(rdata_range.file_offset, &rdata);
+        writer.write_section(data_range.file_offset, &data);
+        writer.write_section(pdata_range.file_offset, &pdata);
+        writer.write_reloc_section();
+
+        buffer
+    }
+
+    /// Build the DLL and parse it.
+    pub fn build_pe64(&self) -> PeFile64<'static> {
+        let data = Vec::leak(self.build());
+        PeFile64::parse(&*data).unwrap()
+    }
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,166 @@
+pub mod builder;
+
+use object::read::RelocationTarget;
+use object::{
+    Object as _, ObjectSection as _, ObjectSymbol as _, RelocationKind, SymbolKind, SymbolScope,
+};
+
+/// Convert the given input, and parse the output as a COFF file.
+fn convert(builder: &builder::DllBuilder) -> object::File<'static> {
+    let output = crate::pe::convert(builder.build_pe64(), &Default::default())
+        .unwrap()
+        .write()
+        .unwrap();
+    object::File::parse(&*Vec::leak(output)).unwrap()
+}
+
+/// Get the offset, the kind, the target name and the implicit addend of each relocation in the given section of the
+/// output, ordered by offset.
+fn get_relocations(
+    file: &object::File,
+    section_name: &str,
+) -> Vec<(u64, RelocationKind, String, u64)> {
+    let section = file.section_by_name(section_name).unwrap();
+    let data = section.data().unwrap();
+    let mut relocs: Vec<_> = section
+        .relocations()
+        .map(|(offset, reloc)| {
+            let RelocationTarget::Symbol(sym) = reloc.target() else {
+                panic!("unexpected relocation target {:?}", reloc.target());
+            };
+            let target = String::from(file.symbol_by_index(sym).unwrap().name().unwrap());
+            let offset_usize = offset as usize;
+            let value = match reloc.size() {
+                32 => u64::from(u32::from_le_bytes(
+                    data[offset_usize..offset_usize + 4].try_into().unwrap(),
+                )),
+                64 => u64::from_le_bytes(data[offset_usize..offset_usize + 8].try_into().unwrap()),
+                size => panic!("unexpected relocation size {}", size),
+            };
+            (offset, reloc.kind(), target, value)
+        })
+        .collect();
+    relocs.sort_by_key(|reloc| reloc.0);
+    relocs
+}
+
+#[test]
+fn test_convert_dll() {
+    let output_file = convert(&builder::DllBuilder::default());
+
+    let soda = output_file.section_by_name(".soda").unwrap();
+    let soda_data = soda.data().unwrap();
+    assert_eq!(soda.size(), 0x6000);
+    assert_eq!(&soda_data[..2], b"MZ");
+    assert_eq!(soda_data[0x1000], 0xC3);
+
+    // The .reloc section is discardable and thus not copied.
+    assert!(soda_data[0x5000..].iter().all(|byte| *byte == 0));
+
+    let soda_name = String::from(".soda");
+    assert_eq!(
+        get_relocations(&output_file, ".soda"),
+        vec![
+            (
+                0x2218,
+                RelocationKind::ImageOffset,
+                soda_name.clone(),
+                0x1000
+            ),
+            (
+                0x221c,
+                RelocationKind::ImageOffset,
+                soda_name.clone(),
+                0x1008
+            ),
+            (
+                0x2220,
+                RelocationKind::ImageOffset,
+                soda_name.clone(),
+                0x2200
+            ),
+            (0x3000, RelocationKind::Absolute, soda_name.clone(), 0x1010),
+            (
+                0x3100,
+                RelocationKind::Absolute,
+                String::from("GetLastError"),
+                0
+            ),
+        ]
+    );
+
+    let pdata_relocs = get_relocations(&output_file, ".pdata");
+    assert_eq!(
+        pdata_relocs
+            .iter()
+            .map(|(offset, kind, target, value)| {
+                assert_eq!(*kind, RelocationKind::ImageOffset);
+                assert_eq!(*target, soda_name);
+                (*offset, *value)
+            })
+            .collect::<Vec<_>>(),
+        vec![
+            (0, 0x1000),
+            (4, 0x1008),
+            (8, 0x2200),
+            (12, 0x1010),
+            (16, 0x1018),
+            (20, 0x2210),
+        ]
+    );
+
+    let find_symbol = |name| output_file.symbols().find(|sym| sym.name() == Ok(name));
+    let symbol = |name| find_symbol(name).unwrap();
+    assert_eq!(symbol("foo").address(), 0x1000);
+    assert_eq!(symbol("foo").kind(), SymbolKind::Text);
+    assert!(symbol("foo").is_global());
+    assert_eq!(symbol("counter").address(), 0x3008);
+    assert_eq!(symbol("counter").kind(), SymbolKind::Data);
+    assert_eq!(symbol("__soda_dll_entry").address(), 0x1018);
+    assert_eq!(symbol("__soda_dll_entry").scope(), SymbolScope::Linkage);
+    assert!(symbol("GetLastError").is_undefined());
+    assert!(find_symbol("Forwarded").is_none());
+    assert!(find_symbol("__imp_GetLastError").is_none());
+}
+
+#[test]
+fn test_convert_dll_unsupported() {
+    use object::pe::{IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, IMAGE_DIRECTORY_ENTRY_TLS};
+
+    let convert_err = |builder: builder::DllBuilder| {
+        format!(
+            "{:#}",
+            anyhow::Error::from(
+                crate::pe::convert(builder.build_pe64(), &Default::default()).unwrap_err()
+            )
+        )
+    };
+
+    let err = convert_err(builder::DllBuilder {
+        import_by_ordinal: true,
+        ..Default::default()
+    });
+    assert!(err.contains("ordinal 5 from KERNEL32.dll"), "{}", err);
+
+    let err = convert_err(builder::DllBuilder {
+        exception_handler: true,
+        ..Default::default()
+    });
+    assert!(
+        err.contains("function at 0x1000 has an exception handler"),
+        "{}",
+        err
+    );
+
+    let err = convert_err(builder::DllBuilder {
+        directories: vec![(IMAGE_DIRECTORY_ENTRY_TLS, 0x2280, 40)],
+        ..Default::default()
+    });
+    assert!(err.contains("TLS directory"), "{}", err);
+
+    let err = convert_err(builder::DllBuilder {
+        directories: vec![(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, 0x2280, 32)],
+        ..Default::default()
+    });
+    assert!(err.contains("delay-load imports"), "{}", err);
+}
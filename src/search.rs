@@ -0,0 +1,170 @@
+//! Find shared libraries given by name in a list of directories, like the `-l` and `-L` options of linkers.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A shared library found by [`find_library`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundLibrary {
+    /// Path to the shared library.
+    pub path: PathBuf,
+
+    /// Paths to the other versions of the shared library in the same directory, which were not chosen since their
+    /// versions are lower.
+    pub other_versions: Vec<PathBuf>,
+}
+
+/// Find the shared library with the given name, which is given to `-l`, in the given directories.
+///
+/// The directories are searched in order, and the first directory that has a match wins. In each directory,
+/// `libNAME.so` is preferred. Otherwise, the versioned file `libNAME.so.VERSION` with the highest version is chosen,
+/// where the version consists of numbers separated by dots. A name that starts with a colon, like `:libfoo.so.1`, is the
+/// exact file name to find instead. Directories that do not exist are skipped.
+pub fn find_library(name: &str, search_dirs: &[PathBuf]) -> std::io::Result<Option<FoundLibrary>> {
+    for dir in search_dirs {
+        if let Some(file_name) = name.strip_prefix(':') {
+            let path = dir.join(file_name);
+            if path.is_file() {
+                return Ok(Some(FoundLibrary {
+                    path,
+                    other_versions: Vec::new(),
+                }));
+            }
+            continue;
+        }
+
+        let file_name = format!("lib{}.so", name);
+        let path = dir.join(&file_name);
+        if path.is_file() {
+            return Ok(Some(FoundLibrary {
+                path,
+                other_versions: Vec::new(),
+            }));
+        }
+
+        let mut versions = find_versioned_libraries(dir, &file_name)?;
+        versions.sort();
+        if let Some((_, path)) = versions.pop() {
+            return Ok(Some(FoundLibrary {
+                path,
+                other_versions: versions.into_iter().rev().map(|(_, path)| path).collect(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find the files in the given directory whose names are the given file name followed by a version, and get their
+/// versions and paths.
+fn find_versioned_libraries(
+    dir: &Path,
+    file_name: &str,
+) -> std::io::Result<Vec<(Vec<u64>, PathBuf)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut versions = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(version) = path
+            .file_name()
+            .and_then(|entry_name| entry_name.to_str())
+            .and_then(|entry_name| entry_name.strip_prefix(file_name))
+            .and_then(|suffix| suffix.strip_prefix('.'))
+            .and_then(parse_version)
+        else {
+            continue;
+        };
+        if path.is_file() {
+            versions.push((version, path));
+        }
+    }
+    Ok(versions)
+}
+
+/// Parse a version that consists of numbers separated by dots, like `1.12.0`.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{find_library, parse_version, FoundLibrary};
+
+    fn create_files(dir: &Path, names: &[&str]) {
+        std::fs::create_dir_all(dir).unwrap();
+        for name in names {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_find_library() {
+        let root = std::env::temp_dir().join(format!("soda-search-{}", std::process::id()));
+        let vendor = root.join("vendor");
+        let system = root.join("system");
+        create_files(
+            &vendor,
+            &[
+                "libfoo.so.1",
+                "libfoo.so.1.10",
+                "libfoo.so.1.9.2",
+                "libfoo.so.1.debug",
+                "libfoo-utils.so.2",
+            ],
+        );
+        create_files(
+            &system,
+            &["libfoo.so", "libbar.so", "libbar.so.3", "libbaz.a"],
+        );
+        std::fs::create_dir_all(vendor.join("libbar.so.4")).unwrap();
+        let search_dirs = [root.join("missing"), vendor.clone(), system.clone()];
+        let find = |name| find_library(name, &search_dirs).unwrap();
+
+        // The first directory with a match wins, and the highest version is chosen in it.
+        assert_eq!(
+            find("foo"),
+            Some(FoundLibrary {
+                path: vendor.join("libfoo.so.1.10"),
+                other_versions: vec![vendor.join("libfoo.so.1.9.2"), vendor.join("libfoo.so.1")],
+            })
+        );
+        assert_eq!(
+            find("foo-utils").map(|lib| lib.path),
+            Some(vendor.join("libfoo-utils.so.2"))
+        );
+
+        // The unversioned name is preferred, and directories are never matched.
+        assert_eq!(
+            find("bar"),
+            Some(FoundLibrary {
+                path: system.join("libbar.so"),
+                other_versions: Vec::new(),
+            })
+        );
+
+        assert_eq!(
+            find(":libfoo.so.1").map(|lib| lib.path),
+            Some(vendor.join("libfoo.so.1"))
+        );
+        assert_eq!(find("baz"), None);
+        assert_eq!(find_library("foo", &[] as &[PathBuf]).unwrap(), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1"), Some(vec![1]));
+        assert_eq!(parse_version("1.12.0"), Some(vec![1, 12, 0]));
+        assert_eq!(parse_version("1.debug"), None);
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("1..2"), None);
+    }
+}
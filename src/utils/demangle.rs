@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+
+use cpp_demangle::{DemangleOptions, Symbol as CppSymbol};
+
+thread_local! {
+    /// Whether the conversion running on the current thread demangles symbol names in messages.
+    static DEMANGLE_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Run the given function with demangling of symbol names in messages enabled or disabled on the current thread.
+///
+/// A conversion runs on the thread that starts it, so this scopes the setting to a single conversion without affecting
+/// the conversions running on other threads.
+pub fn with_demangle_enabled<T>(enabled: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DEMANGLE_ENABLED.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(DEMANGLE_ENABLED.with(|cell| cell.replace(enabled)));
+    f()
+}
+
+/// Demangle the given symbol name for display purposes.
+///
+/// Both Itanium C++ ABI and Rust mangled names are supported. A symbol version suffix such as `@GLIBC_2.14` is kept
+/// as-is. If demangling is disabled or the name cannot be demangled, the raw name is returned.
+pub fn demangle(name: &str) -> Cow<'_, str> {
+    if !DEMANGLE_ENABLED.with(Cell::get) {
+        return Cow::Borrowed(name);
+    }
+
+    let (base_name, version) = match name.find('@') {
+        Some(pos) => name.split_at(pos),
+        None => (name, ""),
+    };
+
+    match demangle_base_name(base_name) {
+        Some(demangled) => Cow::Owned(demangled + version),
+        None => Cow::Borrowed(name),
+    }
+}
+
+/// Demangle the given raw symbol name for display purposes. Invalid UTF-8 sequences are replaced first.
+pub fn demangle_bytes(name: &[u8]) -> String {
+    demangle(&String::from_utf8_lossy(name)).into_owned()
+}
+
+fn demangle_base_name(name: &str) -> Option<String> {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return Some(format!("{:#}", demangled));
+    }
+
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    CppSymbol::new(name)
+        .ok()?
+        .demangle(&DemangleOptions::default())
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{demangle, with_demangle_enabled};
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(demangle("_ZN3foo3barEv"), "foo::bar()");
+        assert_eq!(demangle("_ZN3foo3barEv@@VER_1"), "foo::bar()@@VER_1");
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h0123456789abcdefE"),
+            "core::fmt::write"
+        );
+        assert_eq!(demangle("memcpy@GLIBC_2.14"), "memcpy@GLIBC_2.14");
+        assert_eq!(demangle("_Zinvalid"), "_Zinvalid");
+
+        with_demangle_enabled(false, || {
+            assert_eq!(demangle("_ZN3foo3barEv"), "_ZN3foo3barEv");
+        });
+        assert_eq!(demangle("_ZN3foo3barEv"), "foo::bar()");
+    }
+}
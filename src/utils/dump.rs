@@ -0,0 +1,237 @@
+//! Readable textual renderings of object files.
+//!
+//! The renderings only depend on the contents of the file, and list sections, symbols and relocations in the order of
+//! the file, so they are stable enough to be compared against expected text.
+
+use std::fmt::Write as _;
+
+use object::read::elf::{ElfFile, FileHeader as ElfFileHeader, SectionHeader as _};
+use object::read::{File, Relocation, RelocationTarget, Section, SectionIndex, Symbol};
+use object::{
+    BinaryFormat, Object as _, ObjectSection as _, ObjectSymbol as _, ReadRef, RelocationKind,
+    SectionFlags, SymbolFlags, SymbolKind, SymbolSection,
+};
+
+use crate::utils::stringify::{
+    arch_to_str, binary_format_to_str, reloc_type_to_str, section_type_to_str, symbol_bind_to_str,
+    symbol_type_to_str,
+};
+
+/// Render the header, the sections, the symbols and the relocations of the given file.
+///
+/// The null section and the null symbol of ELF files are left out, and so are section symbols, since they only stand for
+/// their sections.
+pub fn render_object(file: &File<'_>) -> String {
+    let mut text = format!(
+        "{} {} {:?}\n",
+        binary_format_to_str(file.format()),
+        arch_to_str(file.architecture()),
+        file.kind()
+    );
+
+    text.push_str("sections:\n");
+    let is_elf = file.format() == BinaryFormat::Elf;
+    for section in file.sections() {
+        if !(is_elf && section.index().0 == 0) {
+            let _ = writeln!(text, "  {}", render_section(file, &section));
+        }
+    }
+
+    text.push_str("symbols:\n");
+    for symbol in file.symbols() {
+        if !matches!(symbol.kind(), SymbolKind::Null | SymbolKind::Section) {
+            let _ = writeln!(text, "  {}", render_symbol(file, &symbol));
+        }
+    }
+
+    for section in file.sections() {
+        let relocations = render_relocations(file, &section);
+        if !relocations.is_empty() {
+            let _ = writeln!(
+                text,
+                "relocations of {}:",
+                section_name(file, section.index())
+            );
+            for line in relocations.lines() {
+                let _ = writeln!(text, "  {}", line);
+            }
+        }
+    }
+
+    text
+}
+
+/// Render the index, the name, the kind, the ELF section type, the format-specific flags, the size and the alignment of
+/// the given section of the given file in a single line, such as `1 .text Text SHT_PROGBITS flags=0x6 size=0x10
+/// align=16`. The section type is left out for other formats.
+pub fn render_section(file: &File<'_>, section: &Section<'_, '_>) -> String {
+    let flags = match section.flags() {
+        SectionFlags::Elf { sh_flags } => sh_flags,
+        SectionFlags::MachO { flags } => u64::from(flags),
+        SectionFlags::Coff { characteristics } => u64::from(characteristics),
+        SectionFlags::Xcoff { s_flags } => u64::from(s_flags),
+        _ => 0,
+    };
+    let sh_type = get_elf_section_type(file, section.index())
+        .map(|sh_type| format!(" {}", section_type_to_str(sh_type)))
+        .unwrap_or_default();
+    format!(
+        "{} {} {:?}{} flags={:#x} size={:#x} align={}",
+        section.index().0,
+        section.name().unwrap_or("<invalid>"),
+        section.kind(),
+        sh_type,
+        flags,
+        section.size(),
+        section.align()
+    )
+}
+
+/// Render the relocations of the given section of the given file, one line per relocation, such as
+/// `0x8 Absolute/64 func+0x0`. The result is empty if the section has no relocation.
+pub fn render_relocations(file: &File<'_>, section: &Section<'_, '_>) -> String {
+    section
+        .relocations()
+        .map(|(offset, relocation)| {
+            format!("{:#x} {}\n", offset, render_relocation(file, &relocation))
+        })
+        .collect()
+}
+
+fn render_symbol(file: &File<'_>, symbol: &Symbol<'_, '_>) -> String {
+    let section = match symbol.section() {
+        SymbolSection::Undefined => String::from("UND"),
+        SymbolSection::Absolute => String::from("ABS"),
+        SymbolSection::Common => String::from("COM"),
+        SymbolSection::Section(index) => section_name(file, index),
+        _ => String::from("?"),
+    };
+    // ELF symbols are described by their bindings and types, which tell apart more cases than the generic kinds.
+    let kind = match symbol.flags() {
+        SymbolFlags::Elf { st_info, .. } => format!(
+            "{} {}",
+            symbol_bind_to_str(st_info >> 4),
+            symbol_type_to_str(st_info & 0xF)
+        ),
+        _ => format!(
+            "{:?}{}",
+            symbol.kind(),
+            if symbol.is_weak() { " weak" } else { "" }
+        ),
+    };
+    format!(
+        "{} {} {:?} {} section={} value={:#x} size={:#x}",
+        symbol.index().0,
+        symbol.name().unwrap_or("<invalid>"),
+        symbol.scope(),
+        kind,
+        section,
+        symbol.address(),
+        symbol.size()
+    )
+}
+
+fn render_relocation(file: &File<'_>, relocation: &Relocation) -> String {
+    let kind = match relocation.kind() {
+        RelocationKind::Elf(r_type) => reloc_type_to_str(file.architecture(), r_type).into_owned(),
+        kind => format!("{:?}/{}", kind, relocation.size()),
+    };
+    let target = match relocation.target() {
+        RelocationTarget::Symbol(index) => match file.symbol_by_index(index) {
+            Ok(symbol) if symbol.kind() == SymbolKind::Section => match symbol.section_index() {
+                Some(section_idx) => section_name(file, section_idx),
+                None => String::from("<invalid>"),
+            },
+            Ok(symbol) => String::from(symbol.name().unwrap_or("<invalid>")),
+            Err(_) => String::from("<invalid>"),
+        },
+        RelocationTarget::Section(index) => section_name(file, index),
+        RelocationTarget::Absolute => String::from("ABS"),
+        _ => String::from("?"),
+    };
+    let addend = relocation.addend();
+    let sign = if addend < 0 { '-' } else { '+' };
+    format!("{} {}{}{:#x}", kind, target, sign, addend.unsigned_abs())
+}
+
+fn get_elf_section_type(file: &File<'_>, index: SectionIndex) -> Option<u32> {
+    fn get<'d, E, R>(file: &ElfFile<'d, E, R>, index: SectionIndex) -> Option<u32>
+    where
+        E: ElfFileHeader,
+        R: ReadRef<'d>,
+    {
+        let endian = file.endian();
+        let sections = file.raw_header().sections(endian, file.data()).ok()?;
+        Some(sections.section(index).ok()?.sh_type(endian))
+    }
+
+    match file {
+        File::Elf32(elf_file) => get(elf_file, index),
+        File::Elf64(elf_file) => get(elf_file, index),
+        _ => None,
+    }
+}
+
+fn section_name(file: &File<'_>, index: SectionIndex) -> String {
+    file.section_by_index(index)
+        .ok()
+        .and_then(|section| section.name().ok().map(String::from))
+        .unwrap_or_else(|| format!("<section {}>", index.0))
+}
+
+#[cfg(test)]
+mod test {
+    use object::elf::{R_X86_64_64, R_X86_64_RELATIVE, STB_GLOBAL, STT_FUNC};
+    use object::Object as _;
+
+    use crate::elf::test::builder::BuilderSymbol;
+    use crate::ConvertOptions;
+
+    use super::{render_object, render_relocations, render_section};
+
+    #[test]
+    fn test_render_object() {
+        let mut builder = crate::elf::test::builder::minimal_dso();
+        let malloc =
+            builder.add_symbol(BuilderSymbol::new("malloc", STB_GLOBAL, STT_FUNC, 0, 0, 0));
+        builder.add_relocation(0x2000, R_X86_64_RELATIVE, 0, 0x1000);
+        builder.add_relocation(0x2008, R_X86_64_64, malloc, -8);
+        let options = ConvertOptions {
+            provenance_note: false,
+            ..Default::default()
+        };
+        let output = crate::convert_elf(&builder.build(), &options).unwrap();
+        let output_file = object::File::parse(&*output).unwrap();
+
+        assert_eq!(
+            render_object(&output_file),
+            "elf x86_64 Relocatable
+sections:
+  1 .soda Text SHT_PROGBITS flags=0x7 size=0x2010 align=16
+  2 .rela.soda Metadata SHT_RELA flags=0x40 size=0x30 align=8
+  3 .symtab Metadata SHT_SYMTAB flags=0x0 size=0xa8 align=8
+  4 .strtab Metadata SHT_STRTAB flags=0x0 size=0x36 align=1
+  5 .shstrtab Metadata SHT_STRTAB flags=0x0 size=0x26 align=1
+symbols:
+  2 malloc Unknown STB_GLOBAL STT_FUNC section=UND value=0x0 size=0x0
+  3 func Dynamic STB_GLOBAL STT_FUNC section=.soda value=0x1000 size=0x10
+  4 data Dynamic STB_GLOBAL STT_OBJECT section=.soda value=0x2000 size=0x8
+  5 __soda_image_start Dynamic STB_GLOBAL STT_NOTYPE section=.soda value=0x0 size=0x0
+  6 __soda_image_end Dynamic STB_GLOBAL STT_NOTYPE section=.soda value=0x2010 size=0x0
+relocations of .soda:
+  0x2000 Absolute/64 .soda+0x1000
+  0x2008 Absolute/64 malloc-0x8
+"
+        );
+
+        let soda = output_file.section_by_name(".soda").unwrap();
+        assert_eq!(
+            render_section(&output_file, &soda),
+            "1 .soda Text SHT_PROGBITS flags=0x7 size=0x2010 align=16"
+        );
+        assert_eq!(
+            render_relocations(&output_file, &soda),
+            "0x2000 Absolute/64 .soda+0x1000\n0x2008 Absolute/64 malloc-0x8\n"
+        );
+    }
+}
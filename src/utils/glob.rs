@@ -0,0 +1,53 @@
+/// Determine whether the given name matches the given glob pattern.
+///
+/// The pattern may contain `*`, which matches any sequence of bytes, and `?`, which matches any single byte. All other
+/// bytes match themselves.
+pub fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let mut p = 0;
+    let mut n = 0;
+
+    // Position of the last `*` in the pattern and the position in the name where it starts to match.
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    // Let the last `*` match one more byte.
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"foo", b"foo"));
+        assert!(!glob_match(b"foo", b"foobar"));
+        assert!(glob_match(b"foo*", b"foobar"));
+        assert!(glob_match(b"*bar", b"foobar"));
+        assert!(glob_match(b"f?o*r", b"foobar"));
+        assert!(glob_match(b"*o*a*", b"foobar"));
+        assert!(!glob_match(b"*baz", b"foobar"));
+        assert!(glob_match(b"*", b""));
+        assert!(!glob_match(b"?", b""));
+    }
+}
@@ -0,0 +1,226 @@
+/// A set of well-known entry points of a system library.
+#[derive(Clone, Copy, Debug)]
+pub struct KnownSymbolSet {
+    /// Name of the library.
+    pub library: &'static str,
+
+    /// Names of the entry points, without versions.
+    pub names: &'static [&'static str],
+}
+
+/// Well-known entry points of system libraries. Definitions of these symbols in a statically linked object interpose the
+/// system library for the whole program.
+pub const KNOWN_SYMBOL_SETS: &[KnownSymbolSet] = &[
+    KnownSymbolSet {
+        library: "libc",
+        names: &[
+            "malloc",
+            "free",
+            "calloc",
+            "realloc",
+            "reallocarray",
+            "memalign",
+            "posix_memalign",
+            "aligned_alloc",
+            "valloc",
+            "pvalloc",
+            "malloc_usable_size",
+            "memcpy",
+            "memmove",
+            "memset",
+            "memcmp",
+            "strlen",
+            "strcpy",
+            "strncpy",
+            "strcmp",
+            "strncmp",
+            "strdup",
+            "printf",
+            "fprintf",
+            "sprintf",
+            "snprintf",
+            "puts",
+            "fopen",
+            "fclose",
+            "open",
+            "close",
+            "read",
+            "write",
+            "mmap",
+            "munmap",
+            "exit",
+            "abort",
+            "atexit",
+            "__cxa_atexit",
+            "__cxa_finalize",
+            "dlopen",
+            "dlsym",
+            "dlclose",
+        ],
+    },
+    KnownSymbolSet {
+        library: "libm",
+        names: &[
+            "sin", "cos", "tan", "exp", "log", "log2", "log10", "pow", "sqrt", "floor", "ceil",
+            "fabs", "fmod",
+        ],
+    },
+    KnownSymbolSet {
+        library: "libpthread",
+        names: &[
+            "pthread_create",
+            "pthread_join",
+            "pthread_detach",
+            "pthread_once",
+            "pthread_key_create",
+            "pthread_getspecific",
+            "pthread_setspecific",
+            "pthread_mutex_lock",
+            "pthread_mutex_unlock",
+            "pthread_cond_wait",
+            "pthread_cond_signal",
+        ],
+    },
+    KnownSymbolSet {
+        library: "libstdc++",
+        names: &[
+            "_Znwm",
+            "_Znam",
+            "_ZnwmRKSt9nothrow_t",
+            "_ZnamRKSt9nothrow_t",
+            "_ZnwmSt11align_val_t",
+            "_ZnamSt11align_val_t",
+            "_ZdlPv",
+            "_ZdaPv",
+            "_ZdlPvm",
+            "_ZdaPvm",
+            "_ZdlPvSt11align_val_t",
+            "_ZdaPvSt11align_val_t",
+            "__cxa_allocate_exception",
+            "__cxa_free_exception",
+            "__cxa_throw",
+            "__cxa_begin_catch",
+            "__cxa_end_catch",
+            "__gxx_personality_v0",
+        ],
+    },
+];
+
+/// Find the system library that the symbol with the given name is a well-known entry point of. A symbol version suffix
+/// such as `@@GLIBC_2.2.5` in the name is ignored.
+pub fn find_known_library(name: &str) -> Option<&'static str> {
+    let base_name = name.split('@').next().unwrap_or(name);
+    KNOWN_SYMBOL_SETS
+        .iter()
+        .find(|set| set.names.contains(&base_name))
+        .map(|set| set.library)
+}
+
+/// A function of the dynamic loader or the C runtime that libraries call to find themselves as a loaded module.
+#[derive(Clone, Copy, Debug)]
+pub struct IntrospectionSymbol {
+    /// Name of the function, without version.
+    pub name: &'static str,
+
+    /// What goes wrong when a converted library calls the function, since it's no longer a separately loaded module.
+    pub caveat: &'static str,
+}
+
+/// Functions whose callers may misbehave once the calling library is converted and linked statically.
+pub const INTROSPECTION_SYMBOLS: &[IntrospectionSymbol] = &[
+    IntrospectionSymbol {
+        name: "dlopen",
+        caveat: "dlopen(NULL) gives the handle of the main program rather than of the library",
+    },
+    IntrospectionSymbol {
+        name: "dlmopen",
+        caveat: "the library is no longer a module that can be reopened in another namespace",
+    },
+    IntrospectionSymbol {
+        name: "dlsym",
+        caveat: "lookups through the handle of the library find the symbols of the main program instead",
+    },
+    IntrospectionSymbol {
+        name: "dlvsym",
+        caveat: "lookups through the handle of the library find the symbols of the main program instead",
+    },
+    IntrospectionSymbol {
+        name: "dladdr",
+        caveat: "addresses in the library are reported to belong to the main program",
+    },
+    IntrospectionSymbol {
+        name: "dladdr1",
+        caveat: "addresses in the library are reported to belong to the main program",
+    },
+    IntrospectionSymbol {
+        name: "dlinfo",
+        caveat: "the library has no link map entry of its own",
+    },
+    IntrospectionSymbol {
+        name: "dl_iterate_phdr",
+        caveat: "the library is no longer listed as a separate module",
+    },
+    IntrospectionSymbol {
+        name: "__cxa_thread_atexit_impl",
+        caveat: "thread-local destructors are registered against the image of the library, which the main program \
+                 never unloads",
+    },
+    IntrospectionSymbol {
+        name: "__cxa_thread_atexit",
+        caveat: "thread-local destructors are registered against the image of the library, which the main program \
+                 never unloads",
+    },
+];
+
+/// Find the introspection function with the given name. A symbol version suffix such as `@GLIBC_2.2.5` in the name is
+/// ignored.
+pub fn find_introspection_symbol(name: &str) -> Option<&'static IntrospectionSymbol> {
+    let base_name = name.split('@').next().unwrap_or(name);
+    INTROSPECTION_SYMBOLS
+        .iter()
+        .find(|sym| sym.name == base_name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_introspection_symbol, find_known_library, INTROSPECTION_SYMBOLS};
+
+    #[test]
+    fn test_find_known_library() {
+        assert_eq!(find_known_library("malloc"), Some("libc"));
+        assert_eq!(find_known_library("malloc@@GLIBC_2.2.5"), Some("libc"));
+        assert_eq!(find_known_library("_Znwm"), Some("libstdc++"));
+        assert_eq!(
+            find_known_library("pthread_create@GLIBC_2.34"),
+            Some("libpthread")
+        );
+        assert_eq!(find_known_library("my_malloc"), None);
+    }
+
+    #[test]
+    fn test_find_introspection_symbol() {
+        assert_eq!(find_introspection_symbol("dlopen").unwrap().name, "dlopen");
+        assert_eq!(
+            find_introspection_symbol("dl_iterate_phdr@GLIBC_2.2.5")
+                .unwrap()
+                .name,
+            "dl_iterate_phdr"
+        );
+        assert_eq!(
+            find_introspection_symbol("__cxa_thread_atexit_impl@@GLIBC_2.18")
+                .unwrap()
+                .name,
+            "__cxa_thread_atexit_impl"
+        );
+        assert!(find_introspection_symbol("dlopen_ext").is_none());
+        assert!(find_introspection_symbol("malloc").is_none());
+
+        // Every function is listed once and explained.
+        for (idx, sym) in INTROSPECTION_SYMBOLS.iter().enumerate() {
+            assert!(!sym.caveat.is_empty());
+            assert!(INTROSPECTION_SYMBOLS[..idx]
+                .iter()
+                .all(|other| other.name != sym.name));
+        }
+    }
+}
@@ -1 +1,7 @@
+pub mod demangle;
+#[cfg(test)]
+pub mod dump;
+pub mod glob;
+pub mod known_symbols;
+pub mod parallel;
 pub mod stringify;
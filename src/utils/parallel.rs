@@ -0,0 +1,41 @@
+//! Helpers that process independent items on several threads if the `parallel` feature is enabled, and one after
+//! another otherwise.
+//!
+//! The results are always in the order of the items, so the output of the conversion doesn't depend on the feature.
+
+/// Map each of the given items with the given function, and collect the results in the order of the items.
+pub fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Call the given function on each of the given items.
+pub fn for_each_mut<T, F>(items: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(&mut T) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter_mut().for_each(f);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter_mut().for_each(f);
+    }
+}
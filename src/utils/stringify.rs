@@ -1,21 +1,40 @@
-use object::{Architecture, BinaryFormat};
+use std::borrow::Cow;
+
+use object::{Architecture, BinaryFormat, RelocationKind};
+
+/// Expand to a match that gets the name of the given value among the given constants of `object::elf`. The constants
+/// must have distinct values.
+macro_rules! elf_const_name {
+    ($value:expr, [$($name:ident),* $(,)?]) => {
+        match $value {
+            $(object::elf::$name => Some(stringify!($name)),)*
+            _ => None,
+        }
+    };
+}
 
 /// Get the string representation of a `BinaryFormat` value.
-pub fn binary_format_to_str(f: BinaryFormat) -> &'static str {
-    match f {
+///
+/// Formats added by later versions of the object crate are given by their debug representations, like `unknown(Foo)`.
+pub fn binary_format_to_str(f: BinaryFormat) -> Cow<'static, str> {
+    let name = match f {
         BinaryFormat::Coff => "coff",
         BinaryFormat::Elf => "elf",
         BinaryFormat::MachO => "macho",
         BinaryFormat::Pe => "pe",
         BinaryFormat::Wasm => "wasm",
         BinaryFormat::Xcoff => "xcoff",
-        _ => unreachable!(),
-    }
+        f => return Cow::Owned(format!("unknown({:?})", f)),
+    };
+    Cow::Borrowed(name)
 }
 
 /// Get the string representation of an `Architecture` value.
-pub fn arch_to_str(arch: Architecture) -> &'static str {
-    match arch {
+///
+/// Architectures added by later versions of the object crate are given by their debug representations, like
+/// `unknown(Foo)`.
+pub fn arch_to_str(arch: Architecture) -> Cow<'static, str> {
+    let name = match arch {
         Architecture::Unknown => "unknown",
         Architecture::Aarch64 => "aarch64",
         Architecture::Aarch64_Ilp32 => "aarch64_ilp32",
@@ -41,6 +60,1294 @@ pub fn arch_to_str(arch: Architecture) -> &'static str {
         Architecture::Wasm32 => "wasm32",
         Architecture::Wasm64 => "wasm64",
         Architecture::Xtensa => "xtensa",
-        _ => unreachable!(),
+        arch => return Cow::Owned(format!("unknown({:?})", arch)),
+    };
+    Cow::Borrowed(name)
+}
+
+/// Get the name of the given ELF relocation type of the given architecture, like `R_X86_64_GLOB_DAT`.
+///
+/// The relocation types of MIPS64 are composed of up to three types in their low bytes, whose names are joined with `/`.
+/// Unknown types are given by their numbers in hex after the prefix of the architecture, like `R_X86_64_0xfff`, which
+/// tells them apart from types named by their sizes, like `R_X86_64_64`.
+pub fn reloc_type_to_str(arch: Architecture, r_type: u32) -> Cow<'static, str> {
+    if arch == Architecture::Mips64 && r_type > 0xff {
+        let names: Vec<_> = (0..3)
+            .map(|idx| reloc_type_to_str(arch, (r_type >> (8 * idx)) & 0xff))
+            .collect();
+        return Cow::Owned(names.join("/"));
+    }
+
+    match get_elf_reloc_type_name(arch, r_type) {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(format!("{}_{:#x}", get_elf_reloc_type_prefix(arch), r_type)),
+    }
+}
+
+/// Get the name of the given relocation kind of the given architecture. ELF relocation types are named by
+/// [`reloc_type_to_str`], and the generic kinds are given by their variant names.
+pub fn reloc_kind_to_str(arch: Architecture, kind: RelocationKind) -> Cow<'static, str> {
+    match kind {
+        RelocationKind::Elf(r_type) => reloc_type_to_str(arch, r_type),
+        kind => Cow::Owned(format!("{:?}", kind)),
+    }
+}
+
+/// Get the name of the given ELF section type, like `SHT_PROGBITS`.
+///
+/// Types in the ranges reserved for operating systems, processors and users are given by their offsets in the ranges,
+/// like `SHT_LOPROC+0x1`, since their names depend on the OS ABI or the architecture. Other unknown types are given by
+/// their numbers.
+pub fn section_type_to_str(sh_type: u32) -> Cow<'static, str> {
+    use object::elf::{SHT_LOOS, SHT_LOPROC, SHT_LOUSER};
+
+    // The object crate doesn't define this type yet.
+    if sh_type == 19 {
+        return Cow::Borrowed("SHT_RELR");
+    }
+
+    let name = elf_const_name!(
+        sh_type,
+        [
+            SHT_NULL,
+            SHT_PROGBITS,
+            SHT_SYMTAB,
+            SHT_STRTAB,
+            SHT_RELA,
+            SHT_HASH,
+            SHT_DYNAMIC,
+            SHT_NOTE,
+            SHT_NOBITS,
+            SHT_REL,
+            SHT_SHLIB,
+            SHT_DYNSYM,
+            SHT_INIT_ARRAY,
+            SHT_FINI_ARRAY,
+            SHT_PREINIT_ARRAY,
+            SHT_GROUP,
+            SHT_SYMTAB_SHNDX,
+            SHT_GNU_ATTRIBUTES,
+            SHT_GNU_HASH,
+            SHT_GNU_LIBLIST,
+            SHT_CHECKSUM,
+            SHT_GNU_VERDEF,
+            SHT_GNU_VERNEED,
+            SHT_GNU_VERSYM
+        ]
+    );
+    match name {
+        Some(name) => Cow::Borrowed(name),
+        None if sh_type >= SHT_LOUSER => {
+            Cow::Owned(format!("SHT_LOUSER+{:#x}", sh_type - SHT_LOUSER))
+        }
+        None if sh_type >= SHT_LOPROC => {
+            Cow::Owned(format!("SHT_LOPROC+{:#x}", sh_type - SHT_LOPROC))
+        }
+        None if sh_type >= SHT_LOOS => Cow::Owned(format!("SHT_LOOS+{:#x}", sh_type - SHT_LOOS)),
+        None => Cow::Owned(format!("SHT_{}", sh_type)),
+    }
+}
+
+/// Get the name of the given ELF symbol binding, like `STB_GLOBAL`. Unknown bindings are given by their numbers.
+pub fn symbol_bind_to_str(st_bind: u8) -> Cow<'static, str> {
+    match elf_const_name!(st_bind, [STB_LOCAL, STB_GLOBAL, STB_WEAK, STB_GNU_UNIQUE]) {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(format!("STB_{}", st_bind)),
+    }
+}
+
+/// Get the name of the given ELF symbol type, like `STT_FUNC`. Unknown types are given by their numbers.
+pub fn symbol_type_to_str(st_type: u8) -> Cow<'static, str> {
+    let name = elf_const_name!(
+        st_type,
+        [
+            STT_NOTYPE,
+            STT_OBJECT,
+            STT_FUNC,
+            STT_SECTION,
+            STT_FILE,
+            STT_COMMON,
+            STT_TLS,
+            STT_GNU_IFUNC
+        ]
+    );
+    match name {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(format!("STT_{}", st_type)),
+    }
+}
+
+/// Get the prefix of the names of the ELF relocation types of the given architecture, like `R_X86_64`.
+fn get_elf_reloc_type_prefix(arch: Architecture) -> Cow<'static, str> {
+    let prefix = match arch {
+        Architecture::X86_64 | Architecture::X86_64_X32 => "R_X86_64",
+        Architecture::I386 => "R_386",
+        Architecture::Arm => "R_ARM",
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => "R_AARCH64",
+        Architecture::LoongArch64 => "R_LARCH",
+        Architecture::Mips | Architecture::Mips64 => "R_MIPS",
+        Architecture::PowerPc => "R_PPC",
+        Architecture::PowerPc64 => "R_PPC64",
+        Architecture::Riscv32 | Architecture::Riscv64 => "R_RISCV",
+        Architecture::S390x => "R_390",
+        Architecture::Sparc64 => "R_SPARC",
+        arch => return Cow::Owned(format!("R_{}", arch_to_str(arch).to_uppercase())),
+    };
+    Cow::Borrowed(prefix)
+}
+
+fn get_elf_reloc_type_name(arch: Architecture, r_type: u32) -> Option<&'static str> {
+    // The object crate doesn't define these types yet.
+    match (arch, r_type) {
+        (Architecture::Riscv32 | Architecture::Riscv64, 58) => return Some("R_RISCV_IRELATIVE"),
+        (Architecture::Mips64, 128) => return Some("R_MIPS_IRELATIVE"),
+        _ => {}
+    }
+
+    // Aliases of the same types are left out.
+    match arch {
+        Architecture::X86_64 | Architecture::X86_64_X32 => elf_const_name!(
+            r_type,
+            [
+                R_X86_64_NONE,
+                R_X86_64_64,
+                R_X86_64_PC32,
+                R_X86_64_GOT32,
+                R_X86_64_PLT32,
+                R_X86_64_COPY,
+                R_X86_64_GLOB_DAT,
+                R_X86_64_JUMP_SLOT,
+                R_X86_64_RELATIVE,
+                R_X86_64_GOTPCREL,
+                R_X86_64_32,
+                R_X86_64_32S,
+                R_X86_64_16,
+                R_X86_64_PC16,
+                R_X86_64_8,
+                R_X86_64_PC8,
+                R_X86_64_DTPMOD64,
+                R_X86_64_DTPOFF64,
+                R_X86_64_TPOFF64,
+                R_X86_64_TLSGD,
+                R_X86_64_TLSLD,
+                R_X86_64_DTPOFF32,
+                R_X86_64_GOTTPOFF,
+                R_X86_64_TPOFF32,
+                R_X86_64_PC64,
+                R_X86_64_GOTOFF64,
+                R_X86_64_GOTPC32,
+                R_X86_64_GOT64,
+                R_X86_64_GOTPCREL64,
+                R_X86_64_GOTPC64,
+                R_X86_64_GOTPLT64,
+                R_X86_64_PLTOFF64,
+                R_X86_64_SIZE32,
+                R_X86_64_SIZE64,
+                R_X86_64_GOTPC32_TLSDESC,
+                R_X86_64_TLSDESC_CALL,
+                R_X86_64_TLSDESC,
+                R_X86_64_IRELATIVE,
+                R_X86_64_RELATIVE64,
+                R_X86_64_GOTPCRELX,
+                R_X86_64_REX_GOTPCRELX
+            ]
+        ),
+        Architecture::I386 => elf_const_name!(
+            r_type,
+            [
+                R_386_NONE,
+                R_386_32,
+                R_386_PC32,
+                R_386_GOT32,
+                R_386_PLT32,
+                R_386_COPY,
+                R_386_GLOB_DAT,
+                R_386_JMP_SLOT,
+                R_386_RELATIVE,
+                R_386_GOTOFF,
+                R_386_GOTPC,
+                R_386_32PLT,
+                R_386_TLS_TPOFF,
+                R_386_TLS_IE,
+                R_386_TLS_GOTIE,
+                R_386_TLS_LE,
+                R_386_TLS_GD,
+                R_386_TLS_LDM,
+                R_386_16,
+                R_386_PC16,
+                R_386_8,
+                R_386_PC8,
+                R_386_TLS_GD_32,
+                R_386_TLS_GD_PUSH,
+                R_386_TLS_GD_CALL,
+                R_386_TLS_GD_POP,
+                R_386_TLS_LDM_32,
+                R_386_TLS_LDM_PUSH,
+                R_386_TLS_LDM_CALL,
+                R_386_TLS_LDM_POP,
+                R_386_TLS_LDO_32,
+                R_386_TLS_IE_32,
+                R_386_TLS_LE_32,
+                R_386_TLS_DTPMOD32,
+                R_386_TLS_DTPOFF32,
+                R_386_TLS_TPOFF32,
+                R_386_SIZE32,
+                R_386_TLS_GOTDESC,
+                R_386_TLS_DESC_CALL,
+                R_386_TLS_DESC,
+                R_386_IRELATIVE,
+                R_386_GOT32X
+            ]
+        ),
+        Architecture::Arm => elf_const_name!(
+            r_type,
+            [
+                R_ARM_NONE,
+                R_ARM_PC24,
+                R_ARM_ABS32,
+                R_ARM_REL32,
+                R_ARM_PC13,
+                R_ARM_ABS16,
+                R_ARM_ABS12,
+                R_ARM_THM_ABS5,
+                R_ARM_ABS8,
+                R_ARM_SBREL32,
+                R_ARM_THM_PC22,
+                R_ARM_THM_PC8,
+                R_ARM_AMP_VCALL9,
+                R_ARM_SWI24,
+                R_ARM_THM_SWI8,
+                R_ARM_XPC25,
+                R_ARM_THM_XPC22,
+                R_ARM_TLS_DTPMOD32,
+                R_ARM_TLS_DTPOFF32,
+                R_ARM_TLS_TPOFF32,
+                R_ARM_COPY,
+                R_ARM_GLOB_DAT,
+                R_ARM_JUMP_SLOT,
+                R_ARM_RELATIVE,
+                R_ARM_GOTOFF,
+                R_ARM_GOTPC,
+                R_ARM_GOT32,
+                R_ARM_PLT32,
+                R_ARM_CALL,
+                R_ARM_JUMP24,
+                R_ARM_THM_JUMP24,
+                R_ARM_BASE_ABS,
+                R_ARM_ALU_PCREL_7_0,
+                R_ARM_ALU_PCREL_15_8,
+                R_ARM_ALU_PCREL_23_15,
+                R_ARM_LDR_SBREL_11_0,
+                R_ARM_ALU_SBREL_19_12,
+                R_ARM_ALU_SBREL_27_20,
+                R_ARM_TARGET1,
+                R_ARM_SBREL31,
+                R_ARM_V4BX,
+                R_ARM_TARGET2,
+                R_ARM_PREL31,
+                R_ARM_MOVW_ABS_NC,
+                R_ARM_MOVT_ABS,
+                R_ARM_MOVW_PREL_NC,
+                R_ARM_MOVT_PREL,
+                R_ARM_THM_MOVW_ABS_NC,
+                R_ARM_THM_MOVT_ABS,
+                R_ARM_THM_MOVW_PREL_NC,
+                R_ARM_THM_MOVT_PREL,
+                R_ARM_THM_JUMP19,
+                R_ARM_THM_JUMP6,
+                R_ARM_THM_ALU_PREL_11_0,
+                R_ARM_THM_PC12,
+                R_ARM_ABS32_NOI,
+                R_ARM_REL32_NOI,
+                R_ARM_ALU_PC_G0_NC,
+                R_ARM_ALU_PC_G0,
+                R_ARM_ALU_PC_G1_NC,
+                R_ARM_ALU_PC_G1,
+                R_ARM_ALU_PC_G2,
+                R_ARM_LDR_PC_G1,
+                R_ARM_LDR_PC_G2,
+                R_ARM_LDRS_PC_G0,
+                R_ARM_LDRS_PC_G1,
+                R_ARM_LDRS_PC_G2,
+                R_ARM_LDC_PC_G0,
+                R_ARM_LDC_PC_G1,
+                R_ARM_LDC_PC_G2,
+                R_ARM_ALU_SB_G0_NC,
+                R_ARM_ALU_SB_G0,
+                R_ARM_ALU_SB_G1_NC,
+                R_ARM_ALU_SB_G1,
+                R_ARM_ALU_SB_G2,
+                R_ARM_LDR_SB_G0,
+                R_ARM_LDR_SB_G1,
+                R_ARM_LDR_SB_G2,
+                R_ARM_LDRS_SB_G0,
+                R_ARM_LDRS_SB_G1,
+                R_ARM_LDRS_SB_G2,
+                R_ARM_LDC_SB_G0,
+                R_ARM_LDC_SB_G1,
+                R_ARM_LDC_SB_G2,
+                R_ARM_MOVW_BREL_NC,
+                R_ARM_MOVT_BREL,
+                R_ARM_MOVW_BREL,
+                R_ARM_THM_MOVW_BREL_NC,
+                R_ARM_THM_MOVT_BREL,
+                R_ARM_THM_MOVW_BREL,
+                R_ARM_TLS_GOTDESC,
+                R_ARM_TLS_CALL,
+                R_ARM_TLS_DESCSEQ,
+                R_ARM_THM_TLS_CALL,
+                R_ARM_PLT32_ABS,
+                R_ARM_GOT_ABS,
+                R_ARM_GOT_PREL,
+                R_ARM_GOT_BREL12,
+                R_ARM_GOTOFF12,
+                R_ARM_GOTRELAX,
+                R_ARM_GNU_VTENTRY,
+                R_ARM_GNU_VTINHERIT,
+                R_ARM_THM_PC11,
+                R_ARM_THM_PC9,
+                R_ARM_TLS_GD32,
+                R_ARM_TLS_LDM32,
+                R_ARM_TLS_LDO32,
+                R_ARM_TLS_IE32,
+                R_ARM_TLS_LE32,
+                R_ARM_TLS_LDO12,
+                R_ARM_TLS_LE12,
+                R_ARM_TLS_IE12GP,
+                R_ARM_ME_TOO,
+                R_ARM_THM_TLS_DESCSEQ,
+                R_ARM_THM_TLS_DESCSEQ32,
+                R_ARM_THM_GOT_BREL12,
+                R_ARM_IRELATIVE,
+                R_ARM_RXPC25,
+                R_ARM_RSBREL32,
+                R_ARM_THM_RPC22,
+                R_ARM_RREL32,
+                R_ARM_RABS22,
+                R_ARM_RPC24,
+                R_ARM_RBASE
+            ]
+        ),
+        Architecture::Aarch64 => elf_const_name!(
+            r_type,
+            [
+                R_AARCH64_NONE,
+                R_AARCH64_ABS64,
+                R_AARCH64_ABS32,
+                R_AARCH64_ABS16,
+                R_AARCH64_PREL64,
+                R_AARCH64_PREL32,
+                R_AARCH64_PREL16,
+                R_AARCH64_MOVW_UABS_G0,
+                R_AARCH64_MOVW_UABS_G0_NC,
+                R_AARCH64_MOVW_UABS_G1,
+                R_AARCH64_MOVW_UABS_G1_NC,
+                R_AARCH64_MOVW_UABS_G2,
+                R_AARCH64_MOVW_UABS_G2_NC,
+                R_AARCH64_MOVW_UABS_G3,
+                R_AARCH64_MOVW_SABS_G0,
+                R_AARCH64_MOVW_SABS_G1,
+                R_AARCH64_MOVW_SABS_G2,
+                R_AARCH64_LD_PREL_LO19,
+                R_AARCH64_ADR_PREL_LO21,
+                R_AARCH64_ADR_PREL_PG_HI21,
+                R_AARCH64_ADR_PREL_PG_HI21_NC,
+                R_AARCH64_ADD_ABS_LO12_NC,
+                R_AARCH64_LDST8_ABS_LO12_NC,
+                R_AARCH64_TSTBR14,
+                R_AARCH64_CONDBR19,
+                R_AARCH64_JUMP26,
+                R_AARCH64_CALL26,
+                R_AARCH64_LDST16_ABS_LO12_NC,
+                R_AARCH64_LDST32_ABS_LO12_NC,
+                R_AARCH64_LDST64_ABS_LO12_NC,
+                R_AARCH64_MOVW_PREL_G0,
+                R_AARCH64_MOVW_PREL_G0_NC,
+                R_AARCH64_MOVW_PREL_G1,
+                R_AARCH64_MOVW_PREL_G1_NC,
+                R_AARCH64_MOVW_PREL_G2,
+                R_AARCH64_MOVW_PREL_G2_NC,
+                R_AARCH64_MOVW_PREL_G3,
+                R_AARCH64_LDST128_ABS_LO12_NC,
+                R_AARCH64_MOVW_GOTOFF_G0,
+                R_AARCH64_MOVW_GOTOFF_G0_NC,
+                R_AARCH64_MOVW_GOTOFF_G1,
+                R_AARCH64_MOVW_GOTOFF_G1_NC,
+                R_AARCH64_MOVW_GOTOFF_G2,
+                R_AARCH64_MOVW_GOTOFF_G2_NC,
+                R_AARCH64_MOVW_GOTOFF_G3,
+                R_AARCH64_GOTREL64,
+                R_AARCH64_GOTREL32,
+                R_AARCH64_GOT_LD_PREL19,
+                R_AARCH64_LD64_GOTOFF_LO15,
+                R_AARCH64_ADR_GOT_PAGE,
+                R_AARCH64_LD64_GOT_LO12_NC,
+                R_AARCH64_LD64_GOTPAGE_LO15,
+                R_AARCH64_TLSGD_ADR_PREL21,
+                R_AARCH64_TLSGD_ADR_PAGE21,
+                R_AARCH64_TLSGD_ADD_LO12_NC,
+                R_AARCH64_TLSGD_MOVW_G1,
+                R_AARCH64_TLSGD_MOVW_G0_NC,
+                R_AARCH64_TLSLD_ADR_PREL21,
+                R_AARCH64_TLSLD_ADR_PAGE21,
+                R_AARCH64_TLSLD_ADD_LO12_NC,
+                R_AARCH64_TLSLD_MOVW_G1,
+                R_AARCH64_TLSLD_MOVW_G0_NC,
+                R_AARCH64_TLSLD_LD_PREL19,
+                R_AARCH64_TLSLD_MOVW_DTPREL_G2,
+                R_AARCH64_TLSLD_MOVW_DTPREL_G1,
+                R_AARCH64_TLSLD_MOVW_DTPREL_G1_NC,
+                R_AARCH64_TLSLD_MOVW_DTPREL_G0,
+                R_AARCH64_TLSLD_MOVW_DTPREL_G0_NC,
+                R_AARCH64_TLSLD_ADD_DTPREL_HI12,
+                R_AARCH64_TLSLD_ADD_DTPREL_LO12,
+                R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC,
+                R_AARCH64_TLSLD_LDST8_DTPREL_LO12,
+                R_AARCH64_TLSLD_LDST8_DTPREL_LO12_NC,
+                R_AARCH64_TLSLD_LDST16_DTPREL_LO12,
+                R_AARCH64_TLSLD_LDST16_DTPREL_LO12_NC,
+                R_AARCH64_TLSLD_LDST32_DTPREL_LO12,
+                R_AARCH64_TLSLD_LDST32_DTPREL_LO12_NC,
+                R_AARCH64_TLSLD_LDST64_DTPREL_LO12,
+                R_AARCH64_TLSLD_LDST64_DTPREL_LO12_NC,
+                R_AARCH64_TLSIE_MOVW_GOTTPREL_G1,
+                R_AARCH64_TLSIE_MOVW_GOTTPREL_G0_NC,
+                R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21,
+                R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC,
+                R_AARCH64_TLSIE_LD_GOTTPREL_PREL19,
+                R_AARCH64_TLSLE_MOVW_TPREL_G2,
+                R_AARCH64_TLSLE_MOVW_TPREL_G1,
+                R_AARCH64_TLSLE_MOVW_TPREL_G1_NC,
+                R_AARCH64_TLSLE_MOVW_TPREL_G0,
+                R_AARCH64_TLSLE_MOVW_TPREL_G0_NC,
+                R_AARCH64_TLSLE_ADD_TPREL_HI12,
+                R_AARCH64_TLSLE_ADD_TPREL_LO12,
+                R_AARCH64_TLSLE_ADD_TPREL_LO12_NC,
+                R_AARCH64_TLSLE_LDST8_TPREL_LO12,
+                R_AARCH64_TLSLE_LDST8_TPREL_LO12_NC,
+                R_AARCH64_TLSLE_LDST16_TPREL_LO12,
+                R_AARCH64_TLSLE_LDST16_TPREL_LO12_NC,
+                R_AARCH64_TLSLE_LDST32_TPREL_LO12,
+                R_AARCH64_TLSLE_LDST32_TPREL_LO12_NC,
+                R_AARCH64_TLSLE_LDST64_TPREL_LO12,
+                R_AARCH64_TLSLE_LDST64_TPREL_LO12_NC,
+                R_AARCH64_TLSDESC_LD_PREL19,
+                R_AARCH64_TLSDESC_ADR_PREL21,
+                R_AARCH64_TLSDESC_ADR_PAGE21,
+                R_AARCH64_TLSDESC_LD64_LO12,
+                R_AARCH64_TLSDESC_ADD_LO12,
+                R_AARCH64_TLSDESC_OFF_G1,
+                R_AARCH64_TLSDESC_OFF_G0_NC,
+                R_AARCH64_TLSDESC_LDR,
+                R_AARCH64_TLSDESC_ADD,
+                R_AARCH64_TLSDESC_CALL,
+                R_AARCH64_TLSLE_LDST128_TPREL_LO12,
+                R_AARCH64_TLSLE_LDST128_TPREL_LO12_NC,
+                R_AARCH64_TLSLD_LDST128_DTPREL_LO12,
+                R_AARCH64_TLSLD_LDST128_DTPREL_LO12_NC,
+                R_AARCH64_COPY,
+                R_AARCH64_GLOB_DAT,
+                R_AARCH64_JUMP_SLOT,
+                R_AARCH64_RELATIVE,
+                R_AARCH64_TLS_DTPMOD,
+                R_AARCH64_TLS_DTPREL,
+                R_AARCH64_TLS_TPREL,
+                R_AARCH64_TLSDESC,
+                R_AARCH64_IRELATIVE
+            ]
+        ),
+        Architecture::LoongArch64 => elf_const_name!(
+            r_type,
+            [
+                R_LARCH_NONE,
+                R_LARCH_32,
+                R_LARCH_64,
+                R_LARCH_RELATIVE,
+                R_LARCH_COPY,
+                R_LARCH_JUMP_SLOT,
+                R_LARCH_TLS_DTPMOD32,
+                R_LARCH_TLS_DTPMOD64,
+                R_LARCH_TLS_DTPREL32,
+                R_LARCH_TLS_DTPREL64,
+                R_LARCH_TLS_TPREL32,
+                R_LARCH_TLS_TPREL64,
+                R_LARCH_IRELATIVE,
+                R_LARCH_MARK_LA,
+                R_LARCH_MARK_PCREL,
+                R_LARCH_SOP_PUSH_PCREL,
+                R_LARCH_SOP_PUSH_ABSOLUTE,
+                R_LARCH_SOP_PUSH_DUP,
+                R_LARCH_SOP_PUSH_GPREL,
+                R_LARCH_SOP_PUSH_TLS_TPREL,
+                R_LARCH_SOP_PUSH_TLS_GOT,
+                R_LARCH_SOP_PUSH_TLS_GD,
+                R_LARCH_SOP_PUSH_PLT_PCREL,
+                R_LARCH_SOP_ASSERT,
+                R_LARCH_SOP_NOT,
+                R_LARCH_SOP_SUB,
+                R_LARCH_SOP_SL,
+                R_LARCH_SOP_SR,
+                R_LARCH_SOP_ADD,
+                R_LARCH_SOP_AND,
+                R_LARCH_SOP_IF_ELSE,
+                R_LARCH_SOP_POP_32_S_10_5,
+                R_LARCH_SOP_POP_32_U_10_12,
+                R_LARCH_SOP_POP_32_S_10_12,
+                R_LARCH_SOP_POP_32_S_10_16,
+                R_LARCH_SOP_POP_32_S_10_16_S2,
+                R_LARCH_SOP_POP_32_S_5_20,
+                R_LARCH_SOP_POP_32_S_0_5_10_16_S2,
+                R_LARCH_SOP_POP_32_S_0_10_10_16_S2,
+                R_LARCH_SOP_POP_32_U,
+                R_LARCH_ADD8,
+                R_LARCH_ADD16,
+                R_LARCH_ADD24,
+                R_LARCH_ADD32,
+                R_LARCH_ADD64,
+                R_LARCH_SUB8,
+                R_LARCH_SUB16,
+                R_LARCH_SUB24,
+                R_LARCH_SUB32,
+                R_LARCH_SUB64,
+                R_LARCH_GNU_VTINHERIT,
+                R_LARCH_GNU_VTENTRY,
+                R_LARCH_B16,
+                R_LARCH_B21,
+                R_LARCH_B26,
+                R_LARCH_ABS_HI20,
+                R_LARCH_ABS_LO12,
+                R_LARCH_ABS64_LO20,
+                R_LARCH_ABS64_HI12,
+                R_LARCH_PCALA_HI20,
+                R_LARCH_PCALA_LO12,
+                R_LARCH_PCALA64_LO20,
+                R_LARCH_PCALA64_HI12,
+                R_LARCH_GOT_PC_HI20,
+                R_LARCH_GOT_PC_LO12,
+                R_LARCH_GOT64_PC_LO20,
+                R_LARCH_GOT64_PC_HI12,
+                R_LARCH_GOT_HI20,
+                R_LARCH_GOT_LO12,
+                R_LARCH_GOT64_LO20,
+                R_LARCH_GOT64_HI12,
+                R_LARCH_TLS_LE_HI20,
+                R_LARCH_TLS_LE_LO12,
+                R_LARCH_TLS_LE64_LO20,
+                R_LARCH_TLS_LE64_HI12,
+                R_LARCH_TLS_IE_PC_HI20,
+                R_LARCH_TLS_IE_PC_LO12,
+                R_LARCH_TLS_IE64_PC_LO20,
+                R_LARCH_TLS_IE64_PC_HI12,
+                R_LARCH_TLS_IE_HI20,
+                R_LARCH_TLS_IE_LO12,
+                R_LARCH_TLS_IE64_LO20,
+                R_LARCH_TLS_IE64_HI12,
+                R_LARCH_TLS_LD_PC_HI20,
+                R_LARCH_TLS_LD_HI20,
+                R_LARCH_TLS_GD_PC_HI20,
+                R_LARCH_TLS_GD_HI20,
+                R_LARCH_32_PCREL,
+                R_LARCH_RELAX
+            ]
+        ),
+        Architecture::PowerPc64 => elf_const_name!(
+            r_type,
+            [
+                R_PPC64_NONE,
+                R_PPC64_ADDR32,
+                R_PPC64_ADDR24,
+                R_PPC64_ADDR16,
+                R_PPC64_ADDR16_LO,
+                R_PPC64_ADDR16_HI,
+                R_PPC64_ADDR16_HA,
+                R_PPC64_ADDR14,
+                R_PPC64_ADDR14_BRTAKEN,
+                R_PPC64_ADDR14_BRNTAKEN,
+                R_PPC64_REL24,
+                R_PPC64_REL14,
+                R_PPC64_REL14_BRTAKEN,
+                R_PPC64_REL14_BRNTAKEN,
+                R_PPC64_GOT16,
+                R_PPC64_GOT16_LO,
+                R_PPC64_GOT16_HI,
+                R_PPC64_GOT16_HA,
+                R_PPC64_COPY,
+                R_PPC64_GLOB_DAT,
+                R_PPC64_JMP_SLOT,
+                R_PPC64_RELATIVE,
+                R_PPC64_UADDR32,
+                R_PPC64_UADDR16,
+                R_PPC64_REL32,
+                R_PPC64_PLT32,
+                R_PPC64_PLTREL32,
+                R_PPC64_PLT16_LO,
+                R_PPC64_PLT16_HI,
+                R_PPC64_PLT16_HA,
+                R_PPC64_SECTOFF,
+                R_PPC64_SECTOFF_LO,
+                R_PPC64_SECTOFF_HI,
+                R_PPC64_SECTOFF_HA,
+                R_PPC64_ADDR30,
+                R_PPC64_ADDR64,
+                R_PPC64_ADDR16_HIGHER,
+                R_PPC64_ADDR16_HIGHERA,
+                R_PPC64_ADDR16_HIGHEST,
+                R_PPC64_ADDR16_HIGHESTA,
+                R_PPC64_UADDR64,
+                R_PPC64_REL64,
+                R_PPC64_PLT64,
+                R_PPC64_PLTREL64,
+                R_PPC64_TOC16,
+                R_PPC64_TOC16_LO,
+                R_PPC64_TOC16_HI,
+                R_PPC64_TOC16_HA,
+                R_PPC64_TOC,
+                R_PPC64_PLTGOT16,
+                R_PPC64_PLTGOT16_LO,
+                R_PPC64_PLTGOT16_HI,
+                R_PPC64_PLTGOT16_HA,
+                R_PPC64_ADDR16_DS,
+                R_PPC64_ADDR16_LO_DS,
+                R_PPC64_GOT16_DS,
+                R_PPC64_GOT16_LO_DS,
+                R_PPC64_PLT16_LO_DS,
+                R_PPC64_SECTOFF_DS,
+                R_PPC64_SECTOFF_LO_DS,
+                R_PPC64_TOC16_DS,
+                R_PPC64_TOC16_LO_DS,
+                R_PPC64_PLTGOT16_DS,
+                R_PPC64_PLTGOT16_LO_DS,
+                R_PPC64_TLS,
+                R_PPC64_DTPMOD64,
+                R_PPC64_TPREL16,
+                R_PPC64_TPREL16_LO,
+                R_PPC64_TPREL16_HI,
+                R_PPC64_TPREL16_HA,
+                R_PPC64_TPREL64,
+                R_PPC64_DTPREL16,
+                R_PPC64_DTPREL16_LO,
+                R_PPC64_DTPREL16_HI,
+                R_PPC64_DTPREL16_HA,
+                R_PPC64_DTPREL64,
+                R_PPC64_GOT_TLSGD16,
+                R_PPC64_GOT_TLSGD16_LO,
+                R_PPC64_GOT_TLSGD16_HI,
+                R_PPC64_GOT_TLSGD16_HA,
+                R_PPC64_GOT_TLSLD16,
+                R_PPC64_GOT_TLSLD16_LO,
+                R_PPC64_GOT_TLSLD16_HI,
+                R_PPC64_GOT_TLSLD16_HA,
+                R_PPC64_GOT_TPREL16_DS,
+                R_PPC64_GOT_TPREL16_LO_DS,
+                R_PPC64_GOT_TPREL16_HI,
+                R_PPC64_GOT_TPREL16_HA,
+                R_PPC64_GOT_DTPREL16_DS,
+                R_PPC64_GOT_DTPREL16_LO_DS,
+                R_PPC64_GOT_DTPREL16_HI,
+                R_PPC64_GOT_DTPREL16_HA,
+                R_PPC64_TPREL16_DS,
+                R_PPC64_TPREL16_LO_DS,
+                R_PPC64_TPREL16_HIGHER,
+                R_PPC64_TPREL16_HIGHERA,
+                R_PPC64_TPREL16_HIGHEST,
+                R_PPC64_TPREL16_HIGHESTA,
+                R_PPC64_DTPREL16_DS,
+                R_PPC64_DTPREL16_LO_DS,
+                R_PPC64_DTPREL16_HIGHER,
+                R_PPC64_DTPREL16_HIGHERA,
+                R_PPC64_DTPREL16_HIGHEST,
+                R_PPC64_DTPREL16_HIGHESTA,
+                R_PPC64_TLSGD,
+                R_PPC64_TLSLD,
+                R_PPC64_TOCSAVE,
+                R_PPC64_ADDR16_HIGH,
+                R_PPC64_ADDR16_HIGHA,
+                R_PPC64_TPREL16_HIGH,
+                R_PPC64_TPREL16_HIGHA,
+                R_PPC64_DTPREL16_HIGH,
+                R_PPC64_DTPREL16_HIGHA,
+                R_PPC64_JMP_IREL,
+                R_PPC64_IRELATIVE,
+                R_PPC64_REL16,
+                R_PPC64_REL16_LO,
+                R_PPC64_REL16_HI,
+                R_PPC64_REL16_HA
+            ]
+        ),
+        Architecture::Riscv32 | Architecture::Riscv64 => elf_const_name!(
+            r_type,
+            [
+                R_RISCV_NONE,
+                R_RISCV_32,
+                R_RISCV_64,
+                R_RISCV_RELATIVE,
+                R_RISCV_COPY,
+                R_RISCV_JUMP_SLOT,
+                R_RISCV_TLS_DTPMOD32,
+                R_RISCV_TLS_DTPMOD64,
+                R_RISCV_TLS_DTPREL32,
+                R_RISCV_TLS_DTPREL64,
+                R_RISCV_TLS_TPREL32,
+                R_RISCV_TLS_TPREL64,
+                R_RISCV_BRANCH,
+                R_RISCV_JAL,
+                R_RISCV_CALL,
+                R_RISCV_CALL_PLT,
+                R_RISCV_GOT_HI20,
+                R_RISCV_TLS_GOT_HI20,
+                R_RISCV_TLS_GD_HI20,
+                R_RISCV_PCREL_HI20,
+                R_RISCV_PCREL_LO12_I,
+                R_RISCV_PCREL_LO12_S,
+                R_RISCV_HI20,
+                R_RISCV_LO12_I,
+                R_RISCV_LO12_S,
+                R_RISCV_TPREL_HI20,
+                R_RISCV_TPREL_LO12_I,
+                R_RISCV_TPREL_LO12_S,
+                R_RISCV_TPREL_ADD,
+                R_RISCV_ADD8,
+                R_RISCV_ADD16,
+                R_RISCV_ADD32,
+                R_RISCV_ADD64,
+                R_RISCV_SUB8,
+                R_RISCV_SUB16,
+                R_RISCV_SUB32,
+                R_RISCV_SUB64,
+                R_RISCV_GNU_VTINHERIT,
+                R_RISCV_GNU_VTENTRY,
+                R_RISCV_ALIGN,
+                R_RISCV_RVC_BRANCH,
+                R_RISCV_RVC_JUMP,
+                R_RISCV_RVC_LUI,
+                R_RISCV_GPREL_I,
+                R_RISCV_GPREL_S,
+                R_RISCV_TPREL_I,
+                R_RISCV_TPREL_S,
+                R_RISCV_RELAX,
+                R_RISCV_SUB6,
+                R_RISCV_SET6,
+                R_RISCV_SET8,
+                R_RISCV_SET16,
+                R_RISCV_SET32,
+                R_RISCV_32_PCREL
+            ]
+        ),
+        Architecture::S390x => elf_const_name!(
+            r_type,
+            [
+                R_390_NONE,
+                R_390_8,
+                R_390_12,
+                R_390_16,
+                R_390_32,
+                R_390_PC32,
+                R_390_GOT12,
+                R_390_GOT32,
+                R_390_PLT32,
+                R_390_COPY,
+                R_390_GLOB_DAT,
+                R_390_JMP_SLOT,
+                R_390_RELATIVE,
+                R_390_GOTOFF32,
+                R_390_GOTPC,
+                R_390_GOT16,
+                R_390_PC16,
+                R_390_PC16DBL,
+                R_390_PLT16DBL,
+                R_390_PC32DBL,
+                R_390_PLT32DBL,
+                R_390_GOTPCDBL,
+                R_390_64,
+                R_390_PC64,
+                R_390_GOT64,
+                R_390_PLT64,
+                R_390_GOTENT,
+                R_390_GOTOFF16,
+                R_390_GOTOFF64,
+                R_390_GOTPLT12,
+                R_390_GOTPLT16,
+                R_390_GOTPLT32,
+                R_390_GOTPLT64,
+                R_390_GOTPLTENT,
+                R_390_PLTOFF16,
+                R_390_PLTOFF32,
+                R_390_PLTOFF64,
+                R_390_TLS_LOAD,
+                R_390_TLS_GDCALL,
+                R_390_TLS_LDCALL,
+                R_390_TLS_GD32,
+                R_390_TLS_GD64,
+                R_390_TLS_GOTIE12,
+                R_390_TLS_GOTIE32,
+                R_390_TLS_GOTIE64,
+                R_390_TLS_LDM32,
+                R_390_TLS_LDM64,
+                R_390_TLS_IE32,
+                R_390_TLS_IE64,
+                R_390_TLS_IEENT,
+                R_390_TLS_LE32,
+                R_390_TLS_LE64,
+                R_390_TLS_LDO32,
+                R_390_TLS_LDO64,
+                R_390_TLS_DTPMOD,
+                R_390_TLS_DTPOFF,
+                R_390_TLS_TPOFF,
+                R_390_20,
+                R_390_GOT20,
+                R_390_GOTPLT20,
+                R_390_TLS_GOTIE20,
+                R_390_IRELATIVE
+            ]
+        ),
+        Architecture::Mips64 => elf_const_name!(
+            r_type,
+            [
+                R_MIPS_NONE,
+                R_MIPS_16,
+                R_MIPS_32,
+                R_MIPS_REL32,
+                R_MIPS_26,
+                R_MIPS_HI16,
+                R_MIPS_LO16,
+                R_MIPS_GPREL16,
+                R_MIPS_LITERAL,
+                R_MIPS_GOT16,
+                R_MIPS_PC16,
+                R_MIPS_CALL16,
+                R_MIPS_GPREL32,
+                R_MIPS_SHIFT5,
+                R_MIPS_SHIFT6,
+                R_MIPS_64,
+                R_MIPS_GOT_DISP,
+                R_MIPS_GOT_PAGE,
+                R_MIPS_GOT_OFST,
+                R_MIPS_GOT_HI16,
+                R_MIPS_GOT_LO16,
+                R_MIPS_SUB,
+                R_MIPS_INSERT_A,
+                R_MIPS_INSERT_B,
+                R_MIPS_DELETE,
+                R_MIPS_HIGHER,
+                R_MIPS_HIGHEST,
+                R_MIPS_CALL_HI16,
+                R_MIPS_CALL_LO16,
+                R_MIPS_SCN_DISP,
+                R_MIPS_REL16,
+                R_MIPS_ADD_IMMEDIATE,
+                R_MIPS_PJUMP,
+                R_MIPS_RELGOT,
+                R_MIPS_JALR,
+                R_MIPS_TLS_DTPMOD32,
+                R_MIPS_TLS_DTPREL32,
+                R_MIPS_TLS_DTPMOD64,
+                R_MIPS_TLS_DTPREL64,
+                R_MIPS_TLS_GD,
+                R_MIPS_TLS_LDM,
+                R_MIPS_TLS_DTPREL_HI16,
+                R_MIPS_TLS_DTPREL_LO16,
+                R_MIPS_TLS_GOTTPREL,
+                R_MIPS_TLS_TPREL32,
+                R_MIPS_TLS_TPREL64,
+                R_MIPS_TLS_TPREL_HI16,
+                R_MIPS_TLS_TPREL_LO16,
+                R_MIPS_GLOB_DAT,
+                R_MIPS_COPY,
+                R_MIPS_JUMP_SLOT
+            ]
+        ),
+        _ => None,
+    }
+}
+
+/// Format the given string as a JSON string literal, with quotes, backslashes and control characters escaped.
+pub fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use object::elf::{SHT_GNU_HASH, SHT_LOPROC, SHT_PROGBITS, STB_GNU_UNIQUE, STT_GNU_IFUNC};
+    use object::{Architecture, BinaryFormat, RelocationKind};
+
+    use super::{
+        arch_to_str, binary_format_to_str, get_elf_reloc_type_prefix, json_string,
+        reloc_kind_to_str, reloc_type_to_str, section_type_to_str, symbol_bind_to_str,
+        symbol_type_to_str,
+    };
+
+    /// Assert that each of the given relocation types of `object::elf` is named after its constant.
+    macro_rules! assert_reloc_names {
+        ($arch:expr, [$($name:ident),* $(,)?]) => {
+            $(assert_eq!(reloc_type_to_str($arch, object::elf::$name), stringify!($name));)*
+        };
+    }
+
+    #[test]
+    fn test_reloc_type_to_str_table() {
+        // The relocation types that the conversion handles.
+        assert_reloc_names!(
+            Architecture::X86_64,
+            [
+                R_X86_64_16,
+                R_X86_64_32,
+                R_X86_64_32S,
+                R_X86_64_64,
+                R_X86_64_8,
+                R_X86_64_COPY,
+                R_X86_64_DTPMOD64,
+                R_X86_64_DTPOFF64,
+                R_X86_64_GLOB_DAT,
+                R_X86_64_GOT32,
+                R_X86_64_GOTPCREL,
+                R_X86_64_IRELATIVE,
+                R_X86_64_JUMP_SLOT,
+                R_X86_64_PC16,
+                R_X86_64_PC32,
+                R_X86_64_PC8,
+                R_X86_64_PLT32,
+                R_X86_64_RELATIVE,
+                R_X86_64_SIZE32,
+                R_X86_64_SIZE64,
+                R_X86_64_TLSDESC,
+                R_X86_64_TPOFF32,
+                R_X86_64_TPOFF64,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::I386,
+            [
+                R_386_16,
+                R_386_32,
+                R_386_8,
+                R_386_COPY,
+                R_386_GLOB_DAT,
+                R_386_GOT32,
+                R_386_GOTOFF,
+                R_386_GOTPC,
+                R_386_IRELATIVE,
+                R_386_JMP_SLOT,
+                R_386_PC16,
+                R_386_PC32,
+                R_386_PC8,
+                R_386_PLT32,
+                R_386_RELATIVE,
+                R_386_TLS_LE,
+                R_386_TLS_LE_32,
+                R_386_TLS_TPOFF,
+                R_386_TLS_TPOFF32,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::Arm,
+            [
+                R_ARM_ABS32,
+                R_ARM_COPY,
+                R_ARM_GLOB_DAT,
+                R_ARM_IRELATIVE,
+                R_ARM_JUMP_SLOT,
+                R_ARM_RELATIVE,
+                R_ARM_TLS_DTPMOD32,
+                R_ARM_TLS_DTPOFF32,
+                R_ARM_TLS_LDO32,
+                R_ARM_TLS_LE32,
+                R_ARM_TLS_TPOFF32,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::Aarch64,
+            [
+                R_AARCH64_ABS16,
+                R_AARCH64_ABS32,
+                R_AARCH64_ABS64,
+                R_AARCH64_ADR_PREL_PG_HI21,
+                R_AARCH64_CALL26,
+                R_AARCH64_COPY,
+                R_AARCH64_GLOB_DAT,
+                R_AARCH64_IRELATIVE,
+                R_AARCH64_JUMP_SLOT,
+                R_AARCH64_LDST64_ABS_LO12_NC,
+                R_AARCH64_PREL16,
+                R_AARCH64_PREL32,
+                R_AARCH64_PREL64,
+                R_AARCH64_RELATIVE,
+                R_AARCH64_TLS_DTPMOD,
+                R_AARCH64_TLS_DTPREL,
+                R_AARCH64_TLS_TPREL,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::Riscv64,
+            [
+                R_RISCV_32,
+                R_RISCV_64,
+                R_RISCV_COPY,
+                R_RISCV_JUMP_SLOT,
+                R_RISCV_RELATIVE,
+                R_RISCV_TLS_DTPMOD64,
+                R_RISCV_TLS_DTPREL64,
+                R_RISCV_TLS_TPREL64,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::LoongArch64,
+            [
+                R_LARCH_32,
+                R_LARCH_32_PCREL,
+                R_LARCH_64,
+                R_LARCH_B16,
+                R_LARCH_B21,
+                R_LARCH_B26,
+                R_LARCH_COPY,
+                R_LARCH_IRELATIVE,
+                R_LARCH_JUMP_SLOT,
+                R_LARCH_PCALA_HI20,
+                R_LARCH_PCALA_LO12,
+                R_LARCH_RELATIVE,
+                R_LARCH_TLS_DTPMOD64,
+                R_LARCH_TLS_DTPREL64,
+                R_LARCH_TLS_TPREL64,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::PowerPc64,
+            [
+                R_PPC64_ADDR32,
+                R_PPC64_ADDR64,
+                R_PPC64_COPY,
+                R_PPC64_DTPMOD64,
+                R_PPC64_DTPREL64,
+                R_PPC64_GLOB_DAT,
+                R_PPC64_IRELATIVE,
+                R_PPC64_JMP_SLOT,
+                R_PPC64_RELATIVE,
+                R_PPC64_TPREL64,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::S390x,
+            [
+                R_390_16,
+                R_390_32,
+                R_390_64,
+                R_390_8,
+                R_390_COPY,
+                R_390_GLOB_DAT,
+                R_390_GOT16,
+                R_390_GOT32,
+                R_390_GOT64,
+                R_390_GOTENT,
+                R_390_GOTOFF16,
+                R_390_GOTOFF32,
+                R_390_GOTOFF64,
+                R_390_GOTPC,
+                R_390_GOTPCDBL,
+                R_390_IRELATIVE,
+                R_390_JMP_SLOT,
+                R_390_PC16,
+                R_390_PC16DBL,
+                R_390_PC32,
+                R_390_PC32DBL,
+                R_390_PC64,
+                R_390_PLT16DBL,
+                R_390_PLT32DBL,
+                R_390_RELATIVE,
+                R_390_TLS_DTPMOD,
+                R_390_TLS_DTPOFF,
+                R_390_TLS_LDO64,
+                R_390_TLS_LE64,
+                R_390_TLS_TPOFF,
+            ]
+        );
+        assert_reloc_names!(
+            Architecture::Mips64,
+            [
+                R_MIPS_16,
+                R_MIPS_32,
+                R_MIPS_64,
+                R_MIPS_COPY,
+                R_MIPS_JUMP_SLOT,
+                R_MIPS_NONE,
+                R_MIPS_REL32,
+                R_MIPS_TLS_DTPMOD64,
+                R_MIPS_TLS_DTPREL64,
+                R_MIPS_TLS_TPREL64,
+            ]
+        );
+        assert_reloc_names!(Architecture::Riscv32, [R_RISCV_32, R_RISCV_RELATIVE]);
+        assert_reloc_names!(Architecture::X86_64_X32, [R_X86_64_32, R_X86_64_RELATIVE]);
+    }
+
+    #[test]
+    fn test_reloc_type_to_str_prefixes() {
+        // The names of the types of each architecture are distinct and start with its prefix, so no table is mixed up
+        // with the table of another architecture.
+        for arch in [
+            Architecture::X86_64,
+            Architecture::I386,
+            Architecture::Arm,
+            Architecture::Aarch64,
+            Architecture::LoongArch64,
+            Architecture::PowerPc64,
+            Architecture::Riscv64,
+            Architecture::S390x,
+            Architecture::Mips64,
+        ] {
+            let prefix = format!("{}_", get_elf_reloc_type_prefix(arch));
+            let mut names = HashSet::new();
+            for r_type in 0..=0xff {
+                let name = reloc_type_to_str(arch, r_type);
+                assert!(name.starts_with(&prefix), "{} of {:?}", name, arch);
+                assert!(names.insert(name.clone()), "{} of {:?}", name, arch);
+            }
+        }
+
+        assert_eq!(reloc_type_to_str(Architecture::X86_64, 64), "R_X86_64_0x40");
+        assert_eq!(
+            reloc_type_to_str(Architecture::Riscv32, 0xff),
+            "R_RISCV_0xff"
+        );
+        assert_eq!(
+            reloc_type_to_str(Architecture::Sparc64, 0xff),
+            "R_SPARC_0xff"
+        );
+        assert_eq!(reloc_type_to_str(Architecture::Bpf, 0xff), "R_BPF_0xff");
+    }
+
+    #[test]
+    fn test_reloc_kind_to_str() {
+        assert_eq!(
+            reloc_kind_to_str(Architecture::Aarch64, RelocationKind::Elf(1027)),
+            "R_AARCH64_RELATIVE"
+        );
+        assert_eq!(
+            reloc_kind_to_str(Architecture::Aarch64, RelocationKind::Absolute),
+            "Absolute"
+        );
+    }
+
+    #[test]
+    fn test_section_type_to_str() {
+        let table = [
+            (SHT_PROGBITS, "SHT_PROGBITS"),
+            (object::elf::SHT_NOBITS, "SHT_NOBITS"),
+            (object::elf::SHT_INIT_ARRAY, "SHT_INIT_ARRAY"),
+            (object::elf::SHT_SYMTAB_SHNDX, "SHT_SYMTAB_SHNDX"),
+            (19, "SHT_RELR"),
+            (SHT_GNU_HASH, "SHT_GNU_HASH"),
+            (object::elf::SHT_GNU_VERSYM, "SHT_GNU_VERSYM"),
+            (20, "SHT_20"),
+            (0x6000_0010, "SHT_LOOS+0x10"),
+            (SHT_LOPROC + 1, "SHT_LOPROC+0x1"),
+            (0x8000_0002, "SHT_LOUSER+0x2"),
+        ];
+        for (sh_type, name) in table {
+            assert_eq!(section_type_to_str(sh_type), name);
+        }
+    }
+
+    #[test]
+    fn test_symbol_bind_and_type_to_str() {
+        let binds = [
+            (object::elf::STB_LOCAL, "STB_LOCAL"),
+            (object::elf::STB_GLOBAL, "STB_GLOBAL"),
+            (object::elf::STB_WEAK, "STB_WEAK"),
+            (STB_GNU_UNIQUE, "STB_GNU_UNIQUE"),
+            (13, "STB_13"),
+        ];
+        for (st_bind, name) in binds {
+            assert_eq!(symbol_bind_to_str(st_bind), name);
+        }
+
+        let types = [
+            (object::elf::STT_NOTYPE, "STT_NOTYPE"),
+            (object::elf::STT_OBJECT, "STT_OBJECT"),
+            (object::elf::STT_FUNC, "STT_FUNC"),
+            (object::elf::STT_SECTION, "STT_SECTION"),
+            (object::elf::STT_FILE, "STT_FILE"),
+            (object::elf::STT_COMMON, "STT_COMMON"),
+            (object::elf::STT_TLS, "STT_TLS"),
+            (STT_GNU_IFUNC, "STT_GNU_IFUNC"),
+            (13, "STT_13"),
+        ];
+        for (st_type, name) in types {
+            assert_eq!(symbol_type_to_str(st_type), name);
+        }
+    }
+
+    #[test]
+    fn test_arch_to_str() {
+        // Every architecture of the object crate has its own name. Architectures of later versions fall back to
+        // `unknown(...)` rather than panic.
+        let archs = [
+            Architecture::Aarch64,
+            Architecture::Aarch64_Ilp32,
+            Architecture::Arm,
+            Architecture::Avr,
+            Architecture::Bpf,
+            Architecture::Csky,
+            Architecture::I386,
+            Architecture::X86_64,
+            Architecture::X86_64_X32,
+            Architecture::Hexagon,
+            Architecture::LoongArch64,
+            Architecture::Mips,
+            Architecture::Mips64,
+            Architecture::Msp430,
+            Architecture::PowerPc,
+            Architecture::PowerPc64,
+            Architecture::Riscv32,
+            Architecture::Riscv64,
+            Architecture::S390x,
+            Architecture::Sbf,
+            Architecture::Sparc64,
+            Architecture::Wasm32,
+            Architecture::Wasm64,
+            Architecture::Xtensa,
+        ];
+        let mut names = HashSet::new();
+        for arch in archs {
+            let name = arch_to_str(arch);
+            assert!(!name.starts_with("unknown"), "{:?}", arch);
+            assert!(names.insert(name), "{:?}", arch);
+        }
+        assert_eq!(arch_to_str(Architecture::Unknown), "unknown");
+    }
+
+    #[test]
+    fn test_binary_format_to_str() {
+        let formats = [
+            (BinaryFormat::Coff, "coff"),
+            (BinaryFormat::Elf, "elf"),
+            (BinaryFormat::MachO, "macho"),
+            (BinaryFormat::Pe, "pe"),
+            (BinaryFormat::Wasm, "wasm"),
+            (BinaryFormat::Xcoff, "xcoff"),
+        ];
+        for (format, name) in formats {
+            assert_eq!(binary_format_to_str(format), name);
+        }
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("foo@@VER_1"), "\"foo@@VER_1\"");
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
     }
 }
@@ -0,0 +1,39 @@
+//! End-to-end test of linking an object written by the raw output backend.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+/// The library has a constructor, pointers in data and calls through the PLT.
+const LIB_SOURCE: &str = r#"
+static int value;
+static int *value_ptr = &value;
+
+__attribute__((constructor)) static void init(void) { *value_ptr = 40; }
+
+int add(int a, int b) { return a + b; }
+
+int get_value(void) { return add(*value_ptr, 2); }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+int get_value(void);
+int main(void) { return get_value() == 42 ? 0 : 1; }
+"#;
+
+#[test]
+fn test_link_raw_backend_output() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("backend");
+    let lib = common::compile_shared_lib(&dir, "backend", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &["--backend=raw"]);
+
+    // The program exits successfully only if the constructor runs and the relocations are applied.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
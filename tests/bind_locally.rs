@@ -0,0 +1,46 @@
+//! End-to-end test of linking a converted object whose references to its own definitions are bound to the image, with
+//! a program that defines a symbol of the same name.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+const LIB_SOURCE: &str = r#"
+int helper(void) { return 1; }
+int get(void) { return helper() + 10; }
+"#;
+
+/// The program defines helper as well. The definition of the program comes first on the command line, so the linker
+/// picks it for references by symbol.
+fn main_source(expected: i32) -> String {
+    format!(
+        r#"
+int helper(void) {{ return 2; }}
+int get(void);
+int main(void) {{ return get() == {} ? 0 : 1; }}
+"#,
+        expected
+    )
+}
+
+#[test]
+fn test_link_object_bound_locally() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("bind_locally");
+    let lib = common::compile_shared_lib(&dir, "bind_locally", LIB_SOURCE);
+    let link_args = ["-Wl,--allow-multiple-definition"];
+
+    // The call to helper is interposed by the definition of the program by default.
+    let object = common::convert(&dir, &lib, &[]);
+    common::link_and_run_with_args(&dir, &main_source(12), &[&object], &link_args);
+
+    // The call to helper goes to the definition of the library if it is bound locally.
+    let object = common::convert(&dir, &lib, &["--bind-locally"]);
+    common::link_and_run_with_args(&dir, &main_source(11), &[&object], &link_args);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
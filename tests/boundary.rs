@@ -0,0 +1,45 @@
+//! End-to-end test of the symbols that mark the boundaries of the converted image.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use object::{Object as _, ObjectSection as _};
+
+const LIB_SOURCE: &str = r#"
+int value = 42;
+int get_value(void) { return value; }
+"#;
+
+#[test]
+fn test_image_boundary_symbols() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("boundary");
+    let lib = common::compile_shared_lib(&dir, "boundary", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &[]);
+
+    // The image size is the size of the output section that holds the copied image.
+    let object_data = std::fs::read(&object).unwrap();
+    let object_file = object::File::parse(&*object_data).unwrap();
+    let image_size = object_file.section_by_name(".soda").unwrap().size();
+    assert!(image_size > 0);
+
+    // The program exits successfully only if the distance between the symbols is the image size.
+    let main_source = format!(
+        r#"
+extern char __soda_image_start[], __soda_image_end[];
+int get_value(void);
+int main(void) {{
+    return get_value() == 42 && __soda_image_end - __soda_image_start == {} ? 0 : 1;
+}}
+"#,
+        image_size
+    );
+    common::link_and_run(&dir, &main_source, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
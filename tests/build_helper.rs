@@ -0,0 +1,27 @@
+//! End-to-end test of the build script helper, through the example crate under `examples/vendored`, which converts a
+//! vendor shared library in its build script and calls into it in its tests.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_example_crate() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    // The example crate has its own target directory below ours, so that its dependencies are only built once.
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    common::run(
+        Command::new(env!("CARGO"))
+            .args(["test", "--offline", "--manifest-path"])
+            .arg(manifest_dir.join("examples/vendored/Cargo.toml"))
+            .arg("--target-dir")
+            .arg(manifest_dir.join("target/examples")),
+    );
+}
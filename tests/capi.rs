@@ -0,0 +1,135 @@
+//! End-to-end test of the C ABI of the `capi` feature, through a C program linked against the cdylib.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+#![cfg(feature = "capi")]
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+const MAIN_SOURCE: &str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "soda.h"
+
+#define CHECK(cond)                                                         \
+    do {                                                                     \
+        if (!(cond)) {                                                       \
+            fprintf(stderr, "line %d: check failed: %s\n", __LINE__, #cond); \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+static unsigned char *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    if (!file) {
+        return NULL;
+    }
+    fseek(file, 0, SEEK_END);
+    *len = (size_t)ftell(file);
+    fseek(file, 0, SEEK_SET);
+    unsigned char *data = malloc(*len);
+    if (fread(data, 1, *len, file) != *len) {
+        free(data);
+        data = NULL;
+    }
+    fclose(file);
+    return data;
+}
+
+int main(int argc, char **argv) {
+    size_t input_len;
+    unsigned char *input = read_file(argv[1], &input_len);
+    CHECK(input != NULL);
+
+    /* A successful conversion hands over the output and reports no error. */
+    uint8_t *out = (uint8_t *)1;
+    size_t out_len = 0;
+    char *err_msg = (char *)1;
+    CHECK(soda_convert(input, input_len, NULL, &out, &out_len, &err_msg) == SODA_OK);
+    CHECK(out != NULL && out_len > 4 && memcmp(out, "\177ELF", 4) == 0);
+    CHECK(err_msg == NULL);
+    soda_free_buffer(out, out_len);
+
+    /* Options select the output section and the boundary symbols. */
+    soda_options opts;
+    memset(&opts, 0, sizeof(opts));
+    opts.image_start_symbol = "";
+    opts.section_name = ".vendor_image";
+    CHECK(soda_convert(input, input_len, &opts, &out, &out_len, NULL) == SODA_OK);
+    CHECK(memmem(out, out_len, ".vendor_image", 13) != NULL);
+    CHECK(memmem(out, out_len, "__soda_image_start", 18) == NULL);
+    CHECK(memmem(out, out_len, "__soda_image_end", 16) != NULL);
+    soda_free_buffer(out, out_len);
+
+    /* A failed conversion hands over the error message only. */
+    CHECK(soda_convert((const uint8_t *)"garbage", 7, NULL, &out, &out_len, &err_msg) ==
+          SODA_ERROR_INVALID_INPUT);
+    CHECK(out == NULL && out_len == 0);
+    CHECK(err_msg != NULL && strstr(err_msg, "cannot parse input") != NULL);
+    soda_free_error(err_msg);
+
+    opts.only_export_file = "/nonexistent/exports.txt";
+    CHECK(soda_convert(input, input_len, &opts, &out, &out_len, &err_msg) == SODA_ERROR_FAILURE);
+    CHECK(out == NULL);
+    CHECK(err_msg != NULL && strstr(err_msg, "/nonexistent/exports.txt") != NULL);
+    soda_free_error(err_msg);
+
+    /* Missing output pointers are rejected, and errors may be ignored. */
+    CHECK(soda_convert(input, input_len, NULL, NULL, &out_len, NULL) == SODA_ERROR_FAILURE);
+
+    /* Releasing null pointers is a no-op. */
+    soda_free_buffer(NULL, 0);
+    soda_free_error(NULL);
+
+    free(input);
+    return 0;
+}
+"#;
+
+#[test]
+fn test_capi() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    // Cargo does not build the cdylib for tests, and builds without the feature overwrite it, so build it again. It
+    // is placed next to the binary.
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .args(["build", "--lib", "--features", "capi", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    common::run(&mut build);
+    let lib_dir = Path::new(env!("CARGO_BIN_EXE_soda")).parent().unwrap();
+    let include_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("include");
+    let input = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/elf/test/libspdlog.so.1.12.0");
+
+    let dir = common::create_work_dir("capi");
+    let source = dir.join("main.c");
+    let exe = dir.join("main");
+    std::fs::write(&source, MAIN_SOURCE).unwrap();
+    common::run(
+        Command::new("cc")
+            .args(["-Wall", "-Werror", "-D_GNU_SOURCE"])
+            .arg("-I")
+            .arg(&include_dir)
+            .arg("-o")
+            .arg(&exe)
+            .arg(&source)
+            .arg("-L")
+            .arg(lib_dir)
+            .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+            .arg("-lsoda"),
+    );
+    common::run(Command::new(&exe).arg(&input));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
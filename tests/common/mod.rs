@@ -0,0 +1,88 @@
+//! Helpers shared by the end-to-end tests.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Create an empty working directory for the test with the given name.
+pub fn create_work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("soda-test-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Determine whether a C compiler that targets x86_64 Linux is available.
+pub fn has_c_compiler() -> bool {
+    cfg!(all(target_arch = "x86_64", target_os = "linux"))
+        && Command::new("cc").arg("--version").output().is_ok()
+}
+
+/// Run the given command and assert that it succeeds.
+pub fn run(cmd: &mut Command) {
+    let output = cmd.output().unwrap();
+    assert!(
+        output.status.success(),
+        "command {:?} failed: {}",
+        cmd,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Compile the given C source into a shared library named `lib<name>.so` in the given directory.
+pub fn compile_shared_lib(dir: &Path, name: &str, source: &str) -> PathBuf {
+    compile_shared_lib_with_args(dir, name, source, &[])
+}
+
+/// Compile the given C source into a shared library named `lib<name>.so` in the given directory, passing the given
+/// extra arguments to the compiler.
+pub fn compile_shared_lib_with_args(
+    dir: &Path,
+    name: &str,
+    source: &str,
+    args: &[&str],
+) -> PathBuf {
+    let source_path = dir.join(format!("{}.c", name));
+    let lib = dir.join(format!("lib{}.so", name));
+    std::fs::write(&source_path, source).unwrap();
+
+    // Build without the start files so that the library only contains the code under test.
+    run(Command::new("cc")
+        .args(["-shared", "-fPIC", "-O1", "-nostartfiles"])
+        .args(args)
+        .arg("-o")
+        .arg(&lib)
+        .arg(&source_path));
+    lib
+}
+
+/// Convert the given shared library into a relocatable object file with soda, passing the given extra arguments.
+pub fn convert(dir: &Path, lib: &Path, args: &[&str]) -> PathBuf {
+    let object = dir.join(lib.with_extension("o").file_name().unwrap());
+    run(Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg(lib)
+        .arg("-o")
+        .arg(&object)
+        .args(args));
+    object
+}
+
+/// Compile the given C source of the main program, link it with the given objects and run the resulting executable.
+pub fn link_and_run(dir: &Path, main_source: &str, objects: &[&Path]) {
+    link_and_run_with_args(dir, main_source, objects, &[]);
+}
+
+/// Like [`link_and_run`], but passes the given extra arguments to the compiler driver.
+pub fn link_and_run_with_args(dir: &Path, main_source: &str, objects: &[&Path], args: &[&str]) {
+    let main_source_path = dir.join("main.c");
+    let exe = dir.join("main");
+    std::fs::write(&main_source_path, main_source).unwrap();
+    run(Command::new("cc")
+        .arg("-o")
+        .arg(&exe)
+        .arg(&main_source_path)
+        .args(objects)
+        .args(args));
+
+    run(&mut Command::new(&exe));
+}
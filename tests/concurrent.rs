@@ -0,0 +1,82 @@
+//! Test of converting shared libraries on several threads at once, each with its own options and diagnostic sink.
+
+use std::fmt::Arguments;
+use std::sync::{Arc, Barrier, Mutex};
+
+use soda::{ConvertOptions, DiagnosticSink, Severity};
+
+const FIXTURES: &[&str] = &["libspdlog.so.1.12.0", "libsoda32.so"];
+
+/// Collects the diagnostics of a single conversion.
+#[derive(Debug, Default)]
+struct CollectingSink {
+    messages: Mutex<Vec<(Severity, String)>>,
+}
+
+impl DiagnosticSink for CollectingSink {
+    fn report(&self, severity: Severity, message: Arguments<'_>) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push((severity, message.to_string()));
+    }
+}
+
+/// Convert the given fixture with or without demangling, and get the output and the diagnostics.
+fn convert(fixture: &str, demangle: bool) -> (Vec<u8>, Vec<(Severity, String)>) {
+    let path = format!("{}/src/elf/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    let input = std::fs::read(path).unwrap();
+    let sink = Arc::new(CollectingSink::default());
+    let options = ConvertOptions {
+        diagnostics: Some(sink.clone()),
+        demangle,
+        ..Default::default()
+    };
+    let output = soda::convert(&input, &options).unwrap();
+    let messages = std::mem::take(&mut *sink.messages.lock().unwrap());
+    (output, messages)
+}
+
+#[test]
+fn test_convert_concurrently() {
+    let cases: Vec<_> = FIXTURES
+        .iter()
+        .flat_map(|fixture| [(*fixture, true), (*fixture, false)])
+        .collect();
+    let expected: Vec<_> = cases
+        .iter()
+        .map(|(fixture, demangle)| convert(fixture, *demangle))
+        .collect();
+
+    // Demangling is a per-conversion option, so the messages of the same input differ between the cases.
+    assert_ne!(expected[0].1, expected[1].1);
+
+    const ROUNDS: usize = 4;
+    let barrier = Arc::new(Barrier::new(cases.len() * ROUNDS));
+    let threads: Vec<_> = (0..ROUNDS)
+        .flat_map(|_| cases.iter().copied().enumerate())
+        .map(|(idx, (fixture, demangle))| {
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                (idx, convert(fixture, demangle))
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        let (idx, (output, messages)) = thread.join().unwrap();
+        let (fixture, demangle) = cases[idx];
+        assert!(
+            output == expected[idx].0,
+            "output of {} differs (demangle: {})",
+            fixture,
+            demangle
+        );
+        assert_eq!(
+            messages, expected[idx].1,
+            "diagnostics of {} differ (demangle: {})",
+            fixture, demangle
+        );
+    }
+}
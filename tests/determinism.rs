@@ -0,0 +1,112 @@
+//! Test that converting the same shared library twice gives the same output, which must hold whether or not the
+//! conversion runs on several threads with the `parallel` feature, and that writing the output as it is serialized or
+//! converting an input that was parsed and checked beforehand gives the same output as well.
+
+use object::ObjectKind;
+use soda::{ConvertOptions, Input};
+
+const FIXTURES: &[&str] = &["libspdlog.so.1.12.0", "libsoda32.so"];
+
+#[test]
+fn test_convert_deterministic() {
+    for fixture in FIXTURES {
+        let path = format!("{}/src/elf/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        let input = std::fs::read(path).unwrap();
+
+        // RELRO data can only be protected on x86_64.
+        let protect_relro_values: &[bool] = if fixture.starts_with("libspdlog") {
+            &[false, true]
+        } else {
+            &[false]
+        };
+        for &protect_relro in protect_relro_values {
+            let options = ConvertOptions {
+                protect_relro,
+                ..Default::default()
+            };
+            let first = soda::convert(&input, &options).unwrap();
+            let second = soda::convert(&input, &options).unwrap();
+            assert!(
+                first == second,
+                "output of {} differs between conversions (protect_relro: {})",
+                fixture,
+                protect_relro
+            );
+        }
+    }
+}
+
+#[test]
+fn test_convert_to_writer() {
+    let inputs: Vec<_> = FIXTURES
+        .iter()
+        .map(|fixture| {
+            let path = format!("{}/src/elf/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            (*fixture, std::fs::read(path).unwrap())
+        })
+        .collect();
+    let options = ConvertOptions::default();
+
+    for (fixture, input) in &inputs {
+        let mut output = Vec::new();
+        soda::convert_to_writer(input, &options, &mut output).unwrap();
+        assert!(
+            output == soda::convert(input, &options).unwrap(),
+            "streamed output of {} differs",
+            fixture
+        );
+    }
+
+    // Merging a library with itself defines every symbol twice, which fails before anything is written.
+    let (fixture, input) = &inputs[0];
+    let merged_inputs = [
+        (*fixture, input.as_slice()),
+        ("libcopy.so", input.as_slice()),
+    ];
+    let mut output = Vec::new();
+    assert!(soda::merge_elf_to_writer(&merged_inputs, &options, &mut output).is_err());
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_convert_parsed_input() {
+    let options = ConvertOptions::default();
+    for fixture in FIXTURES {
+        let path = format!("{}/src/elf/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        let data = std::fs::read(path).unwrap();
+
+        let input = Input::parse(&data).unwrap();
+        let summary = input.summary().clone();
+        assert_eq!(summary.kind, ObjectKind::Dynamic);
+        assert!(summary.dynamic_table.is_some());
+
+        let mut output = Vec::new();
+        input.convert_to_writer(&options, &mut output).unwrap();
+        assert!(
+            output == soda::convert(&data, &options).unwrap(),
+            "output of parsed {} differs",
+            fixture
+        );
+
+        // The summary describes the same input whenever it is parsed.
+        assert_eq!(Input::parse(&data).unwrap().summary(), &summary);
+    }
+
+    // Merging parsed inputs fails like merging their data.
+    let path = format!(
+        "{}/src/elf/test/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        FIXTURES[0]
+    );
+    let data = std::fs::read(path).unwrap();
+    let inputs = vec![
+        (FIXTURES[0], Input::parse(&data).unwrap()),
+        ("libcopy.so", Input::parse(&data).unwrap()),
+    ];
+    let mut output = Vec::new();
+    let err = soda::merge_inputs_to_writer(inputs, &options, &mut output).unwrap_err();
+    let expected =
+        soda::merge_elf(&[(FIXTURES[0], &data), ("libcopy.so", &data)], &options).unwrap_err();
+    assert_eq!(err.to_string(), expected.to_string());
+    assert!(output.is_empty());
+}
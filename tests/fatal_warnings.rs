@@ -0,0 +1,37 @@
+//! Test that `--fatal-warnings` turns the warnings of a conversion into an error.
+
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn test_fatal_warnings() {
+    let input = format!("{}/src/elf/test/libsoda32.so", env!("CARGO_MANIFEST_DIR"));
+    let dir = common::create_work_dir("fatal-warnings");
+    let output = dir.join("soda32.o");
+
+    // The exported data objects of the library are reported as likely targets of copy relocations.
+    let result = Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .arg("--fatal-warnings")
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("1 warning was reported and --fatal-warnings is given"));
+    assert!(!output.exists());
+
+    // The conversion succeeds if nothing is reported.
+    common::run(
+        Command::new(env!("CARGO_BIN_EXE_soda"))
+            .arg(&input)
+            .arg("-o")
+            .arg(&output)
+            .args(["--fatal-warnings", "--no-copy-reloc-check"]),
+    );
+    assert!(output.exists());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,60 @@
+//! End-to-end test of collecting the data that the root symbols of a converted shared library cannot reach.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::process::Command;
+
+// The blob is placed into a section of its own, which the linker keeps apart from .rodata.
+const LIB_SOURCE: &str = r#"
+__attribute__((section("soda_blob"))) const unsigned char blob[0x10000] = {1, 2, 3};
+static const int squares[] = {0, 1, 4, 9};
+int square(int i) { return squares[i]; }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+extern const unsigned char blob[] __attribute__((weak));
+int square(int);
+int main(void) {
+    if (square(3) != 9) return 1;
+    if (blob) return 2;
+    return 0;
+}
+"#;
+
+#[test]
+fn test_gc_roots() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("gc-roots");
+    let lib = common::compile_shared_lib(&dir, "gc_roots", LIB_SOURCE);
+    let roots = dir.join("roots.txt");
+    std::fs::write(&roots, "# Functions used by the program\nsquare\n").unwrap();
+
+    let object = dir.join("gc_roots.o");
+    let output = Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg(&lib)
+        .arg("-o")
+        .arg(&object)
+        .arg("--gc-roots")
+        .arg(&roots)
+        .arg("-v")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("Collected 1 unreachable sections, zero-filling 65536 bytes of the image"),
+        "{}",
+        stderr
+    );
+
+    // The program exits successfully only if the root still works and the blob is no longer defined.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,35 @@
+//! End-to-end test of converting a shared library that contains IRELATIVE relocations.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+const LIB_SOURCE: &str = r#"
+static int add_one(int x) { return x + 1; }
+static int add_two(int x) { return x + 2; }
+static int (*resolve_add(void))(int) { return add_two; }
+__attribute__((visibility("hidden"))) int add(int x) __attribute__((ifunc("resolve_add")));
+int call_add(int x) { return add(x); }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+int call_add(int);
+int main(void) { return call_add(40) == 42 ? 0 : 1; }
+"#;
+
+#[test]
+fn test_call_ifunc_from_converted_object() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("ifunc");
+    let lib = common::compile_shared_lib(&dir, "ifunc", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &[]);
+
+    // The program exits successfully only if the resolver-selected implementation is called.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,88 @@
+//! End-to-end tests of linking converted libraries whose constructors and destructors must run in the program.
+//!
+//! The tests require a C compiler that targets x86_64 Linux and compiles C++. They are skipped if no such compiler is
+//! available.
+
+mod common;
+
+/// The constructor of a global object sets the value that get_value returns, and the destructor exits the program
+/// successfully, which overrides the failure that main returns.
+const LIB_SOURCE: &str = r#"
+#include <unistd.h>
+
+static int value;
+
+struct Registry {
+    Registry() { value = 42; }
+};
+
+static Registry registry;
+
+__attribute__((destructor)) static void on_exit(void) { _exit(0); }
+
+extern "C" int get_value(void) { return value; }
+"#;
+
+/// The library is linked with legacy_init as its DT_INIT function, which must run before the constructor.
+const LEGACY_LIB_SOURCE: &str = r#"
+static int value;
+
+void legacy_init(void) { value = 1; }
+
+__attribute__((constructor)) static void init(void) { value = value == 1 ? 42 : 0; }
+
+int get_value(void) { return value; }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+int get_value(void);
+int main(void) { return get_value() == 42 ? 1 : 2; }
+"#;
+
+const LEGACY_MAIN_SOURCE: &str = r#"
+int get_value(void);
+int main(void) { return get_value() == 42 ? 0 : 1; }
+"#;
+
+#[test]
+fn test_run_constructors_of_converted_object() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("init_array");
+    let lib = common::compile_shared_lib_with_args(&dir, "init_array", LIB_SOURCE, &["-x", "c++"]);
+    let object = common::convert(&dir, &lib, &[]);
+
+    // The program exits successfully only if both the constructor and the destructor run.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_run_legacy_init_of_converted_object() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("legacy_init");
+    let lib = common::compile_shared_lib_with_args(
+        &dir,
+        "legacy_init",
+        LEGACY_LIB_SOURCE,
+        &["-Wl,-init,legacy_init"],
+    );
+    let object = common::convert(&dir, &lib, &[]);
+
+    // The program exits successfully only if legacy_init runs before the constructor.
+    common::link_and_run(&dir, LEGACY_MAIN_SOURCE, &[&object]);
+
+    // The calls keep their order in .ctors, which the linker script of the compiler collects as well.
+    let object = common::convert(&dir, &lib, &["--ctors-style=legacy"]);
+    common::link_and_run(&dir, LEGACY_MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
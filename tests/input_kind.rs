@@ -0,0 +1,98 @@
+//! End-to-end test of the handling of inputs that are not shared libraries.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+int get_value(void) { return 42; }
+"#;
+
+/// Run soda on the given input with the given extra arguments, expect it to fail, and get its error output.
+fn convert_and_fail(input: &Path, output: &Path, args: &[&str]) -> String {
+    let result = Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg(input)
+        .arg("-o")
+        .arg(output)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    assert!(!output.exists());
+    String::from_utf8_lossy(&result.stderr).into_owned()
+}
+
+#[test]
+fn test_relocatable_and_archive_inputs() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("input-kind");
+    let source_path = dir.join("value.c");
+    let object = dir.join("value.o");
+    let archive = dir.join("libvalue.a");
+    std::fs::write(&source_path, SOURCE).unwrap();
+    common::run(
+        Command::new("cc")
+            .args(["-c", "-o"])
+            .arg(&object)
+            .arg(&source_path),
+    );
+    common::run(Command::new("ar").arg("rcs").arg(&archive).arg(&object));
+
+    // Relocatable objects are rejected with a hint at --copy-through, which copies them unchanged.
+    let output = dir.join("out.o");
+    let stderr = convert_and_fail(&object, &output, &[]);
+    assert!(stderr.contains("is already a relocatable object"));
+    assert!(stderr.contains("--copy-through"));
+
+    common::run(
+        Command::new(env!("CARGO_BIN_EXE_soda"))
+            .arg(&object)
+            .arg("-o")
+            .arg(&output)
+            .arg("--copy-through"),
+    );
+    assert_eq!(
+        std::fs::read(&output).unwrap(),
+        std::fs::read(&object).unwrap()
+    );
+
+    // Archives are rejected with a hint at extracting their members, even with --copy-through.
+    let output = dir.join("out-archive.o");
+    let stderr = convert_and_fail(&archive, &output, &[]);
+    assert!(stderr.contains("is a static archive"));
+    assert!(stderr.contains("ar x"));
+    convert_and_fail(&archive, &output, &["--copy-through"]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_executable_input() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("input-kind-exe");
+    let source_path = dir.join("main.c");
+    let exe = dir.join("main");
+    std::fs::write(&source_path, "int main(void) { return 0; }\n").unwrap();
+    common::run(
+        Command::new("cc")
+            .args(["-no-pie", "-o"])
+            .arg(&exe)
+            .arg(&source_path),
+    );
+
+    let stderr = convert_and_fail(&exe, &dir.join("main.o"), &["--copy-through"]);
+    assert!(stderr.contains("is a position-dependent executable"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,105 @@
+//! End-to-end test of merging shared libraries that refer to each other into a single object.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::process::Command;
+
+const FOO_SOURCE: &str = r#"
+int utils_scale(int value);
+int foo_base = 20;
+int foo_compute(void) { return utils_scale(foo_base); }
+"#;
+
+const UTILS_SOURCE: &str = r#"
+extern int foo_base;
+int utils_scale(int value) { return value * 2 + foo_base / 10; }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+int foo_compute(void);
+int main(void) { return foo_compute() == 42 ? 0 : 1; }
+"#;
+
+#[test]
+fn test_link_merged_object() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("merge");
+    let foo = common::compile_shared_lib(&dir, "foo", FOO_SOURCE);
+    let utils = common::compile_shared_lib(&dir, "foo-utils", UTILS_SOURCE);
+
+    let object = dir.join("combined.o");
+    common::run(
+        Command::new(env!("CARGO_BIN_EXE_soda"))
+            .arg(&foo)
+            .arg(&utils)
+            .arg("-o")
+            .arg(&object),
+    );
+
+    // The references between the libraries are bound within the merged object, and each library keeps its own
+    // boundary symbols.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+    let output = Command::new("nm").arg(&object).output().unwrap();
+    let symbols = String::from_utf8_lossy(&output.stdout);
+    assert!(!symbols.contains(" U "), "{}", symbols);
+    assert!(
+        symbols.contains("__soda_image_start_foo_utils"),
+        "{}",
+        symbols
+    );
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_merge_libraries_found_with_search_dirs() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("merge-search");
+    let foo = common::compile_shared_lib(&dir, "foo", FOO_SOURCE);
+    let utils = common::compile_shared_lib(&dir, "foo-utils", UTILS_SOURCE);
+
+    // Only a versioned file of the utils library is in the search directory.
+    let lib_dir = dir.join("lib");
+    std::fs::create_dir_all(&lib_dir).unwrap();
+    std::fs::rename(&utils, lib_dir.join("libfoo-utils.so.1")).unwrap();
+
+    let object = dir.join("combined.o");
+    common::run(
+        Command::new(env!("CARGO_BIN_EXE_soda"))
+            .arg(&foo)
+            .arg("-L")
+            .arg(dir.join("missing"))
+            .arg("-L")
+            .arg(&lib_dir)
+            .arg("-lfoo-utils")
+            .arg("-o")
+            .arg(&object),
+    );
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    // A library that is not found is an error.
+    let output = Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lmissing")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("cannot find -lmissing"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,55 @@
+//! End-to-end test of the handling of position-independent executables.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::process::Command;
+
+use object::{Object as _, ObjectSymbol as _};
+
+const MAIN_SOURCE: &str = r#"
+int main(void) { return 0; }
+"#;
+
+#[test]
+fn test_reject_pie() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("pie");
+    let source_path = dir.join("main.c");
+    let exe = dir.join("main");
+    std::fs::write(&source_path, MAIN_SOURCE).unwrap();
+    common::run(
+        Command::new("cc")
+            .args(["-fPIE", "-pie", "-o"])
+            .arg(&exe)
+            .arg(&source_path),
+    );
+
+    // PIEs are rejected with a clear message by default, and no output is left behind.
+    let object = dir.join("main.o");
+    let output = Command::new(env!("CARGO_BIN_EXE_soda"))
+        .arg(&exe)
+        .arg("-o")
+        .arg(&object)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("input appears to be a position-independent executable, not a shared library"));
+    assert!(!object.exists());
+
+    // The entry point is kept as a local symbol when conversion is allowed.
+    let object = common::convert(&dir, &exe, &["--allow-pie"]);
+    let object_data = std::fs::read(&object).unwrap();
+    let object_file = object::File::parse(&*object_data).unwrap();
+    assert!(object_file
+        .symbols()
+        .any(|sym| sym.name() == Ok("__soda_entry") && sym.is_local()));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
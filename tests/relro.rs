@@ -0,0 +1,133 @@
+//! End-to-end test of protecting the RELRO data of a converted shared library.
+//!
+//! The final link cannot place the converted RELRO data under the PT_GNU_RELRO segment of the executable, so `readelf -l`
+//! of the linked executable is only expected to show it in a writable segment. The executable checks that the data is
+//! read-only at run time instead.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+// The table of function pointers is placed into .data.rel.ro like a vtable.
+const LIB_SOURCE: &str = r#"
+static int one(void) { return 1; }
+static int two(void) { return 2; }
+int (*const table[])(void) = {one, two};
+int counter;
+int call(int i) { return table[i](); }
+const void *table_addr(void) { return table; }
+int *counter_addr(void) { return &counter; }
+int bump(void) { return ++counter; }
+"#;
+
+/// Run `readelf -lW` on the given file and return its output.
+fn read_program_headers(path: &Path) -> String {
+    let output = Command::new("readelf")
+        .arg("-lW")
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Get the address range of the pages that hold nothing but the RELRO data, from the output of `readelf -lW`.
+fn get_relro_pages(program_headers: &str) -> (u64, u64) {
+    let fields: Vec<_> = program_headers
+        .lines()
+        .find(|line| line.trim_start().starts_with("GNU_RELRO"))
+        .unwrap()
+        .split_whitespace()
+        .collect();
+    let parse = |field: &str| u64::from_str_radix(field.trim_start_matches("0x"), 16).unwrap();
+    let (addr, size) = (parse(fields[2]), parse(fields[5]));
+    (addr & !0xfff, (addr + size) & !0xfff)
+}
+
+/// Get the flags of the segments that the given section is mapped into, from the output of `readelf -lW`.
+fn get_section_segment_flags(program_headers: &str, section: &str) -> Vec<String> {
+    let segments: Vec<_> = program_headers
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("Type"))
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        .filter(|line| !line.trim_start().starts_with('['))
+        .map(|line| {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            // The flags may contain spaces, and sit between the sizes and the alignment.
+            (fields[0], fields[6..fields.len() - 1].concat())
+        })
+        .collect();
+    program_headers
+        .lines()
+        .skip_while(|line| !line.contains("Section to Segment mapping"))
+        .skip(2)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let index: usize = fields.next()?.parse().ok()?;
+            fields
+                .any(|name| name == section)
+                .then(|| format!("{} {}", segments[index].0, segments[index].1))
+        })
+        .collect()
+}
+
+#[test]
+fn test_protect_relro() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("relro");
+    let lib = common::compile_shared_lib(&dir, "relro", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &["--protect-relro"]);
+
+    let (relro_start, relro_end) = get_relro_pages(&read_program_headers(&lib));
+    assert!(relro_start < relro_end);
+
+    // The program exits successfully only if the calls through the table work, the pages of the RELRO data are
+    // read-only while the counter after them stays writable.
+    let main_source = format!(
+        r#"
+#include <stdio.h>
+extern char __soda_image_start[];
+int call(int);
+const void *table_addr(void);
+int *counter_addr(void);
+int bump(void);
+static int is_writable(const char *p) {{
+    unsigned long addr = (unsigned long)p, start, end;
+    char perms[5];
+    int writable = -1;
+    FILE *maps = fopen("/proc/self/maps", "r");
+    while (fscanf(maps, "%lx-%lx %4s%*[^\n]", &start, &end, perms) == 3)
+        if (addr >= start && addr < end) writable = perms[1] == 'w';
+    fclose(maps);
+    return writable;
+}}
+int main(void) {{
+    const char *start = __soda_image_start + {relro_start}, *end = __soda_image_start + {relro_end};
+    if (call(0) != 1 || call(1) != 2) return 1;
+    if ((const char *)table_addr() < start || (const char *)table_addr() >= end) return 2;
+    for (const char *page = start; page < end; page += 0x1000)
+        if (is_writable(page) != 0) return 3;
+    if (bump() != 1 || bump() != 2 || is_writable((const char *)counter_addr()) != 1) return 4;
+    return 0;
+}}
+"#
+    );
+    common::link_and_run(&dir, &main_source, &[&object]);
+
+    // The converted image is only mapped into a writable segment of the executable rather than under its PT_GNU_RELRO
+    // segment, so the protection above comes from the conversion.
+    let exe_program_headers = read_program_headers(&dir.join("main"));
+    let flags = get_section_segment_flags(&exe_program_headers, ".soda");
+    assert_eq!(flags.len(), 1, "{}", exe_program_headers);
+    assert!(flags[0].starts_with("LOAD") && flags[0].contains('W'));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,120 @@
+//! End-to-end test of converting a shared library that accesses thread-local variables.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+use object::elf::{R_X86_64_DTPMOD64, R_X86_64_DTPOFF64};
+use object::{
+    Object as _, ObjectSection as _, ObjectSymbol as _, RelocationKind, RelocationTarget,
+    SymbolKind,
+};
+
+mod common;
+
+// GNU ld refuses DTPOFF64 relocations in executable sections, which the general-dynamic tls_index pairs end up in, so
+// the library is either built with the initial-exec model, which only uses TPOFF64 relocations, or with TLS descriptors.
+// The static variable is accessed without a symbol.
+const LIB_SOURCE: &str = r#"
+#ifdef INITIAL_EXEC
+#define IE __attribute__((tls_model("initial-exec")))
+#else
+#define IE
+#endif
+IE __thread int counter;
+IE __thread int base = 40;
+static IE __thread int hidden_count = 3;
+int bump(void) { hidden_count++; return base + ++counter; }
+int get_hidden(void) { return hidden_count; }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+#include <pthread.h>
+extern __thread int counter;
+int bump(void);
+int get_hidden(void);
+static void *worker(void *arg) { (void)arg; return (void *)(long)bump(); }
+int main(void) {
+    if (bump() != 41 || bump() != 42 || counter != 2) return 1;
+    pthread_t thread;
+    void *ret;
+    if (pthread_create(&thread, 0, worker, 0) != 0 || pthread_join(thread, &ret) != 0) return 2;
+    if ((long)ret != 41 || counter != 2) return 3;
+    return get_hidden() == 5 ? 0 : 4;
+}
+"#;
+
+/// Convert the library built with the given extra compiler arguments, and run the main program against it.
+fn run_tls_test(name: &str, args: &[&str]) {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir(name);
+    let lib = common::compile_shared_lib_with_args(&dir, "tls", LIB_SOURCE, args);
+    let object = common::convert(&dir, &lib, &[]);
+
+    // The program exits successfully only if each thread gets its own, correctly initialized copy of the variables.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_access_tls_from_converted_object() {
+    run_tls_test("tls", &["-DINITIAL_EXEC"]);
+}
+
+#[test]
+fn test_access_tls_through_descriptors_from_converted_object() {
+    run_tls_test("tlsdesc", &["-mtls-dialect=gnu2"]);
+}
+
+#[test]
+fn test_convert_general_dynamic_tls() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    // The output can't be linked, so only check that the module ID of each tls_index pair is resolved in place without
+    // any relocation, and that each offset refers to the TLS image.
+    let dir = common::create_work_dir("tlsgd");
+    let lib = common::compile_shared_lib(&dir, "tls", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &[]);
+
+    let lib_data = std::fs::read(&lib).unwrap();
+    let lib_file = object::File::parse(lib_data.as_slice()).unwrap();
+    let dtpmod_addrs: Vec<_> = lib_file
+        .dynamic_relocations()
+        .unwrap()
+        .filter(|(_, reloc)| reloc.kind() == RelocationKind::Elf(R_X86_64_DTPMOD64))
+        .map(|(addr, _)| addr)
+        .collect();
+    assert!(!dtpmod_addrs.is_empty());
+
+    let object_data = std::fs::read(&object).unwrap();
+    let object_file = object::File::parse(object_data.as_slice()).unwrap();
+    let tls_section_index = object_file.section_by_name(".tdata").unwrap().index();
+    let image = object_file.section_by_name(".soda").unwrap();
+    let image_data = image.data().unwrap();
+    let relocs: Vec<_> = image.relocations().collect();
+    for addr in dtpmod_addrs {
+        let slot = addr as usize..addr as usize + 8;
+        assert_eq!(&image_data[slot], &1u64.to_le_bytes());
+        assert!(relocs.iter().all(|(offset, _)| *offset != addr));
+
+        let (_, offset_reloc) = relocs
+            .iter()
+            .find(|(offset, _)| *offset == addr + 8)
+            .unwrap();
+        assert_eq!(offset_reloc.kind(), RelocationKind::Elf(R_X86_64_DTPOFF64));
+        let RelocationTarget::Symbol(sym_idx) = offset_reloc.target() else {
+            panic!("unexpected relocation target");
+        };
+        let sym = object_file.symbol_by_index(sym_idx).unwrap();
+        assert!(sym.kind() == SymbolKind::Tls || sym.kind() == SymbolKind::Section);
+        assert_eq!(sym.section_index(), Some(tls_section_index));
+    }
+
+    std::fs::remove_dir_all(&dir).ok();
+}
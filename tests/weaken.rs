@@ -0,0 +1,34 @@
+//! End-to-end test of linking a converted object whose exports are weakened together with strong definitions.
+//!
+//! The test requires a C compiler that targets x86_64 Linux. It is skipped if no such compiler is available.
+
+mod common;
+
+const LIB_SOURCE: &str = r#"
+int get_value(void) { return 1; }
+int get_other(void) { return 2; }
+"#;
+
+const MAIN_SOURCE: &str = r#"
+int get_value(void) { return 40; }
+int get_other(void);
+int main(void) { return get_value() + get_other() == 42 ? 0 : 1; }
+"#;
+
+#[test]
+fn test_link_weakened_object_with_strong_definition() {
+    if !common::has_c_compiler() {
+        eprintln!("skipping: no C compiler targeting x86_64 Linux");
+        return;
+    }
+
+    let dir = common::create_work_dir("weaken");
+    let lib = common::compile_shared_lib(&dir, "weaken", LIB_SOURCE);
+    let object = common::convert(&dir, &lib, &["--weaken"]);
+
+    // The link fails with duplicate definitions of get_value unless the converted definition is weak, and the program
+    // exits successfully only if the strong definition is picked.
+    common::link_and_run(&dir, MAIN_SOURCE, &[&object]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}